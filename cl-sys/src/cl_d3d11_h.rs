@@ -1,8 +1,96 @@
 //! OpenCL / DirectX 11 sharing.
 
-pub use crate::cl_h::cl_uint;
+#![allow(non_camel_case_types, non_upper_case_globals)]
 
+use libc::c_void;
+pub use crate::cl_h::{cl_uint, cl_int, cl_platform_id, cl_device_id, cl_context, cl_mem_flags,
+    cl_mem, cl_command_queue, cl_event, cl_command_type, cl_mem_info, cl_image_info};
+
+pub type cl_d3d11_device_source_khr = cl_uint;
+pub type cl_d3d11_device_set_khr = cl_uint;
+
+// Error codes:
+pub const CL_INVALID_D3D11_DEVICE_KHR: cl_int = -1006;
+pub const CL_INVALID_D3D11_RESOURCE_KHR: cl_int = -1007;
+pub const CL_D3D11_RESOURCE_ALREADY_ACQUIRED_KHR: cl_int = -1008;
+pub const CL_D3D11_RESOURCE_NOT_ACQUIRED_KHR: cl_int = -1009;
+
+// cl_d3d11_device_source:
+pub const CL_D3D11_DEVICE_KHR: cl_d3d11_device_source_khr = 0x4019;
+pub const CL_D3D11_DXGI_ADAPTER_KHR: cl_d3d11_device_source_khr = 0x401A;
+
+// cl_d3d11_device_set:
+pub const CL_PREFERRED_DEVICES_FOR_D3D11_KHR: cl_d3d11_device_set_khr = 0x401B;
+pub const CL_ALL_DEVICES_FOR_D3D11_KHR: cl_d3d11_device_set_khr = 0x401C;
+
+// cl_context_info:
 pub const CL_CONTEXT_D3D11_DEVICE_KHR: cl_uint = 0x401D;
+pub const CL_CONTEXT_D3D11_PREFER_SHARED_RESOURCES_KHR: cl_uint = 0x402D;
+
+// cl_mem_info:
+pub const CL_MEM_D3D11_RESOURCE_KHR: cl_mem_info = 0x401E;
+
+// cl_image_info:
+pub const CL_IMAGE_D3D11_SUBRESOURCE_KHR: cl_image_info = 0x401F;
+
+// cl_command_type:
+pub const CL_COMMAND_ACQUIRE_D3D11_OBJECTS_KHR: cl_command_type = 0x4020;
+pub const CL_COMMAND_RELEASE_D3D11_OBJECTS_KHR: cl_command_type = 0x4021;
+
+// The functions below are extension functions, obtained at runtime with
+// `clGetExtensionFunctionAddressForPlatform`. Direct3D interface pointers
+// (`ID3D11Device *`, `ID3D11Buffer *`, etc.) are passed as `*mut c_void`.
+
+pub type clGetDeviceIDsFromD3D11KHR_fn = extern "system" fn(
+    platform: cl_platform_id,
+    d3d_device_source: cl_d3d11_device_source_khr,
+    d3d_object: *mut c_void,
+    d3d_device_set: cl_d3d11_device_set_khr,
+    num_entries: cl_uint,
+    devices: *mut cl_device_id,
+    num_devices: *mut cl_uint)
+    -> cl_int;
+
+pub type clCreateFromD3D11BufferKHR_fn = extern "system" fn(
+    context: cl_context,
+    flags: cl_mem_flags,
+    resource: *mut c_void,
+    errcode_ret: *mut cl_int)
+    -> cl_mem;
+
+pub type clCreateFromD3D11Texture2DKHR_fn = extern "system" fn(
+    context: cl_context,
+    flags: cl_mem_flags,
+    resource: *mut c_void,
+    subresource: cl_uint,
+    errcode_ret: *mut cl_int)
+    -> cl_mem;
+
+pub type clCreateFromD3D11Texture3DKHR_fn = extern "system" fn(
+    context: cl_context,
+    flags: cl_mem_flags,
+    resource: *mut c_void,
+    subresource: cl_uint,
+    errcode_ret: *mut cl_int)
+    -> cl_mem;
+
+pub type clEnqueueAcquireD3D11ObjectsKHR_fn = extern "system" fn(
+    command_queue: cl_command_queue,
+    num_objects: cl_uint,
+    mem_objects: *const cl_mem,
+    num_events_in_wait_list: cl_uint,
+    event_wait_list: *const cl_event,
+    event: *mut cl_event)
+    -> cl_int;
+
+pub type clEnqueueReleaseD3D11ObjectsKHR_fn = extern "system" fn(
+    command_queue: cl_command_queue,
+    num_objects: cl_uint,
+    mem_objects: *const cl_mem,
+    num_events_in_wait_list: cl_uint,
+    event_wait_list: *const cl_event,
+    event: *mut cl_event)
+    -> cl_int;
 
 // /**********************************************************************************
 //  * Copyright (c) 2008-2015 The Khronos Group Inc.
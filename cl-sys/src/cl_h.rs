@@ -86,6 +86,9 @@ pub type cl_command_type                    = cl_uint;
 pub type cl_profiling_info                  = cl_uint;
 pub type cl_sampler_properties              = cl_bitfield;
 pub type cl_kernel_exec_info                = cl_uint;
+pub type cl_version                         = cl_uint;
+pub type cl_device_atomic_capabilities      = cl_bitfield;
+pub type cl_device_device_enqueue_capabilities = cl_bitfield;
 
 #[repr(C)]
 pub struct cl_image_format {
@@ -108,6 +111,15 @@ pub struct cl_image_desc {
     pub buffer:             cl_mem,
 }
 
+pub const CL_NAME_VERSION_MAX_NAME_SIZE:                usize = 64;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct cl_name_version {
+    pub version:            cl_version,
+    pub name:               [c_char; CL_NAME_VERSION_MAX_NAME_SIZE],
+}
+
 #[repr(C)]
 pub struct cl_buffer_region {
     pub origin:     size_t,
@@ -310,6 +322,22 @@ pub const CL_DEVICE_IMAGE_BASE_ADDRESS_ALIGNMENT:           cl_uint = 0x104B;
     pub const CL_DEVICE_IL_VERSION:                             cl_uint = 0x105B;
     pub const CL_DEVICE_MAX_NUM_SUB_GROUPS:                     cl_uint = 0x105C;
     pub const CL_DEVICE_SUB_GROUP_INDEPENDENT_FORWARD_PROGRESS: cl_uint = 0x105D;
+    //###### NEW 3.0 ########
+    pub const CL_DEVICE_NUMERIC_VERSION:                        cl_uint = 0x105E;
+    pub const CL_DEVICE_EXTENSIONS_WITH_VERSION:                cl_uint = 0x1060;
+    pub const CL_DEVICE_ILS_WITH_VERSION:                       cl_uint = 0x1061;
+    pub const CL_DEVICE_BUILT_IN_KERNELS_WITH_VERSION:          cl_uint = 0x1062;
+    pub const CL_DEVICE_ATOMIC_MEMORY_CAPABILITIES:             cl_uint = 0x1063;
+    pub const CL_DEVICE_ATOMIC_FENCE_CAPABILITIES:              cl_uint = 0x1064;
+    pub const CL_DEVICE_NON_UNIFORM_WORK_GROUP_SUPPORT:         cl_uint = 0x1065;
+    pub const CL_DEVICE_OPENCL_C_ALL_VERSIONS:                  cl_uint = 0x1066;
+    pub const CL_DEVICE_PREFERRED_WORK_GROUP_SIZE_MULTIPLE:     cl_uint = 0x1067;
+    pub const CL_DEVICE_WORK_GROUP_COLLECTIVE_FUNCTIONS_SUPPORT: cl_uint = 0x1068;
+    pub const CL_DEVICE_GENERIC_ADDRESS_SPACE_SUPPORT:          cl_uint = 0x1069;
+    pub const CL_DEVICE_OPENCL_C_FEATURES:                      cl_uint = 0x106F;
+    pub const CL_DEVICE_DEVICE_ENQUEUE_CAPABILITIES:            cl_uint = 0x1070;
+    pub const CL_DEVICE_PIPE_SUPPORT:                           cl_uint = 0x1071;
+    pub const CL_DEVICE_LATEST_CONFORMANCE_VERSION_PASSED:      cl_uint = 0x1072;
 
 // cl_device_fp_config - bitfield:
 pub const CL_FP_DENORM:                                 cl_bitfield = 1 << 0;
@@ -372,6 +400,25 @@ pub const CL_DEVICE_AFFINITY_DOMAIN_NEXT_PARTITIONABLE: cl_bitfield = 1 << 5;
     pub const CL_DEVICE_SVM_FINE_GRAIN_SYSTEM:             cl_bitfield = 1 << 2;
     pub const CL_DEVICE_SVM_ATOMICS:                       cl_bitfield = 1 << 3;
 
+    //###### NEW 3.0 ########
+    // cl_device_atomic_capabilities:
+    pub const CL_DEVICE_ATOMIC_ORDER_RELAXED:              cl_bitfield = 1 << 0;
+    pub const CL_DEVICE_ATOMIC_ORDER_ACQ_REL:              cl_bitfield = 1 << 1;
+    pub const CL_DEVICE_ATOMIC_ORDER_SEQ_CST:              cl_bitfield = 1 << 2;
+    pub const CL_DEVICE_ATOMIC_SCOPE_WORK_ITEM:            cl_bitfield = 1 << 3;
+    pub const CL_DEVICE_ATOMIC_SCOPE_WORK_GROUP:           cl_bitfield = 1 << 4;
+    pub const CL_DEVICE_ATOMIC_SCOPE_DEVICE:               cl_bitfield = 1 << 5;
+    pub const CL_DEVICE_ATOMIC_SCOPE_ALL_DEVICES:          cl_bitfield = 1 << 6;
+
+    // cl_device_device_enqueue_capabilities:
+    pub const CL_DEVICE_QUEUE_SUPPORTED:                   cl_bitfield = 1 << 0;
+    pub const CL_DEVICE_QUEUE_REPLACEABLE_DEFAULT:         cl_bitfield = 1 << 1;
+
+    // cl_version:
+    pub const CL_VERSION_MAJOR_BITS:                       cl_uint = 10;
+    pub const CL_VERSION_MINOR_BITS:                       cl_uint = 10;
+    pub const CL_VERSION_PATCH_BITS:                       cl_uint = 12;
+
 // cl_command_queue_info:
 pub const CL_QUEUE_CONTEXT:                             cl_uint = 0x1090;
 pub const CL_QUEUE_DEVICE:                              cl_uint = 0x1091;
@@ -380,6 +427,16 @@ pub const CL_QUEUE_PROPERTIES:                          cl_uint = 0x1093;
     //###### NEW ########
     pub const CL_QUEUE_SIZE:                                cl_uint = 0x1094;
     pub const CL_QUEUE_DEVICE_DEFAULT:                      cl_uint = 0x1095;
+    // cl_khr_priority_hints:
+    pub const CL_QUEUE_PRIORITY_KHR:                        cl_uint = 0x1096;
+    pub const CL_QUEUE_PRIORITY_HIGH_KHR:                   cl_bitfield = 1 << 0;
+    pub const CL_QUEUE_PRIORITY_MED_KHR:                    cl_bitfield = 1 << 1;
+    pub const CL_QUEUE_PRIORITY_LOW_KHR:                    cl_bitfield = 1 << 2;
+    // cl_khr_throttle_hints:
+    pub const CL_QUEUE_THROTTLE_KHR:                        cl_uint = 0x1097;
+    pub const CL_QUEUE_THROTTLE_HIGH_KHR:                   cl_bitfield = 1 << 0;
+    pub const CL_QUEUE_THROTTLE_MED_KHR:                    cl_bitfield = 1 << 1;
+    pub const CL_QUEUE_THROTTLE_LOW_KHR:                    cl_bitfield = 1 << 2;
 
 // cl_mem_flags and cl_svm_mem_flags - bitfield:
 pub const CL_MEM_READ_WRITE:                            cl_bitfield = 1 << 0;
@@ -733,8 +790,8 @@ extern "system" {
     //############################### NEW 2.1 #################################
     #[cfg(feature = "opencl_version_2_1")]
     pub fn clGetDeviceAndHostTimer(device: cl_device_id,
-                                   device_timestamp: cl_ulong,
-                                   host_timestamp: cl_ulong) -> cl_int;
+                                   device_timestamp: *mut cl_ulong,
+                                   host_timestamp: *mut cl_ulong) -> cl_int;
 
     // extern CL_API_ENTRY cl_int CL_API_CALL
     // clGetHostTimer(cl_device_id /* device */,
@@ -742,7 +799,7 @@ extern "system" {
     //############################### NEW 2.1 #################################
     #[cfg(feature = "opencl_version_2_1")]
     pub fn clGetHostTimer(device: cl_device_id,
-                          host_timestamp: cl_ulong) -> cl_int;
+                          host_timestamp: *mut cl_ulong) -> cl_int;
 
     // Context APIs:
     pub fn clCreateContext(properties: *const cl_context_properties,
@@ -1045,6 +1102,18 @@ extern "system" {
                   user_data: *mut c_void,
                   errcode_ret: *mut cl_int) -> cl_program;
 
+    // extern CL_API_ENTRY cl_int CL_API_CALL
+    // clSetProgramSpecializationConstant(cl_program  /* program */,
+    //                                    cl_uint     /* spec_id */,
+    //                                    size_t      /* spec_size */,
+    //                                    const void* /* spec_value */) CL_API_SUFFIX__VERSION_2_2;
+    //############################### NEW 2.2 #################################
+    #[cfg(feature = "opencl_version_2_2")]
+    pub fn clSetProgramSpecializationConstant(program: cl_program,
+                                              spec_id: cl_uint,
+                                              spec_size: size_t,
+                                              spec_value: *const c_void) -> cl_int;
+
     // extern CL_API_ENTRY cl_int CL_API_CALL
     // clUnloadPlatformCompiler(cl_platform_id /* platform */) CL_API_SUFFIX__VERSION_1_2;
     // //############################### NEW 1.2 #################################
@@ -56,7 +56,16 @@ pub use self::cl_d3d10_h::{CL_CONTEXT_D3D10_DEVICE_KHR, cl_d3d10_device_source_k
 pub use self::cl_dx9_media_sharing_h::{CL_CONTEXT_ADAPTER_D3D9_KHR, CL_CONTEXT_ADAPTER_D3D9EX_KHR,
     CL_CONTEXT_ADAPTER_DXVA_KHR};
 
-pub use self::cl_d3d11_h::CL_CONTEXT_D3D11_DEVICE_KHR;
+pub use self::cl_d3d11_h::{CL_CONTEXT_D3D11_DEVICE_KHR, cl_d3d11_device_source_khr,
+    cl_d3d11_device_set_khr, CL_INVALID_D3D11_DEVICE_KHR, CL_INVALID_D3D11_RESOURCE_KHR,
+    CL_D3D11_RESOURCE_ALREADY_ACQUIRED_KHR, CL_D3D11_RESOURCE_NOT_ACQUIRED_KHR,
+    CL_D3D11_DEVICE_KHR, CL_D3D11_DXGI_ADAPTER_KHR, CL_PREFERRED_DEVICES_FOR_D3D11_KHR,
+    CL_ALL_DEVICES_FOR_D3D11_KHR, CL_CONTEXT_D3D11_PREFER_SHARED_RESOURCES_KHR,
+    CL_MEM_D3D11_RESOURCE_KHR, CL_IMAGE_D3D11_SUBRESOURCE_KHR,
+    CL_COMMAND_ACQUIRE_D3D11_OBJECTS_KHR, CL_COMMAND_RELEASE_D3D11_OBJECTS_KHR,
+    clGetDeviceIDsFromD3D11KHR_fn, clCreateFromD3D11BufferKHR_fn,
+    clCreateFromD3D11Texture2DKHR_fn, clCreateFromD3D11Texture3DKHR_fn,
+    clEnqueueAcquireD3D11ObjectsKHR_fn, clEnqueueReleaseD3D11ObjectsKHR_fn};
 
 // Types:
 pub use self::cl_h::{cl_platform_id, cl_device_id, cl_context, cl_command_queue, cl_mem,
@@ -73,10 +82,11 @@ pub use self::cl_h::{cl_platform_id, cl_device_id, cl_context, cl_command_queue,
     cl_kernel_info, cl_kernel_arg_info, cl_kernel_arg_address_qualifier,
     cl_kernel_arg_access_qualifier, cl_kernel_arg_type_qualifier, cl_kernel_work_group_info,
     cl_kernel_sub_group_info, cl_event_info, cl_command_type, cl_profiling_info,
-    cl_sampler_properties, cl_kernel_exec_info};
+    cl_sampler_properties, cl_kernel_exec_info, cl_version, cl_device_atomic_capabilities,
+    cl_device_device_enqueue_capabilities};
 
 // Structs:
-pub use self::cl_h::{cl_image_format, cl_image_desc, cl_buffer_region};
+pub use self::cl_h::{cl_image_format, cl_image_desc, cl_buffer_region, cl_name_version};
 
 // Consts:
 pub use self::cl_h::{CL_SUCCESS, CL_DEVICE_NOT_FOUND, CL_DEVICE_NOT_AVAILABLE,
@@ -143,7 +153,14 @@ pub use self::cl_h::{CL_VERSION_1_0, CL_VERSION_1_1, CL_VERSION_1_2, CL_VERSION_
     CL_DEVICE_PIPE_MAX_ACTIVE_RESERVATIONS, CL_DEVICE_PIPE_MAX_PACKET_SIZE,
     CL_DEVICE_PREFERRED_PLATFORM_ATOMIC_ALIGNMENT, CL_DEVICE_PREFERRED_GLOBAL_ATOMIC_ALIGNMENT,
     CL_DEVICE_PREFERRED_LOCAL_ATOMIC_ALIGNMENT, CL_DEVICE_IL_VERSION, CL_DEVICE_MAX_NUM_SUB_GROUPS,
-    CL_DEVICE_SUB_GROUP_INDEPENDENT_FORWARD_PROGRESS, CL_FP_DENORM, CL_FP_INF_NAN,
+    CL_DEVICE_SUB_GROUP_INDEPENDENT_FORWARD_PROGRESS, CL_DEVICE_NUMERIC_VERSION,
+    CL_DEVICE_EXTENSIONS_WITH_VERSION, CL_DEVICE_ILS_WITH_VERSION,
+    CL_DEVICE_BUILT_IN_KERNELS_WITH_VERSION, CL_DEVICE_ATOMIC_MEMORY_CAPABILITIES,
+    CL_DEVICE_ATOMIC_FENCE_CAPABILITIES, CL_DEVICE_NON_UNIFORM_WORK_GROUP_SUPPORT,
+    CL_DEVICE_OPENCL_C_ALL_VERSIONS, CL_DEVICE_PREFERRED_WORK_GROUP_SIZE_MULTIPLE,
+    CL_DEVICE_WORK_GROUP_COLLECTIVE_FUNCTIONS_SUPPORT, CL_DEVICE_GENERIC_ADDRESS_SPACE_SUPPORT,
+    CL_DEVICE_OPENCL_C_FEATURES, CL_DEVICE_DEVICE_ENQUEUE_CAPABILITIES, CL_DEVICE_PIPE_SUPPORT,
+    CL_DEVICE_LATEST_CONFORMANCE_VERSION_PASSED, CL_FP_DENORM, CL_FP_INF_NAN,
     CL_FP_ROUND_TO_NEAREST, CL_FP_ROUND_TO_ZERO, CL_FP_ROUND_TO_INF, CL_FP_FMA, CL_FP_SOFT_FLOAT,
     CL_FP_CORRECTLY_ROUNDED_DIVIDE_SQRT, CL_NONE, CL_READ_ONLY_CACHE, CL_READ_WRITE_CACHE,
     CL_LOCAL, CL_GLOBAL, CL_EXEC_KERNEL, CL_EXEC_NATIVE_KERNEL,
@@ -156,8 +173,16 @@ pub use self::cl_h::{CL_VERSION_1_0, CL_VERSION_1_1, CL_VERSION_1_2, CL_VERSION_
     CL_DEVICE_AFFINITY_DOMAIN_L3_CACHE, CL_DEVICE_AFFINITY_DOMAIN_L2_CACHE,
     CL_DEVICE_AFFINITY_DOMAIN_L1_CACHE, CL_DEVICE_AFFINITY_DOMAIN_NEXT_PARTITIONABLE,
     CL_DEVICE_SVM_COARSE_GRAIN_BUFFER, CL_DEVICE_SVM_FINE_GRAIN_BUFFER,
-    CL_DEVICE_SVM_FINE_GRAIN_SYSTEM, CL_DEVICE_SVM_ATOMICS, CL_QUEUE_CONTEXT, CL_QUEUE_DEVICE,
+    CL_DEVICE_SVM_FINE_GRAIN_SYSTEM, CL_DEVICE_SVM_ATOMICS, CL_DEVICE_ATOMIC_ORDER_RELAXED,
+    CL_DEVICE_ATOMIC_ORDER_ACQ_REL, CL_DEVICE_ATOMIC_ORDER_SEQ_CST,
+    CL_DEVICE_ATOMIC_SCOPE_WORK_ITEM, CL_DEVICE_ATOMIC_SCOPE_WORK_GROUP,
+    CL_DEVICE_ATOMIC_SCOPE_DEVICE, CL_DEVICE_ATOMIC_SCOPE_ALL_DEVICES, CL_DEVICE_QUEUE_SUPPORTED,
+    CL_DEVICE_QUEUE_REPLACEABLE_DEFAULT, CL_VERSION_MAJOR_BITS, CL_VERSION_MINOR_BITS,
+    CL_VERSION_PATCH_BITS, CL_NAME_VERSION_MAX_NAME_SIZE, CL_QUEUE_CONTEXT, CL_QUEUE_DEVICE,
     CL_QUEUE_REFERENCE_COUNT, CL_QUEUE_PROPERTIES, CL_QUEUE_SIZE, CL_QUEUE_DEVICE_DEFAULT,
+    CL_QUEUE_PRIORITY_KHR, CL_QUEUE_PRIORITY_HIGH_KHR, CL_QUEUE_PRIORITY_MED_KHR,
+    CL_QUEUE_PRIORITY_LOW_KHR, CL_QUEUE_THROTTLE_KHR, CL_QUEUE_THROTTLE_HIGH_KHR,
+    CL_QUEUE_THROTTLE_MED_KHR, CL_QUEUE_THROTTLE_LOW_KHR,
     CL_MEM_READ_WRITE, CL_MEM_WRITE_ONLY, CL_MEM_READ_ONLY, CL_MEM_USE_HOST_PTR,
     CL_MEM_ALLOC_HOST_PTR, CL_MEM_COPY_HOST_PTR, CL_MEM_HOST_WRITE_ONLY, CL_MEM_HOST_READ_ONLY,
     CL_MEM_HOST_NO_ACCESS, CL_MEM_SVM_FINE_GRAIN_BUFFER, CL_MEM_SVM_ATOMICS,
@@ -249,3 +274,6 @@ pub use self::cl_h::{clCreateCommandQueueWithProperties, clCreatePipe, clGetPipe
 #[cfg(feature = "opencl_version_2_1")]
 pub use self::cl_h::{clSetDefaultDeviceCommandQueue, clGetDeviceAndHostTimer, clGetHostTimer,
     clCreateProgramWithIL, clCloneKernel, clGetKernelSubGroupInfo, clEnqueueSVMMigrateMem};
+
+#[cfg(feature = "opencl_version_2_2")]
+pub use self::cl_h::clSetProgramSpecializationConstant;
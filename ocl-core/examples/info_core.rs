@@ -10,7 +10,7 @@ use crate::core::{PlatformInfo, DeviceInfo, ContextInfo, CommandQueueInfo, MemIn
     SamplerInfo, ProgramInfo, ProgramBuildInfo, KernelInfo, KernelArgInfo, KernelWorkGroupInfo,
     EventInfo, ProfilingInfo, ContextProperties, PlatformId, DeviceId, ImageFormat,
     ImageDescriptor, MemObjectType, AddressingMode, FilterMode, Event, ContextInfoResult,
-    ArgVal, Status};
+    ArgVal};
 
 const DIMS: [usize; 3] = [1024, 64, 16];
 const INFO_FORMAT_MULTILINE: bool = true;
@@ -26,12 +26,8 @@ macro_rules! to_string {
     ( $ expr : expr ) => {
         match $expr {
             Ok(info) => info.to_string(),
-            Err(err) => {
-                match err.api_status() {
-                    Some(Status::CL_KERNEL_ARG_INFO_NOT_AVAILABLE) => "Not available".into(),
-                    _ => err.to_string(),
-                }
-            },
+            Err(ref err) if err.is_kernel_arg_info_unavailable() => "Not available".into(),
+            Err(err) => err.to_string(),
         }
     };
 }
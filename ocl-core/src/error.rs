@@ -4,7 +4,8 @@
 use std::fmt;
 use failure::{Context, Fail, Backtrace};
 use crate::util::UtilError;
-use crate::functions::{ApiError, VersionLowError, ProgramBuildError, ApiWrapperError};
+use crate::functions::{ApiError, VersionLowError, ProgramBuildError, ApiWrapperError,
+    CallbackPanicError, ProfilingUnavailableError, KernelArgInfoUnavailableError};
 use crate::{Status, EmptyInfoResultError};
 
 
@@ -53,6 +54,15 @@ pub enum ErrorKind {
     // ApiWrapper:
     #[fail(display = "{}", _0)]
     ApiWrapper(ApiWrapperError),
+    // CallbackPanic:
+    #[fail(display = "{}", _0)]
+    CallbackPanic(CallbackPanicError),
+    // ProfilingUnavailable:
+    #[fail(display = "{}", _0)]
+    ProfilingUnavailable(ProfilingUnavailableError),
+    // KernelArgInfoUnavailable:
+    #[fail(display = "{}", _0)]
+    KernelArgInfoUnavailable(KernelArgInfoUnavailableError),
 }
 
 
@@ -70,6 +80,27 @@ impl Error {
         }
     }
 
+    /// Returns true if this error indicates that profiling information is
+    /// not available for an event.
+    pub fn is_profiling_unavailable(&self) -> bool {
+        matches!(*self.kind(), ErrorKind::ProfilingUnavailable(_))
+    }
+
+    /// Returns true if this error indicates that argument information is not
+    /// available for a kernel (see `get_kernel_arg_info`).
+    pub fn is_kernel_arg_info_unavailable(&self) -> bool {
+        matches!(*self.kind(), ErrorKind::KernelArgInfoUnavailable(_))
+    }
+
+    /// Returns the details if this error was returned because a device or
+    /// platform version was too low to call a function.
+    pub fn version_low(&self) -> Option<&VersionLowError> {
+        match *self.kind() {
+            ErrorKind::VersionLow(ref err) => Some(err),
+            _ => None,
+        }
+    }
+
     /// Returns the error variant and contents.
     pub fn kind(&self) -> &ErrorKind {
         self.inner.get_context()
@@ -189,3 +220,21 @@ impl From<ApiWrapperError> for Error {
         Error { inner: Context::new(ErrorKind::ApiWrapper(err)) }
     }
 }
+
+impl From<CallbackPanicError> for Error {
+    fn from(err: CallbackPanicError) -> Self {
+        Error { inner: Context::new(ErrorKind::CallbackPanic(err)) }
+    }
+}
+
+impl From<ProfilingUnavailableError> for Error {
+    fn from(err: ProfilingUnavailableError) -> Self {
+        Error { inner: Context::new(ErrorKind::ProfilingUnavailable(err)) }
+    }
+}
+
+impl From<KernelArgInfoUnavailableError> for Error {
+    fn from(err: KernelArgInfoUnavailableError) -> Self {
+        Error { inner: Context::new(ErrorKind::KernelArgInfoUnavailable(err)) }
+    }
+}
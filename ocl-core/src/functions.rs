@@ -20,6 +20,10 @@ use std::thread;
 use std::time::Duration;
 use std::env;
 use std::fmt;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use failure::Fail;
 use crate::ffi::{size_t, c_void};
 use num_traits::FromPrimitive;
@@ -53,11 +57,15 @@ use crate::{OclPrm, PlatformId, DeviceId, Context, ContextProperties, ContextInf
     CreateContextCallbackFn, UserDataPtr, ClPlatformIdPtr, ClDeviceIdPtr, ClContextPtr,
     EventCallbackFn, BuildProgramCallbackFn, MemMigrationFlags, MapFlags, BufferRegion,
     BufferCreateType, OpenclVersion, ClVersions, Status, CommandQueueProperties, MemMap, AsMem,
-    MemCmdRw, MemCmdAll, Event, ImageFormatParseResult};
+    MemCmdRw, MemCmdAll, Event, ImageFormatParseResult, DevicePartition};
 
 #[cfg(not(feature="opencl_vendor_mesa"))]
 use crate::{GlContextInfo, GlContextInfoResult};
 
+#[cfg(feature = "opencl_version_2_0")]
+use crate::{ffi::cl_kernel_exec_info, KernelExecInfo, QueuePriority, QueueThrottle, QueueProperties,
+    SvmFreeCallbackFn};
+
 // [TODO]: Do proper auto-detection of available OpenGL context type.
 #[cfg(target_os="macos")]
 const CL_GL_SHARING_EXT: &str = "cl_APPLE_gl_sharing";
@@ -75,6 +83,136 @@ const PLATFORM_IDS_ATTEMPT_COUNT: u64 = 5;
 //============================================================================
 
 
+/// Set when `CALLBACK_PANIC` holds an error (checked without locking).
+static CALLBACK_PANICKED: AtomicBool = AtomicBool::new(false);
+static CALLBACK_PANIC: Mutex<Option<CallbackPanicError>> = Mutex::new(None);
+
+thread_local! {
+    // Set while running a guarded callback so that API calls made from
+    // within it do not consume an earlier panic.
+    static IN_CALLBACK: Cell<bool> = const { Cell::new(false) };
+}
+
+
+/// A panic caught within a callback invoked by the OpenCL runtime.
+#[derive(Debug, Fail)]
+#[fail(display = "A panic occurred within the '{}' callback (invoked by the OpenCL runtime): {}",
+    callback, message)]
+pub struct CallbackPanicError {
+    callback: &'static str,
+    message: String,
+}
+
+impl CallbackPanicError {
+    /// Returns the name of the callback which panicked.
+    pub fn callback(&self) -> &'static str {
+        self.callback
+    }
+
+    /// Returns the panic message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+
+/// Profiling information was requested for an event which has none.
+///
+/// Profiling information is only recorded for complete commands enqueued on
+/// a queue created with `CommandQueueProperties::PROFILING_ENABLE`. User
+/// events never have any.
+#[derive(Debug, Fail)]
+#[fail(display = "Profiling information is not available for this event ({:?}). It is only \
+    recorded for complete commands enqueued on queues with profiling enabled.", status)]
+pub struct ProfilingUnavailableError {
+    status: Status,
+}
+
+impl ProfilingUnavailableError {
+    /// Returns the status returned by `clGetEventProfilingInfo`
+    /// (`CL_PROFILING_INFO_NOT_AVAILABLE`, or `CL_INVALID_VALUE` on some
+    /// platforms).
+    pub fn status(&self) -> Status {
+        self.status
+    }
+}
+
+
+/// Argument information was requested for a kernel whose program was built
+/// without the `-cl-kernel-arg-info` option.
+///
+/// Some platforms return an empty result rather than
+/// `CL_KERNEL_ARG_INFO_NOT_AVAILABLE` in this case. Others do not implement
+/// `clGetKernelArgInfo` at all and fail in irregular ways.
+#[derive(Debug, Fail)]
+#[fail(display = "Argument information is not available for kernel argument {} ({:?}). Build \
+    the program with the '-cl-kernel-arg-info' option to make it available.", arg_index, status)]
+pub struct KernelArgInfoUnavailableError {
+    arg_index: u32,
+    status: Option<Status>,
+}
+
+impl KernelArgInfoUnavailableError {
+    /// Returns the index of the argument queried.
+    pub fn arg_index(&self) -> u32 {
+        self.arg_index
+    }
+
+    /// Returns the status returned by `clGetKernelArgInfo`
+    /// (`CL_KERNEL_ARG_INFO_NOT_AVAILABLE`) or `None` if the result was
+    /// empty.
+    pub fn status(&self) -> Option<Status> {
+        self.status
+    }
+}
+
+
+/// Runs `f`, catching any panic so that it does not unwind across the FFI
+/// boundary.
+///
+/// Use this within every callback called by the OpenCL runtime. A caught
+/// panic is recorded (the first one only, until it is taken) and returned as
+/// an error by the next API function wrapper to be called, from any thread.
+/// Note that the result of that call is discarded.
+pub fn guard_callback<F: FnOnce()>(callback: &'static str, f: F) {
+    let was_in_callback = IN_CALLBACK.with(|c| c.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    IN_CALLBACK.with(|c| c.set(was_in_callback));
+
+    if let Err(payload) = result {
+        let message = match payload.downcast::<String>() {
+            Ok(msg) => *msg,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(msg) => (*msg).to_owned(),
+                Err(_) => "<non-string panic payload>".to_owned(),
+            },
+        };
+
+        let mut recorded = CALLBACK_PANIC.lock().unwrap_or_else(|e| e.into_inner());
+        if recorded.is_none() {
+            *recorded = Some(CallbackPanicError { callback, message });
+            CALLBACK_PANICKED.store(true, Ordering::Release);
+        }
+    }
+}
+
+/// Removes and returns the error recorded by the most recent panic caught
+/// by `guard_callback` which has not yet been surfaced.
+pub fn take_callback_panic() -> Option<CallbackPanicError> {
+    if !CALLBACK_PANICKED.load(Ordering::Acquire) { return None; }
+    let mut recorded = CALLBACK_PANIC.lock().unwrap_or_else(|e| e.into_inner());
+    CALLBACK_PANICKED.store(false, Ordering::Release);
+    recorded.take()
+}
+
+/// Returns the error recorded by a callback panic unless called from within
+/// a callback.
+fn surface_callback_panic() -> Option<CallbackPanicError> {
+    if IN_CALLBACK.with(|c| c.get()) { return None; }
+    take_callback_panic()
+}
+
+
 /// Don't be a dummy. Buckle your `_dummy_callback`.
 pub extern "C" fn _dummy_event_callback(_: ffi::cl_event, _: i32, _: *mut c_void) {}
 
@@ -91,35 +229,37 @@ pub extern "C" fn _dummy_event_callback(_: ffi::cl_event, _: i32, _: *mut c_void
 pub extern "C" fn _complete_user_event(src_event_ptr: cl_event, event_status: i32,
         user_data: *mut c_void)
 {
-    #[cfg(not(feature = "event_debug_print"))]
-    let _ = src_event_ptr;
+    guard_callback("_complete_user_event", || {
+        #[cfg(not(feature = "event_debug_print"))]
+        let _ = src_event_ptr;
 
-    if event_status == CommandExecutionStatus::Complete as i32 && !user_data.is_null() {
-        let tar_event_ptr = user_data as *mut _ as cl_event;
+        if event_status == CommandExecutionStatus::Complete as i32 && !user_data.is_null() {
+            let tar_event_ptr = user_data as *mut _ as cl_event;
 
-        unsafe {
-            let user_event = Event::from_raw(tar_event_ptr);
+            unsafe {
+                let user_event = Event::from_raw(tar_event_ptr);
 
-            #[cfg(feature = "event_debug_print")]
-            println!("::_complete_user_event: Setting event complete for: \
-                source: {:?}, target: {:?}...", src_event_ptr, &user_event);
+                #[cfg(feature = "event_debug_print")]
+                println!("::_complete_user_event: Setting event complete for: \
+                    source: {:?}, target: {:?}...", src_event_ptr, &user_event);
 
-            crate::set_user_event_status(&user_event, CommandExecutionStatus::Complete).unwrap();
-        }
+                crate::set_user_event_status(&user_event, CommandExecutionStatus::Complete).unwrap();
+            }
 
-        #[cfg(feature = "event_debug_print")]
-        println!("  - Event status has been set to 'CommandExecutionStatus::Complete' \
-            for event: {:?}", tar_event_ptr);
-    } else {
-        // NOTE: Though these should be unreachable, panic/unwrap will likely
-        // crash the calling module:
-        match CommandExecutionStatus::from_i32(event_status) {
-            Some(status_enum) => panic!("ocl_core::_complete_event: User data is null or event \
-                is not complete. Status: '{:?}'", status_enum),
-            None => eval_errcode(event_status, (), "clSetEventCallback",
-                Some(format!("src_event_ptr: {:?}", src_event_ptr))).unwrap(),
+            #[cfg(feature = "event_debug_print")]
+            println!("  - Event status has been set to 'CommandExecutionStatus::Complete' \
+                for event: {:?}", tar_event_ptr);
+        } else {
+            // NOTE: Though these should be unreachable, a panic here is caught
+            // and surfaced by the next API call:
+            match CommandExecutionStatus::from_i32(event_status) {
+                Some(status_enum) => panic!("ocl_core::_complete_event: User data is null or event \
+                    is not complete. Status: '{:?}'", status_enum),
+                None => eval_errcode(event_status, (), "clSetEventCallback",
+                    Some(format!("src_event_ptr: {:?}", src_event_ptr))).unwrap(),
+            }
         }
-    }
+    })
 }
 
 //============================================================================
@@ -197,6 +337,10 @@ impl fmt::Debug for ApiError {
 fn eval_errcode<T, S>(errcode: cl_int, result: T, fn_name: &'static str, fn_info: Option<S>)
         -> OclCoreResult<T>
         where S: Into<String> {
+    if let Some(err) = surface_callback_panic() {
+        return Err(err.into());
+    }
+
     if (Status::CL_SUCCESS as i32) == errcode {
         Ok(result)
     } else {
@@ -257,9 +401,11 @@ pub fn program_build_err<D: ClDeviceIdPtr>(program: &Program, device_ids: &[D])
 #[derive(Debug)]
 pub(crate) enum ApiFunction {
     None,
+    CreateSubDevices,
     RetainDevice,
     ReleaseDevice,
-    CreateProgramWithIl,
+    CreateProgramWithIL,
+    CreateProgramWithBuiltInKernels,
     CreateImage,
     CreateFromGLTexture,
     GetKernelArgInfo,
@@ -271,28 +417,81 @@ pub(crate) enum ApiFunction {
     GetExtensionFunctionAddressForPlatform,
     CompileProgram,
     LinkProgram,
-}
-
-
-/// A version kind identifier.
-#[derive(Debug)]
-pub(crate) enum VersionKind {
+    SetKernelExecInfo,
+    SetProgramSpecializationConstant,
+    CreateCommandQueueWithProperties,
+    GetDeviceAndHostTimer,
+    GetHostTimer,
+    SVMAlloc,
+    SetKernelArgSVMPointer,
+    EnqueueSVMMap,
+    EnqueueSVMUnmap,
+    EnqueueSVMMemcpy,
+    EnqueueSVMMemFill,
+    EnqueueSVMFree,
+}
+
+
+/// The kind of object whose version was checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionKind {
     Device,
     Platform,
 }
 
+impl fmt::Display for VersionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VersionKind::Device => f.write_str("device"),
+            VersionKind::Platform => f.write_str("platform"),
+        }
+    }
+}
+
 
 /// A version too low error.
+///
+/// Returned in place of calling an OpenCL function (or using a feature)
+/// which the device or platform is too old to support.
 #[derive(Debug, Fail)]
-#[fail(display = "OpenCL ({:?}) version too low to use {:?} (detected: {}, required: {}).",
-    kind, function, detected, required)]
+#[fail(display = "{} requires OpenCL {} but the {} only supports OpenCL {}. Use a {2} and \
+    driver supporting OpenCL {1} or later.", feature, required, kind, detected)]
 pub struct VersionLowError {
     detected: OpenclVersion,
     required: OpenclVersion,
-    function: ApiFunction,
+    feature: String,
     kind: VersionKind,
 }
 
+impl VersionLowError {
+    /// Returns a new error for `feature` (usually a function or method
+    /// name), which requires `required` but found `detected`.
+    pub fn new<S: Into<String>>(kind: VersionKind, detected: OpenclVersion,
+            required: OpenclVersion, feature: S) -> VersionLowError {
+        VersionLowError { detected, required, feature: feature.into(), kind }
+    }
+
+    /// Returns the version of the device or platform.
+    pub fn detected(&self) -> OpenclVersion {
+        self.detected
+    }
+
+    /// Returns the version required.
+    pub fn required(&self) -> OpenclVersion {
+        self.required
+    }
+
+    /// Returns the name of the function or feature which was unavailable.
+    pub fn feature(&self) -> &str {
+        &self.feature
+    }
+
+    /// Returns whether a device or platform version was too low.
+    pub fn kind(&self) -> VersionKind {
+        self.kind
+    }
+}
+
 
 /// An error representing miscellaneous errors from throughout this module.
 #[derive(Debug, Fail)]
@@ -315,6 +514,11 @@ pub enum ApiWrapperError {
     #[fail(display = "Length of 'devices' must equal the length of 'binaries' \
         (e.g. one binary per device).")]
     CreateProgramWithBinaryDevicesLenMismatch,
+    #[fail(display = "Length of 'devices' must be greater than zero.")]
+    CreateProgramWithBuiltInKernelsDevicesLenZero,
+    #[fail(display = "A memory object location (offset: {}) lies outside of the native \
+        kernel arguments (length: {}).", offset, len)]
+    EnqueueNativeKernelMemLocOutOfRange { offset: usize, len: usize },
     #[fail(display = "The specified function does not exist for the implementation or \
         'platform' is not a valid platform.")]
     GetExtensionFunctionAddressForPlatformInvalidFunction,
@@ -324,6 +528,18 @@ pub enum ApiWrapperError {
         'OCL_DEFAULT_PLATFORM_IDX' has an index which is out of range \
         (index: [{}], max: [{}]).", default_platform_idx, max_idx)]
     DefaultPlatformEnvVarBadIdx { default_platform_idx: usize, max_idx: usize },
+    #[fail(display = "No platform name or vendor contains the value of the environment \
+        variable 'OCL_DEFAULT_PLATFORM_NAME' ('{}').", _0)]
+    DefaultPlatformEnvVarNoMatch(String),
+    #[fail(display = "No devices found on the default platform.")]
+    DefaultDeviceNoDevices,
+    #[fail(display = "The default device set by the environment variable \
+        'OCL_DEFAULT_DEVICE_IDX' has an index which is out of range \
+        (index: [{}], max: [{}]).", default_device_idx, max_idx)]
+    DefaultDeviceEnvVarBadIdx { default_device_idx: usize, max_idx: usize },
+    #[fail(display = "No device name contains the value of the environment variable \
+        'OCL_DEFAULT_DEVICE_NAME' ('{}').", _0)]
+    DefaultDeviceEnvVarNoMatch(String),
     #[fail(display = "The default device type set by the environment variable \
         'OCL_DEFAULT_DEVICE_TYPE': ('{}') is invalid. Valid types are: 'DEFAULT', 'CPU', \
         'GPU', 'ACCELERATOR', 'CUSTOM', and 'ALL'.", _0)]
@@ -417,12 +633,11 @@ pub(crate) fn verify_versions(versions: &[OpenclVersion], required_version: [u16
 
     for &d_ver in versions {
         if d_ver < reqd_ver {
-            return Err(VersionLowError {
-                detected: d_ver,
-                required: reqd_ver,
-                function,
-                kind,
-            }.into())
+            let feature = match function {
+                ApiFunction::None => "This operation".to_owned(),
+                ref function => format!("'cl{:?}'", function),
+            };
+            return Err(VersionLowError::new(kind, d_ver, reqd_ver, feature).into())
         }
     }
 
@@ -690,18 +905,69 @@ pub fn get_device_info<D: ClDeviceIdPtr>(device: D, request: DeviceInfo)
     }
 }
 
-/// [UNIMPLEMENTED: Please implement me]
+/// Returns a device timestamp and the host timestamp taken at the same
+/// moment, both in nanoseconds, as `(device_timestamp, host_timestamp)`.
+///
+/// Device timestamps use the same clock as event profiling info; host
+/// timestamps use the clock reported by `get_host_timer`.
+///
+/// [Version Controlled: OpenCL 2.1+] See module docs for more info.
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/specs/2.2/html/OpenCL_API.html#clGetDeviceAndHostTimer)
+#[cfg(feature = "opencl_version_2_1")]
+pub fn get_device_and_host_timer<D>(device: D, device_version: Option<&OpenclVersion>)
+        -> OclCoreResult<(u64, u64)>
+        where D: ClDeviceIdPtr + ClVersions
+{
+    verify_device_version(device_version, [2, 1], &device, ApiFunction::GetDeviceAndHostTimer)?;
+
+    let mut device_timestamp = 0u64;
+    let mut host_timestamp = 0u64;
+    let errcode = unsafe { ffi::clGetDeviceAndHostTimer(device.as_ptr(), &mut device_timestamp,
+        &mut host_timestamp) };
+    eval_errcode(errcode, (device_timestamp, host_timestamp), "clGetDeviceAndHostTimer",
+        None::<String>)
+}
+
+/// Returns the current value of the host clock, in nanoseconds, as seen by
+/// `device`.
+///
+/// [Version Controlled: OpenCL 2.1+] See module docs for more info.
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/specs/2.2/html/OpenCL_API.html#clGetHostTimer)
+#[cfg(feature = "opencl_version_2_1")]
+pub fn get_host_timer<D>(device: D, device_version: Option<&OpenclVersion>) -> OclCoreResult<u64>
+        where D: ClDeviceIdPtr + ClVersions
+{
+    verify_device_version(device_version, [2, 1], &device, ApiFunction::GetHostTimer)?;
+
+    let mut host_timestamp = 0u64;
+    let errcode = unsafe { ffi::clGetHostTimer(device.as_ptr(), &mut host_timestamp) };
+    eval_errcode(errcode, host_timestamp, "clGetHostTimer", None::<String>)
+}
+
+/// Partitions `device` into sub-devices.
+///
+/// Each returned sub-device holds a reference which must eventually be
+/// released with `release_device`.
 ///
 /// [Version Controlled: OpenCL 1.2+] See module docs for more info.
-pub fn create_sub_devices(device_version: Option<&OpenclVersion>) -> OclCoreResult<()> {
-    // clCreateSubDevices(in_device: cl_device_id,
-    //                    properties: *const cl_device_partition_property,
-    //                    num_devices: cl_uint,
-    //                    out_devices: *mut cl_device_id,
-    //                    num_devices_ret: *mut cl_uint) -> cl_int;
-
-    let _ = device_version;
-    unimplemented!();
+pub fn create_sub_devices(device: &DeviceId, partition: &DevicePartition,
+        device_version: Option<&OpenclVersion>) -> OclCoreResult<Vec<DeviceId>> {
+    verify_device_version(device_version, [1, 2], device, ApiFunction::CreateSubDevices)?;
+    let properties = partition.to_raw();
+    let mut num_devices: cl_uint = 0;
+
+    let errcode = unsafe { ffi::clCreateSubDevices(device.as_ptr(), properties.as_ptr(), 0,
+        ptr::null_mut(), &mut num_devices) };
+    eval_errcode(errcode, (), "clCreateSubDevices", None::<String>)?;
+
+    let mut sub_devices: Vec<DeviceId> = iter::repeat_n(unsafe { DeviceId::null() },
+        num_devices as usize).collect();
+
+    let errcode = unsafe { ffi::clCreateSubDevices(device.as_ptr(), properties.as_ptr(),
+        num_devices, sub_devices.as_mut_ptr() as *mut cl_device_id, ptr::null_mut()) };
+    eval_errcode(errcode, sub_devices, "clCreateSubDevices", None::<String>)
 }
 
 /// Increments the reference count of a device.
@@ -729,11 +995,10 @@ pub unsafe fn release_device(device: &DeviceId, device_version: Option<&OpenclVe
 /// Creates a new context pointer valid for all devices in `device_ids`.
 ///
 /// Platform is specified in `properties`. If `properties` is `None`, the platform may
-/// default to the first available.
+/// default to the first available. Returns an error if `properties` do not
+/// form a valid combination (see `ContextProperties::validate`).
 ///
-/// [FIXME]: Incomplete implementation. Callback and userdata untested.
 /// [FIXME]: Verify OpenCL Version on property.
-/// [FIXME]: Most context sources not implemented for `ContextProperties`.
 //
 // [NOTE]: Leave commented "DEBUG" print statements intact until more
 // `ContextProperties` variants are implemented. [PROBABLY DONE]
@@ -765,7 +1030,10 @@ pub fn create_context<D: ClDeviceIdPtr>(properties: Option<&ContextProperties>,
     }
 
     let properties_bytes: Vec<isize> = match properties {
-        Some(props) => props.to_raw(),
+        Some(props) => {
+            props.validate()?;
+            props.to_raw()
+        },
         None => Vec::<isize>::with_capacity(0),
     };
 
@@ -781,11 +1049,7 @@ pub fn create_context<D: ClDeviceIdPtr>(properties: Option<&ContextProperties>,
         properties_bytes.as_ptr()
     };
 
-    // [FIXME]: Disabled:
-    let user_data_ptr = match user_data {
-        Some(_) => ptr::null_mut(),
-        None => ptr::null_mut(),
-    };
+    let user_data_ptr = user_data.unwrap_or(ptr::null_mut());
 
     let device_ids: Vec<_> = device_ids.iter().map(|d| d.as_ptr()).collect();
 
@@ -808,11 +1072,10 @@ pub fn create_context<D: ClDeviceIdPtr>(properties: Option<&ContextProperties>,
 /// Creates a new context pointer for all devices of a specific type.
 ///
 /// Platform is specified in `properties`. If `properties` is `None`, the platform may
-/// default to the first available.
+/// default to the first available. Returns an error if `properties` do not
+/// form a valid combination (see `ContextProperties::validate`).
 ///
-/// [FIXME]: Incomplete implementation. Callback and userdata untested.
 /// [FIXME]: Verify OpenCL Version on property.
-/// [FIXME]: Most context sources not implemented for `ContextProperties`.
 //
 // [NOTE]: Leave commented "DEBUG" print statements intact until more
 // `ContextProperties` variants are implemented.
@@ -824,7 +1087,10 @@ pub fn create_context_from_type<D: ClDeviceIdPtr>(properties: Option<&ContextPro
     // println!("CREATE_CONTEXT: ORIGINAL: properties: {:?}", properties);
 
     let properties_bytes: Vec<isize> = match properties {
-        Some(props) => props.to_raw(),
+        Some(props) => {
+            props.validate()?;
+            props.to_raw()
+        },
         None => Vec::<isize>::with_capacity(0),
     };
 
@@ -840,11 +1106,7 @@ pub fn create_context_from_type<D: ClDeviceIdPtr>(properties: Option<&ContextPro
         properties_bytes.as_ptr()
     };
 
-    // [FIXME]: Disabled:
-    let user_data_ptr = match user_data {
-        Some(_) => ptr::null_mut(),
-        None => ptr::null_mut(),
-    };
+    let user_data_ptr = user_data.unwrap_or(ptr::null_mut());
 
     let mut errcode: cl_int = 0;
 
@@ -1082,6 +1344,69 @@ pub fn create_command_queue<C, D>(
 
 }
 
+/// Returns a new command queue pointer created from a list of properties.
+///
+/// `queue_size` may only be specified for on-device queues (those with
+/// `CommandQueueProperties::ON_DEVICE` set) and must not exceed
+/// `CL_DEVICE_QUEUE_ON_DEVICE_MAX_SIZE`.
+///
+/// `priority` and `throttle` require the `cl_khr_priority_hints` and
+/// `cl_khr_throttle_hints` extensions respectively.
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_0")]
+pub fn create_command_queue_with_properties<C, D>(
+            context: C,
+            device: D,
+            properties: Option<CommandQueueProperties>,
+            queue_size: Option<u32>,
+            priority: Option<QueuePriority>,
+            throttle: Option<QueueThrottle>,
+            device_version: Option<&OpenclVersion>,
+        ) -> OclCoreResult<CommandQueue>
+        where C: ClContextPtr + ClVersions, D: ClDeviceIdPtr
+{
+    let mut queue_props = QueueProperties::new();
+    if let Some(props) = properties { queue_props = queue_props.flags(props); }
+    if let Some(size) = queue_size { queue_props = queue_props.size(size); }
+    if let Some(priority) = priority { queue_props = queue_props.priority(priority); }
+    if let Some(throttle) = throttle { queue_props = queue_props.throttle(throttle); }
+
+    create_command_queue_with_properties_list(context, device, &queue_props, device_version)
+}
+
+/// Returns a new command queue pointer created with an arbitrary list of
+/// properties, including vendor-specific properties.
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_0")]
+pub fn create_command_queue_with_properties_list<C, D>(
+            context: C,
+            device: D,
+            properties: &QueueProperties,
+            device_version: Option<&OpenclVersion>,
+        ) -> OclCoreResult<CommandQueue>
+        where C: ClContextPtr + ClVersions, D: ClDeviceIdPtr
+{
+    verify_device_version(device_version, [2, 0], &context,
+        ApiFunction::CreateCommandQueueWithProperties)?;
+
+    // Verify that the context is valid:
+    verify_context(context)?;
+
+    let queue_props = properties.to_raw();
+    let mut errcode: cl_int = 0;
+
+    let cq_ptr = unsafe { ffi::clCreateCommandQueueWithProperties(
+        context.as_ptr(),
+        device.as_ptr(),
+        queue_props.as_ptr(),
+        &mut errcode
+    ) };
+    eval_errcode(errcode, cq_ptr, "clCreateCommandQueueWithProperties", None::<String>)
+        .map(|cq_ptr| unsafe { CommandQueue::from_raw_create_ptr(cq_ptr) })
+}
+
 /// Increments the reference count of a command queue.
 pub unsafe fn retain_command_queue(queue: &CommandQueue) -> OclCoreResult<()> {
     eval_errcode(ffi::clRetainCommandQueue(queue.as_ptr()), (), "clRetainCommandQueue", None::<String>)
@@ -1572,6 +1897,56 @@ pub fn get_image_info(obj: &Mem, request: ImageInfo) -> OclCoreResult<ImageInfoR
     ImageInfoResult::from_bytes(request, result)
 }
 
+/// Allocates a shared virtual memory (SVM) buffer of `size` bytes and
+/// returns a pointer to it.
+///
+/// `flags` may contain one of `MemFlags::READ_WRITE`, `WRITE_ONLY`, or
+/// `READ_ONLY`, and `MemFlags::SVM_FINE_GRAIN_BUFFER` (optionally with
+/// `MemFlags::SVM_ATOMICS`) for a fine-grained buffer. An `alignment` of
+/// zero uses the default alignment (the size of the largest OpenCL type
+/// supported by the devices in `context`).
+///
+/// The allocation must be freed with `svm_free`.
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clSVMAlloc.html)
+#[cfg(feature = "opencl_version_2_0")]
+pub fn svm_alloc<C>(context: C, flags: MemFlags, size: usize, alignment: u32,
+        device_versions: Option<&[OpenclVersion]>) -> OclCoreResult<*mut c_void>
+        where C: ClContextPtr + ClVersions
+{
+    verify_device_versions(device_versions, [2, 0], &context, ApiFunction::SVMAlloc)?;
+
+    let svm_ptr = unsafe { ffi::clSVMAlloc(
+        context.as_ptr(),
+        flags.bits() as ffi::cl_svm_mem_flags,
+        size,
+        alignment,
+    ) };
+
+    if svm_ptr.is_null() {
+        Err(format!("clSVMAlloc: Unable to allocate {} bytes (alignment: {}). The size may \
+            exceed 'CL_DEVICE_MAX_MEM_ALLOC_SIZE', the flags or alignment may be invalid, or \
+            the devices in the context may not support SVM.", size, alignment).into())
+    } else {
+        Ok(svm_ptr)
+    }
+}
+
+/// Frees a shared virtual memory buffer allocated with `svm_alloc`.
+///
+/// ### Safety
+///
+/// `svm_ptr` must have been returned by `svm_alloc` for `context`, must not
+/// have already been freed, and must not be in use by any enqueued command.
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clSVMFree.html)
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn svm_free<C: ClContextPtr>(context: C, svm_ptr: *mut c_void) {
+    ffi::clSVMFree(context.as_ptr(), svm_ptr)
+}
+
 /// [UNIMPLEMENTED: Please implement me]
 pub fn set_mem_object_destructor_callback() -> OclCoreResult<()> {
     // ffi::clSetMemObjectDestructorCallback(memobj: cl_mem,
@@ -1737,18 +2112,46 @@ pub fn create_program_with_binary<C, D>(
     unsafe { Ok(Program::from_raw_create_ptr(program)) }
 }
 
-/// [UNIMPLEMENTED: Please implement me]
+/// Creates a program object for a context, and loads the information related
+/// to the built-in kernels named in `kernel_names` into it.
+///
+/// Built-in kernels are fixed-function kernels provided by a device (often
+/// an FPGA or other accelerator) and listed by `DeviceInfo::BuiltInKernels`.
+/// The resulting program does not need to be built; kernels may be created
+/// from it directly.
 ///
 /// [Version Controlled: OpenCL 1.2+] See module docs for more info.
-pub fn create_program_with_built_in_kernels(device_version: Option<&OpenclVersion>)
-            -> OclCoreResult<()> {
-    // clCreateProgramWithBuiltInKernels(context: cl_context,
-    //                                  num_devices: cl_uint,
-    //                                  device_list: *const cl_device_id,
-    //                                  kernel_names: *mut char,
-    //                                  errcode_ret: *mut cl_int) -> cl_program;
-    let _ =  device_version;
-    unimplemented!();
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/sdk/1.2/docs/man/xhtml/clCreateProgramWithBuiltInKernels.html)
+pub fn create_program_with_built_in_kernels<C, D>(
+            context: C,
+            devices: &[D],
+            kernel_names: &[&str],
+            device_versions: Option<&[OpenclVersion]>,
+        ) -> OclCoreResult<Program>
+        where C: ClContextPtr + ClVersions, D: ClDeviceIdPtr
+{
+    verify_device_versions(device_versions, [1, 2], &context,
+        ApiFunction::CreateProgramWithBuiltInKernels)?;
+
+    if devices.is_empty() {
+        return Err(ApiWrapperError::CreateProgramWithBuiltInKernelsDevicesLenZero.into())
+    }
+
+    let device_ptrs = DevicePtrList::new(Some(devices));
+    let kernel_names = CString::new(kernel_names.join(";"))?;
+    let mut errcode: cl_int = 0;
+
+    let program_ptr = unsafe { ffi::clCreateProgramWithBuiltInKernels(
+        context.as_ptr(),
+        device_ptrs.num(),
+        device_ptrs.as_ptr(),
+        kernel_names.as_ptr() as *mut _,
+        &mut errcode,
+    ) };
+
+    eval_errcode(errcode, program_ptr, "clCreateProgramWithBuiltInKernels", None::<String>)
+        .map(|ptr| unsafe { Program::from_raw_create_ptr(ptr) })
 }
 
 /// Returns a new `Program` loaded with the provided IL bytes.
@@ -1762,7 +2165,7 @@ pub fn create_program_with_il<C>(
         ) -> OclCoreResult<Program>
         where C: ClContextPtr + ClVersions
 {
-    verify_device_versions(device_versions, [2, 1], &context, ApiFunction::CreateProgramWithIl)?;
+    verify_device_versions(device_versions, [2, 1], &context, ApiFunction::CreateProgramWithIL)?;
 
     let mut errcode: cl_int = 0;
 
@@ -1777,6 +2180,33 @@ pub fn create_program_with_il<C>(
         .map(|ptr| unsafe { Program::from_raw_create_ptr(ptr) })
 }
 
+/// Sets the value of a specialization constant of a program created from
+/// SPIR-V IL.
+///
+/// `spec_value` must be the size of the constant as declared in the IL (a
+/// `bool` constant, for example, is one byte). Specialization constants must
+/// be set before the program is built.
+///
+/// [Version Controlled: OpenCL 2.2+] See module docs for more info.
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/specs/2.2/html/OpenCL_API.html#clSetProgramSpecializationConstant)
+#[cfg(feature = "opencl_version_2_2")]
+pub fn set_program_specialization_constant<T: Copy>(program: &Program, spec_id: u32,
+        spec_value: &[T], device_versions: Option<&[OpenclVersion]>) -> OclCoreResult<()>
+{
+    verify_device_versions(device_versions, [2, 2], program,
+        ApiFunction::SetProgramSpecializationConstant)?;
+
+    let errcode = unsafe { ffi::clSetProgramSpecializationConstant(
+        program.as_ptr(),
+        spec_id,
+        mem::size_of_val(spec_value) as size_t,
+        spec_value.as_ptr() as *const c_void,
+    ) };
+
+    eval_errcode(errcode, (), "clSetProgramSpecializationConstant", None::<String>)
+}
+
 /// Increments a program reference counter.
 pub unsafe fn retain_program(program: &Program) -> OclCoreResult<()> {
     eval_errcode(ffi::clRetainProgram(program.as_ptr()), (), "clRetainProgram", None::<String>)
@@ -1842,6 +2272,49 @@ pub fn build_program<D: ClDeviceIdPtr>(
     }
 }
 
+/// Builds a program without waiting for the build to complete, calling
+/// `pfn_notify` with `user_data` once it has (successfully or otherwise).
+///
+/// Some platforms build synchronously regardless, in which case `pfn_notify`
+/// may be called before this function returns. Check the build status of
+/// each device (`ProgramBuildInfo::BuildStatus`) once notified.
+///
+/// ## Safety
+///
+/// `pfn_notify` is called on a thread owned by the OpenCL runtime, at most
+/// once, and is responsible for any clean-up of `user_data`. If this function
+/// returns an error, `pfn_notify` may or may not have been called.
+pub unsafe fn build_program_notify<D: ClDeviceIdPtr>(
+            program: &Program,
+            devices: Option<&[D]>,
+            options: &CString,
+            pfn_notify: BuildProgramCallbackFn,
+            user_data: *mut c_void,
+        ) -> OclCoreResult<()>
+{
+    let device_ptrs = DevicePtrList::from(devices);
+
+    let errcode = ffi::clBuildProgram(
+        program.as_ptr() as cl_program,
+        device_ptrs.num(),
+        device_ptrs.as_ptr(),
+        options.as_ptr(),
+        Some(pfn_notify),
+        user_data,
+    );
+
+    if errcode == Status::CL_BUILD_PROGRAM_FAILURE as i32 {
+        if let Some(ds) = devices {
+            program_build_err(program, ds).map_err(|err| err.into())
+        } else {
+            let ds = program.devices()?;
+            program_build_err(program, &ds).map_err(|err| err.into())
+        }
+    } else {
+        eval_errcode(errcode, (), "clBuildProgram", None::<String>)
+    }
+}
+
 /// Compiles a program’s source for all the devices or a specific device(s) in
 /// the OpenCL context associated with program.
 ///
@@ -2144,23 +2617,117 @@ pub fn set_kernel_arg(kernel: &Kernel, index: u32, arg_val: ArgVal)
     }
 }
 
-/// Get kernel info.
-pub fn get_kernel_info(obj: &Kernel, request: KernelInfo) -> OclCoreResult<KernelInfoResult> {
-    let mut result_size: size_t = 0;
+/// Sets the argument values for each `(index, value)` pair in `args`, in
+/// order, stopping at the first failure.
+///
+/// The resulting error identifies the kernel, the index which failed, and
+/// its position within `args`.
+///
+/// ### Example:
+///
+/// ```rust, ignore
+/// let kernel = core::create_kernel(&program, "multiply")?;
+/// core::set_kernel_args(&kernel, &[
+///     (0, ArgVal::scalar(&10.0f32)),
+///     (1, ArgVal::mem(&buffer)),
+/// ])?;
+/// ```
+///
+/// [SDK Documentation](https://www.khronos.org/registry/cl/sdk/1.2/docs/man/xhtml/clSetKernelArg.html)
+pub fn set_kernel_args(kernel: &Kernel, args: &[(u32, ArgVal)]) -> OclCoreResult<()> {
+    for (pos, &(index, ref arg_val)) in args.iter().enumerate() {
+        let (size, value) = arg_val.as_raw();
+
+        let err = unsafe { ffi::clSetKernelArg(
+                kernel.as_ptr(),
+                index,
+                size,
+                value,
+        ) };
+
+        if err != Status::CL_SUCCESS as i32 {
+            let name = get_kernel_name(kernel)?;
+            let info = format!("{}: argument index: {} ({} of {})", name, index, pos + 1,
+                args.len());
+            return eval_errcode(err, (), "clSetKernelArg", Some(info));
+        }
+    }
+    Ok(())
+}
 
-    let errcode = unsafe { ffi::clGetKernelInfo(
-        obj.as_ptr() as cl_kernel,
-        request as cl_kernel_info,
-        0 as size_t,
-        ptr::null_mut(),
-        &mut result_size as *mut size_t,
-    ) };
+/// Sets a shared virtual memory pointer as the argument value at `index`.
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+///
+/// ### Safety
+///
+/// `svm_ptr` must point into an SVM allocation (see `svm_alloc`) which
+/// remains valid for as long as the kernel may be enqueued.
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clSetKernelArgSVMPointer.html)
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn set_kernel_arg_svm_pointer(kernel: &Kernel, index: u32, svm_ptr: *const c_void,
+        device_versions: Option<&[OpenclVersion]>) -> OclCoreResult<()>
+{
+    verify_device_versions(device_versions, [2, 0], kernel, ApiFunction::SetKernelArgSVMPointer)?;
 
-    eval_errcode(errcode, (), "clGetKernelInfo", None::<String>)?;
+    let err = ffi::clSetKernelArgSVMPointer(kernel.as_ptr(), index, svm_ptr);
 
-    // If result size is zero, return an empty info result directly:
-    if result_size == 0 {
-        return KernelInfoResult::from_bytes(request, vec![]);
+    if err != Status::CL_SUCCESS as i32 {
+        let name = get_kernel_name(kernel)?;
+        eval_errcode(err, (), "clSetKernelArgSVMPointer", Some(name))
+    } else {
+        Ok(())
+    }
+}
+
+/// Passes additional information other than argument values to a kernel.
+///
+/// `param_value` is passed as an array of `T`: a list of SVM pointers
+/// (`*const c_void`) for `KernelExecInfo::SvmPtrs` or a single `cl_bool`
+/// for `KernelExecInfo::SvmFineGrainSystem`.
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+///
+/// ### Safety
+///
+/// Any pointers passed must remain valid for as long as the kernel may be
+/// enqueued.
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clSetKernelExecInfo.html)
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn set_kernel_exec_info<T: Copy>(kernel: &Kernel, param_name: KernelExecInfo,
+        param_value: &[T], device_versions: Option<&[OpenclVersion]>) -> OclCoreResult<()>
+{
+    verify_device_versions(device_versions, [2, 0], kernel, ApiFunction::SetKernelExecInfo)?;
+
+    let errcode = ffi::clSetKernelExecInfo(
+        kernel.as_ptr(),
+        param_name as cl_kernel_exec_info,
+        mem::size_of_val(param_value) as size_t,
+        param_value.as_ptr() as *const c_void,
+    );
+
+    eval_errcode(errcode, (), "clSetKernelExecInfo", None::<String>)
+}
+
+/// Get kernel info.
+pub fn get_kernel_info(obj: &Kernel, request: KernelInfo) -> OclCoreResult<KernelInfoResult> {
+    let mut result_size: size_t = 0;
+
+    let errcode = unsafe { ffi::clGetKernelInfo(
+        obj.as_ptr() as cl_kernel,
+        request as cl_kernel_info,
+        0 as size_t,
+        ptr::null_mut(),
+        &mut result_size as *mut size_t,
+    ) };
+
+    eval_errcode(errcode, (), "clGetKernelInfo", None::<String>)?;
+
+    // If result size is zero, return an empty info result directly:
+    if result_size == 0 {
+        return KernelInfoResult::from_bytes(request, vec![]);
     }
 
     let mut result: Vec<u8> = iter::repeat(0u8).take(result_size).collect();
@@ -2179,6 +2746,12 @@ pub fn get_kernel_info(obj: &Kernel, request: KernelInfo) -> OclCoreResult<Kerne
 
 /// Get kernel arg info.
 ///
+/// Returns a `KernelArgInfoUnavailableError` (see
+/// `Error::is_kernel_arg_info_unavailable`) if the program was built without
+/// the `-cl-kernel-arg-info` option. `Error::api_status` returns `None` for
+/// this error, even when the platform reported
+/// `CL_KERNEL_ARG_INFO_NOT_AVAILABLE`.
+///
 /// [Version Controlled: OpenCL 1.2+] See module docs for more info.
 pub fn get_kernel_arg_info(obj: &Kernel, arg_index: u32, request: KernelArgInfo,
         device_versions: Option<&[OpenclVersion]>) -> OclCoreResult<KernelArgInfoResult>
@@ -2207,11 +2780,15 @@ pub fn get_kernel_arg_info(obj: &Kernel, arg_index: u32, request: KernelArgInfo,
     // if let Err(err) = eval_errcode(errcode, (), "clGetKernelArgInfo", None::<String>) {
     //     return KernelArgInfoResult::from(err);
     // }
+    if errcode == Status::CL_KERNEL_ARG_INFO_NOT_AVAILABLE as i32 {
+        return Err(KernelArgInfoUnavailableError { arg_index,
+            status: Some(Status::CL_KERNEL_ARG_INFO_NOT_AVAILABLE) }.into());
+    }
     eval_errcode(errcode, (), "clGetKernelArgInfo", None::<String>)?;
 
-    // If result size is zero, return an empty info result directly:
+    // An empty result means the same thing on some platforms:
     if result_size == 0 {
-        return KernelArgInfoResult::from_bytes(request, vec![]);
+        return Err(KernelArgInfoUnavailableError { arg_index, status: None }.into());
     }
 
     let mut result: Vec<u8> = iter::repeat(0u8).take(result_size).collect();
@@ -2380,6 +2957,23 @@ pub fn set_user_event_status<'e,E: ClEventPtrRef<'e>>(event: &'e E,
     }
 }
 
+/// Terminates a user event with an error status.
+///
+/// Commands waiting on the event are not executed and their own events are
+/// terminated with an error (the exact status is implementation-defined).
+/// `error` must be a (negative) error code.
+pub fn set_user_event_error<'e,E: ClEventPtrRef<'e>>(event: &'e E, error: Status)
+        -> OclCoreResult<()>
+{
+    if (error as cl_int) >= 0 {
+        return Err(format!("set_user_event_error: '{:?}' is not an error status.", error).into());
+    }
+    unsafe {
+        eval_errcode(ffi::clSetUserEventStatus(*event.as_ptr_ref(), error as cl_int),
+            (), "clSetUserEventStatus", None::<String>)
+    }
+}
+
 /// Sets a callback function which is called as soon as the `callback_trigger`
 /// status is reached.
 ///
@@ -2427,8 +3021,14 @@ pub fn get_event_profiling_info<'e, E: ClEventPtrRef<'e>>(event: &'e E, request:
 
     // Don't generate a full error report for `CL_INVALID_VALUE` it just means
     // that event profiling info is not available on this platform.
-    if errcode < 0 && Status::from_i32(errcode).unwrap() == Status::CL_INVALID_VALUE {
-        return Err(OclCoreError::from("<unavailable (CL_INVALID_VALUE)>"));
+    if errcode < 0 {
+        match Status::from_i32(errcode) {
+            Some(status @ Status::CL_PROFILING_INFO_NOT_AVAILABLE) |
+                    Some(status @ Status::CL_INVALID_VALUE) => {
+                return Err(ProfilingUnavailableError { status }.into());
+            },
+            _ => (),
+        }
     }
 
     eval_errcode(errcode, (), "clGetEventProfilingInfo", None::<String>)?;
@@ -2872,6 +3472,159 @@ pub fn enqueue_release_gl_objects<En, Ewl>(
     eval_errcode(errcode, (), "clEnqueueReleaseGLObjects", None::<String>)
 }
 
+/// Returns the address of the `cl_khr_d3d11_sharing` extension function
+/// named `fn_name` for the platform of `context`.
+#[cfg(all(windows, feature = "d3d11_sharing"))]
+unsafe fn d3d11_sharing_fn<C>(context: C, fn_name: &'static str) -> OclCoreResult<*mut c_void>
+        where C: ClContextPtr
+{
+    let platform = match get_context_platform(context)? {
+        Some(platform) => platform,
+        None => return Err(format!("ocl::core::{}: The context properties do not specify \
+            a platform.", fn_name).into()),
+    };
+    get_extension_function_address_for_platform(&platform, fn_name, None)
+}
+
+/// Returns a buffer created from a Direct3D 11 buffer (`ID3D11Buffer *`).
+///
+/// Requires the `cl_khr_d3d11_sharing` extension and a context created with
+/// `ContextProperties::d3d11_device`.
+///
+/// ## Safety
+///
+/// `resource` must be a valid `ID3D11Buffer *` created by the Direct3D
+/// device associated with `context`, and `flags` must be appropriate for it.
+///
+// [UNTESTED]
+//
+#[cfg(all(windows, feature = "d3d11_sharing"))]
+pub unsafe fn create_from_d3d11_buffer<C>(context: C, flags: MemFlags, resource: *mut c_void)
+        -> OclCoreResult<Mem>
+        where C: ClContextPtr + Copy
+{
+    let create_fn: ffi::clCreateFromD3D11BufferKHR_fn =
+        mem::transmute(d3d11_sharing_fn(context, "clCreateFromD3D11BufferKHR")?);
+    let mut errcode: cl_int = 0;
+
+    let buf_ptr = create_fn(context.as_ptr(), flags.bits() as cl_mem_flags, resource,
+        &mut errcode);
+    eval_errcode(errcode, buf_ptr, "clCreateFromD3D11BufferKHR", None::<String>)
+        .map(|ptr| Mem::from_raw_create_ptr(ptr))
+}
+
+/// Returns an image created from a subresource of a Direct3D 11 2D texture
+/// (`ID3D11Texture2D *`).
+///
+/// Requires the `cl_khr_d3d11_sharing` extension and a context created with
+/// `ContextProperties::d3d11_device`.
+///
+/// ## Safety
+///
+/// `resource` must be a valid `ID3D11Texture2D *` created by the Direct3D
+/// device associated with `context`, and `flags` must be appropriate for it.
+///
+// [UNTESTED]
+//
+#[cfg(all(windows, feature = "d3d11_sharing"))]
+pub unsafe fn create_from_d3d11_texture_2d<C>(context: C, flags: MemFlags, resource: *mut c_void,
+            subresource: u32) -> OclCoreResult<Mem>
+        where C: ClContextPtr + Copy
+{
+    let create_fn: ffi::clCreateFromD3D11Texture2DKHR_fn =
+        mem::transmute(d3d11_sharing_fn(context, "clCreateFromD3D11Texture2DKHR")?);
+    let mut errcode: cl_int = 0;
+
+    let image_ptr = create_fn(context.as_ptr(), flags.bits() as cl_mem_flags, resource,
+        subresource, &mut errcode);
+    eval_errcode(errcode, image_ptr, "clCreateFromD3D11Texture2DKHR", None::<String>)
+        .map(|ptr| Mem::from_raw_create_ptr(ptr))
+}
+
+/// Returns an image created from a subresource of a Direct3D 11 3D texture
+/// (`ID3D11Texture3D *`).
+///
+/// Requires the `cl_khr_d3d11_sharing` extension and a context created with
+/// `ContextProperties::d3d11_device`.
+///
+/// ## Safety
+///
+/// `resource` must be a valid `ID3D11Texture3D *` created by the Direct3D
+/// device associated with `context`, and `flags` must be appropriate for it.
+///
+// [UNTESTED]
+//
+#[cfg(all(windows, feature = "d3d11_sharing"))]
+pub unsafe fn create_from_d3d11_texture_3d<C>(context: C, flags: MemFlags, resource: *mut c_void,
+            subresource: u32) -> OclCoreResult<Mem>
+        where C: ClContextPtr + Copy
+{
+    let create_fn: ffi::clCreateFromD3D11Texture3DKHR_fn =
+        mem::transmute(d3d11_sharing_fn(context, "clCreateFromD3D11Texture3DKHR")?);
+    let mut errcode: cl_int = 0;
+
+    let image_ptr = create_fn(context.as_ptr(), flags.bits() as cl_mem_flags, resource,
+        subresource, &mut errcode);
+    eval_errcode(errcode, image_ptr, "clCreateFromD3D11Texture3DKHR", None::<String>)
+        .map(|ptr| Mem::from_raw_create_ptr(ptr))
+}
+
+/// Acquire OpenCL memory objects (buffers and images) that have been created
+/// from Direct3D 11 resources.
+#[cfg(all(windows, feature = "d3d11_sharing"))]
+pub fn enqueue_acquire_d3d11_objects<En, Ewl>(
+            command_queue: &CommandQueue,
+            mem_objects: &[Mem],
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+        ) -> OclCoreResult<()>
+        where En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    let acquire_fn: ffi::clEnqueueAcquireD3D11ObjectsKHR_fn = unsafe {
+        mem::transmute(d3d11_sharing_fn(command_queue, "clEnqueueAcquireD3D11ObjectsKHR")?)
+    };
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let errcode = acquire_fn(
+        command_queue.as_ptr(),
+        mem_objects.len() as u32,
+        mem_objects.as_ptr() as *const cl_mem,
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr
+    );
+    eval_errcode(errcode, (), "clEnqueueAcquireD3D11ObjectsKHR", None::<String>)
+}
+
+/// Release OpenCL memory objects (buffers and images) that have been created
+/// from Direct3D 11 resources.
+#[cfg(all(windows, feature = "d3d11_sharing"))]
+pub fn enqueue_release_d3d11_objects<En, Ewl>(
+            command_queue: &CommandQueue,
+            mem_objects: &[Mem],
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+        ) -> OclCoreResult<()>
+        where En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    let release_fn: ffi::clEnqueueReleaseD3D11ObjectsKHR_fn = unsafe {
+        mem::transmute(d3d11_sharing_fn(command_queue, "clEnqueueReleaseD3D11ObjectsKHR")?)
+    };
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let errcode = release_fn(
+        command_queue.as_ptr(),
+        mem_objects.len() as u32,
+        mem_objects.as_ptr() as *const cl_mem,
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr
+    );
+    eval_errcode(errcode, (), "clEnqueueReleaseD3D11ObjectsKHR", None::<String>)
+}
+
 
 /// Reads an image from device to host memory.
 ///
@@ -3287,6 +4040,220 @@ pub fn enqueue_unmap_mem_object<T, M, En, Ewl>(
     eval_errcode(errcode, (), "clEnqueueUnmapMemObject", None::<String>)
 }
 
+/// Enqueues a command to map `size` bytes of a coarse-grained shared virtual
+/// memory buffer, starting at `svm_ptr`, for host access.
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+///
+/// ## Safety
+///
+/// `svm_ptr` and `size` must lie within an SVM allocation (see `svm_alloc`).
+/// The region must not be accessed by the host until the map is complete
+/// and must not be used by the device until `enqueue_svm_unmap` completes.
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clEnqueueSVMMap.html)
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn enqueue_svm_map<En, Ewl>(
+            command_queue: &CommandQueue,
+            block: bool,
+            map_flags: MapFlags,
+            svm_ptr: *mut c_void,
+            size: usize,
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+            device_version: Option<&OpenclVersion>,
+        ) -> OclCoreResult<()>
+        where En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    verify_device_version(device_version, [2, 0], command_queue, ApiFunction::EnqueueSVMMap)?;
+
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let errcode = ffi::clEnqueueSVMMap(
+        command_queue.as_ptr(),
+        block as cl_bool,
+        map_flags.bits(),
+        svm_ptr,
+        size,
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr,
+    );
+
+    eval_errcode(errcode, (), "clEnqueueSVMMap", None::<String>)
+}
+
+/// Enqueues a command to unmap a shared virtual memory region previously
+/// mapped with `enqueue_svm_map`.
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+///
+/// ## Safety
+///
+/// `svm_ptr` must have been mapped by `enqueue_svm_map` and must not be
+/// accessed by the host after this command is enqueued.
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clEnqueueSVMUnmap.html)
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn enqueue_svm_unmap<En, Ewl>(
+            command_queue: &CommandQueue,
+            svm_ptr: *mut c_void,
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+            device_version: Option<&OpenclVersion>,
+        ) -> OclCoreResult<()>
+        where En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    verify_device_version(device_version, [2, 0], command_queue, ApiFunction::EnqueueSVMUnmap)?;
+
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let errcode = ffi::clEnqueueSVMUnmap(
+        command_queue.as_ptr(),
+        svm_ptr,
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr,
+    );
+
+    eval_errcode(errcode, (), "clEnqueueSVMUnmap", None::<String>)
+}
+
+/// Enqueues a command to copy `size` bytes from `src_ptr` to `dst_ptr`,
+/// either of which may be a shared virtual memory or host pointer.
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+///
+/// ## Safety
+///
+/// Both regions must be valid for `size` bytes and must not overlap. Unless
+/// `block` is set, neither region may be accessed by the host until the
+/// command completes.
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clEnqueueSVMMemcpy.html)
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn enqueue_svm_memcpy<En, Ewl>(
+            command_queue: &CommandQueue,
+            block: bool,
+            dst_ptr: *mut c_void,
+            src_ptr: *const c_void,
+            size: usize,
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+            device_version: Option<&OpenclVersion>,
+        ) -> OclCoreResult<()>
+        where En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    verify_device_version(device_version, [2, 0], command_queue, ApiFunction::EnqueueSVMMemcpy)?;
+
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let errcode = ffi::clEnqueueSVMMemcpy(
+        command_queue.as_ptr(),
+        block as cl_bool,
+        dst_ptr,
+        src_ptr,
+        size,
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr,
+    );
+
+    eval_errcode(errcode, (), "clEnqueueSVMMemcpy", None::<String>)
+}
+
+/// Enqueues a command to fill `size` bytes of a shared virtual memory
+/// region, starting at `svm_ptr`, with repeated copies of `pattern`.
+///
+/// `size` must be a multiple of the size of `T`.
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+///
+/// ## Safety
+///
+/// `svm_ptr` and `size` must lie within an SVM allocation (see `svm_alloc`).
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clEnqueueSVMMemFill.html)
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn enqueue_svm_mem_fill<T, En, Ewl>(
+            command_queue: &CommandQueue,
+            svm_ptr: *mut c_void,
+            pattern: T,
+            size: usize,
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+            device_version: Option<&OpenclVersion>,
+        ) -> OclCoreResult<()>
+        where T: OclPrm, En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    verify_device_version(device_version, [2, 0], command_queue, ApiFunction::EnqueueSVMMemFill)?;
+
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let errcode = ffi::clEnqueueSVMMemFill(
+        command_queue.as_ptr(),
+        svm_ptr,
+        &pattern as *const _ as *const c_void,
+        mem::size_of::<T>(),
+        size,
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr,
+    );
+
+    eval_errcode(errcode, (), "clEnqueueSVMMemFill", None::<String>)
+}
+
+/// Enqueues a command to free a list of shared virtual memory allocations
+/// once all preceding commands in the queue have completed.
+///
+/// If `pfn_free_func` is `None` the allocations are freed by the runtime
+/// with `clSVMFree`. Otherwise `pfn_free_func` is called, with `user_data`,
+/// when the command executes and is responsible for freeing them.
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+///
+/// ## Safety
+///
+/// Each pointer must have been returned by `svm_alloc` and must not be used
+/// by commands enqueued after this one or by the host.
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clEnqueueSVMFree.html)
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn enqueue_svm_free<En, Ewl>(
+            command_queue: &CommandQueue,
+            svm_ptrs: &[*mut c_void],
+            pfn_free_func: Option<SvmFreeCallbackFn>,
+            user_data: *mut c_void,
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+            device_version: Option<&OpenclVersion>,
+        ) -> OclCoreResult<()>
+        where En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    verify_device_version(device_version, [2, 0], command_queue, ApiFunction::EnqueueSVMFree)?;
+
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let errcode = ffi::clEnqueueSVMFree(
+        command_queue.as_ptr(),
+        svm_ptrs.len() as cl_uint,
+        svm_ptrs.as_ptr() as *const *const c_void,
+        pfn_free_func,
+        user_data,
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr,
+    );
+
+    eval_errcode(errcode, (), "clEnqueueSVMFree", None::<String>)
+}
+
 /// Enqueues a command to indicate which device a set of memory objects should
 /// be associated with.
 ///
@@ -3461,19 +4428,73 @@ pub unsafe fn enqueue_task<En: ClNullEventPtr, Ewl: ClWaitListPtr>(
     eval_errcode(errcode, (), "clEnqueueTask", kernel_name)
 }
 
-/// [UNIMPLEMENTED: Please implement me]
-pub fn enqueue_native_kernel() -> OclCoreResult<()> {
-    // ffi::clEnqueueNativeKernel(command_queue: cl_command_queue,
-    //                          user_func: extern fn (*mut c_void),
-    //                          args: *mut c_void,
-    //                          cb_args: size_t,
-    //                          num_mem_objects: cl_uint,
-    //                          mem_list: *const cl_mem,
-    //                          args_mem_loc: *const *const c_void,
-    //                          num_events_in_wait_list: cl_uint,
-    //                          event_wait_list: *const cl_event,
-    //                          event: *mut cl_event) -> cl_int;
-    unimplemented!();
+/// Enqueues a command to execute a native (host) function, `user_func`, on
+/// a device supporting `DeviceExecCapabilities::NATIVE_KERNEL`.
+///
+/// `args` is copied by the implementation and a pointer to the copy is
+/// passed to `user_func`. Each entry of `mem_objects` is a memory object and
+/// the byte offset within `args` at which its handle is stored; the handle
+/// is written by this function and replaced by the implementation with a
+/// pointer to the memory object's contents before `user_func` is called.
+///
+/// ## Safety
+///
+/// `user_func` runs on an implementation thread and must interpret `args`
+/// correctly. Offsets must be suitably aligned for a pointer.
+///
+/// [SDK Documentation](https://www.khronos.org/registry/OpenCL/sdk/1.2/docs/man/xhtml/clEnqueueNativeKernel.html)
+pub unsafe fn enqueue_native_kernel<En, Ewl>(
+            command_queue: &CommandQueue,
+            user_func: extern "C" fn(*mut c_void),
+            args: &mut [u8],
+            mem_objects: &[(&Mem, usize)],
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+        ) -> OclCoreResult<()>
+        where En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    let mut mem_list = Vec::with_capacity(mem_objects.len());
+    let mut args_mem_loc = Vec::with_capacity(mem_objects.len());
+
+    for &(mem, offset) in mem_objects {
+        if offset + mem::size_of::<cl_mem>() > args.len() {
+            return Err(ApiWrapperError::EnqueueNativeKernelMemLocOutOfRange {
+                offset, len: args.len() }.into());
+        }
+        let loc = args.as_mut_ptr().add(offset);
+        ptr::write_unaligned(loc as *mut cl_mem, mem.as_ptr());
+        mem_list.push(mem.as_ptr());
+        args_mem_loc.push(loc as *const c_void);
+    }
+
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let (args_ptr, args_len) = if args.is_empty() {
+        (ptr::null_mut(), 0)
+    } else {
+        (args.as_mut_ptr() as *mut c_void, args.len())
+    };
+
+    let (mem_list_ptr, args_mem_loc_ptr) = if mem_list.is_empty() {
+        (ptr::null(), ptr::null())
+    } else {
+        (mem_list.as_ptr(), args_mem_loc.as_ptr())
+    };
+
+    let errcode = ffi::clEnqueueNativeKernel(
+        command_queue.as_ptr(),
+        Some(user_func),
+        args_ptr,
+        args_len,
+        mem_list.len() as cl_uint,
+        mem_list_ptr,
+        args_mem_loc_ptr,
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr,
+    );
+    eval_errcode(errcode, (), "clEnqueueNativeKernel", None::<String>)
 }
 
 /// Enqueues a marker command which waits for either a list of events to
@@ -3637,11 +4658,25 @@ pub fn default_platform_idx() -> usize {
 }
 
 /// Returns the default or first platform.
+///
+/// If the `OCL_DEFAULT_PLATFORM_NAME` environment variable is set, the first
+/// platform whose name or vendor contains its value (ignoring case) is
+/// returned. Otherwise the platform at the index given by
+/// `OCL_DEFAULT_PLATFORM_IDX` (default: 0) is returned.
 pub fn default_platform() -> OclCoreResult<PlatformId> {
     let platform_list = r#try!(get_platform_ids());
 
     if platform_list.is_empty() {
         Err(ApiWrapperError::DefaultPlatformNoPlatforms.into())
+    } else if let Some(pattern) = env_var_lowercase("OCL_DEFAULT_PLATFORM_NAME") {
+        for &platform in &platform_list {
+            let name = get_platform_info(platform, PlatformInfo::Name)?.to_string();
+            let vendor = get_platform_info(platform, PlatformInfo::Vendor)?.to_string();
+            if name.to_lowercase().contains(&pattern) || vendor.to_lowercase().contains(&pattern) {
+                return Ok(platform);
+            }
+        }
+        Err(ApiWrapperError::DefaultPlatformEnvVarNoMatch(pattern).into())
     } else {
         let default_platform_idx = default_platform_idx();
         if default_platform_idx > platform_list.len() - 1 {
@@ -3672,6 +4707,53 @@ pub fn default_device_type() -> OclCoreResult<DeviceType> {
     }
 }
 
+/// Returns the default device on `platform`.
+///
+/// Only devices of the type given by `OCL_DEFAULT_DEVICE_TYPE` (default:
+/// all) are considered. If the `OCL_DEFAULT_DEVICE_NAME` environment
+/// variable is set, the first device whose name contains its value (ignoring
+/// case) is returned. Otherwise the device at the index given by
+/// `OCL_DEFAULT_DEVICE_IDX` (default: 0) is returned.
+pub fn default_device<P: ClPlatformIdPtr>(platform: P) -> OclCoreResult<DeviceId> {
+    let device_list = get_device_ids(platform, None, None)?;
+
+    if device_list.is_empty() {
+        return Err(ApiWrapperError::DefaultDeviceNoDevices.into());
+    }
+
+    if let Some(pattern) = env_var_lowercase("OCL_DEFAULT_DEVICE_NAME") {
+        for &device in &device_list {
+            let name = get_device_info(device, DeviceInfo::Name)?.to_string();
+            if name.to_lowercase().contains(&pattern) {
+                return Ok(device);
+            }
+        }
+        return Err(ApiWrapperError::DefaultDeviceEnvVarNoMatch(pattern).into());
+    }
+
+    let default_device_idx = default_device_idx();
+    match device_list.get(default_device_idx) {
+        Some(&device) => Ok(device),
+        None => Err(ApiWrapperError::DefaultDeviceEnvVarBadIdx {
+            default_device_idx, max_idx: device_list.len() - 1 }.into()),
+    }
+}
+
+/// Returns the default device index as specified by the
+/// `OCL_DEFAULT_DEVICE_IDX` environment variable or else zero.
+pub fn default_device_idx() -> usize {
+    match env::var("OCL_DEFAULT_DEVICE_IDX") {
+        Ok(s) => s.parse::<usize>().unwrap_or(0),
+        Err(_) => 0,
+    }
+}
+
+/// Returns the trimmed, lowercase value of the environment variable `key`,
+/// if it is set and not empty.
+fn env_var_lowercase(key: &str) -> Option<String> {
+    env::var(key).ok().map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty())
+}
+
 /// Returns the name of a kernel.
 pub fn get_kernel_name(kernel: &Kernel) -> OclCoreResult<String> {
     let result = get_kernel_info(kernel, KernelInfo::FunctionName)?;
@@ -3724,6 +4806,23 @@ pub fn event_status<'e, E: ClEventPtrRef<'e>>(event: &'e E) -> OclCoreResult<Com
         'clGetEventInfo' status output."))
 }
 
+/// Returns the raw execution status of an event: a `CommandExecutionStatus`
+/// value, or a negative error code if its command terminated abnormally.
+pub fn event_status_code<'e, E: ClEventPtrRef<'e>>(event: &'e E) -> OclCoreResult<cl_int> {
+    let mut status_int: cl_int = 0;
+
+    let errcode = unsafe {
+        ffi::clGetEventInfo(
+            *event.as_ptr_ref(),
+            ffi::CL_EVENT_COMMAND_EXECUTION_STATUS,
+            mem::size_of::<cl_int>(),
+            &mut status_int as *mut _ as *mut c_void,
+            ptr::null_mut(),
+        )
+    };
+    eval_errcode(errcode, status_int, "clGetEventInfo", None::<String>)
+}
+
 /// Returns true if an event is complete, false if not complete.
 pub fn event_is_complete<'e, E: ClEventPtrRef<'e>>(event: &'e E) -> OclCoreResult<bool> {
     let mut status_int: cl_int = 0;
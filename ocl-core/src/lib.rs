@@ -128,7 +128,7 @@ pub use self::types::abs::{ClWaitListPtr, ClNullEventPtr, ClEventPtrRef, ClPlatf
 
 pub use self::types::structs::{self, OpenclVersion, ContextProperties, ImageFormatParseError,
     ImageFormatParseResult, ImageFormat, ImageDescriptor, BufferRegion, ContextPropertyValue,
-    ArgVal};
+    ArgVal, QueueProperties, DevicePartition, NumericVersion, NameVersion};
 
 pub use self::types::enums::{EmptyInfoResultError, PlatformInfoResult, DeviceInfoResult,
     ContextInfoResult, GlContextInfoResult, CommandQueueInfoResult, MemInfoResult, ImageInfoResult,
@@ -144,11 +144,13 @@ pub use self::functions::{get_platform_ids, get_platform_info, get_device_ids, g
     set_mem_object_destructor_callback, create_sampler, retain_sampler, release_sampler,
     get_sampler_info, create_program_with_source, create_program_with_binary,
     create_program_with_built_in_kernels, retain_program, release_program, build_program,
+    build_program_notify,
     compile_program, link_program, create_build_program, get_program_info, get_program_build_info,
     create_kernel, create_kernels_in_program, retain_kernel, release_kernel, set_kernel_arg,
-    get_kernel_info, get_kernel_arg_info, get_kernel_work_group_info, wait_for_events,
-    get_event_info, create_user_event, retain_event, release_event, set_user_event_status,
-    set_event_callback, get_event_profiling_info, flush, finish, enqueue_read_buffer,
+    set_kernel_args, get_kernel_info, get_kernel_arg_info, get_kernel_work_group_info,
+    wait_for_events, get_event_info, create_user_event, retain_event, release_event,
+    set_user_event_status, set_user_event_error, set_event_callback, get_event_profiling_info, flush, finish,
+    enqueue_read_buffer,
     enqueue_read_buffer_rect, enqueue_write_buffer, enqueue_write_buffer_rect, enqueue_copy_buffer,
     enqueue_copy_buffer_rect,
     enqueue_read_image, enqueue_write_image, enqueue_fill_image, enqueue_copy_image,
@@ -156,9 +158,12 @@ pub use self::functions::{get_platform_ids, get_platform_info, get_device_ids, g
     enqueue_map_image, enqueue_unmap_mem_object, enqueue_migrate_mem_objects, enqueue_kernel,
     enqueue_task, enqueue_native_kernel, enqueue_marker_with_wait_list,
     enqueue_barrier_with_wait_list, get_extension_function_address_for_platform, wait_for_event,
-    event_status, default_platform_idx, program_build_err, verify_context, default_platform,
-    default_device_type, device_versions, event_is_complete, _dummy_event_callback,
-    _complete_user_event, get_context_platform, get_device_info_raw};
+    event_status, event_status_code, default_platform_idx, program_build_err, verify_context,
+    default_platform, default_device_type, default_device, default_device_idx, device_versions,
+    event_is_complete, _dummy_event_callback,
+    _complete_user_event, get_context_platform, get_device_info_raw, ProgramBuildError,
+    guard_callback, take_callback_panic, CallbackPanicError, ProfilingUnavailableError,
+    KernelArgInfoUnavailableError, VersionLowError, VersionKind};
 
 #[cfg(not(feature="opencl_vendor_mesa"))]
 pub use self::functions::{
@@ -167,6 +172,11 @@ pub use self::functions::{
     create_from_gl_texture_2d, create_from_gl_texture_3d, enqueue_fill_buffer,
     enqueue_acquire_gl_objects, enqueue_release_gl_objects};
 
+#[cfg(all(windows, feature = "d3d11_sharing"))]
+pub use self::functions::{
+    create_from_d3d11_buffer, create_from_d3d11_texture_2d, create_from_d3d11_texture_3d,
+    enqueue_acquire_d3d11_objects, enqueue_release_d3d11_objects};
+
 pub use crate::traits::{OclPrm, OclNum, OclScl};
 
 #[cfg(feature = "ocl-core-vector")]
@@ -188,7 +198,16 @@ pub use self::vector::{
 pub use crate::traits::OclVec;
 
 #[cfg(feature = "opencl_version_2_1")]
-pub use self::functions::{create_program_with_il};
+pub use self::functions::{create_program_with_il, get_device_and_host_timer, get_host_timer};
+
+#[cfg(feature = "opencl_version_2_0")]
+pub use self::functions::{set_kernel_exec_info, create_command_queue_with_properties,
+    create_command_queue_with_properties_list, svm_alloc, svm_free, set_kernel_arg_svm_pointer,
+    enqueue_svm_map, enqueue_svm_unmap, enqueue_svm_memcpy, enqueue_svm_mem_fill,
+    enqueue_svm_free};
+
+#[cfg(feature = "opencl_version_2_2")]
+pub use self::functions::{set_program_specialization_constant};
 
 
 
@@ -207,6 +226,8 @@ pub type EventCallbackFn = extern "C" fn (ffi::cl_event, i32, *mut ffi::c_void);
 pub type CreateContextCallbackFn = extern "C" fn (*const ffi::c_char, *const ffi::c_void,
     ffi::size_t, *mut ffi::c_void);
 pub type BuildProgramCallbackFn = extern "C" fn (*mut ffi::c_void, *mut ffi::c_void);
+pub type SvmFreeCallbackFn = extern "C" fn (ffi::cl_command_queue, ffi::cl_uint,
+    *const *const ffi::c_void, *mut ffi::c_void);
 pub type UserDataPtr = *mut ffi::c_void;
 
 //=============================================================================
@@ -415,6 +436,40 @@ pub const EXEC_KERNEL: DeviceExecCapabilities = DeviceExecCapabilities::KERNEL;
 pub const EXEC_NATIVE_KERNEL: DeviceExecCapabilities = DeviceExecCapabilities::NATIVE_KERNEL;
 
 
+bitflags! {
+    /// cl_device_svm_capabilities - bitfield
+    pub struct DeviceSvmCapabilities: u64 {
+        const COARSE_GRAIN_BUFFER = 1;
+        const FINE_GRAIN_BUFFER = 1 << 1;
+        const FINE_GRAIN_SYSTEM = 1 << 2;
+        const ATOMICS = 1 << 3;
+    }
+}
+
+
+bitflags! {
+    /// cl_device_atomic_capabilities - bitfield
+    pub struct DeviceAtomicCapabilities: u64 {
+        const ORDER_RELAXED = 1;
+        const ORDER_ACQ_REL = 1 << 1;
+        const ORDER_SEQ_CST = 1 << 2;
+        const SCOPE_WORK_ITEM = 1 << 3;
+        const SCOPE_WORK_GROUP = 1 << 4;
+        const SCOPE_DEVICE = 1 << 5;
+        const SCOPE_ALL_DEVICES = 1 << 6;
+    }
+}
+
+
+bitflags! {
+    /// cl_device_device_enqueue_capabilities - bitfield
+    pub struct DeviceEnqueueCapabilities: u64 {
+        const QUEUE_SUPPORTED = 1;
+        const QUEUE_REPLACEABLE_DEFAULT = 1 << 1;
+    }
+}
+
+
 bitflags! {
     /// cl_command_queue_properties - bitfield
     pub struct CommandQueueProperties: u64 {
@@ -479,6 +534,9 @@ bitflags! {
         const HOST_WRITE_ONLY = 1 << 7;
         const HOST_READ_ONLY = 1 << 8;
         const HOST_NO_ACCESS = 1 << 9;
+        // Used by `svm_alloc` only:
+        const SVM_FINE_GRAIN_BUFFER = 1 << 10;
+        const SVM_ATOMICS = 1 << 11;
     }
 }
 
@@ -493,6 +551,8 @@ impl MemFlags {
     #[inline] pub fn host_write_only(self) -> MemFlags { self | MemFlags::HOST_WRITE_ONLY }
     #[inline] pub fn host_read_only(self) -> MemFlags { self | MemFlags::HOST_READ_ONLY }
     #[inline] pub fn host_no_access(self) -> MemFlags { self | MemFlags::HOST_NO_ACCESS }
+    #[inline] pub fn svm_fine_grain_buffer(self) -> MemFlags { self | MemFlags::SVM_FINE_GRAIN_BUFFER }
+    #[inline] pub fn svm_atomics(self) -> MemFlags { self | MemFlags::SVM_ATOMICS }
 }
 
 impl Default for MemFlags {
@@ -655,6 +715,10 @@ enum_from_primitive! {
         CL_INVALID_DEVICE_QUEUE                         = -70,
         CL_INVALID_GL_SHAREGROUP_REFERENCE_KHR          = -1000,
         CL_PLATFORM_NOT_FOUND_KHR                       = -1001,
+        CL_INVALID_D3D11_DEVICE_KHR                     = -1006,
+        CL_INVALID_D3D11_RESOURCE_KHR                   = -1007,
+        CL_D3D11_RESOURCE_ALREADY_ACQUIRED_KHR          = -1008,
+        CL_D3D11_RESOURCE_NOT_ACQUIRED_KHR              = -1009,
         CL_NV_INVALID_MEM_ACCESS                        = -9999,
     }
 }
@@ -890,6 +954,42 @@ enum_from_primitive! {
         PrintfBufferSize = ffi::CL_DEVICE_PRINTF_BUFFER_SIZE as isize,
         ImagePitchAlignment = ffi::CL_DEVICE_IMAGE_PITCH_ALIGNMENT as isize,
         ImageBaseAddressAlignment = ffi::CL_DEVICE_IMAGE_BASE_ADDRESS_ALIGNMENT as isize,
+        // OpenCL 2.0+:
+        MaxReadWriteImageArgs = ffi::CL_DEVICE_MAX_READ_WRITE_IMAGE_ARGS as isize,
+        MaxGlobalVariableSize = ffi::CL_DEVICE_MAX_GLOBAL_VARIABLE_SIZE as isize,
+        QueueOnDeviceProperties = ffi::CL_DEVICE_QUEUE_ON_DEVICE_PROPERTIES as isize,
+        QueueOnDevicePreferredSize = ffi::CL_DEVICE_QUEUE_ON_DEVICE_PREFERRED_SIZE as isize,
+        QueueOnDeviceMaxSize = ffi::CL_DEVICE_QUEUE_ON_DEVICE_MAX_SIZE as isize,
+        MaxOnDeviceQueues = ffi::CL_DEVICE_MAX_ON_DEVICE_QUEUES as isize,
+        MaxOnDeviceEvents = ffi::CL_DEVICE_MAX_ON_DEVICE_EVENTS as isize,
+        SvmCapabilities = ffi::CL_DEVICE_SVM_CAPABILITIES as isize,
+        GlobalVariablePreferredTotalSize = ffi::CL_DEVICE_GLOBAL_VARIABLE_PREFERRED_TOTAL_SIZE as isize,
+        MaxPipeArgs = ffi::CL_DEVICE_MAX_PIPE_ARGS as isize,
+        PipeMaxActiveReservations = ffi::CL_DEVICE_PIPE_MAX_ACTIVE_RESERVATIONS as isize,
+        PipeMaxPacketSize = ffi::CL_DEVICE_PIPE_MAX_PACKET_SIZE as isize,
+        PreferredPlatformAtomicAlignment = ffi::CL_DEVICE_PREFERRED_PLATFORM_ATOMIC_ALIGNMENT as isize,
+        PreferredGlobalAtomicAlignment = ffi::CL_DEVICE_PREFERRED_GLOBAL_ATOMIC_ALIGNMENT as isize,
+        PreferredLocalAtomicAlignment = ffi::CL_DEVICE_PREFERRED_LOCAL_ATOMIC_ALIGNMENT as isize,
+        // OpenCL 2.1+:
+        IlVersion = ffi::CL_DEVICE_IL_VERSION as isize,
+        MaxNumSubGroups = ffi::CL_DEVICE_MAX_NUM_SUB_GROUPS as isize,
+        SubGroupIndependentForwardProgress = ffi::CL_DEVICE_SUB_GROUP_INDEPENDENT_FORWARD_PROGRESS as isize,
+        // OpenCL 3.0+:
+        NumericVersion = ffi::CL_DEVICE_NUMERIC_VERSION as isize,
+        ExtensionsWithVersion = ffi::CL_DEVICE_EXTENSIONS_WITH_VERSION as isize,
+        IlsWithVersion = ffi::CL_DEVICE_ILS_WITH_VERSION as isize,
+        BuiltInKernelsWithVersion = ffi::CL_DEVICE_BUILT_IN_KERNELS_WITH_VERSION as isize,
+        AtomicMemoryCapabilities = ffi::CL_DEVICE_ATOMIC_MEMORY_CAPABILITIES as isize,
+        AtomicFenceCapabilities = ffi::CL_DEVICE_ATOMIC_FENCE_CAPABILITIES as isize,
+        NonUniformWorkGroupSupport = ffi::CL_DEVICE_NON_UNIFORM_WORK_GROUP_SUPPORT as isize,
+        OpenclCAllVersions = ffi::CL_DEVICE_OPENCL_C_ALL_VERSIONS as isize,
+        PreferredWorkGroupSizeMultiple = ffi::CL_DEVICE_PREFERRED_WORK_GROUP_SIZE_MULTIPLE as isize,
+        WorkGroupCollectiveFunctionsSupport = ffi::CL_DEVICE_WORK_GROUP_COLLECTIVE_FUNCTIONS_SUPPORT as isize,
+        GenericAddressSpaceSupport = ffi::CL_DEVICE_GENERIC_ADDRESS_SPACE_SUPPORT as isize,
+        OpenclCFeatures = ffi::CL_DEVICE_OPENCL_C_FEATURES as isize,
+        DeviceEnqueueCapabilities = ffi::CL_DEVICE_DEVICE_ENQUEUE_CAPABILITIES as isize,
+        PipeSupport = ffi::CL_DEVICE_PIPE_SUPPORT as isize,
+        LatestConformanceVersionPassed = ffi::CL_DEVICE_LATEST_CONFORMANCE_VERSION_PASSED as isize,
     }
 }
 
@@ -1013,6 +1113,30 @@ enum_from_primitive! {
 }
 
 
+enum_from_primitive! {
+    /// cl_queue_priority_khr (`cl_khr_priority_hints`)
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum QueuePriority {
+        High = ffi::CL_QUEUE_PRIORITY_HIGH_KHR as isize,
+        Medium = ffi::CL_QUEUE_PRIORITY_MED_KHR as isize,
+        Low = ffi::CL_QUEUE_PRIORITY_LOW_KHR as isize,
+    }
+}
+
+
+enum_from_primitive! {
+    /// cl_queue_throttle_khr (`cl_khr_throttle_hints`)
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum QueueThrottle {
+        High = ffi::CL_QUEUE_THROTTLE_HIGH_KHR as isize,
+        Medium = ffi::CL_QUEUE_THROTTLE_MED_KHR as isize,
+        Low = ffi::CL_QUEUE_THROTTLE_LOW_KHR as isize,
+    }
+}
+
+
 enum_from_primitive! {
     /// cl_channel_type
     #[repr(C)]
@@ -1158,6 +1282,7 @@ enum_from_primitive! {
         BuildOptions = ffi::CL_PROGRAM_BUILD_OPTIONS as isize,
         BuildLog = ffi::CL_PROGRAM_BUILD_LOG as isize,
         BinaryType = ffi::CL_PROGRAM_BINARY_TYPE as isize,
+        BuildGlobalVariableTotalSize = ffi::CL_PROGRAM_BUILD_GLOBAL_VARIABLE_TOTAL_SIZE as isize,
     }
 }
 
@@ -1249,6 +1374,17 @@ enum_from_primitive! {
 }
 
 
+enum_from_primitive! {
+    /// cl_kernel_exec_info
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum KernelExecInfo {
+        SvmPtrs = ffi::CL_KERNEL_EXEC_INFO_SVM_PTRS as isize,
+        SvmFineGrainSystem = ffi::CL_KERNEL_EXEC_INFO_SVM_FINE_GRAIN_SYSTEM as isize,
+    }
+}
+
+
 enum_from_primitive! {
     /// cl_event_info
     #[repr(C)]
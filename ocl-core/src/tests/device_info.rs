@@ -0,0 +1,58 @@
+//! Parsing of OpenCL 3.0 device info results and partition properties.
+
+use std::mem;
+use crate::ffi::{cl_name_version, CL_NAME_VERSION_MAX_NAME_SIZE};
+use crate::{DeviceInfo, DeviceInfoResult, NumericVersion, OpenclVersion, DevicePartition,
+    DeviceAffinityDomain};
+
+#[test]
+fn numeric_version_round_trip() {
+    let version = NumericVersion::new(3, 0, 11);
+    assert_eq!(NumericVersion::from_raw(version.to_raw()), version);
+    assert_eq!(version.to_raw(), (3 << 22) | 11);
+    assert_eq!(version.to_opencl_version(), OpenclVersion::new(3, 0));
+    assert_eq!(version.to_string(), "3.0.11");
+}
+
+#[test]
+fn extensions_with_version_from_bytes() {
+    let mut name = [0; CL_NAME_VERSION_MAX_NAME_SIZE];
+    for (dst, &src) in name.iter_mut().zip(b"cl_khr_fp64".iter()) {
+        *dst = src as _;
+    }
+    let raw = cl_name_version { version: NumericVersion::new(1, 0, 0).to_raw(), name };
+
+    let mut bytes = Vec::with_capacity(mem::size_of::<cl_name_version>() * 2);
+    for _ in 0..2 {
+        let chunk: [u8; mem::size_of::<cl_name_version>()] = unsafe { mem::transmute(raw) };
+        bytes.extend_from_slice(&chunk);
+    }
+
+    match DeviceInfoResult::from_bytes(DeviceInfo::ExtensionsWithVersion, bytes).unwrap() {
+        DeviceInfoResult::ExtensionsWithVersion(list) => {
+            assert_eq!(list.len(), 2);
+            assert_eq!(list[0].name, "cl_khr_fp64");
+            assert_eq!(list[0].version, NumericVersion::new(1, 0, 0));
+        },
+        r => panic!("Unexpected result: {:?}", r),
+    }
+
+    match DeviceInfoResult::from_bytes(DeviceInfo::ExtensionsWithVersion, Vec::new()).unwrap() {
+        DeviceInfoResult::ExtensionsWithVersion(list) => assert!(list.is_empty()),
+        r => panic!("Unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn device_partition_round_trip() {
+    let partitions = [
+        DevicePartition::Equally(4),
+        DevicePartition::ByCounts(vec![2, 6]),
+        DevicePartition::ByAffinityDomain(DeviceAffinityDomain::NUMA),
+    ];
+    for partition in &partitions {
+        assert_eq!(DevicePartition::from_raw(&partition.to_raw()).as_ref(), Some(partition));
+    }
+    assert_eq!(DevicePartition::from_raw(&[]), None);
+    assert_eq!(DevicePartition::from_raw(&[0]), None);
+}
@@ -2,3 +2,26 @@
 //!
 //! Check that device.info(DeviceInfo::OpenclCVersion == "OpenCL C 1.2") or greater
 //! Perhaps add something to parse the OpenCL C version into a number.
+
+use crate::{OpenclVersion, VersionLowError, VersionKind};
+
+#[test]
+fn opencl_version_from_str() {
+    assert_eq!("2.1".parse::<OpenclVersion>().unwrap(), OpenclVersion::new(2, 1));
+    assert_eq!("OpenCL 1.2 CUDA 11.4.1".parse::<OpenclVersion>().unwrap(),
+        OpenclVersion::new(1, 2));
+    assert!("CUDA".parse::<OpenclVersion>().is_err());
+}
+
+#[test]
+fn version_low_error() {
+    let err = VersionLowError::new(VersionKind::Device, OpenclVersion::new(1, 2),
+        OpenclVersion::new(2, 0), "'clSetKernelExecInfo'");
+    assert_eq!(err.detected(), OpenclVersion::new(1, 2));
+    assert_eq!(err.required(), OpenclVersion::new(2, 0));
+    assert_eq!(err.to_string(), "'clSetKernelExecInfo' requires OpenCL 2.0 but the device \
+        only supports OpenCL 1.2. Use a device and driver supporting OpenCL 2.0 or later.");
+
+    let err = crate::Error::from(err);
+    assert_eq!(err.version_low().map(|e| e.kind()), Some(VersionKind::Device));
+}
@@ -0,0 +1,62 @@
+use std::ffi::CString;
+use crate::{KernelArgInfo, KernelArgInfoResult, KernelArgAddressQualifier,
+    KernelArgAccessQualifier};
+
+static SRC: &'static str = r#"
+    __kernel void add(__global const float* restrict src, __global float* dst, float addend) {
+        dst[get_global_id(0)] = src[get_global_id(0)] + addend;
+    }
+"#;
+
+#[test]
+fn kernel_arg_info() {
+    for (_, device, context) in super::get_available_contexts() {
+        let device_versions = crate::device_versions(&[device]).unwrap();
+        let src_cstring = CString::new(SRC).unwrap();
+
+        // Built with `-cl-kernel-arg-info`:
+        let program = crate::create_program_with_source(&context, &[src_cstring.clone()])
+            .unwrap();
+        crate::build_program(&program, Some(&[device]),
+            &CString::new("-cl-kernel-arg-info").unwrap(), None, None).unwrap();
+        let kernel = crate::create_kernel(&program, "add").unwrap();
+        let arg_info = |idx, request| {
+            crate::get_kernel_arg_info(&kernel, idx, request, Some(&device_versions))
+        };
+
+        match arg_info(0, KernelArgInfo::AddressQualifier).unwrap() {
+            KernelArgInfoResult::AddressQualifier(KernelArgAddressQualifier::Global) => (),
+            r => panic!("Unexpected address qualifier: {}", r),
+        }
+        match arg_info(2, KernelArgInfo::AccessQualifier).unwrap() {
+            KernelArgInfoResult::AccessQualifier(KernelArgAccessQualifier::None) => (),
+            r => panic!("Unexpected access qualifier: {}", r),
+        }
+        match arg_info(0, KernelArgInfo::TypeQualifier).unwrap() {
+            KernelArgInfoResult::TypeQualifier(q) => {
+                assert!(q.contains(crate::KERNEL_ARG_TYPE_CONST))
+            },
+            r => panic!("Unexpected type qualifier: {}", r),
+        }
+        match arg_info(1, KernelArgInfo::TypeName).unwrap() {
+            KernelArgInfoResult::TypeName(name) => assert_eq!(name, "float*"),
+            r => panic!("Unexpected type name: {}", r),
+        }
+        match arg_info(2, KernelArgInfo::Name).unwrap() {
+            KernelArgInfoResult::Name(name) => assert_eq!(name, "addend"),
+            r => panic!("Unexpected name: {}", r),
+        }
+
+        // Built without it, platforms may or may not make names available:
+        let program = crate::create_program_with_source(&context, &[src_cstring]).unwrap();
+        crate::build_program(&program, Some(&[device]), &CString::new("").unwrap(), None, None)
+            .unwrap();
+        let kernel = crate::create_kernel(&program, "add").unwrap();
+        match crate::get_kernel_arg_info(&kernel, 2, KernelArgInfo::Name,
+                Some(&device_versions)) {
+            Ok(KernelArgInfoResult::Name(name)) => assert_eq!(name, "addend"),
+            Ok(r) => panic!("Unexpected name: {}", r),
+            Err(err) => assert!(err.is_kernel_arg_info_unavailable(), "{}", err),
+        }
+    }
+}
@@ -19,6 +19,9 @@ pub mod buffer_copy;
 pub mod buffer_fill;
 pub mod vector_types;
 pub mod compile_program;
+pub mod device_info;
+pub mod device_version_check;
+pub mod kernel_arg_info;
 use self::rand::Rng;
 use crate::error::{Result as OclCoreResult};
 use crate::{OclScl, PlatformId, DeviceId, Context};
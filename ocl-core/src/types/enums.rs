@@ -10,10 +10,12 @@
 #![allow(dead_code)]
 
 use std::fmt;
+use std::mem;
+use std::ptr;
 use failure::Fail;
 use num_traits::FromPrimitive;
 use crate::util;
-use crate::ffi::{cl_image_format, cl_context_properties, c_void};
+use crate::ffi::{cl_image_format, cl_context_properties, cl_version, cl_name_version, c_void};
 
 use crate::{CommandQueueProperties, PlatformId, PlatformInfo, DeviceId, DeviceInfo, ContextInfo,
     GlContextInfo, Context, CommandQueue, CommandQueueInfo, CommandType, CommandExecutionStatus,
@@ -23,7 +25,8 @@ use crate::{CommandQueueProperties, PlatformId, PlatformInfo, DeviceId, DeviceIn
     KernelArgTypeQualifier, ImageInfo, ImageFormat, EventInfo, ProfilingInfo, DeviceType,
     DeviceFpConfig, DeviceMemCacheType, DeviceLocalMemType, DeviceExecCapabilities,
     DevicePartitionProperty, DeviceAffinityDomain, OpenclVersion, ContextProperties,
-    ImageFormatParseResult, Status};
+    ImageFormatParseResult, Status, DeviceSvmCapabilities, DeviceAtomicCapabilities,
+    DeviceEnqueueCapabilities, NumericVersion, NameVersion};
 
 use crate::error::{Result as OclCoreResult, Error as OclCoreError};
 
@@ -52,8 +55,6 @@ pub enum EmptyInfoResultError {
     ProgramBuild,
     #[fail(display = "Kernel info unavailable")]
     Kernel,
-    #[fail(display = "Kernel argument info unavailable")]
-    KernelArg,
     #[fail(display = "Kernel work-group info unavailable")]
     KernelWorkGroup,
     #[fail(display = "Event info unavailable")]
@@ -308,6 +309,39 @@ pub enum DeviceInfoResult {
     PrintfBufferSize(usize),         // usize
     ImagePitchAlignment(u32),      // cl_uint
     ImageBaseAddressAlignment(u32),// cl_uint
+    MaxReadWriteImageArgs(u32),    // cl_uint
+    MaxGlobalVariableSize(usize),    // usize
+    QueueOnDeviceProperties(CommandQueueProperties),  // cl_command_queue_properties    FLAGS u64
+    QueueOnDevicePreferredSize(u32),    // cl_uint
+    QueueOnDeviceMaxSize(u32),     // cl_uint
+    MaxOnDeviceQueues(u32),        // cl_uint
+    MaxOnDeviceEvents(u32),        // cl_uint
+    SvmCapabilities(DeviceSvmCapabilities),   // cl_device_svm_capabilities     FLAGS u64
+    GlobalVariablePreferredTotalSize(usize),    // usize
+    MaxPipeArgs(u32),              // cl_uint
+    PipeMaxActiveReservations(u32),// cl_uint
+    PipeMaxPacketSize(u32),        // cl_uint
+    PreferredPlatformAtomicAlignment(u32),  // cl_uint
+    PreferredGlobalAtomicAlignment(u32),    // cl_uint
+    PreferredLocalAtomicAlignment(u32),     // cl_uint
+    IlVersion(String),                // String
+    MaxNumSubGroups(u32),          // cl_uint
+    SubGroupIndependentForwardProgress(bool),   // cl_bool
+    NumericVersion(NumericVersion),   // cl_version
+    ExtensionsWithVersion(Vec<NameVersion>),    // cl_name_version[]
+    IlsWithVersion(Vec<NameVersion>),           // cl_name_version[]
+    BuiltInKernelsWithVersion(Vec<NameVersion>),    // cl_name_version[]
+    AtomicMemoryCapabilities(DeviceAtomicCapabilities), // cl_device_atomic_capabilities  FLAGS u64
+    AtomicFenceCapabilities(DeviceAtomicCapabilities),  // cl_device_atomic_capabilities  FLAGS u64
+    NonUniformWorkGroupSupport(bool),   // cl_bool
+    OpenclCAllVersions(Vec<NameVersion>),       // cl_name_version[]
+    PreferredWorkGroupSizeMultiple(usize),  // usize
+    WorkGroupCollectiveFunctionsSupport(bool),  // cl_bool
+    GenericAddressSpaceSupport(bool),   // cl_bool
+    OpenclCFeatures(Vec<NameVersion>),  // cl_name_version[]
+    DeviceEnqueueCapabilities(DeviceEnqueueCapabilities),   // FLAGS u64
+    PipeSupport(bool),              // cl_bool
+    LatestConformanceVersionPassed(String),     // String
 }
 
 impl DeviceInfoResult {
@@ -350,7 +384,12 @@ impl DeviceInfoResult {
     /// Returns a new `DeviceInfoResult` for all variants except `MaxWorkItemSizes`.
     pub fn from_bytes(request: DeviceInfo, result: Vec<u8>)
             -> OclCoreResult<DeviceInfoResult> {
-        if result.is_empty() {
+        // Lists of name/version pairs may legitimately be empty:
+        let may_be_empty = matches!(request,
+            DeviceInfo::ExtensionsWithVersion | DeviceInfo::IlsWithVersion
+                | DeviceInfo::BuiltInKernelsWithVersion | DeviceInfo::OpenclCAllVersions
+                | DeviceInfo::OpenclCFeatures);
+        if result.is_empty() && !may_be_empty {
             return Err(OclCoreError::from(
                 EmptyInfoResultError::Device));
         }
@@ -704,6 +743,137 @@ impl DeviceInfoResult {
                 let r = unsafe { util::bytes_into::<u32>(result)? };
                 DeviceInfoResult::ImageBaseAddressAlignment(r)
             },
+            DeviceInfo::MaxReadWriteImageArgs => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::MaxReadWriteImageArgs(r)
+            },
+            DeviceInfo::MaxGlobalVariableSize => {
+                let r = unsafe { util::bytes_into::<usize>(result)? };
+                DeviceInfoResult::MaxGlobalVariableSize(r)
+            },
+            DeviceInfo::QueueOnDeviceProperties => {
+                let r = unsafe { util::bytes_into::<CommandQueueProperties>(result)? };
+                DeviceInfoResult::QueueOnDeviceProperties(r)
+            },
+            DeviceInfo::QueueOnDevicePreferredSize => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::QueueOnDevicePreferredSize(r)
+            },
+            DeviceInfo::QueueOnDeviceMaxSize => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::QueueOnDeviceMaxSize(r)
+            },
+            DeviceInfo::MaxOnDeviceQueues => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::MaxOnDeviceQueues(r)
+            },
+            DeviceInfo::MaxOnDeviceEvents => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::MaxOnDeviceEvents(r)
+            },
+            DeviceInfo::SvmCapabilities => {
+                let r = unsafe { util::bytes_into::<DeviceSvmCapabilities>(result)? };
+                DeviceInfoResult::SvmCapabilities(r)
+            },
+            DeviceInfo::GlobalVariablePreferredTotalSize => {
+                let r = unsafe { util::bytes_into::<usize>(result)? };
+                DeviceInfoResult::GlobalVariablePreferredTotalSize(r)
+            },
+            DeviceInfo::MaxPipeArgs => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::MaxPipeArgs(r)
+            },
+            DeviceInfo::PipeMaxActiveReservations => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::PipeMaxActiveReservations(r)
+            },
+            DeviceInfo::PipeMaxPacketSize => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::PipeMaxPacketSize(r)
+            },
+            DeviceInfo::PreferredPlatformAtomicAlignment => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::PreferredPlatformAtomicAlignment(r)
+            },
+            DeviceInfo::PreferredGlobalAtomicAlignment => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::PreferredGlobalAtomicAlignment(r)
+            },
+            DeviceInfo::PreferredLocalAtomicAlignment => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::PreferredLocalAtomicAlignment(r)
+            },
+            DeviceInfo::IlVersion => {
+                match util::bytes_into_string(result) {
+                    Ok(s) => DeviceInfoResult::IlVersion(s),
+                    Err(err) => return Err(err.into()),
+                }
+            },
+            DeviceInfo::MaxNumSubGroups => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::MaxNumSubGroups(r)
+            },
+            DeviceInfo::SubGroupIndependentForwardProgress => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::SubGroupIndependentForwardProgress(r != 0)
+            },
+            DeviceInfo::NumericVersion => {
+                let r = unsafe { util::bytes_into::<cl_version>(result)? };
+                DeviceInfoResult::NumericVersion(NumericVersion::from_raw(r))
+            },
+            DeviceInfo::ExtensionsWithVersion => {
+                DeviceInfoResult::ExtensionsWithVersion(name_versions(result)?)
+            },
+            DeviceInfo::IlsWithVersion => {
+                DeviceInfoResult::IlsWithVersion(name_versions(result)?)
+            },
+            DeviceInfo::BuiltInKernelsWithVersion => {
+                DeviceInfoResult::BuiltInKernelsWithVersion(name_versions(result)?)
+            },
+            DeviceInfo::AtomicMemoryCapabilities => {
+                let r = unsafe { util::bytes_into::<DeviceAtomicCapabilities>(result)? };
+                DeviceInfoResult::AtomicMemoryCapabilities(r)
+            },
+            DeviceInfo::AtomicFenceCapabilities => {
+                let r = unsafe { util::bytes_into::<DeviceAtomicCapabilities>(result)? };
+                DeviceInfoResult::AtomicFenceCapabilities(r)
+            },
+            DeviceInfo::NonUniformWorkGroupSupport => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::NonUniformWorkGroupSupport(r != 0)
+            },
+            DeviceInfo::OpenclCAllVersions => {
+                DeviceInfoResult::OpenclCAllVersions(name_versions(result)?)
+            },
+            DeviceInfo::PreferredWorkGroupSizeMultiple => {
+                let r = unsafe { util::bytes_into::<usize>(result)? };
+                DeviceInfoResult::PreferredWorkGroupSizeMultiple(r)
+            },
+            DeviceInfo::WorkGroupCollectiveFunctionsSupport => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::WorkGroupCollectiveFunctionsSupport(r != 0)
+            },
+            DeviceInfo::GenericAddressSpaceSupport => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::GenericAddressSpaceSupport(r != 0)
+            },
+            DeviceInfo::OpenclCFeatures => {
+                DeviceInfoResult::OpenclCFeatures(name_versions(result)?)
+            },
+            DeviceInfo::DeviceEnqueueCapabilities => {
+                let r = unsafe { util::bytes_into::<DeviceEnqueueCapabilities>(result)? };
+                DeviceInfoResult::DeviceEnqueueCapabilities(r)
+            },
+            DeviceInfo::PipeSupport => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::PipeSupport(r != 0)
+            },
+            DeviceInfo::LatestConformanceVersionPassed => {
+                match util::bytes_into_string(result) {
+                    Ok(s) => DeviceInfoResult::LatestConformanceVersionPassed(s),
+                    Err(err) => return Err(err.into()),
+                }
+            },
             // _ => DeviceInfoResult::TemporaryPlaceholderVariant(result),
         };
 
@@ -807,12 +977,67 @@ impl fmt::Display for DeviceInfoResult {
             DeviceInfoResult::PrintfBufferSize(ref s) => write!(f, "{}", s),
             DeviceInfoResult::ImagePitchAlignment(ref s) => write!(f, "{}", s),
             DeviceInfoResult::ImageBaseAddressAlignment(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::MaxReadWriteImageArgs(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::MaxGlobalVariableSize(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::QueueOnDeviceProperties(ref s) => write!(f, "{:?}", s),
+            DeviceInfoResult::QueueOnDevicePreferredSize(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::QueueOnDeviceMaxSize(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::MaxOnDeviceQueues(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::MaxOnDeviceEvents(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::SvmCapabilities(ref s) => write!(f, "{:?}", s),
+            DeviceInfoResult::GlobalVariablePreferredTotalSize(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::MaxPipeArgs(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::PipeMaxActiveReservations(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::PipeMaxPacketSize(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::PreferredPlatformAtomicAlignment(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::PreferredGlobalAtomicAlignment(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::PreferredLocalAtomicAlignment(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::IlVersion(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::MaxNumSubGroups(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::SubGroupIndependentForwardProgress(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::NumericVersion(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::ExtensionsWithVersion(ref s) => fmt_name_versions(f, s),
+            DeviceInfoResult::IlsWithVersion(ref s) => fmt_name_versions(f, s),
+            DeviceInfoResult::BuiltInKernelsWithVersion(ref s) => fmt_name_versions(f, s),
+            DeviceInfoResult::AtomicMemoryCapabilities(ref s) => write!(f, "{:?}", s),
+            DeviceInfoResult::AtomicFenceCapabilities(ref s) => write!(f, "{:?}", s),
+            DeviceInfoResult::NonUniformWorkGroupSupport(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::OpenclCAllVersions(ref s) => fmt_name_versions(f, s),
+            DeviceInfoResult::PreferredWorkGroupSizeMultiple(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::WorkGroupCollectiveFunctionsSupport(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::GenericAddressSpaceSupport(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::OpenclCFeatures(ref s) => fmt_name_versions(f, s),
+            DeviceInfoResult::DeviceEnqueueCapabilities(ref s) => write!(f, "{:?}", s),
+            DeviceInfoResult::PipeSupport(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::LatestConformanceVersionPassed(ref s) => write!(f, "{}", s),
         }
     }
 }
 
 
 
+/// Converts a raw array of `cl_name_version` structs.
+fn name_versions(bytes: Vec<u8>) -> OclCoreResult<Vec<NameVersion>> {
+    let size = mem::size_of::<cl_name_version>();
+    if !bytes.len().is_multiple_of(size) {
+        return Err(util::UtilError::BytesIntoVec { src: bytes.len(), dst: size }.into());
+    }
+    Ok(bytes.chunks(size).map(|chunk| {
+        let raw = unsafe { ptr::read_unaligned(chunk.as_ptr() as *const cl_name_version) };
+        NameVersion::from_raw(&raw)
+    }).collect())
+}
+
+/// Formats a list of name/version pairs, space-separated.
+fn fmt_name_versions(f: &mut fmt::Formatter, list: &[NameVersion]) -> fmt::Result {
+    for (i, nv) in list.iter().enumerate() {
+        if i > 0 { f.write_str(" ")?; }
+        write!(f, "{}", nv)?;
+    }
+    Ok(())
+}
+
+
 /// A context info result.
 ///
 /// [INCOMPLETE][FIXME]: Figure out what to do with the properties variant.
@@ -1434,6 +1659,7 @@ pub enum ProgramBuildInfoResult {
     BuildOptions(String),
     BuildLog(String),
     BinaryType(ProgramBinaryType),
+    BuildGlobalVariableTotalSize(usize),
 }
 
 impl ProgramBuildInfoResult {
@@ -1467,6 +1693,10 @@ impl ProgramBuildInfoResult {
                 let r = unsafe { util::bytes_into::<ProgramBinaryType>(result)? };
                 ProgramBuildInfoResult::BinaryType(r)
             },
+            ProgramBuildInfo::BuildGlobalVariableTotalSize => {
+                let r = unsafe { util::bytes_into::<usize>(result)? };
+                ProgramBuildInfoResult::BuildGlobalVariableTotalSize(r)
+            },
         };
         Ok(ir)
     }
@@ -1485,6 +1715,7 @@ impl fmt::Display for ProgramBuildInfoResult {
             ProgramBuildInfoResult::BuildOptions(ref s) => write!(f, "{}", s),
             ProgramBuildInfoResult::BuildLog(ref s) => write!(f, "{}", s),
             ProgramBuildInfoResult::BinaryType(ref s) => write!(f, "{:?}", s),
+            ProgramBuildInfoResult::BuildGlobalVariableTotalSize(ref s) => write!(f, "{}", s),
         }
     }
 }
@@ -1583,10 +1814,6 @@ pub enum KernelArgInfoResult {
 
 impl KernelArgInfoResult {
     pub fn from_bytes(request: KernelArgInfo, result: Vec<u8>) -> OclCoreResult<KernelArgInfoResult> {
-        if result.is_empty() {
-            return Err(OclCoreError::from(
-                EmptyInfoResultError::KernelArg));
-        }
         let ir = match request {
             KernelArgInfo::AddressQualifier => {
                 let r = unsafe { util::bytes_into::<u32>(result)? };
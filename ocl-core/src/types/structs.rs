@@ -8,9 +8,10 @@ use std::collections::HashMap;
 use num_traits::FromPrimitive;
 use crate::error::{Error as OclCoreError, Result as OclCoreResult};
 use crate::ffi::{self,cl_mem, cl_sampler, cl_buffer_region, cl_context_properties, cl_platform_id,
-    c_void, size_t};
+    cl_queue_properties, cl_device_partition_property, c_void, size_t};
 use crate::{Mem, MemObjectType, ImageChannelOrder, ImageChannelDataType, ContextProperty,
-    PlatformId, OclPrm, Sampler};
+    PlatformId, OclPrm, Sampler, CommandQueueProperties, QueuePriority, QueueThrottle,
+    DevicePartitionProperty, DeviceAffinityDomain};
 
 
 // Until everything can be implemented:
@@ -173,6 +174,16 @@ impl OpenclVersion {
         (self.ver[0], self.ver[1])
     }
 
+    /// Returns the major version number.
+    pub fn major(&self) -> u16 {
+        self.ver[0]
+    }
+
+    /// Returns the minor version number.
+    pub fn minor(&self) -> u16 {
+        self.ver[1]
+    }
+
     /// Parse the string `ver` and return a dual-integer result as
     /// `OpenclVersion`.
     ///
@@ -228,6 +239,22 @@ impl From<[u16; 2]> for OpenclVersion {
     }
 }
 
+impl std::str::FromStr for OpenclVersion {
+    type Err = OclCoreError;
+
+    /// Parses either a bare version (e.g. '2.1') or a version string as
+    /// returned by a platform or device (e.g. 'OpenCL 2.1 CUDA 11.4.1').
+    fn from_str(s: &str) -> OclCoreResult<OpenclVersion> {
+        let mut nums = s.trim().splitn(2, '.');
+        if let (Some(major), Some(minor)) = (nums.next(), nums.next()) {
+            if let (Ok(major), Ok(minor)) = (major.parse(), minor.parse()) {
+                return Ok(OpenclVersion::new(major, minor));
+            }
+        }
+        OpenclVersion::from_info_str(s)
+    }
+}
+
 impl std::fmt::Display for OpenclVersion {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}.{}", self.ver[0], self.ver[1])
@@ -304,18 +331,69 @@ pub enum ContextPropertyValue {
     D3d11DeviceKhr(isize),
 }
 
+impl ContextPropertyValue {
+    /// Returns the property this value is for.
+    pub fn property(&self) -> ContextProperty {
+        match *self {
+            ContextPropertyValue::Platform(_) => ContextProperty::Platform,
+            ContextPropertyValue::InteropUserSync(_) => ContextProperty::InteropUserSync,
+            ContextPropertyValue::D3d10DeviceKhr(_) => ContextProperty::D3d10DeviceKhr,
+            ContextPropertyValue::GlContextKhr(_) => ContextProperty::GlContextKhr,
+            ContextPropertyValue::EglDisplayKhr(_) => ContextProperty::EglDisplayKhr,
+            ContextPropertyValue::GlxDisplayKhr(_) => ContextProperty::GlxDisplayKhr,
+            ContextPropertyValue::CglSharegroupKhr(_) => ContextProperty::CglSharegroupKhr,
+            ContextPropertyValue::WglHdcKhr(_) => ContextProperty::WglHdcKhr,
+            ContextPropertyValue::AdapterD3d9Khr(_) => ContextProperty::AdapterD3d9Khr,
+            ContextPropertyValue::AdapterD3d9exKhr(_) => ContextProperty::AdapterD3d9exKhr,
+            ContextPropertyValue::AdapterDxvaKhr(_) => ContextProperty::AdapterDxvaKhr,
+            ContextPropertyValue::D3d11DeviceKhr(_) => ContextProperty::D3d11DeviceKhr,
+        }
+    }
+
+    /// Returns the value as it appears in a raw properties list.
+    pub fn to_raw(&self) -> cl_context_properties {
+        match *self {
+            ContextPropertyValue::Platform(ref platform) => platform.as_ptr() as isize,
+            ContextPropertyValue::InteropUserSync(sync) => sync as isize,
+            ContextPropertyValue::D3d10DeviceKhr(ptr) => ptr as isize,
+            ContextPropertyValue::GlContextKhr(ptr) => ptr as isize,
+            ContextPropertyValue::EglDisplayKhr(ptr) => ptr as isize,
+            ContextPropertyValue::GlxDisplayKhr(ptr) => ptr as isize,
+            ContextPropertyValue::CglSharegroupKhr(ptr) => ptr as isize,
+            ContextPropertyValue::WglHdcKhr(ptr) => ptr as isize,
+            ContextPropertyValue::AdapterD3d9Khr(val) => val,
+            ContextPropertyValue::AdapterD3d9exKhr(val) => val,
+            ContextPropertyValue::AdapterDxvaKhr(val) => val,
+            ContextPropertyValue::D3d11DeviceKhr(val) => val,
+        }
+    }
+
+    /// Returns true if this value specifies an OpenGL context or share group
+    /// or a display associated with one.
+    fn is_gl(&self) -> bool {
+        matches!(*self,
+            ContextPropertyValue::GlContextKhr(_) | ContextPropertyValue::EglDisplayKhr(_) |
+                ContextPropertyValue::GlxDisplayKhr(_) | ContextPropertyValue::CglSharegroupKhr(_) |
+                ContextPropertyValue::WglHdcKhr(_))
+    }
+}
+
 unsafe impl Send for ContextPropertyValue {}
 unsafe impl Sync for ContextPropertyValue {}
 
 
-/// Context properties list.
+/// Context properties list, as passed to `clCreateContext`.
 ///
-/// [MINIMALLY TESTED]
+/// Holds at most one value for each standard property (setting a property
+/// again replaces its value) along with any number of vendor-specific
+/// properties. Combinations are checked by `::validate` before a context is
+/// created.
 ///
-/// TODO: Check for duplicate property assignments.
+/// [MINIMALLY TESTED]
 #[derive(Clone, Debug)]
 pub struct ContextProperties {
     props: HashMap<ContextProperty, ContextPropertyValue>,
+    vendor: Vec<(cl_context_properties, cl_context_properties)>,
     contains_gl_context_or_sharegroup: bool,
 }
 
@@ -324,6 +402,7 @@ impl ContextProperties {
     pub fn new() -> ContextProperties {
         ContextProperties {
             props: HashMap::with_capacity(16),
+            vendor: Vec::new(),
             contains_gl_context_or_sharegroup: false,
         }
     }
@@ -372,6 +451,13 @@ impl ContextProperties {
         self
     }
 
+    /// Specifies the `ID3D11Device *` to use for Direct3D 11 sharing
+    /// (builder-style).
+    pub fn d3d11_device(mut self, d3d11_device: *mut c_void) -> ContextProperties {
+        self.set_d3d11_device(d3d11_device);
+        self
+    }
+
     /// Pushes a `ContextPropertyValue` onto this list of properties
     /// (builder-style).
     pub fn property_value(mut self, prop: ContextPropertyValue) -> ContextProperties {
@@ -379,6 +465,14 @@ impl ContextProperties {
         self
     }
 
+    /// Specifies a vendor-specific property, replacing any previous value
+    /// for `name` (builder-style).
+    pub fn vendor(mut self, name: cl_context_properties, value: cl_context_properties)
+            -> ContextProperties {
+        self.set_vendor(name, value);
+        self
+    }
+
     /// Specifies a platform.
     pub fn set_platform<P: Into<PlatformId>>(&mut self, platform: P) {
         self.props.insert(ContextProperty::Platform, ContextPropertyValue::Platform(platform.into()));
@@ -421,42 +515,28 @@ impl ContextProperties {
         self.contains_gl_context_or_sharegroup = true;
     }
 
+    /// Specifies the `ID3D11Device *` to use for Direct3D 11 sharing
+    /// (requires the `cl_khr_d3d11_sharing` extension).
+    pub fn set_d3d11_device(&mut self, d3d11_device: *mut c_void) {
+        self.props.insert(ContextProperty::D3d11DeviceKhr,
+            ContextPropertyValue::D3d11DeviceKhr(d3d11_device as isize));
+    }
+
     /// Pushes a `ContextPropertyValue` onto this list of properties.
     pub fn set_property_value(&mut self, prop: ContextPropertyValue) {
-        match prop {
-            ContextPropertyValue::Platform(val) => {
-                self.props.insert(ContextProperty::Platform, ContextPropertyValue::Platform(val));
-            },
-            ContextPropertyValue::InteropUserSync(val) => {
-                self.props.insert(ContextProperty::InteropUserSync,
-                    ContextPropertyValue::InteropUserSync(val));
-            },
-            ContextPropertyValue::GlContextKhr(val) => {
-                self.props.insert(ContextProperty::GlContextKhr,
-                    ContextPropertyValue::GlContextKhr(val));
-                self.contains_gl_context_or_sharegroup = true;
-            },
-            ContextPropertyValue::GlxDisplayKhr(val) => {
-                self.props.insert(ContextProperty::GlxDisplayKhr,
-                    ContextPropertyValue::GlxDisplayKhr(val));
-                self.contains_gl_context_or_sharegroup = true;
-            },
-            ContextPropertyValue::WglHdcKhr(val) => {
-                self.props.insert(ContextProperty::WglHdcKhr,
-                    ContextPropertyValue::WglHdcKhr(val));
-                self.contains_gl_context_or_sharegroup = true;
-            },
-            ContextPropertyValue::CglSharegroupKhr(val) => {
-                self.props.insert(ContextProperty::CglSharegroupKhr,
-                    ContextPropertyValue::CglSharegroupKhr(val));
-                self.contains_gl_context_or_sharegroup = true;
-            },
-            ContextPropertyValue::EglDisplayKhr(val) => {
-                self.props.insert(ContextProperty::EglDisplayKhr,
-                    ContextPropertyValue::EglDisplayKhr(val));
-                self.contains_gl_context_or_sharegroup = true;
-            },
-            _ => panic!("'{:?}' is not yet a supported variant.", prop),
+        if prop.is_gl() { self.contains_gl_context_or_sharegroup = true; }
+        self.props.insert(prop.property(), prop);
+    }
+
+    /// Specifies a vendor-specific property, replacing any previous value
+    /// for `name`.
+    ///
+    /// Standard properties must be set with their own setters; `::validate`
+    /// returns an error if `name` is one of them.
+    pub fn set_vendor(&mut self, name: cl_context_properties, value: cl_context_properties) {
+        match self.vendor.iter_mut().find(|(n, _)| *n == name) {
+            Some(prop) => prop.1 = value,
+            None => self.vendor.push((name, value)),
         }
     }
 
@@ -474,6 +554,59 @@ impl ContextProperties {
         }
     }
 
+    /// Returns the value of a property, if specified.
+    pub fn get(&self, property: ContextProperty) -> Option<&ContextPropertyValue> {
+        self.props.get(&property)
+    }
+
+    /// Returns whether the user is responsible for synchronization between
+    /// OpenCL and other APIs (`false` unless specified).
+    pub fn get_interop_user_sync(&self) -> bool {
+        match self.props.get(&ContextProperty::InteropUserSync) {
+            Some(&ContextPropertyValue::InteropUserSync(sync)) => sync,
+            _ => false,
+        }
+    }
+
+    /// Returns the vendor-specific properties as (name, value) pairs.
+    pub fn get_vendor(&self) -> &[(cl_context_properties, cl_context_properties)] {
+        &self.vendor
+    }
+
+    /// Checks that the properties form a valid combination.
+    ///
+    /// Returns an error if an OpenGL display (EGL, GLX, or WGL) is specified
+    /// without an OpenGL context or more than one is specified, or if a
+    /// vendor-specific property is zero or duplicates a standard property.
+    pub fn validate(&self) -> OclCoreResult<()> {
+        let displays: Vec<ContextProperty> = [ContextProperty::EglDisplayKhr,
+            ContextProperty::GlxDisplayKhr, ContextProperty::WglHdcKhr].iter().cloned()
+            .filter(|prop| self.props.contains_key(prop)).collect();
+
+        if displays.len() > 1 {
+            return Err(format!("ContextProperties::validate: Only one OpenGL display may be \
+                specified (found: {:?}).", displays).into());
+        }
+        if let Some(display) = displays.first() {
+            if !self.props.contains_key(&ContextProperty::GlContextKhr) {
+                return Err(format!("ContextProperties::validate: '{:?}' requires an OpenGL \
+                    context ('GlContextKhr') to also be specified.", display).into());
+            }
+        }
+
+        for &(name, _) in &self.vendor {
+            if name == 0 {
+                return Err("ContextProperties::validate: Vendor property names must be \
+                    non-zero.".into());
+            }
+            if let Some(prop) = ContextProperty::from_isize(name) {
+                return Err(format!("ContextProperties::validate: Vendor property {:#x} is the \
+                    standard property '{:?}'. Use its setter instead.", name, prop).into());
+            }
+        }
+        Ok(())
+    }
+
     /// Returns true if this set of context properties specifies any OpenGL
     /// context or sharegroup to associate with.
     pub fn contains_gl_context_or_sharegroup(&self) -> bool {
@@ -489,49 +622,23 @@ impl ContextProperties {
     //
     // [FIXME]: Change return type to `Vec<(cl_context_properties, isize)>`
     pub fn to_raw(&self) -> Vec<isize> {
-        let mut props_raw = Vec::with_capacity(32);
-
-        // For each property ...
-        for (key, val) in self.props.iter() {
-            // convert both the kind of property (a u32 originally) and
-            // the value (variable type/size) to an isize:
-            match *val {
-                ContextPropertyValue::Platform(ref platform_id_core) => {
-                    props_raw.push(*key as isize);
-                    props_raw.push(platform_id_core.as_ptr() as isize);
-                },
-                ContextPropertyValue::InteropUserSync(sync) => {
-                    props_raw.push(*key as isize);
-                    props_raw.push(sync as isize);
-                },
-                ContextPropertyValue::GlContextKhr(sync) => {
-                    props_raw.push(*key as isize);
-                    props_raw.push(sync as isize);
-                },
-                ContextPropertyValue::GlxDisplayKhr(sync) => {
-                    props_raw.push(*key as isize);
-                    props_raw.push(sync as isize);
-                },
-                ContextPropertyValue::WglHdcKhr(sync) => {
-                    props_raw.push(*key as isize);
-                    props_raw.push(sync as isize);
-                },
-                ContextPropertyValue::CglSharegroupKhr(sync) => {
-                    props_raw.push(*key as isize);
-                    props_raw.push(sync as isize);
-                },
-                ContextPropertyValue::EglDisplayKhr(sync) => {
-                    props_raw.push(*key as isize);
-                    props_raw.push(sync as isize);
-                },
-                _ => panic!("'{:?}' is not yet a supported variant.", key),
-            };
+        let mut props_raw = Vec::with_capacity((self.props.len() + self.vendor.len()) * 2 + 1);
+
+        // Platform first, then in a consistent order:
+        let mut keys: Vec<&ContextProperty> = self.props.keys().collect();
+        keys.sort_by_key(|&&key| (key != ContextProperty::Platform, key as isize));
+
+        for key in keys {
+            props_raw.push(*key as isize);
+            props_raw.push(self.props[key].to_raw());
+        }
+        for &(name, value) in &self.vendor {
+            props_raw.push(name);
+            props_raw.push(value);
         }
 
         // Add a terminating 0:
         props_raw.push(0);
-
-        props_raw.shrink_to_fit();
         props_raw
     }
 
@@ -580,6 +687,7 @@ impl ContextProperties {
         let pair_count = raw_context_properties.len() / 2;
         let mut context_props = ContextProperties {
             props: HashMap::with_capacity(pair_count),
+            vendor: Vec::new(),
             contains_gl_context_or_sharegroup: false,
         };
 
@@ -588,9 +696,14 @@ impl ContextProperties {
             let key_raw = *raw_context_properties.get_unchecked(idz);
             let val_raw = *raw_context_properties.get_unchecked(idz + 1);
 
-            let key = ContextProperty::from_isize(key_raw).ok_or_else(|| OclCoreError::from(
-                format!("ContextProperties::from_raw: Unable to convert '{}' using \
-                    'ContextProperty::from_isize'.", key_raw)))?;
+            // Unknown properties are assumed to be vendor-specific:
+            let key = match ContextProperty::from_isize(key_raw) {
+                Some(key) => key,
+                None => {
+                    context_props.vendor.push((key_raw, val_raw));
+                    continue;
+                },
+            };
 
             match key {
                     ContextProperty::Platform => {
@@ -679,6 +792,314 @@ impl From<ContextProperties> for Vec<isize> {
 
 
 
+/// A version number as reported by OpenCL 3.0 queries (`cl_version`),
+/// e.g. `CL_DEVICE_NUMERIC_VERSION`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NumericVersion {
+    major: u16,
+    minor: u16,
+    patch: u16,
+}
+
+impl NumericVersion {
+    /// Returns a new version.
+    pub fn new(major: u16, minor: u16, patch: u16) -> NumericVersion {
+        NumericVersion { major, minor, patch }
+    }
+
+    /// Decodes a packed `cl_version`.
+    pub fn from_raw(raw: ffi::cl_version) -> NumericVersion {
+        let minor_patch_bits = ffi::CL_VERSION_MINOR_BITS + ffi::CL_VERSION_PATCH_BITS;
+        NumericVersion {
+            major: (raw >> minor_patch_bits) as u16,
+            minor: ((raw >> ffi::CL_VERSION_PATCH_BITS) & ((1 << ffi::CL_VERSION_MINOR_BITS) - 1))
+                as u16,
+            patch: (raw & ((1 << ffi::CL_VERSION_PATCH_BITS) - 1)) as u16,
+        }
+    }
+
+    /// Returns the packed `cl_version`.
+    pub fn to_raw(&self) -> ffi::cl_version {
+        ((self.major as u32) << (ffi::CL_VERSION_MINOR_BITS + ffi::CL_VERSION_PATCH_BITS))
+            | ((self.minor as u32) << ffi::CL_VERSION_PATCH_BITS)
+            | self.patch as u32
+    }
+
+    pub fn major(&self) -> u16 {
+        self.major
+    }
+
+    pub fn minor(&self) -> u16 {
+        self.minor
+    }
+
+    pub fn patch(&self) -> u16 {
+        self.patch
+    }
+
+    /// Returns the major and minor version, dropping the patch number.
+    pub fn to_opencl_version(&self) -> OpenclVersion {
+        OpenclVersion::new(self.major, self.minor)
+    }
+}
+
+impl std::fmt::Display for NumericVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+
+/// A name paired with a version (`cl_name_version`), as returned for
+/// extensions, ILs, built-in kernels, and OpenCL C versions and features.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NameVersion {
+    pub name: String,
+    pub version: NumericVersion,
+}
+
+impl NameVersion {
+    /// Converts a raw `cl_name_version`.
+    pub fn from_raw(raw: &ffi::cl_name_version) -> NameVersion {
+        let len = raw.name.iter().position(|&c| c == 0).unwrap_or(raw.name.len());
+        let bytes: Vec<u8> = raw.name[..len].iter().map(|&c| c as u8).collect();
+        NameVersion {
+            name: String::from_utf8_lossy(&bytes).into_owned(),
+            version: NumericVersion::from_raw(raw.version),
+        }
+    }
+}
+
+impl std::fmt::Display for NameVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {}", self.name, self.version)
+    }
+}
+
+
+/// A way of partitioning a device into sub-devices, as passed to
+/// `clCreateSubDevices`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DevicePartition {
+    /// As many sub-devices as possible, each with the given number of
+    /// compute units.
+    Equally(u32),
+    /// One sub-device for each count, with that many compute units.
+    ByCounts(Vec<u32>),
+    /// Sub-devices sharing the given level of the cache hierarchy or NUMA
+    /// node.
+    ByAffinityDomain(DeviceAffinityDomain),
+}
+
+impl DevicePartition {
+    /// Returns the zero-terminated raw properties list.
+    pub fn to_raw(&self) -> Vec<cl_device_partition_property> {
+        match *self {
+            DevicePartition::Equally(units) => vec![
+                DevicePartitionProperty::Equally as cl_device_partition_property,
+                units as cl_device_partition_property,
+                0,
+            ],
+            DevicePartition::ByCounts(ref counts) => {
+                let mut raw = Vec::with_capacity(counts.len() + 3);
+                raw.push(DevicePartitionProperty::ByCounts as cl_device_partition_property);
+                raw.extend(counts.iter().map(|&c| c as cl_device_partition_property));
+                raw.push(DevicePartitionProperty::ByCountsListEnd as cl_device_partition_property);
+                raw.push(0);
+                raw
+            },
+            DevicePartition::ByAffinityDomain(domain) => vec![
+                DevicePartitionProperty::ByAffinityDomain as cl_device_partition_property,
+                domain.bits() as cl_device_partition_property,
+                0,
+            ],
+        }
+    }
+
+    /// Parses a raw properties list, as returned for
+    /// `CL_DEVICE_PARTITION_TYPE`.
+    ///
+    /// Returns `None` for an empty list (a device which is not a sub-device)
+    /// or an unknown partition type.
+    pub fn from_raw(raw: &[cl_device_partition_property]) -> Option<DevicePartition> {
+        let value = |idx: usize| raw.get(idx).cloned().unwrap_or(0);
+        match raw.first() {
+            Some(&p) if p == DevicePartitionProperty::Equally as cl_device_partition_property => {
+                Some(DevicePartition::Equally(value(1) as u32))
+            },
+            Some(&p) if p == DevicePartitionProperty::ByCounts as cl_device_partition_property => {
+                let end = DevicePartitionProperty::ByCountsListEnd as cl_device_partition_property;
+                Some(DevicePartition::ByCounts(raw[1..].iter()
+                    .take_while(|&&c| c != end && c != 0)
+                    .map(|&c| c as u32)
+                    .collect()))
+            },
+            Some(&p) if p == DevicePartitionProperty::ByAffinityDomain
+                    as cl_device_partition_property => {
+                Some(DevicePartition::ByAffinityDomain(
+                    DeviceAffinityDomain::from_bits_truncate(value(1) as u64)))
+            },
+            _ => None,
+        }
+    }
+}
+
+
+/// Command queue properties list, as passed to
+/// `clCreateCommandQueueWithProperties`.
+///
+/// Holds the standard properties (`CL_QUEUE_PROPERTIES` and
+/// `CL_QUEUE_SIZE`), the `cl_khr_priority_hints` and `cl_khr_throttle_hints`
+/// properties, and any number of vendor-specific properties.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueueProperties {
+    flags: Option<CommandQueueProperties>,
+    size: Option<u32>,
+    priority: Option<QueuePriority>,
+    throttle: Option<QueueThrottle>,
+    vendor: Vec<(cl_queue_properties, cl_queue_properties)>,
+}
+
+impl QueueProperties {
+    /// Returns an empty new list of queue properties.
+    pub fn new() -> QueueProperties {
+        QueueProperties::default()
+    }
+
+    /// Specifies the `CL_QUEUE_PROPERTIES` bitfield (builder-style).
+    pub fn flags(mut self, flags: CommandQueueProperties) -> QueueProperties {
+        self.set_flags(Some(flags));
+        self
+    }
+
+    /// Specifies the size of an on-device queue in bytes (builder-style).
+    pub fn size(mut self, size: u32) -> QueueProperties {
+        self.set_size(Some(size));
+        self
+    }
+
+    /// Specifies a priority hint (builder-style).
+    pub fn priority(mut self, priority: QueuePriority) -> QueueProperties {
+        self.set_priority(Some(priority));
+        self
+    }
+
+    /// Specifies a throttle hint (builder-style).
+    pub fn throttle(mut self, throttle: QueueThrottle) -> QueueProperties {
+        self.set_throttle(Some(throttle));
+        self
+    }
+
+    /// Specifies a vendor-specific property, replacing any previous value
+    /// for `name` (builder-style).
+    pub fn vendor(mut self, name: cl_queue_properties, value: cl_queue_properties) -> QueueProperties {
+        self.set_vendor(name, value);
+        self
+    }
+
+    /// Specifies or clears the `CL_QUEUE_PROPERTIES` bitfield.
+    pub fn set_flags(&mut self, flags: Option<CommandQueueProperties>) {
+        self.flags = flags;
+    }
+
+    /// Specifies or clears the size of an on-device queue.
+    pub fn set_size(&mut self, size: Option<u32>) {
+        self.size = size;
+    }
+
+    /// Specifies or clears the priority hint.
+    pub fn set_priority(&mut self, priority: Option<QueuePriority>) {
+        self.priority = priority;
+    }
+
+    /// Specifies or clears the throttle hint.
+    pub fn set_throttle(&mut self, throttle: Option<QueueThrottle>) {
+        self.throttle = throttle;
+    }
+
+    /// Specifies a vendor-specific property, replacing any previous value
+    /// for `name`.
+    pub fn set_vendor(&mut self, name: cl_queue_properties, value: cl_queue_properties) {
+        match self.vendor.iter_mut().find(|(n, _)| *n == name) {
+            Some(prop) => prop.1 = value,
+            None => self.vendor.push((name, value)),
+        }
+    }
+
+    /// Returns the `CL_QUEUE_PROPERTIES` bitfield, if specified.
+    pub fn get_flags(&self) -> Option<CommandQueueProperties> {
+        self.flags
+    }
+
+    /// Returns the queue size, if specified.
+    pub fn get_size(&self) -> Option<u32> {
+        self.size
+    }
+
+    /// Returns the priority hint, if specified.
+    pub fn get_priority(&self) -> Option<QueuePriority> {
+        self.priority
+    }
+
+    /// Returns the throttle hint, if specified.
+    pub fn get_throttle(&self) -> Option<QueueThrottle> {
+        self.throttle
+    }
+
+    /// Returns the vendor-specific properties as (name, value) pairs.
+    pub fn get_vendor(&self) -> &[(cl_queue_properties, cl_queue_properties)] {
+        &self.vendor
+    }
+
+    /// Returns true if these properties can be passed to the OpenCL 1.x
+    /// `clCreateCommandQueue`, which accepts only the bitfield.
+    pub fn is_legacy_compatible(&self) -> bool {
+        self.size.is_none() && self.priority.is_none() && self.throttle.is_none() &&
+            self.vendor.is_empty()
+    }
+
+    /// Returns a zero-terminated list of property names and values.
+    pub fn to_raw(&self) -> Vec<cl_queue_properties> {
+        let mut props = Vec::with_capacity(9 + self.vendor.len() * 2);
+        if let Some(flags) = self.flags {
+            props.push(ffi::CL_QUEUE_PROPERTIES as cl_queue_properties);
+            props.push(flags.bits());
+        }
+        if let Some(size) = self.size {
+            props.push(ffi::CL_QUEUE_SIZE as cl_queue_properties);
+            props.push(size as cl_queue_properties);
+        }
+        if let Some(priority) = self.priority {
+            props.push(ffi::CL_QUEUE_PRIORITY_KHR as cl_queue_properties);
+            props.push(priority as cl_queue_properties);
+        }
+        if let Some(throttle) = self.throttle {
+            props.push(ffi::CL_QUEUE_THROTTLE_KHR as cl_queue_properties);
+            props.push(throttle as cl_queue_properties);
+        }
+        for &(name, value) in self.vendor.iter() {
+            props.push(name);
+            props.push(value);
+        }
+        props.push(0);
+        props
+    }
+}
+
+impl From<CommandQueueProperties> for QueueProperties {
+    fn from(flags: CommandQueueProperties) -> QueueProperties {
+        QueueProperties::new().flags(flags)
+    }
+}
+
+impl From<QueueProperties> for Vec<cl_queue_properties> {
+    fn from(qp: QueueProperties) -> Vec<cl_queue_properties> {
+        qp.to_raw()
+    }
+}
+
+
+
 /// Defines a buffer region for creating a sub-buffer.
 ///
 /// ### Info (from [SDK](https://www.khronos.org/registry/cl/sdk/1.2/docs/man/xhtml/clCreateSubBuffer.html))
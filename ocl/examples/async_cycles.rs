@@ -24,7 +24,6 @@
 
 extern crate chrono;
 extern crate futures;
-extern crate futures_cpupool;
 extern crate fil_ocl as ocl;
 #[macro_use] extern crate colorify;
 
@@ -32,11 +31,15 @@ use std::fmt::Debug;
 use std::thread::{self, JoinHandle};
 use std::sync::mpsc::{self, Receiver};
 use chrono::{Duration, DateTime, Local};
-use futures::{Future};
-use futures_cpupool::{CpuPool, CpuFuture};
+use std::future::Future;
+use std::pin::Pin;
+use futures::{future, FutureExt, TryFutureExt};
+use futures::executor::{block_on, ThreadPool};
+use futures::future::RemoteHandle;
+use futures::task::SpawnExt;
 use ocl::{Platform, Device, Context, Queue, Program, Kernel, Event, Buffer, RwVec};
 use ocl::traits::{IntoRawEventArray};
-use ocl::error::{Error as OclError, Result as OclResult};
+use ocl::error::{Result as OclResult};
 use ocl::flags::{MemFlags, CommandQueueProperties};
 use ocl::prm::Int4;
 use ocl::ffi::{cl_event, c_void};
@@ -100,8 +103,8 @@ pub fn timestamp() -> String {
 }
 
 /// Returns a thread hooked up to the provided receiver which simply waits for
-/// completion of each `CpuFuture` sent until none remain.
-pub fn completion_thread<T, E>(rx: Receiver<Option<CpuFuture<T, E>>>)
+/// completion of each `RemoteHandle` sent until none remain.
+pub fn completion_thread<T, E>(rx: Receiver<Option<RemoteHandle<Result<T, E>>>>)
         -> JoinHandle<()>
         where T: Send + 'static, E: Send + Debug + 'static {
     thread::spawn(move || {
@@ -110,7 +113,7 @@ pub fn completion_thread<T, E>(rx: Receiver<Option<CpuFuture<T, E>>>)
         loop {
             match rx.recv().unwrap() {
                 Some(task) => {
-                    task.wait().unwrap();
+                    block_on(task).unwrap();
                     println!("Task {} complete (t: {}s)", task_i, timestamp());
 
                     task_i += 1;
@@ -187,7 +190,7 @@ pub fn write_init(src_buf: &Buffer<Int4>, common_queue: &Queue,
         write_val: i32, task_iter: i32)
         // -> AndThen<FutureMemMap<Int4>, OclResult<i32>,
         //     impl FnOnce(MemMap<Int4>) -> OclResult<i32>>
-        -> OclResult<Box<dyn Future<Item=i32, Error=OclError> + Send>> {
+        -> OclResult<Pin<Box<dyn Future<Output=OclResult<i32>> + Send>>> {
     extern "C" fn _write_complete(_: cl_event, _: i32, task_iter : *mut c_void) {
         printlnc!(teal_bold: "* Write init complete \t\t(iter: {}, t: {}s)",
             task_iter as usize, timestamp());
@@ -221,7 +224,7 @@ pub fn write_init(src_buf: &Buffer<Int4>, common_queue: &Queue,
     unsafe { write_init_event.as_ref().unwrap().set_callback(_write_complete,
         task_iter as *mut c_void)?; }
 
-    Ok(Box::new(future_write_data.and_then(move |mut data| {
+    Ok(Box::pin(future_write_data.map_ok(move |mut data| {
         printlnc!(teal_bold: "* Write init starting \t\t(iter: {}, t: {}s) ...",
             task_iter, timestamp());
 
@@ -229,7 +232,7 @@ pub fn write_init(src_buf: &Buffer<Int4>, common_queue: &Queue,
             *val = Int4::new(write_val, write_val, write_val, write_val);
         }
 
-        Ok(task_iter)
+        task_iter
     })))
 }
 
@@ -248,7 +251,7 @@ pub fn verify_init(src_buf: &Buffer<Int4>, dst_vec: &RwVec<Int4>, common_queue:
         correct_val: i32, task_iter: i32)
         // -> AndThen<PendingRwGuard<Int4>, OclResult<i32>,
         //     impl FnOnce(RwGuard<Int4>) -> OclResult<i32>>
-        -> OclResult<Box<dyn Future<Item=i32, Error=OclError> + Send>> {
+        -> OclResult<Pin<Box<dyn Future<Output=OclResult<i32>> + Send>>> {
     extern "C" fn _verify_starting(_: cl_event, _: i32, task_iter : *mut c_void) {
         printlnc!(blue_bold: "* Verify init starting \t\t(iter: {}, t: {}s) ...",
             task_iter as usize, timestamp());
@@ -274,7 +277,7 @@ pub fn verify_init(src_buf: &Buffer<Int4>, dst_vec: &RwVec<Int4>, common_queue:
         ?.clone());
 
     // The future which will actually verify the initial value:
-    Ok(Box::new(future_read_data.and_then(move |data| {
+    Ok(Box::pin(future_read_data.map(move |res| res.and_then(|data| {
         let mut val_count = 0;
 
         for (idx, val) in data.iter().enumerate() {
@@ -289,7 +292,7 @@ pub fn verify_init(src_buf: &Buffer<Int4>, dst_vec: &RwVec<Int4>, common_queue:
             task_iter, timestamp());
 
         Ok(val_count)
-    })))
+    }))))
 }
 
 
@@ -367,7 +370,7 @@ pub fn verify_add(dst_buf: &Buffer<Int4>, common_queue: &Queue,
         correct_val: i32, task_iter: i32)
         // -> AndThen<FutureMemMap<Int4>, OclResult<i32>,
         //     impl FnOnce(MemMap<Int4>) -> OclResult<i32>>
-        -> OclResult<Box<dyn Future<Item=i32, Error=OclError> + Send>> {
+        -> OclResult<Pin<Box<dyn Future<Output=OclResult<i32>> + Send>>> {
     extern "C" fn _verify_starting(_: cl_event, _: i32, task_iter : *mut c_void) {
         printlnc!(lime_bold: "* Verify add starting \t\t(iter: {}, t: {}s) ...",
             task_iter as usize, timestamp());
@@ -393,7 +396,7 @@ pub fn verify_add(dst_buf: &Buffer<Int4>, common_queue: &Queue,
             .with_unmap_queue(verify_add_unmap_queue)
     };
 
-    Ok(Box::new(future_read_data.and_then(move |data| {
+    Ok(Box::pin(future_read_data.map(move |res| res.and_then(|data| {
         let mut val_count = 0;
         let cval = Int4::splat(correct_val);
 
@@ -409,7 +412,7 @@ pub fn verify_add(dst_buf: &Buffer<Int4>, common_queue: &Queue,
             task_iter, timestamp());
 
         Ok(val_count)
-    })))
+    }))))
 }
 
 
@@ -487,10 +490,10 @@ pub fn async_cycles() -> OclResult<()> {
     let rw_vec: RwVec<Int4> = RwVec::from(vec![Default::default(); WORK_SIZE]);
 
     // Thread pool for offloaded tasks.
-    let thread_pool = CpuPool::new_num_cpus();
+    let thread_pool = ThreadPool::new()?;
 
     // A channel with room to keep a pre-specified number of tasks in-flight.
-    let (tx, rx) = mpsc::sync_channel::<Option<CpuFuture<_, _>>>(MAX_CONCURRENT_TASK_COUNT - 2);
+    let (tx, rx) = mpsc::sync_channel::<Option<RemoteHandle<_>>>(MAX_CONCURRENT_TASK_COUNT - 2);
 
     // Create a thread to handle the stream of work. If this were graphics,
     // this thread could represent the processing being done after a 'finish'
@@ -556,8 +559,8 @@ pub fn async_cycles() -> OclResult<()> {
         printlnc!(orange: "All commands for iteration {} enqueued    (t: {}s)",
             task_iter, timestamp());
 
-        let join = write_init.join3(verify_init, verify_add);
-        let join_spawned = thread_pool.spawn(join);
+        let join = future::try_join3(write_init, verify_init, verify_add);
+        let join_spawned = thread_pool.spawn_with_handle(join).unwrap();
 
         // This places our already spawned and running task into the queue for
         // later collection by our completion thread. This call will block if
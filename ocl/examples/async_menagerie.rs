@@ -8,7 +8,6 @@
 //!
 
 extern crate futures;
-extern crate futures_cpupool;
 extern crate rand;
 extern crate chrono;
 extern crate fil_ocl as ocl;
@@ -17,9 +16,12 @@ extern crate ocl_extras as extras;
 
 use rand::{Rng, XorShiftRng};
 use rand::distributions::{IndependentSample, Range as RandRange};
-use futures::{stream, Future, Sink, Stream, Join};
-use futures::sync::mpsc::{self, Sender};
-use futures_cpupool::{CpuPool, CpuFuture};
+use futures::{future, FutureExt, TryFutureExt, TryStreamExt};
+use futures::future::{RemoteHandle, TryJoin};
+use futures::stream::FuturesUnordered;
+use futures::channel::mpsc::{self, Sender};
+use futures::executor::{block_on, block_on_stream, ThreadPool};
+use futures::task::SpawnExt;
 use ocl::{Result as OclResult, Platform, Device, Context, Queue, Program, Kernel, OclPrm,
     Event, EventList, FutureMemMap};
 use ocl::flags::{MemFlags, MapFlags, CommandQueueProperties};
@@ -328,32 +330,32 @@ fn create_simple_task(task_id: usize, device: Device, context: &Context,
 }
 
 /// Enqueues a unique simple task as defined above.
-fn enqueue_simple_task(task: &Task, buf_pool: &SubBufferPool<Float4>, thread_pool: &CpuPool,
-        tx: Sender<usize>) -> Join<CpuFuture<usize, OclError>, CpuFuture<Sender<usize>, OclError>>
+fn enqueue_simple_task(task: &Task, buf_pool: &SubBufferPool<Float4>, thread_pool: &ThreadPool,
+        mut tx: Sender<usize>)
+        -> TryJoin<RemoteHandle<OclResult<usize>>, RemoteHandle<OclResult<()>>>
 {
     // Do some extra work:
     let task_id = task.task_id;
 
     // (0) Write a bunch of 50's:
-    let write = task.map(0, &buf_pool).and_then(move |mut data| {
+    let write = task.map(0, &buf_pool).map_ok(move |mut data| {
         for val in data.iter_mut() {
             *val = Float4::new(50., 50., 50., 50.);
         }
 
         printlnc!(green: "Task [{}] (simple): Buffer initialized.", task_id);
 
-        Ok(task_id)
+        task_id
     });
 
-    // let write_spawned = thread_pool.spawn(write).wait().unwrap();
-    let write_spawned = thread_pool.spawn(write);
+    let write_spawned = thread_pool.spawn_with_handle(write).unwrap();
 
     // (1) Run kernel (adds 100 to everything):
     task.kernel(1);
 
     // (2) Read results and verify them:
     let verify = task.map(2, &buf_pool)
-        .and_then(move |data| {
+        .map(move |res| res.and_then(|data| {
             let mut val_count = 0usize;
 
             for val in data.iter() {
@@ -367,14 +369,12 @@ fn enqueue_simple_task(task: &Task, buf_pool: &SubBufferPool<Float4>, thread_poo
             printlnc!(yellow: "Task [{}] (simple): Verify successful: \
                 {} values correct.", task_id, val_count);
 
-            Ok(tx.send(val_count))
-        })
-        .and_then(|send| send.map_err(|e| OclError::from(e)));
+            tx.try_send(val_count).map_err(|e| OclError::from(e.into_send_error()))
+        }));
 
-    let verify_spawned = thread_pool.spawn(verify);
+    let verify_spawned = thread_pool.spawn_with_handle(verify).unwrap();
 
-    write_spawned.join(verify_spawned)
-    // write_spawned.join(verify_spawned).wait().unwrap();
+    future::try_join(write_spawned, verify_spawned)
 }
 
 
@@ -532,20 +532,21 @@ fn create_complex_task(task_id: usize, device: Device, context: &Context,
 }
 
 /// Enqueues a unique complex task as defined above.
-fn enqueue_complex_task(task: &Task, buf_pool: &SubBufferPool<Float4>, thread_pool: &CpuPool,
-        tx: Sender<usize>) -> Join<CpuFuture<usize, OclError>, CpuFuture<Sender<usize>, OclError>>
+fn enqueue_complex_task(task: &Task, buf_pool: &SubBufferPool<Float4>, thread_pool: &ThreadPool,
+        mut tx: Sender<usize>)
+        -> TryJoin<RemoteHandle<OclResult<usize>>, RemoteHandle<OclResult<()>>>
 {
     let task_id = task.task_id;
 
     // (0) Initially write 500s:
-    let write = task.map(0, &buf_pool).and_then(move |mut data| {
+    let write = task.map(0, &buf_pool).map_ok(move |mut data| {
         for val in data.iter_mut() {
             *val = Float4::new(500., 500., 500., 500.);
         }
 
         printlnc!(green_bold: "Task [{}] (complex): Buffer initialized.", task_id);
 
-        Ok(task_id)
+        task_id
     });
 
     // (1) Kernel A -- Add values:
@@ -570,7 +571,7 @@ fn enqueue_complex_task(task: &Task, buf_pool: &SubBufferPool<Float4>, thread_po
     let expected_result = task.expected_result.unwrap();
 
     let verify = task.map(7, &buf_pool)
-        .and_then(move |data| {
+        .map(move |res| res.and_then(|data| {
             let mut val_count = 0usize;
 
             for val in data.iter() {
@@ -584,14 +585,13 @@ fn enqueue_complex_task(task: &Task, buf_pool: &SubBufferPool<Float4>, thread_po
             printlnc!(yellow_bold: "Task [{}] (complex): Verify successful: \
                 {} values correct.", task_id, val_count);
 
-            Ok(tx.send(val_count))
-        })
-        .and_then(|send| send.map_err(|e| OclError::from(e)));
+            tx.try_send(val_count).map_err(|e| OclError::from(e.into_send_error()))
+        }));
 
-    let write_spawned = thread_pool.spawn(write);
-    let verify_spawned = thread_pool.spawn(verify);
+    let write_spawned = thread_pool.spawn_with_handle(write).unwrap();
+    let verify_spawned = thread_pool.spawn_with_handle(verify).unwrap();
 
-    write_spawned.join(verify_spawned)
+    future::try_join(write_spawned, verify_spawned)
 }
 
 
@@ -644,7 +644,7 @@ pub fn async_menagerie() -> OclResult<()> {
     let mut tasks = Vec::with_capacity(256);
 
     // Our thread pool for offloading reading, writing, and other host-side processing.
-    let thread_pool = CpuPool::new_num_cpus();
+    let thread_pool = ThreadPool::new()?;
     let mut correct_val_count = 0usize;
 
     // Channels are used to communicate result counts (this isn't really
@@ -689,15 +689,15 @@ pub fn async_menagerie() -> OclResult<()> {
     let task_count = tasks.len();
     printlnc!(white_bold: "Waiting on {} tasks to complete...", task_count);
 
-    stream::futures_unordered(tasks).for_each(|(task_id, _)| {
+    block_on(tasks.into_iter().collect::<FuturesUnordered<_>>().try_for_each(|(task_id, _)| {
         printlnc!(orange: "Task [{}]: Complete.", task_id);
-        Ok(())
-    }).wait()?;
+        future::ready(Ok(()))
+    }))?;
 
     rx.close();
 
-    for count in rx.wait() {
-        correct_val_count += count.unwrap();
+    for count in block_on_stream(rx) {
+        correct_val_count += count;
     }
 
     let run_duration = chrono::Local::now() - start_time - create_enqueue_duration;
@@ -5,15 +5,16 @@
 //!
 
 extern crate futures;
-extern crate futures_cpupool;
 extern crate chrono;
 extern crate fil_ocl as ocl;
 #[macro_use] extern crate colorify;
 
 use std::cell::Cell;
 use std::collections::VecDeque;
-use futures::{stream, Stream, Future};
-use futures_cpupool::CpuPool;
+use futures::{future, FutureExt, TryFutureExt, TryStreamExt};
+use futures::executor::{block_on, ThreadPool};
+use futures::stream::FuturesUnordered;
+use futures::task::SpawnExt;
 use ocl::{Result as OclResult, Platform, Device, Context, Queue, Program, Buffer, Kernel, Event};
 use ocl::flags::{MemFlags, MapFlags, CommandQueueProperties};
 use ocl::prm::Float4;
@@ -60,7 +61,7 @@ pub fn async_process() -> OclResult<()> {
     let kern_queue = Queue::new(&context, device, queue_flags).or_else(|_|
         Queue::new(&context, device, None))?;
 
-    let thread_pool = CpuPool::new_num_cpus();
+    let thread_pool = ThreadPool::new()?;
     let task_count = 12;
     let redundancy_count = 2000;
     let mut offloads = VecDeque::with_capacity(task_count);
@@ -121,7 +122,7 @@ pub fn async_process() -> OclResult<()> {
         future_write_data.set_unmap_wait_events(&fill_event);
         let write_unmap_event = future_write_data.create_unmap_event()?.clone();
 
-        let write = future_write_data.and_then(move |mut data| {
+        let write = future_write_data.map_ok(move |mut data| {
             for _ in 0..redundancy_count {
                 for val in data.iter_mut() {
                     *val = Float4::new(50., 50., 50., 50.);
@@ -129,10 +130,10 @@ pub fn async_process() -> OclResult<()> {
             }
 
             println!("Mapped write complete (task: {}). ", task_id);
-            Ok(task_id)
+            task_id
         });
 
-        let spawned_write = thread_pool.spawn(write);
+        let spawned_write = thread_pool.spawn_with_handle(write).unwrap();
 
         // (2) KERNEL: Run kernel: Add 100 to everything (total should now be 150):
         let mut kern_event = Event::empty();
@@ -153,7 +154,7 @@ pub fn async_process() -> OclResult<()> {
                 .enq_async()?
         };
 
-        let read = future_read_data.and_then(move |data| {
+        let read = future_read_data.map(move |res| res.and_then(|data| {
                 let mut val_count = 0usize;
 
                 for _ in 0..redundancy_count {
@@ -169,11 +170,11 @@ pub fn async_process() -> OclResult<()> {
                 println!("Mapped read and verify complete (task: {}). ", task_id);
 
                 Ok(val_count)
-            });
+            }));
 
-        let spawned_read = thread_pool.spawn(read);
-        // Presumably this could be either `join` or `and_then`:
-        let offload = spawned_write.join(spawned_read);
+        let spawned_read = thread_pool.spawn_with_handle(read).unwrap();
+        // Presumably this could be either `try_join` or `and_then`:
+        let offload = future::try_join(spawned_write, spawned_read);
 
         offloads.push_back(offload);
     }
@@ -183,11 +184,12 @@ pub fn async_process() -> OclResult<()> {
     let correct_val_count = Cell::new(0usize);
 
     // Finish things up (basically a thread join):
-    stream::futures_unordered(offloads).for_each(|(task_id, val_count)| {
-        correct_val_count.set(correct_val_count.get() + val_count);
-        println!("Task: {} has completed.", task_id);
-        Ok(())
-    }).wait()?;
+    block_on(offloads.into_iter().collect::<FuturesUnordered<_>>()
+        .try_for_each(|(task_id, val_count)| {
+            correct_val_count.set(correct_val_count.get() + val_count);
+            println!("Task: {} has completed.", task_id);
+            future::ready(Ok(()))
+        }))?;
 
     let run_duration = chrono::Local::now() - start_time - create_duration;
     let total_duration = chrono::Local::now() - start_time;
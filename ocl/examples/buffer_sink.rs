@@ -4,7 +4,7 @@ extern crate ocl;
 extern crate ocl_extras;
 
 use std::thread::{JoinHandle, Builder as ThreadBuilder};
-use futures::Future;
+use futures::executor::block_on;
 use ocl::{ProQue, Buffer, MemFlags};
 use ocl::r#async::{BufferSink, WriteGuard};
 
@@ -54,8 +54,9 @@ fn buffer_sink() -> ocl::Result<()> {
             WORK_SIZE)?
     };
     // let source_data = ocl_extras::scrambled_vec((0, 20), ocl_pq.dims().to_len());
+    let len = ocl_pq.dims()?.to_len();
     let source_datas: Vec<_> = (0..THREAD_COUNT).map(|_| {
-        ocl_extras::scrambled_vec((0, 20), ocl_pq.dims().to_len())
+        ocl_extras::scrambled_vec((0, 20), len)
     }).collect();
     let mut threads = Vec::<JoinHandle<()>>::with_capacity(THREAD_COUNT * 2);
 
@@ -65,7 +66,7 @@ fn buffer_sink() -> ocl::Result<()> {
             let mut write_guard = writer_0.wait().unwrap();
             write_guard.copy_from_slice(&[0i32; WORK_SIZE]);
             let buffer_sink: BufferSink<_> = WriteGuard::release(write_guard).into();
-            buffer_sink.flush().enq().unwrap().wait().unwrap();
+            block_on(buffer_sink.flush().enq().unwrap()).unwrap();
         })?);
 
         let source_data = source_datas[i].clone();
@@ -75,7 +76,7 @@ fn buffer_sink() -> ocl::Result<()> {
             let mut write_guard = writer_1.wait().unwrap();
             write_guard.copy_from_slice(&source_data);
             let buffer_sink: BufferSink<_> = WriteGuard::release(write_guard).into();
-            buffer_sink.flush().enq().unwrap().wait().unwrap();
+            block_on(buffer_sink.flush().enq().unwrap()).unwrap();
         })?);
 
         unsafe { kern.enq()?; }
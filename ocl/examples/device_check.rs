@@ -22,12 +22,15 @@ extern crate fil_ocl as ocl;
 #[macro_use] extern crate colorify;
 
 use std::fmt::{Debug};
-use futures::{Future};
+use std::future::Future;
+use std::pin::Pin;
+use futures::{future, FutureExt, TryFutureExt};
+use futures::executor::block_on;
 use rand::{XorShiftRng};
 use rand::distributions::{IndependentSample, Range as RandRange};
 use ocl::{core, Platform, Device, Context, Queue, Program, Buffer, Kernel, OclPrm,
     Event, EventList, MemMap, RwVec};
-use ocl::error::{Error as OclError, Result as OclResult};
+use ocl::error::{Result as OclResult};
 use ocl::flags::{MemFlags, MapFlags, CommandQueueProperties};
 use ocl::traits::{IntoRawEventArray};
 use ocl::prm::{Float4, Int4};
@@ -731,7 +734,7 @@ pub fn vec_write_async(
         fill_event: Option<&Event>,
         write_event: &mut Option<Event>,
         write_val: i32, task_iter: i32)
-        -> Box<dyn Future<Item=i32, Error=OclError> + Send>
+        -> Pin<Box<dyn Future<Output=OclResult<i32>> + Send>>
 {
     extern "C" fn _write_complete(_: cl_event, _: i32, task_iter : *mut c_void) {
         if PRINT { println!("* Write init complete  \t(iter: {})", task_iter as usize); }
@@ -742,14 +745,12 @@ pub fn vec_write_async(
     future_guard.set_lock_wait_events(fill_event);
     let release_event = future_guard.create_release_event(write_release_queue).unwrap().clone();
 
-    let future_write_vec = future_guard.and_then(move |mut data| {
+    let future_write_vec = future_guard.map_ok(move |mut data| {
         if PRINT { println!("* Write init starting  \t(iter: {}) ...", task_iter); }
 
         for val in data.iter_mut() {
             *val = Int4::splat(write_val);
         }
-
-        Ok(())
     });
 
     let mut future_write_buffer = src_buf.cmd().write(rw_vec)
@@ -764,9 +765,9 @@ pub fn vec_write_async(
     unsafe { write_event.as_ref().unwrap().set_callback(_write_complete,
         task_iter as *mut c_void).unwrap(); }
 
-    let future_drop_guard = future_write_buffer.and_then(move |_| Ok(()));
+    let future_drop_guard = future_write_buffer.map_ok(move |_| ());
 
-    Box::new(future_write_vec.join(future_drop_guard).map(move |(_, _)| task_iter))
+    Box::pin(future::try_join(future_write_vec, future_drop_guard).map_ok(move |(_, _)| task_iter))
 }
 
 pub fn kernel_add(
@@ -807,7 +808,7 @@ pub fn kernel_add(
 pub fn map_read_async(dst_buf: &Buffer<Int4>, common_queue: &Queue,
         verify_add_unmap_queue: Queue, wait_event: Option<&Event>,
         verify_add_event: &mut Option<Event>, correct_val: i32,
-        task_iter: i32) -> Box<dyn Future<Item=i32, Error=OclError> + Send>
+        task_iter: i32) -> Pin<Box<dyn Future<Output=OclResult<i32>> + Send>>
 {
     extern "C" fn _verify_starting(_: cl_event, _: i32, task_iter : *mut c_void) {
         printlnc!(lime_bold: "* Verify add starting \t\t(iter: {}) ...",
@@ -827,7 +828,7 @@ pub fn map_read_async(dst_buf: &Buffer<Int4>, common_queue: &Queue,
 
     *verify_add_event = Some(future_read_data.create_unmap_event().unwrap().clone());
 
-    Box::new(future_read_data.and_then(move |mut data| {
+    Box::pin(future_read_data.map(move |res| res.and_then(|mut data| {
         let mut val_count = 0;
 
         for (idx, val) in data.iter().enumerate() {
@@ -845,13 +846,13 @@ pub fn map_read_async(dst_buf: &Buffer<Int4>, common_queue: &Queue,
         data.unmap().queue(&verify_add_unmap_queue).enq()?;
 
         Ok(val_count)
-    }))
+    })))
 }
 
 pub fn vec_read_async(dst_buf: &Buffer<Int4>, rw_vec: &RwVec<Int4>, common_queue: &Queue,
         verify_add_release_queue: &Queue, kernel_event: Option<&Event>,
         verify_add_event: &mut Option<Event>, correct_val: i32, task_iter: i32)
-        -> Box<dyn Future<Item=i32, Error=OclError> + Send>
+        -> Pin<Box<dyn Future<Output=OclResult<i32>> + Send>>
 {
     extern "C" fn _verify_starting(_: cl_event, _: i32, task_iter : *mut c_void) {
         if PRINT { println!("* Verify add starting  \t(iter: {}) ...", task_iter as usize); }
@@ -871,7 +872,7 @@ pub fn vec_read_async(dst_buf: &Buffer<Int4>, rw_vec: &RwVec<Int4>, common_queue
     *verify_add_event = Some(future_read_data.create_release_event(verify_add_release_queue)
         .unwrap().clone());
 
-    Box::new(future_read_data.and_then(move |data| {
+    Box::pin(future_read_data.map(move |res| res.and_then(|data| {
         let mut val_count = 0;
 
         for (idx, val) in data.iter().enumerate() {
@@ -886,7 +887,7 @@ pub fn vec_read_async(dst_buf: &Buffer<Int4>, rw_vec: &RwVec<Int4>, common_queue
         if PRINT { println!("* Verify add complete  \t(iter: {})", task_iter); }
 
         Ok(val_count)
-    }))
+    })))
 }
 
 pub fn check_async(device: Device, context: &Context, rng: &mut XorShiftRng, cfg: Switches<Int4>)
@@ -1020,14 +1021,14 @@ pub fn check_async(device: Device, context: &Context, rng: &mut XorShiftRng, cfg
 
         if PRINT { println!("All commands for iteration {} enqueued", task_iter); }
 
-        let task = write.join(read);
+        let task = future::try_join(write, read);
 
         threads.push(thread::Builder::new()
                 .name(format!("task_iter_[{}]", task_iter).into())
                 .spawn(move ||
         {
             if PRINT { println!("Waiting on task iter [{}]...", task_iter); }
-            match task.wait() {
+            match block_on(task) {
                 Ok(res) => {
                     if PRINT { println!("Task iter [{}] complete with result: {:?}", task_iter, res); }
                     Ok(res)
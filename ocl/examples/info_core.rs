@@ -7,7 +7,7 @@ extern crate fil_ocl as ocl;
 use ocl::core::{self, PlatformInfo, DeviceInfo, ContextInfo,
     CommandQueueInfo, MemInfo, ImageInfo, SamplerInfo, ProgramInfo,
     ProgramBuildInfo, KernelInfo, KernelArgInfo, KernelWorkGroupInfo,
-    EventInfo, ProfilingInfo, Status};
+    EventInfo, ProfilingInfo};
 use ocl::{Platform, Device, Context, Queue, Buffer, Image, Sampler, Program,
     Kernel, Event, EventList, SpatialDims};
 
@@ -25,12 +25,8 @@ macro_rules! to_string {
     ( $ expr : expr ) => {
         match $expr {
             Ok(info) => info.to_string(),
-            Err(err) => {
-                match err.api_status() {
-                    Some(Status::CL_KERNEL_ARG_INFO_NOT_AVAILABLE) => "Not available".into(),
-                    _ => err.to_string(),
-                }
-            },
+            Err(ref err) if err.is_kernel_arg_info_unavailable() => "Not available".into(),
+            Err(err) => err.to_string(),
         }
     };
 }
@@ -47,7 +47,7 @@ fn timed() -> ocl::Result<()> {
     let ocl_pq = ProQue::builder().src(src).dims(WORK_SIZE).build()?;
 
     // Create init and result buffers and vectors:
-    let vec_init = ocl_extras::scrambled_vec(INIT_VAL_RANGE, ocl_pq.dims().to_len());
+    let vec_init = ocl_extras::scrambled_vec(INIT_VAL_RANGE, ocl_pq.dims()?.to_len());
 
     let buffer_init = Buffer::builder()
             .queue(ocl_pq.queue().clone())
@@ -64,7 +64,7 @@ fn timed() -> ocl::Result<()> {
 
     // Create a kernel with arguments matching those in the kernel:
     let kern = ocl_pq.kernel_builder("add")
-        .global_work_size(ocl_pq.dims().clone())
+        .global_work_size(*ocl_pq.dims()?)
         .arg_named("source", Some(&buffer_init))
         .arg(SCALAR)
         .arg(&buffer_result)
@@ -1,8 +1,10 @@
 use ocl::{Device, DeviceType, CommandQueueProperties};
 use ocl::enums::{DeviceMemCacheType, DevicePartitionProperty, DeviceLocalMemType};
-use ocl::flags::{DeviceAffinityDomain, DeviceExecCapabilities, DeviceFpConfig};
+use ocl::flags::{DeviceAffinityDomain, DeviceExecCapabilities, DeviceFpConfig,
+	DeviceSvmCapabilities, DeviceAtomicCapabilities, DeviceEnqueueCapabilities};
 use ocl::error::{Result as OclResult};
-use ocl::core::{DeviceId, PlatformId, OpenclVersion, DeviceInfo, DeviceInfoResult};
+use ocl::core::{DeviceId, PlatformId, OpenclVersion, NumericVersion, NameVersion, DeviceInfo,
+	DeviceInfoResult};
 
 /// Provides a more convenient and safe interface to access less commonly used device information.
 /// The methods return the appropriate type for the given device info, rather than a
@@ -100,6 +102,39 @@ pub trait FullDeviceInfo {
 	fn printf_buffer_size(&self) -> OclResult<usize>;
 	fn image_pitch_alignment(&self) -> OclResult<u32>;
 	fn image_base_address_alignment(&self) -> OclResult<u32>;
+	fn max_read_write_image_args(&self) -> OclResult<u32>;
+	fn max_global_variable_size(&self) -> OclResult<usize>;
+	fn queue_on_device_properties(&self) -> OclResult<CommandQueueProperties>;
+	fn queue_on_device_preferred_size(&self) -> OclResult<u32>;
+	fn queue_on_device_max_size(&self) -> OclResult<u32>;
+	fn max_on_device_queues(&self) -> OclResult<u32>;
+	fn max_on_device_events(&self) -> OclResult<u32>;
+	fn svm_capabilities(&self) -> OclResult<DeviceSvmCapabilities>;
+	fn global_variable_preferred_total_size(&self) -> OclResult<usize>;
+	fn max_pipe_args(&self) -> OclResult<u32>;
+	fn pipe_max_active_reservations(&self) -> OclResult<u32>;
+	fn pipe_max_packet_size(&self) -> OclResult<u32>;
+	fn preferred_platform_atomic_alignment(&self) -> OclResult<u32>;
+	fn preferred_global_atomic_alignment(&self) -> OclResult<u32>;
+	fn preferred_local_atomic_alignment(&self) -> OclResult<u32>;
+	fn il_version(&self) -> OclResult<String>;
+	fn max_num_sub_groups(&self) -> OclResult<u32>;
+	fn sub_group_independent_forward_progress(&self) -> OclResult<bool>;
+	fn numeric_version(&self) -> OclResult<NumericVersion>;
+	fn extensions_with_version(&self) -> OclResult<Vec<NameVersion>>;
+	fn ils_with_version(&self) -> OclResult<Vec<NameVersion>>;
+	fn built_in_kernels_with_version(&self) -> OclResult<Vec<NameVersion>>;
+	fn atomic_memory_capabilities(&self) -> OclResult<DeviceAtomicCapabilities>;
+	fn atomic_fence_capabilities(&self) -> OclResult<DeviceAtomicCapabilities>;
+	fn non_uniform_work_group_support(&self) -> OclResult<bool>;
+	fn opencl_c_all_versions(&self) -> OclResult<Vec<NameVersion>>;
+	fn preferred_work_group_size_multiple(&self) -> OclResult<usize>;
+	fn work_group_collective_functions_support(&self) -> OclResult<bool>;
+	fn generic_address_space_support(&self) -> OclResult<bool>;
+	fn opencl_c_features(&self) -> OclResult<Vec<NameVersion>>;
+	fn device_enqueue_capabilities(&self) -> OclResult<DeviceEnqueueCapabilities>;
+	fn pipe_support(&self) -> OclResult<bool>;
+	fn latest_conformance_version_passed(&self) -> OclResult<String>;
 }
 
 macro_rules! dev_info_fn {
@@ -190,4 +225,37 @@ impl FullDeviceInfo for Device {
 	dev_info_fn! { fn printf_buffer_size() PrintfBufferSize -> usize }
 	dev_info_fn! { fn image_pitch_alignment() ImagePitchAlignment -> u32 }
 	dev_info_fn! { fn image_base_address_alignment() ImageBaseAddressAlignment -> u32 }
+	dev_info_fn! { fn max_read_write_image_args() MaxReadWriteImageArgs -> u32 }
+	dev_info_fn! { fn max_global_variable_size() MaxGlobalVariableSize -> usize }
+	dev_info_fn! { fn queue_on_device_properties() QueueOnDeviceProperties -> CommandQueueProperties }
+	dev_info_fn! { fn queue_on_device_preferred_size() QueueOnDevicePreferredSize -> u32 }
+	dev_info_fn! { fn queue_on_device_max_size() QueueOnDeviceMaxSize -> u32 }
+	dev_info_fn! { fn max_on_device_queues() MaxOnDeviceQueues -> u32 }
+	dev_info_fn! { fn max_on_device_events() MaxOnDeviceEvents -> u32 }
+	dev_info_fn! { fn svm_capabilities() SvmCapabilities -> DeviceSvmCapabilities }
+	dev_info_fn! { fn global_variable_preferred_total_size() GlobalVariablePreferredTotalSize -> usize }
+	dev_info_fn! { fn max_pipe_args() MaxPipeArgs -> u32 }
+	dev_info_fn! { fn pipe_max_active_reservations() PipeMaxActiveReservations -> u32 }
+	dev_info_fn! { fn pipe_max_packet_size() PipeMaxPacketSize -> u32 }
+	dev_info_fn! { fn preferred_platform_atomic_alignment() PreferredPlatformAtomicAlignment -> u32 }
+	dev_info_fn! { fn preferred_global_atomic_alignment() PreferredGlobalAtomicAlignment -> u32 }
+	dev_info_fn! { fn preferred_local_atomic_alignment() PreferredLocalAtomicAlignment -> u32 }
+	dev_info_fn! { fn il_version() IlVersion -> String }
+	dev_info_fn! { fn max_num_sub_groups() MaxNumSubGroups -> u32 }
+	dev_info_fn! { fn sub_group_independent_forward_progress() SubGroupIndependentForwardProgress -> bool }
+	dev_info_fn! { fn numeric_version() NumericVersion -> NumericVersion }
+	dev_info_fn! { fn extensions_with_version() ExtensionsWithVersion -> Vec<NameVersion> }
+	dev_info_fn! { fn ils_with_version() IlsWithVersion -> Vec<NameVersion> }
+	dev_info_fn! { fn built_in_kernels_with_version() BuiltInKernelsWithVersion -> Vec<NameVersion> }
+	dev_info_fn! { fn atomic_memory_capabilities() AtomicMemoryCapabilities -> DeviceAtomicCapabilities }
+	dev_info_fn! { fn atomic_fence_capabilities() AtomicFenceCapabilities -> DeviceAtomicCapabilities }
+	dev_info_fn! { fn non_uniform_work_group_support() NonUniformWorkGroupSupport -> bool }
+	dev_info_fn! { fn opencl_c_all_versions() OpenclCAllVersions -> Vec<NameVersion> }
+	dev_info_fn! { fn preferred_work_group_size_multiple() PreferredWorkGroupSizeMultiple -> usize }
+	dev_info_fn! { fn work_group_collective_functions_support() WorkGroupCollectiveFunctionsSupport -> bool }
+	dev_info_fn! { fn generic_address_space_support() GenericAddressSpaceSupport -> bool }
+	dev_info_fn! { fn opencl_c_features() OpenclCFeatures -> Vec<NameVersion> }
+	dev_info_fn! { fn device_enqueue_capabilities() DeviceEnqueueCapabilities -> DeviceEnqueueCapabilities }
+	dev_info_fn! { fn pipe_support() PipeSupport -> bool }
+	dev_info_fn! { fn latest_conformance_version_passed() LatestConformanceVersionPassed -> String }
 }
\ No newline at end of file
@@ -1,12 +1,14 @@
 //! High performance buffer writes.
 
 use std::ops::{Deref, DerefMut};
-use futures::{Future, Poll};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 use crate::core::{self, OclPrm, MemMap as MemMapCore,
     MemFlags, MapFlags, ClNullEventPtr};
 use crate::standard::{Event, EventList, Queue, Buffer, ClWaitListPtrEnum, ClNullEventPtrEnum};
 use crate::r#async::{OrderLock, FutureGuard, ReadGuard, WriteGuard};
-use crate::error::{Error as OclError, Result as OclResult};
+use crate::error::{Result as OclResult};
 
 
 #[must_use = "futures do nothing unless polled"]
@@ -27,12 +29,11 @@ impl<T: OclPrm> FutureFlush<T> {
 }
 
 impl<T: OclPrm> Future for FutureFlush<T> {
-    type Item = ();
-    type Error = OclError;
+    type Output = OclResult<()>;
 
     #[inline]
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        self.future_guard.poll().map(|res| res.map(|_read_guard| ()))
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.future_guard).poll(cx).map(|res| res.map(|_read_guard| ()))
     }
 }
 
@@ -106,7 +107,8 @@ impl<'c, T> FlushCmd<'c, T> where T: OclPrm {
     /// associated with this command.
     ///
     /// The destination can be a mutable reference to an empty event (created
-    /// using [`Event::empty`]) or a mutable reference to an event list.
+    /// using [`Event::empty`]), to an `Option<Event>` (replacing any event it
+    /// holds), or to an event list.
     ///
     /// After this command is enqueued, the event in the destination can be
     /// passed to the `::ewait` method of another command. Doing so will cause
@@ -208,6 +210,10 @@ impl<T: OclPrm> Inner<T> {
     }
 }
 
+// The mapped memory is only accessed through the guards of the lock which
+// holds this `Inner`.
+unsafe impl<T: OclPrm> Sync for Inner<T> {}
+
 impl<T: OclPrm> Deref for Inner<T> {
     type Target = [T];
 
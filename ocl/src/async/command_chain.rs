@@ -0,0 +1,489 @@
+use std::any::Any;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use crate::core::{self, OclPrm, ClNullEventPtr};
+use crate::error::{Error as OclError, Result as OclResult};
+use crate::standard::{ClNullEventPtrEnum, completion};
+use crate::r#async::{DropPolicy, FutureReadGuard, FutureWriteGuard};
+use crate::{Kernel, Buffer, Queue, Event, EventList};
+
+
+/// A lock guard future which releases its lock once its command completes.
+type HeldGuard = Pin<Box<dyn Future<Output = OclResult<()>> + Send>>;
+
+
+/// Blocks the current thread until each guard future has resolved (and
+/// released its lock).
+///
+/// Guarded commands can not run until their guard futures are polled.
+fn resolve_guards(guards: &mut Vec<HeldGuard>) {
+    for guard in guards.drain(..) {
+        futures::executor::block_on(guard).ok();
+    }
+}
+
+
+/// The state threaded through the links of a chain as they are enqueued.
+struct ChainState {
+    wait_list: EventList,
+    last_event: Option<Event>,
+    held: Vec<Box<dyn Any + Send>>,
+    guards: Vec<HeldGuard>,
+    reads: Vec<Box<dyn Any + Send>>,
+}
+
+impl ChainState {
+    /// Makes `event` the one the next link waits on and flushes its queue so
+    /// that the chain progresses regardless of the flush policy in use.
+    fn push_event(&mut self, event: Option<Event>) -> OclResult<()> {
+        let event = event.expect("CommandChain::enq: No event created.");
+        let queue = event.queue_core()?;
+        self.wait_list = EventList::from(event.clone());
+        self.last_event = Some(event);
+        core::flush(&queue).map_err(OclError::from)
+    }
+}
+
+type Link<'c> = Box<dyn FnOnce(&mut ChainState, Option<&'c Queue>) -> OclResult<()> + 'c>;
+
+
+/// A sequence of buffer writes, kernel enqueues, and buffer reads, each of
+/// which waits on the one before it.
+///
+/// Commands are appended in order and enqueued all at once with `::enq`,
+/// which returns a single future for the whole chain. The event of each
+/// command is passed to the next as its wait list, so commands may use
+/// different (or out-of-order) queues without further synchronization.
+///
+/// Writes from and reads into an `RwVec` are appended with
+/// `::write_guarded` and `::read_guarded`, which also wait for their lock
+/// (see `FutureGuard`) and hold it until the command completes.
+///
+/// ```rust,ignore
+/// let reads = unsafe {
+///     CommandChain::new()
+///         .write(&src_buffer, src_vec)
+///         .kernel(&kernel)
+///         .read(&dst_buffer)
+///         .enq()?
+/// }.wait()?;
+/// let dst_vec: Vec<f32> = reads.take(0)?;
+/// ```
+///
+/// [UNSTABLE]: This type's methods may be renamed or otherwise changed at any time.
+#[must_use = "commands are not enqueued until `::enq` is called"]
+pub struct CommandChain<'c> {
+    links: Vec<Link<'c>>,
+    queue: Option<&'c Queue>,
+    wait_events: Option<EventList>,
+    new_event: Option<ClNullEventPtrEnum<'c>>,
+    read_count: usize,
+}
+
+impl<'c> CommandChain<'c> {
+    /// Returns a new, empty `CommandChain`.
+    pub fn new() -> CommandChain<'c> {
+        CommandChain {
+            links: Vec::new(),
+            queue: None,
+            wait_events: None,
+            new_event: None,
+            read_count: 0,
+        }
+    }
+
+    /// Specifies a queue to use for every command in the chain.
+    ///
+    /// Commands otherwise use the default queue of their buffer or kernel.
+    pub fn queue(mut self, queue: &'c Queue) -> CommandChain<'c> {
+        self.queue = Some(queue);
+        self
+    }
+
+    /// Specifies events which the first command in the chain must wait on.
+    pub fn ewait<Ewl: Into<EventList>>(mut self, wait_events: Ewl) -> CommandChain<'c> {
+        self.wait_events = Some(wait_events.into());
+        self
+    }
+
+    /// Specifies the destination to store the event of the last command in
+    /// the chain.
+    pub fn enew<En>(mut self, new_event_dest: En) -> CommandChain<'c>
+            where En: Into<ClNullEventPtrEnum<'c>> {
+        self.new_event = Some(new_event_dest.into());
+        self
+    }
+
+    /// Appends a write of the entire contents of `data` to the beginning of
+    /// `buffer`.
+    ///
+    /// `data` is held until the chain completes.
+    pub fn write<T: OclPrm>(mut self, buffer: &'c Buffer<T>, data: Vec<T>) -> CommandChain<'c> {
+        self.links.push(Box::new(move |state: &mut ChainState, queue: Option<&'c Queue>| {
+            let mut event = None;
+            {
+                let mut cmd = buffer.write(&data[..]).ewait(&state.wait_list).enew(&mut event);
+                if let Some(queue) = queue {
+                    cmd = cmd.queue(queue);
+                }
+                unsafe { cmd.block(false).enq()?; }
+            }
+            state.held.push(Box::new(data));
+            state.push_event(event)
+        }));
+        self
+    }
+
+    /// Appends a write of the entire contents of the `Vec` guarded by
+    /// `reader` to the beginning of `buffer`.
+    ///
+    /// The write runs once the previous command has completed and the read
+    /// lock has been obtained. The lock is released once the write
+    /// completes.
+    pub fn write_guarded<T: OclPrm>(mut self, buffer: &'c Buffer<T>,
+            reader: FutureReadGuard<Vec<T>>) -> CommandChain<'c> {
+        self.links.push(Box::new(move |state: &mut ChainState, queue: Option<&'c Queue>| {
+            let mut event = None;
+            let future_guard = {
+                let mut cmd = buffer.write(reader).ewait(&state.wait_list).enew(&mut event);
+                if let Some(queue) = queue {
+                    cmd = cmd.queue(queue);
+                }
+                cmd.enq_async()?
+            };
+            state.guards.push(Box::pin(async move { future_guard.await.map(drop) }));
+            state.push_event(event)
+        }));
+        self
+    }
+
+    /// Appends an enqueue of `kernel`, using its current arguments and work
+    /// sizes.
+    pub fn kernel(mut self, kernel: &'c Kernel) -> CommandChain<'c> {
+        self.links.push(Box::new(move |state: &mut ChainState, queue: Option<&'c Queue>| {
+            let mut event = None;
+            let mut cmd = kernel.cmd().ewait(&state.wait_list).enew(&mut event);
+            if let Some(queue) = queue {
+                cmd = cmd.queue(queue);
+            }
+            unsafe { cmd.enq()?; }
+            state.push_event(event)
+        }));
+        self
+    }
+
+    /// Appends a read of the entire contents of `buffer` into a new `Vec`.
+    ///
+    /// The `Vec` can be taken from the chain's output using the index of
+    /// this read among all reads in the chain (starting at zero).
+    pub fn read<T: OclPrm>(mut self, buffer: &'c Buffer<T>) -> CommandChain<'c> {
+        self.links.push(Box::new(move |state: &mut ChainState, queue: Option<&'c Queue>| {
+            let mut vec = vec![T::default(); buffer.len()];
+            let mut event = None;
+            {
+                let mut cmd = buffer.read(&mut vec).ewait(&state.wait_list).enew(&mut event);
+                if let Some(queue) = queue {
+                    cmd = cmd.queue(queue);
+                }
+                unsafe { cmd.block(false).enq()?; }
+            }
+            state.reads.push(Box::new(vec));
+            state.push_event(event)
+        }));
+        self.read_count += 1;
+        self
+    }
+
+    /// Appends a read of the entire contents of `buffer` into the `Vec`
+    /// guarded by `writer`.
+    ///
+    /// The read runs once the previous command has completed and the write
+    /// lock has been obtained. The lock is released once the read completes.
+    /// The data is not included in the chain's output.
+    pub fn read_guarded<T: OclPrm>(mut self, buffer: &'c Buffer<T>,
+            writer: FutureWriteGuard<Vec<T>>) -> CommandChain<'c> {
+        self.links.push(Box::new(move |state: &mut ChainState, queue: Option<&'c Queue>| {
+            let mut event = None;
+            let future_guard = {
+                let mut cmd = buffer.read(writer).ewait(&state.wait_list).enew(&mut event);
+                if let Some(queue) = queue {
+                    cmd = cmd.queue(queue);
+                }
+                cmd.enq_async()?
+            };
+            state.guards.push(Box::pin(async move { future_guard.await.map(drop) }));
+            state.push_event(event)
+        }));
+        self
+    }
+
+    /// Returns the number of commands in the chain.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Returns true if no commands have been appended.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// Enqueues every command in the chain, in order, and returns a future
+    /// which resolves to the results of its reads once the last command
+    /// completes.
+    ///
+    /// If a command fails to enqueue, the current thread blocks until the
+    /// commands already enqueued have completed (and their locks have been
+    /// released) before the error is returned.
+    ///
+    /// ## Safety
+    ///
+    /// All kernel code must be considered untrusted (see `KernelCmd::enq`).
+    pub unsafe fn enq(mut self) -> OclResult<FutureChain> {
+        let mut state = ChainState {
+            wait_list: self.wait_events.take().unwrap_or_else(EventList::new),
+            last_event: None,
+            held: Vec::new(),
+            guards: Vec::new(),
+            reads: Vec::with_capacity(self.read_count),
+        };
+
+        for link in self.links.drain(..) {
+            if let Err(err) = link(&mut state, self.queue) {
+                resolve_guards(&mut state.guards);
+                if let Some(ref event) = state.last_event {
+                    event.wait_for().ok();
+                }
+                return Err(err);
+            }
+        }
+
+        if let (Some(mut enew), Some(ref event)) = (self.new_event.take(), &state.last_event) {
+            enew.clone_from(event);
+        }
+
+        Ok(FutureChain {
+            event: state.last_event,
+            held: state.held,
+            guards: state.guards,
+            reads: Some(state.reads),
+            drop_policy: DropPolicy::default(),
+        })
+    }
+}
+
+impl<'c> Default for CommandChain<'c> {
+    fn default() -> CommandChain<'c> {
+        CommandChain::new()
+    }
+}
+
+impl<'c> fmt::Debug for CommandChain<'c> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CommandChain")
+            .field("len", &self.links.len())
+            .field("queue", &self.queue)
+            .field("wait_events", &self.wait_events)
+            .field("read_count", &self.read_count)
+            .finish()
+    }
+}
+
+
+/// A future which resolves to the results of the reads in a `CommandChain`
+/// once its last command completes.
+///
+/// The commands of guarded links (see `CommandChain::write_guarded`) can not
+/// run until their locks have been obtained, which happens as this future is
+/// polled.
+///
+/// If this future is dropped before then, the data used by the chain is
+/// freed according to the future's `DropPolicy`. Regardless of the policy,
+/// dropping blocks until the locks of any guarded links have been released.
+///
+/// [UNSTABLE]: This type's methods may be renamed or otherwise changed at any time.
+#[must_use = "futures do nothing unless polled"]
+pub struct FutureChain {
+    event: Option<Event>,
+    held: Vec<Box<dyn Any + Send>>,
+    guards: Vec<HeldGuard>,
+    reads: Option<Vec<Box<dyn Any + Send>>>,
+    drop_policy: DropPolicy,
+}
+
+impl FutureChain {
+    /// Returns the event of the last command in the chain, if any.
+    #[inline]
+    pub fn event(&self) -> Option<&Event> {
+        self.event.as_ref()
+    }
+
+    /// Returns the policy followed if this future is dropped before
+    /// resolving.
+    #[inline]
+    pub fn drop_policy(&self) -> DropPolicy {
+        self.drop_policy
+    }
+
+    /// Specifies the policy followed if this future is dropped before
+    /// resolving (see `DropPolicy`).
+    pub fn set_drop_policy(&mut self, drop_policy: DropPolicy) {
+        self.drop_policy = drop_policy
+    }
+
+    /// Specifies the policy followed if this future is dropped before
+    /// resolving.
+    ///
+    /// See `::set_drop_policy`.
+    pub fn with_drop_policy(mut self, drop_policy: DropPolicy) -> FutureChain {
+        self.set_drop_policy(drop_policy);
+        self
+    }
+
+    /// Blocks the current thread until the chain is complete.
+    pub fn wait(self) -> OclResult<ChainReads> {
+        futures::executor::block_on(self)
+    }
+
+    /// Polls the guard futures of guarded links, dropping each once it has
+    /// resolved.
+    fn poll_guards(&mut self, cx: &mut Context<'_>) -> Poll<OclResult<()>> {
+        let mut idx = 0;
+        while idx < self.guards.len() {
+            match self.guards[idx].as_mut().poll(cx) {
+                Poll::Ready(Ok(())) => drop(self.guards.swap_remove(idx)),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => idx += 1,
+            }
+        }
+        if self.guards.is_empty() { Poll::Ready(Ok(())) } else { Poll::Pending }
+    }
+
+    /// Releases the data held by the chain and returns its reads.
+    fn resolve(&mut self) -> OclResult<ChainReads> {
+        self.held.clear();
+        match self.reads.take() {
+            Some(reads) => Ok(ChainReads { vecs: reads.into_iter().map(Some).collect() }),
+            None => Err("FutureChain::poll: Already resolved.".into()),
+        }
+    }
+}
+
+#[cfg(not(feature = "async_block"))]
+impl Future for FutureChain {
+    type Output = OclResult<ChainReads>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.poll_guards(cx)?.is_pending() {
+            return Poll::Pending;
+        }
+        let is_complete = match this.event {
+            Some(ref event) => event.is_complete(),
+            None => Ok(true),
+        };
+        match is_complete {
+            Ok(true) => Poll::Ready(this.resolve()),
+            Ok(false) => {
+                completion::register(this.event.as_ref().unwrap(), cx.waker())?;
+                Poll::Pending
+            },
+            Err(err) => Poll::Ready(Err(err.into())),
+        }
+    }
+}
+
+/// Blocking implementation.
+#[cfg(feature = "async_block")]
+impl Future for FutureChain {
+    type Output = OclResult<ChainReads>;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        for guard in this.guards.drain(..) {
+            futures::executor::block_on(guard)?;
+        }
+        if let Some(ref event) = this.event {
+            event.wait_for()?;
+        }
+        Poll::Ready(this.resolve())
+    }
+}
+
+impl Drop for FutureChain {
+    /// Frees the data held by the chain, if it has not yet resolved, once the
+    /// chain completes, ensuring the data outlives its commands.
+    fn drop(&mut self) {
+        resolve_guards(&mut self.guards);
+        if let (Some(reads), Some(ref event)) = (self.reads.take(), &self.event) {
+            match self.drop_policy {
+                DropPolicy::Block => { event.wait_for().ok(); },
+                DropPolicy::Detach => {
+                    let held = std::mem::take(&mut self.held);
+                    completion::on_complete(event, move || drop((held, reads)));
+                },
+            }
+        }
+    }
+}
+
+impl Unpin for FutureChain {}
+
+impl fmt::Debug for FutureChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FutureChain")
+            .field("event", &self.event)
+            .field("held", &self.held)
+            .field("guards", &self.guards.len())
+            .field("reads", &self.reads)
+            .field("drop_policy", &self.drop_policy)
+            .finish()
+    }
+}
+
+
+/// The results of the reads in a completed `CommandChain`.
+#[derive(Debug)]
+pub struct ChainReads {
+    vecs: Vec<Option<Box<dyn Any + Send>>>,
+}
+
+impl ChainReads {
+    /// Takes the `Vec` read by the read at `idx` (in the order reads were
+    /// appended to the chain).
+    ///
+    /// Returns an error if `idx` is out of range, if the read has already
+    /// been taken, or if `T` is not the element type of the buffer read.
+    pub fn take<T: OclPrm>(&mut self, idx: usize) -> OclResult<Vec<T>> {
+        let slot = match self.vecs.get_mut(idx) {
+            Some(slot) => slot,
+            None => return Err(OclError::from(format!("ChainReads::take: No read at index {}.",
+                idx))),
+        };
+        let vec = slot.take().ok_or_else(|| OclError::from(format!("ChainReads::take: \
+            Read {} has already been taken.", idx)))?;
+        match vec.downcast::<Vec<T>>() {
+            Ok(vec) => Ok(*vec),
+            Err(vec) => {
+                *slot = Some(vec);
+                Err(OclError::from(format!("ChainReads::take: Read {} is not of the \
+                    requested type.", idx)))
+            },
+        }
+    }
+
+    /// Returns the number of reads in the chain.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.vecs.len()
+    }
+
+    /// Returns true if the chain contained no reads.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.vecs.is_empty()
+    }
+}
@@ -0,0 +1,33 @@
+/// Specifies what a future does when it is dropped before resolving while
+/// the command it represents may still be running.
+///
+/// The command itself can not be withdrawn once enqueued. What can be chosen
+/// is how the resources it uses (host memory, mapped regions) are kept alive
+/// until it completes and how they are released afterwards:
+///
+/// * `FutureMemMap` (and `FutureSharedMemMap`): with `Block`, waits for the
+///   map command, then unmaps. With `Detach`, immediately enqueues an unmap
+///   command which waits on the map command. Either way the unmap event (if
+///   any) is triggered once the region is unmapped. `::cancel` does the same
+///   as `Detach` but returns the unmap event and any error.
+/// * `FutureWriteVec` and `FutureChain`: with `Block`, waits for the command
+///   (or chain) to complete before freeing the memory it uses. With `Detach`,
+///   the memory is freed on a background thread once the command completes.
+///   `FutureWriteVec::cancel` returns the source `Vec` instead, once it is
+///   safe to do so.
+/// * `FutureGuard` and `FutureKernel` (when holding values) always block, as
+///   the values they hold can not be assumed to be sendable to another
+///   thread. `FutureFinish` holds nothing and never blocks.
+///
+/// No future waits on or releases anything when dropped after resolving.
+///
+/// [UNSTABLE]: This type may be renamed or otherwise changed at any time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// Blocks the dropping thread until the command completes, then
+    /// releases its resources. This is the default.
+    #[default]
+    Block,
+    /// Returns immediately, releasing resources once the command completes.
+    Detach,
+}
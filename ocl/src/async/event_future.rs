@@ -0,0 +1,178 @@
+//! Futures resolving on completion of several events.
+
+use std::future::Future;
+use std::pin::Pin;
+#[cfg(feature = "async_block")]
+use std::sync::mpsc;
+use std::task::{Context, Poll};
+use crate::core;
+use crate::core::CommandExecutionStatus;
+use crate::error::{Result as OclResult};
+use crate::standard::event_failed;
+#[cfg(not(feature = "async_block"))]
+use crate::standard::completion;
+use crate::{Event, EventList};
+
+
+/// Returns a future which resolves once every event in `events` has
+/// completed.
+pub fn join_events<L: Into<EventList>>(events: L) -> EventListFuture {
+    EventListFuture::new(events)
+}
+
+/// Returns a future which resolves to the first event in `events` to
+/// complete, along with its index.
+pub fn select_events<L: Into<EventList>>(events: L) -> EventSelect {
+    EventSelect::new(events)
+}
+
+
+/// A future which resolves to its list of events once every event in it has
+/// completed.
+///
+/// Unlike polling an `EventList` directly, the list is returned on
+/// completion so that it can be reused (as a wait list, for example).
+///
+/// Resolves to an error for which `Error::failed_event` identifies the event
+/// if any command terminates abnormally.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct EventListFuture {
+    events: Option<EventList>,
+}
+
+impl EventListFuture {
+    /// Returns a new `EventListFuture`.
+    pub fn new<L: Into<EventList>>(events: L) -> EventListFuture {
+        EventListFuture { events: Some(events.into()) }
+    }
+
+    /// Returns the list of events, if the future has not yet resolved.
+    pub fn events(&self) -> Option<&EventList> {
+        self.events.as_ref()
+    }
+
+    fn take_events(&mut self) -> EventList {
+        self.events.take().expect("EventListFuture::poll: Polled after completion.")
+    }
+}
+
+#[cfg(not(feature = "async_block"))]
+impl Future for EventListFuture {
+    type Output = OclResult<EventList>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let idx = match self.events {
+            Some(ref events) => events.first_incomplete()?,
+            None => panic!("EventListFuture::poll: Polled after completion."),
+        };
+
+        match idx {
+            Some(idx) => {
+                completion::register(&self.events.as_ref().unwrap()[idx], cx.waker())?;
+                Poll::Pending
+            },
+            None => Poll::Ready(Ok(self.take_events())),
+        }
+    }
+}
+
+/// Blocking implementation.
+#[cfg(feature = "async_block")]
+impl Future for EventListFuture {
+    type Output = OclResult<EventList>;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let events = self.take_events();
+        events.wait_for()?;
+        Poll::Ready(Ok(events))
+    }
+}
+
+
+/// A future which resolves to the first of a list of events to complete,
+/// along with its index within the list.
+///
+/// Resolves to an error for which `Error::failed_event` identifies the event
+/// if a command terminates abnormally before any other completes, or to an
+/// error if the list is empty.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct EventSelect {
+    events: EventList,
+}
+
+impl EventSelect {
+    /// Returns a new `EventSelect`.
+    pub fn new<L: Into<EventList>>(events: L) -> EventSelect {
+        EventSelect { events: events.into() }
+    }
+
+    /// Returns the list of events.
+    pub fn events(&self) -> &EventList {
+        &self.events
+    }
+
+    /// Returns the index of the first complete event, if any.
+    fn first_complete(&self) -> OclResult<Option<usize>> {
+        if self.events.is_empty() {
+            return Err("EventSelect::poll: No events to select from.".into());
+        }
+        for (index, event) in self.events.iter().enumerate() {
+            let status = core::event_status_code(event)?;
+            if status < 0 {
+                return Err(event_failed(index, event, status));
+            } else if status == CommandExecutionStatus::Complete as i32 {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(not(feature = "async_block"))]
+impl Future for EventSelect {
+    type Output = OclResult<(usize, Event)>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.first_complete()? {
+            Some(idx) => Poll::Ready(Ok((idx, self.events[idx].clone()))),
+            None => {
+                for event in self.events.iter() {
+                    completion::register(event, cx.waker())?;
+                }
+                Poll::Pending
+            },
+        }
+    }
+}
+
+/// Blocking implementation.
+#[cfg(feature = "async_block")]
+impl Future for EventSelect {
+    type Output = OclResult<(usize, Event)>;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(idx) = self.first_complete()? {
+            return Poll::Ready(Ok((idx, self.events[idx].clone())));
+        }
+
+        // Block until any event completes:
+        let (tx, rx) = mpsc::channel();
+        for event in self.events.iter() {
+            let tx = tx.clone();
+            event.on_complete(move |_| { tx.send(()).ok(); })?;
+        }
+        drop(tx);
+
+        loop {
+            if rx.recv().is_err() {
+                return Poll::Ready(Err("EventSelect::poll: Completion callbacks were dropped \
+                    before any event completed.".into()));
+            }
+            if let Some(idx) = self.first_complete()? {
+                return Poll::Ready(Ok((idx, self.events[idx].clone())));
+            }
+        }
+    }
+}
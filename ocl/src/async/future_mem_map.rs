@@ -1,15 +1,22 @@
 // use std::sync::Arc;
 // use std::sync::atomic::AtomicBool;
-use futures::{Future, Poll, Async};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 use crate::core::{OclPrm, MemMap as MemMapCore, Mem, ClNullEventPtr};
-use crate::r#async::MemMap;
-use crate::error::{Error as OclError, Result as OclResult};
-use crate::{Event, Queue, EventList};
+use crate::r#async::{MemMap, FutureSharedMemMap, DropPolicy};
+use crate::error::{Result as OclResult};
+use crate::{Event, Queue, EventList, Priority};
+#[cfg(not(feature = "async_block"))]
+use crate::standard::completion;
 
 
 /// A future which resolves to a `MemMap` as soon as its creating command
 /// completes.
 ///
+/// If dropped before resolving, the region is unmapped according to the
+/// future's `DropPolicy`.
+///
 /// [UNSTABLE]: This type's methods may be renamed or otherwise changed at any time.
 #[must_use = "futures do nothing unless polled"]
 #[derive(Debug)]
@@ -21,7 +28,8 @@ pub struct FutureMemMap<T: OclPrm> {
     unmap_event: Option<Event>,
     buffer: Option<Mem>,
     queue: Option<Queue>,
-    callback_is_set: bool,
+    priority: Priority,
+    drop_policy: DropPolicy,
     // buffer_is_mapped: Option<Arc<AtomicBool>>,
 }
 
@@ -37,7 +45,8 @@ impl<T: OclPrm> FutureMemMap<T> {
             unmap_event: None,
             buffer: Some(buffer),
             queue: Some(queue),
-            callback_is_set: false,
+            priority: Priority::current(),
+            drop_policy: DropPolicy::default(),
             // buffer_is_mapped: Some(buffer_is_mapped),
         }
     }
@@ -116,10 +125,83 @@ impl<T: OclPrm> FutureMemMap<T> {
         self.unmap_event.as_ref()
     }
 
+    /// Returns the priority tag inherited from the task which created this
+    /// future (see `Priority::current`).
+    #[inline]
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Overrides the priority tag of this future.
+    pub fn set_priority(&mut self, priority: Priority) {
+        self.priority = priority
+    }
+
+    /// Overrides the priority tag of this future.
+    ///
+    /// See `::set_priority`.
+    pub fn with_priority(mut self, priority: Priority) -> FutureMemMap<T> {
+        self.set_priority(priority);
+        self
+    }
+
+    /// Returns the policy followed if this future is dropped before
+    /// resolving.
+    #[inline]
+    pub fn drop_policy(&self) -> DropPolicy {
+        self.drop_policy
+    }
+
+    /// Specifies the policy followed if this future is dropped before
+    /// resolving (see `DropPolicy`).
+    pub fn set_drop_policy(&mut self, drop_policy: DropPolicy) {
+        self.drop_policy = drop_policy
+    }
+
+    /// Specifies the policy followed if this future is dropped before
+    /// resolving.
+    ///
+    /// See `::set_drop_policy`.
+    pub fn with_drop_policy(mut self, drop_policy: DropPolicy) -> FutureMemMap<T> {
+        self.set_drop_policy(drop_policy);
+        self
+    }
+
+    /// Abandons this future without waiting for the map command, enqueuing
+    /// an unmap command which waits on it.
+    ///
+    /// Returns the event associated with the unmap command. The unmap event
+    /// (if created) is triggered once the unmap command completes.
+    pub fn cancel(mut self) -> OclResult<Event> {
+        self.unmap_after_map()
+    }
+
+    /// Converts this future into one which resolves to a shareable,
+    /// read-only `SharedMemMap`.
+    ///
+    /// The mapped region is unmapped once the last clone of the resolved
+    /// `SharedMemMap` is dropped.
+    pub fn shared(self) -> FutureSharedMemMap<T> {
+        FutureSharedMemMap::new(self)
+    }
+
     /// Blocks the current thread until the OpenCL command is complete and an
     /// appropriate lock can be obtained on the underlying data.
     pub fn wait(self) -> OclResult<MemMap<T>> {
-        <Self as Future>::wait(self)
+        futures::executor::block_on(self)
+    }
+
+    /// Resolves this `FutureMemMap` into a `MemMap` without waiting for the
+    /// map command, then enqueues an unmap command which waits on it.
+    fn unmap_after_map(&mut self) -> OclResult<Event> {
+        let mut unmap_wait_events = self.unmap_wait_events.take().unwrap_or_else(EventList::new);
+        unmap_wait_events.push(self.map_event.clone());
+        self.unmap_wait_events = Some(unmap_wait_events);
+
+        let mut mem_map = self.to_mapped_mem()?;
+        let mut unmap_event = Event::empty();
+        mem_map.unmap().enew(&mut unmap_event).enq()?;
+        Ok(unmap_event)
     }
 
     /// Resolves this `FutureMemMap` into a `MemMap`.
@@ -138,24 +220,20 @@ impl<T: OclPrm> FutureMemMap<T> {
 
 #[cfg(not(feature = "async_block"))]
 impl<T> Future for FutureMemMap<T> where T: OclPrm + 'static {
-    type Item = MemMap<T>;
-    type Error = OclError;
+    type Output = OclResult<MemMap<T>>;
 
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         // println!("Polling FutureMemMap...");
-        match self.map_event.is_complete() {
+        let this = self.get_mut();
+        match this.map_event.is_complete() {
             Ok(true) => {
-                self.to_mapped_mem().map(|mm| Async::Ready(mm))
+                Poll::Ready(this.to_mapped_mem())
             }
             Ok(false) => {
-                if !self.callback_is_set {
-                    self.map_event.set_unpark_callback()?;
-                    self.callback_is_set = true;
-                }
-
-                Ok(Async::NotReady)
+                completion::register(&this.map_event, cx.waker())?;
+                Poll::Pending
             },
-            Err(err) => Err(err.into()),
+            Err(err) => Poll::Ready(Err(err.into())),
         }
     }
 }
@@ -163,17 +241,35 @@ impl<T> Future for FutureMemMap<T> where T: OclPrm + 'static {
 /// Blocking implementation.
 #[cfg(feature = "async_block")]
 impl<T: OclPrm> Future for FutureMemMap<T> {
-    type Item = MemMap<T>;
-    type Error = OclError;
+    type Output = OclResult<MemMap<T>>;
 
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
         // println!("Polling FutureMemMap...");
-        let _ = self.callback_is_set;
-        self.map_event.wait_for()?;
-        self.to_mapped_mem().map(|mm| Async::Ready(mm))
+        let this = self.get_mut();
+        this.map_event.wait_for()?;
+        Poll::Ready(this.to_mapped_mem())
+    }
+}
+
+impl<T: OclPrm> Drop for FutureMemMap<T> {
+    /// Unmaps the region, either after blocking until the map command
+    /// completes or by way of an unmap command which waits on it, if this
+    /// future has not yet resolved.
+    fn drop(&mut self) {
+        if self.core.is_none() { return; }
+        match self.drop_policy {
+            DropPolicy::Block => {
+                self.map_event.wait_for().ok();
+                self.to_mapped_mem().ok();
+            },
+            DropPolicy::Detach => {
+                self.unmap_after_map().ok();
+            },
+        }
     }
 }
 
+impl<T: OclPrm> Unpin for FutureMemMap<T> {}
 unsafe impl<T: OclPrm> Send for FutureMemMap<T> {}
 unsafe impl<T: OclPrm> Sync for FutureMemMap<T> {}
 
@@ -0,0 +1,127 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use crate::core::OclPrm;
+use crate::error::{Result as OclResult};
+use crate::Event;
+use crate::r#async::DropPolicy;
+use crate::standard::completion;
+
+
+/// A future which resolves to the destination `Vec` of a non-blocking read
+/// command as soon as that command completes.
+///
+/// The `Vec` is held until the read completes. If this future is dropped
+/// before then, the `Vec` is freed according to the future's `DropPolicy`.
+///
+/// [UNSTABLE]: This type's methods may be renamed or otherwise changed at any time.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct FutureReadVec<T: OclPrm> {
+    vec: Option<Vec<T>>,
+    read_event: Event,
+    drop_policy: DropPolicy,
+}
+
+impl<T: OclPrm> FutureReadVec<T> {
+    /// Returns a new `FutureReadVec`.
+    ///
+    /// ## Safety
+    ///
+    /// `read_event` must be the event of a command which writes to `vec`.
+    pub unsafe fn new(vec: Vec<T>, read_event: Event) -> FutureReadVec<T> {
+        FutureReadVec { vec: Some(vec), read_event, drop_policy: DropPolicy::default() }
+    }
+
+    /// Returns the event associated with the read command.
+    #[inline]
+    pub fn read_event(&self) -> &Event {
+        &self.read_event
+    }
+
+    /// Returns the policy followed if this future is dropped before
+    /// resolving.
+    #[inline]
+    pub fn drop_policy(&self) -> DropPolicy {
+        self.drop_policy
+    }
+
+    /// Specifies the policy followed if this future is dropped before
+    /// resolving (see `DropPolicy`).
+    pub fn set_drop_policy(&mut self, drop_policy: DropPolicy) {
+        self.drop_policy = drop_policy
+    }
+
+    /// Specifies the policy followed if this future is dropped before
+    /// resolving.
+    ///
+    /// See `::set_drop_policy`.
+    pub fn with_drop_policy(mut self, drop_policy: DropPolicy) -> FutureReadVec<T> {
+        self.set_drop_policy(drop_policy);
+        self
+    }
+
+    /// Abandons this future, returning the destination `Vec` once the read
+    /// command completes.
+    ///
+    /// Unlike `::wait`, the `Vec` is returned even if the read command
+    /// fails or is terminated, in which case its contents are unspecified.
+    pub fn cancel(mut self) -> Vec<T> {
+        self.read_event.wait_for().ok();
+        self.vec.take().unwrap_or_default()
+    }
+
+    /// Blocks the current thread until the read command is complete.
+    pub fn wait(self) -> OclResult<Vec<T>> {
+        futures::executor::block_on(self)
+    }
+
+    /// Returns the destination `Vec`.
+    fn take_vec(&mut self) -> OclResult<Vec<T>> {
+        self.vec.take().ok_or_else(|| "FutureReadVec::poll: Already resolved.".into())
+    }
+}
+
+#[cfg(not(feature = "async_block"))]
+impl<T: OclPrm> Future for FutureReadVec<T> {
+    type Output = OclResult<Vec<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        match this.read_event.is_complete() {
+            Ok(true) => Poll::Ready(this.take_vec()),
+            Ok(false) => {
+                completion::register(&this.read_event, cx.waker())?;
+                Poll::Pending
+            },
+            Err(err) => Poll::Ready(Err(err.into())),
+        }
+    }
+}
+
+/// Blocking implementation.
+#[cfg(feature = "async_block")]
+impl<T: OclPrm> Future for FutureReadVec<T> {
+    type Output = OclResult<Vec<T>>;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        this.read_event.wait_for()?;
+        Poll::Ready(this.take_vec())
+    }
+}
+
+impl<T: OclPrm> Drop for FutureReadVec<T> {
+    /// Frees the `Vec`, if it has not yet been returned, once the read
+    /// command completes, ensuring it outlives the command.
+    fn drop(&mut self) {
+        if let Some(vec) = self.vec.take() {
+            match self.drop_policy {
+                DropPolicy::Block => { self.read_event.wait_for().ok(); },
+                DropPolicy::Detach => completion::on_complete(&self.read_event, move || drop(vec)),
+            }
+        }
+    }
+}
+
+impl<T: OclPrm> Unpin for FutureReadVec<T> {}
@@ -0,0 +1,200 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use futures::Stream;
+use crate::core::OclPrm;
+use crate::error::{Result as OclResult};
+use crate::{Kernel, Buffer, Queue, Event};
+#[cfg(not(feature = "async_block"))]
+use crate::standard::completion;
+
+
+/// An iteration which has been enqueued but not yet yielded.
+#[derive(Debug)]
+struct Frame<T: OclPrm> {
+    vec: Vec<T>,
+    read_event: Event,
+}
+
+
+/// A stream which repeatedly enqueues a kernel and yields the contents of
+/// its output buffer after each iteration.
+///
+/// Two output buffers are used in alternation: while the results of one
+/// iteration are being read, the kernel is already writing the next into
+/// the other buffer. Each item is an owned `Vec` read from the output buffer
+/// of one iteration, yielded in iteration order.
+///
+/// If the stream is dropped with reads still pending, the current thread
+/// blocks until they complete.
+///
+/// [UNSTABLE]: This type's methods may be renamed or otherwise changed at any time.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct KernelStream<T: OclPrm> {
+    kernel: Kernel,
+    arg_idx: u32,
+    buffers: [Buffer<T>; 2],
+    read_queue: Option<Queue>,
+    iterations: Option<usize>,
+    enqueued: usize,
+    frames: VecDeque<Frame<T>>,
+    last_reads: [Option<Event>; 2],
+    is_done: bool,
+}
+
+impl<T: OclPrm> KernelStream<T> {
+    /// Returns a new `KernelStream` which sets the kernel argument at
+    /// `arg_idx` to each of `buffers` in turn before enqueuing it.
+    ///
+    /// Use `Kernel::named_arg_idx` to look up the index of a named argument.
+    /// The stream is unbounded unless `::iterations` is also used.
+    pub fn new(kernel: Kernel, arg_idx: u32, buffers: [Buffer<T>; 2]) -> KernelStream<T> {
+        KernelStream {
+            kernel,
+            arg_idx,
+            buffers,
+            read_queue: None,
+            iterations: None,
+            enqueued: 0,
+            frames: VecDeque::with_capacity(2),
+            last_reads: [None, None],
+            is_done: false,
+        }
+    }
+
+    /// Limits the number of kernel iterations (and items) to `iterations`.
+    pub fn iterations(mut self, iterations: usize) -> KernelStream<T> {
+        self.iterations = Some(iterations);
+        self
+    }
+
+    /// Specifies a queue to use for reads, allowing them to overlap with
+    /// subsequent kernel iterations.
+    ///
+    /// Reads otherwise use the default queue of each output buffer.
+    pub fn read_queue(mut self, queue: Queue) -> KernelStream<T> {
+        self.read_queue = Some(queue);
+        self
+    }
+
+    /// Returns a reference to the kernel.
+    #[inline]
+    pub fn kernel(&self) -> &Kernel {
+        &self.kernel
+    }
+
+    /// Returns the number of kernel iterations enqueued so far.
+    #[inline]
+    pub fn enqueued(&self) -> usize {
+        self.enqueued
+    }
+
+    /// Enqueues kernel iterations (and their reads) until both output
+    /// buffers are in use or the iteration limit has been reached.
+    fn fill(&mut self) -> OclResult<()> {
+        while self.frames.len() < 2 && self.iterations.is_none_or(|n| self.enqueued < n) {
+            let slot = self.enqueued % 2;
+            let buffer = &self.buffers[slot];
+            self.kernel.set_arg(self.arg_idx, buffer)?;
+
+            // Wait for the previous read of this buffer before overwriting it:
+            let mut kernel_event = None;
+            unsafe {
+                self.kernel.cmd().ewait(&self.last_reads[slot]).enew(&mut kernel_event).enq()?;
+            }
+
+            let mut vec = vec![T::default(); buffer.len()];
+            let mut read_event = None;
+            {
+                let mut cmd = buffer.read(&mut vec).ewait(&kernel_event).enew(&mut read_event);
+                if let Some(ref queue) = self.read_queue {
+                    cmd = cmd.queue(queue);
+                }
+                unsafe { cmd.block(false).enq()?; }
+            }
+
+            let read_event = read_event.expect("KernelStream::fill: No read event created.");
+            self.last_reads[slot] = Some(read_event.clone());
+            self.frames.push_back(Frame { vec, read_event });
+            self.enqueued += 1;
+        }
+        Ok(())
+    }
+
+    /// Marks this stream as finished and returns the error.
+    fn fail(&mut self, err: crate::Error) -> Poll<Option<OclResult<Vec<T>>>> {
+        self.is_done = true;
+        Poll::Ready(Some(Err(err)))
+    }
+}
+
+#[cfg(not(feature = "async_block"))]
+impl<T: OclPrm> Stream for KernelStream<T> {
+    type Item = OclResult<Vec<T>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.is_done { return Poll::Ready(None); }
+        if let Err(err) = this.fill() { return this.fail(err); }
+
+        let is_complete = match this.frames.front() {
+            Some(frame) => frame.read_event.is_complete(),
+            None => {
+                this.is_done = true;
+                return Poll::Ready(None);
+            },
+        };
+
+        match is_complete {
+            Ok(true) => {
+                // The next iteration is enqueued on the next poll:
+                let frame = this.frames.pop_front().unwrap();
+                Poll::Ready(Some(Ok(frame.vec)))
+            },
+            Ok(false) => {
+                let read_event = &this.frames.front().unwrap().read_event;
+                if let Err(err) = completion::register(read_event, cx.waker()) {
+                    return this.fail(err);
+                }
+                Poll::Pending
+            },
+            Err(err) => this.fail(err.into()),
+        }
+    }
+}
+
+/// Blocking implementation.
+#[cfg(feature = "async_block")]
+impl<T: OclPrm> Stream for KernelStream<T> {
+    type Item = OclResult<Vec<T>>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.is_done { return Poll::Ready(None); }
+        if let Err(err) = this.fill() { return this.fail(err); }
+
+        let frame = match this.frames.pop_front() {
+            Some(frame) => frame,
+            None => {
+                this.is_done = true;
+                return Poll::Ready(None);
+            },
+        };
+
+        if let Err(err) = frame.read_event.wait_for() { return this.fail(err.into()); }
+        Poll::Ready(Some(Ok(frame.vec)))
+    }
+}
+
+impl<T: OclPrm> Drop for KernelStream<T> {
+    /// Blocks until all pending reads complete, ensuring their destination
+    /// `Vec`s outlive them.
+    fn drop(&mut self) {
+        for frame in self.frames.iter() {
+            frame.read_event.wait_for().ok();
+        }
+    }
+}
+
+impl<T: OclPrm> Unpin for KernelStream<T> {}
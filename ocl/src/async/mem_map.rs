@@ -76,7 +76,8 @@ impl<'c, T> MemUnmapCmd<'c, T> where T: OclPrm {
     /// associated with this command.
     ///
     /// The destination can be a mutable reference to an empty event (created
-    /// using [`Event::empty`]) or a mutable reference to an event list.
+    /// using [`Event::empty`]), to an `Option<Event>` (replacing any event it
+    /// holds), or to an event list.
     ///
     /// After this command is enqueued, the event in the destination can be
     /// passed to the `::ewait` method of another command. Doing so will cause
@@ -179,8 +180,13 @@ impl<T> MemMap<T>  where T: OclPrm {
                 None
             };
 
-            core::enqueue_unmap_mem_object(queue.unwrap_or(&self.queue), &self.buffer,
-                &self.core, ewait_opt.and(self.unmap_wait_events.as_ref()), origin_event_opt.as_mut())?;
+            let queue = queue.unwrap_or(&self.queue);
+            match ewait_opt {
+                Some(ewait) => core::enqueue_unmap_mem_object(queue, &self.buffer, &self.core,
+                    Some(ewait), origin_event_opt.as_mut())?,
+                None => core::enqueue_unmap_mem_object(queue, &self.buffer, &self.core,
+                    self.unmap_wait_events.as_ref(), origin_event_opt.as_mut())?,
+            }
 
             self.is_unmapped = true;
 
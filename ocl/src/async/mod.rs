@@ -1,21 +1,36 @@
 //! Types related to futures and asynchrony.
 
-extern crate qutex;
-
+mod qrw_lock;
 mod future_mem_map;
+mod shared_mem_map;
+mod future_write_vec;
+mod future_read_vec;
 mod order_lock;
 mod rw_vec;
 mod mem_map;
 mod buffer_sink;
 mod buffer_stream;
-
-pub use self::order_lock::{OrderLock, ReadGuard, WriteGuard, FutureGuard, FutureReadGuard,
-    FutureWriteGuard, OrderGuard};
+mod event_future;
+mod kernel_stream;
+mod command_chain;
+mod drop_policy;
+mod scope;
+
+pub use self::order_lock::{OrderLock, ReadGuard, WriteGuard, UpgradableReadGuard, FutureGuard,
+    FutureReadGuard, FutureWriteGuard, FutureUpgradableReadGuard, FutureUpgrade, OrderGuard};
 pub use self::rw_vec::RwVec;
 pub use self::mem_map::MemMap;
 pub use self::future_mem_map::FutureMemMap;
+pub use self::shared_mem_map::{SharedMemMap, FutureSharedMemMap};
+pub use self::future_write_vec::FutureWriteVec;
+pub use self::future_read_vec::FutureReadVec;
 pub use self::buffer_sink::{BufferSink, FutureFlush, Inner as BufferSinkInner};
 pub use self::buffer_stream::{BufferStream, FutureFlood, Inner as BufferStreamInner};
+pub use self::event_future::{EventListFuture, EventSelect, join_events, select_events};
+pub use self::kernel_stream::KernelStream;
+pub use self::command_chain::{CommandChain, FutureChain, ChainReads};
+pub use self::drop_policy::DropPolicy;
+pub use self::scope::{scope, TransferScope, FutureScopedRead};
 
 
 // * TODO: Implement this:
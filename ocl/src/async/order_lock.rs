@@ -1,19 +1,36 @@
 //! A mutex-like lock with a conserved global ordering which can be shared
 //! between threads and can interact with OpenCL events.
 //!
+//! ## Ordering
+//!
+//! A lock request takes its place in the global order when `::read` or
+//! `::write` is called. Requesting the lock again while holding a guard will
+//! deadlock if a conflicting request has been queued in the meantime. To run
+//! a read-analyze-write cycle without giving up the lock, use the guard
+//! itself as the ordering token for the next command:
+//!
+//! * `OrderLock::upgradable_read` requests a read lock which
+//!   `UpgradableReadGuard::upgrade` converts into a write lock without
+//!   releasing it. Only one upgradable read lock is held at a time, so no
+//!   other writer can intervene. `WriteGuard::downgrade` converts back.
+//! * `ReadGuard::into_future_guard` and `WriteGuard::into_future_guard`
+//!   convert a held guard into an already locked `FutureGuard`, which can be
+//!   passed to a buffer command in place of a newly requested one.
 //!
 //! TODO: Add doc links.
 //
 //
 
+use std::future::Future;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
-use futures::{Future, Poll, Async};
-use futures::sync::oneshot::{self, Receiver};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use futures::channel::oneshot::{self, Receiver};
 use crate::core::{ClContextPtr, ClNullEventPtr};
-use crate::error::{Error as OclError, Result as OclResult};
-use crate::{Event, EventList};
-use crate::r#async::qutex::{QrwLock, QrwRequest, RequestKind};
+use crate::error::{Result as OclResult};
+use crate::{Event, EventList, Priority};
+use crate::r#async::qrw_lock::{QrwLock, QrwRequest, RequestKind};
 
 
 const PRINT_DEBUG: bool = false;
@@ -21,6 +38,7 @@ const PRINT_DEBUG: bool = false;
 
 pub type FutureReadGuard<V> = FutureGuard<V, ReadGuard<V>>;
 pub type FutureWriteGuard<V> = FutureGuard<V, WriteGuard<V>>;
+pub type FutureUpgradableReadGuard<V> = FutureGuard<V, UpgradableReadGuard<V>>;
 
 
 /// Prints a debugging message.
@@ -115,6 +133,16 @@ impl<V> ReadGuard<V> {
         }
     }
 
+    /// Converts this guard into an already locked `FutureReadGuard` without
+    /// releasing the lock.
+    ///
+    /// The returned future can be passed to a buffer write command which
+    /// will then run under this lock instead of queuing a new request.
+    pub fn into_future_guard(mut guard: ReadGuard<V>) -> FutureReadGuard<V> {
+        let release_event = guard.release_event.take();
+        FutureGuard::locked(unsafe { extract_order_lock(guard) }, release_event)
+    }
+
     /// Triggers the release event by setting it complete.
     fn complete_release_event(guard: &mut ReadGuard<V>) {
         if let Some(ref e) = guard.release_event.take() {
@@ -159,6 +187,105 @@ impl<V> OrderGuard<V> for ReadGuard<V> {
 }
 
 
+/// Allows read access to the data contained within a lock just like a mutex
+/// guard and can be upgraded to a `WriteGuard` without releasing the lock.
+///
+/// Only one `UpgradableReadGuard` exists for a lock at any time. Plain read
+/// locks may be held alongside it.
+#[derive(Debug)]
+pub struct UpgradableReadGuard<V> {
+    order_lock: OrderLock<V>,
+    release_event: Option<Event>,
+}
+
+impl<V> UpgradableReadGuard<V> {
+    /// Returns a new `UpgradableReadGuard`.
+    fn new(order_lock: OrderLock<V>, release_event: Option<Event>) -> UpgradableReadGuard<V> {
+        print_debug(order_lock.id(), "UpgradableReadGuard::new: Upgradable read lock acquired");
+        UpgradableReadGuard {
+            order_lock,
+            release_event,
+        }
+    }
+
+    /// Returns a reference to the release event, if any.
+    ///
+    /// See `ReadGuard::release_event`.
+    pub fn release_event(guard: &UpgradableReadGuard<V>) -> Option<&Event> {
+        guard.release_event.as_ref()
+    }
+
+    /// Triggers the release event and releases the lock held by this
+    /// `UpgradableReadGuard` before returning the original `OrderLock`.
+    pub fn release(mut guard: UpgradableReadGuard<V>) -> OrderLock<V> {
+        print_debug(guard.order_lock.id(), "UpgradableReadGuard::release: Releasing read lock");
+        unsafe {
+            Self::release_components(&mut guard);
+            extract_order_lock(guard)
+        }
+    }
+
+    /// Converts this read lock into a write lock without releasing it,
+    /// returning a future which resolves to a `WriteGuard`.
+    ///
+    /// The upgrade is granted immediately if this is the only read lock,
+    /// otherwise once all other read locks have been released. Queued read
+    /// requests are not fulfilled in the meantime.
+    ///
+    /// The release event, if any, is carried over to the `WriteGuard`.
+    pub fn upgrade(mut guard: UpgradableReadGuard<V>) -> FutureUpgrade<V> {
+        print_debug(guard.order_lock.id(), "UpgradableReadGuard::upgrade: Upgrade requested");
+        let release_event = guard.release_event.take();
+        let order_lock = unsafe { extract_order_lock(guard) };
+        let upgrade_rx = unsafe { order_lock.lock.upgrade_read_lock() }.err();
+        FutureUpgrade { order_lock: Some(order_lock), upgrade_rx, release_event }
+    }
+
+    /// Triggers the release event by setting it complete.
+    fn complete_release_event(guard: &mut UpgradableReadGuard<V>) {
+        if let Some(ref e) = guard.release_event.take() {
+            if !e.is_complete().expect("UpgradableReadGuard::complete_release_event") {
+                print_debug(guard.order_lock.id(), "UpgradableReadGuard::complete_release_event: \
+                    Setting release event complete");
+                e.set_complete().expect("UpgradableReadGuard::complete_release_event");
+            }
+        }
+    }
+
+    /// Releases the lock and completes the release event.
+    unsafe fn release_components(guard: &mut UpgradableReadGuard<V>) {
+        guard.order_lock.lock.release_upgradable_read_lock();
+        Self::complete_release_event(guard);
+    }
+}
+
+impl<V> Deref for UpgradableReadGuard<V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        unsafe { &*self.order_lock.lock.as_ptr() }
+    }
+}
+
+impl<V> Drop for UpgradableReadGuard<V> {
+    fn drop(&mut self) {
+        print_debug(self.order_lock.id(), "UpgradableReadGuard::drop: Dropping and releasing \
+            UpgradableReadGuard");
+        unsafe { Self::release_components(self) }
+    }
+}
+
+impl<V> OrderGuard<V> for UpgradableReadGuard<V> {
+    fn new(order_lock: OrderLock<V>, release_event: Option<Event>) -> UpgradableReadGuard<V> {
+        UpgradableReadGuard::new(order_lock, release_event)
+    }
+
+    fn order_lock(&self) -> &OrderLock<V> {
+        &self.order_lock
+    }
+}
+
+
 /// Allows access to the data contained within just like a mutex guard.
 #[derive(Debug)]
 pub struct WriteGuard<V> {
@@ -196,6 +323,28 @@ impl<V> WriteGuard<V> {
         }
     }
 
+    /// Converts this write lock into a read lock without releasing it,
+    /// allowing queued read requests to proceed.
+    ///
+    /// The release event, if any, is carried over to the `ReadGuard`.
+    pub fn downgrade(mut guard: WriteGuard<V>) -> ReadGuard<V> {
+        print_debug(guard.order_lock.id(), "WriteGuard::downgrade: Downgrading write lock");
+        let release_event = guard.release_event.take();
+        let order_lock = unsafe { extract_order_lock(guard) };
+        unsafe { order_lock.lock.downgrade_write_lock(); }
+        ReadGuard::new(order_lock, release_event)
+    }
+
+    /// Converts this guard into an already locked `FutureWriteGuard` without
+    /// releasing the lock.
+    ///
+    /// The returned future can be passed to a buffer read command which will
+    /// then run under this lock instead of queuing a new request.
+    pub fn into_future_guard(mut guard: WriteGuard<V>) -> FutureWriteGuard<V> {
+        let release_event = guard.release_event.take();
+        FutureGuard::locked(unsafe { extract_order_lock(guard) }, release_event)
+    }
+
     /// Triggers the release event by setting it complete.
     fn complete_release_event(guard: &mut WriteGuard<V>) {
         if let Some(ref e) = guard.release_event.take() {
@@ -279,6 +428,7 @@ pub struct FutureGuard<V, G> where G: OrderGuard<V> {
     upgrade_rx: Option<Receiver<()>>,
     release_event: Option<Event>,
     stage: Stage,
+    priority: Priority,
     _guard: PhantomData<G>,
 }
 
@@ -295,10 +445,20 @@ impl<V, G> FutureGuard<V, G> where G: OrderGuard<V> {
             upgrade_rx: None,
             release_event: None,
             stage: Stage::WaitEvents,
+            priority: Priority::current(),
             _guard: PhantomData,
         }
     }
 
+    /// Returns a new `FutureGuard` for a lock which is already held.
+    fn locked(order_lock: OrderLock<V>, release_event: Option<Event>) -> FutureGuard<V, G> {
+        let (tx, rx) = oneshot::channel();
+        tx.send(()).expect("FutureGuard::locked");
+        let mut future_guard = FutureGuard::new(order_lock, rx);
+        future_guard.release_event = release_event;
+        future_guard
+    }
+
     /// Sets an event wait list.
     ///
     /// Setting a wait list will cause this `FutureGuard` to wait until
@@ -437,10 +597,29 @@ impl<V, G> FutureGuard<V, G> where G: OrderGuard<V> {
         self.release_event.as_ref()
     }
 
+    /// Returns the priority tag inherited from the task which created this
+    /// future (see `Priority::current`).
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Overrides the priority tag of this future.
+    pub fn set_priority(&mut self, priority: Priority) {
+        self.priority = priority
+    }
+
+    /// Overrides the priority tag of this future.
+    ///
+    /// See `::set_priority`.
+    pub fn with_priority(mut self, priority: Priority) -> FutureGuard<V, G> {
+        self.set_priority(priority);
+        self
+    }
+
     /// Blocks the current thread until the OpenCL command is complete and an
     /// appropriate lock can be obtained on the underlying data.
     pub fn wait(self) -> OclResult<G> {
-        <Self as Future>::wait(self)
+        futures::executor::block_on(self)
     }
 
     /// Returns a mutable pointer to the data contained within the internal
@@ -479,7 +658,7 @@ impl<V, G> FutureGuard<V, G> where G: OrderGuard<V> {
 
     /// Polls the wait events until all requisite commands have completed then
     /// polls the lock queue.
-    fn poll_wait_events(&mut self) -> OclResult<Async<G>> {
+    fn poll_wait_events(&mut self, cx: &mut Context<'_>) -> Poll<OclResult<G>> {
         debug_assert!(self.stage == Stage::WaitEvents);
         print_debug(self.order_lock.as_ref().unwrap().id(), "FutureGuard::poll_wait_events: Called");
 
@@ -489,20 +668,20 @@ impl<V, G> FutureGuard<V, G> where G: OrderGuard<V> {
             //     Polling wait_events (thread: {})...", self.order_lock.as_ref().unwrap().id(),
             //     ::std::thread::current().name().unwrap_or("<unnamed>")); }
 
-            if let Async::NotReady = wait_events.poll()? {
-                return Ok(Async::NotReady);
+            if Pin::new(wait_events).poll(cx)?.is_pending() {
+                return Poll::Pending;
             }
 
         }
 
         self.stage = Stage::LockQueue;
-        self.poll_lock()
+        self.poll_lock(cx)
     }
 
     /// Polls the lock until we have obtained a lock then polls the command
     /// event.
     #[cfg(not(feature = "async_block"))]
-    fn poll_lock(&mut self) -> OclResult<Async<G>> {
+    fn poll_lock(&mut self, cx: &mut Context<'_>) -> Poll<OclResult<G>> {
         debug_assert!(self.stage == Stage::LockQueue);
         print_debug(self.order_lock.as_ref().unwrap().id(), "FutureGuard::poll_lock: Called");
 
@@ -511,40 +690,36 @@ impl<V, G> FutureGuard<V, G> where G: OrderGuard<V> {
 
         // Check for completion of the lock rx:
         if let Some(ref mut lock_rx) = self.lock_rx {
-            match lock_rx.poll() {
-                // If the poll returns `Async::Ready`, we have been popped from
+            match Pin::new(lock_rx).poll(cx) {
+                // If the poll returns `Poll::Ready`, we have been popped from
                 // the front of the lock queue and we now have exclusive access.
-                // Otherwise, return the `NotReady`. The rx (oneshot channel) will
-                // arrange for this task to be awakened when it's ready.
-                Ok(status) => {
+                // Otherwise, return `Poll::Pending`. The rx (oneshot channel)
+                // will arrange for this task to be awakened when it's ready.
+                Poll::Ready(Ok(status)) => {
                     if PRINT_DEBUG { println!("###### [{}] FutureGuard::poll_lock: status: {:?}, \
                         (thread: {}).", self.order_lock.as_ref().unwrap().id(), status,
                         ::std::thread::current().name().unwrap_or("<unnamed>")); }
-                    match status {
-                        Async::Ready(_) => {
-                            if let Some(ref lock_event) = self.lock_event {
-                                lock_event.set_complete()?
-                            }
-                            self.stage = Stage::Command;
-                        },
-                        Async::NotReady => return Ok(Async::NotReady),
+                    if let Some(ref lock_event) = self.lock_event {
+                        lock_event.set_complete()?
                     }
+                    self.stage = Stage::Command;
                 },
-                // Err(e) => return Err(e.into()),
-                Err(e) => panic!("FutureGuard::poll_lock: {:?}", e),
+                Poll::Pending => return Poll::Pending,
+                // Poll::Ready(Err(e)) => return Poll::Ready(Err(e.into())),
+                Poll::Ready(Err(e)) => panic!("FutureGuard::poll_lock: {:?}", e),
             }
         } else {
             unreachable!();
         }
 
-        self.poll_command()
+        self.poll_command(cx)
     }
 
 
     /// Polls the lock until we have obtained a lock then polls the command
     /// event.
     #[cfg(feature = "async_block")]
-    fn poll_lock(&mut self) -> OclResult<Async<G>> {
+    fn poll_lock(&mut self, cx: &mut Context<'_>) -> Poll<OclResult<G>> {
         debug_assert!(self.stage == Stage::LockQueue);
         print_debug(self.order_lock.as_ref().unwrap().id(), "FutureGuard::poll_lock: Called");
 
@@ -552,7 +727,7 @@ impl<V, G> FutureGuard<V, G> where G: OrderGuard<V> {
         unsafe { self.order_lock.as_ref().unwrap().lock.process_queues(); }
 
         // Wait until completion of the lock rx:
-        self.lock_rx.take().wait()?;
+        futures::executor::block_on(self.lock_rx.take().unwrap())?;
 
         if let Some(ref lock_event) = self.lock_event {
             lock_event.set_complete()?
@@ -561,21 +736,21 @@ impl<V, G> FutureGuard<V, G> where G: OrderGuard<V> {
         self.stage = Stage::Command;
         // if PRINT_DEBUG { println!("###### [{}] FutureGuard::poll_lock: Moving to command stage.",
         //     self.order_lock.as_ref().unwrap().id()); }
-        return self.poll_command();
+        return self.poll_command(cx);
     }
 
     /// Polls the command event until it is complete then returns an `OrderGuard`
     /// which can be safely accessed immediately.
-    fn poll_command(&mut self) -> OclResult<Async<G>> {
+    fn poll_command(&mut self, cx: &mut Context<'_>) -> Poll<OclResult<G>> {
         debug_assert!(self.stage == Stage::Command);
         print_debug(self.order_lock.as_ref().unwrap().id(), "FutureGuard::poll_command: Called");
 
         if let Some(ref mut command_event) = self.command_event {
             print_debug(self.order_lock.as_ref().unwrap().id(), "FutureGuard::poll_command: Event exists");
 
-            if let Async::NotReady = command_event.poll()? {
+            if Pin::new(command_event).poll(cx)?.is_pending() {
                 print_debug(self.order_lock.as_ref().unwrap().id(), "FutureGuard::poll_command: Event not ready");
-                return Ok(Async::NotReady);
+                return Poll::Pending;
             }
             print_debug(self.order_lock.as_ref().unwrap().id(), "FutureGuard::poll_command: Event is ready");
         }
@@ -586,9 +761,9 @@ impl<V, G> FutureGuard<V, G> where G: OrderGuard<V> {
 
         if self.upgrade_after_command {
             self.stage = Stage::Upgrade;
-            self.poll_upgrade()
+            self.poll_upgrade(cx)
         } else {
-            Ok(Async::Ready(self.into_guard()))
+            Poll::Ready(Ok(self.into_guard()))
         }
     }
 
@@ -597,53 +772,49 @@ impl<V, G> FutureGuard<V, G> where G: OrderGuard<V> {
     /// Only used if `::upgrade_after_command` has been called.
     ///
     #[cfg(not(feature = "async_block"))]
-    fn poll_upgrade(&mut self) -> OclResult<Async<G>> {
+    fn poll_upgrade(&mut self, cx: &mut Context<'_>) -> Poll<OclResult<G>> {
         debug_assert!(self.stage == Stage::Upgrade);
         debug_assert!(self.upgrade_after_command);
         print_debug(self.order_lock.as_ref().unwrap().id(), "FutureGuard::poll_upgrade: Called");
 
         if self.upgrade_rx.is_none() {
+            if let Err(err) = self.make_upgradable() { return Poll::Ready(Err(err)); }
+
             match unsafe { self.order_lock.as_ref().unwrap().lock.upgrade_read_lock() } {
                 Ok(_) => {
                     print_debug(self.order_lock.as_ref().unwrap().id(),
                         "FutureGuard::poll_upgrade: Write lock acquired. Upgrading immediately.");
-                    Ok(Async::Ready(self.into_guard()))
+                    Poll::Ready(Ok(self.into_guard()))
                 },
                 Err(rx) => {
                     self.upgrade_rx = Some(rx);
-                    match self.upgrade_rx.as_mut().unwrap().poll() {
-                        Ok(res) => {
-                            // print_debug(self.order_lock.as_ref().unwrap().id(),
-                            //     "FutureGuard::poll_upgrade: Channel completed. Upgrading.");
-                            // Ok(res.map(|_| self.into_guard()))
-                            match res {
-                                Async::Ready(_) => {
-                                    print_debug(self.order_lock.as_ref().unwrap().id(),
-                                        "FutureGuard::poll_upgrade: Channel completed. Upgrading.");
-                                    Ok(Async::Ready(self.into_guard()))
-                                },
-                                Async::NotReady => {
-                                    print_debug(self.order_lock.as_ref().unwrap().id(),
-                                        "FutureGuard::poll_upgrade: Upgrade rx not ready.");
-                                    Ok(Async::NotReady)
-                                },
-                            }
+                    match Pin::new(self.upgrade_rx.as_mut().unwrap()).poll(cx) {
+                        Poll::Ready(Ok(())) => {
+                            print_debug(self.order_lock.as_ref().unwrap().id(),
+                                "FutureGuard::poll_upgrade: Channel completed. Upgrading.");
+                            Poll::Ready(Ok(self.into_guard()))
+                        },
+                        Poll::Pending => {
+                            print_debug(self.order_lock.as_ref().unwrap().id(),
+                                "FutureGuard::poll_upgrade: Upgrade rx not ready.");
+                            Poll::Pending
                         },
-                        // Err(e) => Err(e.into()),
-                        Err(e) => panic!("FutureGuard::poll_upgrade: {:?}", e),
+                        // Poll::Ready(Err(e)) => Poll::Ready(Err(e.into())),
+                        Poll::Ready(Err(e)) => panic!("FutureGuard::poll_upgrade: {:?}", e),
                    }
                 },
             }
         } else {
             // Check for completion of the upgrade rx:
-            match self.upgrade_rx.as_mut().unwrap().poll() {
-                Ok(status) => {
+            match Pin::new(self.upgrade_rx.as_mut().unwrap()).poll(cx) {
+                Poll::Ready(Ok(())) => {
                     print_debug(self.order_lock.as_ref().unwrap().id(),
-                        &format!("FutureGuard::poll_upgrade: Status: {:?}", status));
-                    Ok(status.map(|_| self.into_guard()))
+                        "FutureGuard::poll_upgrade: Channel completed. Upgrading.");
+                    Poll::Ready(Ok(self.into_guard()))
                 },
-                // Err(e) => Err(e.into()),
-                Err(e) => panic!("FutureGuard::poll_upgrade: {:?}", e),
+                Poll::Pending => Poll::Pending,
+                // Poll::Ready(Err(e)) => Poll::Ready(Err(e.into())),
+                Poll::Ready(Err(e)) => panic!("FutureGuard::poll_upgrade: {:?}", e),
             }
         }
     }
@@ -653,21 +824,39 @@ impl<V, G> FutureGuard<V, G> where G: OrderGuard<V> {
     /// Only used if `::upgrade_after_command` has been called.
     ///
     #[cfg(feature = "async_block")]
-    fn poll_upgrade(&mut self) -> OclResult<Async<G>> {
+    fn poll_upgrade(&mut self, _cx: &mut Context<'_>) -> Poll<OclResult<G>> {
         debug_assert!(self.stage == Stage::Upgrade);
         debug_assert!(self.upgrade_after_command);
         print_debug(self.order_lock.as_ref().unwrap().id(), "FutureGuard::poll_upgrade: Called");
 
+        self.make_upgradable()?;
+
         match unsafe { self.order_lock.as_ref().unwrap().lock.upgrade_read_lock() } {
-            Ok(_) => Ok(Async::Ready(self.into_guard())),
+            Ok(_) => Poll::Ready(Ok(self.into_guard())),
             Err(rx) => {
                 self.upgrade_rx = Some(rx);
-                self.upgrade_rx.take().unwrap().wait()?;
-                Ok(Async::Ready(self.into_guard()))
+                futures::executor::block_on(self.upgrade_rx.take().unwrap())?;
+                Poll::Ready(Ok(self.into_guard()))
             }
         }
     }
 
+    /// Makes the read lock held by this `FutureGuard` upgradable.
+    ///
+    /// Only used if `::upgrade_after_command` has been called. If another
+    /// read lock is already upgradable, the upgrade could not be performed
+    /// without releasing the lock, so the read lock is released instead and
+    /// an error is returned.
+    fn make_upgradable(&mut self) -> OclResult<()> {
+        if unsafe { self.order_lock.as_ref().unwrap().lock.make_read_lock_upgradable() } {
+            Ok(())
+        } else {
+            drop(ReadGuard::new(self.order_lock.take().unwrap(), self.release_event.take()));
+            Err("FutureGuard::poll_upgrade: Unable to upgrade the read lock: Another read \
+                lock is already upgradable.".into())
+        }
+    }
+
     /// Resolves this `FutureGuard` into the appropriate result guard.
     fn into_guard(&mut self) -> G {
         print_debug(self.order_lock.as_ref().unwrap().id(), "FutureGuard::into_guard: All polling complete");
@@ -676,24 +865,26 @@ impl<V, G> FutureGuard<V, G> where G: OrderGuard<V> {
 }
 
 impl<V, G> Future for FutureGuard<V, G> where G: OrderGuard<V> {
-    type Item = G;
-    type Error = OclError;
+    type Output = OclResult<G>;
 
     #[inline]
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        if self.order_lock.is_some() {
-            match self.stage {
-                Stage::WaitEvents => self.poll_wait_events(),
-                Stage::LockQueue => self.poll_lock(),
-                Stage::Command => self.poll_command(),
-                Stage::Upgrade => self.poll_upgrade(),
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.order_lock.is_some() {
+            match this.stage {
+                Stage::WaitEvents => this.poll_wait_events(cx),
+                Stage::LockQueue => this.poll_lock(cx),
+                Stage::Command => this.poll_command(cx),
+                Stage::Upgrade => this.poll_upgrade(cx),
             }
         } else {
-            Err("FutureGuard::poll: Task already completed.".into())
+            Poll::Ready(Err("FutureGuard::poll: Task already completed.".into()))
         }
     }
 }
 
+impl<V, G> Unpin for FutureGuard<V, G> where G: OrderGuard<V> {}
+
 impl<V, G> Drop for FutureGuard<V, G> where G: OrderGuard<V> {
     /// Drops this FutureGuard.
     ///
@@ -717,21 +908,13 @@ impl<V, G> Drop for FutureGuard<V, G> where G: OrderGuard<V> {
         if let Some(ref mut lock_rx) = self.lock_rx {
             lock_rx.close();
 
-            match lock_rx.poll() {
-                Ok(status) => {
-                    match status {
-                        Async::Ready(_) => {
-                            if let Some(ref lock_event) = self.lock_event {
-                                lock_event.set_complete().ok();
-                            }
-                            // Drop and release lock.
-                            let _guard = G::new(self.order_lock.take().unwrap(),
-                                self.release_event.take());
-                        },
-                        Async::NotReady => (),
-                    }
-                },
-                Err(_) => (),
+            if let Ok(Some(())) = lock_rx.try_recv() {
+                if let Some(ref lock_event) = self.lock_event {
+                    lock_event.set_complete().ok();
+                }
+                // Drop and release lock.
+                let _guard = G::new(self.order_lock.take().unwrap(),
+                    self.release_event.take());
             }
         }
         if let Some(ref ccev) = self.command_event {
@@ -749,6 +932,11 @@ impl<V, G> Drop for FutureGuard<V, G> where G: OrderGuard<V> {
 
 // a.k.a. FutureRead<V>
 impl<V> FutureGuard<V, ReadGuard<V>> {
+    /// Converts this future into one which upgrades its read lock to a write
+    /// lock, without releasing it, once the command has completed.
+    ///
+    /// The upgrade resolves to an error (and the read lock is released) if
+    /// another read lock is already upgradable at that point.
     pub fn upgrade_after_command(self) -> FutureGuard<V, WriteGuard<V>> {
         use std::ptr::read;
 
@@ -763,6 +951,7 @@ impl<V> FutureGuard<V, ReadGuard<V>> {
                 command_event: read(&self.command_event),
                 release_event: read(&self.release_event),
                 stage: read(&self.stage),
+                priority: self.priority,
                 _guard: PhantomData,
             }
         };
@@ -774,6 +963,90 @@ impl<V> FutureGuard<V, ReadGuard<V>> {
 }
 
 
+/// A future which resolves to a `WriteGuard` once an upgradable read lock
+/// has been upgraded.
+///
+/// Created by `UpgradableReadGuard::upgrade`.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct FutureUpgrade<V> {
+    order_lock: Option<OrderLock<V>>,
+    upgrade_rx: Option<Receiver<()>>,
+    release_event: Option<Event>,
+}
+
+impl<V> FutureUpgrade<V> {
+    /// Blocks the current thread until the upgrade has been granted.
+    pub fn wait(self) -> OclResult<WriteGuard<V>> {
+        futures::executor::block_on(self)
+    }
+
+    /// Resolves this `FutureUpgrade` into a `WriteGuard`.
+    fn take_guard(&mut self) -> OclResult<WriteGuard<V>> {
+        match self.order_lock.take() {
+            Some(order_lock) => Ok(WriteGuard::new(order_lock, self.release_event.take())),
+            None => Err("FutureUpgrade::poll: Task already completed.".into()),
+        }
+    }
+}
+
+#[cfg(not(feature = "async_block"))]
+impl<V> Future for FutureUpgrade<V> {
+    type Output = OclResult<WriteGuard<V>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Some(ref mut upgrade_rx) = this.upgrade_rx {
+            if Pin::new(upgrade_rx).poll(cx)?.is_pending() {
+                return Poll::Pending;
+            }
+        }
+        this.upgrade_rx = None;
+        Poll::Ready(this.take_guard())
+    }
+}
+
+/// Blocking implementation.
+#[cfg(feature = "async_block")]
+impl<V> Future for FutureUpgrade<V> {
+    type Output = OclResult<WriteGuard<V>>;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Some(upgrade_rx) = this.upgrade_rx.take() {
+            futures::executor::block_on(upgrade_rx)?;
+        }
+        Poll::Ready(this.take_guard())
+    }
+}
+
+impl<V> Unpin for FutureUpgrade<V> {}
+
+impl<V> Drop for FutureUpgrade<V> {
+    /// Releases the write lock if the upgrade has already been granted,
+    /// otherwise withdraws the upgrade request and releases the upgradable
+    /// read lock.
+    fn drop(&mut self) {
+        let order_lock = match self.order_lock.take() {
+            Some(order_lock) => order_lock,
+            None => return,
+        };
+        let is_granted = match self.upgrade_rx {
+            Some(ref mut upgrade_rx) => {
+                upgrade_rx.close();
+                matches!(upgrade_rx.try_recv(), Ok(Some(())))
+            },
+            None => true,
+        };
+        if is_granted {
+            drop(WriteGuard::new(order_lock, self.release_event.take()));
+        } else {
+            drop(UpgradableReadGuard::new(order_lock, self.release_event.take()));
+        }
+    }
+}
+
+
 /// A lock with conserved global order which interoperates with OpenCL events
 /// and Rust futures to provide exclusive access to data.
 ///
@@ -811,6 +1084,20 @@ impl<V> OrderLock<V> {
         FutureGuard::new(self.into(), rx)
     }
 
+    /// Returns a new `FutureGuard` which will resolve into an
+    /// `UpgradableReadGuard`.
+    ///
+    /// The request is fulfilled alongside read requests but not while
+    /// another upgradable read lock is held.
+    pub fn upgradable_read(self) -> FutureGuard<V, UpgradableReadGuard<V>> {
+        print_debug(self.id(), "OrderLock::upgradable_read: Upgradable read lock requested");
+        let (tx, rx) = oneshot::channel();
+        unsafe {
+            self.lock.enqueue_lock_request(QrwRequest::new(tx, RequestKind::UpgradableRead));
+        }
+        FutureGuard::new(self, rx)
+    }
+
     /// Returns a new `FutureGuard` which will resolve into a a `OrderGuard`.
     pub fn write(self) -> FutureGuard<V, WriteGuard<V>> {
         print_debug(self.id(), "OrderLock::write: Write lock requested");
@@ -840,12 +1127,6 @@ impl<V> OrderLock<V> {
     }
 }
 
-impl<V> From<QrwLock<V>> for OrderLock<V> {
-    fn from(q: QrwLock<V>) -> OrderLock<V> {
-        OrderLock { lock: q }
-    }
-}
-
 impl<V> From<V> for OrderLock<V> {
     fn from(vec: V) -> OrderLock<V> {
         OrderLock { lock: QrwLock::new(vec) }
@@ -0,0 +1,362 @@
+//! A queue-backed read/write data lock.
+//!
+//! Adapted from the `qrw_lock` module of the `qutex` crate (the only parts
+//! used by `OrderLock`), with lock requests completed through
+//! `futures::channel::oneshot`.
+//!
+//! As with any queue-backed system, deadlocks must be carefully avoided when
+//! interoperating with other queues.
+
+use std::cell::UnsafeCell;
+use std::sync::Arc;
+use std::sync::atomic::{fence, AtomicUsize};
+use std::sync::atomic::Ordering::{Acquire, SeqCst};
+use std::thread;
+use crossbeam_queue::SegQueue;
+use futures::channel::oneshot::{self, Receiver, Sender};
+
+
+const READ_COUNT_MASK: usize = 0x00FF_FFFF;
+const WRITE_LOCKED: usize = 1 << 24;
+const CONTENDED: usize = 1 << 25;
+const UPGRADABLE: usize = 1 << 26;
+const UPGRADE_PENDING: usize = 1 << 27;
+
+
+/// The type of a lock request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RequestKind {
+    Read,
+    /// A read lock which can later be upgraded to a write lock without being
+    /// released. Only one may be held at a time.
+    UpgradableRead,
+    Write,
+}
+
+
+/// A request to lock the lock for either read or write access.
+#[derive(Debug)]
+pub(crate) struct QrwRequest {
+    tx: Sender<()>,
+    kind: RequestKind,
+}
+
+impl QrwRequest {
+    /// Returns a new `QrwRequest`.
+    pub fn new(tx: Sender<()>, kind: RequestKind) -> QrwRequest {
+        QrwRequest { tx, kind }
+    }
+}
+
+
+/// The guts of a `QrwLock`.
+#[derive(Debug)]
+struct Inner<T> {
+    state: AtomicUsize,
+    cell: UnsafeCell<T>,
+    queue: SegQueue<QrwRequest>,
+    tip: UnsafeCell<Option<QrwRequest>>,
+    upgrade_tx: UnsafeCell<Option<Sender<()>>>,
+}
+
+unsafe impl<T: Send> Send for Inner<T> {}
+unsafe impl<T: Send + Sync> Sync for Inner<T> {}
+
+
+/// A queue-backed read/write data lock.
+///
+/// Requests are fulfilled in the order they are enqueued. Consecutive read
+/// requests are fulfilled together.
+///
+/// At most one read lock at a time may be upgradable. Because it is never
+/// released while being upgraded, no other writer can intervene.
+#[derive(Debug)]
+pub(crate) struct QrwLock<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> QrwLock<T> {
+    /// Creates and returns a new `QrwLock`.
+    #[inline]
+    pub fn new(val: T) -> QrwLock<T> {
+        QrwLock {
+            inner: Arc::new(Inner {
+                state: AtomicUsize::new(0),
+                cell: UnsafeCell::new(val),
+                queue: SegQueue::new(),
+                tip: UnsafeCell::new(None),
+                upgrade_tx: UnsafeCell::new(None),
+            }),
+        }
+    }
+
+    /// Pushes a lock request onto the queue.
+    ///
+    /// The request is fulfilled (its channel completed) during a later call
+    /// to `::process_queues`.
+    #[inline]
+    pub unsafe fn enqueue_lock_request(&self, req: QrwRequest) {
+        self.inner.queue.push(req);
+    }
+
+    /// Returns a pointer to the inner value.
+    #[inline]
+    pub fn as_ptr(&self) -> *const T {
+        self.inner.cell.get()
+    }
+
+    /// Returns a mutable pointer to the inner value.
+    #[inline]
+    pub fn as_mut_ptr(&self) -> *mut T {
+        self.inner.cell.get()
+    }
+
+    /// Pops the next lock request from the queue, storing the one after it
+    /// in `tip`.
+    ///
+    /// The `CONTENDED` bit must be set while calling.
+    fn pop_request(&self) -> Option<QrwRequest> {
+        debug_assert_eq!(self.inner.state.load(Acquire) & CONTENDED, CONTENDED);
+
+        unsafe {
+            ::std::mem::replace(&mut *self.inner.tip.get(), self.inner.queue.pop()).or_else(|| {
+                if (*self.inner.tip.get()).is_some() {
+                    self.pop_request()
+                } else {
+                    None
+                }
+            })
+        }
+    }
+
+    /// Returns the kind of the next lock request.
+    ///
+    /// The `CONTENDED` bit must be set while calling.
+    fn peek_request_kind(&self) -> Option<RequestKind> {
+        debug_assert_eq!(self.inner.state.load(Acquire) & CONTENDED, CONTENDED);
+
+        unsafe {
+            if (*self.inner.tip.get()).is_none() {
+                *self.inner.tip.get() = self.inner.queue.pop();
+            }
+            (*self.inner.tip.get()).as_ref().map(|req| req.kind)
+        }
+    }
+
+    /// Fulfills lock requests (all consecutive read requests or a single
+    /// write request) and returns the new lock state.
+    ///
+    /// Requests whose receivers have been dropped are skipped. Fulfillment
+    /// stops at a write request while read locked, at an upgradable read
+    /// request while another upgradable read lock is held, and while an
+    /// upgrade is pending.
+    fn fulfill_request(&self, mut state: usize) -> usize {
+        debug_assert_eq!(self.inner.state.load(Acquire) & CONTENDED, CONTENDED);
+        debug_assert_eq!(state & WRITE_LOCKED, 0);
+
+        while state & UPGRADE_PENDING == 0 {
+            let kind = match self.peek_request_kind() {
+                Some(RequestKind::Write) if state != 0 => break,
+                Some(RequestKind::UpgradableRead) if state & UPGRADABLE != 0 => break,
+                Some(kind) => kind,
+                None => break,
+            };
+
+            let req = self.pop_request().expect("QrwLock::fulfill_request: No request.");
+            debug_assert_eq!(req.kind, kind);
+
+            if req.tx.send(()).is_ok() {
+                match kind {
+                    RequestKind::Read => state += 1,
+                    RequestKind::UpgradableRead => state = (state + 1) | UPGRADABLE,
+                    RequestKind::Write => return WRITE_LOCKED,
+                }
+            }
+        }
+
+        state
+    }
+
+    /// Grants a pending upgrade if the upgradable read lock is the only
+    /// read lock remaining and returns the new lock state.
+    fn grant_upgrade(&self, state: usize) -> usize {
+        debug_assert_eq!(self.inner.state.load(Acquire) & CONTENDED, CONTENDED);
+        debug_assert_eq!(state & (UPGRADABLE | UPGRADE_PENDING), UPGRADABLE | UPGRADE_PENDING);
+
+        if state & READ_COUNT_MASK != 1 { return state; }
+
+        let tx = unsafe { (*self.inner.upgrade_tx.get()).take() }
+            .expect("QrwLock::grant_upgrade: No upgrade request.");
+
+        if tx.send(()).is_ok() {
+            WRITE_LOCKED
+        } else {
+            // The upgrade was withdrawn and the upgradable read lock will be
+            // released by its holder:
+            self.fulfill_request(state & !UPGRADE_PENDING)
+        }
+    }
+
+    /// Acquires exclusive access to the lock state, setting the `CONTENDED`
+    /// bit, and returns the prior state (without the `CONTENDED` bit).
+    ///
+    /// The caller must store a new state (which clears the bit) when done.
+    #[inline(always)]
+    fn contend(&self) -> usize {
+        let mut spins: u32 = 0;
+
+        loop {
+            let state = self.inner.state.fetch_or(CONTENDED, SeqCst);
+            if state & CONTENDED != 0 {
+                if spins >= 16 {
+                    thread::yield_now();
+                } else {
+                    for _ in 0..(2 << spins) {
+                        fence(SeqCst);
+                    }
+                }
+                spins += 1;
+            } else {
+                return state;
+            }
+        }
+    }
+
+    /// Restores `state`, releasing contention so that the lock does not hang,
+    /// then panics.
+    ///
+    /// Called when `state`, as returned by `contend`, is impossible for the
+    /// operation being performed.
+    fn invalid_state(&self, operation: &str, state: usize) -> ! {
+        self.inner.state.store(state, SeqCst);
+        panic!("QrwLock::{}: Invalid lock state: {:#x}.", operation, state);
+    }
+
+    /// Pops the next lock request from the queue if possible.
+    pub unsafe fn process_queues(&self) {
+        match self.contend() {
+            // Write locked, unset CONTENDED flag:
+            WRITE_LOCKED => self.inner.state.store(WRITE_LOCKED, SeqCst),
+            // Upgrade pending:
+            state if state & UPGRADE_PENDING != 0 => {
+                let new_state = self.grant_upgrade(state);
+                self.inner.state.store(new_state, SeqCst);
+            },
+            // Unlocked, read locked, or already being processed:
+            state => {
+                let new_state = self.fulfill_request(state);
+                self.inner.state.store(new_state, SeqCst);
+            },
+        }
+    }
+
+    /// Converts the upgradable read lock into a write lock without releasing
+    /// it.
+    ///
+    /// Returns `Ok(())` if it is the only read lock and was upgraded
+    /// immediately, otherwise returns a receiver which will complete once
+    /// all other read locks have been released. No further read requests are
+    /// fulfilled in the meantime.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if no upgradable read lock is held or if its upgrade is already
+    /// pending.
+    pub unsafe fn upgrade_read_lock(&self) -> Result<(), Receiver<()>> {
+        let state = self.contend();
+
+        if state & UPGRADABLE == 0 || state & UPGRADE_PENDING != 0 {
+            self.inner.state.store(state, SeqCst);
+            panic!("Unable to upgrade this QrwLock: no upgradable read lock.");
+        }
+
+        if state & READ_COUNT_MASK == 1 {
+            self.inner.state.store(WRITE_LOCKED, SeqCst);
+            Ok(())
+        } else {
+            let (tx, rx) = oneshot::channel();
+            *self.inner.upgrade_tx.get() = Some(tx);
+            self.inner.state.store(state | UPGRADE_PENDING, SeqCst);
+            Err(rx)
+        }
+    }
+
+    /// Makes a plain read lock upgradable, returning `false` if another read
+    /// lock already is.
+    pub unsafe fn make_read_lock_upgradable(&self) -> bool {
+        let state = self.contend();
+        debug_assert!(state & READ_COUNT_MASK != 0 && state & WRITE_LOCKED == 0);
+
+        if state & UPGRADABLE == 0 {
+            self.inner.state.store(state | UPGRADABLE, SeqCst);
+            true
+        } else {
+            self.inner.state.store(state, SeqCst);
+            false
+        }
+    }
+
+    /// Decreases the reader count by one and unparks the next requester task
+    /// in the queue if possible.
+    pub unsafe fn release_read_lock(&self) {
+        debug_assert!(self.inner.state.load(SeqCst) & READ_COUNT_MASK != 0);
+
+        match self.contend() {
+            state if state & READ_COUNT_MASK == 0 || state & WRITE_LOCKED != 0 => {
+                self.invalid_state("release_read_lock", state)
+            },
+            state => {
+                self.inner.state.store(state - 1, SeqCst);
+                self.process_queues();
+            },
+        }
+    }
+
+    /// Releases the upgradable read lock, withdrawing its upgrade if pending,
+    /// and unparks the next requester task in the queue if possible.
+    pub unsafe fn release_upgradable_read_lock(&self) {
+        let state = self.contend();
+        debug_assert!(state & UPGRADABLE != 0 && state & READ_COUNT_MASK != 0);
+
+        *self.inner.upgrade_tx.get() = None;
+        self.inner.state.store((state - 1) & !(UPGRADABLE | UPGRADE_PENDING), SeqCst);
+        self.process_queues();
+    }
+
+    /// Converts a write lock into a single read lock and fulfills any read
+    /// requests at the front of the queue.
+    pub unsafe fn downgrade_write_lock(&self) {
+        debug_assert!(self.inner.state.load(SeqCst) & READ_COUNT_MASK == 0);
+
+        match self.contend() {
+            WRITE_LOCKED => {
+                self.inner.state.store(1, SeqCst);
+                self.process_queues();
+            },
+            state => self.invalid_state("downgrade_write_lock", state),
+        }
+    }
+
+    /// Unlocks this (write) lock and unparks the next requester task in the
+    /// queue if possible.
+    pub unsafe fn release_write_lock(&self) {
+        debug_assert!(self.inner.state.load(SeqCst) & READ_COUNT_MASK == 0);
+
+        match self.contend() {
+            WRITE_LOCKED => {
+                self.inner.state.store(0, SeqCst);
+                self.process_queues();
+            },
+            state => self.invalid_state("release_write_lock", state),
+        }
+    }
+}
+
+impl<T> Clone for QrwLock<T> {
+    #[inline]
+    fn clone(&self) -> QrwLock<T> {
+        QrwLock {
+            inner: self.inner.clone(),
+        }
+    }
+}
@@ -8,8 +8,7 @@
 
 
 use std::ops::{Deref, DerefMut};
-use crate::r#async::{OrderLock, FutureGuard, ReadGuard, WriteGuard};
-use crate::r#async::qutex::QrwLock;
+use crate::r#async::{OrderLock, FutureGuard, ReadGuard, WriteGuard, UpgradableReadGuard};
 
 
 // pub type FutureRwVecGuard<T, G> = FutureGuard<Vec<T>, G>;
@@ -52,6 +51,14 @@ impl<T> RwVec<T> {
 
     }
 
+    /// Returns a new `FutureGuard` which will resolve into an
+    /// `UpgradableReadGuard`.
+    ///
+    /// See `OrderLock::upgradable_read`.
+    pub fn upgradable_read(self) -> FutureGuard<Vec<T>, UpgradableReadGuard<Vec<T>>> {
+        self.lock.upgradable_read()
+    }
+
     /// Returns a new `FutureRwGuard` which will resolve into a a `RwGuard`.
     pub fn write(self) -> FutureGuard<Vec<T>, WriteGuard<Vec<T>>> {
         self.lock.write()
@@ -94,12 +101,6 @@ impl<T> RwVec<T> {
     }
 }
 
-impl<T> From<QrwLock<Vec<T>>> for RwVec<T> {
-    fn from(q: QrwLock<Vec<T>>) -> RwVec<T> {
-        RwVec { lock: OrderLock::from(q) }
-    }
-}
-
 impl<T> From<Vec<T>> for RwVec<T> {
     fn from(vec: Vec<T>) -> RwVec<T> {
         RwVec { lock: OrderLock::from(vec) }
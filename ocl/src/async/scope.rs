@@ -0,0 +1,163 @@
+use std::marker::PhantomData;
+use std::sync::Mutex;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use crate::core::OclPrm;
+use crate::error::{Result as OclResult};
+use crate::Event;
+#[cfg(not(feature = "async_block"))]
+use crate::standard::completion;
+
+
+/// Creates a scope within which non-blocking commands may borrow host
+/// memory, such as a slice read into with `BufferReadCmd::enq_async_scoped`.
+///
+/// Before returning (or unwinding), blocks until every command enqueued
+/// within the scope has completed. Borrowed memory therefore outlives the
+/// commands which use it regardless of what becomes of their futures, which
+/// may be awaited (or dropped, or leaked) freely within `f`.
+///
+/// Scoped reads use the regular read builder, `Buffer::read`, so that they
+/// support the same offsets, queues, and events as any other read. Only the
+/// final call differs: `::enq_async_scoped` takes the scope, which a
+/// separate `read_scoped` builder would need anyway, in place of `::enq`.
+///
+/// ```rust,ignore
+/// let mut vec = vec![0.0f32; buffer.len()];
+/// ocl::async::scope(|scope| {
+///     let future_read = buffer.read(&mut vec[..]).enq_async_scoped(scope)?;
+///     // ... do something else in the meantime ...
+///     let slice = future_read.wait()?;
+///     Ok(())
+/// })?;
+/// ```
+///
+/// [UNSTABLE]: This function may be renamed or otherwise changed at any time.
+pub fn scope<'env, F, R>(f: F) -> R where F: FnOnce(&TransferScope<'env>) -> R {
+    let scope = TransferScope { events: Mutex::new(Vec::new()), env: PhantomData };
+    f(&scope)
+}
+
+
+/// A scope within which non-blocking commands may borrow host memory for
+/// `'env`.
+///
+/// Created by `scope`, which waits for the commands registered with it
+/// before returning.
+///
+/// [UNSTABLE]: This type's methods may be renamed or otherwise changed at any time.
+#[derive(Debug)]
+pub struct TransferScope<'env> {
+    events: Mutex<Vec<Event>>,
+    // Invariant in `'env` so that borrows can not be shortened to within the
+    // scope.
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'env> TransferScope<'env> {
+    /// Registers the event of a command which uses memory borrowed for
+    /// `'env`, to be waited on before the scope ends.
+    ///
+    /// Events whose status can not be queried are kept and waited on.
+    pub(crate) fn register(&self, event: Event) {
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        // Prune completed events so long-lived scopes do not accumulate them:
+        events.retain(|event| !event.is_complete().unwrap_or(false));
+        events.push(event);
+    }
+
+    /// Returns the number of commands registered with this scope which have
+    /// not yet completed (or whose status can not be queried).
+    pub fn pending(&self) -> usize {
+        let events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        events.iter().filter(|event| !event.is_complete().unwrap_or(false)).count()
+    }
+}
+
+impl<'env> Drop for TransferScope<'env> {
+    /// Blocks until every command registered with this scope has completed.
+    fn drop(&mut self) {
+        let events = self.events.get_mut().unwrap_or_else(|e| e.into_inner());
+        for event in events.drain(..) {
+            event.wait_for().ok();
+        }
+    }
+}
+
+
+/// A future which resolves to the destination slice of a non-blocking read
+/// command as soon as that command completes.
+///
+/// Returned by `BufferReadCmd::enq_async_scoped`. The enclosing
+/// `TransferScope` ensures the slice outlives the read, so dropping this
+/// future before it resolves never blocks.
+///
+/// [UNSTABLE]: This type's methods may be renamed or otherwise changed at any time.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct FutureScopedRead<'env, T: OclPrm> {
+    dst: Option<&'env mut [T]>,
+    read_event: Event,
+}
+
+impl<'env, T: OclPrm> FutureScopedRead<'env, T> {
+    /// Returns a new `FutureScopedRead`, registering `read_event` with
+    /// `scope`.
+    ///
+    /// ## Safety
+    ///
+    /// `read_event` must be the event of a command which writes to `dst`.
+    pub(crate) unsafe fn new(dst: &'env mut [T], read_event: Event, scope: &TransferScope<'env>)
+            -> FutureScopedRead<'env, T> {
+        scope.register(read_event.clone());
+        FutureScopedRead { dst: Some(dst), read_event }
+    }
+
+    /// Returns the event associated with the read command.
+    #[inline]
+    pub fn read_event(&self) -> &Event {
+        &self.read_event
+    }
+
+    /// Blocks the current thread until the read command is complete.
+    pub fn wait(self) -> OclResult<&'env mut [T]> {
+        futures::executor::block_on(self)
+    }
+
+    /// Returns the destination slice.
+    fn take_dst(&mut self) -> OclResult<&'env mut [T]> {
+        self.dst.take().ok_or_else(|| "FutureScopedRead::poll: Already resolved.".into())
+    }
+}
+
+#[cfg(not(feature = "async_block"))]
+impl<'env, T: OclPrm> Future for FutureScopedRead<'env, T> {
+    type Output = OclResult<&'env mut [T]>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        match this.read_event.is_complete() {
+            Ok(true) => Poll::Ready(this.take_dst()),
+            Ok(false) => {
+                completion::register(&this.read_event, cx.waker())?;
+                Poll::Pending
+            },
+            Err(err) => Poll::Ready(Err(err.into())),
+        }
+    }
+}
+
+/// Blocking implementation.
+#[cfg(feature = "async_block")]
+impl<'env, T: OclPrm> Future for FutureScopedRead<'env, T> {
+    type Output = OclResult<&'env mut [T]>;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        this.read_event.wait_for()?;
+        Poll::Ready(this.take_dst())
+    }
+}
+
+impl<'env, T: OclPrm> Unpin for FutureScopedRead<'env, T> {}
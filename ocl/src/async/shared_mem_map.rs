@@ -0,0 +1,184 @@
+use std::ops::Deref;
+use std::sync::Arc;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use crate::core::{OclPrm, ClNullEventPtr};
+use crate::r#async::{MemMap, FutureMemMap, DropPolicy};
+use crate::error::{Result as OclResult};
+use crate::{Event, Queue, EventList};
+
+
+/// A shareable, read-only view of memory mapped by `clEnqueueMap{...}`.
+///
+/// Clones refer to the same mapped region and may be sent to and read from
+/// multiple threads concurrently. The region is unmapped when the last clone
+/// is dropped.
+///
+/// [UNSTABLE]: This type's methods may be renamed or otherwise changed at any time.
+#[derive(Debug)]
+pub struct SharedMemMap<T: OclPrm> {
+    mem_map: Arc<MemMap<T>>,
+}
+
+impl<T: OclPrm> SharedMemMap<T> {
+    /// Returns a new `SharedMemMap`.
+    pub fn new(mem_map: MemMap<T>) -> SharedMemMap<T> {
+        SharedMemMap { mem_map: Arc::new(mem_map) }
+    }
+
+    /// Returns the number of guards currently sharing this mapped region.
+    #[inline]
+    pub fn guard_count(&self) -> usize {
+        Arc::strong_count(&self.mem_map)
+    }
+
+    /// Returns a reference to the unmap target event if it has been set.
+    #[inline]
+    pub fn unmap_event(&self) -> Option<&Event> {
+        self.mem_map.unmap_event()
+    }
+
+    /// Returns a pointer to the host mapped memory.
+    #[inline]
+    pub fn as_ptr(&self) -> *const T {
+        self.mem_map.as_ptr()
+    }
+
+    /// Returns a reference to the queue used for the unmap command.
+    #[inline]
+    pub fn queue(&self) -> &Queue {
+        self.mem_map.queue()
+    }
+
+    /// Returns the inner `MemMap` if this is the only remaining guard,
+    /// otherwise returns this guard unchanged.
+    pub fn try_unwrap(self) -> Result<MemMap<T>, SharedMemMap<T>> {
+        Arc::try_unwrap(self.mem_map).map_err(|mem_map| SharedMemMap { mem_map })
+    }
+}
+
+impl<T: OclPrm> Clone for SharedMemMap<T> {
+    #[inline]
+    fn clone(&self) -> SharedMemMap<T> {
+        SharedMemMap { mem_map: self.mem_map.clone() }
+    }
+}
+
+impl<T: OclPrm> Deref for SharedMemMap<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.mem_map
+    }
+}
+
+impl<T: OclPrm> From<MemMap<T>> for SharedMemMap<T> {
+    fn from(mem_map: MemMap<T>) -> SharedMemMap<T> {
+        SharedMemMap::new(mem_map)
+    }
+}
+
+// Guards only ever read the mapped region. The unmap command is enqueued
+// when the last guard drops, at which point no other access is possible.
+unsafe impl<T: OclPrm> Send for SharedMemMap<T> {}
+unsafe impl<T: OclPrm> Sync for SharedMemMap<T> {}
+
+
+/// A future which resolves to a `SharedMemMap` as soon as its creating
+/// command completes.
+///
+/// [UNSTABLE]: This type's methods may be renamed or otherwise changed at any time.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct FutureSharedMemMap<T: OclPrm> {
+    future_mem_map: FutureMemMap<T>,
+}
+
+impl<T: OclPrm> FutureSharedMemMap<T> {
+    /// Returns a new `FutureSharedMemMap`.
+    pub fn new(future_mem_map: FutureMemMap<T>) -> FutureSharedMemMap<T> {
+        FutureSharedMemMap { future_mem_map }
+    }
+
+    /// Set an event wait list for the unmap command.
+    ///
+    /// See `FutureMemMap::set_unmap_wait_events`.
+    pub fn set_unmap_wait_events<El>(&mut self, wait_events: El) where El: Into<EventList> {
+        self.future_mem_map.set_unmap_wait_events(wait_events)
+    }
+
+    /// Set an event wait list for the unmap command.
+    ///
+    /// See `FutureMemMap::set_unmap_wait_events`.
+    pub fn ewait_unmap<L: Into<EventList>>(mut self, wait_events: L) -> FutureSharedMemMap<T> {
+        self.set_unmap_wait_events(wait_events);
+        self
+    }
+
+    /// Create an event which will be triggered (set complete) after this
+    /// future resolves into a `SharedMemMap` **and** after the last clone of
+    /// that `SharedMemMap` is dropped.
+    ///
+    /// See `FutureMemMap::create_unmap_event`.
+    pub fn create_unmap_event(&mut self) -> OclResult<&mut Event> {
+        self.future_mem_map.create_unmap_event()
+    }
+
+    /// Specifies an event which will be triggered (set complete) after this
+    /// future resolves into a `SharedMemMap` **and** after the last clone of
+    /// that `SharedMemMap` is dropped.
+    ///
+    /// See `::create_unmap_event`.
+    pub fn enew_unmap<En>(self, enew: En) -> FutureSharedMemMap<T>
+            where En: ClNullEventPtr {
+        FutureSharedMemMap { future_mem_map: self.future_mem_map.enew_unmap(enew) }
+    }
+
+    /// Specifies the queue to be used for the unmap command.
+    pub fn with_unmap_queue(self, queue: Queue) -> FutureSharedMemMap<T> {
+        FutureSharedMemMap { future_mem_map: self.future_mem_map.with_unmap_queue(queue) }
+    }
+
+    /// Returns the unmap event if it has been created.
+    #[inline]
+    pub fn unmap_event(&self) -> Option<&Event> {
+        self.future_mem_map.unmap_event()
+    }
+
+    /// Specifies the policy followed if this future is dropped before
+    /// resolving.
+    ///
+    /// See `FutureMemMap::set_drop_policy`.
+    pub fn with_drop_policy(self, drop_policy: DropPolicy) -> FutureSharedMemMap<T> {
+        FutureSharedMemMap { future_mem_map: self.future_mem_map.with_drop_policy(drop_policy) }
+    }
+
+    /// Abandons this future without waiting for the map command.
+    ///
+    /// See `FutureMemMap::cancel`.
+    pub fn cancel(self) -> OclResult<Event> {
+        self.future_mem_map.cancel()
+    }
+
+    /// Blocks the current thread until the OpenCL command is complete.
+    pub fn wait(self) -> OclResult<SharedMemMap<T>> {
+        futures::executor::block_on(self)
+    }
+}
+
+impl<T> Future for FutureSharedMemMap<T> where T: OclPrm + 'static {
+    type Output = OclResult<SharedMemMap<T>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.future_mem_map).poll(cx).map(|res| res.map(SharedMemMap::new))
+    }
+}
+
+impl<T: OclPrm> From<FutureMemMap<T>> for FutureSharedMemMap<T> {
+    fn from(future_mem_map: FutureMemMap<T>) -> FutureSharedMemMap<T> {
+        FutureSharedMemMap::new(future_mem_map)
+    }
+}
+
+impl<T: OclPrm> Unpin for FutureSharedMemMap<T> {}
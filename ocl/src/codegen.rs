@@ -0,0 +1,602 @@
+//! Typed kernel launch wrappers generated from OpenCL C source.
+//!
+//! Intended for use from a build script. The kernel declarations in one or
+//! more `.cl` files are parsed and a function is generated for each kernel
+//! which takes each argument as the matching Rust type, builds the kernel
+//! from a `Program` and enqueues it. Misspelled kernel names and arguments
+//! passed in the wrong order or with the wrong type then become compile
+//! errors at the call site.
+//!
+//! ```rust,ignore
+//! // build.rs:
+//! extern crate fil_ocl as ocl;
+//!
+//! fn main() {
+//!     let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("kernels.rs");
+//!     ocl::codegen::StubGenerator::new()
+//!         .source("src/kernels.cl")
+//!         .write_to(out)
+//!         .unwrap();
+//! }
+//!
+//! // src/main.rs:
+//! mod kernels { include!(concat!(env!("OUT_DIR"), "/kernels.rs")); }
+//!
+//! // `__kernel void add(__global int* a, __global const int* b)` becomes:
+//! let program = kernels::build_program(&queue)?;
+//! unsafe { kernels::add(&program, &queue, 1024, &a, &b)?; }
+//! ```
+//!
+//! Argument types are mapped as follows:
+//!
+//! * Scalars (`int`, `float`, ...) and vectors (`float4`, ...): The
+//!   corresponding Rust primitive or `prm` type, passed by value.
+//! * `__global` and `__constant` pointers: `&Buffer<T>`.
+//! * `__local` pointers: A `usize` length (in elements of `T`).
+//! * Images: `&Image<T>`, generic over `T`.
+//! * `sampler_t`: `&Sampler`.
+//!
+//! Kernels with any other argument type (structs, `half`, typedefs) are
+//! skipped and a comment noting the reason is emitted in their place.
+//! Preprocessor directives are ignored and macros are not expanded, so
+//! kernel declarations must be written out in full.
+
+use std;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::core::KernelArgAddressQualifier;
+use crate::error::Result as OclResult;
+
+
+/// A parameter of a kernel declaration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KernelParam {
+    name: String,
+    type_name: String,
+    address_space: KernelArgAddressQualifier,
+    is_pointer: bool,
+}
+
+impl KernelParam {
+    /// Returns the parameter name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the type name with qualifiers and any pointer removed, with
+    /// `unsigned X` normalized to `uX` (e.g. `uint`).
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Returns the address space (`Private` if none was specified).
+    pub fn address_space(&self) -> KernelArgAddressQualifier {
+        self.address_space
+    }
+
+    /// Returns true if the parameter is a pointer.
+    pub fn is_pointer(&self) -> bool {
+        self.is_pointer
+    }
+}
+
+
+/// The name and parameters of a kernel declaration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KernelSignature {
+    name: String,
+    params: Vec<KernelParam>,
+}
+
+impl KernelSignature {
+    /// Returns the kernel name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the parameters, in order.
+    pub fn params(&self) -> &[KernelParam] {
+        &self.params
+    }
+}
+
+
+/// Removes comments, string literals and preprocessor lines.
+fn strip_source(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut chars = src.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                while let Some(&n) = chars.peek() {
+                    if n == '\n' { break; }
+                    chars.next();
+                }
+            },
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                for n in chars.by_ref() {
+                    if prev == '*' && n == '/' { break; }
+                    prev = n;
+                }
+                out.push(' ');
+            },
+            '"' | '\'' => {
+                let mut escaped = false;
+                for n in chars.by_ref() {
+                    if !escaped && n == c { break; }
+                    escaped = !escaped && n == '\\';
+                }
+                out.push(' ');
+            },
+            '#' if line_start => {
+                // Skip the directive, including any continuation lines.
+                let mut prev = '\0';
+                while let Some(&n) = chars.peek() {
+                    if n == '\n' && prev != '\\' { break; }
+                    prev = n;
+                    chars.next();
+                }
+            },
+            _ => out.push(c),
+        }
+
+        if c == '\n' {
+            line_start = true;
+        } else if !c.is_whitespace() {
+            line_start = false;
+        }
+    }
+    out
+}
+
+fn tokenize(src: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = src.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            let mut tok = c.to_string();
+            while let Some(&n) = chars.peek() {
+                if !(n.is_alphanumeric() || n == '_') { break; }
+                tok.push(n);
+                chars.next();
+            }
+            tokens.push(tok);
+        } else if !c.is_whitespace() {
+            tokens.push(c.to_string());
+        }
+    }
+    tokens
+}
+
+/// Returns the index of the parenthesis closing the one at `open`.
+fn closing_paren(tokens: &[String], open: usize) -> OclResult<usize> {
+    let mut depth = 0;
+    for (idx, tok) in tokens.iter().enumerate().skip(open) {
+        match tok.as_str() {
+            "(" => depth += 1,
+            ")" => {
+                depth -= 1;
+                if depth == 0 { return Ok(idx); }
+            },
+            _ => (),
+        }
+    }
+    Err("codegen: Unbalanced parentheses in kernel declaration.".into())
+}
+
+/// Returns the index of the first token after any `__attribute__((...))`
+/// starting at `idx`.
+fn skip_attributes(tokens: &[String], mut idx: usize) -> OclResult<usize> {
+    while idx < tokens.len() && tokens[idx] == "__attribute__" {
+        if tokens.get(idx + 1).map(|t| t.as_str()) != Some("(") {
+            return Err("codegen: Malformed '__attribute__'.".into());
+        }
+        idx = closing_paren(tokens, idx + 1)? + 1;
+    }
+    Ok(idx)
+}
+
+fn parse_param(tokens: &[String], kernel: &str) -> OclResult<KernelParam> {
+    let mut address_space = KernelArgAddressQualifier::Private;
+    let mut is_pointer = false;
+    let mut idents = Vec::new();
+
+    let mut idx = 0;
+    while idx < tokens.len() {
+        idx = skip_attributes(tokens, idx)?;
+        let tok = match tokens.get(idx) {
+            Some(tok) => tok.as_str(),
+            None => break,
+        };
+        match tok {
+            "__global" | "global" => address_space = KernelArgAddressQualifier::Global,
+            "__constant" | "constant" => address_space = KernelArgAddressQualifier::Constant,
+            "__local" | "local" => address_space = KernelArgAddressQualifier::Local,
+            "__private" | "private" => address_space = KernelArgAddressQualifier::Private,
+            "const" | "restrict" | "__restrict" | "volatile" | "signed"
+                | "__read_only" | "read_only" | "__write_only" | "write_only"
+                | "__read_write" | "read_write" => (),
+            "*" => is_pointer = true,
+            "[" | "]" => {
+                return Err(format!("codegen: Kernel '{}': Array parameters are not \
+                    supported.", kernel).into());
+            },
+            _ => idents.push(tok),
+        }
+        idx += 1;
+    }
+
+    let name = match idents.pop() {
+        Some(name) if !idents.is_empty() => name.to_owned(),
+        _ => return Err(format!("codegen: Kernel '{}': Unable to parse parameter '{}'.",
+            kernel, tokens.join(" ")).into()),
+    };
+
+    let type_name = match idents.as_slice() {
+        ["unsigned"] => "uint".to_owned(),
+        ["unsigned", ty] => format!("u{}", ty),
+        _ => idents.join(" "),
+    };
+
+    Ok(KernelParam { name, type_name, address_space, is_pointer })
+}
+
+/// Returns the signature of every kernel declared in `src`.
+pub fn parse_kernels(src: &str) -> OclResult<Vec<KernelSignature>> {
+    let tokens = tokenize(&strip_source(src));
+    let mut kernels = Vec::new();
+    let mut idx = 0;
+
+    while idx < tokens.len() {
+        if tokens[idx] != "__kernel" && tokens[idx] != "kernel" {
+            idx += 1;
+            continue;
+        }
+
+        let ret = skip_attributes(&tokens, idx + 1)?;
+        if tokens.get(ret).map(|t| t.as_str()) != Some("void") {
+            // Not a kernel declaration (e.g. an identifier named `kernel`).
+            idx += 1;
+            continue;
+        }
+
+        let name_idx = skip_attributes(&tokens, ret + 1)?;
+        let name = match tokens.get(name_idx) {
+            Some(name) => name.clone(),
+            None => return Err("codegen: Kernel declaration without a name.".into()),
+        };
+        if tokens.get(name_idx + 1).map(|t| t.as_str()) != Some("(") {
+            return Err(format!("codegen: Kernel '{}': Expected '('.", name).into());
+        }
+        let close = closing_paren(&tokens, name_idx + 1)?;
+        let param_tokens = &tokens[name_idx + 2..close];
+
+        let mut params = Vec::new();
+        if !(param_tokens.is_empty() || (param_tokens.len() == 1 && param_tokens[0] == "void")) {
+            let mut depth = 0;
+            let mut start = 0;
+            for (i, tok) in param_tokens.iter().enumerate() {
+                match tok.as_str() {
+                    "(" => depth += 1,
+                    ")" => depth -= 1,
+                    "," if depth == 0 => {
+                        params.push(parse_param(&param_tokens[start..i], &name)?);
+                        start = i + 1;
+                    },
+                    _ => (),
+                }
+            }
+            params.push(parse_param(&param_tokens[start..], &name)?);
+        }
+
+        kernels.push(KernelSignature { name, params });
+        idx = close + 1;
+    }
+    Ok(kernels)
+}
+
+
+/// Returns the Rust type corresponding to an OpenCL scalar or vector type.
+fn rust_prm_type(type_name: &str, crate_path: &str) -> Option<String> {
+    let scalar = |ty: &str| -> Option<(&'static str, &'static str)> {
+        Some(match ty {
+            "char" => ("i8", "Char"),
+            "uchar" => ("u8", "Uchar"),
+            "short" => ("i16", "Short"),
+            "ushort" => ("u16", "Ushort"),
+            "int" => ("i32", "Int"),
+            "uint" => ("u32", "Uint"),
+            "long" => ("i64", "Long"),
+            "ulong" => ("u64", "Ulong"),
+            "float" => ("f32", "Float"),
+            "double" => ("f64", "Double"),
+            _ => return None,
+        })
+    };
+
+    if let Some((prim, _)) = scalar(type_name) {
+        return Some(prim.to_owned());
+    }
+
+    let split = type_name.find(|c: char| c.is_ascii_digit())?;
+    let (base, width) = type_name.split_at(split);
+    match width {
+        "2" | "3" | "4" | "8" | "16" => {
+            scalar(base).map(|(_, vec)| format!("{}::prm::{}{}", crate_path, vec, width))
+        },
+        _ => None,
+    }
+}
+
+fn is_image_type(type_name: &str) -> bool {
+    matches!(type_name, "image1d_t" | "image1d_buffer_t" | "image1d_array_t" | "image2d_t"
+        | "image2d_array_t" | "image3d_t")
+}
+
+fn is_keyword(ident: &str) -> bool {
+    matches!(ident, "as" | "break" | "const" | "continue" | "crate" | "else" | "enum" | "extern"
+        | "false" | "fn" | "for" | "if" | "impl" | "in" | "let" | "loop" | "match"
+        | "mod" | "move" | "mut" | "pub" | "ref" | "return" | "self" | "Self"
+        | "static" | "struct" | "super" | "trait" | "true" | "type" | "unsafe"
+        | "use" | "where" | "while" | "async" | "await" | "dyn" | "abstract"
+        | "become" | "box" | "do" | "final" | "macro" | "override" | "priv"
+        | "typeof" | "unsized" | "virtual" | "yield" | "try")
+}
+
+/// Parameter names used by every generated function.
+const RESERVED_PARAMS: &[&str] = &["program", "queue", "global_work_size"];
+
+
+/// Generates typed launch wrappers for the kernels in a set of `.cl` files.
+///
+/// See the [module documentation](index.html) for an example.
+#[derive(Clone, Debug)]
+pub struct StubGenerator {
+    crate_path: String,
+    sources: Vec<PathBuf>,
+}
+
+impl StubGenerator {
+    /// Returns a new generator with no sources.
+    pub fn new() -> StubGenerator {
+        StubGenerator {
+            crate_path: "::fil_ocl".to_owned(),
+            sources: Vec::new(),
+        }
+    }
+
+    /// Sets the path through which generated code refers to this crate
+    /// (default: `::fil_ocl`).
+    pub fn crate_path<S: Into<String>>(&mut self, path: S) -> &mut StubGenerator {
+        self.crate_path = path.into();
+        self
+    }
+
+    /// Adds a `.cl` file.
+    ///
+    /// Relative paths are resolved against the current directory, which is
+    /// the package root when run from a build script.
+    pub fn source<P: AsRef<Path>>(&mut self, path: P) -> &mut StubGenerator {
+        self.sources.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Reads each source file and returns the generated code.
+    pub fn generate(&self) -> OclResult<String> {
+        let mut sources = Vec::with_capacity(self.sources.len());
+        for path in self.sources.iter() {
+            let path = fs::canonicalize(path).map_err(|err| format!("StubGenerator::generate: \
+                Unable to resolve '{}': {}", path.display(), err))?;
+            let src = fs::read_to_string(&path).map_err(|err| format!("StubGenerator::\
+                generate: Unable to read '{}': {}", path.display(), err))?;
+            sources.push((path.display().to_string(), src));
+        }
+        self.render(&sources)
+    }
+
+    /// Writes the generated code to `out_path` and prints a
+    /// `cargo:rerun-if-changed` line for each source file.
+    pub fn write_to<P: AsRef<Path>>(&self, out_path: P) -> OclResult<()> {
+        let code = self.generate()?;
+        fs::write(out_path.as_ref(), code).map_err(|err| format!("StubGenerator::write_to: \
+            Unable to write '{}': {}", out_path.as_ref().display(), err))?;
+        for path in self.sources.iter() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+        Ok(())
+    }
+
+    /// Renders the code for `sources`, a list of `(include path, source)`.
+    fn render(&self, sources: &[(String, String)]) -> OclResult<String> {
+        let p = &self.crate_path;
+        let mut out = String::new();
+        writeln!(out, "// Generated by `codegen::StubGenerator`. Do not edit.").unwrap();
+
+        writeln!(out, "\n/// Builds a program from the kernel sources for the device of `queue`.").unwrap();
+        writeln!(out, "#[allow(dead_code)]").unwrap();
+        writeln!(out, "pub fn build_program(queue: &{p}::Queue) -> {p}::Result<{p}::Program> {{", p = p).unwrap();
+        writeln!(out, "    {}::Program::builder()", p).unwrap();
+        for (path, _) in sources.iter() {
+            writeln!(out, "        .src(include_str!({:?}))", path).unwrap();
+        }
+        writeln!(out, "        .devices(queue.device())").unwrap();
+        writeln!(out, "        .build(&queue.context())").unwrap();
+        writeln!(out, "}}").unwrap();
+
+        let mut names = HashSet::new();
+        for (path, src) in sources.iter() {
+            for kernel in parse_kernels(src).map_err(|err| format!("{} ('{}')", err, path))? {
+                if !names.insert(kernel.name.clone()) {
+                    return Err(format!("StubGenerator: Kernel '{}' is declared more than once.",
+                        kernel.name).into());
+                }
+                out.push('\n');
+                match self.render_kernel(&kernel) {
+                    Ok(code) => out.push_str(&code),
+                    Err(reason) => writeln!(out, "// Skipped kernel `{}`: {}", kernel.name, reason).unwrap(),
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Renders the wrapper for a single kernel or returns the reason it is
+    /// unsupported.
+    fn render_kernel(&self, kernel: &KernelSignature) -> Result<String, String> {
+        let p = &self.crate_path;
+        let mut generics = vec![format!("W: Into<{}::SpatialDims>", p)];
+        let mut params = Vec::with_capacity(kernel.params.len());
+        let mut args = Vec::with_capacity(kernel.params.len());
+
+        for param in kernel.params.iter() {
+            let mut name = param.name.clone();
+            if is_keyword(&name) || RESERVED_PARAMS.contains(&name.as_str()) {
+                name.push('_');
+            }
+
+            let (ty, arg) = if param.is_pointer {
+                let elem = rust_prm_type(&param.type_name, p).ok_or_else(|| format!(
+                    "Unsupported pointer type '{}' for '{}'.", param.type_name, param.name))?;
+                match param.address_space {
+                    KernelArgAddressQualifier::Global | KernelArgAddressQualifier::Constant => {
+                        (format!("&{}::Buffer<{}>", p, elem), format!(".arg({})", name))
+                    },
+                    KernelArgAddressQualifier::Local => {
+                        ("usize".to_owned(), format!(".arg_local::<{}>({})", elem, name))
+                    },
+                    KernelArgAddressQualifier::Private => {
+                        return Err(format!("Pointer '{}' has no address space.", param.name));
+                    },
+                }
+            } else if is_image_type(&param.type_name) {
+                let generic = format!("I{}", generics.len() - 1);
+                generics.push(format!("{}: {}::OclPrm", generic, p));
+                (format!("&{}::Image<{}>", p, generic), format!(".arg({})", name))
+            } else if param.type_name == "sampler_t" {
+                (format!("&{}::Sampler", p), format!(".arg_sampler({})", name))
+            } else {
+                let ty = rust_prm_type(&param.type_name, p).ok_or_else(|| format!(
+                    "Unsupported type '{}' for '{}'.", param.type_name, param.name))?;
+                (ty, format!(".arg({})", name))
+            };
+
+            params.push(format!("{}: {}", name, ty));
+            args.push(arg);
+        }
+
+        let fn_name = if is_keyword(&kernel.name) {
+            format!("r#{}", kernel.name)
+        } else {
+            kernel.name.clone()
+        };
+        let decl: Vec<String> = kernel.params.iter().map(|param| {
+            let ptr = if param.is_pointer { "*" } else { "" };
+            match param.address_space {
+                KernelArgAddressQualifier::Private => format!("{}{} {}", param.type_name, ptr, param.name),
+                space => format!("__{} {}{} {}", format!("{:?}", space).to_lowercase(),
+                    param.type_name, ptr, param.name),
+            }
+        }).collect();
+
+        let mut out = String::new();
+        writeln!(out, "/// Enqueues `__kernel void {}({})`.", kernel.name, decl.join(", ")).unwrap();
+        writeln!(out, "///\n/// ## Safety\n///\n/// See `Kernel::enq`.").unwrap();
+        writeln!(out, "#[allow(dead_code, non_snake_case, clippy::too_many_arguments)]").unwrap();
+        writeln!(out, "pub unsafe fn {}<{}>(program: &{p}::Program, queue: &{p}::Queue, \
+            global_work_size: W{}{}) -> {p}::Result<()> {{", fn_name, generics.join(", "),
+            if params.is_empty() { "" } else { ", " }, params.join(", "), p = p).unwrap();
+        writeln!(out, "    {}::Kernel::builder()", p).unwrap();
+        writeln!(out, "        .program(program)").unwrap();
+        writeln!(out, "        .name({:?})", kernel.name).unwrap();
+        writeln!(out, "        .queue(queue.clone())").unwrap();
+        writeln!(out, "        .global_work_size(global_work_size)").unwrap();
+        for arg in args.iter() {
+            writeln!(out, "        {}", arg).unwrap();
+        }
+        writeln!(out, "        .build()?").unwrap();
+        writeln!(out, "        .enq()").unwrap();
+        writeln!(out, "}}").unwrap();
+        Ok(out)
+    }
+}
+
+impl Default for StubGenerator {
+    fn default() -> StubGenerator {
+        StubGenerator::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::core::KernelArgAddressQualifier;
+    use super::{parse_kernels, StubGenerator};
+
+    const SRC: &str = r#"
+        #define SCALE 2.0f
+        // __kernel void commented_out(__global int* a) {}
+        __kernel __attribute__((reqd_work_group_size(64, 1, 1)))
+        void add(__global int* restrict a, __global const int* b, unsigned int len) {
+            /* kernel void not_a_kernel(int x) */
+            uint idx = get_global_id(0);
+            if (idx < len) { a[idx] += b[idx]; }
+        }
+
+        kernel void reduce(global const float4* in, __local float4* scratch,
+                __global float* out, read_only image2d_t img, sampler_t smp) {}
+
+        __kernel void empty(void) {}
+
+        __kernel void with_struct(__global MyStruct* s) {}
+    "#;
+
+    #[test]
+    fn parse() {
+        let kernels = parse_kernels(SRC).unwrap();
+        let names: Vec<&str> = kernels.iter().map(|k| k.name()).collect();
+        assert_eq!(names, ["add", "reduce", "empty", "with_struct"]);
+
+        let add = kernels[0].params();
+        assert_eq!(add.len(), 3);
+        assert_eq!((add[0].name(), add[0].type_name(), add[0].is_pointer()), ("a", "int", true));
+        assert_eq!(add[1].address_space(), KernelArgAddressQualifier::Global);
+        assert_eq!((add[2].name(), add[2].type_name(), add[2].is_pointer()), ("len", "uint", false));
+        assert_eq!(add[2].address_space(), KernelArgAddressQualifier::Private);
+
+        let reduce = kernels[1].params();
+        assert_eq!(reduce[1].address_space(), KernelArgAddressQualifier::Local);
+        assert_eq!(reduce[3].type_name(), "image2d_t");
+        assert!(kernels[2].params().is_empty());
+    }
+
+    #[test]
+    fn render() {
+        let code = StubGenerator::new().crate_path("ocl")
+            .render(&[("/src/kernels.cl".to_owned(), SRC.to_owned())]).unwrap();
+
+        assert!(code.contains(".src(include_str!(\"/src/kernels.cl\"))"));
+        assert!(code.contains("pub unsafe fn add<W: Into<ocl::SpatialDims>>(program: &ocl::Program, \
+            queue: &ocl::Queue, global_work_size: W, a: &ocl::Buffer<i32>, b: &ocl::Buffer<i32>, \
+            len: u32) -> ocl::Result<()>"));
+        assert!(code.contains("pub unsafe fn reduce<W: Into<ocl::SpatialDims>, I0: ocl::OclPrm>("));
+        assert!(code.contains("in_: &ocl::Buffer<ocl::prm::Float4>, scratch: usize"));
+        assert!(code.contains(".arg_local::<ocl::prm::Float4>(scratch)"));
+        assert!(code.contains("img: &ocl::Image<I0>, smp: &ocl::Sampler"));
+        assert!(code.contains(".arg_sampler(smp)"));
+        assert!(code.contains("global_work_size: W) -> ocl::Result<()>"));
+        assert!(code.contains("// Skipped kernel `with_struct`: Unsupported pointer type 'MyStruct'"));
+    }
+
+    #[test]
+    fn duplicate_kernel() {
+        let src = "__kernel void a(int x) {}\n__kernel void a(int y) {}";
+        assert!(StubGenerator::new().render(&[("a.cl".to_owned(), src.to_owned())]).is_err());
+    }
+}
@@ -2,13 +2,14 @@
 //!
 
 use std;
+use std::time::Duration;
 // use std::sync::mpsc::{SendError as StdMpscSendError, RecvError as StdMpscRecvError};
 use failure::{Context, Fail, Backtrace};
-use futures::sync::oneshot::Canceled as OneshotCanceled;
-use futures::sync::mpsc::SendError;
+use futures::channel::oneshot::Canceled as OneshotCanceled;
+use futures::channel::mpsc::SendError;
 use crate::core::error::{Error as OclCoreError};
-use crate::core::Status;
-use crate::standard::{DeviceError, PlatformError, KernelError};
+use crate::core::{Status, VersionLowError};
+use crate::standard::{DeviceError, PlatformError, KernelError, EventFailedError, MemoryLimitError};
 
 use crate::BufferCmdError;
 
@@ -38,6 +39,12 @@ pub enum ErrorKind {
     Platform(PlatformError),
     #[fail(display = "{}", _0)]
     Kernel(KernelError),
+    #[fail(display = "{}", _0)]
+    EventFailed(EventFailedError),
+    #[fail(display = "Timed out after {:?} waiting for events to complete.", _0)]
+    TimedOut(Duration),
+    #[fail(display = "{}", _0)]
+    MemoryLimit(MemoryLimitError),
 }
 
 
@@ -55,6 +62,67 @@ impl Error {
         }
     }
 
+    /// Returns true if this error indicates that profiling information is
+    /// not available for an event (see `Event::profiling_time`).
+    pub fn is_profiling_unavailable(&self) -> bool {
+        match *self.kind() {
+            ErrorKind::OclCore(ref err) => err.is_profiling_unavailable(),
+            _ => false,
+        }
+    }
+
+    /// Returns true if this error indicates that argument information is not
+    /// available for a kernel (see `Kernel::arg_info`).
+    pub fn is_kernel_arg_info_unavailable(&self) -> bool {
+        match *self.kind() {
+            ErrorKind::OclCore(ref err) => err.is_kernel_arg_info_unavailable(),
+            _ => false,
+        }
+    }
+
+    /// Returns the failed event if this error was returned because a
+    /// command in a wait list terminated abnormally.
+    pub fn failed_event(&self) -> Option<&EventFailedError> {
+        match *self.kind() {
+            ErrorKind::EventFailed(ref err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Returns true if this error was returned because a wait timed out
+    /// (see `Event::wait_timeout`).
+    pub fn is_timed_out(&self) -> bool {
+        matches!(*self.kind(), ErrorKind::TimedOut(_))
+    }
+
+    /// Returns the details if this error was returned because an allocation
+    /// would have exceeded a context's memory limit (see
+    /// `Context::set_memory_headroom`).
+    pub fn memory_limit(&self) -> Option<&MemoryLimitError> {
+        match *self.kind() {
+            ErrorKind::MemoryLimit(ref err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Returns the details if this error was returned because platforms
+    /// could not be listed.
+    pub fn platform(&self) -> Option<&PlatformError> {
+        match *self.kind() {
+            ErrorKind::Platform(ref err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Returns the details if this error was returned because a device or
+    /// platform version was too low for the requested function.
+    pub fn version_low(&self) -> Option<&VersionLowError> {
+        match *self.kind() {
+            ErrorKind::OclCore(ref err) => err.version_low(),
+            _ => None,
+        }
+    }
+
     /// Returns the error variant and contents.
     pub fn kind(&self) -> &ErrorKind {
         self.inner.get_context()
@@ -95,8 +163,8 @@ impl From<OclCoreError> for Error {
     }
 }
 
-impl<T> From<SendError<T>> for Error {
-    fn from(err: SendError<T>) -> Error {
+impl From<SendError> for Error {
+    fn from(err: SendError) -> Error {
         let debug = format!("{:?}", err);
         let display = format!("{}", err);
         Error { inner: Context::new(ErrorKind::FuturesMpscSend(
@@ -175,6 +243,24 @@ impl From<KernelError> for Error {
     }
 }
 
+impl From<EventFailedError> for Error {
+    fn from(err: EventFailedError) -> Error {
+        Error { inner: Context::new(ErrorKind::EventFailed(err)) }
+    }
+}
+
+impl From<MemoryLimitError> for Error {
+    fn from(err: MemoryLimitError) -> Error {
+        Error { inner: Context::new(ErrorKind::MemoryLimit(err)) }
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Error {
+        Error { inner: Context::new(kind) }
+    }
+}
+
 impl From<Error> for String {
     fn from(err: Error) -> String {
         err.to_string()
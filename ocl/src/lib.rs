@@ -45,14 +45,18 @@
 //! [examples]: https://github.com/cogciprocate/ocl/tree/master/examples
 
 #![doc(html_root_url = "https://docs.rs/ocl/0.19.3")]
+#![cfg_attr(feature = "svm_allocator_api", feature(allocator_api))]
 
 // #![warn(missing_docs)]
 
 extern crate num_traits;
 extern crate futures;
+extern crate crossbeam_queue;
 #[macro_use]
 extern crate failure;
 pub extern crate fil_ocl_core as core;
+#[cfg(feature = "serde")]
+extern crate serde;
 
 
 #[cfg(test)]
@@ -60,11 +64,28 @@ mod tests;
 mod standard;
 pub mod error;
 pub mod r#async;
+pub mod testing;
+pub mod codegen;
 
 pub use self::standard::{Platform, Extensions, Device, Context, Program, Queue, Kernel, Buffer, Image, Event,
-    EventList, EventArray, Sampler, SpatialDims, ProQue, BufferCmdError};
-pub use self::r#async::{MemMap, FutureMemMap, RwVec, ReadGuard, WriteGuard,
-    FutureReadGuard, FutureWriteGuard};
+    EventList, EventArray, UserEvent, EventProfiling, Sampler, SpatialDims, ProQue, BufferCmdError,
+    Priority, FairScheduler, MemPool, TemporaryBuffer, TemporaryImage, Quirks, DriverId,
+    TransferPolicy, TransferMetrics, TransferKind, ChunkMetrics, ProgramHandle, Allocation,
+    AllocationKind, MemoryUsage, PciBusAddress, DeviceUuid, FutureFinish, FutureKernel,
+    QueuePool, DispatchPolicy, HostMirror, DirtyRanges, DeviceCache, CachedBuffer,
+    FlushPolicy, Trace, DeviceTimerSync, CommandGraph, GraphNode, DeviceCriteria, DeviceScore,
+    SubDevice, NumaNode, DeviceExtensions, CapabilityReport, PlatformError, IcdSearch,
+    WorkSizeLimits, CachedKernel, MultiProQue, KernelStats, ProfilingSummary, FutureProgram,
+    FutureBuild};
+#[cfg(feature = "opencl_version_2_2")]
+pub use self::standard::SpecConstantPrm;
+#[cfg(feature = "opencl_version_2_0")]
+pub use self::standard::{SvmBuffer, SvmMap, SvmCmd, FineSvmBuffer,
+    SvmAtomicPrm, SvmBox, SvmVec, SvmAllocator};
+pub use self::r#async::{MemMap, FutureMemMap, SharedMemMap, FutureSharedMemMap, RwVec,
+    ReadGuard, WriteGuard, UpgradableReadGuard, FutureReadGuard, FutureWriteGuard, FutureWriteVec,
+    FutureReadVec, EventListFuture, EventSelect, KernelStream, CommandChain, FutureChain,
+    ChainReads, DropPolicy, TransferScope, FutureScopedRead};
 pub use crate::error::{Error, Result};
 pub use crate::{core::Error as OclCoreError};
 #[doc(no_inline)]
@@ -125,7 +146,7 @@ pub mod prm {
 pub mod traits {
     //! Commonly used traits.
 
-    pub use crate::standard::{WorkDims, MemLen, IntoMarker, IntoRawEventArray};
+    pub use crate::standard::{WorkDims, MemLen, IntoMarker, IntoRawEventArray, GraphCommand};
     pub use crate::core::{OclPrm, OclScl, OclVec};
 }
 
@@ -135,9 +156,9 @@ pub mod builders {
     pub use crate::standard::{ContextBuilder, BuildOpt, ProgramBuilder, ImageBuilder, ProQueBuilder,
         DeviceSpecifier, BufferCmdKind, BufferCmdDataShape, BufferCmd, BufferReadCmd,
         BufferWriteCmd, BufferMapCmd, ImageCmdKind, ImageCmd, KernelCmd, BufferBuilder,
-        KernelBuilder};
+        KernelBuilder, SourceFragment, LaunchConfig, QueueBuilder, ContextErrorFn};
     pub use crate::standard::{ClNullEventPtrEnum, ClWaitListPtrEnum};
-    pub use crate::core::{ImageFormat, ImageDescriptor, ContextProperties};
+    pub use crate::core::{ImageFormat, ImageDescriptor, ContextProperties, QueueProperties};
     // #[cfg(not(release))] pub use standard::BufferTest;
 }
 
@@ -153,6 +174,9 @@ pub mod flags {
             FP_ROUND_TO_INF, FP_FMA, FP_SOFT_FLOAT, FP_CORRECTLY_ROUNDED_DIVIDE_SQRT,
         // cl_device_exec_capabilities - bitfield
         DeviceExecCapabilities, EXEC_KERNEL, EXEC_NATIVE_KERNEL,
+        // cl_device_svm_capabilities, cl_device_atomic_capabilities, and
+        // cl_device_device_enqueue_capabilities - bitfields
+        DeviceSvmCapabilities, DeviceAtomicCapabilities, DeviceEnqueueCapabilities,
         // cl_command_queue_properties - bitfield
         CommandQueueProperties, QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE, QUEUE_PROFILING_ENABLE,
         // cl_device_affinity_domain
@@ -188,13 +212,14 @@ pub mod enums {
         MemObjectType, MemInfo, ImageInfo, AddressingMode, FilterMode, SamplerInfo, ProgramInfo,
         ProgramBuildInfo, ProgramBuildStatus, KernelInfo, KernelArgInfo, KernelArgAddressQualifier,
         KernelArgAccessQualifier, KernelWorkGroupInfo, EventInfo, CommandType,
-        CommandExecutionStatus, BufferCreateType, ProfilingInfo};
+        CommandExecutionStatus, BufferCreateType, ProfilingInfo, QueuePriority, QueueThrottle};
 
     // Custom enums.
     pub use crate::core::{ArgVal, ContextPropertyValue, PlatformInfoResult, DeviceInfoResult,
         ContextInfoResult, CommandQueueInfoResult, MemInfoResult, ImageInfoResult,
         SamplerInfoResult, ProgramInfoResult, ProgramBuildInfoResult, KernelInfoResult,
-        KernelArgInfoResult, KernelWorkGroupInfoResult, EventInfoResult, ProfilingInfoResult};
+        KernelArgInfoResult, KernelWorkGroupInfoResult, EventInfoResult, ProfilingInfoResult,
+        DevicePartition};
 
     // Error status.
     pub use crate::core::Status;
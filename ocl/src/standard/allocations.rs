@@ -0,0 +1,385 @@
+//! Tracking of live device memory allocations.
+//!
+//! Every `Buffer` and `Image` created through this crate records its size,
+//! flags, and creation call site. Use `Context::dump_allocations` to list
+//! the allocations currently alive within a context and
+//! `Context::memory_usage` for their totals.
+//!
+//! A context can also be given a memory limit (see
+//! `Context::set_memory_headroom`), in which case creating a buffer or image
+//! which would exceed a device's global memory size, less the headroom,
+//! fails with a `MemoryLimitError` before anything is allocated.
+
+use std::panic::Location;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use crate::core::{Context as ContextCore, MemFlags, DeviceInfo, DeviceInfoResult};
+use crate::error::Result as OclResult;
+use crate::standard::Device;
+
+
+// The memory state of each context, keyed by context pointer. Each is kept
+// alive by the `Context`s wrapping its context and by its allocations.
+static CONTEXTS: Mutex<Vec<(usize, Weak<ContextMemory>)>> = Mutex::new(Vec::new());
+
+
+/// The kind of memory object an allocation belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AllocationKind {
+    Buffer,
+    Image,
+}
+
+
+/// The live allocations, usage, and memory limit of a context.
+#[derive(Debug)]
+pub(crate) struct ContextMemory(Mutex<MemoryState>);
+
+#[derive(Debug)]
+struct MemoryState {
+    records: Vec<Weak<AllocationRecord>>,
+    usage: MemoryUsage,
+    headroom: Option<u64>,
+    // The global memory size of each device of the context, queried when
+    // first needed to enforce the headroom.
+    global_mem_sizes: Option<Vec<(Device, u64)>>,
+}
+
+impl ContextMemory {
+    /// Returns the memory state of `context`, creating it if nothing holds
+    /// it.
+    pub(crate) fn for_context(context: &ContextCore) -> Arc<ContextMemory> {
+        let key = context.as_ptr() as usize;
+        let mut contexts = CONTEXTS.lock().unwrap_or_else(|e| e.into_inner());
+        contexts.retain(|(_, weak)| weak.strong_count() > 0);
+
+        let existing = contexts.iter().find(|(k, _)| *k == key).and_then(|(_, w)| w.upgrade());
+        if let Some(memory) = existing {
+            return memory;
+        }
+        let memory = Arc::new(ContextMemory(Mutex::new(MemoryState {
+            records: Vec::new(),
+            usage: MemoryUsage { total: 0, devices: Vec::new() },
+            headroom: None,
+            global_mem_sizes: None,
+        })));
+        contexts.push((key, Arc::downgrade(&memory)));
+        memory
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MemoryState> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the live allocations, largest first.
+    pub(crate) fn live(&self) -> Vec<Allocation> {
+        // Upgraded records are only dropped once the lock is released, as
+        // dropping the last reference to one takes the lock:
+        let records: Vec<Arc<AllocationRecord>> = {
+            let mut state = self.lock();
+            state.records.retain(|r| r.strong_count() > 0);
+            state.records.iter().filter_map(|r| r.upgrade()).collect()
+        };
+
+        let mut allocations: Vec<Allocation> = records.iter().map(|r| r.snapshot()).collect();
+        allocations.sort_by_key(|a| std::cmp::Reverse(a.size));
+        allocations
+    }
+
+    /// Returns the total size of the live allocations.
+    pub(crate) fn usage(&self) -> MemoryUsage {
+        self.lock().usage.clone()
+    }
+
+    /// Sets or clears the memory headroom.
+    pub(crate) fn set_headroom(&self, headroom: Option<u64>) {
+        self.lock().headroom = headroom;
+    }
+
+    /// Returns the memory headroom, if any.
+    pub(crate) fn headroom(&self) -> Option<u64> {
+        self.lock().headroom
+    }
+}
+
+
+/// Memory counted toward the usage of a context, until dropped.
+#[derive(Debug)]
+pub(crate) struct Reservation {
+    memory: Arc<ContextMemory>,
+    device: Option<Device>,
+    size: u64,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.memory.lock().usage.remove(self.device, self.size);
+    }
+}
+
+
+/// Counts `size` bytes, to be allocated within `context` on `device` if
+/// specified, toward the context's usage.
+///
+/// Returns an error, counting nothing, if this would exceed the memory limit
+/// of any device (only possible if a headroom has been set for the context).
+/// Allocations not made for a particular device count against every device
+/// of the context. The check and the count happen under one lock, so
+/// concurrent reservations can not overshoot the limit.
+pub(crate) fn reserve(context: &ContextCore, device: Option<Device>, size: u64)
+        -> OclResult<Reservation> {
+    let memory = ContextMemory::for_context(context);
+    {
+        let mut guard = memory.lock();
+        let state = &mut *guard;
+
+        if let Some(headroom) = state.headroom {
+            if state.global_mem_sizes.is_none() {
+                state.global_mem_sizes = Some(global_mem_sizes(context)?);
+            }
+            let global_mem_sizes = state.global_mem_sizes.iter().flatten()
+                .filter(|&&(d, _)| device.map(|device| d == device).unwrap_or(true));
+
+            for &(device, global_mem_size) in global_mem_sizes {
+                let limit = global_mem_size.saturating_sub(headroom);
+                let used = state.usage.on_device(&device);
+                if used + size > limit {
+                    return Err(MemoryLimitError { device, requested: size, used, limit }.into());
+                }
+            }
+        }
+        state.usage.add(device, size);
+    }
+    Ok(Reservation { memory, device, size })
+}
+
+
+/// Counts `size` bytes, allocated within `context` on `device` if specified,
+/// toward the context's usage without checking its memory limit.
+pub(crate) fn reserve_unchecked(context: &ContextCore, device: Option<Device>, size: u64)
+        -> Reservation {
+    let memory = ContextMemory::for_context(context);
+    memory.lock().usage.add(device, size);
+    Reservation { memory, device, size }
+}
+
+
+/// Returns the global memory size of each device of `context`.
+fn global_mem_sizes(context: &ContextCore) -> OclResult<Vec<(Device, u64)>> {
+    Device::list_from_core(context.devices()?).into_iter().map(|device| {
+        match device.info(DeviceInfo::GlobalMemSize)? {
+            DeviceInfoResult::GlobalMemSize(size) => Ok((device, size)),
+            _ => unreachable!(),
+        }
+    }).collect()
+}
+
+
+/// The record shared by a memory object and its clones.
+#[derive(Debug)]
+pub(crate) struct AllocationRecord {
+    kind: AllocationKind,
+    flags: MemFlags,
+    location: Option<&'static Location<'static>>,
+    tag: Mutex<Option<String>>,
+    reservation: Reservation,
+}
+
+impl AllocationRecord {
+    /// Sets the user-defined tag.
+    pub(crate) fn set_tag(&self, tag: Option<String>) {
+        *self.tag.lock().unwrap_or_else(|e| e.into_inner()) = tag;
+    }
+
+    /// Returns a copy of the user-defined tag.
+    pub(crate) fn tag(&self) -> Option<String> {
+        self.tag.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn snapshot(&self) -> Allocation {
+        Allocation {
+            kind: self.kind,
+            size: self.reservation.size as usize,
+            flags: self.flags,
+            location: self.location,
+            tag: self.tag(),
+        }
+    }
+}
+
+
+/// Records the creation of a memory object for which `reservation` was made
+/// and returns the record to be held by it.
+///
+/// `flags` are those the memory object was created with (taken from its
+/// builder rather than queried).
+pub(crate) fn track(kind: AllocationKind, reservation: Reservation, flags: MemFlags,
+        location: Option<&'static Location<'static>>) -> Arc<AllocationRecord> {
+    let memory = reservation.memory.clone();
+    let record = Arc::new(AllocationRecord {
+        kind,
+        flags,
+        location,
+        tag: Mutex::new(None),
+        reservation,
+    });
+
+    let mut state = memory.lock();
+    state.records.retain(|r| r.strong_count() > 0);
+    state.records.push(Arc::downgrade(&record));
+    record
+}
+
+
+/// Memory allocated through this crate within a context (see
+/// `Context::memory_usage`).
+///
+/// Only buffers and images created with a queue are attributed to a
+/// device; those created with just a context count toward the total only.
+#[derive(Clone, Debug)]
+pub struct MemoryUsage {
+    total: u64,
+    devices: Vec<(Device, u64)>,
+}
+
+impl MemoryUsage {
+    /// Returns the total size of all live allocations, in bytes.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the size of the live allocations created for `device`, in
+    /// bytes.
+    pub fn device(&self, device: &Device) -> u64 {
+        self.devices.iter().find(|&&(d, _)| d == *device).map(|&(_, size)| size).unwrap_or(0)
+    }
+
+    /// Returns the size of the live allocations not created for any
+    /// particular device, in bytes.
+    pub fn unattributed(&self) -> u64 {
+        self.total - self.devices.iter().map(|&(_, size)| size).sum::<u64>()
+    }
+
+    /// Returns each device with live allocations and their size, in bytes.
+    pub fn devices(&self) -> &[(Device, u64)] {
+        &self.devices
+    }
+
+    /// Returns the memory which may be resident on `device`: that created
+    /// for it plus that not created for any particular device.
+    fn on_device(&self, device: &Device) -> u64 {
+        self.device(device) + self.unattributed()
+    }
+
+    fn add(&mut self, device: Option<Device>, size: u64) {
+        self.total += size;
+        if let Some(device) = device {
+            match self.devices.iter_mut().find(|(d, _)| *d == device) {
+                Some((_, used)) => *used += size,
+                None => self.devices.push((device, size)),
+            }
+        }
+    }
+
+    fn remove(&mut self, device: Option<Device>, size: u64) {
+        self.total -= size;
+        if let Some(device) = device {
+            if let Some(idx) = self.devices.iter().position(|&(d, _)| d == device) {
+                self.devices[idx].1 -= size;
+                if self.devices[idx].1 == 0 { self.devices.remove(idx); }
+            }
+        }
+    }
+}
+
+
+/// An error returned when creating a buffer or image would exceed the memory
+/// limit of a context (see `Context::set_memory_headroom`).
+#[derive(Debug, Fail)]
+pub struct MemoryLimitError {
+    device: Device,
+    requested: u64,
+    used: u64,
+    limit: u64,
+}
+
+impl MemoryLimitError {
+    /// Returns the device whose limit would have been exceeded.
+    pub fn device(&self) -> Device {
+        self.device
+    }
+
+    /// Returns the size of the rejected allocation, in bytes.
+    pub fn requested(&self) -> u64 {
+        self.requested
+    }
+
+    /// Returns the memory already in use on the device, in bytes.
+    pub fn used(&self) -> u64 {
+        self.used
+    }
+
+    /// Returns the device's global memory size less the headroom, in bytes.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+impl std::fmt::Display for MemoryLimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Allocating {} bytes would exceed the memory limit of device '{}' \
+            ({} of {} bytes in use).", self.requested,
+            self.device.name().unwrap_or_else(|_| "<unknown>".to_owned()), self.used, self.limit)
+    }
+}
+
+
+/// A snapshot of a live buffer or image allocation.
+#[derive(Clone, Debug)]
+pub struct Allocation {
+    kind: AllocationKind,
+    size: usize,
+    flags: MemFlags,
+    location: Option<&'static Location<'static>>,
+    tag: Option<String>,
+}
+
+impl Allocation {
+    /// Returns the kind of memory object.
+    pub fn kind(&self) -> AllocationKind {
+        self.kind
+    }
+
+    /// Returns the size of the allocation, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the flags the memory object was created with.
+    pub fn flags(&self) -> MemFlags {
+        self.flags
+    }
+
+    /// Returns the source location the memory object was created from, if
+    /// it was captured.
+    pub fn location(&self) -> Option<&'static Location<'static>> {
+        self.location
+    }
+
+    /// Returns the tag assigned to the memory object, if any.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+}
+
+impl std::fmt::Display for Allocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}: {} bytes, flags: {:?}", self.kind, self.size, self.flags)?;
+        if let Some(location) = self.location {
+            write!(f, ", created at: {}", location)?;
+        }
+        if let Some(ref tag) = self.tag {
+            write!(f, ", tag: '{}'", tag)?;
+        }
+        Ok(())
+    }
+}
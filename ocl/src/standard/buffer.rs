@@ -3,12 +3,17 @@
 use std;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut, Range};
+use std::panic::Location;
+use std::sync::Arc;
 use crate::core::{self, Error as OclCoreError, Result as OclCoreResult, OclPrm, Mem as MemCore,
-    MemFlags, MemInfo, MemInfoResult, BufferRegion, MapFlags, AsMem, MemCmdRw, MemCmdAll,
-    ClNullEventPtr};
-use crate::{Context, Queue, FutureMemMap, MemMap, Event, RwVec, FutureReadGuard, FutureWriteGuard,
-    SpatialDims};
-use crate::standard::{ClNullEventPtrEnum, ClWaitListPtrEnum, HostSlice};
+    MemFlags, MemInfo, MemInfoResult, BufferRegion, MapFlags, AsMem, MemCmdRw, MemCmdAll};
+use crate::{Context, Queue, FutureMemMap, FutureSharedMemMap, MemMap, Event, RwVec,
+    FutureReadGuard, FutureWriteGuard, FutureWriteVec, FutureReadVec, SpatialDims, Device};
+use crate::r#async::{TransferScope, FutureScopedRead};
+use crate::standard::{ClNullEventPtrEnum, ClWaitListPtrEnum, HostSlice, TransferPolicy};
+use crate::standard::transfer::{ChunkedTransfer, TransferKind};
+use crate::standard::allocations::{self, AllocationKind, AllocationRecord};
+use crate::standard::tracking::Tracker;
 use crate::error::{Error as OclError, Result as OclResult};
 
 #[cfg(not(feature="opencl_vendor_mesa"))]
@@ -67,6 +72,14 @@ impl<'o> QueCtx<'o> {
             QueCtx::Context(c) => c.clone(),
         }
     }
+
+    /// Returns the device of the contained queue, if any.
+    pub(crate) fn device(&self) -> Option<Device> {
+        match *self {
+            QueCtx::Queue(ref q) => Some(q.device()),
+            QueCtx::Context(_) => None,
+        }
+    }
 }
 
 impl<'o> From<Queue> for QueCtx<'o> {
@@ -108,6 +121,10 @@ pub enum BufferCmdKind<'c, T> where T: 'c {
     CopyToImage { image: &'c MemCore, dst_origin: [usize; 3], region: [usize; 3] },
     GLAcquire,
     GLRelease,
+    #[cfg(all(windows, feature = "d3d11_sharing"))]
+    D3d11Acquire,
+    #[cfg(all(windows, feature = "d3d11_sharing"))]
+    D3d11Release,
 }
 
 impl<'c, T> BufferCmdKind<'c, T> {
@@ -169,6 +186,7 @@ pub struct BufferCmd<'c, T> where T: 'c + OclPrm {
     shape: BufferCmdDataShape,
     ewait: Option<ClWaitListPtrEnum<'c>>,
     enew: Option<ClNullEventPtrEnum<'c>>,
+    label: Option<&'c str>,
     mem_len: usize,
 }
 
@@ -187,6 +205,7 @@ impl<'c, T> BufferCmd<'c, T> where T: 'c + OclPrm {
             shape: BufferCmdDataShape::Lin { offset: 0 },
             ewait: None,
             enew: None,
+            label: None,
             mem_len,
         }
     }
@@ -346,6 +365,40 @@ impl<'c, T> BufferCmd<'c, T> where T: 'c + OclPrm {
         self
     }
 
+    /// Specifies that this command will acquire a buffer created from a
+    /// Direct3D 11 buffer (see `Buffer::from_d3d11_buffer`).
+    ///
+    /// If `.block(..)` has been set it will be ignored.
+    ///
+    /// ## Panics
+    ///
+    /// The command operation kind must not have already been specified
+    ///
+    #[cfg(all(windows, feature = "d3d11_sharing"))]
+    pub fn d3d11_acquire(mut self) -> BufferCmd<'c, T> {
+        assert!(self.kind.is_unspec(), "ocl::BufferCmd::d3d11_acquire(): Operation kind \
+            already set for this command.");
+        self.kind = BufferCmdKind::D3d11Acquire;
+        self
+    }
+
+    /// Specifies that this command will release a buffer created from a
+    /// Direct3D 11 buffer.
+    ///
+    /// If `.block(..)` has been set it will be ignored.
+    ///
+    /// ## Panics
+    ///
+    /// The command operation kind must not have already been specified
+    ///
+    #[cfg(all(windows, feature = "d3d11_sharing"))]
+    pub fn d3d11_release(mut self) -> BufferCmd<'c, T> {
+        assert!(self.kind.is_unspec(), "ocl::BufferCmd::d3d11_release(): Operation kind \
+            already set for this command.");
+        self.kind = BufferCmdKind::D3d11Release;
+        self
+    }
+
     /// Specifies that this command will be a fill operation.
     ///
     /// If `.block(..)` has been set it will be ignored.
@@ -481,7 +534,8 @@ impl<'c, T> BufferCmd<'c, T> where T: 'c + OclPrm {
     /// associated with this command.
     ///
     /// The destination can be a mutable reference to an empty event (created
-    /// using [`Event::empty`]) or a mutable reference to an event list.
+    /// using [`Event::empty`]), to an `Option<Event>` (replacing any event it
+    /// holds), or to an event list.
     ///
     /// After this command is enqueued, the event in the destination can be
     /// passed to the `::ewait` method of another command. Doing so will cause
@@ -514,14 +568,27 @@ impl<'c, T> BufferCmd<'c, T> where T: 'c + OclPrm {
         self
     }
 
+
+    /// Attaches a label to the new event created by this command (see
+    /// `Event::set_label`).
+    ///
+    /// Has no effect unless a destination for the new event is also set
+    /// with `::enew`.
+    pub fn label(mut self, label: &'c str) -> BufferCmd<'c, T> {
+        self.label = Some(label);
+        self
+    }
+
     /// Enqueues this command.
-    pub fn enq(self) -> OclResult<()> {
+    pub fn enq(mut self) -> OclResult<()> {
         let queue = match self.queue {
             Some(q) => q,
             None => return Err("BufferCmd::enq: No queue set.".into()),
         };
+        let mut tracker = Tracker::new(queue, self.enew.take())?.labeled(self.label);
+        self.ewait = queue.fan_in_wait_list(tracker.wait_list(self.ewait.take()))?;
 
-        match self.kind {
+        let result = match self.kind {
             BufferCmdKind::Copy { dst_buffer, dst_offset, len } => {
                 match self.shape {
                     BufferCmdDataShape::Lin { offset } => {
@@ -531,7 +598,7 @@ impl<'c, T> BufferCmd<'c, T> where T: 'c + OclPrm {
 
                         core::enqueue_copy_buffer::<T, _, _, _>(queue,
                             &self.buffer.obj_core, dst_buffer, offset, dst_offset, len,
-                            self.ewait, self.enew).map_err(OclError::from)
+                            self.ewait, tracker.enew()).map_err(OclError::from)
                     },
                     BufferCmdDataShape::Rect { src_origin, dst_origin, region,
                         src_row_pitch_bytes, src_slc_pitch_bytes, dst_row_pitch_bytes,
@@ -546,7 +613,7 @@ impl<'c, T> BufferCmd<'c, T> where T: 'c + OclPrm {
                         core::enqueue_copy_buffer_rect::<T, _, _, _>(queue, &self.buffer.obj_core,
                             dst_buffer, src_origin, dst_origin, region, src_row_pitch_bytes,
                             src_slc_pitch_bytes, dst_row_pitch_bytes, dst_slc_pitch_bytes,
-                            self.ewait, self.enew).map_err(OclError::from)
+                            self.ewait, tracker.enew()).map_err(OclError::from)
                     },
                 }
             },
@@ -563,7 +630,7 @@ impl<'c, T> BufferCmd<'c, T> where T: 'c + OclPrm {
                         check_len(self.mem_len, len, offset)?;
 
                         core::enqueue_fill_buffer(queue, &self.buffer.obj_core, pattern,
-                            offset, len, self.ewait, self.enew, Some(&queue.device_version()))
+                            offset, len, self.ewait, tracker.enew(), Some(&queue.device_version()))
                             .map_err(OclError::from)
                     },
                     BufferCmdDataShape::Rect { .. } => Err(
@@ -574,13 +641,27 @@ impl<'c, T> BufferCmd<'c, T> where T: 'c + OclPrm {
             #[cfg(not(feature="opencl_vendor_mesa"))]
             BufferCmdKind::GLAcquire => {
                 let buf_slc = unsafe { std::slice::from_raw_parts(&self.buffer.obj_core, 1) };
-                core::enqueue_acquire_gl_objects(queue, buf_slc, self.ewait, self.enew).map_err(OclError::from)
+                core::enqueue_acquire_gl_objects(queue, buf_slc, self.ewait, tracker.enew()).map_err(OclError::from)
             },
 
             #[cfg(not(feature="opencl_vendor_mesa"))]
             BufferCmdKind::GLRelease => {
                 let buf_slc = unsafe { std::slice::from_raw_parts(&self.buffer.obj_core, 1) };
-                core::enqueue_release_gl_objects(queue, buf_slc, self.ewait, self.enew).map_err(OclError::from)
+                core::enqueue_release_gl_objects(queue, buf_slc, self.ewait, tracker.enew()).map_err(OclError::from)
+            },
+
+            #[cfg(all(windows, feature = "d3d11_sharing"))]
+            BufferCmdKind::D3d11Acquire => {
+                let buf_slc = std::slice::from_ref(&self.buffer.obj_core);
+                core::enqueue_acquire_d3d11_objects(queue, buf_slc, self.ewait, tracker.enew())
+                    .map_err(OclError::from)
+            },
+
+            #[cfg(all(windows, feature = "d3d11_sharing"))]
+            BufferCmdKind::D3d11Release => {
+                let buf_slc = std::slice::from_ref(&self.buffer.obj_core);
+                core::enqueue_release_d3d11_objects(queue, buf_slc, self.ewait, tracker.enew())
+                    .map_err(OclError::from)
             },
 
             BufferCmdKind::Unspecified => Err("ocl::BufferCmd::enq(): \
@@ -588,7 +669,9 @@ impl<'c, T> BufferCmd<'c, T> where T: 'c + OclPrm {
                 '.enq()'.".into()),
             BufferCmdKind::Map { .. } => unreachable!(),
             _ => unimplemented!(),
-        }
+        };
+        let result = tracker.finish(result);
+        queue.enqueued(result, false)
     }
 }
 
@@ -596,6 +679,7 @@ impl<'c, T> BufferCmd<'c, T> where T: 'c + OclPrm {
 /// The data destination for a buffer read command.
 pub enum ReadDst<'d, T> where T: 'd {
     Slice(&'d mut [T]),
+    Vec(Vec<T>),
     RwVec(RwVec<T>),
     Writer(FutureWriteGuard<Vec<T>>),
     None,
@@ -611,6 +695,7 @@ impl<'d, T> ReadDst<'d, T> {
             ReadDst::RwVec(ref rw_vec) => rw_vec.len_stale(),
             ReadDst::Writer(ref writer) => unsafe { (*writer.as_ptr()).len() },
             ReadDst::Slice(ref slice) => slice.len(),
+            ReadDst::Vec(ref vec) => vec.len(),
             ReadDst::None => 0,
         }
     }
@@ -628,6 +713,12 @@ impl<'d, T> From<&'d mut Vec<T>> for ReadDst<'d, T>  where T: OclPrm {
     }
 }
 
+impl<'d, T> From<Vec<T>> for ReadDst<'d, T>  where T: OclPrm {
+    fn from(vec: Vec<T>) -> ReadDst<'d, T> {
+        ReadDst::Vec(vec)
+    }
+}
+
 impl<'d, T> From<RwVec<T>> for ReadDst<'d, T> where T: OclPrm {
     fn from(rw_vec: RwVec<T>) -> ReadDst<'d, T> {
         ReadDst::RwVec(rw_vec)
@@ -800,7 +891,8 @@ impl<'c, 'd, T> BufferReadCmd<'c, 'd, T> where T: OclPrm {
     /// associated with this command.
     ///
     /// The destination can be a mutable reference to an empty event (created
-    /// using [`Event::empty`]) or a mutable reference to an event list.
+    /// using [`Event::empty`]), to an `Option<Event>` (replacing any event it
+    /// holds), or to an event list.
     ///
     /// After this command is enqueued, the event in the destination can be
     /// passed to the `::ewait` method of another command. Doing so will cause
@@ -833,6 +925,17 @@ impl<'c, 'd, T> BufferReadCmd<'c, 'd, T> where T: OclPrm {
         self
     }
 
+
+    /// Attaches a label to the new event created by this command (see
+    /// `Event::set_label`).
+    ///
+    /// Has no effect unless a destination for the new event is also set
+    /// with `::enew`.
+    pub fn label(mut self, label: &'c str) -> BufferReadCmd<'c, 'd, T> {
+        self.cmd.label = Some(label);
+        self
+    }
+
     /// Enqueues this command, blocking the current thread until it is complete.
     ///
     /// If an `RwVec` is being used as the data destination, the current
@@ -851,16 +954,37 @@ impl<'c, 'd, T> BufferReadCmd<'c, 'd, T> where T: OclPrm {
                 Some(q) => q,
                 None => return Err("BufferReadCmd::enq: No queue set.".into()),
             };
+            let mut tracker = Tracker::new(queue, self.cmd.enew.take())?.labeled(self.cmd.label);
+            self.cmd.ewait = queue.fan_in_wait_list(tracker.wait_list(self.cmd.ewait.take()))?;
 
-            match self.cmd.kind {
+            let result = match self.cmd.kind {
                 BufferCmdKind::Read => {
                     match self.cmd.shape {
                         BufferCmdDataShape::Lin { offset } => {
                             check_len(self.cmd.mem_len, dst.len(), offset)?;
-                            unsafe {
-                                core::enqueue_read_buffer(queue, &self.cmd.buffer.obj_core,
-                                    self.cmd.block, offset, dst, self.cmd.ewait.take(),
-                                    self.cmd.enew.take()).map_err(OclError::from)
+                            let policy = self.cmd.buffer.transfer_policy
+                                .unwrap_or(*queue.transfer_policy());
+
+                            match policy.chunks(dst.len(), std::mem::size_of::<T>()) {
+                                Some(chunks) => {
+                                    let buffer = &self.cmd.buffer.obj_core;
+                                    let transfer = ChunkedTransfer { queue,
+                                        kind: TransferKind::Read, chunks,
+                                        concurrency: policy.get_concurrency(),
+                                        elem_size: std::mem::size_of::<T>(), offset,
+                                        block: self.cmd.block };
+                                    transfer.enqueue(self.cmd.ewait.take(), tracker.enew(),
+                                        |range, ewait, enew| unsafe {
+                                            core::enqueue_read_buffer(queue, buffer, false,
+                                                offset + range.start, &mut dst[range], ewait,
+                                                Some(enew)).map_err(OclError::from)
+                                        })
+                                },
+                                None => unsafe {
+                                    core::enqueue_read_buffer(queue, &self.cmd.buffer.obj_core,
+                                        self.cmd.block, offset, dst, self.cmd.ewait.take(),
+                                        tracker.enew()).map_err(OclError::from)
+                                },
                             }
                         },
                         BufferCmdDataShape::Rect { src_origin, dst_origin, region, src_row_pitch_bytes,
@@ -872,13 +996,15 @@ impl<'c, 'd, T> BufferReadCmd<'c, 'd, T> where T: OclPrm {
                                     self.cmd.block, src_origin, dst_origin, region,
                                     src_row_pitch_bytes, src_slc_pitch_bytes, dst_row_pitch_bytes,
                                     dst_slc_pitch_bytes, dst, self.cmd.ewait.take(),
-                                    self.cmd.enew.take()).map_err(OclError::from)
+                                    tracker.enew()).map_err(OclError::from)
                             }
                         }
                     }
                 },
                 _ => unreachable!(),
-            }
+            };
+            let result = tracker.finish(result);
+            queue.enqueued(result, false)
         };
 
         match read_dst {
@@ -895,6 +1021,8 @@ impl<'c, 'd, T> BufferReadCmd<'c, 'd, T> where T: OclPrm {
                     .map_err(|_| OclError::from("Unable to obtain lock."))?;
                 enqueue_with_data(&mut guard.as_mut_slice()[range])
             }
+            ReadDst::Vec(_) => Err("BufferReadCmd::enq: An owned 'Vec' can only be read into \
+                using '::enq_async_vec'.".into()),
             ReadDst::None => panic!("Invalid read destination."),
         }
     }
@@ -928,7 +1056,8 @@ impl<'c, 'd, T> BufferReadCmd<'c, 'd, T> where T: OclPrm {
 
                 writer.create_lock_event(queue.context_ptr()?)?;
 
-                if let Some(wl) = self.cmd.ewait {
+                let tracker = Tracker::new(queue, self.cmd.enew.take())?.labeled(self.cmd.label);
+                if let Some(wl) = tracker.wait_list(self.cmd.ewait) {
                     writer.set_lock_wait_events(wl);
                 }
 
@@ -959,12 +1088,126 @@ impl<'c, 'd, T> BufferReadCmd<'c, 'd, T> where T: OclPrm {
                     }
                 }
 
-                if let Some(ref mut enew) = self.cmd.enew.take() {
-                    unsafe { enew.clone_from(&read_event) }
-                }
+                tracker.record(&read_event);
 
                 writer.set_command_wait_event(read_event);
-                Ok(writer)
+                queue.enqueued(Ok(writer), true)
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// Enqueues this command and returns a future which resolves to the
+    /// destination `Vec` once the read is complete.
+    ///
+    /// An owned `Vec` must have been passed to `::read`. It is kept alive
+    /// until the read completes, making this the way to perform a
+    /// non-blocking read without locking the destination.
+    ///
+    pub fn enq_async_vec(mut self) -> OclResult<FutureReadVec<T>> {
+        match self.cmd.kind {
+            BufferCmdKind::Read => {
+                let mut vec = match self.dst.take() {
+                    ReadDst::Vec(vec) => vec,
+                    _ => return Err("BufferReadCmd::enq_async_vec: Invalid data destination \
+                        kind. The read destination must be a 'Vec'.".into()),
+                };
+                if self.range.end > vec.len() { return Err(OclError::from(
+                    "Unable to enqueue buffer read command: Invalid src_offset and/or len.")) }
+
+                let queue = match self.cmd.queue {
+                    Some(q) => q,
+                    None => return Err("BufferCmd::enq: No queue set.".into()),
+                };
+
+                let tracker = Tracker::new(queue, self.cmd.enew.take())?.labeled(self.cmd.label);
+                let ewait = queue.fan_in_wait_list(tracker.wait_list(self.cmd.ewait.take()))?;
+                let dst = &mut vec[self.range.clone()];
+                let mut read_event = Event::empty();
+
+                match self.cmd.shape {
+                    BufferCmdDataShape::Lin { offset } => {
+                        check_len(self.cmd.mem_len, dst.len(), offset)?;
+                        unsafe {
+                            core::enqueue_read_buffer(queue, &self.cmd.buffer.obj_core, false,
+                                offset, dst, ewait, Some(&mut read_event))?;
+                        }
+                    },
+                    BufferCmdDataShape::Rect { src_origin, dst_origin, region,
+                            src_row_pitch_bytes, src_slc_pitch_bytes,
+                                dst_row_pitch_bytes, dst_slc_pitch_bytes } =>
+                    {
+                        unsafe {
+                            core::enqueue_read_buffer_rect(queue, &self.cmd.buffer.obj_core,
+                                false, src_origin, dst_origin, region, src_row_pitch_bytes,
+                                src_slc_pitch_bytes, dst_row_pitch_bytes, dst_slc_pitch_bytes,
+                                dst, ewait, Some(&mut read_event))?;
+                        }
+                    }
+                }
+
+                tracker.record(&read_event);
+
+                queue.enqueued(Ok(unsafe { FutureReadVec::new(vec, read_event) }), true)
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// Enqueues this command and returns a future which resolves to the
+    /// destination slice once the read is complete.
+    ///
+    /// A slice must have been passed to `::read`, borrowed for at least as
+    /// long as `scope`. The scope blocks on exit until the read completes,
+    /// making this a zero-copy, non-blocking read into borrowed memory. See
+    /// `ocl::async::scope`.
+    ///
+    pub fn enq_async_scoped(mut self, scope: &TransferScope<'d>)
+            -> OclResult<FutureScopedRead<'d, T>> {
+        match self.cmd.kind {
+            BufferCmdKind::Read => {
+                let slice = match self.dst.take() {
+                    ReadDst::Slice(slice) => slice,
+                    _ => return Err("BufferReadCmd::enq_async_scoped: Invalid data destination \
+                        kind. The read destination must be a slice.".into()),
+                };
+                if self.range.end > slice.len() { return Err(OclError::from(
+                    "Unable to enqueue buffer read command: Invalid src_offset and/or len.")) }
+
+                let queue = match self.cmd.queue {
+                    Some(q) => q,
+                    None => return Err("BufferCmd::enq: No queue set.".into()),
+                };
+
+                let tracker = Tracker::new(queue, self.cmd.enew.take())?.labeled(self.cmd.label);
+                let ewait = queue.fan_in_wait_list(tracker.wait_list(self.cmd.ewait.take()))?;
+                let dst = &mut slice[self.range.clone()];
+                let mut read_event = Event::empty();
+
+                match self.cmd.shape {
+                    BufferCmdDataShape::Lin { offset } => {
+                        check_len(self.cmd.mem_len, dst.len(), offset)?;
+                        unsafe {
+                            core::enqueue_read_buffer(queue, &self.cmd.buffer.obj_core, false,
+                                offset, &mut *dst, ewait, Some(&mut read_event))?;
+                        }
+                    },
+                    BufferCmdDataShape::Rect { src_origin, dst_origin, region,
+                            src_row_pitch_bytes, src_slc_pitch_bytes,
+                                dst_row_pitch_bytes, dst_slc_pitch_bytes } =>
+                    {
+                        unsafe {
+                            core::enqueue_read_buffer_rect(queue, &self.cmd.buffer.obj_core,
+                                false, src_origin, dst_origin, region, src_row_pitch_bytes,
+                                src_slc_pitch_bytes, dst_row_pitch_bytes, dst_slc_pitch_bytes,
+                                &mut *dst, ewait, Some(&mut read_event))?;
+                        }
+                    }
+                }
+
+                tracker.record(&read_event);
+
+                queue.enqueued(Ok(unsafe { FutureScopedRead::new(dst, read_event, scope) }), true)
             },
             _ => unreachable!(),
         }
@@ -975,6 +1218,7 @@ impl<'c, 'd, T> BufferReadCmd<'c, 'd, T> where T: OclPrm {
 /// The data destination for a buffer read command.
 pub enum WriteSrc<'d, T> where T: 'd {
     Slice(&'d [T]),
+    Vec(Vec<T>),
     RwVec(RwVec<T>),
     Reader(FutureReadGuard<Vec<T>>),
     None,
@@ -990,6 +1234,7 @@ impl<'d, T> WriteSrc<'d, T> {
             WriteSrc::RwVec(ref rw_vec) => rw_vec.len_stale(),
             WriteSrc::Reader(ref writer) => unsafe { (*writer.as_ptr()).len() },
             WriteSrc::Slice(slice) => slice.len(),
+            WriteSrc::Vec(ref vec) => vec.len(),
             WriteSrc::None => 0,
         }
     }
@@ -1007,6 +1252,12 @@ impl<'d, T> From<&'d Vec<T>> for WriteSrc<'d, T>  where T: OclPrm {
     }
 }
 
+impl<'d, T> From<Vec<T>> for WriteSrc<'d, T>  where T: OclPrm {
+    fn from(vec: Vec<T>) -> WriteSrc<'d, T> {
+        WriteSrc::Vec(vec)
+    }
+}
+
 impl<'d, T> From<RwVec<T>> for WriteSrc<'d, T> where T: OclPrm {
     fn from(rw_vec: RwVec<T>) -> WriteSrc<'d, T> {
         WriteSrc::RwVec(rw_vec)
@@ -1179,7 +1430,8 @@ impl<'c, 'd, T> BufferWriteCmd<'c, 'd, T> where T: OclPrm {
     /// associated with this command.
     ///
     /// The destination can be a mutable reference to an empty event (created
-    /// using [`Event::empty`]) or a mutable reference to an event list.
+    /// using [`Event::empty`]), to an `Option<Event>` (replacing any event it
+    /// holds), or to an event list.
     ///
     /// After this command is enqueued, the event in the destination can be
     /// passed to the `::ewait` method of another command. Doing so will cause
@@ -1212,6 +1464,17 @@ impl<'c, 'd, T> BufferWriteCmd<'c, 'd, T> where T: OclPrm {
         self
     }
 
+
+    /// Attaches a label to the new event created by this command (see
+    /// `Event::set_label`).
+    ///
+    /// Has no effect unless a destination for the new event is also set
+    /// with `::enew`.
+    pub fn label(mut self, label: &'c str) -> BufferWriteCmd<'c, 'd, T> {
+        self.cmd.label = Some(label);
+        self
+    }
+
     /// Enqueues this command, blocking the current thread until it is complete.
     ///
     /// If an `RwVec` is being used as the data destination, the current
@@ -1231,16 +1494,38 @@ impl<'c, 'd, T> BufferWriteCmd<'c, 'd, T> where T: OclPrm {
                 None => return Err("BufferCmd::enq: No queue set.".into()),
             };
 
-            match self.cmd.kind {
+            if queue.quirks().blocking_writes { self.cmd.block = true; }
+            let mut tracker = Tracker::new(queue, self.cmd.enew.take())?.labeled(self.cmd.label);
+            self.cmd.ewait = queue.fan_in_wait_list(tracker.wait_list(self.cmd.ewait.take()))?;
+
+            let result = match self.cmd.kind {
                 BufferCmdKind::Write => {
                     match self.cmd.shape {
                         BufferCmdDataShape::Lin { offset } => {
                             check_len(self.cmd.mem_len, src.len(), offset)?;
-
-                            unsafe {
-                                core::enqueue_write_buffer(queue, &self.cmd.buffer.obj_core, self.cmd.block,
-                                    offset, src, self.cmd.ewait.take(), self.cmd.enew.take())
-                                    .map_err(OclError::from)
+                            let policy = self.cmd.buffer.transfer_policy
+                                .unwrap_or(*queue.transfer_policy());
+
+                            match policy.chunks(src.len(), std::mem::size_of::<T>()) {
+                                Some(chunks) => {
+                                    let buffer = &self.cmd.buffer.obj_core;
+                                    let transfer = ChunkedTransfer { queue,
+                                        kind: TransferKind::Write, chunks,
+                                        concurrency: policy.get_concurrency(),
+                                        elem_size: std::mem::size_of::<T>(), offset,
+                                        block: self.cmd.block };
+                                    transfer.enqueue(self.cmd.ewait.take(), tracker.enew(),
+                                        |range, ewait, enew| unsafe {
+                                            core::enqueue_write_buffer(queue, buffer, false,
+                                                offset + range.start, &src[range], ewait,
+                                                Some(enew)).map_err(OclError::from)
+                                        })
+                                },
+                                None => unsafe {
+                                    core::enqueue_write_buffer(queue, &self.cmd.buffer.obj_core,
+                                        self.cmd.block, offset, src, self.cmd.ewait.take(),
+                                        tracker.enew()).map_err(OclError::from)
+                                },
                             }
                         },
                         BufferCmdDataShape::Rect { src_origin, dst_origin, region,
@@ -1251,20 +1536,24 @@ impl<'c, 'd, T> BufferWriteCmd<'c, 'd, T> where T: OclPrm {
                                 core::enqueue_write_buffer_rect(queue, &self.cmd.buffer.obj_core,
                                     self.cmd.block, src_origin, dst_origin, region, src_row_pitch_bytes,
                                     src_slc_pitch_bytes, dst_row_pitch_bytes, dst_slc_pitch_bytes,
-                                    src, self.cmd.ewait.take(), self.cmd.enew.take())
+                                    src, self.cmd.ewait.take(), tracker.enew())
                                     .map_err(OclError::from)
                             }
                         }
                     }
                 },
                 _ => unreachable!(),
-            }
+            };
+            let result = tracker.finish(result);
+            queue.enqueued(result, false)
         };
 
         match write_src {
             WriteSrc::Slice(slice) => {
                 enqueue_with_data(&slice[range])
             },
+            WriteSrc::Vec(_) => Err("BufferWriteCmd::enq: An owned 'Vec' can only be written \
+                using '::enq_async_vec'.".into()),
             WriteSrc::RwVec(rw_vec) => {
                 let guard = rw_vec.read().wait()
                     .map_err(|_| OclError::from("Unable to obtain lock."))?;
@@ -1300,15 +1589,16 @@ impl<'c, 'd, T> BufferWriteCmd<'c, 'd, T> where T: OclPrm {
                 if self.range.end > reader_len { return Err(OclError::from(
                     "Unable to enqueue buffer write command: Invalid src_offset and/or len.")) }
 
-                if let Some(wl) = self.cmd.ewait {
-                    reader.set_lock_wait_events(wl);
-                }
-
                 let queue = match self.cmd.queue {
                     Some(q) => q,
                     None => return Err("BufferCmd::enq: No queue set.".into()),
                 };
 
+                let tracker = Tracker::new(queue, self.cmd.enew.take())?.labeled(self.cmd.label);
+                if let Some(wl) = tracker.wait_list(self.cmd.ewait) {
+                    reader.set_lock_wait_events(wl);
+                }
+
                 reader.create_lock_event(queue.context_ptr()?)?;
 
                 let src = unsafe {
@@ -1338,12 +1628,10 @@ impl<'c, 'd, T> BufferWriteCmd<'c, 'd, T> where T: OclPrm {
                     }
                 }
 
-                if let Some(ref mut enew) = self.cmd.enew.take() {
-                    unsafe { enew.clone_from(&write_event) }
-                }
+                tracker.record(&write_event);
 
                 reader.set_command_wait_event(write_event);
-                Ok(reader)
+                queue.enqueued(Ok(reader), true)
             },
             _ => unreachable!(),
         }
@@ -1359,7 +1647,9 @@ impl<'c, 'd, T> BufferWriteCmd<'c, 'd, T> where T: OclPrm {
     /// A data destination container appropriate for an asynchronous operation
     /// (such as `RwVec`) must have been passed to `::write`.
     ///
-    /// The returned future must be resolved.
+    /// The returned future must be resolved. It resolves to an error if
+    /// another read lock on the same data is upgradable when the command
+    /// completes (see `FutureGuard::upgrade_after_command`).
     ///
     pub fn enq_async_then_write(self) -> OclResult<FutureWriteGuard<Vec<T>>> {
         // NOTE: The precise point in time at which `::upgrade_after_command`
@@ -1370,6 +1660,63 @@ impl<'c, 'd, T> BufferWriteCmd<'c, 'd, T> where T: OclPrm {
         // request interfering with the global r/w order.
         self.enq_async().map(|read_guard| read_guard.upgrade_after_command())
     }
+
+    /// Enqueues this command and returns a future which resolves to the
+    /// source `Vec` once the write is complete.
+    ///
+    /// An owned `Vec` must have been passed to `::write`. It is kept alive
+    /// until the write completes, making this the way to perform a
+    /// non-blocking write without copying or locking the source data.
+    ///
+    pub fn enq_async_vec(mut self) -> OclResult<FutureWriteVec<T>> {
+        match self.cmd.kind {
+            BufferCmdKind::Write => {
+                let vec = match self.src.take() {
+                    WriteSrc::Vec(vec) => vec,
+                    _ => return Err("BufferWriteCmd::enq_async_vec: Invalid data source kind. \
+                        The write source must be a 'Vec'.".into()),
+                };
+                if self.range.end > vec.len() { return Err(OclError::from(
+                    "Unable to enqueue buffer write command: Invalid src_offset and/or len.")) }
+
+                let queue = match self.cmd.queue {
+                    Some(q) => q,
+                    None => return Err("BufferCmd::enq: No queue set.".into()),
+                };
+
+                let tracker = Tracker::new(queue, self.cmd.enew.take())?.labeled(self.cmd.label);
+                let ewait = queue.fan_in_wait_list(tracker.wait_list(self.cmd.ewait.take()))?;
+                let src = &vec[self.range.clone()];
+                let mut write_event = Event::empty();
+
+                match self.cmd.shape {
+                    BufferCmdDataShape::Lin { offset } => {
+                        check_len(self.cmd.mem_len, src.len(), offset)?;
+                        unsafe {
+                            core::enqueue_write_buffer(queue, &self.cmd.buffer.obj_core, false,
+                                offset, src, ewait, Some(&mut write_event))?;
+                        }
+                    },
+                    BufferCmdDataShape::Rect { src_origin, dst_origin, region,
+                            src_row_pitch_bytes, src_slc_pitch_bytes,
+                                dst_row_pitch_bytes, dst_slc_pitch_bytes } =>
+                    {
+                        unsafe {
+                            core::enqueue_write_buffer_rect(queue, &self.cmd.buffer.obj_core,
+                                false, src_origin, dst_origin, region, src_row_pitch_bytes,
+                                src_slc_pitch_bytes, dst_row_pitch_bytes, dst_slc_pitch_bytes,
+                                src, ewait, Some(&mut write_event))?;
+                        }
+                    }
+                }
+
+                tracker.record(&write_event);
+
+                queue.enqueued(Ok(unsafe { FutureWriteVec::new(vec, write_event) }), true)
+            },
+            _ => unreachable!(),
+        }
+    }
 }
 
 
@@ -1527,7 +1874,8 @@ impl<'c, T> BufferMapCmd<'c, T> where T: OclPrm {
     /// associated with this command.
     ///
     /// The destination can be a mutable reference to an empty event (created
-    /// using [`Event::empty`]) or a mutable reference to an event list.
+    /// using [`Event::empty`]), to an `Option<Event>` (replacing any event it
+    /// holds), or to an event list.
     ///
     /// After this command is enqueued, the event in the destination can be
     /// passed to the `::ewait` method of another command. Doing so will cause
@@ -1560,6 +1908,17 @@ impl<'c, T> BufferMapCmd<'c, T> where T: OclPrm {
         self
     }
 
+
+    /// Attaches a label to the new event created by this command (see
+    /// `Event::set_label`).
+    ///
+    /// Has no effect unless a destination for the new event is also set
+    /// with `::enew`.
+    pub fn label(mut self, label: &'c str) -> BufferMapCmd<'c, T> {
+        self.cmd.label = Some(label);
+        self
+    }
+
     /// Returns operation details.
     #[inline]
     fn enq_details(&mut self) -> OclResult<(usize, usize, Queue, MapFlags,
@@ -1601,11 +1960,16 @@ impl<'c, T> BufferMapCmd<'c, T> where T: OclPrm {
     /// other synchronization mechanism to ensure this.
     pub unsafe fn enq(mut self) -> OclResult<MemMap<T>> {
         let (offset, len, queue, flags, ewait, enew, /*is_mapped*/) = self.enq_details()?;
+        let mut tracker = Tracker::new(&queue, enew)?.labeled(self.cmd.label);
+        let ewait = queue.fan_in_wait_list(tracker.wait_list(ewait))?;
 
-        let mm_core = core::enqueue_map_buffer::<T, _, _, _>(&queue,
-            &self.cmd.buffer.obj_core, true, flags, offset, len, ewait, enew)?;
+        let result = core::enqueue_map_buffer::<T, _, _, _>(&queue,
+            &self.cmd.buffer.obj_core, true, flags, offset, len, ewait, tracker.enew())
+            .map_err(OclError::from);
+        let mm_core = tracker.finish(result)?;
 
         let unmap_event = None;
+        queue.enqueued(Ok(()), false)?;
 
         Ok(MemMap::new(mm_core, len, None, unmap_event, self.cmd.buffer.obj_core.clone(),
             queue))
@@ -1625,6 +1989,8 @@ impl<'c, T> BufferMapCmd<'c, T> where T: OclPrm {
     /// other synchronization mechanism to ensure this.
     pub unsafe fn enq_async(mut self) -> OclResult<FutureMemMap<T>> {
         let (offset, len, queue, flags, ewait, enew, /*is_mapped*/) = self.enq_details()?;
+        let tracker = Tracker::new(&queue, enew)?.labeled(self.cmd.label);
+        let ewait = queue.fan_in_wait_list(tracker.wait_list(ewait))?;
 
         let mut map_event = Event::empty();
 
@@ -1632,17 +1998,32 @@ impl<'c, T> BufferMapCmd<'c, T> where T: OclPrm {
             &self.cmd.buffer.obj_core, false, flags, offset, len, ewait,
             Some(&mut map_event))?;
 
-        // If a 'new/null event' has been set, copy pointer
-        // into it and increase refcount (to 2).
-        if let Some(mut self_enew) = enew {
-            // map_event/self_enew refcount: 2
-            self_enew.clone_from(&map_event)
-        }
+        // Copies the event into any 'new/null event' set (refcount: 2):
+        tracker.record(&map_event);
+        queue.enqueued(Ok(()), true)?;
 
         Ok(FutureMemMap::new(mm_core, len, map_event,
             self.cmd.buffer.obj_core.clone(), queue, /*is_mapped*/))
 
     }
+
+    /// Enqueues a read-only map command and returns a future which resolves
+    /// to a shareable `SharedMemMap`.
+    ///
+    /// The region is always mapped with `MAP_READ`, overriding any flags
+    /// specified. Clones of the resolved `SharedMemMap` can be read from
+    /// multiple threads at once and the region is unmapped when the last one
+    /// is dropped.
+    ///
+    /// ## Safety
+    ///
+    /// The caller must ensure that no other mapping allows writes to the
+    /// same memory region while any clone of the resulting `SharedMemMap`
+    /// exists.
+    pub unsafe fn enq_async_shared(mut self) -> OclResult<FutureSharedMemMap<T>> {
+        self.flags = Some(crate::flags::MAP_READ);
+        self.enq_async().map(FutureMemMap::shared)
+    }
 }
 
 
@@ -1658,6 +2039,8 @@ pub struct Buffer<T: OclPrm> {
     queue: Option<Queue>,
     len: usize,
     offset: Option<usize>,
+    transfer_policy: Option<TransferPolicy>,
+    allocation: Option<Arc<AllocationRecord>>,
     _data: PhantomData<T>,
 }
 
@@ -1684,6 +2067,7 @@ impl<T: OclPrm> Buffer<T> {
     /// [`BufferBuilder`]: builders/struct.BufferBuilder.html
     /// [SDK]: https://www.khronos.org/registry/cl/sdk/1.2/docs/man/xhtml/clCreateBuffer.html
     ///
+    #[track_caller]
     pub unsafe fn new<'e, 'o, Q, D>(que_ctx: Q, flags: MemFlags, len: D,
             host_slice: Option<&[T]>) -> OclResult<Buffer<T>>
             where Q: Into<QueCtx<'o>>, D: Into<SpatialDims> {
@@ -1700,6 +2084,8 @@ impl<T: OclPrm> Buffer<T> {
             QueCtx::Context(c) => c,
         };
 
+        let reservation = allocations::reserve(ctx_ref, que_ctx.device(),
+            (len * std::mem::size_of::<T>()) as u64)?;
         let obj_core = core::create_buffer(ctx_ref, flags, len, host_slice)?;
 
         debug_assert!({
@@ -1710,11 +2096,16 @@ impl<T: OclPrm> Buffer<T> {
             size_info >= (::std::mem::size_of::<T>() * len)
         });
 
+        let allocation = allocations::track(AllocationKind::Buffer, reservation, flags,
+            Some(Location::caller()));
+
         let buf = Buffer {
             obj_core,
             queue: que_ctx.into(),
             len,
             offset: None,
+            transfer_policy: None,
+            allocation: Some(allocation),
             _data: PhantomData,
         };
 
@@ -1734,33 +2125,85 @@ impl<T: OclPrm> Buffer<T> {
     /// for more info.
     ///
     #[cfg(not(feature="opencl_vendor_mesa"))]
+    #[track_caller]
     pub fn from_gl_buffer<'o, Q>(que_ctx: Q, flags_opt: Option<MemFlags>, gl_object: cl_GLuint)
             -> OclResult<Buffer<T>>
             where Q: Into<QueCtx<'o>> {
         let flags = flags_opt.unwrap_or(core::MEM_READ_WRITE);
         let que_ctx = que_ctx.into();
 
-        let obj_core = match que_ctx {
-            QueCtx::Queue(ref q) => unsafe { core::create_from_gl_buffer(&q.context(), gl_object, flags)? },
-            QueCtx::Context(c) => unsafe { core::create_from_gl_buffer(c, gl_object, flags)? },
-        };
+        let context = que_ctx.context_cloned();
+        let obj_core = unsafe { core::create_from_gl_buffer(&context, gl_object, flags)? };
 
-        let len = match core::get_mem_object_info(&obj_core, MemInfo::Size)? {
-            MemInfoResult::Size(len_bytes) => len_bytes / ::std::mem::size_of::<T>(),
+        let len_bytes = match core::get_mem_object_info(&obj_core, MemInfo::Size)? {
+            MemInfoResult::Size(len_bytes) => len_bytes,
             _ => unreachable!(),
         };
+        let len = len_bytes / ::std::mem::size_of::<T>();
+
+        let reservation = allocations::reserve_unchecked(&context, que_ctx.device(),
+            len_bytes as u64);
+        let allocation = allocations::track(AllocationKind::Buffer, reservation, flags,
+            Some(Location::caller()));
 
         let buf = Buffer {
             obj_core,
             queue: que_ctx.into(),
             len,
             offset: None,
+            transfer_policy: None,
+            allocation: Some(allocation),
             _data: PhantomData,
         };
 
         Ok(buf)
     }
 
+    /// Creates a buffer linked to a Direct3D 11 buffer (`ID3D11Buffer *`).
+    ///
+    /// The context must have been created with a Direct3D 11 device (see
+    /// `ContextProperties::d3d11_device`). Use `.cmd().d3d11_acquire().enq()`
+    /// before using the buffer and `.cmd().d3d11_release().enq()` after.
+    ///
+    /// [UNTESTED]
+    ///
+    /// ## Safety
+    ///
+    /// `resource` must be a valid `ID3D11Buffer *` created by the context's
+    /// Direct3D device and must outlive the returned buffer.
+    ///
+    #[cfg(all(windows, feature = "d3d11_sharing"))]
+    #[track_caller]
+    pub unsafe fn from_d3d11_buffer<'o, Q>(que_ctx: Q, flags_opt: Option<MemFlags>,
+            resource: *mut crate::ffi::c_void) -> OclResult<Buffer<T>>
+            where Q: Into<QueCtx<'o>> {
+        let flags = flags_opt.unwrap_or(core::MEM_READ_WRITE);
+        let que_ctx = que_ctx.into();
+        let context = que_ctx.context_cloned();
+        let obj_core = core::create_from_d3d11_buffer(&context, flags, resource)?;
+
+        let len_bytes = match core::get_mem_object_info(&obj_core, MemInfo::Size)? {
+            MemInfoResult::Size(len_bytes) => len_bytes,
+            _ => unreachable!(),
+        };
+        let len = len_bytes / ::std::mem::size_of::<T>();
+
+        let reservation = allocations::reserve_unchecked(&context, que_ctx.device(),
+            len_bytes as u64);
+        let allocation = allocations::track(AllocationKind::Buffer, reservation, flags,
+            Some(Location::caller()));
+
+        Ok(Buffer {
+            obj_core,
+            queue: que_ctx.into(),
+            len,
+            offset: None,
+            transfer_policy: None,
+            allocation: Some(allocation),
+            _data: PhantomData,
+        })
+    }
+
     /// Returns a command builder used to read, write, copy, etc.
     ///
     /// Call `.enq()` to enqueue the command.
@@ -1951,6 +2394,35 @@ impl<T: OclPrm> Buffer<T> {
         self.queue.as_ref()
     }
 
+    /// Sets a policy used to split up large reads and writes of this buffer,
+    /// overriding the policy of the queue used. Pass `None` to use the
+    /// queue's policy.
+    pub fn set_transfer_policy(&mut self, policy: Option<TransferPolicy>) -> &mut Buffer<T> {
+        self.transfer_policy = policy;
+        self
+    }
+
+    /// Returns the transfer policy override for this buffer, if any.
+    #[inline]
+    pub fn transfer_policy(&self) -> Option<&TransferPolicy> {
+        self.transfer_policy.as_ref()
+    }
+
+    /// Assigns a tag identifying this buffer in allocation dumps (see
+    /// `Context::dump_allocations`).
+    ///
+    /// Tags are shared with clones and sub-buffers.
+    pub fn set_tag<S: Into<String>>(&self, tag: S) {
+        if let Some(ref allocation) = self.allocation {
+            allocation.set_tag(Some(tag.into()));
+        }
+    }
+
+    /// Returns the tag assigned to this buffer, if any.
+    pub fn tag(&self) -> Option<String> {
+        self.allocation.as_ref().and_then(|a| a.tag())
+    }
+
     /// Returns a reference to the core pointer wrapper, usable by functions in
     /// the `core` module.
     ///
@@ -2056,6 +2528,8 @@ impl<T: OclPrm> Buffer<T> {
             // Share mapped status with super-buffer:
             // is_mapped: self.is_mapped.clone(),
             offset: Some(offset),
+            transfer_policy: self.transfer_policy,
+            allocation: self.allocation.clone(),
             _data: PhantomData,
         })
     }
@@ -2136,7 +2610,8 @@ pub struct BufferBuilder<'a, T> where T: OclPrm {
     flags: Option<MemFlags>,
     host_slice: HostSlice<'a, T>,
     len: usize,
-    fill_val: Option<(T, Option<ClNullEventPtrEnum<'a>>)>
+    fill_val: Option<(T, Option<ClNullEventPtrEnum<'a>>)>,
+    tag: Option<String>,
 }
 
 impl<'a, T> BufferBuilder<'a, T> where T: 'a + OclPrm {
@@ -2148,6 +2623,7 @@ impl<'a, T> BufferBuilder<'a, T> where T: 'a + OclPrm {
             host_slice: HostSlice::None,
             len: 0,
             fill_val: None,
+            tag: None,
         }
     }
 
@@ -2330,10 +2806,18 @@ impl<'a, T> BufferBuilder<'a, T> where T: 'a + OclPrm {
         self
     }
 
+    /// Assigns a tag identifying the buffer in allocation dumps (see
+    /// `Context::dump_allocations`).
+    pub fn tag<S: Into<String>>(mut self, tag: S) -> BufferBuilder<'a, T> {
+        self.tag = Some(tag.into());
+        self
+    }
+
     /// Creates a buffer and returns it.
     ///
     /// Dimensions and either a context or default queue must be specified
     /// before calling `::build`.
+    #[track_caller]
     pub fn build(self) -> OclResult<Buffer<T>> {
         let mut flags = match self.flags {
             Some(f) => f,
@@ -2375,6 +2859,9 @@ impl<'a, T> BufferBuilder<'a, T> where T: 'a + OclPrm {
         };
 
         let buf = unsafe { Buffer::new(qc, flags, len, host_slice)? };
+        if let Some(tag) = self.tag {
+            buf.set_tag(tag);
+        }
 
         // Fill buffer if `fill_val` and a queue have been specified,
         // blocking if the `fill_event` is `None`.
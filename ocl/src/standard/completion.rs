@@ -0,0 +1,264 @@
+//! Callback-driven wake-ups for futures waiting on events.
+//!
+//! The first time a task waits on a given event, a single completion
+//! callback is set on that event. Later polls, from the same or other tasks,
+//! only add the task to the event's list of waiters, so polling a future
+//! repeatedly does not pile up driver callbacks.
+//!
+//! Completion callbacks run on driver threads, some of which must not be
+//! blocked or re-entered. The callback therefore only forwards the event to
+//! a dedicated waker thread, which wakes each waiting task. With
+//! `Device::set_pin_internal_threads` enabled, there is one waker thread per
+//! NUMA node, pinned to that node.
+//!
+//! Because of this, waiting requires neither a particular executor nor a
+//! blocking thread per future. The event's queue is flushed when the
+//! callback is set so that the command is guaranteed to be submitted to
+//! the device (and eventually complete) regardless of the flush policy in
+//! use.
+//!
+//! The same mechanism runs deferred clean-up (see `on_complete`), such as
+//! freeing memory used by a command whose future has been dropped. Clean-up
+//! may block, so the waker thread hands it off to a separate thread.
+
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::task::{Wake, Waker};
+use crate::ffi::{c_void, cl_event};
+use crate::core::{self, guard_callback, EventInfo, EventInfoResult, CommandType};
+use crate::error::{Error as OclError, Result as OclResult};
+use crate::standard::{Device, Event};
+use crate::standard::locality;
+
+
+// Wakers of the tasks waiting on each event, along with the NUMA node of the
+// waker thread to wake them on. Each entry retains its event so that the
+// event's pointer, passed to the completion callback, can not be reused by
+// another event until the entry is removed on completion.
+type Waiter = (Event, Option<u32>, Vec<Waker>);
+static WAITERS: Mutex<Vec<Waiter>> = Mutex::new(Vec::new());
+
+// A waker thread for each NUMA node (`None`: unpinned).
+static WAKERS: Mutex<Vec<(Option<u32>, Sender<usize>)>> = Mutex::new(Vec::new());
+
+// The thread running deferred functions.
+static DEFERRED: Mutex<Option<Sender<Arc<Deferred>>>> = Mutex::new(None);
+
+
+/// Arranges for `waker` to be woken once `event` completes (successfully or
+/// otherwise).
+///
+/// To be called from within `Future::poll`, with the waker of its context.
+pub(crate) fn register(event: &Event, waker: &Waker) -> OclResult<()> {
+    let key = event_key(event);
+
+    if add_waiter(key, waker, None) { return Ok(()); }
+    // Looked up without holding the lock as it queries the driver:
+    let node = event_node(event);
+    if add_waiter(key, waker, Some((event, node))) { return Ok(()); }
+
+    // The lock must not be held here: some drivers run the callback
+    // immediately (on this thread) if the event has already completed.
+    let result = unsafe { event.set_callback(_forward_completion, key as *mut c_void) }
+        .map_err(OclError::from)
+        .and_then(|_| flush_queue(event));
+    if let Err(err) = result {
+        // Wake any tasks added in the meantime so they can observe the error.
+        wake(key);
+        return Err(err);
+    }
+    Ok(())
+}
+
+
+/// Removes `waker` from the waiters on `event`, if present.
+///
+/// For waits which end before the event completes. The event's entry, and
+/// its callback, remain until it does.
+pub(crate) fn deregister(event: &Event, waker: &Waker) {
+    let key = event_key(event);
+    let mut waiters = WAITERS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(entry) = waiters.iter_mut().find(|(e, _, _)| event_key(e) == key) {
+        entry.2.retain(|w| !w.will_wake(waker));
+    }
+}
+
+
+/// Returns the pointer of `event`, identifying it within `WAITERS`.
+fn event_key(event: &Event) -> usize {
+    unsafe { *event.as_core().as_ptr_ref() as usize }
+}
+
+
+/// Adds `waker` to the waiters on the event with pointer `key`, returning
+/// true, if there are any. Otherwise, if `new` is specified, adds a new entry
+/// for its event to be woken on its node's waker thread.
+fn add_waiter(key: usize, waker: &Waker, new: Option<(&Event, Option<u32>)>) -> bool {
+    let mut waiters = WAITERS.lock().unwrap_or_else(|e| e.into_inner());
+    match waiters.iter_mut().find(|(e, _, _)| event_key(e) == key) {
+        Some(&mut (_, _, ref mut wakers)) => {
+            if !wakers.iter().any(|w| w.will_wake(waker)) {
+                wakers.push(waker.clone());
+            }
+            true
+        },
+        None => {
+            if let Some((event, node)) = new {
+                waiters.push((event.clone(), node, vec![waker.clone()]));
+            }
+            false
+        },
+    }
+}
+
+
+/// Calls `f` on the deferred function thread once `event` completes
+/// (successfully or otherwise).
+///
+/// Unlike `Event::on_complete`, `f` may block or enqueue commands, though
+/// doing so delays other deferred functions. If a callback can not be set on
+/// `event`, blocks until it completes and calls `f` on the current thread
+/// instead.
+pub(crate) fn on_complete<F>(event: &Event, f: F) where F: FnOnce() + Send + 'static {
+    let deferred = Arc::new(Deferred { event: event.clone(), f: Mutex::new(Some(Box::new(f))) });
+    if register(event, &Waker::from(deferred.clone())).is_err() {
+        deferred.run();
+    }
+}
+
+
+/// A function deferred until an event completes, handed off to the deferred
+/// function thread when woken.
+struct Deferred {
+    event: Event,
+    f: Mutex<Option<Box<dyn FnOnce() + Send>>>,
+}
+
+impl Deferred {
+    /// Waits for the event to complete then calls the function, if it has
+    /// not already been called.
+    fn run(&self) {
+        let f = self.f.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(f) = f {
+            // Returns immediately unless the callback could not be set:
+            self.event.wait_for().ok();
+            f();
+        }
+    }
+}
+
+impl Wake for Deferred {
+    fn wake(self: Arc<Self>) {
+        send_to_deferred(self);
+    }
+}
+
+
+/// Sends `deferred` to the deferred function thread, starting it if
+/// necessary (or if a previous function panicked).
+fn send_to_deferred(deferred: Arc<Deferred>) {
+    let mut sender = DEFERRED.lock().unwrap_or_else(|e| e.into_inner());
+    let deferred = match *sender {
+        Some(ref tx) => match tx.send(deferred) {
+            Ok(()) => return,
+            Err(mpsc::SendError(deferred)) => deferred,
+        },
+        None => deferred,
+    };
+
+    let (tx, rx) = mpsc::channel::<Arc<Deferred>>();
+    let spawned = thread::Builder::new().name("ocl-deferred".to_owned()).spawn(move || {
+        for deferred in rx {
+            deferred.run();
+        }
+    });
+    match spawned {
+        Ok(_) => {
+            tx.send(deferred).expect("ocl::standard::completion: Deferred thread exited.");
+            *sender = Some(tx);
+        },
+        // Run here rather than never.
+        Err(_) => deferred.run(),
+    }
+}
+
+
+/// Returns the NUMA node of the waker thread for `event`: that of the device
+/// its command was enqueued on, if internal threads are pinned.
+fn event_node(event: &Event) -> Option<u32> {
+    match event.info(EventInfo::CommandType) {
+        Ok(EventInfoResult::CommandType(CommandType::User)) | Err(_) => None,
+        _ => event.queue_core().ok().and_then(|queue| queue.device().ok())
+            .and_then(|device| locality::internal_thread_node(&Device::from(device))),
+    }
+}
+
+
+/// Flushes the queue of the command associated with `event`, if any.
+///
+/// User events have no queue and are left alone.
+fn flush_queue(event: &Event) -> OclResult<()> {
+    match event.info(EventInfo::CommandType)? {
+        EventInfoResult::CommandType(CommandType::User) => Ok(()),
+        _ => core::flush(&event.queue_core()?).map_err(OclError::from),
+    }
+}
+
+
+/// Wakes and removes the tasks waiting on the event with pointer `key`.
+fn wake(key: usize) {
+    // The event is released once the lock is no longer held:
+    let (_event, _, wakers) = {
+        let mut waiters = WAITERS.lock().unwrap_or_else(|e| e.into_inner());
+        match waiters.iter().position(|(e, _, _)| event_key(e) == key) {
+            Some(idx) => waiters.swap_remove(idx),
+            None => return,
+        }
+    };
+    for waker in wakers {
+        waker.wake();
+    }
+}
+
+
+/// Sends `key` to the waker thread for its node, starting it if necessary.
+fn send_to_waker(key: usize) {
+    let node = {
+        let waiters = WAITERS.lock().unwrap_or_else(|e| e.into_inner());
+        match waiters.iter().find(|(e, _, _)| event_key(e) == key) {
+            Some(&(_, node, _)) => node,
+            None => return,
+        }
+    };
+
+    let mut wakers = WAKERS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(idx) = wakers.iter().position(|(n, _)| *n == node) {
+        if wakers[idx].1.send(key).is_ok() { return; }
+        wakers.swap_remove(idx);
+    }
+
+    let (tx, rx) = mpsc::channel::<usize>();
+    let spawned = thread::Builder::new().name("ocl-event-waker".to_owned()).spawn(move || {
+        if let Some(node) = node { locality::pin_current_thread(node).ok(); }
+        for key in rx {
+            wake(key);
+        }
+    });
+    match spawned {
+        Ok(_) => {
+            tx.send(key).expect("ocl::standard::completion: Waker thread exited.");
+            wakers.push((node, tx));
+        },
+        // Wake directly rather than leave the tasks waiting forever.
+        Err(_) => wake(key),
+    }
+}
+
+
+extern "C" fn _forward_completion(event_ptr: cl_event, event_status: i32, user_data: *mut c_void) {
+    guard_callback("_forward_completion", || {
+        let _ = (event_ptr, event_status);
+        send_to_waker(user_data as usize);
+    })
+}
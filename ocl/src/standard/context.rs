@@ -1,14 +1,132 @@
 //! An `OpenCL` context.
 
 use std;
+use std::ffi::CStr;
 use std::ops::{Deref, DerefMut};
-use crate::ffi::cl_context;
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::ffi::{c_char, c_void, size_t, cl_context, cl_context_properties};
 use crate::core::{self, Context as ContextCore, ContextProperties, ContextPropertyValue, ContextInfo,
     ContextInfoResult, DeviceInfo, DeviceInfoResult, PlatformInfo, PlatformInfoResult,
-    CreateContextCallbackFn, UserDataPtr, OpenclVersion, ClContextPtr, ClVersions};
+    CreateContextCallbackFn, UserDataPtr, OpenclVersion, ClContextPtr, ClVersions,
+    ClPlatformIdPtr, DeviceType, DeviceId as DeviceIdCore};
 use crate::core::error::{Result as OclCoreResult};
 use crate::error::{Error as OclError, Result as OclResult};
-use crate::standard::{Platform, Device, DeviceSpecifier};
+use crate::standard::{Platform, PlatformError, Device, DeviceSpecifier, DeviceError, Allocation,
+    MemoryUsage, DeviceCache, Queue};
+use crate::standard::allocations::ContextMemory;
+
+
+/// A closure receiving errors reported by the OpenCL implementation for a
+/// context (see `ContextBuilder::error_callback`).
+///
+/// It is passed the error message and a block of implementation-specific
+/// binary data, which may be empty.
+pub type ContextErrorFn = dyn Fn(&str, &[u8]) + Send + Sync;
+
+
+// The state shared by the `Context`s wrapping each OpenCL context, keyed by
+// context pointer. Each `Context` holds a strong reference, keeping the state
+// alive for as long as any of them is.
+static SHARED: Mutex<Vec<(usize, Weak<ContextShared>)>> = Mutex::new(Vec::new());
+
+// The error callbacks of live contexts, keyed by the id passed to
+// `_call_context_error_fn` as `user_data`. Ids are never reused, so a call
+// arriving after its entry is gone finds nothing rather than another closure.
+static ERROR_FNS: Mutex<Vec<(usize, Arc<ContextErrorFn>)>> = Mutex::new(Vec::new());
+static NEXT_ERROR_FN_ID: AtomicUsize = AtomicUsize::new(1);
+
+
+/// An entry in the error callback registry, removed when dropped.
+#[derive(Debug)]
+struct ErrorFnRegistration {
+    id: usize,
+}
+
+impl ErrorFnRegistration {
+    fn new(f: Arc<ContextErrorFn>) -> ErrorFnRegistration {
+        let id = NEXT_ERROR_FN_ID.fetch_add(1, Ordering::Relaxed);
+        ERROR_FNS.lock().unwrap_or_else(|e| e.into_inner()).push((id, f));
+        ErrorFnRegistration { id }
+    }
+
+    /// Returns the `user_data` identifying this entry to `_call_context_error_fn`.
+    fn user_data(&self) -> UserDataPtr {
+        self.id as UserDataPtr
+    }
+}
+
+impl Drop for ErrorFnRegistration {
+    fn drop(&mut self) {
+        ERROR_FNS.lock().unwrap_or_else(|e| e.into_inner()).retain(|(id, _)| *id != self.id);
+    }
+}
+
+
+/// State shared by every `Context` wrapping the same OpenCL context.
+#[derive(Debug)]
+struct ContextShared {
+    device_cache: DeviceCache,
+    // An `Arc` of its own, as it is also held by allocations, including
+    // those in the device cache.
+    memory: Arc<ContextMemory>,
+    error_fn: Mutex<Option<ErrorFnRegistration>>,
+}
+
+impl ContextShared {
+    /// Returns the state of `context`, creating it if no `Context` wrapping
+    /// it is alive.
+    fn for_context(context: &ContextCore) -> Arc<ContextShared> {
+        let key = context.as_ptr() as usize;
+        let mut shared = SHARED.lock().unwrap_or_else(|e| e.into_inner());
+        shared.retain(|(_, weak)| weak.strong_count() > 0);
+
+        if let Some(state) = shared.iter().find(|(k, _)| *k == key).and_then(|(_, w)| w.upgrade()) {
+            return state;
+        }
+        let state = Arc::new(ContextShared {
+            device_cache: DeviceCache::new(None),
+            memory: ContextMemory::for_context(context),
+            error_fn: Mutex::new(None),
+        });
+        shared.push((key, Arc::downgrade(&state)));
+        state
+    }
+}
+
+
+/// Returns the message and private info passed to a context error callback.
+unsafe fn error_info<'a>(errinfo: *const c_char, private_info: *const c_void, cb: size_t)
+        -> (std::borrow::Cow<'a, str>, &'a [u8]) {
+    let msg = if errinfo.is_null() {
+        "<no error info>".into()
+    } else {
+        CStr::from_ptr(errinfo).to_string_lossy()
+    };
+    let info = if private_info.is_null() || cb == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(private_info as *const u8, cb)
+    };
+    (msg, info)
+}
+
+/// Calls the `ContextErrorFn` registered under the id passed as `user_data`
+/// by `ContextBuilder::build`, if it is still registered.
+extern "C" fn _call_context_error_fn(errinfo: *const c_char, private_info: *const c_void,
+        cb: size_t, user_data: *mut c_void) {
+    core::guard_callback("_call_context_error_fn", || {
+        let id = user_data as usize;
+        let f = ERROR_FNS.lock().unwrap_or_else(|e| e.into_inner()).iter()
+            .find(|(k, _)| *k == id)
+            .map(|(_, f)| f.clone());
+
+        if let Some(f) = f {
+            let (msg, info) = unsafe { error_info(errinfo, private_info, cb) };
+            f(&msg, info)
+        }
+    })
+}
 
 
 
@@ -24,7 +142,7 @@ use crate::standard::{Platform, Device, DeviceSpecifier};
 //   `ContextInfoResult::Properties` before we can eliminate `platform`.
 //
 #[derive(Debug, Clone)]
-pub struct Context(ContextCore);
+pub struct Context(ContextCore, Arc<ContextShared>);
 
 impl Context {
     /// Returns a [`ContextBuilder`](/ocl/ocl/struct.ContextBuilder.html).
@@ -48,21 +166,15 @@ impl Context {
     /// * All devices associated with the 'NULL' platform
     /// * No notify callback function or user data.
     ///
+    /// `user_data` must remain valid for as long as the context exists.
+    ///
     /// Don't rely on these defaults, instead rely on the `ContextBuilder`
     /// defaults. In other words, use: `Context::builder().build().unwrap()`
     /// rather than `Context::new(None, None, None, None).unwrap()`.
     ///
-    /// ## Panics
-    ///
-    /// [TEMPORARY] Passing a `Some` variant for `pfn_notify` or `user_data` is
-    /// not yet supported. File an issue if you need this.
-    ///
     pub fn new(properties: Option<ContextProperties>, device_spec: Option<DeviceSpecifier>,
                 pfn_notify: Option<CreateContextCallbackFn>, user_data: Option<UserDataPtr>)
             -> OclResult<Context> {
-        assert!(pfn_notify.is_none() && user_data.is_none(),
-            "Context creation callbacks not yet implemented - file issue if you need this.");
-
         let platform: Option<Platform> = match properties {
             Some(ref props) => props.get_platform().map(Platform::new),
             None => None,
@@ -75,9 +187,41 @@ impl Context {
 
         let device_list = device_spec.to_device_list(platform)?;
 
+        if let DeviceSpecifier::Single(_) | DeviceSpecifier::List(_) = device_spec {
+            check_device_list(&device_list, platform)?;
+        }
+
         let obj_core = core::create_context(properties.as_ref(), &device_list, pfn_notify, user_data)?;
 
-        Ok(Context(obj_core))
+        Ok(Context::from(obj_core))
+    }
+
+    /// Returns a new context containing every device of type `device_type`
+    /// on the first platform which has any.
+    ///
+    /// Platforms are tried in the order of `Platform::list`. No error
+    /// callback is set for the context.
+    ///
+    /// ```rust,ignore
+    /// let context = Context::from_type(DeviceType::GPU)?;
+    /// ```
+    pub fn from_type(device_type: DeviceType) -> OclResult<Context> {
+        let mut last_err = None;
+
+        for platform in Platform::list()? {
+            let mut props = ContextProperties::new();
+            props.set_platform(platform);
+            match core::create_context_from_type::<DeviceIdCore>(Some(&props), device_type,
+                    None, None) {
+                Ok(obj_core) => return Ok(Context::from(obj_core)),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        match last_err {
+            Some(err) => Err(err.into()),
+            None => Err(PlatformError::no_platforms().into()),
+        }
     }
 
     /// Resolves a list of zero-based device indices into a list of Devices.
@@ -97,6 +241,15 @@ impl Context {
         self.resolve_wrapping_device_idxs(&[index; 1])[0]
     }
 
+    /// Returns a new queue for each device associated with this context, in
+    /// the order of `::devices`.
+    ///
+    /// Useful for multi-device contexts, where each device needs its own
+    /// queue.
+    pub fn queues_for_all_devices(&self) -> OclResult<Vec<Queue>> {
+        self.devices().into_iter().map(|device| Queue::new(self, device, None)).collect()
+    }
+
     /// Returns info about the platform associated with the context.
     pub fn platform_info(&self, info_kind: PlatformInfo) -> OclResult<PlatformInfoResult> {
         match self.platform() {
@@ -132,6 +285,22 @@ impl Context {
         &self.0
     }
 
+    /// Returns the reference count of this context.
+    pub fn reference_count(&self) -> OclResult<u32> {
+        match self.info(ContextInfo::ReferenceCount)? {
+            ContextInfoResult::ReferenceCount(count) => Ok(count),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the number of devices associated with this context.
+    pub fn num_devices(&self) -> OclResult<u32> {
+        match self.info(ContextInfo::NumDevices)? {
+            ContextInfoResult::NumDevices(num) => Ok(num),
+            _ => unreachable!(),
+        }
+    }
+
     /// Returns the list of devices associated with this context.
     ///
     /// Panics upon any OpenCL error.
@@ -139,6 +308,14 @@ impl Context {
         Device::list_from_core(self.0.devices().unwrap())
     }
 
+    /// Returns the properties this context was created with, decoded.
+    pub fn properties(&self) -> OclResult<ContextProperties> {
+        match self.info(ContextInfo::Properties)? {
+            ContextInfoResult::Properties(props) => Ok(props),
+            _ => unreachable!(),
+        }
+    }
+
     /// Returns the list of device versions associated with this context.
     pub fn device_versions(&self) -> OclResult<Vec<OpenclVersion>> {
         Device::list_from_core(self.0.devices().map_err(OclError::from)?).into_iter()
@@ -150,6 +327,59 @@ impl Context {
         self.0.platform().map(|opt| opt.map(Platform::from)).map_err(OclError::from)
     }
 
+    /// Returns every buffer and image currently alive within this context,
+    /// largest first.
+    ///
+    /// Each entry includes the size, flags, creation call site, and tag (see
+    /// `BufferBuilder::tag`) of the memory object. Clones and sub-buffers of
+    /// a buffer are not listed separately.
+    pub fn dump_allocations(&self) -> Vec<Allocation> {
+        self.1.memory.live()
+    }
+
+    /// Returns the memory used by buffers and images currently alive within
+    /// this context, in total and for each device.
+    ///
+    /// Memory objects created with a queue are attributed to the queue's
+    /// device. Interop objects (e.g. `Buffer::from_gl_buffer`) are included
+    /// even though their memory belongs to the other API.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        self.1.memory.usage()
+    }
+
+    /// Limits the memory which buffers and images created within this
+    /// context may use on each device to the device's global memory size
+    /// (`CL_DEVICE_GLOBAL_MEM_SIZE`) less `headroom` bytes. Pass `None` to
+    /// remove the limit.
+    ///
+    /// Creating a memory object which would exceed the limit fails, before
+    /// anything is allocated, with an error for which `Error::memory_limit`
+    /// returns the details. Memory objects not created with a queue count
+    /// against every device of the context. Interop objects are not checked.
+    ///
+    /// Out of memory conditions on GPUs often surface only later, as
+    /// unrelated failures when the memory is first used, so this makes them
+    /// easier to diagnose. The limit only accounts for memory allocated
+    /// through this crate, and is kept for as long as any `Context` wrapping
+    /// this context, or any buffer or image created within it, is alive.
+    pub fn set_memory_headroom(&self, headroom: Option<u64>) {
+        self.1.memory.set_headroom(headroom)
+    }
+
+    /// Returns the memory headroom set with `::set_memory_headroom`, if any.
+    pub fn memory_headroom(&self) -> Option<u64> {
+        self.1.memory.headroom()
+    }
+
+    /// Returns the `DeviceCache` shared by all users of this context.
+    ///
+    /// The cache has unlimited capacity by default. It is dropped, along
+    /// with its entries, once every `Context` for the underlying OpenCL
+    /// context (and every handle to the cache) has been dropped.
+    pub fn device_cache(&self) -> DeviceCache {
+        self.1.device_cache.clone()
+    }
+
     fn fmt_info(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.debug_struct("Context")
             .field("ReferenceCount", &self.info(ContextInfo::ReferenceCount))
@@ -160,9 +390,26 @@ impl Context {
     }
 }
 
+/// Checks that an explicitly specified list of devices contains no
+/// duplicates and that each device belongs to `platform`, if specified.
+fn check_device_list(devices: &[Device], platform: Option<Platform>) -> OclResult<()> {
+    for (idx, device) in devices.iter().enumerate() {
+        if devices[..idx].contains(device) {
+            return Err(DeviceError::DuplicateDevice(device.name()?).into());
+        }
+        if let Some(ref platform) = platform {
+            if device.platform()?.as_ptr() != platform.as_ptr() {
+                return Err(DeviceError::PlatformMismatch(device.name()?).into());
+            }
+        }
+    }
+    Ok(())
+}
+
 impl From<ContextCore> for Context {
     fn from(c: ContextCore) -> Context {
-        Context(c)
+        let shared = ContextShared::for_context(&c);
+        Context(c, shared)
     }
 }
 
@@ -205,14 +452,11 @@ impl<'a> ClVersions for &'a Context {
 
 
 /// A builder for `Context`.
-///
-// * TODO:
-//   - Handle context creation callbacks.
-//
 #[must_use = "builders do nothing unless '::build' is called"]
 pub struct ContextBuilder {
     properties: ContextProperties,
     device_spec: Option<DeviceSpecifier>,
+    error_callback: Option<Arc<ContextErrorFn>>,
 }
 
 impl ContextBuilder {
@@ -224,7 +468,7 @@ impl ContextBuilder {
     ///
     /// * The first avaliable platform
     /// * All devices associated with the first available platform
-    /// * No notify callback function or user data.
+    /// * No error callback
     ///
     pub fn new() -> ContextBuilder {
         // Default platform will be set within `::build` if unspecified by that time.
@@ -233,6 +477,7 @@ impl ContextBuilder {
         ContextBuilder {
             properties,
             device_spec: None,
+            error_callback: None,
         }
     }
 
@@ -258,13 +503,36 @@ impl ContextBuilder {
 
     /// Specifies a platform.
     ///
-    /// Overwrites any previously specified platform.
+    /// Overwrites any previously specified platform. If no platform is
+    /// specified, the platform of the devices passed to `::devices` is used
+    /// when they are listed explicitly, otherwise the default platform (see
+    /// `Platform::default`).
     ///
     pub fn platform(&mut self, platform: Platform) -> &mut ContextBuilder {
         self.properties.set_platform(platform);
         self
     }
 
+    /// Specifies whether the user is responsible for synchronization between
+    /// OpenCL and other APIs sharing objects with the context.
+    ///
+    pub fn interop_user_sync(&mut self, sync: bool) -> &mut ContextBuilder {
+        self.properties.set_interop_user_sync(sync);
+        self
+    }
+
+    /// Specifies a vendor-specific property, passed to `clCreateContext`
+    /// along with the standard ones.
+    ///
+    /// `::build` returns an error if `name` is a standard property (see
+    /// `ContextProperties::validate`).
+    ///
+    pub fn vendor_property(&mut self, name: cl_context_properties, value: cl_context_properties)
+            -> &mut ContextBuilder {
+        self.properties.set_vendor(name, value);
+        self
+    }
+
     /// Specifies an OpenGL context to associate with.
     ///
     /// Overwrites any previously specified OpenGL context.
@@ -283,12 +551,34 @@ impl ContextBuilder {
         self
     }
 
+    /// Specifies a Direct3D 11 device (`ID3D11Device *`) to share resources
+    /// with (requires the `cl_khr_d3d11_sharing` extension).
+    ///
+    /// Overwrites any previously specified Direct3D 11 device.
+    ///
+    pub fn d3d11_device(&mut self, d3d11_device: *mut crate::ffi::c_void) -> &mut ContextBuilder {
+        self.properties.set_d3d11_device(d3d11_device);
+        self
+    }
+
     /// Specifies a list of devices with which to associate the context.
     ///
     /// Devices may be specified in any number of ways including simply
     /// passing a device or slice of devices. See the [`impl From`] section of
     /// [`DeviceSpecifier`][device_specifier] for more information.
     ///
+    /// Several devices of one platform can be listed to create a
+    /// multi-device context (use `Context::queues_for_all_devices` to create
+    /// a queue for each):
+    ///
+    /// ```rust,ignore
+    /// let context = Context::builder().platform(platform).devices(&[d0, d1][..]).build()?;
+    /// let queues = context.queues_for_all_devices()?;
+    /// ```
+    ///
+    /// `::build` returns an error if a listed device is listed twice or
+    /// does not belong to the platform.
+    ///
     ///
     /// ## Panics
     ///
@@ -304,20 +594,86 @@ impl ContextBuilder {
         self
     }
 
+    /// Specifies a closure to be called with errors reported by the OpenCL
+    /// implementation for the context, such as details of resource
+    /// exhaustion. Without one, such errors are discarded.
+    ///
+    /// The closure is passed the error message and implementation-specific
+    /// binary data (possibly empty). It may be called on a thread owned by
+    /// the implementation, so it should return quickly and must not call
+    /// blocking OpenCL functions.
+    ///
+    /// The closure is dropped along with the last `Context` wrapping the
+    /// context. Errors reported after that, for instance while queues or
+    /// buffers still hold the context, are discarded.
+    pub fn error_callback<F>(&mut self, f: F) -> &mut ContextBuilder
+            where F: Fn(&str, &[u8]) + Send + Sync + 'static {
+        self.error_callback = Some(Arc::new(f));
+        self
+    }
+
     /// Returns a new `Context` with the parameters hitherinforthto specified (say what?).
     ///
     /// Returns a newly created context with the specified platform and set of device types.
-    ///
-    // * TODO:
-    //   - Handle context creation callbacks.
-    //
     pub fn build(&self) -> OclResult<Context> {
         let mut props = self.properties.clone();
 
         if props.get_platform().is_none() {
-            props.set_platform(Platform::default());
+            let platform = match self.device_spec {
+                Some(DeviceSpecifier::Single(ref device)) => device.platform()?,
+                Some(DeviceSpecifier::List(ref devices)) if !devices.is_empty() => {
+                    devices[0].platform()?
+                },
+                _ => Platform::try_default()?,
+            };
+            props.set_platform(platform);
         }
 
-        Context::new(Some(props), self.device_spec.clone(), None, None)
+        match self.error_callback {
+            Some(ref f) => {
+                // Dropping the registration on failure is fine: the callback
+                // is never called once creation has failed.
+                let registration = ErrorFnRegistration::new(f.clone());
+                let context = Context::new(Some(props), self.device_spec.clone(),
+                    Some(_call_context_error_fn), Some(registration.user_data()))?;
+                *context.1.error_fn.lock().unwrap_or_else(|e| e.into_inner()) = Some(registration);
+                Ok(context)
+            },
+            None => Context::new(Some(props), self.device_spec.clone(), None, None),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+    use std::sync::{Arc, Mutex};
+    use super::{_call_context_error_fn, ErrorFnRegistration};
+
+    #[test]
+    fn error_fn_registration() {
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let errors_cb = errors.clone();
+        let registration = ErrorFnRegistration::new(Arc::new(move |msg: &str, info: &[u8]| {
+            errors_cb.lock().unwrap().push((msg.to_owned(), info.to_vec()))
+        }));
+        let user_data = registration.user_data();
+
+        let msg = b"out of resources\0";
+        let info = [1u8, 2, 3];
+        _call_context_error_fn(msg.as_ptr() as *const _, info.as_ptr() as *const _, info.len(),
+            user_data);
+        _call_context_error_fn(ptr::null(), ptr::null(), 0, user_data);
+        assert_eq!(*errors.lock().unwrap(), vec![
+            ("out of resources".to_owned(), vec![1, 2, 3]),
+            ("<no error info>".to_owned(), vec![]),
+        ]);
+
+        // Once unregistered, the closure is dropped and late calls are ignored:
+        drop(registration);
+        _call_context_error_fn(msg.as_ptr() as *const _, ptr::null(), 0, user_data);
+        assert_eq!(errors.lock().unwrap().len(), 2);
+        assert_eq!(Arc::strong_count(&errors), 1);
     }
 }
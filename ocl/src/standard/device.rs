@@ -3,10 +3,20 @@
 use std;
 use std::ops::{Deref, DerefMut};
 use std::borrow::Borrow;
-use crate::ffi::cl_device_id;
-use crate::core::{self, util, DeviceId as DeviceIdCore, DeviceType, DeviceInfo, DeviceInfoResult, ClDeviceIdPtr};
+use crate::ffi::{cl_device_id, cl_device_partition_property};
+use crate::core::{self, util, DeviceId as DeviceIdCore, DeviceType, DeviceInfo, DeviceInfoResult, ClDeviceIdPtr,
+    OpenclVersion, DevicePartition, DeviceAffinityDomain, NumericVersion, NameVersion,
+    VersionLowError, VersionKind, Error as OclCoreError, CommandQueueProperties,
+    DeviceSvmCapabilities};
 use crate::error::{Error as OclError, Result as OclResult};
-use crate::standard::Platform;
+use crate::standard::{Platform, PciBusAddress, DeviceUuid, Context, Queue};
+use crate::standard::locality;
+#[cfg(feature = "opencl_version_2_1")]
+use std::time::Instant;
+#[cfg(feature = "opencl_version_2_1")]
+use crate::standard::DeviceTimerSync;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
 
 
 /// A device related error.
@@ -19,35 +29,313 @@ pub enum DeviceError {
     #[fail(display = "An index in the resolve list is out of range (index: {}, max: {})",
         idx, max)]
     ResolveIdxsInvalidIndex { idx: usize, max: usize },
+    #[fail(display = "Device '{}' is listed more than once.", _0)]
+    DuplicateDevice(String),
+    #[fail(display = "Device '{}' does not belong to the specified platform.", _0)]
+    PlatformMismatch(String),
+    #[fail(display = "No devices meet the selection criteria ({}). Devices found: {}.",
+        criteria, available)]
+    NoMatchingDevices { criteria: String, available: String },
+}
+
+
+/// Requirements used to select devices, ranked by capability (see
+/// `Device::select` and `DeviceSpecifier::Criteria`).
+///
+/// Only available devices are ever selected. Matching devices are sorted
+/// best first: GPUs before accelerators before CPUs, then by compute units
+/// times maximum clock frequency, then by global memory size.
+///
+/// ```rust,ignore
+/// let criteria = DeviceCriteria::gpu()
+///     .min_global_mem_gb(4)
+///     .extension("cl_khr_fp64");
+/// let best = Device::select(&criteria)?.first().cloned();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DeviceCriteria {
+    device_type: Option<DeviceType>,
+    min_global_mem_size: Option<u64>,
+    min_compute_units: Option<u32>,
+    min_version: Option<OpenclVersion>,
+    extensions: Vec<String>,
+}
+
+impl DeviceCriteria {
+    /// Returns new criteria matching any available device.
+    pub fn new() -> DeviceCriteria {
+        DeviceCriteria::default()
+    }
+
+    /// Returns new criteria matching any available GPU.
+    pub fn gpu() -> DeviceCriteria {
+        DeviceCriteria::new().device_type(DeviceType::GPU)
+    }
+
+    /// Returns new criteria matching any available CPU.
+    pub fn cpu() -> DeviceCriteria {
+        DeviceCriteria::new().device_type(DeviceType::CPU)
+    }
+
+    /// Returns new criteria matching any available accelerator.
+    pub fn accelerator() -> DeviceCriteria {
+        DeviceCriteria::new().device_type(DeviceType::ACCELERATOR)
+    }
+
+    /// Requires the device to be of one of the types in `device_type`.
+    pub fn device_type(mut self, device_type: DeviceType) -> DeviceCriteria {
+        self.device_type = Some(device_type);
+        self
+    }
+
+    /// Requires at least `size` bytes of global memory.
+    pub fn min_global_mem_size(mut self, size: u64) -> DeviceCriteria {
+        self.min_global_mem_size = Some(size);
+        self
+    }
+
+    /// Requires at least `gb` gibibytes of global memory.
+    ///
+    /// Sizes too large to represent in bytes saturate, matching no device.
+    pub fn min_global_mem_gb(self, gb: u64) -> DeviceCriteria {
+        self.min_global_mem_size(gb.saturating_mul(1 << 30))
+    }
+
+    /// Requires at least `count` compute units.
+    pub fn min_compute_units(mut self, count: u32) -> DeviceCriteria {
+        self.min_compute_units = Some(count);
+        self
+    }
+
+    /// Requires the device to support at least OpenCL `version`.
+    pub fn min_version(mut self, version: OpenclVersion) -> DeviceCriteria {
+        self.min_version = Some(version);
+        self
+    }
+
+    /// Requires support for the extension named `name` (e.g.
+    /// `cl_khr_fp64`). May be called more than once.
+    pub fn extension<S: Into<String>>(mut self, name: S) -> DeviceCriteria {
+        self.extensions.push(name.into());
+        self
+    }
+
+    /// Returns true if `device` meets all requirements.
+    pub fn matches(&self, device: &Device) -> OclResult<bool> {
+        if !device.is_available()? { return Ok(false); }
+        if let Some(device_type) = self.device_type {
+            if !device_type.intersects(device.device_type()?) { return Ok(false); }
+        }
+        if let Some(size) = self.min_global_mem_size {
+            if device.global_mem_size()? < size { return Ok(false); }
+        }
+        if let Some(count) = self.min_compute_units {
+            if device.max_compute_units()? < count { return Ok(false); }
+        }
+        if let Some(version) = self.min_version {
+            if device.version()? < version { return Ok(false); }
+        }
+        for name in &self.extensions {
+            if !device.has_extension(name)? { return Ok(false); }
+        }
+        Ok(true)
+    }
+
+    /// Returns the devices within `devices` which meet all requirements,
+    /// best first.
+    pub fn filter(&self, devices: &[Device]) -> OclResult<Vec<Device>> {
+        Ok(self.rank(devices)?.into_iter().map(|(device, _)| device).collect())
+    }
+
+    /// Returns the devices within `devices` which meet all requirements,
+    /// each with its score, best first.
+    pub fn rank(&self, devices: &[Device]) -> OclResult<Vec<(Device, DeviceScore)>> {
+        let mut scored = Vec::with_capacity(devices.len());
+        for &device in devices {
+            if self.matches(&device)? {
+                scored.push((device, device.score()?));
+            }
+        }
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        Ok(scored)
+    }
+
+    /// Returns the best device within `devices` which meets all
+    /// requirements.
+    ///
+    /// The error lists each device in `devices` if none match.
+    pub fn select_from(&self, devices: &[Device]) -> OclResult<Device> {
+        match self.filter(devices)?.first() {
+            Some(&device) => Ok(device),
+            None => Err(self.no_matching_devices(devices)),
+        }
+    }
+
+    /// Returns a `DeviceError::NoMatchingDevices` describing `devices`.
+    fn no_matching_devices(&self, devices: &[Device]) -> OclError {
+        let available = if devices.is_empty() {
+            "none".to_owned()
+        } else {
+            devices.iter().map(describe_device).collect::<Vec<_>>().join(", ")
+        };
+        DeviceError::NoMatchingDevices { criteria: self.to_string(), available }.into()
+    }
+}
+
+impl std::fmt::Display for DeviceCriteria {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut reqs = Vec::new();
+        if let Some(device_type) = self.device_type { reqs.push(format!("{:?}", device_type)); }
+        if let Some(size) = self.min_global_mem_size {
+            reqs.push(format!("global memory >= {}", format_mem_size(size)));
+        }
+        if let Some(count) = self.min_compute_units {
+            reqs.push(format!("compute units >= {}", count));
+        }
+        if let Some(version) = self.min_version {
+            reqs.push(format!("OpenCL >= {}", version));
+        }
+        for name in &self.extensions { reqs.push(name.clone()); }
+        if reqs.is_empty() {
+            f.write_str("any available device")
+        } else {
+            f.write_str(&reqs.join(", "))
+        }
+    }
+}
+
+/// Returns a short description of `device` for error messages, e.g.
+/// `'Name' (GPU, 8.0 GiB, OpenCL 3.0)`.
+fn describe_device(device: &Device) -> String {
+    let name = device.name().unwrap_or_else(|_| "<unknown>".to_owned());
+    let mut details = Vec::with_capacity(4);
+    if let Ok(device_type) = device.device_type() { details.push(format!("{:?}", device_type)); }
+    if let Ok(size) = device.global_mem_size() { details.push(format_mem_size(size)); }
+    if let Ok(version) = device.version() { details.push(format!("OpenCL {}", version)); }
+    if let Ok(false) = device.is_available() { details.push("unavailable".to_owned()); }
+    format!("'{}' ({})", name, details.join(", "))
+}
+
+/// Formats `size` bytes in gibibytes.
+fn format_mem_size(size: u64) -> String {
+    format!("{:.1} GiB", size as f64 / (1u64 << 30) as f64)
+}
+
+
+/// A ranking of a device's capability, compared field by field (see
+/// `Device::score` and `Device::list_ranked`).
+///
+/// Higher scores are better.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeviceScore {
+    /// 3 for GPUs, 2 for accelerators, 1 for CPUs, 0 otherwise.
+    pub type_rank: u8,
+    /// Compute units times maximum clock frequency (MHz).
+    pub throughput: u64,
+    /// Global memory size in bytes.
+    pub global_mem_size: u64,
+}
+
+
+/// The set of extensions supported by a device (see `Device::extensions`).
+///
+/// Extension versions are only known for OpenCL 3.0 devices, which report
+/// `CL_DEVICE_EXTENSIONS_WITH_VERSION`.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceExtensions {
+    inner: Vec<(String, Option<NumericVersion>)>,
+}
+
+impl DeviceExtensions {
+    /// Parses a space-separated list of extension names, as returned by a
+    /// `CL_DEVICE_EXTENSIONS` query.
+    pub fn from_names(names: &str) -> DeviceExtensions {
+        DeviceExtensions {
+            inner: names.split_whitespace().map(|name| (name.to_owned(), None)).collect(),
+        }
+    }
+
+    /// Returns a set of extensions from a
+    /// `CL_DEVICE_EXTENSIONS_WITH_VERSION` query.
+    pub fn from_name_versions(list: Vec<NameVersion>) -> DeviceExtensions {
+        DeviceExtensions {
+            inner: list.into_iter().map(|nv| (nv.name, Some(nv.version))).collect(),
+        }
+    }
+
+    /// Returns true if the extension named `name` (e.g. `cl_khr_fp64`) is
+    /// in the set.
+    pub fn contains(&self, name: &str) -> bool {
+        self.inner.iter().any(|(n, _)| n == name)
+    }
+
+    /// Returns the version of the extension named `name`, if it is in the
+    /// set and its version is known.
+    pub fn version(&self, name: &str) -> Option<NumericVersion> {
+        self.inner.iter().find(|(n, _)| n == name).and_then(|&(_, v)| v)
+    }
+
+    /// Iterates over extension names.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.inner.iter().map(|(n, _)| n.as_str())
+    }
+
+    /// Returns the number of extensions.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns true if there are no extensions.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl std::fmt::Display for DeviceExtensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, name) in self.iter().enumerate() {
+            if i > 0 { f.write_str(" ")?; }
+            f.write_str(name)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// A summary of a device's identity and capabilities (see
+/// `Device::capability_report`).
+///
+/// Contains only plain data, suitable for bug reports and inventory tooling.
+/// Serializable when the `serde` feature is enabled.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct CapabilityReport {
+    pub name: String,
+    pub vendor: String,
+    pub vendor_id: u32,
+    pub device_type: String,
+    pub platform_name: String,
+    pub platform_version: String,
+    pub driver_version: String,
+    pub profile: String,
+    pub opencl_version: String,
+    pub opencl_c_version: String,
+    pub max_compute_units: u32,
+    pub max_clock_frequency: u32,
+    pub address_bits: u32,
+    pub global_mem_size: u64,
+    pub global_mem_cache_size: u64,
+    pub local_mem_size: u64,
+    pub max_mem_alloc_size: u64,
+    pub max_constant_buffer_size: u64,
+    pub max_work_group_size: usize,
+    pub max_work_item_sizes: Vec<usize>,
+    pub image_support: bool,
+    pub extensions: Vec<String>,
+    pub pci_bus_address: Option<String>,
+    pub uuid: Option<String>,
 }
 
-// Perhaps add something like this to the `DeviceSpecifier`.
-//
-// Copied from `https://github.com/TyOverby/ocl-repro/blob/master/src/main.rs`:
-//
-// pub fn first_gpu() -> (Platform, Device) {
-//     let mut out = vec![];
-//     for plat in Platform::list() {
-//         if let Ok(all_devices) = Device::list_all(&plat) {
-//             for dev in all_devices {
-//                 out.push((plat.clone(), dev));
-//             }
-//         }
-//     }
-//
-//     // Prefer GPU
-//     out.sort_by(|&(_, ref a), &(_, ref b)| {
-//         let a_type = a.info(DeviceInfo::Type);
-//         let b_type = b.info(DeviceInfo::Type);
-//         if let (DeviceInfoResult::Type(a_type), DeviceInfoResult::Type(b_type)) = (a_type, b_type) {
-//             b_type.cmp(&a_type)
-//         } else {
-//             (0).cmp(&0)
-//         }
-//     });
-//
-//     out.first().unwrap().clone()
-// }
 
 /// Specifies [what boils down to] a list of devices.
 ///
@@ -71,6 +359,7 @@ pub enum DeviceSpecifier {
     Indices(Vec<usize>),
     WrappingIndices(Vec<usize>),
     TypeFlags(DeviceType),
+    Criteria(DeviceCriteria),
 }
 
 impl DeviceSpecifier {
@@ -82,7 +371,7 @@ impl DeviceSpecifier {
     }
 
     /// Returns a `DeviceSpecifier::First` variant which specifies only
-    /// the first device on a platform.
+    /// the first device on a platform, as chosen by `Device::first`.
     ///
     pub fn first(self) -> DeviceSpecifier {
         DeviceSpecifier::First
@@ -139,6 +428,13 @@ impl DeviceSpecifier {
         DeviceSpecifier::TypeFlags(flags)
     }
 
+    /// Returns a `DeviceSpecifier::Criteria` variant which specifies every
+    /// device on a platform meeting `criteria`, best first.
+    ///
+    pub fn criteria(self, criteria: DeviceCriteria) -> DeviceSpecifier {
+        DeviceSpecifier::Criteria(criteria)
+    }
+
     /// Returns the list of devices matching the parameters specified by this
     /// `DeviceSpecifier`
     ///
@@ -157,7 +453,7 @@ impl DeviceSpecifier {
                 Device::list_all(&platform).map_err(OclError::from)
             },
             DeviceSpecifier::First => {
-                Device::list_select(&platform, None, &[0])
+                Ok(vec![Device::first(platform)?])
             },
             DeviceSpecifier::Single(ref device) => {
                 Ok(vec![*device])
@@ -174,6 +470,12 @@ impl DeviceSpecifier {
             DeviceSpecifier::TypeFlags(flags) => {
                 Device::list(&platform, Some(flags)).map_err(OclError::from)
             },
+            DeviceSpecifier::Criteria(ref criteria) => {
+                let all = Device::list_all(platform)?;
+                let devices = criteria.filter(&all)?;
+                if devices.is_empty() { return Err(criteria.no_matching_devices(&all)); }
+                Ok(devices)
+            },
         }
     }
 }
@@ -238,6 +540,12 @@ impl<'a> From<&'a DeviceIdCore> for DeviceSpecifier {
     }
 }
 
+impl From<DeviceCriteria> for DeviceSpecifier {
+    fn from(criteria: DeviceCriteria) -> DeviceSpecifier {
+        DeviceSpecifier::Criteria(criteria)
+    }
+}
+
 impl From<DeviceType> for DeviceSpecifier {
     fn from(flags: DeviceType) -> DeviceSpecifier {
         DeviceSpecifier::TypeFlags(flags)
@@ -253,10 +561,16 @@ pub struct Device(DeviceIdCore);
 
 impl Device {
     /// Returns the first available device on a platform.
+    ///
+    /// The choice can be steered without recompiling using environment
+    /// variables: `OCL_DEFAULT_DEVICE_TYPE` restricts the device type,
+    /// `OCL_DEFAULT_DEVICE_NAME` selects the first device whose name contains
+    /// its value (ignoring case), and `OCL_DEFAULT_DEVICE_IDX` selects a
+    /// device by index (see `core::default_device`).
     pub fn first<P: Borrow<Platform>>(platform: P) -> OclResult<Device> {
         let device_ids = core::get_device_ids(platform.borrow(), None, None)?;
         if device_ids.is_empty() { return Err(DeviceError::NoDevices.into()) }
-        Ok(Device(device_ids[0]))
+        core::default_device(platform.borrow()).map(Device).map_err(OclError::from)
     }
 
     /// Returns a single device specified by a wrapped index.
@@ -386,6 +700,38 @@ impl Device {
         }
     }
 
+    /// Returns the available devices on `platform`, each with its score,
+    /// best first.
+    ///
+    /// The first device is a reasonable choice when the fastest device
+    /// available is wanted without further configuration.
+    pub fn list_ranked<P: Borrow<Platform>>(platform: P) -> OclResult<Vec<(Device, DeviceScore)>> {
+        DeviceCriteria::new().rank(&Device::list_all(platform)?)
+    }
+
+    /// Returns a score ranking the device's capability.
+    ///
+    /// Devices are ranked by type (GPUs before accelerators before CPUs),
+    /// then by compute units times maximum clock frequency, then by global
+    /// memory size.
+    pub fn score(&self) -> OclResult<DeviceScore> {
+        let device_type = self.device_type()?;
+        let type_rank = if device_type.contains(DeviceType::GPU) {
+            3
+        } else if device_type.contains(DeviceType::ACCELERATOR) {
+            2
+        } else if device_type.contains(DeviceType::CPU) {
+            1
+        } else {
+            0
+        };
+        Ok(DeviceScore {
+            type_rank,
+            throughput: self.max_compute_units()? as u64 * self.max_clock_frequency()? as u64,
+            global_mem_size: self.global_mem_size()?,
+        })
+    }
+
     /// Returns the device name.
     pub fn name(&self) -> OclResult<String> {
         core::get_device_info(&self.0, DeviceInfo::Name)
@@ -398,6 +744,78 @@ impl Device {
             .map(|r| r.to_string()).map_err(OclError::from)
     }
 
+    /// Returns every available device on every platform meeting `criteria`,
+    /// best first.
+    ///
+    /// The devices may belong to different platforms. Use the first to pick
+    /// the most capable device on the machine.
+    pub fn select(criteria: &DeviceCriteria) -> OclResult<Vec<Device>> {
+        let mut devices = Vec::new();
+        for platform in Platform::list()? {
+            devices.extend(Device::list_all(platform)?);
+        }
+        criteria.filter(&devices)
+    }
+
+    /// Returns the type of the device.
+    pub fn device_type(&self) -> OclResult<DeviceType> {
+        match self.info(DeviceInfo::Type) {
+            Ok(DeviceInfoResult::Type(r)) => Ok(r),
+            Err(err) => Err(err),
+            _ => panic!("Device::device_type: Unexpected 'DeviceInfoResult' variant."),
+        }
+    }
+
+    /// Returns the number of compute units on the device.
+    pub fn max_compute_units(&self) -> OclResult<u32> {
+        match self.info(DeviceInfo::MaxComputeUnits) {
+            Ok(DeviceInfoResult::MaxComputeUnits(r)) => Ok(r),
+            Err(err) => Err(err),
+            _ => panic!("Device::max_compute_units: Unexpected 'DeviceInfoResult' variant."),
+        }
+    }
+
+    /// Returns the maximum clock frequency of the device, in MHz.
+    pub fn max_clock_frequency(&self) -> OclResult<u32> {
+        match self.info(DeviceInfo::MaxClockFrequency) {
+            Ok(DeviceInfoResult::MaxClockFrequency(r)) => Ok(r),
+            Err(err) => Err(err),
+            _ => panic!("Device::max_clock_frequency: Unexpected 'DeviceInfoResult' variant."),
+        }
+    }
+
+    /// Returns the size of the device's global memory, in bytes.
+    pub fn global_mem_size(&self) -> OclResult<u64> {
+        match self.info(DeviceInfo::GlobalMemSize) {
+            Ok(DeviceInfoResult::GlobalMemSize(r)) => Ok(r),
+            Err(err) => Err(err),
+            _ => panic!("Device::global_mem_size: Unexpected 'DeviceInfoResult' variant."),
+        }
+    }
+
+    /// Returns the device's shared virtual memory capabilities.
+    ///
+    /// Empty for devices older than OpenCL 2.0.
+    pub fn svm_capabilities(&self) -> OclResult<DeviceSvmCapabilities> {
+        if self.version()? < OpenclVersion::new(2, 0) {
+            return Ok(DeviceSvmCapabilities::empty());
+        }
+        match self.info(DeviceInfo::SvmCapabilities) {
+            Ok(DeviceInfoResult::SvmCapabilities(r)) => Ok(r),
+            Err(err) => Err(err),
+            _ => panic!("Device::svm_capabilities: Unexpected 'DeviceInfoResult' variant."),
+        }
+    }
+
+    /// Returns the maximum workgroup size in each dimension or an error.
+    pub fn max_work_item_sizes(&self) -> OclResult<Vec<usize>> {
+        match self.info(DeviceInfo::MaxWorkItemSizes) {
+            Ok(DeviceInfoResult::MaxWorkItemSizes(r)) => Ok(r),
+            Err(err) => Err(err),
+            _ => panic!("Device::max_work_item_sizes: Unexpected 'DeviceInfoResult' variant."),
+        }
+    }
+
     /// Returns the maximum workgroup size or an error.
     pub fn max_wg_size(&self) -> OclResult<usize> {
         match self.info(DeviceInfo::MaxWorkGroupSize) {
@@ -416,6 +834,15 @@ impl Device {
         }
     }
 
+    /// Returns the platform the device belongs to.
+    pub fn platform(&self) -> OclResult<Platform> {
+        match self.info(DeviceInfo::Platform) {
+            Ok(DeviceInfoResult::Platform(p)) => Ok(Platform::new(p)),
+            Err(err) => Err(err),
+            _ => panic!("Device::platform: Unexpected 'DeviceInfoResult' variant."),
+        }
+    }
+
     /// Returns whether or not the device is available for use.
     pub fn is_available(&self) -> OclResult<bool> {
         match self.info(DeviceInfo::Available) {
@@ -425,6 +852,276 @@ impl Device {
         }
     }
 
+    /// Returns a summary of the device's identity and capabilities.
+    pub fn capability_report(&self) -> OclResult<CapabilityReport> {
+        macro_rules! info {
+            ($variant:ident) => {
+                match self.info(DeviceInfo::$variant)? {
+                    DeviceInfoResult::$variant(r) => r,
+                    _ => panic!("Device::capability_report: Unexpected 'DeviceInfoResult' \
+                        variant."),
+                }
+            };
+        }
+
+        let platform = self.platform()?;
+        Ok(CapabilityReport {
+            name: info!(Name),
+            vendor: info!(Vendor),
+            vendor_id: info!(VendorId),
+            device_type: format!("{:?}", info!(Type)),
+            platform_name: platform.name()?,
+            platform_version: platform.version()?,
+            driver_version: info!(DriverVersion),
+            profile: info!(Profile),
+            opencl_version: info!(Version).to_string(),
+            opencl_c_version: info!(OpenclCVersion),
+            max_compute_units: info!(MaxComputeUnits),
+            max_clock_frequency: info!(MaxClockFrequency),
+            address_bits: info!(AddressBits),
+            global_mem_size: info!(GlobalMemSize),
+            global_mem_cache_size: info!(GlobalMemCacheSize),
+            local_mem_size: info!(LocalMemSize),
+            max_mem_alloc_size: info!(MaxMemAllocSize),
+            max_constant_buffer_size: info!(MaxConstantBufferSize),
+            max_work_group_size: info!(MaxWorkGroupSize),
+            max_work_item_sizes: info!(MaxWorkItemSizes),
+            image_support: info!(ImageSupport),
+            extensions: self.extensions()?.iter().map(str::to_owned).collect(),
+            pci_bus_address: self.pci_bus_address()?.map(|a| a.to_string()),
+            uuid: self.uuid()?.map(|u| u.to_string()),
+        })
+    }
+
+    /// Returns a device timestamp and the host timestamp taken at the same
+    /// moment, in nanoseconds, as `(device_timestamp, host_timestamp)`.
+    ///
+    /// Requires OpenCL 2.1.
+    #[cfg(feature = "opencl_version_2_1")]
+    pub fn device_and_host_timer(&self) -> OclResult<(u64, u64)> {
+        core::get_device_and_host_timer(self.0, None).map_err(OclError::from)
+    }
+
+    /// Returns the current value of the host clock used by
+    /// `device_and_host_timer`, in nanoseconds.
+    ///
+    /// Requires OpenCL 2.1.
+    #[cfg(feature = "opencl_version_2_1")]
+    pub fn host_timer(&self) -> OclResult<u64> {
+        core::get_host_timer(self.0, None).map_err(OclError::from)
+    }
+
+    /// Returns a sync point relating device timestamps (such as those of
+    /// event profiling info) to host `Instant`s.
+    ///
+    /// Requires OpenCL 2.1.
+    #[cfg(feature = "opencl_version_2_1")]
+    pub fn timer_sync(&self) -> OclResult<DeviceTimerSync> {
+        let before = Instant::now();
+        let (device_timestamp, host_timestamp) = self.device_and_host_timer()?;
+        let after = Instant::now();
+        Ok(DeviceTimerSync::new(device_timestamp, host_timestamp, before + (after - before) / 2))
+    }
+
+    /// Returns the names of the built-in kernels supported by the device.
+    ///
+    /// Built-in kernels are fixed-function kernels, common on FPGAs and
+    /// other accelerators. See `Program::with_built_in_kernels`.
+    pub fn built_in_kernels(&self) -> OclResult<Vec<String>> {
+        match self.info(DeviceInfo::BuiltInKernels) {
+            Ok(DeviceInfoResult::BuiltInKernels(names)) => Ok(names.split(';')
+                .map(str::trim).filter(|name| !name.is_empty()).map(str::to_owned).collect()),
+            Err(err) => Err(err),
+            _ => panic!("Device::built_in_kernels: Unexpected 'DeviceInfoResult' variant."),
+        }
+    }
+
+    /// Returns an error for which `Error::version_low` returns the details
+    /// if the device does not support at least OpenCL `required`.
+    ///
+    /// `feature` names the function or feature being gated and is included
+    /// in the error message.
+    pub fn require_version(&self, required: OpenclVersion, feature: &str) -> OclResult<()> {
+        let detected = self.version()?;
+        if detected < required {
+            return Err(OclCoreError::from(VersionLowError::new(VersionKind::Device, detected,
+                required, feature)).into());
+        }
+        Ok(())
+    }
+
+    /// Returns the set of extensions supported by the device.
+    ///
+    /// Extension versions are included for OpenCL 3.0 devices.
+    pub fn extensions(&self) -> OclResult<DeviceExtensions> {
+        if self.version()? >= OpenclVersion::new(3, 0) {
+            if let Ok(DeviceInfoResult::ExtensionsWithVersion(list)) =
+                    self.info(DeviceInfo::ExtensionsWithVersion) {
+                return Ok(DeviceExtensions::from_name_versions(list));
+            }
+        }
+        match self.info(DeviceInfo::Extensions) {
+            Ok(DeviceInfoResult::Extensions(exts)) => Ok(DeviceExtensions::from_names(&exts)),
+            Err(err) => Err(err),
+            _ => panic!("Device::extensions: Unexpected 'DeviceInfoResult' variant."),
+        }
+    }
+
+    /// Returns true if the device supports the extension named `name` (e.g.
+    /// `cl_khr_fp64`).
+    pub fn has_extension(&self, name: &str) -> OclResult<bool> {
+        match self.info(DeviceInfo::Extensions) {
+            Ok(DeviceInfoResult::Extensions(exts)) => Ok(exts.split_whitespace().any(|e| e == name)),
+            Err(err) => Err(err),
+            _ => panic!("Device::has_extension: Unexpected 'DeviceInfoResult' variant."),
+        }
+    }
+
+    /// Partitions the device into as many sub-devices as possible, each with
+    /// `compute_units` compute units.
+    ///
+    /// Requires OpenCL 1.2 and a device supporting
+    /// `CL_DEVICE_PARTITION_EQUALLY` (mostly CPUs).
+    pub fn partition_equally(&self, compute_units: u32) -> OclResult<Vec<SubDevice>> {
+        self.partition(&DevicePartition::Equally(compute_units))
+    }
+
+    /// Partitions the device into one sub-device for each entry of `counts`,
+    /// with that many compute units.
+    pub fn partition_by_counts(&self, counts: &[u32]) -> OclResult<Vec<SubDevice>> {
+        self.partition(&DevicePartition::ByCounts(counts.to_vec()))
+    }
+
+    /// Partitions the device into sub-devices sharing the cache level or
+    /// NUMA node given by `domain`.
+    ///
+    /// Use `DeviceAffinityDomain::NUMA` to give each NUMA node of a CPU
+    /// device its own sub-device, and so its own context and queues.
+    pub fn partition_by_affinity(&self, domain: DeviceAffinityDomain)
+            -> OclResult<Vec<SubDevice>> {
+        self.partition(&DevicePartition::ByAffinityDomain(domain))
+    }
+
+    /// Partitions the device into sub-devices as described by `partition`.
+    pub fn partition(&self, partition: &DevicePartition) -> OclResult<Vec<SubDevice>> {
+        let sub_devices = core::create_sub_devices(&self.0, partition, None)?;
+        Ok(sub_devices.into_iter().map(|d| SubDevice(Device(d))).collect())
+    }
+
+    /// Returns how this device was partitioned from its parent, or `None` if
+    /// it is not a sub-device.
+    pub fn partition_type(&self) -> OclResult<Option<DevicePartition>> {
+        let raw = core::get_device_info_raw(self.0, DeviceInfo::PartitionType as u32)?;
+        let raw = unsafe { util::bytes_into_vec::<cl_device_partition_property>(raw) }
+            .map_err(OclCoreError::from)?;
+        Ok(DevicePartition::from_raw(&raw))
+    }
+
+    /// Returns true if the device can be partitioned by NUMA node (see
+    /// `Device::numa_nodes`).
+    pub fn supports_numa_partition(&self) -> OclResult<bool> {
+        match self.info(DeviceInfo::PartitionAffinityDomain)? {
+            DeviceInfoResult::PartitionAffinityDomain(domains) => {
+                Ok(domains.contains(DeviceAffinityDomain::NUMA))
+            },
+            _ => panic!("Device::supports_numa_partition: Unexpected 'DeviceInfoResult' variant."),
+        }
+    }
+
+    /// Partitions the device into one sub-device per NUMA node.
+    ///
+    /// Nodes are numbered by partition index, in the order the sub-devices
+    /// are returned by the implementation. OpenCL does not report which host
+    /// NUMA node each sub-device belongs to, so this index need not match
+    /// the operating system's node id. Typically only available for CPU
+    /// devices on multi-socket machines; returns an error if the device does
+    /// not support `CL_DEVICE_AFFINITY_DOMAIN_NUMA` partitioning.
+    pub fn numa_nodes(&self) -> OclResult<Vec<NumaNode>> {
+        let sub_devices = self.partition_by_affinity(DeviceAffinityDomain::NUMA)?;
+        Ok(sub_devices.into_iter().enumerate()
+            .map(|(partition_idx, sub_device)| NumaNode { partition_idx, sub_device })
+            .collect())
+    }
+
+    /// Partitions the device by NUMA node and creates one queue per node,
+    /// all sharing a single new context.
+    ///
+    /// Work enqueued on each queue runs only on the compute units of its
+    /// node, avoiding cross-socket traffic between them. Nodes are identified
+    /// by partition index only (see `::numa_nodes`).
+    pub fn numa_queues(&self, properties: Option<CommandQueueProperties>)
+            -> OclResult<Vec<(NumaNode, Queue)>> {
+        let nodes = self.numa_nodes()?;
+        let devices: Vec<Device> = nodes.iter().map(|n| n.device()).collect();
+        let context = Context::builder()
+            .platform(self.platform()?)
+            .devices(&devices[..])
+            .build()?;
+
+        let mut queues = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let queue = Queue::new(&context, node.device(), properties)?;
+            queues.push((node, queue));
+        }
+        Ok(queues)
+    }
+
+    /// Returns the PCI address of the device.
+    ///
+    /// Returns `None` if the device is not attached by PCI or if none of the
+    /// `cl_khr_pci_bus_info`, `cl_amd_device_attribute_query`, or
+    /// `cl_nv_device_attribute_query` extensions are supported.
+    pub fn pci_bus_address(&self) -> OclResult<Option<PciBusAddress>> {
+        locality::pci_bus_address(&self.0)
+    }
+
+    /// Returns the UUID of the device.
+    ///
+    /// Returns `None` if the `cl_khr_device_uuid` extension is not
+    /// supported. Together with `pci_bus_address`, this can be used to match
+    /// OpenCL devices to GPUs assigned by a scheduler or listed by
+    /// `nvidia-smi`.
+    pub fn uuid(&self) -> OclResult<Option<DeviceUuid>> {
+        locality::device_uuid(&self.0)
+    }
+
+    /// Returns the UUID of the driver for the device, if the
+    /// `cl_khr_device_uuid` extension is supported.
+    pub fn driver_uuid(&self) -> OclResult<Option<DeviceUuid>> {
+        locality::driver_uuid(&self.0)
+    }
+
+    /// Returns the host NUMA node closest to the device, if it can be
+    /// determined.
+    pub fn numa_node(&self) -> OclResult<Option<u32>> {
+        self.pci_bus_address().map(|a| a.and_then(|a| locality::numa_node(&a)))
+    }
+
+    /// Pins the calling thread to the CPUs of the NUMA node closest to the
+    /// device.
+    ///
+    /// Intended for threads which stage data to or from the device. Returns
+    /// `false`, leaving the thread unpinned, if the device's node can not be
+    /// determined (including on platforms other than Linux).
+    pub fn pin_current_thread(&self) -> OclResult<bool> {
+        match self.numa_node()? {
+            Some(node) => locality::pin_current_thread(node),
+            None => Ok(false),
+        }
+    }
+
+    /// Enables or disables (default) pinning of the threads this crate
+    /// spawns to the NUMA node closest to the device they serve.
+    ///
+    /// Affects the threads which wake tasks waiting on events and run
+    /// deferred clean-up (one per node, serving the events of commands on
+    /// that node's devices) and program build threads (see
+    /// `ProgramBuilder::build_async`). Only threads spawned afterwards are
+    /// pinned.
+    pub fn set_pin_internal_threads(enabled: bool) {
+        locality::set_pin_internal_threads(enabled)
+    }
+
     /// Returns raw info about the device, as a vector of bytes. Intended for use with non-standard
     /// OpenCL extensions.
     pub fn info_raw(&self, info_kind: u32) -> OclResult<Vec<u8>> {
@@ -583,3 +1280,92 @@ impl DerefMut for Device {
         &mut self.0
     }
 }
+
+
+/// A sub-device created by partitioning a device (see `Device::partition`).
+///
+/// Unlike a `Device`, a sub-device is reference counted: it is retained when
+/// cloned and released when dropped. Contexts created with it hold their own
+/// reference. Derefs to `Device`.
+#[derive(Debug)]
+pub struct SubDevice(Device);
+
+impl SubDevice {
+    /// Returns the sub-device as a plain `Device`, valid while this
+    /// `SubDevice` (or a context using it) exists.
+    pub fn device(&self) -> Device {
+        self.0
+    }
+}
+
+impl Clone for SubDevice {
+    fn clone(&self) -> SubDevice {
+        unsafe { core::retain_device(self.0.as_core(), None).unwrap(); }
+        SubDevice(self.0)
+    }
+}
+
+impl Drop for SubDevice {
+    fn drop(&mut self) {
+        unsafe { core::release_device(self.0.as_core(), None).unwrap(); }
+    }
+}
+
+impl Deref for SubDevice {
+    type Target = Device;
+
+    fn deref(&self) -> &Device {
+        &self.0
+    }
+}
+
+impl AsRef<Device> for SubDevice {
+    fn as_ref(&self) -> &Device {
+        &self.0
+    }
+}
+
+impl<'a> From<&'a SubDevice> for DeviceSpecifier {
+    fn from(sub_device: &'a SubDevice) -> DeviceSpecifier {
+        DeviceSpecifier::Single(sub_device.device())
+    }
+}
+
+impl<'a> From<&'a [SubDevice]> for DeviceSpecifier {
+    fn from(sub_devices: &'a [SubDevice]) -> DeviceSpecifier {
+        DeviceSpecifier::List(sub_devices.iter().map(|d| d.device()).collect())
+    }
+}
+
+
+/// A NUMA node of a device, as a sub-device (see `Device::numa_nodes`).
+#[derive(Clone, Debug)]
+pub struct NumaNode {
+    partition_idx: usize,
+    sub_device: SubDevice,
+}
+
+impl NumaNode {
+    /// Returns the index of this node's sub-device, in the order the
+    /// implementation returned the NUMA sub-devices.
+    ///
+    /// This is not a host NUMA node id, which OpenCL does not report.
+    pub fn partition_idx(&self) -> usize {
+        self.partition_idx
+    }
+
+    /// Returns the sub-device for this node.
+    pub fn sub_device(&self) -> &SubDevice {
+        &self.sub_device
+    }
+
+    /// Returns the sub-device for this node as a plain `Device`.
+    pub fn device(&self) -> Device {
+        self.sub_device.device()
+    }
+
+    /// Returns the number of compute units (cores) of this node.
+    pub fn compute_units(&self) -> OclResult<u32> {
+        self.sub_device.max_compute_units()
+    }
+}
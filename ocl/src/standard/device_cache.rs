@@ -0,0 +1,273 @@
+//! Named device buffers shared within a context.
+//!
+//! A `DeviceCache` maps string keys to device buffers holding data which is
+//! expensive to produce (precomputed twiddle factors, lookup tables, etc.)
+//! so that independent parts of a program can share it without passing
+//! buffers through every layer in between. Use `Context::device_cache` to
+//! obtain the cache shared by every user of a context.
+//!
+//! Each entry carries an optional 'ready' event, the completion of the
+//! command(s) which fill the buffer. Callers include it in the wait list of
+//! any command reading the buffer. Replacing or evicting an entry only drops
+//! the cache's reference: buffers already handed out remain valid for as
+//! long as they (and any commands using them) are alive.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::mem;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use crate::core::OclPrm;
+use crate::error::Result as OclResult;
+use crate::standard::{Buffer, Event};
+
+
+#[derive(Debug)]
+struct Entry {
+    buffer: Box<dyn Any + Send + Sync>,
+    ready: Option<Event>,
+    size: usize,
+    last_used: u64,
+}
+
+
+#[derive(Debug)]
+struct Inner {
+    entries: HashMap<String, Entry>,
+    capacity: Option<usize>,
+    size: usize,
+    clock: u64,
+}
+
+impl Inner {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Evicts least recently used entries until `additional` more bytes fit
+    /// within the capacity.
+    fn make_room(&mut self, additional: usize) {
+        let capacity = match self.capacity {
+            Some(c) => c,
+            None => return,
+        };
+        while self.size + additional > capacity {
+            let lru = self.entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k.clone());
+            match lru {
+                Some(key) => {
+                    let entry = self.entries.remove(&key).unwrap();
+                    self.size -= entry.size;
+                },
+                None => break,
+            }
+        }
+    }
+
+    fn get<T: OclPrm>(&mut self, key: &str) -> OclResult<Option<CachedBuffer<T>>> {
+        let now = self.tick();
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                let buffer = match entry.buffer.downcast_ref::<Buffer<T>>() {
+                    Some(b) => b.clone(),
+                    None => return Err(format!("DeviceCache::get: The entry '{}' does not \
+                        contain a 'Buffer<{}>'.", key, std::any::type_name::<T>()).into()),
+                };
+                entry.last_used = now;
+                Ok(Some(CachedBuffer { buffer, ready: entry.ready.clone() }))
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+
+/// A size-limited map of named device buffers.
+///
+/// Cloning a `DeviceCache` returns another handle to the same cache. See the
+/// [module documentation](index.html) for details.
+///
+/// ### Example
+///
+/// ```rust,ignore
+/// let cache = context.device_cache();
+/// let twiddles = cache.get_or_insert_with("fft.twiddles.4096", || {
+///     let buffer = Buffer::<Float2>::builder().queue(queue.clone()).len(4096).build()?;
+///     let mut ready = Event::empty();
+///     buffer.write(&host_twiddles).enew(&mut ready).enq()?;
+///     Ok((buffer, Some(ready)))
+/// })?;
+/// kernel.set_arg("twiddles", &*twiddles)?;
+/// unsafe { kernel.cmd().ewait(twiddles.ready()).enq()?; }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DeviceCache {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl DeviceCache {
+    /// Returns a new, empty cache holding at most `capacity` bytes (or
+    /// unlimited if `None`).
+    ///
+    /// This cache is not shared. Use `Context::device_cache` for the cache
+    /// shared by all users of a context.
+    pub fn new(capacity: Option<usize>) -> DeviceCache {
+        DeviceCache {
+            inner: Arc::new(Mutex::new(Inner {
+                entries: HashMap::new(),
+                capacity,
+                size: 0,
+                clock: 0,
+            })),
+        }
+    }
+
+    /// Returns the buffer stored under `key`, if any, marking it as recently
+    /// used.
+    ///
+    /// Returns an error if the entry holds a buffer of another type.
+    pub fn get<T: OclPrm>(&self, key: &str) -> OclResult<Option<CachedBuffer<T>>> {
+        self.inner.lock().unwrap().get(key)
+    }
+
+    /// Stores `buffer` under `key`, replacing any existing entry and evicting
+    /// least recently used entries as needed to stay within the capacity.
+    ///
+    /// `ready` should be the event signaling that the buffer's contents have
+    /// been written, if that has not already completed.
+    pub fn insert<T: OclPrm, S: Into<String>>(&self, key: S, buffer: Buffer<T>,
+            ready: Option<Event>) -> OclResult<()> {
+        let key = key.into();
+        let size = buffer.len() * mem::size_of::<T>();
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(capacity) = inner.capacity {
+            if size > capacity {
+                return Err(format!("DeviceCache::insert: The buffer for '{}' ({} bytes) is \
+                    larger than the cache capacity ({} bytes).", key, size, capacity).into());
+            }
+        }
+        if let Some(old) = inner.entries.remove(&key) {
+            inner.size -= old.size;
+        }
+        inner.make_room(size);
+
+        let last_used = inner.tick();
+        inner.size += size;
+        inner.entries.insert(key, Entry { buffer: Box::new(buffer), ready, size, last_used });
+        Ok(())
+    }
+
+    /// Returns the buffer stored under `key`, first calling `f` to create it
+    /// (and its ready event) if there is none.
+    ///
+    /// `f` is called without the cache locked, so it may use the cache
+    /// itself. If another thread stores an entry under `key` in the
+    /// meantime, that entry is kept and returned instead.
+    pub fn get_or_insert_with<T, S, F>(&self, key: S, f: F) -> OclResult<CachedBuffer<T>>
+            where T: OclPrm, S: AsRef<str>, F: FnOnce() -> OclResult<(Buffer<T>, Option<Event>)> {
+        let key = key.as_ref();
+        if let Some(cached) = self.get(key)? {
+            return Ok(cached);
+        }
+
+        let (buffer, ready) = f()?;
+        if let Some(cached) = self.get(key)? {
+            return Ok(cached);
+        }
+        self.insert(key, buffer.clone(), ready.clone())?;
+        Ok(CachedBuffer { buffer, ready })
+    }
+
+    /// Removes the entry stored under `key`, returning true if there was one.
+    pub fn remove(&self, key: &str) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.entries.remove(key) {
+            Some(entry) => {
+                inner.size -= entry.size;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Returns true if an entry is stored under `key`.
+    pub fn contains(&self, key: &str) -> bool {
+        self.inner.lock().unwrap().entries.contains_key(key)
+    }
+
+    /// Returns the keys of all entries, in no particular order.
+    pub fn keys(&self) -> Vec<String> {
+        self.inner.lock().unwrap().entries.keys().cloned().collect()
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Returns true if the cache has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the total size of all entries, in bytes.
+    pub fn size(&self) -> usize {
+        self.inner.lock().unwrap().size
+    }
+
+    /// Returns the capacity in bytes, or `None` if unlimited.
+    pub fn capacity(&self) -> Option<usize> {
+        self.inner.lock().unwrap().capacity
+    }
+
+    /// Sets the capacity in bytes (`None` for unlimited), evicting least
+    /// recently used entries if the current contents do not fit.
+    pub fn set_capacity(&self, capacity: Option<usize>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.capacity = capacity;
+        inner.make_room(0);
+    }
+
+    /// Removes all entries.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.clear();
+        inner.size = 0;
+    }
+}
+
+
+/// A buffer returned by a `DeviceCache` along with the event signaling that
+/// its contents are ready.
+#[derive(Clone, Debug)]
+pub struct CachedBuffer<T: OclPrm> {
+    buffer: Buffer<T>,
+    ready: Option<Event>,
+}
+
+impl<T: OclPrm> CachedBuffer<T> {
+    /// Returns the buffer.
+    pub fn buffer(&self) -> &Buffer<T> {
+        &self.buffer
+    }
+
+    /// Returns the event which must complete before the buffer is read, if
+    /// any.
+    pub fn ready(&self) -> Option<&Event> {
+        self.ready.as_ref()
+    }
+
+    /// Returns the buffer and ready event.
+    pub fn into_parts(self) -> (Buffer<T>, Option<Event>) {
+        (self.buffer, self.ready)
+    }
+}
+
+impl<T: OclPrm> Deref for CachedBuffer<T> {
+    type Target = Buffer<T>;
+
+    fn deref(&self) -> &Buffer<T> {
+        &self.buffer
+    }
+}
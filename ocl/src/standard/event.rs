@@ -3,8 +3,8 @@
 //
 // ### Notes
 //
-// * `EventArray` (a stack allocated event list with a maximum length of 8,
-//   akin to `RawEventArray`) is incomplete (TODO: Complete it).
+// * `EventArray` (a stack allocated event list with a fixed capacity, akin
+//   to `RawEventArray`) is incomplete (TODO: Complete it).
 // * It's not yet clear whether or not to keep EventArray and EventList
 //   separate or to combine them into a smart-list that might be either one
 //   depending on the circumstances.
@@ -16,28 +16,95 @@
 extern crate nodrop;
 
 
-use std::{mem, ptr, fmt};
+use std::{cmp, mem, ptr, fmt};
+use std::collections::HashMap;
 use std::borrow::Borrow;
 use std::ops::{Deref, DerefMut};
 use std::cell::Ref;
-use futures::{Future, Poll, Async};
-#[cfg(not(feature = "async_block"))]
-use futures::task;
-use crate::ffi::cl_event;
+use std::time::{Duration, Instant};
+use std::sync::{mpsc, Arc, Condvar, Mutex, RwLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context as TaskContext, Poll};
+use std::task::{Wake, Waker};
+use futures::channel::oneshot;
+use num_traits::FromPrimitive;
+use crate::ffi::{c_void, cl_event};
 use crate::core::{self, Event as EventCore, EventInfo, EventInfoResult, ProfilingInfo,
     ProfilingInfoResult, ClNullEventPtr, ClWaitListPtr, ClEventPtrRef,
-    CommandQueue as CommandQueueCore, ClContextPtr};
-use crate::error::{Error as OclError, Result as OclResult};
+    CommandQueue as CommandQueueCore, ClContextPtr, CommandExecutionStatus, CommandType, Status};
+use crate::error::{Error as OclError, ErrorKind as OclErrorKind, Result as OclResult};
 use crate::standard::{Queue, ClWaitListPtrEnum};
+use crate::r#async::EventListFuture;
 #[cfg(not(feature = "async_block"))]
 use crate::standard::{_unpark_task, box_raw_void};
+use crate::standard::completion;
 use self::nodrop::NoDrop;
 
 const PRINT_DEBUG: bool = false;
 
+
+type CompletionFn = Box<dyn FnOnce(OclResult<CommandExecutionStatus>) + Send>;
+
+/// Calls the `CompletionFn` boxed in `user_data`, passed by
+/// `Event::on_complete`.
+extern "C" fn _call_completion_fn(event_ptr: cl_event, event_status: i32, user_data: *mut c_void) {
+    core::guard_callback("_call_completion_fn", || {
+        let _ = event_ptr;
+        if user_data.is_null() { return; }
+        let f = unsafe { Box::from_raw(user_data as *mut CompletionFn) };
+
+        let status = match CommandExecutionStatus::from_i32(event_status) {
+            Some(status) => Ok(status),
+            None => Err(format!("The command terminated abnormally (status: {:?}).",
+                Status::from_i32(event_status)).into()),
+        };
+        f(status)
+    })
+}
+
+// Labels set with `Event::set_label`, keyed by event pointer. Each entry
+// holds a reference to its event so that the pointer can not be reused
+// while the label exists. The entry is removed when the last other `Event`
+// for it is dropped (see `Drop for Event`).
+type LabelMap = HashMap<usize, (Event, Arc<str>)>;
+static LABELS: RwLock<Option<LabelMap>> = RwLock::new(None);
+
+// The number of entries in `LABELS`, allowing it to be skipped entirely
+// (without locking) while there are none.
+static LABEL_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Removes the label of the event with pointer `key` if `dropped` is the
+/// last handle to it other than the one held by `LABELS`.
+fn release_label(key: usize, dropped: &Event) {
+    {
+        let labels = LABELS.read().unwrap_or_else(|e| e.into_inner());
+        if !labels.as_ref().map(|labels| labels.contains_key(&key)).unwrap_or(false) {
+            return;
+        }
+    }
+    match core::get_event_info(&dropped.0, EventInfo::ReferenceCount) {
+        Ok(EventInfoResult::ReferenceCount(count)) if count <= 2 => (),
+        _ => return,
+    }
+
+    let removed = {
+        let mut labels = LABELS.write().unwrap_or_else(|e| e.into_inner());
+        labels.as_mut().and_then(|labels| labels.remove(&key))
+    };
+    if removed.is_some() {
+        LABEL_COUNT.fetch_sub(1, Ordering::AcqRel);
+    }
+    // The entry's event is dropped (and released) once the lock is no longer
+    // held.
+    drop(removed);
+}
+
+
 /// An event representing a command or user created event.
 ///
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Hash, PartialEq, Eq)]
 #[repr(C)]
 #[must_use = "futures do nothing unless polled"]
 pub struct Event(EventCore);
@@ -64,19 +131,17 @@ impl Event {
     }
 
     /// Sets a callback function to trigger upon completion of this event
-    /// which will unpark the current task.
+    /// which will wake the task associated with `waker`.
     ///
-    /// To be used within the context of a futures task.
+    /// To be used within an implementation of `Future::poll`, passing the
+    /// waker of its context.
     ///
-    /// ## Panics
-    ///
-    /// This function will panic if a task is not currently being executed.
-    /// That is, this method can be dangerous to call outside of an
-    /// implementation of poll.
+    /// Each call sets a new callback. Prefer polling the event itself, which
+    /// sets at most one callback per event.
     #[cfg(not(feature = "async_block"))]
-    pub fn set_unpark_callback(&self) -> OclResult<()> {
-        let task_ptr = box_raw_void(task::current());
-        unsafe { self.set_callback(_unpark_task, task_ptr).map_err(OclError::from) }
+    pub fn set_unpark_callback(&self, waker: &Waker) -> OclResult<()> {
+        let waker_ptr = box_raw_void(waker.clone());
+        unsafe { self.set_callback(_unpark_task, waker_ptr).map_err(OclError::from) }
     }
 
     /// Registers a user event to have its status set to complete
@@ -105,16 +170,158 @@ impl Event {
         self.set_callback(core::_complete_user_event, unmap_event_ptr).map_err(OclError::from)
     }
 
+    /// Returns the execution status of the command associated with this
+    /// event, without blocking.
+    ///
+    /// Returns an error if the command terminated abnormally. Use
+    /// `::is_complete` to check for completion alone.
+    pub fn status(&self) -> OclResult<CommandExecutionStatus> {
+        let status = core::event_status_code(&self.0)?;
+        CommandExecutionStatus::from_i32(status).ok_or_else(|| format!("The command \
+            terminated abnormally (status: {:?}).", Status::from_i32(status)).into())
+    }
+
+    /// Blocks the host thread until the command associated with this event
+    /// has completed or `timeout` has elapsed.
+    ///
+    /// Returns an error for which `Error::is_timed_out` is true if the
+    /// command has not completed after `timeout`, or for which
+    /// `Error::failed_event` is set if it terminated abnormally.
+    pub fn wait_timeout(&self, timeout: Duration) -> OclResult<()> {
+        wait_events_timeout(::std::slice::from_ref(self), timeout)
+    }
+
+    /// Returns a future which resolves once the command associated with this
+    /// event has completed, without blocking any thread while waiting.
+    ///
+    /// The future is a clone of this event (events are themselves futures).
+    pub fn wait_async(&self) -> Event {
+        self.clone()
+    }
+
+    /// Calls `f` once the command associated with this event has completed.
+    ///
+    /// `f` is passed `CommandExecutionStatus::Complete`, or an error if the
+    /// command terminated abnormally. It is called on a thread owned by the
+    /// OpenCL runtime, so it should return quickly and must not call
+    /// blocking OpenCL functions (such as `::wait_for` or `Queue::finish`).
+    /// A panic within `f` is caught and returned as an error by the next
+    /// OpenCL call.
+    ///
+    /// `f` is dropped without being called if the callback can not be set.
+    pub fn on_complete<F>(&self, f: F) -> OclResult<()>
+            where F: FnOnce(OclResult<CommandExecutionStatus>) + Send + 'static {
+        let user_data = Box::into_raw(Box::new(Box::new(f) as CompletionFn));
+        match unsafe { self.set_callback(_call_completion_fn, user_data as *mut c_void) } {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                unsafe { drop(Box::from_raw(user_data)); }
+                Err(OclError::from(err))
+            },
+        }
+    }
+
+    /// Returns a receiver which gets the outcome of the command once it has
+    /// completed, for notifying code which is not otherwise asynchronous.
+    ///
+    /// The receiver is a future and can be awaited or waited on directly
+    /// (with `futures::executor::block_on`).
+    pub fn into_oneshot(self) -> OclResult<oneshot::Receiver<OclResult<()>>> {
+        let (tx, rx) = oneshot::channel();
+        self.on_complete(move |status| { let _ = tx.send(status.map(|_| ())); })?;
+        Ok(rx)
+    }
+
     /// Returns info about the event.
     pub fn info(&self, info_kind: EventInfo) -> OclResult<EventInfoResult> {
         core::get_event_info(&self.0, info_kind).map_err(OclError::from)
     }
 
-    /// Returns info about the event.
+    /// Returns profiling info about the event.
+    ///
+    /// Returns an error for which `Error::is_profiling_unavailable` is true
+    /// if the event has no profiling information (see `::profiling_time`).
     pub fn profiling_info(&self, info_kind: ProfilingInfo) -> OclResult<ProfilingInfoResult> {
         core::get_event_profiling_info(&self.0, info_kind).map_err(OclError::from)
     }
 
+    /// Returns the device time, in nanoseconds, at which the command reached
+    /// the stage specified by `info_kind`.
+    ///
+    /// Returns `None` if the event has no profiling information: its command
+    /// has not completed, it was enqueued on a queue without profiling
+    /// enabled, or it is a user event.
+    pub fn profiling_time(&self, info_kind: ProfilingInfo) -> OclResult<Option<u64>> {
+        match self.profiling_info(info_kind) {
+            Ok(result) => result.time().map(Some).map_err(OclError::from),
+            Err(ref err) if err.is_profiling_unavailable() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns the time the command spent executing on the device (from
+    /// `ProfilingInfo::Start` to `ProfilingInfo::End`), or `None` if the
+    /// event has no profiling information.
+    pub fn profiling_duration(&self) -> OclResult<Option<Duration>> {
+        let start = match self.profiling_time(ProfilingInfo::Start)? {
+            Some(t) => t,
+            None => return Ok(None),
+        };
+        Ok(self.profiling_time(ProfilingInfo::End)?
+            .map(|end| Duration::from_nanos(end.saturating_sub(start))))
+    }
+
+    /// Returns the times at which the command passed through each stage of
+    /// execution.
+    ///
+    /// Returns an error for which `Error::is_profiling_unavailable` is true
+    /// if the event has no profiling information: its command has not
+    /// completed, it was enqueued on a queue without profiling enabled (see
+    /// `Queue::with_profiling`), or it is a user event.
+    pub fn profiling(&self) -> OclResult<EventProfiling> {
+        let time = |info_kind| -> OclResult<u64> {
+            self.profiling_info(info_kind)?.time().map_err(OclError::from)
+        };
+        Ok(EventProfiling {
+            queued: time(ProfilingInfo::Queued)?,
+            submit: time(ProfilingInfo::Submit)?,
+            start: time(ProfilingInfo::Start)?,
+            end: time(ProfilingInfo::End)?,
+        })
+    }
+
+    /// Attaches a label to this event, replacing any existing label.
+    ///
+    /// Labels are shared by all clones of an event and appear in its `Debug`
+    /// output, in `EventFailedError`, and in profiling traces (see
+    /// `Trace::record_event`). They can also be set when enqueuing a command
+    /// (e.g. `KernelCmd::label`). Has no effect on an empty event.
+    pub fn set_label(&self, label: &str) {
+        if self.is_empty() { return; }
+        let key = self.as_ptr_key();
+        let replaced = {
+            let mut labels = LABELS.write().unwrap_or_else(|e| e.into_inner());
+            labels.get_or_insert_with(HashMap::new).insert(key, (self.clone(), Arc::from(label)))
+        };
+        if replaced.is_none() {
+            LABEL_COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    /// Returns the label attached to this event, if any.
+    pub fn label(&self) -> Option<String> {
+        if self.is_empty() || LABEL_COUNT.load(Ordering::Acquire) == 0 { return None; }
+        let labels = LABELS.read().unwrap_or_else(|e| e.into_inner());
+        labels.as_ref()
+            .and_then(|labels| labels.get(&self.as_ptr_key()))
+            .map(|(_, label)| label.to_string())
+    }
+
+    #[inline]
+    fn as_ptr_key(&self) -> usize {
+        unsafe { *self.0.as_ptr_ref() as usize }
+    }
+
     /// Returns this event's associated command queue.
     pub fn queue_core(&self) -> OclResult<CommandQueueCore> {
         match self.info(EventInfo::CommandQueue)? {
@@ -140,7 +347,15 @@ impl Event {
     ///
     #[inline]
     pub fn into_raw(self) -> cl_event {
-        self.0.into_raw()
+        self.into_core().into_raw()
+    }
+
+    /// Consumes the `Event`, returning the wrapped core event without
+    /// releasing it (or its label).
+    fn into_core(self) -> EventCore {
+        let core = unsafe { ptr::read(&self.0) };
+        mem::forget(self);
+        core
     }
 
     /// Constructs an `Event` from a raw `cl_event` pointer.
@@ -185,7 +400,7 @@ impl From<Event> for EventCore {
     #[inline]
     fn from(ev: Event) -> EventCore {
         if ev.is_valid() {
-            ev.0
+            ev.into_core()
         } else {
             panic!("ocl::EventCore::from::<Event>: Invalid event.");
         }
@@ -198,6 +413,14 @@ impl Default for Event {
     }
 }
 
+impl Drop for Event {
+    fn drop(&mut self) {
+        if LABEL_COUNT.load(Ordering::Acquire) != 0 && !self.is_empty() {
+            release_label(self.as_ptr_key(), self);
+        }
+    }
+}
+
 impl Deref for Event {
     type Target = EventCore;
 
@@ -219,6 +442,15 @@ impl AsRef<EventCore> for Event {
 }
 
 
+impl fmt::Debug for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.label() {
+            Some(label) => f.debug_tuple("Event").field(&self.0).field(&label).finish(),
+            None => f.debug_tuple("Event").field(&self.0).finish(),
+        }
+    }
+}
+
 impl fmt::Display for Event {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.fmt_info(f)
@@ -250,52 +482,222 @@ unsafe impl<'a> ClWaitListPtr for  &'a Event {
 }
 
 impl Future for Event {
-    type Item = ();
-    type Error = OclError;
+    type Output = OclResult<()>;
 
     // Non-blocking, proper implementation.
-    //
-    // * NOTE: There is currently no check to ensure that only one callback is
-    //   created (is this ok?).
-    //   - TODO: Look into possible effects of unparking a task multiple times.
-    //
     #[cfg(not(feature = "async_block"))]
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
         debug_assert!(self.0.is_valid());
 
         match self.is_complete() {
             Ok(true) => {
-                Ok(Async::Ready(()))
+                Poll::Ready(Ok(()))
             }
             Ok(false) => {
-                self.set_unpark_callback()?;
-                Ok(Async::NotReady)
+                completion::register(&self, cx.waker())?;
+                Poll::Pending
             },
-            Err(err) => Err(OclError::from(err)),
+            Err(err) => Poll::Ready(Err(OclError::from(err))),
         }
     }
 
     // Blocking implementation (yuk).
     #[cfg(feature = "async_block")]
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+    fn poll(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
         debug_assert!(self.0.is_valid());
         self.wait_for()?;
-        Ok(Async::Ready(()))
+        Poll::Ready(Ok(()))
     }
 }
 
 
 
-/// Returns an empty, initialized (zeroed) event array.
-fn empty_event_array() -> NoDrop<[Event; 8]> {
-    let mut array: [Event; 8];
-    unsafe {
-        array = mem::uninitialized();
-        for elem in &mut array[..] {
-            ptr::write(elem, Event::empty());
+/// An event in a list which terminated abnormally.
+///
+/// Returned (within an `Error`) by the `wait_for` and `first_incomplete`
+/// methods of `EventList` and `EventArray`.
+#[derive(Debug, Fail)]
+pub struct EventFailedError {
+    index: usize,
+    command_type: Option<CommandType>,
+    label: Option<String>,
+    status: i32,
+}
+
+impl EventFailedError {
+    /// Returns the position of the event within the list.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the type of the command the event belongs to, if it could be
+    /// determined.
+    pub fn command_type(&self) -> Option<CommandType> {
+        self.command_type
+    }
+
+    /// Returns the label of the event, if one was set (see
+    /// `Event::set_label`).
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Returns the error status the command terminated with, if it is a
+    /// standard status code.
+    pub fn status(&self) -> Option<Status> {
+        Status::from_i32(self.status)
+    }
+
+    /// Returns the raw (negative) error code the command terminated with.
+    pub fn status_code(&self) -> i32 {
+        self.status
+    }
+}
+
+impl fmt::Display for EventFailedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Event {} in the list ", self.index)?;
+        if let Some(ref label) = self.label {
+            write!(f, "('{}') ", label)?;
+        }
+        if let Some(ref ct) = self.command_type {
+            write!(f, "(command: {:?}) ", ct)?;
         }
+        match self.status() {
+            Some(status) => write!(f, "terminated abnormally with status {:?}.", status),
+            None => write!(f, "terminated abnormally with status {}.", self.status),
+        }
+    }
+}
+
+
+
+/// Device timestamps, in nanoseconds, recorded for a command.
+///
+/// Returned by `Event::profiling`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EventProfiling {
+    queued: u64,
+    submit: u64,
+    start: u64,
+    end: u64,
+}
+
+impl EventProfiling {
+    /// Returns the time at which the command was enqueued by the host.
+    pub fn queued(&self) -> u64 {
+        self.queued
+    }
+
+    /// Returns the time at which the command was submitted to the device.
+    pub fn submit(&self) -> u64 {
+        self.submit
+    }
+
+    /// Returns the time at which the command started executing.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Returns the time at which the command finished executing.
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Returns the time spent executing (from start to end).
+    pub fn duration(&self) -> Duration {
+        Duration::from_nanos(self.end.saturating_sub(self.start))
+    }
+
+    /// Returns the time spent waiting between being enqueued and starting
+    /// to execute.
+    pub fn queue_latency(&self) -> Duration {
+        Duration::from_nanos(self.start.saturating_sub(self.queued))
+    }
+
+    /// Returns the time from being enqueued to finishing execution.
+    pub fn total(&self) -> Duration {
+        Duration::from_nanos(self.end.saturating_sub(self.queued))
     }
-    NoDrop::new(array)
+}
+
+
+
+/// An event whose status is set from the host.
+///
+/// Commands which include a user event in their wait list do not start
+/// until it is set complete, letting host-side work (file or network IO,
+/// etc.) gate commands which have already been enqueued. Setting an error
+/// instead causes those commands to be terminated without executing.
+///
+/// The status of a user event may only be set once.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[must_use = "commands waiting on a user event never start unless its status is set"]
+pub struct UserEvent(Event);
+
+impl UserEvent {
+    /// Creates a new user event with a status of
+    /// `CommandExecutionStatus::Submitted`.
+    pub fn new<C: ClContextPtr>(context: C) -> OclResult<UserEvent> {
+        Event::user(context).map(UserEvent)
+    }
+
+    /// Sets this event complete, allowing commands waiting on it to start.
+    pub fn set_complete(&self) -> OclResult<()> {
+        core::set_user_event_status(&self.0, CommandExecutionStatus::Complete)
+            .map_err(OclError::from)
+    }
+
+    /// Terminates this event with the error status `error`, cancelling
+    /// commands waiting on it.
+    ///
+    /// `error` must be an error (negative) status, e.g.
+    /// `Status::CL_OUT_OF_RESOURCES`.
+    pub fn set_error(&self, error: Status) -> OclResult<()> {
+        core::set_user_event_error(&self.0, error).map_err(OclError::from)
+    }
+
+    /// Returns the underlying event.
+    pub fn as_event(&self) -> &Event {
+        &self.0
+    }
+
+    /// Returns the underlying event.
+    pub fn into_event(self) -> Event {
+        self.0
+    }
+}
+
+impl Deref for UserEvent {
+    type Target = Event;
+
+    fn deref(&self) -> &Event {
+        &self.0
+    }
+}
+
+impl From<UserEvent> for Event {
+    fn from(ue: UserEvent) -> Event {
+        ue.0
+    }
+}
+
+impl AsRef<EventCore> for UserEvent {
+    fn as_ref(&self) -> &EventCore {
+        &(self.0).0
+    }
+}
+
+unsafe impl ClWaitListPtr for &UserEvent {
+    #[inline] unsafe fn as_ptr_ptr(&self) -> *const cl_event { (self.0).0.as_ptr_ptr() }
+    #[inline] fn count(&self) -> u32 { self.0._count() }
+}
+
+
+
+/// Returns an empty, initialized (zeroed) event array.
+fn empty_event_array<const N: usize>() -> NoDrop<[Event; N]> {
+    NoDrop::new(std::array::from_fn(|_| Event::empty()))
 }
 
 
@@ -307,8 +709,112 @@ fn take(event: &mut Event) -> Event {
 }
 
 
+/// Returns the error for the event at `index`, which terminated with the
+/// error code `status`.
+pub(crate) fn event_failed(index: usize, event: &Event, status: i32) -> OclError {
+    let command_type = match event.info(EventInfo::CommandType) {
+        Ok(EventInfoResult::CommandType(ct)) => Some(ct),
+        _ => None,
+    };
+    EventFailedError { index, command_type, label: event.label(), status }.into()
+}
+
+
+/// Waits for each event for `EventArray` and `EventList`, identifying the
+/// first event found to have failed.
+fn wait_for_events(events: &[Event]) -> OclResult<()> {
+    for (index, event) in events.iter().enumerate() {
+        if let Err(err) = event.wait_for() {
+            return Err(match core::event_status_code(event) {
+                Ok(status) if status < 0 => event_failed(index, event, status),
+                _ => OclError::from(err),
+            });
+        }
+    }
+    Ok(())
+}
+
+
+/// A thread blocked in `wait_events_timeout`, woken through the `completion`
+/// module.
+#[derive(Default)]
+struct TimeoutWait {
+    woken: Mutex<bool>,
+    cvar: Condvar,
+}
+
+impl Wake for TimeoutWait {
+    fn wake(self: Arc<Self>) {
+        *self.woken.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        self.cvar.notify_all();
+    }
+}
+
+
+/// Waits for each event for `Event`, `EventArray` and `EventList`, returning
+/// an error for which `Error::is_timed_out` is true if they have not all
+/// completed within `timeout`.
+///
+/// The calling thread sleeps on a condition variable, woken through the
+/// `completion` module as each event it waits on completes, until every
+/// event has completed or the timeout elapses. That module sets at most one
+/// callback per event however many waits time out, and the waker of a timed
+/// out wait is removed before returning. A `timeout` too large to represent
+/// as a deadline waits indefinitely.
+fn wait_events_timeout(events: &[Event], timeout: Duration) -> OclResult<()> {
+    let deadline = Instant::now().checked_add(timeout);
+    let wait = Arc::new(TimeoutWait::default());
+    let waker = Waker::from(wait.clone());
+
+    let result = loop {
+        *wait.woken.lock().unwrap_or_else(|e| e.into_inner()) = false;
+        let idx = match first_incomplete(events) {
+            Ok(Some(idx)) => idx,
+            Ok(None) => break Ok(()),
+            Err(err) => break Err(err),
+        };
+        if let Err(err) = completion::register(&events[idx], &waker) { break Err(err); }
+
+        let mut woken = wait.woken.lock().unwrap_or_else(|e| e.into_inner());
+        while !*woken {
+            woken = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline { break; }
+                    wait.cvar.wait_timeout(woken, deadline - now)
+                        .unwrap_or_else(|e| e.into_inner()).0
+                },
+                None => wait.cvar.wait(woken).unwrap_or_else(|e| e.into_inner()),
+            };
+        }
+        if !*woken { break Err(OclErrorKind::TimedOut(timeout).into()); }
+    };
+
+    for event in events {
+        completion::deregister(event, &waker);
+    }
+    result
+}
+
+
+/// Returns the index of the first incomplete event for `EventArray` and
+/// `EventList`, without blocking.
+fn first_incomplete(events: &[Event]) -> OclResult<Option<usize>> {
+    for (index, event) in events.iter().enumerate() {
+        let status = core::event_status_code(event)?;
+        if status < 0 {
+            return Err(event_failed(index, event, status));
+        } else if status != CommandExecutionStatus::Complete as i32 {
+            return Ok(Some(index));
+        }
+    }
+    Ok(None)
+}
+
+
 /// Polls events for `EventArray` and `EventList`
-fn poll_events(events: &[Event]) -> Poll<(), OclError> {
+#[cfg_attr(feature = "async_block", allow(unused_variables))]
+fn poll_events(events: &[Event], cx: &mut TaskContext<'_>) -> Poll<OclResult<()>> {
     if PRINT_DEBUG { println!("####### EventList/Array::poll: Polling Event list (thread: '{}')",
         ::std::thread::current().name().unwrap_or("<unnamed>")); }
 
@@ -320,10 +826,10 @@ fn poll_events(events: &[Event]) -> Poll<(), OclError> {
         } else {
             if !event.is_complete()? {
                 #[cfg(not(feature = "async_block"))]
-                event.set_unpark_callback()?;
+                completion::register(event, cx.waker())?;
                 if PRINT_DEBUG { println!("####### EventList/Array::poll: callback set for event: {:?} \
                     (thread: '{}')", event, ::std::thread::current().name().unwrap_or("<unnamed>")); }
-                return Ok(Async::NotReady);
+                return Poll::Pending;
             } else {
                 if PRINT_DEBUG { println!("####### EventList/Array::poll: event complete: {:?} \
                     (thread: '{}')", event, ::std::thread::current().name().unwrap_or("<unnamed>")); }
@@ -335,7 +841,7 @@ fn poll_events(events: &[Event]) -> Poll<(), OclError> {
     if PRINT_DEBUG { println!("####### EventList/Array::poll: All events complete (thread: '{}')",
         ::std::thread::current().name().unwrap_or("<unnamed>")); }
 
-    Ok(Async::Ready(()))
+    Poll::Ready(Ok(()))
     // res
 }
 
@@ -348,6 +854,12 @@ fn poll_events(events: &[Event]) -> Poll<(), OclError> {
 ///
 /// For access to individual events use `get_clone` or `last_clone`.
 ///
+/// Events are stored inline, up to a capacity of `N` (8 by default), and
+/// the list never allocates. This makes it suitable for hot loops which
+/// enqueue many commands, where it can be used as a wait list (`ewait`) or
+/// a new event destination (`enew`) in the same way as an `EventList`.
+/// Pushing to a full array fails, as does using it as a destination.
+///
 //
 // * [FIXME] TODO: impl Index.
 // #[derive(Debug)]
@@ -355,14 +867,18 @@ fn poll_events(events: &[Event]) -> Poll<(), OclError> {
 // * [NOTE]: Consider replacing with
 //   `https://github.com/servo/rust-smallvec` instead.
 //
-pub struct EventArray {
-    array: NoDrop<[Event; 8]>,
+pub struct EventArray<const N: usize = 8> {
+    array: NoDrop<[Event; N]>,
     len: usize,
 }
 
-impl EventArray {
+impl<const N: usize> EventArray<N> {
+    /// Evaluated (failing to compile) by conversions which place an event in
+    /// a new array, which is impossible with a capacity of zero.
+    const NOT_ZERO_CAPACITY: () = assert!(N > 0, "An `EventArray<0>` can not hold an event.");
+
     /// Returns a new, empty, `EventArray`.
-    pub fn new() -> EventArray {
+    pub fn new() -> EventArray<N> {
         EventArray {
             array: empty_event_array(),
             len: 0,
@@ -426,7 +942,7 @@ impl EventArray {
     #[inline]
     pub fn clear(&mut self) {
         for ev in &mut self.array[..self.len] {
-            let _ = take(ev);
+            drop(take(ev));
         }
         self.len = 0;
     }
@@ -435,26 +951,59 @@ impl EventArray {
     pub fn clear_completed(&mut self) -> OclResult<()> {
         let mut new_len = 0;
 
+        let mut result = Ok(());
+
+        // Events whose status cannot be queried are kept so that the array
+        // remains contiguous.
         for idx in 0..self.len {
-            if self.array[idx].is_complete()? {
-                let _ = take(&mut self.array[idx]);
-            } else {
-                let dst_ptr = &mut self.array[new_len] as *mut Event;
-                unsafe { ptr::swap(&mut self.array[idx], dst_ptr); }
-                new_len += 1;
+            match self.array[idx].is_complete() {
+                Ok(true) => {
+                    drop(take(&mut self.array[idx]));
+                    continue;
+                },
+                Ok(false) => (),
+                Err(err) => if result.is_ok() { result = Err(OclError::from(err)); },
             }
+            let dst_ptr = &mut self.array[new_len] as *mut Event;
+            unsafe { ptr::swap(&mut self.array[idx], dst_ptr); }
+            new_len += 1;
         }
 
         self.len = new_len;
-        Ok(())
+        result
     }
 
     /// Blocks the host thread until all events in this list are complete.
+    ///
+    /// If a command terminated abnormally, returns an error for which
+    /// `Error::failed_event` identifies its event.
     pub fn wait_for(&self) -> OclResult<()> {
-        for ev in &self.array[..self.len] {
-            ev.wait_for()?;
-        }
-        Ok(())
+        wait_for_events(self.as_slice())
+    }
+
+    /// Blocks the host thread until all events in this list are complete or
+    /// `timeout` has elapsed.
+    ///
+    /// Returns an error for which `Error::is_timed_out` is true if any event
+    /// is still incomplete after `timeout`, or for which
+    /// `Error::failed_event` identifies the event if a command terminated
+    /// abnormally.
+    pub fn wait_timeout(&self, timeout: Duration) -> OclResult<()> {
+        wait_events_timeout(self.as_slice(), timeout)
+    }
+
+    /// Returns the index of the first event which has not yet completed, or
+    /// `None` if all have, without blocking.
+    ///
+    /// Returns an error for which `Error::failed_event` is set if a command
+    /// terminated abnormally.
+    pub fn first_incomplete(&self) -> OclResult<Option<usize>> {
+        first_incomplete(self.as_slice())
+    }
+
+    /// Returns true if every event has completed, without blocking.
+    pub fn is_complete(&self) -> OclResult<bool> {
+        self.first_incomplete().map(|idx| idx.is_none())
     }
 
     /// Enqueue a marker event representing the completion of each and every
@@ -470,6 +1019,18 @@ impl EventArray {
         self.len
     }
 
+    /// The maximum number of events this list can hold (`N`).
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns true if no more events can be added.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
     /// Returns a slice of events in the list.
     #[inline]
     pub fn as_slice(&self) -> &[Event] {
@@ -482,6 +1043,12 @@ impl EventArray {
         &mut self.array[..self.len]
     }
 
+    /// Returns the whole backing array along with the length.
+    #[inline]
+    pub(crate) fn parts_mut(&mut self) -> (&mut [Event], &mut usize) {
+        (&mut self.array[..], &mut self.len)
+    }
+
     #[inline]
     fn _alloc_new(&mut self) -> Result<*mut cl_event, &'static str> {
         match self.push(Event::empty()) {
@@ -506,14 +1073,21 @@ impl EventArray {
     }
 }
 
+impl<const N: usize> Default for EventArray<N> {
+    fn default() -> EventArray<N> {
+        EventArray::new()
+    }
+}
+
 
 // Due to a fix to coherence rules
 // (https://github.com/rust-lang/rust/pull/46192) we must manually implement
 // this for each event type.
 macro_rules! from_event_into_event_array(
     ($e:ty) => (
-        impl<'a> From<$e> for EventArray {
-            fn from(event: $e) -> EventArray {
+        impl<'a, const N: usize> From<$e> for EventArray<N> {
+            fn from(event: $e) -> EventArray<N> {
+                let () = EventArray::<N>::NOT_ZERO_CAPACITY;
                 let mut array = empty_event_array();
                 array[0] = event.into();
 
@@ -529,15 +1103,15 @@ from_event_into_event_array!(EventCore);
 from_event_into_event_array!(Event);
 
 
-impl<'a, E> From<&'a E> for EventArray where E: Into<Event> + Clone {
+impl<E, const N: usize> From<&E> for EventArray<N> where E: Into<Event> + Clone {
     #[inline]
-    fn from(event: &E) -> EventArray {
+    fn from(event: &E) -> EventArray<N> {
         Self::from(event.clone().into())
     }
 }
 
-impl<'a, E> From<&'a [E]> for EventArray where E: Into<Event> + Clone {
-    fn from(events: &[E]) -> EventArray {
+impl<E, const N: usize> From<&[E]> for EventArray<N> where E: Into<Event> + Clone {
+    fn from(events: &[E]) -> EventArray<N> {
         let mut array = empty_event_array();
 
         for (idx, event) in events.iter().enumerate() {
@@ -551,7 +1125,7 @@ impl<'a, E> From<&'a [E]> for EventArray where E: Into<Event> + Clone {
     }
 }
 
-impl Deref for EventArray {
+impl<const N: usize> Deref for EventArray<N> {
     type Target = [Event];
 
     #[inline]
@@ -560,15 +1134,15 @@ impl Deref for EventArray {
     }
 }
 
-impl DerefMut for EventArray {
+impl<const N: usize> DerefMut for EventArray<N> {
     #[inline]
     fn deref_mut(&mut self) -> &mut [Event] {
         self.as_mut_slice()
     }
 }
 
-impl Clone for EventArray {
-    fn clone(&self) -> EventArray {
+impl<const N: usize> Clone for EventArray<N> {
+    fn clone(&self) -> EventArray<N> {
         let mut new_a = empty_event_array();
         for i in 0..self.len {
             new_a[i] = self.array[i].clone();
@@ -577,7 +1151,7 @@ impl Clone for EventArray {
     }
 }
 
-impl Drop for EventArray {
+impl<const N: usize> Drop for EventArray<N> {
     fn drop(&mut self) {
         // ptr::drop_in_place(self.as_mut_slice());
         self.clear();
@@ -588,23 +1162,22 @@ impl Drop for EventArray {
     }
 }
 
-impl fmt::Debug for EventArray {
+impl<const N: usize> fmt::Debug for EventArray<N> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "EventArray {{ array: {:?}, len: {} }}", &self.array[..], self.len)
     }
 }
 
-impl Future for EventArray {
-    type Item = ();
-    type Error = OclError;
+impl<const N: usize> Future for EventArray<N> {
+    type Output = OclResult<()>;
 
     /// Polls each event from this list.
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        poll_events(self.as_slice())
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        poll_events(self.as_slice(), cx)
     }
 }
 
-unsafe impl<'a> ClNullEventPtr for &'a mut EventArray {
+unsafe impl<const N: usize> ClNullEventPtr for &mut EventArray<N> {
     #[inline] fn alloc_new(&mut self) -> *mut cl_event {
         self._alloc_new().expect("<EventArray as ClNullEventPtr>::alloc_new")
     }
@@ -616,12 +1189,12 @@ unsafe impl<'a> ClNullEventPtr for &'a mut EventArray {
     }
 }
 
-unsafe impl ClWaitListPtr for EventArray {
+unsafe impl<const N: usize> ClWaitListPtr for EventArray<N> {
     #[inline] unsafe fn as_ptr_ptr(&self) -> *const cl_event { self._as_ptr_ptr() }
     #[inline] fn count(&self) -> u32 { self._count() }
 }
 
-unsafe impl<'a> ClWaitListPtr for &'a EventArray {
+unsafe impl<const N: usize> ClWaitListPtr for &EventArray<N> {
     #[inline] unsafe fn as_ptr_ptr(&self) -> *const cl_event { self._as_ptr_ptr() }
     #[inline] fn count(&self) -> u32 { self._count() }
 }
@@ -636,6 +1209,17 @@ enum Inner {
 }
 
 
+/// The auto-prune settings of an EventList.
+#[derive(Debug, Clone, Copy)]
+struct AutoPrune {
+    threshold: usize,
+    // The length at which the list will next be pruned. Grows when a prune
+    // leaves the list above `threshold` so that lists of long-running
+    // commands are not re-scanned on every push.
+    next: usize,
+}
+
+
 /// A list of events for coordinating enqueued commands.
 ///
 /// Events contain status information about the command that
@@ -659,6 +1243,7 @@ enum Inner {
 #[derive(Debug, Clone)]
 pub struct EventList {
     inner: Inner,
+    prune: Option<AutoPrune>,
 }
 
 impl EventList {
@@ -667,6 +1252,7 @@ impl EventList {
     pub fn new() -> EventList {
         EventList {
             inner: Inner::Array(EventArray::new()),
+            prune: None,
         }
     }
 
@@ -676,9 +1262,9 @@ impl EventList {
     #[inline]
     pub fn with_capacity(cap: usize) -> EventList {
         if cap <= 8 {
-            EventList { inner: Inner::Array(EventArray::new()) }
+            EventList { inner: Inner::Array(EventArray::new()), prune: None }
         } else {
-            EventList { inner: Inner::Vec(Vec::with_capacity(cap)) }
+            EventList { inner: Inner::Vec(Vec::with_capacity(cap)), prune: None }
         }
     }
 
@@ -708,22 +1294,51 @@ impl EventList {
         let mut event: Option<Event> = Some(event.into());
 
         match self.inner {
-            Inner::Array(ref mut a) => if let Some(ev) = event {
-                match a.push(ev) {
-                    Ok(_) => return,
-                    Err(ev) => event = Some(ev),
-                }
+            Inner::Array(ref mut a) => if let Some(ev) = event.take() {
+                if let Err(ev) = a.push(ev) { event = Some(ev) }
             },
-            Inner::Vec(ref mut v) => {
-                if let Some(ev) = event { v.push(ev); }
-                return;
+            Inner::Vec(ref mut v) => if let Some(ev) = event.take() {
+                v.push(ev);
             },
         }
 
         if let Some(ev) = event {
             self.convert();
             self.push(ev);
+            return;
         }
+
+        self.auto_prune();
+    }
+
+    /// Clears completed events once the list reaches its auto-prune
+    /// threshold, if one is set.
+    fn auto_prune(&mut self) {
+        let prune = match self.prune {
+            Some(prune) if self.len() >= prune.next => prune,
+            _ => return,
+        };
+        // Events whose status cannot be queried are kept and the error is
+        // left to whoever waits on them.
+        let _ = self.clear_completed();
+        let next = cmp::max(prune.threshold, self.len() * 2);
+        self.prune = Some(AutoPrune { next, ..prune });
+    }
+
+    /// Sets the length at which completed events are automatically cleared
+    /// from the list when pushing, or disables automatic pruning (the
+    /// default) if `threshold` is `None`.
+    ///
+    /// Use this to keep lists used as rolling wait lists from accumulating
+    /// completed events. The list can still grow past `threshold` while its
+    /// events remain incomplete.
+    pub fn set_auto_prune(&mut self, threshold: Option<usize>) {
+        self.prune = threshold.map(|threshold| AutoPrune { threshold, next: threshold });
+    }
+
+    /// Returns the auto-prune threshold, if set.
+    pub fn auto_prune_threshold(&self) -> Option<usize> {
+        self.prune.map(|prune| prune.threshold)
     }
 
     /// Removes the last event from the list and returns it.
@@ -752,31 +1367,94 @@ impl EventList {
         match self.inner {
             Inner::Array(ref mut a) => a.clear_completed(),
             Inner::Vec(ref mut v) => {
-                // * TODO: Reimplement optimized version using
-                //   `util::vec_remove_rebuild` (from old `EventListCore`).
-                let mut events = Vec::with_capacity(v.capacity());
-                mem::swap(&mut events, v);
-                for event in events {
-                    if !event.is_complete()? {
-                        v.push(event);
-                    }
-                }
-                Ok(())
+                let mut result = Ok(());
+                v.retain(|event| match event.is_complete() {
+                    Ok(complete) => !complete,
+                    Err(err) => {
+                        if result.is_ok() { result = Err(OclError::from(err)); }
+                        true
+                    },
+                });
+                result
             },
         }
     }
 
     /// Blocks the host thread until all events in this list are complete.
+    ///
+    /// If a command terminated abnormally, returns an error for which
+    /// `Error::failed_event` identifies its event.
     pub fn wait_for(&self) -> OclResult<()> {
-        match self.inner {
-            Inner::Array(ref a) => a.wait_for(),
-            Inner::Vec(ref v) => {
-                for event in v.iter() {
-                    event.wait_for()?;
+        wait_for_events(self.as_slice())
+    }
+
+    /// Returns a future which resolves to (a clone of) this list once all of
+    /// its events are complete, without blocking any thread while waiting.
+    ///
+    /// See `EventListFuture`.
+    pub fn wait_all_async(&self) -> EventListFuture {
+        EventListFuture::new(self.clone())
+    }
+
+    /// Blocks the host thread until all events in this list are complete or
+    /// `timeout` has elapsed.
+    ///
+    /// Returns an error for which `Error::is_timed_out` is true if any event
+    /// is still incomplete after `timeout`, or for which
+    /// `Error::failed_event` identifies the event if a command terminated
+    /// abnormally.
+    pub fn wait_timeout(&self, timeout: Duration) -> OclResult<()> {
+        wait_events_timeout(self.as_slice(), timeout)
+    }
+
+    /// Returns the index of the first event which has not yet completed, or
+    /// `None` if all have, without blocking.
+    ///
+    /// Returns an error for which `Error::failed_event` is set if a command
+    /// terminated abnormally.
+    pub fn first_incomplete(&self) -> OclResult<Option<usize>> {
+        first_incomplete(self.as_slice())
+    }
+
+    /// Returns true if every event has completed, without blocking.
+    pub fn is_complete(&self) -> OclResult<bool> {
+        self.first_incomplete().map(|idx| idx.is_none())
+    }
+
+    /// Returns a receiver which gets `Ok(())` once every event in this list
+    /// has completed, for notifying another thread without blocking on (or
+    /// polling) the list.
+    ///
+    /// If a command terminates abnormally, an error for which
+    /// `Error::failed_event` identifies its event is sent instead, as soon
+    /// as it is known. Only one result is ever sent.
+    pub fn into_receiver(self) -> OclResult<mpsc::Receiver<OclResult<()>>> {
+        let (tx, rx) = mpsc::channel();
+        if self.is_empty() {
+            let _ = tx.send(Ok(()));
+            return Ok(rx);
+        }
+
+        let remaining = Arc::new(AtomicUsize::new(self.len()));
+        let tx = Arc::new(Mutex::new(Some(tx)));
+
+        for (index, event) in self.iter().enumerate() {
+            let (remaining, tx, ev) = (remaining.clone(), tx.clone(), event.clone());
+            event.on_complete(move |status| {
+                let result = match status {
+                    Ok(_) if remaining.fetch_sub(1, Ordering::AcqRel) == 1 => Ok(()),
+                    Ok(_) => return,
+                    Err(err) => match core::event_status_code(&ev) {
+                        Ok(code) if code < 0 => Err(event_failed(index, &ev, code)),
+                        _ => Err(err),
+                    },
+                };
+                if let Some(tx) = tx.lock().unwrap_or_else(|e| e.into_inner()).take() {
+                    let _ = tx.send(result);
                 }
-                Ok(())
-            },
+            })?;
         }
+        Ok(rx)
     }
 
     /// Enqueue a marker event representing the completion of each and every
@@ -868,7 +1546,7 @@ macro_rules! from_event_into_event_list(
         impl<'a> From<$e> for EventList {
             #[inline]
             fn from(event: $e) -> EventList {
-                EventList { inner: Inner::Array(EventArray::from(event)) }
+                EventList { inner: Inner::Array(EventArray::from(event)), prune: None }
             }
         }
     )
@@ -879,14 +1557,14 @@ from_event_into_event_list!(Event);
 impl<'a, E> From<&'a E> for EventList where E: Into<Event> + Clone {
     #[inline]
     fn from(event: &E) -> EventList {
-        EventList { inner: Inner::Array(EventArray::from(event)) }
+        EventList { inner: Inner::Array(EventArray::from(event)), prune: None }
     }
 }
 
 impl<'a> From<Vec<Event>> for EventList {
     #[inline]
     fn from(events: Vec<Event>) -> EventList {
-        EventList { inner: Inner::Vec(events) }
+        EventList { inner: Inner::Vec(events), prune: None }
     }
 }
 
@@ -926,9 +1604,10 @@ impl<'a, 'b, E> From<&'a Option<&'b E>> for EventList where 'b: 'a, E: Into<Even
 impl<'a, E> From<&'a [E]> for EventList where E: Into<Event> + Clone {
     fn from(events: &[E]) -> EventList {
         if events.len() <= 8 {
-            EventList { inner: Inner::Array(EventArray::from(events)) }
+            EventList { inner: Inner::Array(EventArray::from(events)), prune: None }
         } else {
-            EventList { inner: Inner::Vec(events.iter().map(|e| e.clone().into()).collect()) }
+            let events = events.iter().map(|e| e.clone().into()).collect();
+            EventList { inner: Inner::Vec(events), prune: None }
         }
     }
 }
@@ -1095,10 +1774,15 @@ impl<'a> From<&'a [cl_event]> for EventList {
     }
 }
 
-impl<'a> From<EventArray> for EventList {
+impl<const N: usize> From<EventArray<N>> for EventList {
     #[inline]
-    fn from(events: EventArray) -> EventList {
-        EventList { inner: Inner::Array(events) }
+    fn from(mut events: EventArray<N>) -> EventList {
+        let mut list = EventList::with_capacity(events.len());
+        for event in events.as_mut_slice() {
+            list.push(take(event));
+        }
+        events.len = 0;
+        list
     }
 }
 
@@ -1187,7 +1871,7 @@ impl IntoIterator for EventList {
     fn into_iter(self) -> Self::IntoIter {
         match self.inner {
             Inner::Array(a) => {
-                let mut el = EventList { inner: Inner::Array(a) };
+                let mut el = EventList { inner: Inner::Array(a), prune: None };
                 el.convert();
                 el.into_iter()
             },
@@ -1197,12 +1881,11 @@ impl IntoIterator for EventList {
 }
 
 impl Future for EventList {
-    type Item = ();
-    type Error = OclError;
+    type Output = OclResult<()>;
 
     /// Polls each event from this list.
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        poll_events(self.as_slice())
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        poll_events(self.as_slice(), cx)
     }
 }
 
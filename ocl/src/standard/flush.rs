@@ -0,0 +1,73 @@
+//! Automatic flushing of command queues.
+//!
+//! Some drivers (notably ARM Mali) do not submit enqueued commands to the
+//! device until the queue is flushed, so futures waiting on those commands
+//! never resolve. A `FlushPolicy` set on a `Queue` flushes it automatically.
+//!
+//! Commands enqueued through this crate's command builders (buffer, image
+//! and kernel commands) are counted, whichever clone of a `Queue` they are
+//! enqueued through. The policy and count are shared by a queue and its
+//! clones.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::core::{self, CommandQueue as CommandQueueCore};
+use crate::error::{Error as OclError, Result as OclResult};
+
+
+/// When a queue is flushed automatically.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FlushPolicy {
+    /// Never flush automatically (call `Queue::flush` as needed).
+    #[default]
+    Manual,
+    /// Flush after every `n` commands.
+    Every(u32),
+    /// Flush after each command enqueued with an `enq_async` method, so that
+    /// the future it returns can resolve.
+    Async,
+}
+
+
+/// The policy shared by a queue and its clones.
+#[derive(Debug, Default)]
+pub(crate) struct FlushState {
+    policy: Mutex<FlushPolicy>,
+    pending: AtomicUsize,
+}
+
+impl FlushState {
+    pub(crate) fn policy(&self) -> FlushPolicy {
+        *self.policy.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Sets the policy, resetting the count of commands pending a flush.
+    pub(crate) fn set_policy(&self, policy: FlushPolicy) {
+        *self.policy.lock().unwrap_or_else(|e| e.into_inner()) = policy;
+        self.pending.store(0, Ordering::Release);
+    }
+
+    /// Passes through the result of enqueuing a command on `queue`, the
+    /// queue owning this state, first flushing it if the command succeeded
+    /// and the policy calls for it.
+    pub(crate) fn enqueued<R>(&self, queue: &CommandQueueCore, result: OclResult<R>,
+            is_async: bool) -> OclResult<R> {
+        let value = result?;
+
+        let flush = match self.policy() {
+            FlushPolicy::Manual => false,
+            FlushPolicy::Every(n) => {
+                let n = (n as usize).max(1);
+                // Counts up to `n` then wraps to zero, flushing on the wrap:
+                let prev = self.pending.fetch_update(Ordering::AcqRel, Ordering::Acquire,
+                    |p| Some(if p + 1 >= n { 0 } else { p + 1 })).unwrap();
+                prev + 1 >= n
+            },
+            FlushPolicy::Async => is_async,
+        };
+        if flush {
+            core::flush(queue).map_err(OclError::from)?;
+        }
+        Ok(value)
+    }
+}
@@ -0,0 +1,309 @@
+//! Named command graphs.
+//!
+//! A `CommandGraph` holds commands registered under names along with the
+//! names of the commands each must wait for. When the graph is enqueued the
+//! commands are enqueued in dependency order, each with a wait list made up
+//! of the events of its dependencies, so that wait lists never need to be
+//! assembled by hand.
+
+use std::fmt;
+use crate::core::OclPrm;
+use crate::error::Result as OclResult;
+use crate::standard::{Event, EventList, BufferCmd, BufferReadCmd, BufferWriteCmd, ImageCmd};
+
+
+/// A command which can be added to a `CommandGraph`.
+///
+/// Implemented for buffer and image commands and for closures of the form
+/// `FnOnce(&EventList, &mut Event) -> OclResult<()>`, which must enqueue a
+/// command waiting on the list and store its new event in the destination.
+/// Use a closure for kernel commands, whose enqueue is unsafe:
+///
+/// ```rust,ignore
+/// graph.add("conv1", |ewait: &EventList, enew: &mut Event| unsafe {
+///     kernel.cmd().ewait(ewait).enew(enew).enq()
+/// }).after("upload");
+/// ```
+pub trait GraphCommand {
+    /// Enqueues the command, waiting on `ewait` and creating its event in
+    /// `enew`.
+    fn enq_graph(self: Box<Self>, ewait: &EventList, enew: &mut Event) -> OclResult<()>;
+}
+
+impl<F> GraphCommand for F where F: FnOnce(&EventList, &mut Event) -> OclResult<()> {
+    fn enq_graph(self: Box<Self>, ewait: &EventList, enew: &mut Event) -> OclResult<()> {
+        (*self)(ewait, enew)
+    }
+}
+
+impl<'c, T: OclPrm> GraphCommand for BufferCmd<'c, T> {
+    fn enq_graph(self: Box<Self>, ewait: &EventList, enew: &mut Event) -> OclResult<()> {
+        (*self).ewait(ewait).enew(enew).enq()
+    }
+}
+
+impl<'c, 'd, T: OclPrm> GraphCommand for BufferReadCmd<'c, 'd, T> {
+    fn enq_graph(self: Box<Self>, ewait: &EventList, enew: &mut Event) -> OclResult<()> {
+        (*self).ewait(ewait).enew(enew).enq()
+    }
+}
+
+impl<'c, 'd, T: OclPrm> GraphCommand for BufferWriteCmd<'c, 'd, T> {
+    fn enq_graph(self: Box<Self>, ewait: &EventList, enew: &mut Event) -> OclResult<()> {
+        (*self).ewait(ewait).enew(enew).enq()
+    }
+}
+
+impl<'c, T: OclPrm> GraphCommand for ImageCmd<'c, T> {
+    fn enq_graph(self: Box<Self>, ewait: &EventList, enew: &mut Event) -> OclResult<()> {
+        (*self).ewait(ewait).enew(enew).enq()
+    }
+}
+
+
+/// A command within a `CommandGraph`.
+pub struct GraphNode<'c> {
+    name: String,
+    deps: Vec<String>,
+    cmd: Option<Box<dyn GraphCommand + 'c>>,
+    event: Option<Event>,
+}
+
+impl<'c> GraphNode<'c> {
+    /// Makes this command wait for the command named `dep`.
+    pub fn after<S: Into<String>>(&mut self, dep: S) -> &mut GraphNode<'c> {
+        self.deps.push(dep.into());
+        self
+    }
+
+    /// Returns the name of this command.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the names of the commands this command waits for.
+    pub fn deps(&self) -> &[String] {
+        &self.deps
+    }
+
+    /// Returns the event of this command once it has been enqueued.
+    pub fn event(&self) -> Option<&Event> {
+        self.event.as_ref()
+    }
+}
+
+impl<'c> fmt::Debug for GraphNode<'c> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GraphNode")
+            .field("name", &self.name)
+            .field("deps", &self.deps)
+            .field("event", &self.event)
+            .finish()
+    }
+}
+
+
+/// A set of named commands and the dependencies between them.
+///
+/// ```rust,ignore
+/// let mut graph = CommandGraph::new();
+/// graph.add("upload", buffer.cmd().write(&data));
+/// graph.add("conv1", |ewait: &EventList, enew: &mut Event| unsafe {
+///     conv1.cmd().ewait(ewait).enew(enew).enq()
+/// }).after("upload");
+/// graph.add("download", buffer.cmd().read(&mut result)).after("conv1");
+/// graph.enq()?;
+/// graph.event("download").unwrap().wait_for()?;
+/// ```
+///
+/// `Display` prints each command with the commands it waits for.
+#[derive(Debug, Default)]
+pub struct CommandGraph<'c> {
+    nodes: Vec<GraphNode<'c>>,
+}
+
+impl<'c> CommandGraph<'c> {
+    /// Returns a new, empty graph.
+    pub fn new() -> CommandGraph<'c> {
+        CommandGraph { nodes: Vec::new() }
+    }
+
+    /// Adds a command named `name`, returning its node so that dependencies
+    /// can be added with `GraphNode::after`.
+    ///
+    /// Duplicate names are reported by `::validate` and `::enq`.
+    pub fn add<S, C>(&mut self, name: S, cmd: C) -> &mut GraphNode<'c>
+            where S: Into<String>, C: GraphCommand + 'c {
+        self.nodes.push(GraphNode {
+            name: name.into(),
+            deps: Vec::new(),
+            cmd: Some(Box::new(cmd)),
+            event: None,
+        });
+        self.nodes.last_mut().unwrap()
+    }
+
+    /// Returns the command named `name`.
+    pub fn node(&self, name: &str) -> Option<&GraphNode<'c>> {
+        self.nodes.iter().find(|node| node.name == name)
+    }
+
+    /// Returns the commands in the order they were added.
+    pub fn nodes(&self) -> &[GraphNode<'c>] {
+        &self.nodes
+    }
+
+    /// Returns the event of the command named `name` once the graph has
+    /// been enqueued.
+    pub fn event(&self, name: &str) -> Option<&Event> {
+        self.node(name).and_then(|node| node.event())
+    }
+
+    /// Returns the number of commands.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if the graph has no commands.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Checks that command names are unique, that every dependency names a
+    /// command in the graph, and that there are no cycles.
+    pub fn validate(&self) -> OclResult<()> {
+        self.order().map(|_| ())
+    }
+
+    /// Returns the indexes of the commands in an order in which each
+    /// command follows its dependencies.
+    fn order(&self) -> OclResult<Vec<usize>> {
+        let mut deps = Vec::with_capacity(self.nodes.len());
+
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if self.nodes[..idx].iter().any(|other| other.name == node.name) {
+                return Err(format!("CommandGraph: Duplicate command name: '{}'.", node.name)
+                    .into());
+            }
+            let mut node_deps = Vec::with_capacity(node.deps.len());
+            for dep in &node.deps {
+                match self.nodes.iter().position(|other| &other.name == dep) {
+                    Some(dep_idx) => node_deps.push(dep_idx),
+                    None => return Err(format!("CommandGraph: Command '{}' depends on unknown \
+                        command '{}'.", node.name, dep).into()),
+                }
+            }
+            deps.push(node_deps);
+        }
+
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut placed = vec![false; self.nodes.len()];
+
+        while order.len() < self.nodes.len() {
+            let ready: Vec<usize> = (0..self.nodes.len())
+                .filter(|&idx| !placed[idx] && deps[idx].iter().all(|&dep| placed[dep]))
+                .collect();
+
+            if ready.is_empty() {
+                let cycle: Vec<&str> = (0..self.nodes.len()).filter(|&idx| !placed[idx])
+                    .map(|idx| self.nodes[idx].name.as_str()).collect();
+                return Err(format!("CommandGraph: Commands {:?} form or depend on a dependency \
+                    cycle.", cycle).into());
+            }
+            for idx in ready {
+                placed[idx] = true;
+                order.push(idx);
+            }
+        }
+
+        Ok(order)
+    }
+
+    /// Enqueues every command in dependency order.
+    ///
+    /// Nothing is enqueued if the graph does not validate. If a command
+    /// fails to enqueue, commands enqueued before it are not rolled back.
+    /// A graph can only be enqueued once.
+    pub fn enq(&mut self) -> OclResult<()> {
+        let order = self.order()?;
+
+        if let Some(node) = self.nodes.iter().find(|node| node.cmd.is_none()) {
+            return Err(format!("CommandGraph::enq: Command '{}' has already been enqueued.",
+                node.name).into());
+        }
+
+        for idx in order {
+            let mut ewait = EventList::new();
+            for dep in &self.nodes[idx].deps {
+                if let Some(event) = self.event(dep) { ewait.push(event.clone()); }
+            }
+
+            let node = &mut self.nodes[idx];
+            let cmd = node.cmd.take().unwrap();
+            let mut event = Event::empty();
+            cmd.enq_graph(&ewait, &mut event)?;
+            if event.is_empty() {
+                return Err(format!("CommandGraph::enq: Command '{}' did not create an event.",
+                    node.name).into());
+            }
+            node.event = Some(event);
+        }
+        Ok(())
+    }
+
+    /// Blocks until every enqueued command has completed.
+    pub fn wait_for(&self) -> OclResult<()> {
+        for event in self.nodes.iter().filter_map(|node| node.event()) {
+            event.wait_for()?;
+        }
+        Ok(())
+    }
+}
+
+impl<'c> fmt::Display for CommandGraph<'c> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for node in &self.nodes {
+            if node.deps.is_empty() {
+                writeln!(f, "{}", node.name)?;
+            } else {
+                writeln!(f, "{} <- {}", node.name, node.deps.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::CommandGraph;
+    use crate::standard::{Event, EventList};
+    use crate::error::Result as OclResult;
+
+    fn nop(_: &EventList, _: &mut Event) -> OclResult<()> { Ok(()) }
+
+    #[test]
+    fn graph_validate() {
+        let mut graph = CommandGraph::new();
+        graph.add("upload", nop);
+        graph.add("conv1", nop).after("upload");
+        graph.add("conv2", nop).after("upload").after("conv1");
+        assert!(graph.validate().is_ok());
+        assert_eq!(graph.order().unwrap(), vec![0, 1, 2]);
+        assert_eq!(graph.to_string(), "upload\nconv1 <- upload\nconv2 <- upload, conv1\n");
+
+        graph.add("pool", nop).after("missing");
+        assert!(graph.validate().unwrap_err().to_string().contains("unknown command 'missing'"));
+
+        let mut graph = CommandGraph::new();
+        graph.add("a", nop).after("b");
+        graph.add("b", nop).after("a");
+        graph.add("c", nop);
+        assert!(graph.validate().unwrap_err().to_string().contains("[\"a\", \"b\"]"));
+
+        let mut graph = CommandGraph::new();
+        graph.add("a", nop);
+        graph.add("a", nop);
+        assert!(graph.validate().unwrap_err().to_string().contains("Duplicate"));
+    }
+}
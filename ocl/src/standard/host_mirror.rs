@@ -0,0 +1,209 @@
+//! Incremental uploads of shared host state.
+//!
+//! A `HostMirror` pairs a host vector shared as `Arc<RwLock<Vec<T>>>` with a
+//! device buffer of the same length. Writers record which ranges they have
+//! modified and `::sync` uploads only those ranges, allowing host-resident
+//! state to be brought up to date on the device without a full re-upload.
+
+use std::ops::Range;
+use std::sync::{Arc, Mutex, RwLock};
+use crate::core::OclPrm;
+use crate::error::Result as OclResult;
+use crate::standard::{Buffer, Queue};
+
+
+/// A sorted list of disjoint, non-adjacent ranges.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DirtyRanges {
+    ranges: Vec<Range<usize>>,
+}
+
+impl DirtyRanges {
+    /// Returns a new, empty list.
+    pub fn new() -> DirtyRanges {
+        DirtyRanges::default()
+    }
+
+    /// Adds `range`, merging it with any overlapping or adjacent ranges.
+    pub fn insert(&mut self, range: Range<usize>) {
+        if range.start >= range.end { return; }
+
+        // The first range which ends at or after the start of the new one
+        // and the first which starts after its end bound the ranges to merge.
+        let first = self.ranges.iter().position(|r| r.end >= range.start)
+            .unwrap_or(self.ranges.len());
+        let last = self.ranges[first..].iter().position(|r| r.start > range.end)
+            .map(|p| first + p).unwrap_or(self.ranges.len());
+
+        let mut merged = range;
+        if first < last {
+            merged.start = merged.start.min(self.ranges[first].start);
+            merged.end = merged.end.max(self.ranges[last - 1].end);
+        }
+        self.ranges.splice(first..last, Some(merged));
+    }
+
+    /// Returns the ranges, in order.
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    /// Returns the total number of elements covered.
+    pub fn len(&self) -> usize {
+        self.ranges.iter().map(|r| r.len()).sum()
+    }
+
+    /// Returns true if no ranges are dirty.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Removes and returns all ranges.
+    pub fn take(&mut self) -> Vec<Range<usize>> {
+        std::mem::take(&mut self.ranges)
+    }
+}
+
+
+/// A device buffer kept up to date with a shared host vector by uploading
+/// only the ranges marked as modified.
+///
+/// Cloning a `HostMirror` returns a handle to the same host vector, buffer,
+/// and dirty range list.
+#[derive(Clone, Debug)]
+pub struct HostMirror<T: OclPrm> {
+    host: Arc<RwLock<Vec<T>>>,
+    buffer: Buffer<T>,
+    dirty: Arc<Mutex<DirtyRanges>>,
+}
+
+impl<T: OclPrm> HostMirror<T> {
+    /// Creates a buffer on `queue` holding a copy of the current contents of
+    /// `host`.
+    pub fn new(host: Arc<RwLock<Vec<T>>>, queue: Queue) -> OclResult<HostMirror<T>> {
+        let buffer = {
+            let data = host.read().unwrap();
+            Buffer::builder().queue(queue).len(data.len()).copy_host_slice(&data[..]).build()?
+        };
+        Ok(HostMirror { host, buffer, dirty: Arc::new(Mutex::new(DirtyRanges::new())) })
+    }
+
+    /// Mirrors `host` into an existing buffer, which must have a default
+    /// queue and the same length as `host`.
+    ///
+    /// The whole buffer is marked dirty and is uploaded by the first
+    /// `::sync`.
+    pub fn with_buffer(host: Arc<RwLock<Vec<T>>>, buffer: Buffer<T>) -> OclResult<HostMirror<T>> {
+        let len = host.read().unwrap().len();
+        if len != buffer.len() {
+            return Err(format!("HostMirror::with_buffer: Host length ({}) does not match \
+                buffer length ({}).", len, buffer.len()).into());
+        }
+        let mut dirty = DirtyRanges::new();
+        dirty.insert(0..len);
+        Ok(HostMirror { host, buffer, dirty: Arc::new(Mutex::new(dirty)) })
+    }
+
+    /// Marks `range` of the host vector as modified.
+    ///
+    /// Use this when modifying the host vector directly through its lock.
+    /// Returns an error if `range` is reversed or extends past the end of
+    /// the buffer.
+    pub fn mark_dirty(&self, range: Range<usize>) -> OclResult<()> {
+        if range.start > range.end || range.end > self.buffer.len() {
+            return Err(format!("HostMirror::mark_dirty: Invalid range ({:?}) for a buffer of \
+                length {}.", range, self.buffer.len()).into());
+        }
+        self.dirty.lock().unwrap().insert(range);
+        Ok(())
+    }
+
+    /// Locks the host vector for writing, calls `f` with `range` of it, and
+    /// marks that range as modified.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn write<R, F>(&self, range: Range<usize>, f: F) -> R
+            where F: FnOnce(&mut [T]) -> R {
+        let mut host = self.host.write().unwrap();
+        let result = f(&mut host[range.clone()]);
+        self.dirty.lock().unwrap().insert(range);
+        result
+    }
+
+    /// Returns a copy of the ranges not yet uploaded.
+    pub fn dirty(&self) -> DirtyRanges {
+        self.dirty.lock().unwrap().clone()
+    }
+
+    /// Uploads all modified ranges to the device, blocking until complete,
+    /// and returns the number of elements written.
+    ///
+    /// Writers are blocked while the upload is in progress.
+    pub fn sync(&self) -> OclResult<usize> {
+        let host = self.host.read().unwrap();
+        if host.len() != self.buffer.len() {
+            return Err(format!("HostMirror::sync: The host vector has been resized (from {} \
+                to {} elements).", self.buffer.len(), host.len()).into());
+        }
+
+        let ranges = self.dirty.lock().unwrap().take();
+        let mut written = 0;
+        for (idx, range) in ranges.iter().enumerate() {
+            let result = self.buffer.write(&host[range.clone()]).offset(range.start).enq();
+            if let Err(err) = result {
+                // Leave what has not been uploaded dirty.
+                let mut dirty = self.dirty.lock().unwrap();
+                ranges[idx..].iter().for_each(|r| dirty.insert(r.clone()));
+                return Err(err);
+            }
+            written += range.len();
+        }
+        Ok(written)
+    }
+
+    /// Returns the shared host vector.
+    pub fn host(&self) -> &Arc<RwLock<Vec<T>>> {
+        &self.host
+    }
+
+    /// Returns the device buffer.
+    ///
+    /// Its contents are only current as of the last `::sync`.
+    pub fn buffer(&self) -> &Buffer<T> {
+        &self.buffer
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::DirtyRanges;
+
+    #[test]
+    fn dirty_ranges_merge() {
+        let mut dirty = DirtyRanges::new();
+        dirty.insert(10..20);
+        dirty.insert(30..40);
+        dirty.insert(0..5);
+        assert_eq!(dirty.ranges(), &[0..5, 10..20, 30..40]);
+
+        // Adjacent:
+        dirty.insert(5..8);
+        assert_eq!(dirty.ranges(), &[0..8, 10..20, 30..40]);
+
+        // Spanning several:
+        dirty.insert(15..35);
+        assert_eq!(dirty.ranges(), &[0..8, 10..40]);
+
+        // Contained and empty:
+        dirty.insert(12..14);
+        dirty.insert(50..50);
+        assert_eq!(dirty.ranges(), &[0..8, 10..40]);
+        assert_eq!(dirty.len(), 38);
+
+        assert_eq!(dirty.take(), vec![0..8, 10..40]);
+        assert!(dirty.is_empty());
+    }
+}
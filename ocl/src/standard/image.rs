@@ -6,9 +6,12 @@
 //
 
 use std;
+use std::cmp;
 use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::marker::PhantomData;
+use std::panic::Location;
+use std::sync::Arc;
 use crate::error::{Error as OclError, Result as OclResult};
 use crate::core::{self, OclPrm, Mem as MemCore, MemFlags, MemObjectType, ImageFormatParseResult,
     ImageFormat, ImageDescriptor, ImageInfo, ImageInfoResult, MemInfo, MemInfoResult,
@@ -16,6 +19,8 @@ use crate::core::{self, OclPrm, Mem as MemCore, MemFlags, MemObjectType, ImageFo
     MapFlags};
 use crate::standard::{Context, Queue, SpatialDims, ClNullEventPtrEnum, ClWaitListPtrEnum,
     QueCtx, HostSlice};
+use crate::standard::allocations::{self, AllocationKind, AllocationRecord};
+use crate::standard::tracking::Tracker;
 use crate::MemMap;
 
 #[cfg(not(feature="opencl_vendor_mesa"))]
@@ -36,6 +41,10 @@ pub enum ImageCmdKind<'c, T: 'c> {
     CopyToBuffer { buffer: &'c MemCore, dst_origin: usize },
     GLAcquire,
     GLRelease,
+    #[cfg(all(windows, feature = "d3d11_sharing"))]
+    D3d11Acquire,
+    #[cfg(all(windows, feature = "d3d11_sharing"))]
+    D3d11Release,
 }
 
 impl<'c, T: 'c> ImageCmdKind<'c, T> {
@@ -83,6 +92,7 @@ pub struct ImageCmd<'c, T: 'c> {
     kind: ImageCmdKind<'c, T>,
     ewait: Option<ClWaitListPtrEnum<'c>>,
     enew: Option<ClNullEventPtrEnum<'c>>,
+    label: Option<&'c str>,
     mem_dims: [usize; 3],
 }
 
@@ -104,6 +114,7 @@ impl<'c, T: 'c + OclPrm> ImageCmd<'c, T> {
             kind: ImageCmdKind::Unspecified,
             ewait: None,
             enew: None,
+            label: None,
             mem_dims: dims,
         }
     }
@@ -247,6 +258,40 @@ impl<'c, T: 'c + OclPrm> ImageCmd<'c, T> {
         self
     }
 
+    /// Specifies that this command will acquire an image created from a
+    /// Direct3D 11 texture (see `Image::from_d3d11_texture_2d`).
+    ///
+    /// If `.block(..)` has been set it will be ignored.
+    ///
+    /// ## Panics
+    ///
+    /// The command operation kind must not have already been specified
+    ///
+    #[cfg(all(windows, feature = "d3d11_sharing"))]
+    pub fn d3d11_acquire(mut self) -> ImageCmd<'c, T> {
+        assert!(self.kind.is_unspec(), "ocl::ImageCmd::d3d11_acquire(): Operation kind \
+            already set for this command.");
+        self.kind = ImageCmdKind::D3d11Acquire;
+        self
+    }
+
+    /// Specifies that this command will release an image created from a
+    /// Direct3D 11 texture.
+    ///
+    /// If `.block(..)` has been set it will be ignored.
+    ///
+    /// ## Panics
+    ///
+    /// The command operation kind must not have already been specified
+    ///
+    #[cfg(all(windows, feature = "d3d11_sharing"))]
+    pub fn d3d11_release(mut self) -> ImageCmd<'c, T> {
+        assert!(self.kind.is_unspec(), "ocl::ImageCmd::d3d11_release(): Operation kind \
+            already set for this command.");
+        self.kind = ImageCmdKind::D3d11Release;
+        self
+    }
+
     /// Specifies that this command will be a fill.
     ///
     /// If `.block(..)` has been set it will be ignored.
@@ -381,7 +426,8 @@ impl<'c, T: 'c + OclPrm> ImageCmd<'c, T> {
     /// associated with this command.
     ///
     /// The destination can be a mutable reference to an empty event (created
-    /// using [`Event::empty`]) or a mutable reference to an event list.
+    /// using [`Event::empty`]), to an `Option<Event>` (replacing any event it
+    /// holds), or to an event list.
     ///
     /// After this command is enqueued, the event in the destination can be
     /// passed to the `::ewait` method of another command. Doing so will cause
@@ -414,52 +460,79 @@ impl<'c, T: 'c + OclPrm> ImageCmd<'c, T> {
         self
     }
 
+
+    /// Attaches a label to the new event created by this command (see
+    /// `Event::set_label`).
+    ///
+    /// Has no effect unless a destination for the new event is also set
+    /// with `::enew`.
+    pub fn label(mut self, label: &'c str) -> ImageCmd<'c, T> {
+        self.label = Some(label);
+        self
+    }
+
     /// Enqueues this command.
     ///
     /// * TODO: FOR COPY, FILL, AND COPYTOBUFFER -- ENSURE PITCHES ARE BOTH
     ///   UNSET.
-    pub fn enq(self) -> OclResult<()> {
+    pub fn enq(mut self) -> OclResult<()> {
         let queue = match self.queue {
             Some(q) => q,
             None => return Err("ImageCmd::enq: No queue set.".into()),
         };
+        let mut tracker = Tracker::new(queue, self.enew.take())?.labeled(self.label);
+        self.ewait = queue.fan_in_wait_list(tracker.wait_list(self.ewait.take()))?;
 
-        match self.kind {
+        let result = match self.kind {
             ImageCmdKind::Read { data } => {
                 unsafe { core::enqueue_read_image(queue, self.obj_core, self.block,
                     self.origin, self.region, self.row_pitch_bytes, self.slc_pitch_bytes, data, self.ewait,
-                    self.enew) }
+                    tracker.enew()) }
             },
             ImageCmdKind::Write { data } => {
                 unsafe {
                     core::enqueue_write_image(queue, self.obj_core, self.block,
                         self.origin, self.region, self.row_pitch_bytes, self.slc_pitch_bytes, data, self.ewait,
-                        self.enew)
+                        tracker.enew())
                 }
             },
             ImageCmdKind::Copy { dst_image, dst_origin } => {
                 core::enqueue_copy_image(queue, self.obj_core, dst_image, self.origin,
-                    dst_origin, self.region, self.ewait, self.enew)
+                    dst_origin, self.region, self.ewait, tracker.enew())
             },
 
             #[cfg(not(feature="opencl_vendor_mesa"))]
             ImageCmdKind::GLAcquire => {
                 // core::enqueue_acquire_gl_buffer(queue, self.obj_core, self.ewait, self.enew)
                 let buf_slc = unsafe { std::slice::from_raw_parts(self.obj_core, 1) };
-                core::enqueue_acquire_gl_objects(queue, buf_slc, self.ewait, self.enew)
+                core::enqueue_acquire_gl_objects(queue, buf_slc, self.ewait, tracker.enew())
             },
 
             #[cfg(not(feature="opencl_vendor_mesa"))]
             ImageCmdKind::GLRelease => {
                 // core::enqueue_release_gl_buffer(queue, self.obj_core, self.ewait, self.enew)
                 let buf_slc = unsafe { std::slice::from_raw_parts(self.obj_core, 1) };
-                core::enqueue_release_gl_objects(queue, buf_slc, self.ewait, self.enew)
+                core::enqueue_release_gl_objects(queue, buf_slc, self.ewait, tracker.enew())
+            },
+
+            #[cfg(all(windows, feature = "d3d11_sharing"))]
+            ImageCmdKind::D3d11Acquire => {
+                let img_slc = std::slice::from_ref(self.obj_core);
+                core::enqueue_acquire_d3d11_objects(queue, img_slc, self.ewait, tracker.enew())
+            },
+
+            #[cfg(all(windows, feature = "d3d11_sharing"))]
+            ImageCmdKind::D3d11Release => {
+                let img_slc = std::slice::from_ref(self.obj_core);
+                core::enqueue_release_d3d11_objects(queue, img_slc, self.ewait, tracker.enew())
             },
 
             ImageCmdKind::Unspecified => Err("ocl::ImageCmd::enq(): No operation \
                 specified. Use '.read(...)', 'write(...)', etc. before calling '.enq()'.".into()),
             _ => unimplemented!(),
-        }.map_err(OclError::from)
+        }.map_err(OclError::from);
+        let result = tracker.finish(result);
+        queue.enqueued(result, false)
     }
 }
 
@@ -559,7 +632,8 @@ impl<'c, T> ImageMapCmd<'c, T> where T: OclPrm {
     /// associated with this command.
     ///
     /// The destination can be a mutable reference to an empty event (created
-    /// using [`Event::empty`]) or a mutable reference to an event list.
+    /// using [`Event::empty`]), to an `Option<Event>` (replacing any event it
+    /// holds), or to an event list.
     ///
     /// After this command is enqueued, the event in the destination can be
     /// passed to the `::ewait` method of another command. Doing so will cause
@@ -596,11 +670,12 @@ impl<'c, T> ImageMapCmd<'c, T> where T: OclPrm {
     ///
     /// * TODO: FOR COPY, FILL, AND COPYTOBUFFER -- ENSURE PITCHES ARE BOTH UNSET.
     #[allow(unused_variables, unreachable_code)]
-    pub fn enq(self) -> OclResult<MemMap<T>> {
+    pub fn enq(mut self) -> OclResult<MemMap<T>> {
         let queue = match self.cmd.queue {
             Some(q) => q,
             None => return Err("ImageCmd::enq: No queue set.".into()),
         };
+        self.cmd.ewait = queue.fan_in_wait_list(self.cmd.ewait.take())?;
 
         let flags = self.flags.unwrap_or(MapFlags::empty());
 
@@ -650,6 +725,13 @@ impl<'c, T> ImageMapCmd<'c, T> where T: OclPrm {
 }
 
 
+/// Returns the size, in bytes, of an image described by `desc` whose pixels
+/// are `pixel_bytes` in size.
+fn image_bytes(pixel_bytes: usize, desc: &ImageDescriptor) -> usize {
+    pixel_bytes * desc.image_width * cmp::max(desc.image_height, 1)
+        * cmp::max(desc.image_depth, 1) * cmp::max(desc.image_array_size, 1)
+}
+
 
 /// A section of device memory which represents one or many images.
 ///
@@ -661,6 +743,7 @@ pub struct Image<T: OclPrm> {
     queue: Option<Queue>,
     dims: SpatialDims,
     pixel_element_len: usize,
+    allocation: Option<Arc<AllocationRecord>>,
     _pixel: PhantomData<T>
 }
 
@@ -680,6 +763,7 @@ impl<T: OclPrm> Image<T> {
     /// Returns a new `Image`.
     ///
     /// Prefer `::builder` to create a new image.
+    #[track_caller]
     pub unsafe fn new<'o, Q>(que_ctx: Q, flags: MemFlags, image_format: ImageFormat,
             image_desc: ImageDescriptor, host_data: Option<&[T]>) -> OclResult<Image<T>>
             where Q: Into<QueCtx<'o>> {
@@ -687,6 +771,12 @@ impl<T: OclPrm> Image<T> {
         let context = que_ctx.context_cloned();
         let device_versions = context.device_versions()?;
 
+        let size = image_bytes(image_format.pixel_bytes(), &image_desc);
+        let reservation = match image_desc.buffer {
+            None => allocations::reserve(&context, que_ctx.device(), size as u64)?,
+            Some(_) => allocations::reserve_unchecked(&context, que_ctx.device(), size as u64),
+        };
+
         let obj_core = core::create_image(
             &context,
             flags,
@@ -704,11 +794,15 @@ impl<T: OclPrm> Image<T> {
 
         let dims = [image_desc.image_width, image_desc.image_height, image_desc.image_depth].into();
 
+        let allocation = allocations::track(AllocationKind::Image, reservation, flags,
+            Some(Location::caller()));
+
         let new_img = Image {
             obj_core,
             queue: que_ctx.into(),
             dims,
             pixel_element_len,
+            allocation: Some(allocation),
             _pixel: PhantomData,
         };
 
@@ -718,6 +812,7 @@ impl<T: OclPrm> Image<T> {
     /// Returns a new `Image` from an existant GL texture2D/3D.
     // [WORK IN PROGRESS]
     #[cfg(not(feature="opencl_vendor_mesa"))]
+    #[track_caller]
     pub fn from_gl_texture<'o, Q>(que_ctx: Q, flags: MemFlags, image_desc: ImageDescriptor,
             texture_target: GlTextureTarget, miplevel: cl_GLint, texture: cl_GLuint)
             -> OclResult<Image<T>>
@@ -750,11 +845,17 @@ impl<T: OclPrm> Image<T> {
 
         let dims = [image_desc.image_width, image_desc.image_height, image_desc.image_depth].into();
 
+        let size = image_bytes(pixel_element_len * mem::size_of::<T>(), &image_desc);
+        let reservation = allocations::reserve_unchecked(&context, que_ctx.device(), size as u64);
+        let allocation = allocations::track(AllocationKind::Image, reservation, flags,
+            Some(Location::caller()));
+
         let new_img = Image {
             obj_core,
             queue: que_ctx.into(),
             dims,
             pixel_element_len,
+            allocation: Some(allocation),
             _pixel: PhantomData,
         };
 
@@ -764,6 +865,7 @@ impl<T: OclPrm> Image<T> {
     /// Returns a new `Image` from an existant renderbuffer.
     // [WORK IN PROGRESS]
     #[cfg(not(feature="opencl_vendor_mesa"))]
+    #[track_caller]
     pub fn from_gl_renderbuffer<'o, Q>(que_ctx: Q, flags: MemFlags, image_desc: ImageDescriptor,
             renderbuffer: cl_GLuint) -> OclResult<Image<T>>
             where Q: Into<QueCtx<'o>>
@@ -786,17 +888,110 @@ impl<T: OclPrm> Image<T> {
 
         let dims = [image_desc.image_width, image_desc.image_height].into();
 
+        let size = image_bytes(pixel_element_len * mem::size_of::<T>(), &image_desc);
+        let reservation = allocations::reserve_unchecked(&context, que_ctx.device(), size as u64);
+        let allocation = allocations::track(AllocationKind::Image, reservation, flags,
+            Some(Location::caller()));
+
         let new_img = Image {
             obj_core,
             queue: que_ctx.into(),
             dims,
             pixel_element_len,
+            allocation: Some(allocation),
             _pixel: PhantomData,
         };
 
         Ok(new_img)
     }
 
+    /// Returns a new `Image` linked to a subresource of a Direct3D 11 2D
+    /// texture (`ID3D11Texture2D *`).
+    ///
+    /// The context must have been created with a Direct3D 11 device (see
+    /// `ContextProperties::d3d11_device`). Use `.cmd().d3d11_acquire().enq()`
+    /// before using the image and `.cmd().d3d11_release().enq()` after.
+    ///
+    /// [UNTESTED]
+    ///
+    /// ## Safety
+    ///
+    /// `resource` must be a valid `ID3D11Texture2D *` created by the
+    /// context's Direct3D device and must outlive the returned image.
+    #[cfg(all(windows, feature = "d3d11_sharing"))]
+    #[track_caller]
+    pub unsafe fn from_d3d11_texture_2d<'o, Q>(que_ctx: Q, flags: MemFlags,
+            resource: *mut crate::ffi::c_void, subresource: u32) -> OclResult<Image<T>>
+            where Q: Into<QueCtx<'o>>
+    {
+        let que_ctx = que_ctx.into();
+        let obj_core = core::create_from_d3d11_texture_2d(&que_ctx.context_cloned(), flags,
+            resource, subresource)?;
+        Image::from_shared_core(que_ctx, obj_core, flags)
+    }
+
+    /// Returns a new `Image` linked to a subresource of a Direct3D 11 3D
+    /// texture (`ID3D11Texture3D *`).
+    ///
+    /// See `::from_d3d11_texture_2d`.
+    ///
+    /// [UNTESTED]
+    ///
+    /// ## Safety
+    ///
+    /// `resource` must be a valid `ID3D11Texture3D *` created by the
+    /// context's Direct3D device and must outlive the returned image.
+    #[cfg(all(windows, feature = "d3d11_sharing"))]
+    #[track_caller]
+    pub unsafe fn from_d3d11_texture_3d<'o, Q>(que_ctx: Q, flags: MemFlags,
+            resource: *mut crate::ffi::c_void, subresource: u32) -> OclResult<Image<T>>
+            where Q: Into<QueCtx<'o>>
+    {
+        let que_ctx = que_ctx.into();
+        let obj_core = core::create_from_d3d11_texture_3d(&que_ctx.context_cloned(), flags,
+            resource, subresource)?;
+        Image::from_shared_core(que_ctx, obj_core, flags)
+    }
+
+    /// Returns a new `Image` wrapping an image created from another API's
+    /// object, reading its dimensions from the image.
+    #[cfg(all(windows, feature = "d3d11_sharing"))]
+    #[track_caller]
+    fn from_shared_core(que_ctx: QueCtx, obj_core: MemCore, flags: MemFlags)
+            -> OclResult<Image<T>> {
+        let info = |kind| match core::get_image_info(&obj_core, kind) {
+            Ok(ImageInfoResult::ElementSize(s)) | Ok(ImageInfoResult::Width(s)) |
+                Ok(ImageInfoResult::Height(s)) | Ok(ImageInfoResult::Depth(s)) => Ok(s),
+            Ok(_) => Err(OclError::from("ocl::Image::from_shared_core(): Unexpected \
+                'ImageInfoResult' variant.")),
+            Err(err) => Err(OclError::from(err)),
+        };
+
+        let element_size = info(ImageInfo::ElementSize)?;
+        let pixel_element_len = element_size / mem::size_of::<T>();
+        let (width, height, depth) = (info(ImageInfo::Width)?, info(ImageInfo::Height)?,
+            info(ImageInfo::Depth)?);
+        let dims = match depth {
+            0 => [width, height].into(),
+            depth => [width, height, depth].into(),
+        };
+
+        let size = element_size * width * cmp::max(height, 1) * cmp::max(depth, 1);
+        let reservation = allocations::reserve_unchecked(&que_ctx.context_cloned(),
+            que_ctx.device(), size as u64);
+        let allocation = allocations::track(AllocationKind::Image, reservation, flags,
+            Some(Location::caller()));
+
+        Ok(Image {
+            obj_core,
+            queue: que_ctx.into(),
+            dims,
+            pixel_element_len,
+            allocation: Some(allocation),
+            _pixel: PhantomData,
+        })
+    }
+
     /// Returns an image command builder used to read, write, copy, etc.
     ///
     /// Call `.enq()` to enqueue the command.
@@ -886,6 +1081,21 @@ impl<T: OclPrm> Image<T> {
         self.queue.as_ref()
     }
 
+    /// Assigns a tag identifying this image in allocation dumps (see
+    /// `Context::dump_allocations`).
+    ///
+    /// Tags are shared with clones.
+    pub fn set_tag<S: Into<String>>(&self, tag: S) {
+        if let Some(ref allocation) = self.allocation {
+            allocation.set_tag(Some(tag.into()));
+        }
+    }
+
+    /// Returns the tag assigned to this image, if any.
+    pub fn tag(&self) -> Option<String> {
+        self.allocation.as_ref().and_then(|a| a.tag())
+    }
+
     /// Returns this image's dimensions.
     pub fn dims(&self) -> &SpatialDims {
         &self.dims
@@ -1009,6 +1219,7 @@ pub struct ImageBuilder<'a, T> where T: 'a {
     host_slice: HostSlice<'a, T>,
     image_format: ImageFormat,
     image_desc: ImageDescriptor,
+    tag: Option<String>,
     _pixel: PhantomData<T>,
 }
 
@@ -1053,6 +1264,7 @@ impl<'a, T> ImageBuilder<'a, T> where T: 'a + OclPrm {
             host_slice: HostSlice::None,
             image_format: ImageFormat::new_rgba(),
             image_desc: ImageDescriptor::new(MemObjectType::Image1d, 0, 0, 0, 0, 0, 0, None),
+            tag: None,
             _pixel: PhantomData,
         }
     }
@@ -1331,8 +1543,16 @@ impl<'a, T> ImageBuilder<'a, T> where T: 'a + OclPrm {
         self
     }
 
+    /// Assigns a tag identifying the image in allocation dumps (see
+    /// `Context::dump_allocations`).
+    pub fn tag<S: Into<String>>(mut self, tag: S) -> ImageBuilder<'a, T> {
+        self.tag = Some(tag.into());
+        self
+    }
+
     /// Builds with no host side image data memory specified and returns a
     /// new `Image`.
+    #[track_caller]
     pub fn build(mut self) -> OclResult<Image<T>> {
         let host_slice = match self.host_slice {
             HostSlice::Use(hs) => {
@@ -1346,14 +1566,19 @@ impl<'a, T> ImageBuilder<'a, T> where T: 'a + OclPrm {
             HostSlice::None => None,
         };
 
-        match self.queue_option {
+        let image = match self.queue_option {
             Some(qo) => {
                 unsafe { Image::new(qo, self.flags, self.image_format.clone(),
-                    self.image_desc.clone(), host_slice) }
+                    self.image_desc.clone(), host_slice)? }
             },
             None => panic!("ocl::ImageBuilder::build: A context or default queue must be set \
                 with '.context(...)' or '.queue(...)'."),
+        };
+
+        if let Some(tag) = self.tag {
+            image.set_tag(tag);
         }
+        Ok(image)
 
     }
 }
@@ -9,14 +9,28 @@ use std::collections::{HashMap, BTreeMap};
 use std::marker::PhantomData;
 use std::cell::RefCell;
 use std::borrow::Borrow;
+use std::sync::Arc;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context as TaskContext, Poll};
 use crate::core::ffi::c_void;
-use crate::core::{self, util, OclPrm, Kernel as KernelCore, CommandQueue as CommandQueueCore, Mem as MemCore,
+use crate::core::{self, util, OclPrm, Kernel as KernelCore, Mem as MemCore,
     ArgVal, KernelInfo, KernelInfoResult, KernelArgInfo, KernelArgInfoResult,
-    KernelWorkGroupInfo, KernelWorkGroupInfoResult, AsMem, MemCmdAll, ClVersions};
+    KernelWorkGroupInfo, KernelWorkGroupInfoResult, AsMem, MemCmdAll, ClVersions,
+    ClNullEventPtr};
+#[cfg(feature = "opencl_version_2_0")]
+use crate::core::KernelExecInfo;
+#[cfg(feature = "opencl_version_2_0")]
+use crate::standard::{SvmBuffer, SvmAlloc, linked_allocs};
 use crate::core::error::{ErrorKind as OclCoreErrorKind};
 use crate::error::{Error as OclError, Result as OclResult, ErrorKind as OclErrorKind};
 use crate::standard::{SpatialDims, Program, Queue, WorkDims, Sampler, Device, ClNullEventPtrEnum,
-    ClWaitListPtrEnum, Buffer, Image};
+    ClWaitListPtrEnum, Buffer, Image, Context, Quirks, Event};
+use crate::standard::reload::KernelReload;
+use crate::standard::quirks;
+use crate::standard::tracking::Tracker;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
 pub use self::arg_type::{BaseType, Cardinality, ArgType};
 
 
@@ -47,6 +61,123 @@ pub enum KernelError {
         (required: {}, specified: {}). Use named arguments with 'None' or zero values to \
         declare arguments you plan to assign a value to at a later time.", required, specified)]
     BuilderWrongArgCount { required: u32, specified: u32 },
+    #[fail(display = "The local work size has {} dimensions but the global work size has {}.",
+        lws_dims, gws_dims)]
+    WorkSizeDimMismatch { gws_dims: u32, lws_dims: u32 },
+    #[fail(display = "The local work size ({}) exceeds the device maximum ({}) in dimension \
+        {}. See 'Device::max_work_item_sizes'.", lws, max, dim)]
+    LwsExceedsMaxWorkItemSize { dim: usize, lws: usize, max: usize },
+    #[fail(display = "The local work size ({} work items) exceeds the device maximum \
+        work-group size ({}). See 'Device::max_wg_size'.", lws, max)]
+    LwsExceedsMaxWorkGroupSize { lws: usize, max: usize },
+    #[fail(display = "The global work size ({}) is not a multiple of the local work size ({}) \
+        in dimension {}. Round the global work size up with 'KernelCmd::pad_global_work_size' \
+        or 'SpatialDims::padded_to' and have the kernel skip the extra work items, for example \
+        by starting it with 'if (get_global_id({}) >= len) return;' where 'len' is the unpadded \
+        size passed as an argument.", gws, lws, dim, dim)]
+    GwsNotMultipleOfLws { dim: usize, gws: usize, lws: usize },
+    #[fail(display = "SVM arguments are not supported on kernels created from a reloadable \
+        program: they cannot be restored after the program is rebuilt.")]
+    SvmArgReloadable,
+}
+
+
+/// A set of work sizes and offsets with which to launch a kernel.
+///
+/// Fields left as `None` leave the corresponding setting unchanged when
+/// applied. With the `serde` feature enabled, a `LaunchConfig` may be loaded
+/// from a configuration file, allowing work sizes to be tuned per deployment.
+///
+/// ```rust,ignore
+/// // { "global_work_size": [1024, 1024], "local_work_size": [16, 16] }
+/// let config: LaunchConfig = serde_json::from_str(&config_text)?;
+/// let kernel = Kernel::builder().program(&program).name("add")
+///     .launch_config(&config)
+///     ...
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct LaunchConfig {
+    pub global_work_offset: Option<SpatialDims>,
+    pub global_work_size: Option<SpatialDims>,
+    pub local_work_size: Option<SpatialDims>,
+}
+
+impl LaunchConfig {
+    /// Overwrites each of the specified settings.
+    fn apply(&self, gwo: &mut SpatialDims, gws: &mut SpatialDims, lws: &mut SpatialDims) {
+        if let Some(d) = self.global_work_offset { *gwo = d; }
+        if let Some(d) = self.global_work_size { *gws = d; }
+        if let Some(d) = self.local_work_size { *lws = d; }
+    }
+}
+
+
+/// The work size limits of a device, used to validate work sizes before a
+/// kernel is enqueued (see `KernelCmd::check_work_sizes`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkSizeLimits {
+    max_work_item_sizes: Vec<usize>,
+    max_work_group_size: usize,
+}
+
+impl WorkSizeLimits {
+    /// Returns a new set of limits from the maximum work-group size in each
+    /// dimension (`CL_DEVICE_MAX_WORK_ITEM_SIZES`) and in total
+    /// (`CL_DEVICE_MAX_WORK_GROUP_SIZE`).
+    pub fn new(max_work_item_sizes: Vec<usize>, max_work_group_size: usize) -> WorkSizeLimits {
+        WorkSizeLimits { max_work_item_sizes, max_work_group_size }
+    }
+
+    /// Returns the limits of `device`.
+    pub fn for_device(device: &Device) -> OclResult<WorkSizeLimits> {
+        Ok(WorkSizeLimits::new(device.max_work_item_sizes()?, device.max_wg_size()?))
+    }
+
+    /// Returns the maximum work-group size in each dimension.
+    pub fn max_work_item_sizes(&self) -> &[usize] {
+        &self.max_work_item_sizes
+    }
+
+    /// Returns the maximum total number of work items in a work-group.
+    pub fn max_work_group_size(&self) -> usize {
+        self.max_work_group_size
+    }
+
+    /// Checks `lws` against these limits and `gws` against `lws`.
+    ///
+    /// An unspecified `lws` (left to the implementation) always passes. The
+    /// global work size must be a multiple of the local work size in each
+    /// dimension, as required by OpenCL 1.x and by 2.0+ kernels built
+    /// with uniform work-groups.
+    pub fn check(&self, gws: &SpatialDims, lws: &SpatialDims) -> Result<(), KernelError> {
+        if lws.is_unspecified() { return Ok(()); }
+
+        if lws.dim_count() != gws.dim_count() {
+            return Err(KernelError::WorkSizeDimMismatch { gws_dims: gws.dim_count(),
+                lws_dims: lws.dim_count() });
+        }
+
+        for dim in 0..lws.dim_count() as usize {
+            let max = self.max_work_item_sizes.get(dim).cloned().unwrap_or(1);
+            if lws[dim] > max {
+                return Err(KernelError::LwsExceedsMaxWorkItemSize { dim, lws: lws[dim], max });
+            }
+        }
+
+        if lws.to_len() > self.max_work_group_size {
+            return Err(KernelError::LwsExceedsMaxWorkGroupSize { lws: lws.to_len(),
+                max: self.max_work_group_size });
+        }
+
+        for dim in 0..gws.dim_count() as usize {
+            if lws[dim] == 0 || !gws[dim].is_multiple_of(lws[dim]) {
+                return Err(KernelError::GwsNotMultipleOfLws { dim, gws: gws[dim],
+                    lws: lws[dim] });
+            }
+        }
+        Ok(())
+    }
 }
 
 
@@ -55,12 +186,17 @@ pub enum KernelError {
 #[must_use = "commands do nothing unless enqueued"]
 pub struct KernelCmd<'k> {
     kernel: &'k KernelCore,
-    queue: Option<&'k CommandQueueCore>,
+    reload: Option<&'k KernelReload>,
+    queue: Option<&'k Queue>,
     gwo: SpatialDims,
     gws: SpatialDims,
     lws: SpatialDims,
     wait_events: Option<ClWaitListPtrEnum<'k>>,
     new_event: Option<ClNullEventPtrEnum<'k>>,
+    label: Option<&'k str>,
+    quirks: Quirks,
+    check_work_sizes: bool,
+    pad_gws: bool,
 }
 
 /// A kernel enqueue command.
@@ -71,7 +207,7 @@ impl<'k> KernelCmd<'k> {
     /// queue is set, this method **must** be called before enqueuing the
     /// kernel.
     pub fn queue<'q, Q>(mut self, queue: &'q Q) -> KernelCmd<'k>
-            where 'q: 'k, Q: 'k + AsRef<CommandQueueCore> {
+            where 'q: 'k, Q: 'k + AsRef<Queue> {
         self.queue = Some(queue.as_ref());
         self
     }
@@ -115,6 +251,33 @@ impl<'k> KernelCmd<'k> {
         self
     }
 
+    /// Applies the work sizes and offset specified by `config` for this call
+    /// only.
+    pub fn launch_config(mut self, config: &LaunchConfig) -> KernelCmd<'k> {
+        config.apply(&mut self.gwo, &mut self.gws, &mut self.lws);
+        self
+    }
+
+    /// Checks the work sizes against the limits of the queue's device (see
+    /// `WorkSizeLimits::check`) before enqueuing, returning a descriptive
+    /// `KernelError` instead of a bare `CL_INVALID_WORK_GROUP_SIZE`.
+    pub fn check_work_sizes(mut self) -> KernelCmd<'k> {
+        self.check_work_sizes = true;
+        self
+    }
+
+    /// Rounds the global work size up to a multiple of the local work size
+    /// in each dimension (see `SpatialDims::padded_to`).
+    ///
+    /// The kernel must skip the extra work items, for example by starting
+    /// with `if (get_global_id(0) >= len) return;`, `len` being the unpadded
+    /// size passed as an argument. Has no effect if no local work size is
+    /// set.
+    pub fn pad_global_work_size(mut self) -> KernelCmd<'k> {
+        self.pad_gws = true;
+        self
+    }
+
     /// Specifies an event or list of events to wait on before the command
     /// will run.
     ///
@@ -153,7 +316,8 @@ impl<'k> KernelCmd<'k> {
     /// associated with this command.
     ///
     /// The destination can be a mutable reference to an empty event (created
-    /// using [`Event::empty`]) or a mutable reference to an event list.
+    /// using [`Event::empty`]), to an `Option<Event>` (replacing any event it
+    /// holds), or to an event list.
     ///
     /// After this command is enqueued, the event in the destination can be
     /// passed to the `::ewait` method of another command. Doing so will cause
@@ -186,6 +350,17 @@ impl<'k> KernelCmd<'k> {
         self
     }
 
+
+    /// Attaches a label to the new event created by this command (see
+    /// `Event::set_label`).
+    ///
+    /// Has no effect unless a destination for the new event is also set
+    /// with `::enew`.
+    pub fn label(mut self, label: &'k str) -> KernelCmd<'k> {
+        self.label = Some(label);
+        self
+    }
+
     /// Enqueues this kernel command.
     ///
     /// # Safety
@@ -193,7 +368,51 @@ impl<'k> KernelCmd<'k> {
     /// All kernel code must be considered untrusted. Therefore the act of
     /// calling this function contains implied unsafety even though the API
     /// itself is safe.
-    pub unsafe fn enq(self) -> OclResult<()> {
+    pub unsafe fn enq(mut self) -> OclResult<()> {
+        let new_event = self.new_event.take();
+        self.enqueue(new_event, false)
+    }
+
+    /// Enqueues this kernel command and returns a future which resolves
+    /// once the kernel completes.
+    ///
+    /// The queue is flushed as its flush policy calls for (see
+    /// `Queue::set_flush_policy`). Use `FlushPolicy::Async` on drivers which
+    /// do not start work until a queue is flushed.
+    ///
+    /// # Safety
+    ///
+    /// See `::enq`.
+    pub unsafe fn enq_async(self) -> OclResult<FutureKernel> {
+        self.enq_async_holding(())
+    }
+
+    /// Enqueues this kernel command and returns a future which resolves to
+    /// `held` once the kernel completes.
+    ///
+    /// `held` is kept alive for the duration of the kernel and is typically
+    /// made up of guards (such as a `MemMap` or `RwVec` guard) protecting
+    /// memory the kernel uses. If the future is dropped before the kernel
+    /// completes, the current thread blocks until it does.
+    ///
+    /// # Safety
+    ///
+    /// See `::enq`.
+    pub unsafe fn enq_async_holding<H>(mut self, held: H) -> OclResult<FutureKernel<H>> {
+        let mut event = Event::empty();
+        let new_event = self.new_event.take();
+        if let Some(ref enew) = new_event { enew.check_capacity()?; }
+        self.enqueue(Some((&mut event).into()), true)?;
+
+        if let Some(mut enew) = new_event {
+            enew.clone_from(&event);
+        }
+        Ok(FutureKernel { event, held: Some(held) })
+    }
+
+    /// Enqueues this kernel command, storing its event in `new_event`.
+    unsafe fn enqueue(self, new_event: Option<ClNullEventPtrEnum>, is_async: bool)
+            -> OclResult<()> {
         let queue = match self.queue {
             Some(q) => q,
             None => return Err(KernelError::CmdNoQueue.into()),
@@ -201,17 +420,82 @@ impl<'k> KernelCmd<'k> {
 
         let dim_count = self.gws.dim_count();
 
-        let gws = match self.gws.to_work_size() {
+        let gws_dims = if self.pad_gws { self.gws.padded_to(&self.lws) } else { self.gws };
+
+        let gws = match gws_dims.to_work_size() {
             Some(gws) => gws,
             None => return Err(KernelError::CmdNoGws.into()),
         };
 
-        core::enqueue_kernel(queue, &self.kernel, dim_count, self.gwo.to_work_offset(),
-            &gws, self.lws.to_work_size(), self.wait_events, self.new_event)
-            .map_err(OclError::from)
+        if self.check_work_sizes {
+            let device = queue.device();
+            WorkSizeLimits::for_device(&device)?.check(&gws_dims, &self.lws)?;
+        }
+
+        let mut tracker = Tracker::new(queue, new_event)?.labeled(self.label);
+        let wait_events = quirks::fan_in_wait_list(queue, tracker.wait_list(self.wait_events),
+            self.quirks.max_wait_list_len)?;
+
+        let replacement = self.reload.and_then(|r| r.replacement());
+        let kernel = replacement.as_ref().unwrap_or(self.kernel);
+
+        let result = core::enqueue_kernel(queue, kernel, dim_count, self.gwo.to_work_offset(),
+            &gws, self.lws.to_work_size(), wait_events, tracker.enew())
+            .map_err(OclError::from);
+        queue.enqueued(tracker.finish(result), is_async)
+    }
+}
+
+
+/// A future which resolves once an enqueued kernel completes.
+///
+/// Resolves to the values held for the duration of the kernel (see
+/// `KernelCmd::enq_async_holding`). Completion is signaled by an event
+/// callback rather than by polling the event status.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct FutureKernel<H = ()> {
+    event: Event,
+    held: Option<H>,
+}
+
+impl<H> FutureKernel<H> {
+    /// Returns the event of the kernel command.
+    pub fn event(&self) -> &Event {
+        &self.event
+    }
+
+    /// Blocks the current thread until the kernel completes.
+    pub fn wait(self) -> OclResult<H> {
+        futures::executor::block_on(self)
     }
 }
 
+impl<H> Future for FutureKernel<H> {
+    type Output = OclResult<H>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.event).poll(cx) {
+            Poll::Ready(Ok(())) => Poll::Ready(self.held.take()
+                .ok_or_else(|| "FutureKernel::poll: Already resolved.".into())),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<H> Drop for FutureKernel<H> {
+    /// Blocks until the kernel completes if the held values have not yet
+    /// been returned, ensuring they outlive the kernel.
+    fn drop(&mut self) {
+        if std::mem::needs_drop::<H>() && self.held.is_some() {
+            self.event.wait_for().ok();
+        }
+    }
+}
+
+impl<H> Unpin for FutureKernel<H> {}
+
 
 /// Converts an argument index specifier to `u32`.
 #[derive(Clone, Debug)]
@@ -268,6 +552,11 @@ enum ArgValKeeper<'b> {
     Shared(ArgVal<'b>),
     OwnedPrm(Vec<u8>),
     OwnedMem(MemCore),
+    #[cfg(feature = "opencl_version_2_0")]
+    Svm(Arc<SvmAlloc>),
+    // An SVM address not owned by an `SvmAlloc` (see `arg_svm_ptr`).
+    #[cfg(feature = "opencl_version_2_0")]
+    SvmPtr(usize),
 }
 
 impl<'b> ArgValKeeper<'b> {
@@ -294,6 +583,9 @@ impl<'b> ArgValKeeper<'b> {
             ArgValKeeper::OwnedMem(ref mem) => {
                 ArgVal::mem(mem)
             }
+            // SVM pointers are set separately, with `clSetKernelArgSVMPointer`:
+            #[cfg(feature = "opencl_version_2_0")]
+            ArgValKeeper::Svm(_) | ArgValKeeper::SvmPtr(_) => ArgVal::mem_null(),
         }
     }
 }
@@ -526,6 +818,14 @@ pub struct Kernel {
     gws: SpatialDims,
     lws: SpatialDims,
     arg_types: Option<Vec<ArgType>>,
+    reload: Option<Arc<KernelReload>>,
+    quirks: Quirks,
+    #[cfg(feature = "opencl_version_2_0")]
+    svm_args: RefCell<BTreeMap<u32, Arc<SvmAlloc>>>,
+    // Allocations linked to from `svm_args`, passed to the kernel as
+    // `KernelExecInfo::SvmPtrs`.
+    #[cfg(feature = "opencl_version_2_0")]
+    svm_linked: RefCell<Vec<Arc<SvmAlloc>>>,
 }
 
 impl Kernel {
@@ -574,13 +874,22 @@ impl Kernel {
     /// This also bypasses the check to determine if the type of the value you
     /// pass here matches the type defined in your kernel.
     pub unsafe fn set_arg_unchecked(&self, arg_idx: u32, arg_val: ArgVal) -> OclResult<()> {
-        core::set_kernel_arg(&self.obj_core, arg_idx, arg_val).map_err(OclError::from)
+        self.set_raw_arg(arg_idx, arg_val)
     }
 
     /// Sets an argument by index.
     fn _set_arg<T: OclPrm>(&self, arg_idx: u32, arg_val: ArgVal) -> OclResult<()> {
         self.verify_arg_type::<T>(arg_idx)?;
-        core::set_kernel_arg(&self.obj_core, arg_idx, arg_val).map_err(OclError::from)
+        self.set_raw_arg(arg_idx, arg_val)
+    }
+
+    /// Sets an argument on the kernel object, retaining a copy if this kernel
+    /// is reloadable.
+    fn set_raw_arg(&self, arg_idx: u32, arg_val: ArgVal) -> OclResult<()> {
+        match self.reload {
+            Some(ref reload) => reload.set_arg(&self.obj_core, arg_idx, arg_val),
+            None => core::set_kernel_arg(&self.obj_core, arg_idx, arg_val).map_err(OclError::from),
+        }
     }
 
     /// Sets a `Buffer`, `Image`, scalar, or vector argument by index or by
@@ -689,6 +998,62 @@ impl Kernel {
         self._set_arg::<T>(arg_idx, ArgVal::vector(vector.borrow()))
     }
 
+    /// Sets a shared virtual memory buffer (`SvmBuffer` or `FineSvmBuffer`)
+    /// argument by index or by name.
+    ///
+    /// The kernel keeps the buffer's allocation alive until the argument is
+    /// set again with this method or the kernel is dropped, along with any
+    /// buffers linked to from it (see `SvmBuffer::link`), which are passed
+    /// to the kernel as `KernelExecInfo::SvmPtrs`. Not supported on kernels
+    /// created from a reloadable program.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn set_arg_svm<T, Ai, B>(&self, idx: Ai, buffer: &B) -> OclResult<()>
+            where T: OclPrm, Ai: Into<ArgIdxSpecifier>, B: AsRef<SvmBuffer<T>> {
+        let buffer = buffer.as_ref();
+        if self.reload.is_some() {
+            return Err(KernelError::SvmArgReloadable.into());
+        }
+        let arg_idx = idx.into().to_idx(&self.named_args)?;
+        self.verify_arg_type::<T>(arg_idx)?;
+        unsafe {
+            core::set_kernel_arg_svm_pointer(&self.obj_core, arg_idx,
+                buffer.as_ptr() as *const c_void, None)?;
+        }
+        self.mem_args.remove(&arg_idx);
+        self.svm_args.borrow_mut().insert(arg_idx, buffer.svm_alloc());
+        let linked = set_svm_linked(&self.obj_core, &self.svm_args.borrow())?;
+        *self.svm_linked.borrow_mut() = linked;
+        Ok(())
+    }
+
+    /// Sets a shared virtual memory pointer argument by index or by name.
+    ///
+    /// Used for memory not owned by an `SvmBuffer`, such as collections
+    /// allocated with `SvmAllocator`. Not supported on kernels created from a
+    /// reloadable program.
+    ///
+    /// ### Safety
+    ///
+    /// `ptr` must point into a shared virtual memory allocation in the
+    /// kernel's context which remains valid for as long as the kernel may be
+    /// enqueued with this argument.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub unsafe fn set_arg_svm_ptr<T, Ai>(&self, idx: Ai, ptr: *const T) -> OclResult<()>
+            where T: OclPrm, Ai: Into<ArgIdxSpecifier> {
+        if self.reload.is_some() {
+            return Err(KernelError::SvmArgReloadable.into());
+        }
+        let arg_idx = idx.into().to_idx(&self.named_args)?;
+        self.verify_arg_type::<T>(arg_idx)?;
+        core::set_kernel_arg_svm_pointer(&self.obj_core, arg_idx, ptr as *const c_void, None)?;
+        self.mem_args.remove(&arg_idx);
+        if self.svm_args.borrow_mut().remove(&arg_idx).is_some() {
+            let linked = set_svm_linked(&self.obj_core, &self.svm_args.borrow())?;
+            *self.svm_linked.borrow_mut() = linked;
+        }
+        Ok(())
+    }
+
     /// Sets the value of a named sampler argument.
     pub fn set_arg_sampler_named<'a, Ai>(&'a self, idx: Ai,
             sampler_opt: Option<&Sampler>) -> OclResult<()>
@@ -706,12 +1071,17 @@ impl Kernel {
     pub fn cmd(&self) -> KernelCmd {
         KernelCmd {
             kernel: &self.obj_core,
-            queue: self.queue.as_ref().map(|q| q.as_ref()),
+            reload: self.reload.as_deref(),
+            queue: self.queue.as_ref(),
             gwo: self.gwo,
             gws: self.gws,
             lws: self.lws,
             wait_events: None,
-            new_event: None
+            new_event: None,
+            label: None,
+            quirks: self.quirks,
+            check_work_sizes: false,
+            pad_gws: false,
         }
     }
 
@@ -729,6 +1099,46 @@ impl Kernel {
         self.cmd().enq()
     }
 
+    /// Returns `true` if the program this kernel was created from has been
+    /// rebuilt since this kernel was last refreshed.
+    ///
+    /// A stale kernel is enqueued using its re-created kernel object but
+    /// its info (including argument types used for type checking) still
+    /// reflects the original.
+    pub fn is_stale(&self) -> bool {
+        self.reload.as_ref().map(|r| r.replacement().is_some()).unwrap_or(false)
+    }
+
+    /// Adopts the kernel object re-created by the most recent rebuild of the
+    /// program this kernel was created from (see `Program::rebuild`).
+    ///
+    /// Returns `false` if this kernel was not stale.
+    pub fn refresh(&mut self) -> OclResult<bool> {
+        let reload = match self.reload {
+            Some(ref reload) => reload,
+            None => return Ok(false),
+        };
+
+        let obj_core = match reload.replacement() {
+            Some(obj_core) => obj_core,
+            None => return Ok(false),
+        };
+
+        if self.arg_types.is_some() {
+            let num_args = match core::get_kernel_info(&obj_core, KernelInfo::NumArgs)? {
+                KernelInfoResult::NumArgs(num) => num,
+                _ => unreachable!(),
+            };
+            self.arg_types = Some((0..num_args)
+                .map(|arg_idx| ArgType::from_kern_and_idx(&obj_core, arg_idx))
+                .collect::<OclResult<Vec<_>>>()?);
+        }
+
+        reload.take_replacement();
+        self.obj_core = obj_core;
+        Ok(true)
+    }
+
     /// Changes the default queue.
     ///
     /// Returns a ref for chaining i.e.:
@@ -786,11 +1196,32 @@ impl Kernel {
         self
     }
 
+    /// Sets the default work sizes and offset specified by `config`.
+    pub fn set_default_launch_config(&mut self, config: &LaunchConfig) -> &mut Kernel {
+        config.apply(&mut self.gwo, &mut self.gws, &mut self.lws);
+        self
+    }
+
+    /// Returns the default work sizes and offset.
+    pub fn default_launch_config(&self) -> LaunchConfig {
+        LaunchConfig {
+            global_work_offset: Some(self.gwo),
+            global_work_size: Some(self.gws),
+            local_work_size: Some(self.lws),
+        }
+    }
+
     /// Returns the default queue for this kernel if one has been set.
     pub fn default_queue(&self) -> Option<&Queue> {
         self.queue.as_ref()
     }
 
+    /// Returns the driver workarounds applied when enqueuing this kernel
+    /// (see `KernelBuilder::quirks`).
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
     /// Returns the default global work offset.
     pub fn default_global_work_offset(&self) -> SpatialDims {
         self.gwo
@@ -824,6 +1255,17 @@ impl Kernel {
         core::get_kernel_work_group_info(&self.obj_core, device, info_kind).map_err(OclError::from)
     }
 
+    /// Passes additional information other than argument values to this
+    /// kernel, such as SVM pointers used indirectly by it.
+    ///
+    /// See `core::set_kernel_exec_info` for details.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub unsafe fn set_exec_info<T: Copy>(&self, param_name: KernelExecInfo, param_value: &[T])
+            -> OclResult<()> {
+        core::set_kernel_exec_info(&self.obj_core, param_name, param_value, None)
+            .map_err(OclError::from)
+    }
+
     /// Returns argument information for this kernel.
     pub fn arg_info(&self, arg_idx: u32, info_kind: KernelArgInfo)
             -> OclResult<KernelArgInfoResult> {
@@ -845,6 +1287,39 @@ impl Kernel {
         }
     }
 
+    /// Returns the reference count of this kernel.
+    pub fn reference_count(&self) -> OclResult<u32> {
+        match self.info(KernelInfo::ReferenceCount)? {
+            KernelInfoResult::ReferenceCount(count) => Ok(count),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the context this kernel was created within.
+    pub fn context(&self) -> OclResult<Context> {
+        match self.info(KernelInfo::Context)? {
+            KernelInfoResult::Context(context) => Ok(Context::from(context)),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the program this kernel was created from.
+    pub fn program(&self) -> OclResult<Program> {
+        match self.info(KernelInfo::Program)? {
+            KernelInfoResult::Program(program) => Ok(Program::from(program)),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns any attributes specified with the `__attribute__` qualifier
+    /// in this kernel's declaration.
+    pub fn attributes(&self) -> OclResult<String> {
+        match self.info(KernelInfo::Attributes)? {
+            KernelInfoResult::Attributes(attributes) => Ok(attributes),
+            _ => unreachable!(),
+        }
+    }
+
     fn fmt_info(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.debug_struct("Kernel")
             .field("FunctionName", &self.info(KernelInfo::FunctionName))
@@ -966,6 +1441,7 @@ pub struct KernelBuilder<'b> {
     gwo: SpatialDims,
     gws: SpatialDims,
     lws: SpatialDims,
+    quirks: Option<Quirks>,
     disable_arg_check: bool,
 }
 
@@ -982,6 +1458,7 @@ impl<'b> KernelBuilder<'b> {
             gwo: SpatialDims::Unspecified,
             gws: SpatialDims::Unspecified,
             lws: SpatialDims::Unspecified,
+            quirks: None,
             disable_arg_check: false,
         }
     }
@@ -1068,6 +1545,21 @@ impl<'b> KernelBuilder<'b> {
         self
     }
 
+    /// Sets the default work sizes and offset specified by `config`.
+    pub fn launch_config<'s>(&'s mut self, config: &LaunchConfig) -> &'s mut KernelBuilder<'b> {
+        config.apply(&mut self.gwo, &mut self.gws, &mut self.lws);
+        self
+    }
+
+    /// Specifies the driver workarounds applied when enqueuing the kernel.
+    ///
+    /// Defaults to the combined quirks (see `Quirks::union`) of every device
+    /// associated with the program, looked up when the kernel is built.
+    pub fn quirks<'s>(&'s mut self, quirks: Quirks) -> &'s mut KernelBuilder<'b> {
+        self.quirks = Some(quirks);
+        self
+    }
+
     /// Adds a new argument to the kernel and returns the index.
     fn new_arg(&mut self, arg_val: ArgValKeeper<'b>, type_id: Option<TypeId>, mem: Option<MemCore>) -> u32 {
         let arg_idx = self.args.len() as u32;
@@ -1234,6 +1726,53 @@ impl<'b> KernelBuilder<'b> {
         self
     }
 
+    /// Adds a new shared virtual memory buffer (`SvmBuffer` or
+    /// `FineSvmBuffer`) argument to the kernel. Argument is added to the
+    /// bottom of the argument order.
+    ///
+    /// Buffers linked to from `buffer` (see `SvmBuffer::link`) are made
+    /// available to the kernel automatically. Not supported on kernels
+    /// created from a reloadable program.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn arg_svm<'s, T, B>(&'s mut self, buffer: &B) -> &'s mut KernelBuilder<'b>
+            where T: OclPrm, B: AsRef<SvmBuffer<T>> {
+        let alloc = buffer.as_ref().svm_alloc();
+        self.new_arg(ArgValKeeper::Svm(alloc), Some(TypeId::of::<T>()), None);
+        self
+    }
+
+    /// Adds a new shared virtual memory pointer argument to the kernel, for
+    /// memory not owned by an `SvmBuffer` (such as collections allocated with
+    /// `SvmAllocator`). Argument is added to the bottom of the argument
+    /// order.
+    ///
+    /// ### Safety
+    ///
+    /// `ptr` must point into a shared virtual memory allocation in the
+    /// kernel's context which remains valid for as long as the kernel may be
+    /// enqueued with this argument.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub unsafe fn arg_svm_ptr<'s, T>(&'s mut self, ptr: *const T) -> &'s mut KernelBuilder<'b>
+            where T: OclPrm {
+        self.new_arg(ArgValKeeper::SvmPtr(ptr as usize), Some(TypeId::of::<T>()), None);
+        self
+    }
+
+    /// Adds a new *named* shared virtual memory buffer (`SvmBuffer` or
+    /// `FineSvmBuffer`) argument to the kernel. Argument is added to the
+    /// bottom of the argument order.
+    ///
+    /// Named arguments can be modified later using `::set_arg_svm()`.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn arg_svm_named<'s, T, S, B>(&'s mut self, name: S, buffer: &B)
+            -> &'s mut KernelBuilder<'b>
+            where T: OclPrm, S: Into<Cow<'static, str>>, B: AsRef<SvmBuffer<T>> {
+        let alloc = buffer.as_ref().svm_alloc();
+        let arg_idx = self.new_arg(ArgValKeeper::Svm(alloc), Some(TypeId::of::<T>()), None);
+        self.named_args.insert(name.into(), arg_idx);
+        self
+    }
+
     /// Adds a new argument to the kernel specifying the sampler object represented
     /// by 'sampler'. Argument is added to the bottom of the argument
     /// order.
@@ -1448,6 +1987,8 @@ impl<'b> KernelBuilder<'b> {
         }
 
         // Check argument types then set arguments.
+        let mut arg_vals = Vec::with_capacity(self.args.len());
+
         for (arg_idx, &(ref arg, ref type_id_opt)) in self.args.iter().enumerate() {
             if !disable_arg_check {
                 if let Some(type_id) = *type_id_opt {
@@ -1464,10 +2005,49 @@ impl<'b> KernelBuilder<'b> {
 
             // Some platforms do not like having a `null` argument set for mem objects.
             if !val.is_mem_null() {
-                core::set_kernel_arg(&obj_core, arg_idx as u32, val)?;
+                arg_vals.push((arg_idx as u32, val));
             }
         }
 
+        core::set_kernel_args(&obj_core, &arg_vals)?;
+
+        #[cfg(feature = "opencl_version_2_0")]
+        let (svm_args, svm_linked) = {
+            let mut svm_args = BTreeMap::new();
+            for (arg_idx, &(ref arg, _)) in self.args.iter().enumerate() {
+                let ptr = match *arg {
+                    ArgValKeeper::Svm(ref alloc) => {
+                        svm_args.insert(arg_idx as u32, alloc.clone());
+                        alloc.as_ptr() as *const c_void
+                    },
+                    ArgValKeeper::SvmPtr(addr) => addr as *const c_void,
+                    _ => continue,
+                };
+                if program.handle().is_some() {
+                    return Err(KernelError::SvmArgReloadable.into());
+                }
+                unsafe { core::set_kernel_arg_svm_pointer(&obj_core, arg_idx as u32, ptr, None)?; }
+            }
+            // A new kernel has no pointers to replace (and may not support
+            // exec info at all):
+            let svm_linked = if svm_args.is_empty() {
+                Vec::new()
+            } else {
+                set_svm_linked(&obj_core, &svm_args)?
+            };
+            (RefCell::new(svm_args), RefCell::new(svm_linked))
+        };
+
+        let reload = program.handle().map(|handle| handle.register(name, &arg_vals));
+
+        // Enqueues may target any of the program's devices:
+        let quirks = match self.quirks {
+            Some(quirks) => quirks,
+            None => program.devices()?.iter().try_fold(Quirks::default(), |quirks, device| {
+                Quirks::for_device(device).map(|q| quirks.union(q))
+            })?,
+        };
+
         let arg_types = if all_arg_types_unknown || disable_arg_check {
             None
         } else {
@@ -1483,11 +2063,32 @@ impl<'b> KernelBuilder<'b> {
             gws: self.gws,
             lws: self.lws,
             arg_types,
+            reload,
+            quirks,
+            #[cfg(feature = "opencl_version_2_0")]
+            svm_args,
+            #[cfg(feature = "opencl_version_2_0")]
+            svm_linked,
         })
     }
 }
 
 
+/// Passes the allocations linked to from `svm_args` to a kernel as
+/// `KernelExecInfo::SvmPtrs`, returning them.
+///
+/// The list is passed even when empty, replacing any passed previously.
+#[cfg(feature = "opencl_version_2_0")]
+fn set_svm_linked(obj_core: &KernelCore, svm_args: &BTreeMap<u32, Arc<SvmAlloc>>)
+        -> OclResult<Vec<Arc<SvmAlloc>>> {
+    let linked = linked_allocs(svm_args.values());
+    let ptrs: Vec<*const c_void> = linked.iter().map(|a| a.as_ptr() as *const c_void).collect();
+    // The kernel keeps the returned allocations alive:
+    unsafe { core::set_kernel_exec_info(obj_core, KernelExecInfo::SvmPtrs, &ptrs, None)?; }
+    Ok(linked)
+}
+
+
 /// Returns argument information for a kernel.
 pub fn arg_info(core: &KernelCore, arg_idx: u32, info_kind: KernelArgInfo)
         -> OclResult<KernelArgInfoResult> {
@@ -1659,29 +2260,11 @@ pub mod arg_type {
         /// `ArgType::unknown()` (which matches any argument type) is returned
         /// if any are found.
         pub fn from_kern_and_idx(core: &KernelCore, arg_idx: u32) -> OclResult<ArgType> {
-            use crate::core::EmptyInfoResultError;
-            use crate::core::ErrorKind as OclCoreErrorKind;
-
             match arg_type_name(core, arg_idx) {
                 Ok(type_name) => ArgType::from_str(type_name.as_str()),
-                Err(err) => {
-                    // Escape hatches for known, platform-specific errors.
-                    if let OclErrorKind::OclCore(ref core_err) = *err.kind() {
-                        match *core_err.kind() {
-                            OclCoreErrorKind::Api(ref api_err) => {
-                                if api_err.status() == Status::CL_KERNEL_ARG_INFO_NOT_AVAILABLE {
-                                    return ArgType::unknown().map_err(OclError::from);
-                                }
-                            }
-                            OclCoreErrorKind::EmptyInfoResult(EmptyInfoResultError::KernelArg) => {
-                                return ArgType::unknown();
-                            },
-                            _ => (),
-                        }
-                    }
-
-                    Err(err)
-                },
+                // Escape hatch for known, platform-specific errors:
+                Err(ref err) if err.is_kernel_arg_info_unavailable() => ArgType::unknown(),
+                Err(err) => Err(err),
             }
         }
 
@@ -1790,4 +2373,85 @@ mod tests {
         unsafe { kernel_diff.enq()?; }
         Ok(())
     }
+
+    #[test]
+    fn work_size_limits() {
+        use crate::SpatialDims;
+        use super::{KernelError, WorkSizeLimits};
+
+        let limits = WorkSizeLimits::new(vec![256, 256, 64], 256);
+        assert!(limits.check(&SpatialDims::One(1000), &SpatialDims::Unspecified).is_ok());
+        assert!(limits.check(&SpatialDims::Two(512, 512), &SpatialDims::Two(16, 16)).is_ok());
+
+        match limits.check(&SpatialDims::Three(128, 128, 128), &SpatialDims::Three(1, 1, 128)) {
+            Err(KernelError::LwsExceedsMaxWorkItemSize { dim: 2, lws: 128, max: 64 }) => (),
+            other => panic!("{:?}", other),
+        }
+        match limits.check(&SpatialDims::Two(512, 512), &SpatialDims::Two(32, 32)) {
+            Err(KernelError::LwsExceedsMaxWorkGroupSize { lws: 1024, max: 256 }) => (),
+            other => panic!("{:?}", other),
+        }
+        match limits.check(&SpatialDims::One(1000), &SpatialDims::One(64)) {
+            Err(KernelError::GwsNotMultipleOfLws { dim: 0, gws: 1000, lws: 64 }) => (),
+            other => panic!("{:?}", other),
+        }
+        match limits.check(&SpatialDims::One(1024), &SpatialDims::Two(64, 1)) {
+            Err(KernelError::WorkSizeDimMismatch { gws_dims: 1, lws_dims: 2 }) => (),
+            other => panic!("{:?}", other),
+        }
+
+        let padded = SpatialDims::Two(1000, 30).padded_to(&SpatialDims::Two(64, 16));
+        assert_eq!(padded, SpatialDims::Two(1024, 32));
+        assert!(limits.check(&padded, &SpatialDims::Two(64, 4)).is_ok());
+        assert_eq!(SpatialDims::One(1000).padded_to(&SpatialDims::Unspecified),
+            SpatialDims::One(1000));
+    }
+
+    #[test]
+    fn pad_global_work_size() -> crate::Result<()> {
+        let src = r#"
+            __kernel void add(__global float* buffer, uint len, float scalar) {
+                if (get_global_id(0) >= len) return;
+                buffer[get_global_id(0)] += scalar;
+            }
+        "#;
+
+        let pro_que = ProQue::builder().src(src).dims(1000).build()?;
+        let lws = std::cmp::min(pro_que.device().max_wg_size()?, 64);
+        let buffer = pro_que.create_buffer::<f32>()?;
+        let kernel = pro_que.kernel_builder("add")
+            .arg(&buffer)
+            .arg(1000u32)
+            .arg(1.0f32)
+            .local_work_size(lws)
+            .build()?;
+
+        if 1000 % lws != 0 {
+            let err = unsafe { kernel.cmd().check_work_sizes().enq() }.unwrap_err();
+            assert!(err.to_string().contains("get_global_id(0) >= len"));
+        }
+        unsafe { kernel.cmd().pad_global_work_size().check_work_sizes().enq()?; }
+
+        let mut vec = vec![0.0f32; buffer.len()];
+        buffer.read(&mut vec).enq()?;
+        assert!(vec.iter().all(|&v| v == 1.0));
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn launch_config_serde() {
+        use crate::SpatialDims;
+        use super::LaunchConfig;
+
+        let config: LaunchConfig = serde_json::from_str(
+            r#"{ "global_work_size": [1024, 512], "local_work_size": 16 }"#).unwrap();
+        assert_eq!(config.global_work_offset, None);
+        assert_eq!(config.global_work_size, Some(SpatialDims::Two(1024, 512)));
+        assert_eq!(config.local_work_size, Some(SpatialDims::One(16)));
+
+        let text = serde_json::to_string(&config).unwrap();
+        assert_eq!(serde_json::from_str::<LaunchConfig>(&text).unwrap(), config);
+        assert!(serde_json::from_str::<SpatialDims>("[1, 2, 3, 4]").is_err());
+    }
 }
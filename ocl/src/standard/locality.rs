@@ -0,0 +1,355 @@
+//! Host locality of devices on NUMA systems.
+//!
+//! On multi-socket machines host memory and CPUs closest to a device's PCIe
+//! root complex provide noticeably higher transfer throughput. The device's
+//! PCI address is queried using whichever vendor extension is available and
+//! the NUMA node is then looked up from the operating system (currently only
+//! on Linux, via sysfs).
+//!
+//! Threads which feed a device (e.g. those filling mapped or host-side
+//! buffers) can be pinned to the device's node with
+//! `Device::pin_current_thread`. The threads this crate spawns itself are
+//! pinned to the node of the device they serve once enabled with
+//! `Device::set_pin_internal_threads`.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::core::{self, DeviceId as DeviceIdCore};
+use crate::error::{Error as OclError, Result as OclResult};
+use crate::standard::{Device, DeviceExtensions};
+
+
+/// `cl_khr_pci_bus_info`: `CL_DEVICE_PCI_BUS_INFO_KHR`.
+const CL_DEVICE_PCI_BUS_INFO_KHR: u32 = 0x410F;
+/// `cl_amd_device_attribute_query`: `CL_DEVICE_TOPOLOGY_AMD`.
+const CL_DEVICE_TOPOLOGY_AMD: u32 = 0x4037;
+/// `cl_nv_device_attribute_query`: `CL_DEVICE_PCI_BUS_ID_NV`.
+const CL_DEVICE_PCI_BUS_ID_NV: u32 = 0x4008;
+/// `cl_nv_device_attribute_query`: `CL_DEVICE_PCI_SLOT_ID_NV`.
+const CL_DEVICE_PCI_SLOT_ID_NV: u32 = 0x4009;
+/// `cl_nv_device_attribute_query`: `CL_DEVICE_PCI_DOMAIN_ID_NV`.
+const CL_DEVICE_PCI_DOMAIN_ID_NV: u32 = 0x400A;
+/// `CL_DEVICE_TOPOLOGY_TYPE_PCIE_AMD`.
+const TOPOLOGY_TYPE_PCIE_AMD: u32 = 1;
+/// `cl_khr_device_uuid`: `CL_DEVICE_UUID_KHR`.
+const CL_DEVICE_UUID_KHR: u32 = 0x106A;
+/// `cl_khr_device_uuid`: `CL_DRIVER_UUID_KHR`.
+const CL_DRIVER_UUID_KHR: u32 = 0x106B;
+/// `cl_khr_device_uuid`: `CL_UUID_SIZE_KHR`.
+const CL_UUID_SIZE_KHR: usize = 16;
+
+
+/// The PCI address of a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PciBusAddress {
+    pub domain: u32,
+    pub bus: u32,
+    pub device: u32,
+    pub function: u32,
+}
+
+impl std::fmt::Display for PciBusAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:04x}:{:02x}:{:02x}.{:x}", self.domain, self.bus, self.device, self.function)
+    }
+}
+
+impl std::str::FromStr for PciBusAddress {
+    type Err = OclError;
+
+    /// Parses a PCI address such as `0000:3b:00.1`, as used by sysfs, or
+    /// `00000000:3B:00.1`, as reported by `nvidia-smi`. The domain may be
+    /// omitted.
+    fn from_str(s: &str) -> OclResult<PciBusAddress> {
+        let invalid = || OclError::from(format!("Invalid PCI bus address: '{}'", s));
+        let hex = |part: &str| u32::from_str_radix(part, 16).map_err(|_| invalid());
+
+        let (rest, function) = match s.trim().rfind('.') {
+            Some(idx) => (&s.trim()[..idx], hex(&s.trim()[idx + 1..])?),
+            None => return Err(invalid()),
+        };
+        let parts: Vec<&str> = rest.split(':').collect();
+        let (domain, bus, device) = match parts.len() {
+            2 => (0, hex(parts[0])?, hex(parts[1])?),
+            3 => (hex(parts[0])?, hex(parts[1])?, hex(parts[2])?),
+            _ => return Err(invalid()),
+        };
+        Ok(PciBusAddress { domain, bus, device, function })
+    }
+}
+
+
+/// A universally unique identifier of a device or driver, as reported by
+/// the `cl_khr_device_uuid` extension.
+///
+/// Device UUIDs match those reported by `nvidia-smi -L` (without the `GPU-`
+/// prefix) and by other APIs such as CUDA and Vulkan.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DeviceUuid(pub [u8; CL_UUID_SIZE_KHR]);
+
+impl DeviceUuid {
+    /// Returns the raw bytes of the UUID.
+    pub fn as_bytes(&self) -> &[u8; CL_UUID_SIZE_KHR] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for DeviceUuid {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i == 4 || i == 6 || i == 8 || i == 10 { f.write_str("-")?; }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for DeviceUuid {
+    type Err = OclError;
+
+    /// Parses a UUID such as `0c9cb1a4-62ad-8f70-1b5b-8d9a1ef14b4e`. Hyphens
+    /// and a `GPU-` prefix (as used by `nvidia-smi` and
+    /// `CUDA_VISIBLE_DEVICES`) are ignored.
+    fn from_str(s: &str) -> OclResult<DeviceUuid> {
+        let invalid = || OclError::from(format!("Invalid device UUID: '{}'", s));
+        let trimmed = s.trim();
+        let digits: Vec<u8> = trimmed.trim_start_matches("GPU-").bytes()
+            .filter(|&b| b != b'-').collect();
+        if digits.len() != CL_UUID_SIZE_KHR * 2 { return Err(invalid()); }
+
+        let mut bytes = [0; CL_UUID_SIZE_KHR];
+        for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+        }
+        Ok(DeviceUuid(bytes))
+    }
+}
+
+
+fn extensions(device: &DeviceIdCore) -> OclResult<DeviceExtensions> {
+    Device::from(*device).extensions()
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes.get(offset..offset + 4).map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn info_u32(device: &DeviceIdCore, info_kind: u32) -> OclResult<Option<u32>> {
+    core::get_device_info_raw(device, info_kind).map(|b| read_u32(&b, 0)).map_err(OclError::from)
+}
+
+/// Returns the PCI address of `device`, if it is attached by PCI and a
+/// supported extension is available to query it.
+pub(crate) fn pci_bus_address(device: &DeviceIdCore) -> OclResult<Option<PciBusAddress>> {
+    let extensions = extensions(device)?;
+
+    if extensions.contains("cl_khr_pci_bus_info") {
+        let bytes = core::get_device_info_raw(device, CL_DEVICE_PCI_BUS_INFO_KHR)?;
+        return Ok(match (read_u32(&bytes, 0), read_u32(&bytes, 4), read_u32(&bytes, 8),
+                read_u32(&bytes, 12)) {
+            (Some(domain), Some(bus), Some(device), Some(function)) => {
+                Some(PciBusAddress { domain, bus, device, function })
+            },
+            _ => None,
+        });
+    }
+
+    if extensions.contains("cl_amd_device_attribute_query") {
+        // `cl_device_topology_amd::pcie`: a type tag followed by 17 unused
+        // bytes then one byte each for the bus, device, and function.
+        let bytes = core::get_device_info_raw(device, CL_DEVICE_TOPOLOGY_AMD)?;
+        if read_u32(&bytes, 0) != Some(TOPOLOGY_TYPE_PCIE_AMD) || bytes.len() < 24 {
+            return Ok(None);
+        }
+        return Ok(Some(PciBusAddress {
+            domain: 0,
+            bus: bytes[21] as u32,
+            device: bytes[22] as u32,
+            function: bytes[23] as u32,
+        }));
+    }
+
+    if extensions.contains("cl_nv_device_attribute_query") {
+        let bus = info_u32(device, CL_DEVICE_PCI_BUS_ID_NV)?;
+        let slot = info_u32(device, CL_DEVICE_PCI_SLOT_ID_NV)?;
+        // Only reported by newer drivers:
+        let domain = info_u32(device, CL_DEVICE_PCI_DOMAIN_ID_NV).ok().and_then(|d| d);
+        return Ok(match (bus, slot) {
+            (Some(bus), Some(slot)) => Some(PciBusAddress {
+                domain: domain.unwrap_or(0),
+                bus,
+                device: slot >> 3,
+                function: slot & 0x7,
+            }),
+            _ => None,
+        });
+    }
+
+    Ok(None)
+}
+
+
+/// Returns the UUID queried with `info_kind` (`CL_DEVICE_UUID_KHR` or
+/// `CL_DRIVER_UUID_KHR`), if `cl_khr_device_uuid` is supported.
+fn uuid(device: &DeviceIdCore, info_kind: u32) -> OclResult<Option<DeviceUuid>> {
+    if !extensions(device)?.contains("cl_khr_device_uuid") { return Ok(None); }
+    let bytes = core::get_device_info_raw(device, info_kind)?;
+    if bytes.len() < CL_UUID_SIZE_KHR { return Ok(None); }
+    let mut uuid = [0; CL_UUID_SIZE_KHR];
+    uuid.copy_from_slice(&bytes[..CL_UUID_SIZE_KHR]);
+    Ok(Some(DeviceUuid(uuid)))
+}
+
+/// Returns the UUID of `device`, if `cl_khr_device_uuid` is supported.
+pub(crate) fn device_uuid(device: &DeviceIdCore) -> OclResult<Option<DeviceUuid>> {
+    uuid(device, CL_DEVICE_UUID_KHR)
+}
+
+/// Returns the UUID of the driver for `device`, if `cl_khr_device_uuid` is
+/// supported.
+pub(crate) fn driver_uuid(device: &DeviceIdCore) -> OclResult<Option<DeviceUuid>> {
+    uuid(device, CL_DRIVER_UUID_KHR)
+}
+
+
+/// Returns the NUMA node the PCI device at `address` is attached to, if
+/// known.
+#[cfg(target_os = "linux")]
+pub(crate) fn numa_node(address: &PciBusAddress) -> Option<u32> {
+    let path = format!("/sys/bus/pci/devices/{}/numa_node", address);
+    // A node of `-1` indicates that the system is not NUMA.
+    std::fs::read_to_string(path).ok().and_then(|s| s.trim().parse::<i32>().ok())
+        .and_then(|n| if n < 0 { None } else { Some(n as u32) })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn numa_node(_address: &PciBusAddress) -> Option<u32> {
+    None
+}
+
+
+static PIN_INTERNAL_THREADS: AtomicBool = AtomicBool::new(false);
+
+// The NUMA node of each device looked up so far.
+static DEVICE_NODES: Mutex<Option<HashMap<Device, Option<u32>>>> = Mutex::new(None);
+
+/// Enables or disables pinning of internal threads (see
+/// `Device::set_pin_internal_threads`).
+pub(crate) fn set_pin_internal_threads(enabled: bool) {
+    PIN_INTERNAL_THREADS.store(enabled, Ordering::SeqCst);
+}
+
+/// Returns the NUMA node an internal thread serving `device` should be
+/// pinned to, or `None` if pinning is disabled or the node is unknown.
+///
+/// Nodes are looked up once per device.
+pub(crate) fn internal_thread_node(device: &Device) -> Option<u32> {
+    if !PIN_INTERNAL_THREADS.load(Ordering::SeqCst) { return None; }
+
+    let mut nodes = DEVICE_NODES.lock().unwrap_or_else(|e| e.into_inner());
+    *nodes.get_or_insert_with(HashMap::new).entry(*device)
+        .or_insert_with(|| device.numa_node().ok().and_then(|n| n))
+}
+
+
+/// Parses a sysfs cpu list such as `0-7,16-23`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for range in list.trim().split(',').filter(|r| !r.is_empty()) {
+        let mut bounds = range.splitn(2, '-');
+        let start: usize = bounds.next()?.parse().ok()?;
+        let end: usize = match bounds.next() {
+            Some(end) => end.parse().ok()?,
+            None => start,
+        };
+        cpus.extend(start..=end);
+    }
+    Some(cpus)
+}
+
+
+#[cfg(target_os = "linux")]
+mod affinity {
+    use std::mem;
+
+    /// Matches glibc's `cpu_set_t` (1024 cpus).
+    pub const CPU_SET_WORDS: usize = 1024 / (8 * mem::size_of::<u64>());
+
+    extern "C" {
+        pub fn sched_setaffinity(pid: i32, cpusetsize: usize, mask: *const u64) -> i32;
+    }
+}
+
+/// Restricts the calling thread to the CPUs of NUMA node `node`.
+///
+/// Returns `false` if the node's CPUs could not be determined.
+#[cfg(target_os = "linux")]
+pub(crate) fn pin_current_thread(node: u32) -> OclResult<bool> {
+    use std::mem;
+
+    let path = format!("/sys/devices/system/node/node{}/cpulist", node);
+    let cpus = match std::fs::read_to_string(path).ok().and_then(|l| parse_cpu_list(&l)) {
+        Some(cpus) if !cpus.is_empty() => cpus,
+        _ => return Ok(false),
+    };
+
+    let mut mask = [0u64; affinity::CPU_SET_WORDS];
+    let word_bits = 8 * mem::size_of::<u64>();
+    for &cpu in cpus.iter().filter(|&&cpu| cpu < affinity::CPU_SET_WORDS * word_bits) {
+        mask[cpu / word_bits] |= 1 << (cpu % word_bits);
+    }
+
+    // A pid of zero refers to the calling thread.
+    let result = unsafe { affinity::sched_setaffinity(0, mem::size_of_val(&mask), mask.as_ptr()) };
+    if result != 0 {
+        return Err(format!("Unable to pin the current thread to NUMA node {}: {}", node,
+            std::io::Error::last_os_error()).into());
+    }
+    Ok(true)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn pin_current_thread(_node: u32) -> OclResult<bool> {
+    Ok(false)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_cpu_list, PciBusAddress, DeviceUuid};
+
+    #[test]
+    fn cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), Some(vec![0, 1, 2, 3, 8, 10, 11]));
+        assert_eq!(parse_cpu_list(""), Some(vec![]));
+        assert_eq!(parse_cpu_list("a-b"), None);
+    }
+
+    #[test]
+    fn pci_bus_address_display() {
+        let address = PciBusAddress { domain: 0, bus: 0x3b, device: 0, function: 1 };
+        assert_eq!(address.to_string(), "0000:3b:00.1");
+    }
+
+    #[test]
+    fn pci_bus_address_parse() {
+        let address = PciBusAddress { domain: 0, bus: 0x3b, device: 0, function: 1 };
+        assert_eq!("0000:3b:00.1".parse::<PciBusAddress>().unwrap(), address);
+        assert_eq!("00000000:3B:00.1".parse::<PciBusAddress>().unwrap(), address);
+        assert_eq!("3b:00.1".parse::<PciBusAddress>().unwrap(), address);
+        assert!("3b:00".parse::<PciBusAddress>().is_err());
+    }
+
+    #[test]
+    fn device_uuid_round_trip() {
+        let s = "0c9cb1a4-62ad-8f70-1b5b-8d9a1ef14b4e";
+        let uuid: DeviceUuid = s.parse().unwrap();
+        assert_eq!(uuid.as_bytes()[0], 0x0c);
+        assert_eq!(uuid.to_string(), s);
+        assert_eq!(format!("GPU-{}", s).parse::<DeviceUuid>().unwrap(), uuid);
+        assert!("0c9cb1a4".parse::<DeviceUuid>().is_err());
+    }
+}
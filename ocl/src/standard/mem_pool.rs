@@ -0,0 +1,339 @@
+//! A pool of reusable device memory.
+//!
+//! Scratch buffers and images rented from a `MemPool` are returned to the
+//! pool automatically when dropped. If commands using a temporary are still
+//! in flight, register their events with `::register_event` and the memory
+//! will not become available for re-rental until the last of those events
+//! has completed.
+
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+use crate::core::{OclPrm, MemFlags, MemObjectType, ImageFormat, ImageChannelOrder,
+    ImageChannelDataType};
+use crate::error::{Error as OclError, Result as OclResult};
+use crate::standard::{Queue, Buffer, Image, Event, EventList, SpatialDims};
+
+
+/// Identifies interchangeable buffers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct BufferKey {
+    len: usize,
+    flags: MemFlags,
+}
+
+/// Identifies interchangeable images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct ImageKey {
+    dims: [usize; 3],
+    image_type: MemObjectType,
+    channel_order: ImageChannelOrder,
+    channel_data_type: ImageChannelDataType,
+    flags: MemFlags,
+}
+
+
+/// Memory which has been dropped but may still be in use by the device.
+#[derive(Debug)]
+enum Returned<T: OclPrm> {
+    Buffer(BufferKey, Buffer<T>),
+    Image(ImageKey, Image<T>),
+}
+
+
+#[derive(Debug)]
+struct Inner<T: OclPrm> {
+    buffers: HashMap<BufferKey, Vec<Buffer<T>>>,
+    images: HashMap<ImageKey, Vec<Image<T>>>,
+    in_flight: Vec<(Returned<T>, EventList)>,
+}
+
+impl<T: OclPrm> Inner<T> {
+    /// Moves any in-flight memory whose events have all completed back into
+    /// the free lists.
+    fn reclaim(&mut self) -> OclResult<()> {
+        let mut idx = 0;
+        while idx < self.in_flight.len() {
+            let mut complete = true;
+            for ev in self.in_flight[idx].1.iter() {
+                if !ev.is_complete()? {
+                    complete = false;
+                    break;
+                }
+            }
+
+            if complete {
+                match self.in_flight.swap_remove(idx).0 {
+                    Returned::Buffer(key, buf) => self.buffers.entry(key).or_default().push(buf),
+                    Returned::Image(key, img) => self.images.entry(key).or_default().push(img),
+                }
+            } else {
+                idx += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn give_back(&mut self, mem: Returned<T>, events: EventList) {
+        if events.is_empty() {
+            match mem {
+                Returned::Buffer(key, buf) => self.buffers.entry(key).or_default().push(buf),
+                Returned::Image(key, img) => self.images.entry(key).or_default().push(img),
+            }
+        } else {
+            self.in_flight.push((mem, events));
+        }
+    }
+}
+
+
+/// A pool of reusable, queue-associated buffers and images.
+///
+/// Cloning a `MemPool` returns another handle to the same pool.
+///
+/// ### Example
+///
+/// ```rust,ignore
+/// let pool = MemPool::<f32>::new(queue.clone());
+/// {
+///     let mut scratch = pool.rent_buffer(1 << 20, MemFlags::new().read_write())?;
+///     kernel.set_arg("scratch", &*scratch)?;
+///     let mut event = Event::empty();
+///     unsafe { kernel.cmd().enew(&mut event).enq()?; }
+///     scratch.register_event(event);
+/// } // `scratch` returns to the pool once the kernel completes.
+/// ```
+#[derive(Debug)]
+pub struct MemPool<T: OclPrm> {
+    queue: Queue,
+    inner: Arc<Mutex<Inner<T>>>,
+}
+
+impl<T: OclPrm> MemPool<T> {
+    /// Returns a new, empty pool. Memory is allocated in the context of
+    /// `queue`, which is also set as the default queue of rented memory.
+    pub fn new(queue: Queue) -> MemPool<T> {
+        MemPool {
+            queue,
+            inner: Arc::new(Mutex::new(Inner {
+                buffers: HashMap::new(),
+                images: HashMap::new(),
+                in_flight: Vec::new(),
+            })),
+        }
+    }
+
+    /// Rents a buffer of length `len` created with `flags`, reusing a
+    /// previously returned buffer if one is available.
+    pub fn rent_buffer(&self, len: usize, flags: MemFlags) -> OclResult<TemporaryBuffer<T>> {
+        let key = BufferKey { len, flags };
+        let pooled = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.reclaim()?;
+            inner.buffers.get_mut(&key).and_then(|list| list.pop())
+        };
+
+        let buffer = match pooled {
+            Some(buf) => buf,
+            None => Buffer::builder()
+                .queue(self.queue.clone())
+                .flags(flags)
+                .len(len)
+                .build()?,
+        };
+
+        Ok(TemporaryBuffer {
+            buffer: Some(buffer),
+            key,
+            events: EventList::new(),
+            pool: self.inner.clone(),
+        })
+    }
+
+    /// Rents an image with the specified dimensions, format and flags,
+    /// reusing a previously returned image if one is available.
+    ///
+    /// The image type (1D/2D/3D) is determined by the dimension count of
+    /// `dims`.
+    pub fn rent_image<D>(&self, dims: D, image_format: ImageFormat, flags: MemFlags)
+            -> OclResult<TemporaryImage<T>>
+            where D: Into<SpatialDims> {
+        let dims = dims.into();
+        let image_type = match dims.dim_count() {
+            1 => MemObjectType::Image1d,
+            2 => MemObjectType::Image2d,
+            _ => MemObjectType::Image3d,
+        };
+        let key = ImageKey {
+            dims: dims.to_lens().map_err(|err| OclError::from(err.to_string()))?,
+            image_type,
+            channel_order: image_format.channel_order,
+            channel_data_type: image_format.channel_data_type,
+            flags,
+        };
+
+        let pooled = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.reclaim()?;
+            inner.images.get_mut(&key).and_then(|list| list.pop())
+        };
+
+        let image = match pooled {
+            Some(img) => img,
+            None => Image::builder()
+                .queue(self.queue.clone())
+                .image_type(image_type)
+                .image_format(image_format)
+                .flags(flags)
+                .dims(dims)
+                .build()?,
+        };
+
+        Ok(TemporaryImage {
+            image: Some(image),
+            key,
+            events: EventList::new(),
+            pool: self.inner.clone(),
+        })
+    }
+
+    /// Makes any returned memory whose registered events have completed
+    /// available for rental.
+    pub fn reclaim(&self) -> OclResult<()> {
+        self.inner.lock().unwrap().reclaim()
+    }
+
+    /// Returns the number of buffers and images currently available for
+    /// rental (not counting those awaiting event completion).
+    pub fn available(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.buffers.values().map(|l| l.len()).sum::<usize>() +
+            inner.images.values().map(|l| l.len()).sum::<usize>()
+    }
+
+    /// Returns the number of returned buffers and images which are waiting
+    /// for their registered events to complete.
+    pub fn in_flight(&self) -> usize {
+        self.inner.lock().unwrap().in_flight.len()
+    }
+
+    /// Drops all idle memory held by the pool.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.buffers.clear();
+        inner.images.clear();
+    }
+
+    /// Returns the queue used when creating new memory.
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+}
+
+impl<T: OclPrm> Clone for MemPool<T> {
+    fn clone(&self) -> MemPool<T> {
+        MemPool { queue: self.queue.clone(), inner: self.inner.clone() }
+    }
+}
+
+
+/// A buffer rented from a `MemPool` which returns itself to the pool when
+/// dropped.
+#[derive(Debug)]
+pub struct TemporaryBuffer<T: OclPrm> {
+    buffer: Option<Buffer<T>>,
+    key: BufferKey,
+    events: EventList,
+    pool: Arc<Mutex<Inner<T>>>,
+}
+
+impl<T: OclPrm> TemporaryBuffer<T> {
+    /// Registers an event which must complete before this buffer may be
+    /// rented again after being dropped.
+    pub fn register_event(&mut self, event: Event) {
+        if !event.is_empty() {
+            self.events.push(event);
+        }
+    }
+
+    /// Removes the buffer from pool management, returning it.
+    pub fn into_inner(mut self) -> Buffer<T> {
+        self.buffer.take().expect("TemporaryBuffer::into_inner: No buffer found.")
+    }
+}
+
+impl<T: OclPrm> Deref for TemporaryBuffer<T> {
+    type Target = Buffer<T>;
+
+    fn deref(&self) -> &Buffer<T> {
+        self.buffer.as_ref().expect("TemporaryBuffer::deref: No buffer found.")
+    }
+}
+
+impl<T: OclPrm> DerefMut for TemporaryBuffer<T> {
+    fn deref_mut(&mut self) -> &mut Buffer<T> {
+        self.buffer.as_mut().expect("TemporaryBuffer::deref_mut: No buffer found.")
+    }
+}
+
+impl<T: OclPrm> Drop for TemporaryBuffer<T> {
+    fn drop(&mut self) {
+        if let Some(buf) = self.buffer.take() {
+            let events = std::mem::replace(&mut self.events, EventList::new());
+            if let Ok(mut inner) = self.pool.lock() {
+                inner.give_back(Returned::Buffer(self.key, buf), events);
+            }
+        }
+    }
+}
+
+
+/// An image rented from a `MemPool` which returns itself to the pool when
+/// dropped.
+#[derive(Debug)]
+pub struct TemporaryImage<T: OclPrm> {
+    image: Option<Image<T>>,
+    key: ImageKey,
+    events: EventList,
+    pool: Arc<Mutex<Inner<T>>>,
+}
+
+impl<T: OclPrm> TemporaryImage<T> {
+    /// Registers an event which must complete before this image may be
+    /// rented again after being dropped.
+    pub fn register_event(&mut self, event: Event) {
+        if !event.is_empty() {
+            self.events.push(event);
+        }
+    }
+
+    /// Removes the image from pool management, returning it.
+    pub fn into_inner(mut self) -> Image<T> {
+        self.image.take().expect("TemporaryImage::into_inner: No image found.")
+    }
+}
+
+impl<T: OclPrm> Deref for TemporaryImage<T> {
+    type Target = Image<T>;
+
+    fn deref(&self) -> &Image<T> {
+        self.image.as_ref().expect("TemporaryImage::deref: No image found.")
+    }
+}
+
+impl<T: OclPrm> DerefMut for TemporaryImage<T> {
+    fn deref_mut(&mut self) -> &mut Image<T> {
+        self.image.as_mut().expect("TemporaryImage::deref_mut: No image found.")
+    }
+}
+
+impl<T: OclPrm> Drop for TemporaryImage<T> {
+    fn drop(&mut self) {
+        if let Some(img) = self.image.take() {
+            let events = std::mem::replace(&mut self.events, EventList::new());
+            if let Ok(mut inner) = self.pool.lock() {
+                inner.give_back(Returned::Image(self.key, img), events);
+            }
+        }
+    }
+}
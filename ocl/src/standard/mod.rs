@@ -14,20 +14,65 @@ mod sampler;
 mod pro_que;
 mod event;
 mod spatial_dims;
-
-pub use self::platform::{PlatformError, Extensions, Platform};
-pub use self::device::{DeviceError, Device, DeviceSpecifier};
-pub use self::context::{Context, ContextBuilder};
-pub use self::program::{Program, ProgramBuilder, BuildOpt};
-pub use self::queue::Queue;
-pub use self::kernel::{KernelError, KernelCmd, Kernel, KernelBuilder};
+mod scheduler;
+mod mem_pool;
+pub(crate) mod quirks;
+pub(crate) mod transfer;
+mod reload;
+mod allocations;
+mod locality;
+mod queue_pool;
+mod host_mirror;
+mod device_cache;
+mod flush;
+mod tracking;
+mod profiling;
+mod graph;
+#[cfg(feature = "opencl_version_2_0")]
+mod svm;
+#[cfg(feature = "opencl_version_2_0")]
+mod svm_collections;
+pub(crate) mod completion;
+
+pub use self::platform::{PlatformError, IcdSearch, Extensions, Platform};
+pub use self::device::{DeviceError, Device, DeviceSpecifier, DeviceCriteria, DeviceScore, SubDevice,
+    NumaNode, DeviceExtensions, CapabilityReport};
+pub use self::context::{Context, ContextBuilder, ContextErrorFn};
+pub use self::program::{Program, ProgramBuilder, BuildOpt, SourceFragment, FutureProgram,
+    FutureBuild};
+#[cfg(feature = "opencl_version_2_2")]
+pub use self::program::SpecConstantPrm;
+pub use self::queue::{Queue, QueueBuilder, FutureFinish};
+pub use self::kernel::{KernelError, KernelCmd, Kernel, KernelBuilder, LaunchConfig, WorkSizeLimits,
+    FutureKernel};
 pub use self::buffer::{BufferCmdKind, BufferCmdDataShape, BufferCmd, Buffer, QueCtx,
     BufferBuilder, BufferReadCmd, BufferWriteCmd, BufferMapCmd, BufferCmdError, WriteSrc};
 pub use self::image::{ImageCmdKind, ImageCmd, Image, ImageBuilder};
 pub use self::sampler::Sampler;
-pub use self::pro_que::{ProQue, ProQueBuilder};
-pub use self::event::{Event, EventArray, EventList, IntoMarker, RawEventArray, IntoRawEventArray};
+pub use self::pro_que::{ProQue, ProQueBuilder, CachedKernel, MultiProQue};
+pub use self::event::{Event, UserEvent, EventProfiling, EventFailedError, EventArray, EventList,
+    IntoMarker, RawEventArray, IntoRawEventArray};
+pub(crate) use self::event::event_failed;
 pub use self::spatial_dims::SpatialDims;
+pub use self::scheduler::{Priority, FairScheduler};
+pub use self::mem_pool::{MemPool, TemporaryBuffer, TemporaryImage};
+pub use self::quirks::{Quirks, DriverId};
+pub use self::transfer::{TransferPolicy, TransferMetrics, TransferKind, ChunkMetrics};
+pub use self::reload::ProgramHandle;
+pub use self::allocations::{Allocation, AllocationKind, MemoryUsage, MemoryLimitError};
+pub use self::locality::{PciBusAddress, DeviceUuid};
+pub use self::queue_pool::{QueuePool, DispatchPolicy};
+pub use self::host_mirror::{HostMirror, DirtyRanges};
+pub use self::device_cache::{DeviceCache, CachedBuffer};
+pub use self::flush::FlushPolicy;
+pub use self::profiling::{Trace, DeviceTimerSync, KernelStats, ProfilingSummary};
+pub use self::graph::{CommandGraph, GraphNode, GraphCommand};
+#[cfg(feature = "opencl_version_2_0")]
+pub use self::svm::{SvmBuffer, SvmMap, SvmCmd, FineSvmBuffer, SvmAtomicPrm};
+#[cfg(feature = "opencl_version_2_0")]
+pub use self::svm_collections::{SvmBox, SvmVec, SvmAllocator};
+#[cfg(feature = "opencl_version_2_0")]
+pub(crate) use self::svm::{SvmAlloc, linked_allocs};
 #[cfg(not(feature = "async_block"))]
 pub use self::cb::{_unpark_task, box_raw_void};
 pub use self::traits::{MemLen, WorkDims};
@@ -66,9 +111,9 @@ impl<'a, T> HostSlice<'a, T> where T: 'a {
 mod cb {
     use crate::core::ffi::c_void;
     use num_traits::FromPrimitive;
-    use futures::task::Task;
+    use std::task::Waker;
     use crate::ffi::cl_event;
-    use crate::core::{CommandExecutionStatus, Status};
+    use crate::core::{CommandExecutionStatus, Status, guard_callback};
 
     pub fn box_raw_void<T>(item: T) -> *mut c_void {
         let item_box = Box::new(item);
@@ -76,27 +121,29 @@ mod cb {
     }
 
     pub extern "C" fn _unpark_task(event_ptr: cl_event, event_status: i32, user_data: *mut c_void) {
-
-        let _ = event_ptr;
-        // println!("'_unpark_task' has been called.");
-        if event_status == CommandExecutionStatus::Complete as i32 && !user_data.is_null() {
-            unsafe {
-                let task_ptr = user_data as *mut _ as *mut Task;
-                let task = Box::from_raw(task_ptr);
-                (*task).notify();
+        guard_callback("_unpark_task", || {
+            let _ = event_ptr;
+            // println!("'_unpark_task' has been called.");
+            if !user_data.is_null() {
+                // Wake the task regardless of status so that it can observe
+                // the failure when polled.
+                let waker = unsafe { Box::from_raw(user_data as *mut _ as *mut Waker) };
+                waker.wake();
             }
-        } else {
-            let status = if event_status < 0 {
-                format!("{:?}", Status::from_i32(event_status))
-            } else {
-                format!("{:?}", CommandExecutionStatus::from_i32(event_status))
-            };
 
-            panic!("ocl::standard::_unpark_task: \n\nWake up user data is null or event is not \
-                complete: {{ status: {:?}, user_data: {:?} }}. If you are getting \
-                `DEVICE_NOT_AVAILABLE` and you are using Intel drivers, switch to AMD OpenCL \
-                drivers instead (will work with Intel CPUs).\n\n", status, user_data);
-        }
+            if event_status != CommandExecutionStatus::Complete as i32 || user_data.is_null() {
+                let status = if event_status < 0 {
+                    format!("{:?}", Status::from_i32(event_status))
+                } else {
+                    format!("{:?}", CommandExecutionStatus::from_i32(event_status))
+                };
+
+                panic!("ocl::standard::_unpark_task: \n\nWake up user data is null or event is not \
+                    complete: {{ status: {:?}, user_data: {:?} }}. If you are getting \
+                    `DEVICE_NOT_AVAILABLE` and you are using Intel drivers, switch to AMD OpenCL \
+                    drivers instead (will work with Intel CPUs).\n\n", status, user_data);
+            }
+        })
     }
 }
 
@@ -110,7 +157,7 @@ mod cb {
 mod types {
     use std::ptr;
     use std::cell::Ref;
-    use crate::standard::{Event, EventList, RawEventArray, Queue};
+    use crate::standard::{Event, UserEvent, EventArray, EventList, RawEventArray, Queue};
     use crate::core::ffi::cl_event;
     use crate::core::{Event as EventCore, ClNullEventPtr, ClWaitListPtr};
     use crate::error::Result as OclResult;
@@ -238,6 +285,12 @@ mod types {
         }
     }
 
+    impl<'a> From<&'a UserEvent> for ClWaitListPtrEnum<'a> {
+        fn from(e: &'a UserEvent) -> ClWaitListPtrEnum<'a> {
+            ClWaitListPtrEnum::Event(e.as_event())
+        }
+    }
+
     impl<'a> From<&'a mut Event> for ClWaitListPtrEnum<'a> {
         fn from(e: &'a mut Event) -> ClWaitListPtrEnum<'a> {
             ClWaitListPtrEnum::Event(e)
@@ -268,6 +321,33 @@ mod types {
         }
     }
 
+    impl<'a, const N: usize> From<&'a EventArray<N>> for ClWaitListPtrEnum<'a> {
+        fn from(ea: &'a EventArray<N>) -> ClWaitListPtrEnum<'a> {
+            ClWaitListPtrEnum::EventSlice(ea.as_slice())
+        }
+    }
+
+    impl<'a> From<&'a Option<Event>> for ClWaitListPtrEnum<'a> {
+        fn from(e: &'a Option<Event>) -> ClWaitListPtrEnum<'a> {
+            match *e {
+                Some(ref e) => ClWaitListPtrEnum::Event(e),
+                None => ClWaitListPtrEnum::Null,
+            }
+        }
+    }
+
+    macro_rules! impl_wait_list_from_event_arrays {
+        ($( $len:expr ),*) => ($(
+            impl<'a> From<&'a [Event; $len]> for ClWaitListPtrEnum<'a> {
+                fn from(es: &'a [Event; $len]) -> ClWaitListPtrEnum<'a> {
+                    ClWaitListPtrEnum::EventSlice(es)
+                }
+            }
+        )*);
+    }
+
+    impl_wait_list_from_event_arrays!(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16);
+
     impl<'a> From<&'a [cl_event]> for ClWaitListPtrEnum<'a> {
         fn from(el: &'a [cl_event]) -> ClWaitListPtrEnum<'a> {
             ClWaitListPtrEnum::EventPtrSlice(el)
@@ -320,6 +400,39 @@ mod types {
         Null,
         Event(&'a mut Event),
         EventList(&'a mut EventList),
+        OptionEvent(&'a mut Option<Event>),
+        /// The backing array and length of an `EventArray`.
+        EventArray(&'a mut [Event], &'a mut usize),
+    }
+
+    impl<'a> ClNullEventPtrEnum<'a> {
+        /// Returns an error if this is an `EventArray` with no room for a new
+        /// event.
+        ///
+        /// Commands check this before enqueuing anything.
+        pub(crate) fn check_capacity(&self) -> OclResult<()> {
+            match *self {
+                ClNullEventPtrEnum::EventArray(ref es, ref len) if **len >= es.len() => {
+                    Err(format!("The EventArray passed to receive a new event is full \
+                        (capacity: {}).", es.len()).into())
+                },
+                _ => Ok(()),
+            }
+        }
+    }
+
+    /// Returns the next free slot of an `EventArray`, extending its length to
+    /// include it.
+    ///
+    /// Only reachable when full if the array is passed directly to a `core`
+    /// function, commands having already returned an error (see
+    /// `::check_capacity`).
+    fn next_array_slot<'e>(events: &'e mut [Event], len: &mut usize) -> &'e mut Event {
+        if *len >= events.len() {
+            panic!("ClNullEventPtrEnum: EventArray is full (capacity: {}).", events.len());
+        }
+        *len += 1;
+        &mut events[*len - 1]
     }
 
     unsafe impl<'a> ClNullEventPtr for ClNullEventPtrEnum<'a> {
@@ -328,6 +441,14 @@ mod types {
                 ClNullEventPtrEnum::Null => panic!("Void events cannot be used."),
                 ClNullEventPtrEnum::Event(ref mut e) => e.alloc_new(),
                 ClNullEventPtrEnum::EventList(ref mut e) => e.alloc_new(),
+                // Any event already held is replaced (and released).
+                ClNullEventPtrEnum::OptionEvent(ref mut e) => {
+                    **e = Some(Event::empty());
+                    e.as_mut().unwrap().alloc_new()
+                },
+                ClNullEventPtrEnum::EventArray(ref mut es, ref mut len) => {
+                    next_array_slot(es, len).alloc_new()
+                },
             }
         }
 
@@ -336,6 +457,14 @@ mod types {
                 ClNullEventPtrEnum::Null => panic!("Void events cannot be used."),
                 ClNullEventPtrEnum::Event(ref mut e) => e.clone_from(ev),
                 ClNullEventPtrEnum::EventList(ref mut e) => e.clone_from(ev),
+                ClNullEventPtrEnum::OptionEvent(ref mut e) => {
+                    assert!(ev.as_ref().is_valid());
+                    **e = Some(ev.as_ref().clone().into())
+                },
+                ClNullEventPtrEnum::EventArray(ref mut es, ref mut len) => {
+                    assert!(ev.as_ref().is_valid());
+                    *next_array_slot(es, len) = ev.as_ref().clone().into()
+                },
             }
         }
     }
@@ -352,6 +481,19 @@ mod types {
         }
     }
 
+    impl<'a> From<&'a mut Option<Event>> for ClNullEventPtrEnum<'a> {
+        fn from(e: &'a mut Option<Event>) -> ClNullEventPtrEnum<'a> {
+            ClNullEventPtrEnum::OptionEvent(e)
+        }
+    }
+
+    impl<'a, const N: usize> From<&'a mut EventArray<N>> for ClNullEventPtrEnum<'a> {
+        fn from(ea: &'a mut EventArray<N>) -> ClNullEventPtrEnum<'a> {
+            let (es, len) = ea.parts_mut();
+            ClNullEventPtrEnum::EventArray(es, len)
+        }
+    }
+
     impl<'a> From<()> for ClNullEventPtrEnum<'a> {
         fn from(_: ()) -> ClNullEventPtrEnum<'a> {
             ClNullEventPtrEnum::Null
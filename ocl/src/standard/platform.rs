@@ -5,17 +5,178 @@
 //! k/1.2/docs/man/xhtml/clGetPlatformInfo.html)
 
 use std;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
 use std::ops::{Deref, DerefMut};
 use std::str::SplitWhitespace;
 use crate::ffi::cl_platform_id;
-use crate::core::{self, PlatformId as PlatformIdCore, PlatformInfo, PlatformInfoResult, ClPlatformIdPtr};
+use crate::core::{self, PlatformId as PlatformIdCore, PlatformInfo, PlatformInfoResult, ClPlatformIdPtr,
+    DeviceType, OpenclVersion};
 use crate::error::{Error as OclError, Result as OclResult};
+use crate::standard::Device;
 
 
+/// Lowercase name or vendor fragments of platforms which emulate devices or
+/// only provide a generic CPU runtime.
+const EMULATED_PLATFORMS: &[&str] = &["emulation", "portable computing language"];
+
+
+/// Directory searched for `.icd` files by the ICD loaders when neither
+/// `OCL_ICD_VENDORS` nor `OPENCL_VENDOR_PATH` is set.
+#[cfg(not(windows))]
+const DEFAULT_VENDORS_DIR: &str = "/etc/OpenCL/vendors";
+
+
+/// An error returned when no platforms could be listed.
+///
+/// Each variant carries an `IcdSearch` describing where installable client
+/// drivers (ICDs) were looked for, to help diagnose minimal or
+/// misconfigured environments such as containers.
 #[derive(Debug, Fail)]
 pub enum PlatformError {
     #[fail(display = "No platforms found.")]
     NoPlatforms,
+    #[fail(display = "No OpenCL platforms found: no installable client drivers (ICDs) are \
+        registered ({}). Install an OpenCL driver or set 'OCL_ICD_VENDORS' to a directory \
+        containing '.icd' files.", _0)]
+    NoIcds(IcdSearch),
+    #[fail(display = "No OpenCL platforms found although installable client drivers (ICDs) \
+        are registered ({}). The driver libraries they name may be missing or failing to \
+        load.", _0)]
+    IcdsUnusable(IcdSearch),
+    #[fail(display = "The OpenCL ICD loader failed to list platforms: {} ({}).", error, search)]
+    Loader { error: String, search: IcdSearch },
+}
+
+impl PlatformError {
+    /// Returns a `NoIcds` or `IcdsUnusable` error, depending on whether any
+    /// ICDs are registered.
+    pub fn no_platforms() -> PlatformError {
+        let search = IcdSearch::new();
+        if search.icds().is_empty() {
+            PlatformError::NoIcds(search)
+        } else {
+            PlatformError::IcdsUnusable(search)
+        }
+    }
+
+    /// Returns the error for a failed platform listing, with `error` being
+    /// the error returned by the ICD loader.
+    fn loader<E: std::fmt::Display>(error: E) -> PlatformError {
+        match PlatformError::no_platforms() {
+            PlatformError::IcdsUnusable(search) => {
+                PlatformError::Loader { error: error.to_string(), search }
+            },
+            err => err,
+        }
+    }
+
+    /// Returns the ICD search details, if any.
+    pub fn icd_search(&self) -> Option<&IcdSearch> {
+        match *self {
+            PlatformError::NoPlatforms => None,
+            PlatformError::NoIcds(ref search) | PlatformError::IcdsUnusable(ref search) => {
+                Some(search)
+            },
+            PlatformError::Loader { ref search, .. } => Some(search),
+        }
+    }
+}
+
+
+/// The locations searched for installable client driver (ICD) files and the
+/// files found there.
+///
+/// Mirrors the search performed by the Khronos and ocl-icd loaders:
+/// `OCL_ICD_VENDORS` (a directory or a single `.icd` file) takes precedence
+/// over `OPENCL_VENDOR_PATH`, which takes precedence over
+/// `/etc/OpenCL/vendors`. Driver libraries listed directly in
+/// `OCL_ICD_FILENAMES` are included as found ICDs. On Windows, ICDs
+/// registered in the system registry are not listed.
+#[derive(Debug, Clone, Default)]
+pub struct IcdSearch {
+    locations: Vec<PathBuf>,
+    icds: Vec<PathBuf>,
+}
+
+impl IcdSearch {
+    /// Searches the locations given by the environment.
+    pub fn new() -> IcdSearch {
+        let locations = icd_search_locations(env::var_os("OCL_ICD_VENDORS"),
+            env::var_os("OPENCL_VENDOR_PATH"));
+        let mut icds = Vec::new();
+
+        for location in &locations {
+            match fs::read_dir(location) {
+                Ok(entries) => {
+                    let mut found: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+                        .map(|e| e.path())
+                        .filter(|p| p.extension().map(|ext| ext == "icd").unwrap_or(false))
+                        .collect();
+                    found.sort();
+                    icds.extend(found);
+                },
+                Err(_) => if location.is_file() { icds.push(location.clone()) },
+            }
+        }
+
+        if let Some(filenames) = env::var_os("OCL_ICD_FILENAMES") {
+            icds.extend(env::split_paths(&filenames).filter(|p| p.is_file()));
+        }
+
+        IcdSearch { locations, icds }
+    }
+
+    /// Returns the directories (or files) searched.
+    pub fn locations(&self) -> &[PathBuf] {
+        &self.locations
+    }
+
+    /// Returns the ICD files found.
+    pub fn icds(&self) -> &[PathBuf] {
+        &self.icds
+    }
+}
+
+impl std::fmt::Display for IcdSearch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let join = |paths: &[PathBuf]| paths.iter().map(|p| p.display().to_string())
+            .collect::<Vec<_>>().join(", ");
+
+        if self.locations.is_empty() {
+            write!(f, "searched: the ICD loader's default locations")?;
+        } else {
+            write!(f, "searched: {}", join(&self.locations))?;
+        }
+        if self.icds.is_empty() {
+            write!(f, "; found: none")
+        } else {
+            write!(f, "; found: {}", join(&self.icds))
+        }
+    }
+}
+
+
+/// Returns the locations searched for `.icd` files given the values of the
+/// `OCL_ICD_VENDORS` and `OPENCL_VENDOR_PATH` environment variables.
+fn icd_search_locations(ocl_icd_vendors: Option<OsString>, opencl_vendor_path: Option<OsString>)
+        -> Vec<PathBuf> {
+    match ocl_icd_vendors.or(opencl_vendor_path) {
+        Some(ref path) if !path.is_empty() => vec![PathBuf::from(path)],
+        _ => default_icd_search_locations(),
+    }
+}
+
+#[cfg(not(windows))]
+fn default_icd_search_locations() -> Vec<PathBuf> {
+    vec![PathBuf::from(DEFAULT_VENDORS_DIR)]
+}
+
+#[cfg(windows)]
+fn default_icd_search_locations() -> Vec<PathBuf> {
+    Vec::new()
 }
 
 
@@ -45,8 +206,12 @@ pub struct Platform(PlatformIdCore);
 
 impl Platform {
     /// Returns a list of all platforms avaliable on the host machine.
+    ///
+    /// If the ICD loader fails to list platforms, for example because no
+    /// OpenCL drivers are installed, the returned `PlatformError` describes
+    /// where drivers were looked for (see `IcdSearch`).
     pub fn list() -> OclResult<Vec<Platform>> {
-        let list_core = core::get_platform_ids()?;
+        let list_core = core::get_platform_ids().map_err(PlatformError::loader)?;
 
         Ok(list_core.into_iter().map(Platform::new).collect())
     }
@@ -54,15 +219,78 @@ impl Platform {
     /// Returns the first available platform.
     ///
     /// This method differs from `Platform::default()` in two ways. First, it
-    /// ignores the `OCL_DEFAULT_PLATFORM_IDX` environment variable
-    /// (`Platform::default` always respects it). Second, this function will
+    /// ignores the `OCL_DEFAULT_PLATFORM_IDX` and `OCL_DEFAULT_PLATFORM_NAME`
+    /// environment variables (`Platform::default` always respects them). Second, this function will
     /// not panic if no platforms are available but will instead return an
     /// error.
     pub fn first() -> OclResult<Platform> {
-        core::get_platform_ids()?
+        Platform::list()?
             .first()
-            .map(|&p| Platform::new(p))
-            .ok_or(PlatformError::NoPlatforms.into())
+            .cloned()
+            .ok_or_else(|| PlatformError::no_platforms().into())
+    }
+
+    /// Returns the default platform, as `Platform::default` does, but returns
+    /// an error instead of panicking if none is available.
+    pub fn try_default() -> OclResult<Platform> {
+        if Platform::list()?.is_empty() {
+            return Err(PlatformError::no_platforms().into());
+        }
+        core::default_platform().map(Platform::new).map_err(OclError::from)
+    }
+
+    /// Returns the platforms whose vendor or name contains `vendor_contains`
+    /// (ignoring case) and whose OpenCL version is at least
+    /// `version_at_least`.
+    ///
+    /// Either requirement may be `None`. Platforms are returned in the order
+    /// reported by the ICD loader.
+    pub fn list_filtered(vendor_contains: Option<&str>, version_at_least: Option<OpenclVersion>)
+            -> OclResult<Vec<Platform>> {
+        let vendor_contains = vendor_contains.map(|v| v.to_lowercase());
+        let mut platforms = Vec::new();
+        for platform in Platform::list()? {
+            if let Some(ref pattern) = vendor_contains {
+                if !platform.vendor()?.to_lowercase().contains(pattern.as_str())
+                        && !platform.name()?.to_lowercase().contains(pattern.as_str()) {
+                    continue;
+                }
+            }
+            if let Some(version) = version_at_least {
+                if platform.opencl_version()? < version { continue; }
+            }
+            platforms.push(platform);
+        }
+        Ok(platforms)
+    }
+
+    /// Returns the platform most likely to be wanted on machines with
+    /// several OpenCL implementations installed.
+    ///
+    /// Platforms with GPU devices are preferred, then those which are not
+    /// emulators or generic CPU runtimes (such as pocl or Intel's FPGA
+    /// emulation platform), then those with the highest OpenCL version.
+    /// Ties go to the platform listed first.
+    ///
+    /// Unlike `Platform::default`, the `OCL_DEFAULT_PLATFORM_IDX` and
+    /// `OCL_DEFAULT_PLATFORM_NAME` environment variables are ignored.
+    pub fn preferred() -> OclResult<Platform> {
+        let mut best: Option<((bool, bool, OpenclVersion), Platform)> = None;
+        for platform in Platform::list()? {
+            let score = platform.preference()?;
+            if best.as_ref().map(|&(best_score, _)| score > best_score).unwrap_or(true) {
+                best = Some((score, platform));
+            }
+        }
+        best.map(|(_, platform)| platform).ok_or_else(|| PlatformError::no_platforms().into())
+    }
+
+    /// Returns a sort key used by `Platform::preferred`, higher being better.
+    fn preference(&self) -> OclResult<(bool, bool, OpenclVersion)> {
+        let has_gpu = !Device::list(self, Some(DeviceType::GPU))?.is_empty();
+        let name = format!("{} {}", self.name()?, self.vendor()?).to_lowercase();
+        let emulated = EMULATED_PLATFORMS.iter().any(|&pattern| name.contains(pattern));
+        Ok((has_gpu, !emulated, self.opencl_version()?))
     }
 
     /// Creates a new `Platform` from a `PlatformIdCore`.
@@ -114,12 +342,17 @@ impl Platform {
     ///
     /// * The major_version.minor_version value returned will be '1.2'.
     ///
-    /// * TODO: Convert this to new version system returning an `OpenclVersion`.
+    /// See `opencl_version` for the parsed version.
     pub fn version(&self) -> OclResult<String> {
         core::get_platform_info(&self.0, PlatformInfo::Version)
             .map(|r| r.into()).map_err(OclError::from)
     }
 
+    /// Returns the parsed OpenCL version supported by the platform.
+    pub fn opencl_version(&self) -> OclResult<OpenclVersion> {
+        self.0.version().map_err(OclError::from)
+    }
+
     /// Returns the platform name as a string.
     pub fn name(&self) -> OclResult<String> {
         core::get_platform_info(&self.0, PlatformInfo::Name)
@@ -166,15 +399,20 @@ unsafe impl ClPlatformIdPtr for Platform {
 
 impl Default for Platform {
     /// Returns the first (0th) platform available, or the platform specified
-    /// by the `OCL_DEFAULT_PLATFORM_IDX` environment variable if it is set.
+    /// by the `OCL_DEFAULT_PLATFORM_NAME` (matched against the platform name
+    /// or vendor, ignoring case) or `OCL_DEFAULT_PLATFORM_IDX` environment
+    /// variable if either is set.
     ///
     /// ### Panics
     ///
-    /// Panics upon any OpenCL API error.
+    /// Panics upon any OpenCL API error or if no platforms are available. Use
+    /// `Platform::try_default` to handle these cases.
     ///
     fn default() -> Platform {
-        let dflt_plat_core = core::default_platform().expect("Platform::default()");
-        Platform::new(dflt_plat_core)
+        match Platform::try_default() {
+            Ok(platform) => platform,
+            Err(err) => panic!("Platform::default(): {}", err),
+        }
     }
 }
 
@@ -222,3 +460,34 @@ impl DerefMut for Platform {
         &mut self.0
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+    use super::{icd_search_locations, IcdSearch};
+
+    #[test]
+    fn icd_search_location_precedence() {
+        let vendors = Some(OsString::from("/opt/vendors"));
+        let vendor_path = Some(OsString::from("/opt/vendor_path"));
+        assert_eq!(icd_search_locations(vendors.clone(), vendor_path.clone()),
+            vec![PathBuf::from("/opt/vendors")]);
+        assert_eq!(icd_search_locations(None, vendor_path),
+            vec![PathBuf::from("/opt/vendor_path")]);
+        #[cfg(not(windows))]
+        assert_eq!(icd_search_locations(Some(OsString::new()), None),
+            vec![PathBuf::from("/etc/OpenCL/vendors")]);
+    }
+
+    #[test]
+    fn icd_search_display() {
+        let search = IcdSearch { locations: vec![PathBuf::from("/opt/vendors")], icds: vec![] };
+        assert_eq!(search.to_string(), "searched: /opt/vendors; found: none");
+        let search = IcdSearch { locations: vec![],
+            icds: vec![PathBuf::from("/opt/vendors/a.icd")] };
+        assert_eq!(search.to_string(),
+            "searched: the ICD loader's default locations; found: /opt/vendors/a.icd");
+    }
+}
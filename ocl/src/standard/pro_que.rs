@@ -1,10 +1,16 @@
 //! A convenient wrapper for `Program` and `Queue`.
 
+use std::collections::HashMap;
+use std::ffi::CString;
 use std::ops::Deref;
+use std::sync::{Arc, Mutex, MutexGuard};
 use crate::error::{Error as OclError, Result as OclResult};
-use crate::core::{OclPrm, CommandQueueProperties};
+use crate::core::{OclPrm, CommandQueueProperties, MemFlags, MemObjectType, ImageChannelOrder,
+    ImageChannelDataType};
 use crate::standard::{Platform, Device, Context, ProgramBuilder, Program, Queue, Kernel, Buffer,
-    MemLen, SpatialDims, WorkDims, DeviceSpecifier, KernelBuilder, BufferBuilder};
+    MemLen, SpatialDims, WorkDims, DeviceSpecifier, DeviceCriteria, KernelBuilder, BufferBuilder,
+    Image, ImageBuilder, Event, ProfilingSummary};
+use crate::standard::profiling::KernelProfiler;
 
 static DIMS_ERR_MSG: &'static str = "This 'ProQue' has not had any dimensions specified. Use
     'ProQueBuilder::dims' during creation or 'ProQue::set_dims' after creation to specify.";
@@ -12,6 +18,28 @@ static DIMS_ERR_MSG: &'static str = "This 'ProQue' has not had any dimensions sp
 const DEBUG_PRINT: bool = false;
 
 
+/// A kernel cached by a `ProQue` (see `ProQue::create_kernel`).
+///
+/// Cloning a `CachedKernel` returns another handle to the same kernel. Lock
+/// it to set arguments or enqueue it.
+///
+/// ```rust,ignore
+/// let kernel = pro_que.create_kernel("add", |kb| { kb.arg(&buffer).arg(10.0f32); })?;
+/// unsafe { kernel.lock().enq()?; }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CachedKernel {
+    kernel: Arc<Mutex<Kernel>>,
+}
+
+impl CachedKernel {
+    /// Locks the kernel, blocking until no other thread holds it.
+    pub fn lock(&self) -> MutexGuard<'_, Kernel> {
+        self.kernel.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+
 /// An all-in-one chimera of the `Program`, `Queue`, `Context` and
 /// (optionally) `SpatialDims` types.
 ///
@@ -30,6 +58,34 @@ const DEBUG_PRINT: bool = false;
 /// 2. Call `::new` and pass pre-created components.
 ///
 ///
+/// # Transfer queue
+///
+/// A `ProQue` may optionally own a second, separate queue for transfers (see
+/// `ProQueBuilder::separate_io_queue` and `::io_queue`), allowing transfers
+/// to overlap with kernel execution. Buffers created by the `ProQue` use the
+/// transfer queue by default and kernels use the compute queue (`::queue`).
+/// Commands on the two queues are not ordered with respect to each other:
+/// use events (`::enew` and `::ewait`) to order them, or `::finish_all` to
+/// wait for both.
+///
+///
+/// # Kernel cache
+///
+/// Kernels created with `::create_kernel` are cached by name and returned by
+/// later calls to `::create_kernel` or `::kernel`, avoiding repeated kernel
+/// creation and argument setup in code run every frame or iteration. Clones
+/// of a `ProQue` share the same cache.
+///
+///
+/// # Profiling
+///
+/// A `ProQue` built with `ProQueBuilder::profiling` creates its queues with
+/// profiling enabled and collects execution time statistics, by kernel
+/// name, for kernels enqueued with `::enq_kernel` or events passed to
+/// `::record_event`. Use `::profiling_summary` to retrieve them. Clones of a
+/// `ProQue` share the same statistics.
+///
+///
 /// # Destruction
 ///
 /// Now handled automatically. Freely use, store, clone, discard, share among
@@ -39,8 +95,12 @@ const DEBUG_PRINT: bool = false;
 pub struct ProQue {
     context: Context,
     queue: Queue,
+    io_queue: Option<Queue>,
     program: Program,
     dims: Option<SpatialDims>,
+    named_dims: HashMap<String, SpatialDims>,
+    kernels: Arc<Mutex<HashMap<String, CachedKernel>>>,
+    profiler: Option<Arc<Mutex<KernelProfiler>>>,
 }
 
 impl ProQue {
@@ -64,20 +124,124 @@ impl ProQue {
         ProQue {
             context,
             queue,
+            io_queue: None,
             program,
             dims: dims.map(|d| d.into()),
+            named_dims: HashMap::new(),
+            kernels: Arc::new(Mutex::new(HashMap::new())),
+            profiler: None,
+        }
+    }
+
+    /// Returns the cached kernel named `name`, creating and caching it if
+    /// necessary.
+    ///
+    /// On creation, `configure` is called with a `KernelBuilder` set up as by
+    /// `::kernel_builder` and should specify the kernel's arguments. It is
+    /// not called if the kernel is already cached, so arguments set since
+    /// (with `Kernel::set_arg`) are kept.
+    ///
+    /// ### Example
+    ///
+    /// ```rust,ignore
+    /// for _ in 0..frames {
+    ///     let kernel = pro_que.create_kernel("add", |kb| {
+    ///         kb.arg(&buffer).arg_named("scalar", 0.0f32);
+    ///     })?;
+    ///     let kernel = kernel.lock();
+    ///     kernel.set_arg("scalar", scalar)?;
+    ///     unsafe { kernel.enq()?; }
+    /// }
+    /// ```
+    pub fn create_kernel<'b, S, F>(&'b self, name: S, configure: F) -> OclResult<CachedKernel>
+            where S: Into<String>, F: FnOnce(&mut KernelBuilder<'b>) {
+        let name = name.into();
+        if let Some(kernel) = self.kernel(&name) {
+            return Ok(kernel);
+        }
+
+        let mut kb = self.kernel_builder(name.clone());
+        configure(&mut kb);
+        let kernel = CachedKernel { kernel: Arc::new(Mutex::new(kb.build()?)) };
+
+        // Another thread may have created the same kernel in the meanwhile:
+        let mut kernels = self.kernels.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(kernels.entry(name).or_insert(kernel).clone())
+    }
+
+    /// Returns the cached kernel named `name`, if it has been created with
+    /// `::create_kernel`.
+    pub fn kernel(&self, name: &str) -> Option<CachedKernel> {
+        self.kernels.lock().unwrap_or_else(|e| e.into_inner()).get(name).cloned()
+    }
+
+    /// Removes all kernels from the cache.
+    ///
+    /// Handles to cached kernels remain valid.
+    pub fn clear_kernels(&self) {
+        self.kernels.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Recompiles the program from `src` in place, keeping the context,
+    /// queues, and any buffers already created.
+    ///
+    /// If the program is reloadable (see `ProQueBuilder::reloadable`), every
+    /// live kernel created from it, including those cached by
+    /// `::create_kernel`, is re-created from the new program with its
+    /// arguments intact (see `Program::rebuild`). Otherwise the kernel cache
+    /// is cleared, and kernels created before the rebuild continue to use the
+    /// old program.
+    ///
+    /// Upon error, the program and its kernels are left unchanged. Clones of
+    /// this `ProQue` keep using the old program.
+    pub fn rebuild<S: Into<String>>(&mut self, src: S) -> OclResult<()> {
+        self.program.rebuild(src)?;
+        if self.program.handle().is_none() {
+            self.clear_kernels();
         }
+        Ok(())
     }
 
-    // /// Creates a kernel with pre-assigned dimensions.
-    // pub fn create_kernel(&self, name: &str) -> OclResult<Kernel> {
-    //     let kernel = Kernel::new(name.to_string(), &self.program)?
-    //         .queue(self.queue.clone());
-    //     match self.dims {
-    //         Some(d) => Ok(kernel.gws(d)),
-    //         None => Ok(kernel),
-    //     }
-    // }
+    /// Builds this `ProQue`'s program on each of `devices` and returns a
+    /// `MultiProQue` with a `ProQue` (and queue) for each.
+    ///
+    /// The program is rebuilt from its source with the same compiler
+    /// options; programs created from binaries or IL cannot be replicated.
+    /// Queue properties, dimensions, a separate transfer queue, and whether
+    /// the program is reloadable are carried over. `devices` must belong to
+    /// the same platform and may include this `ProQue`'s own device.
+    pub fn replicate_across(&self, devices: &[Device]) -> OclResult<MultiProQue> {
+        let platform = match devices.first() {
+            Some(device) => device.platform()?,
+            None => return Err("ProQue::replicate_across: The device list is empty.".into()),
+        };
+
+        let src = self.program.source()?;
+        if src.is_empty() {
+            return Err("ProQue::replicate_across: The program was not built from source.".into());
+        }
+        let cmplr_opts = self.program.build_options(&self.program.devices()?)?;
+
+        let context = Context::builder().platform(platform).devices(devices).build()?;
+        let mut program = Program::with_source(&context, &[CString::new(src)?], Some(devices),
+            &cmplr_opts)?;
+        if self.program.handle().is_some() { program.set_reloadable(); }
+
+        let properties = Some(self.queue.properties()?);
+        let mut pro_ques = Vec::with_capacity(devices.len());
+        for &device in devices {
+            let queue = Queue::new(&context, device, properties)?;
+            let mut pro_que = ProQue::new(context.clone(), queue, program.clone(), self.dims);
+            pro_que.named_dims = self.named_dims.clone();
+            if self.profiler.is_some() { pro_que.enable_profiling(); }
+            if self.io_queue.is_some() {
+                pro_que.set_io_queue(Queue::new(&context, device, properties)?);
+            }
+            pro_ques.push(pro_que);
+        }
+
+        Ok(MultiProQue { context, program, pro_ques })
+    }
 
     /// Returns a new `KernelBuilder` with the name, program, default queue,
     /// and global work size pre-configured.
@@ -110,9 +274,27 @@ impl ProQue {
         kb
     }
 
+    /// Returns a new `KernelBuilder` pre-configured as by `::kernel_builder`
+    /// but with its global work size set to the named dimensions,
+    /// `dims_name`.
+    ///
+    /// # Errors
+    ///
+    /// No dimensions named `dims_name` have been set (see
+    /// `::set_dims_named`).
+    ///
+    pub fn kernel_builder_named_dims<S>(&self, name: S, dims_name: &str)
+            -> OclResult<KernelBuilder<'_>> where S: Into<String> {
+        let dims = *self.dims_named(dims_name)?;
+        let mut kb = self.kernel_builder(name);
+        kb.global_work_size(dims);
+        Ok(kb)
+    }
+
     /// Returns a new buffer.
     ///
-    /// The default dimensions and queue from this `ProQue` will be used.
+    /// The default dimensions and the transfer queue (`::io_queue`) from this
+    /// `ProQue` will be used.
     ///
     /// The buffer will be filled with zeros upon creation, blocking the
     /// current thread until completion.
@@ -126,43 +308,182 @@ impl ProQue {
     /// If not, set them with `::set_dims`, or just create a buffer using
     /// `Buffer::builder()` instead.
     ///
+    #[track_caller]
     pub fn create_buffer<T: OclPrm>(&self) -> OclResult<Buffer<T>> {
         let len = self.dims_result()?.to_len();
         Buffer::<T>::builder()
-            .queue(self.queue.clone())
+            .queue(self.io_queue().clone())
             .len(len)
             .fill_val(Default::default())
             .build()
     }
 
-    /// Returns a new `BufferBuilder` with the default queue and length
-    /// pre-configured.
+    /// Returns a new buffer created with `flags`.
     ///
-    /// Use `.fill_val(Default::default())` to fill buffer with zeros.
+    /// The default dimensions and the transfer queue (`::io_queue`) from this
+    /// `ProQue` will be used. The buffer will be filled with zeros upon
+    /// creation, blocking the current thread until completion.
     ///
-    /// Use `.build()` to create the buffer.
+    /// # Errors
     ///
-    /// ### Panics
+    /// This `ProQue` must have been pre-configured with default dimensions.
+    ///
+    #[track_caller]
+    pub fn create_buffer_with_flags<T: OclPrm>(&self, flags: MemFlags) -> OclResult<Buffer<T>> {
+        let len = self.dims_result()?.to_len();
+        Buffer::<T>::builder()
+            .queue(self.io_queue().clone())
+            .flags(flags)
+            .len(len)
+            .fill_val(Default::default())
+            .build()
+    }
+
+    /// Returns a new buffer with every element set to `fill_val`.
+    ///
+    /// The default dimensions and the transfer queue (`::io_queue`) from this
+    /// `ProQue` will be used. Blocks the current thread until the buffer has
+    /// been filled.
+    ///
+    /// # Errors
     ///
     /// This `ProQue` must have been pre-configured with default dimensions.
-    /// If not, set them with `::set_dims`, or just create a buffer using
-    /// `Buffer::builder()` instead.
     ///
-    pub fn buffer_builder<T: OclPrm>(&self) -> BufferBuilder<T> {
-        let len = self.dims_result()
-            .expect("`ProQue` dimensions not specified. Please specify dimensions \
-                using `::set_dims` before calling this method.")
-            .to_len();
+    #[track_caller]
+    pub fn create_buffer_filled<T: OclPrm>(&self, fill_val: T) -> OclResult<Buffer<T>> {
+        let len = self.dims_result()?.to_len();
         Buffer::<T>::builder()
-            .queue(self.queue.clone())
+            .queue(self.io_queue().clone())
             .len(len)
+            .fill_val(fill_val)
+            .build()
+    }
+
+    /// Returns a new buffer initialized with a copy of `data`.
+    ///
+    /// The buffer has the length of `data`, regardless of the default
+    /// dimensions, and uses the transfer queue (`::io_queue`).
+    #[track_caller]
+    pub fn create_buffer_from_slice<T: OclPrm>(&self, data: &[T]) -> OclResult<Buffer<T>> {
+        Buffer::<T>::builder()
+            .queue(self.io_queue().clone())
+            .len(data.len())
+            .copy_host_slice(data)
+            .build()
+    }
+
+    /// Returns a new `BufferBuilder` with the transfer queue (`::io_queue`)
+    /// and default length pre-configured.
+    ///
+    /// Use `.fill_val(Default::default())` to fill buffer with zeros.
+    ///
+    /// Use `.build()` to create the buffer.
+    ///
+    /// If this `ProQue` has no default dimensions, the length is left unset
+    /// and must be specified with `.len(...)` before building.
+    ///
+    pub fn buffer_builder<T: OclPrm>(&self) -> BufferBuilder<T> {
+        let builder = Buffer::<T>::builder().queue(self.io_queue().clone());
+        match self.dims {
+            Some(dims) => builder.len(dims),
+            None => builder,
+        }
+    }
+
+    /// Returns a new `ImageBuilder` with the transfer queue (`::io_queue`),
+    /// the default dimensions, and an image type matching the number of
+    /// dimensions (`Image1d`, `Image2d`, or `Image3d`) pre-configured.
+    ///
+    /// The channel order and data type must still be set before building.
+    ///
+    /// ### Example
+    ///
+    /// ```rust,ignore
+    /// let image = pro_que.image_builder::<i32>()
+    ///     .channel_order(ImageChannelOrder::Rgba)
+    ///     .channel_data_type(ImageChannelDataType::SignedInt32)
+    ///     .flags(flags::MEM_READ_WRITE | flags::MEM_COPY_HOST_PTR)
+    ///     .copy_host_slice(&vec)
+    ///     .build()?;
+    /// ```
+    ///
+    /// If this `ProQue` has no default dimensions, the image type and
+    /// dimensions are left unset and must be specified with `.image_type(...)`
+    /// and `.dims(...)` before building.
+    ///
+    pub fn image_builder<T: OclPrm>(&self) -> ImageBuilder<'_, T> {
+        let builder = Image::<T>::builder().queue(self.io_queue().clone());
+        let dims = match self.dims {
+            Some(dims) => dims,
+            None => return builder,
+        };
+        let image_type = match dims {
+            SpatialDims::Three(..) => MemObjectType::Image3d,
+            SpatialDims::Two(..) => MemObjectType::Image2d,
+            _ => MemObjectType::Image1d,
+        };
+        builder.image_type(image_type).dims(dims)
+    }
+
+    /// Returns a new image with the given channel order and data type.
+    ///
+    /// The default dimensions, an image type matching the number of
+    /// dimensions, and the transfer queue (`::io_queue`) from this `ProQue`
+    /// will be used. Use `::image_builder` for more options.
+    ///
+    /// # Errors
+    ///
+    /// This `ProQue` must have been pre-configured with default dimensions.
+    ///
+    pub fn create_image<T: OclPrm>(&self, channel_order: ImageChannelOrder,
+            channel_data_type: ImageChannelDataType) -> OclResult<Image<T>> {
+        self.dims()?;
+        self.image_builder::<T>()
+            .channel_order(channel_order)
+            .channel_data_type(channel_data_type)
+            .build()
     }
 
     /// Sets the default dimensions used when creating buffers and kernels.
+    ///
+    /// Kernels already cached by `::create_kernel` keep their global work
+    /// size.
     pub fn set_dims<S: Into<SpatialDims>>(&mut self, dims: S) {
         self.dims = Some(dims.into());
     }
 
+    /// Sets a named set of dimensions, e.g. `"tiles"`, replacing any
+    /// previously set under the same name.
+    ///
+    /// Named dimensions are used by `::kernel_builder_named_dims` and
+    /// `::create_buffer_named_dims` and are independent of the default
+    /// dimensions.
+    pub fn set_dims_named<S, D>(&mut self, name: S, dims: D)
+            where S: Into<String>, D: Into<SpatialDims> {
+        self.named_dims.insert(name.into(), dims.into());
+    }
+
+    /// Returns a new buffer with a length determined by the named dimensions,
+    /// `dims_name`.
+    ///
+    /// The transfer queue (`::io_queue`) is used as the buffer's default
+    /// queue.
+    ///
+    /// # Errors
+    ///
+    /// No dimensions named `dims_name` have been set (see
+    /// `::set_dims_named`).
+    ///
+    #[track_caller]
+    pub fn create_buffer_named_dims<T: OclPrm>(&self, dims_name: &str) -> OclResult<Buffer<T>> {
+        let len = self.dims_named(dims_name)?.to_len();
+        Buffer::<T>::builder()
+            .queue(self.io_queue().clone())
+            .len(len)
+            .fill_val(Default::default())
+            .build()
+    }
+
     /// Returns the maximum workgroup size supported by the device associated
     /// with this `ProQue`.
     ///
@@ -172,10 +493,45 @@ impl ProQue {
     }
 
     /// Returns a reference to the queue associated with this ProQue.
+    ///
+    /// Kernels created with `::kernel_builder` use this queue.
     pub fn queue(&self) -> &Queue {
         &self.queue
     }
 
+    /// Returns a reference to the queue used for transfers.
+    ///
+    /// This is the same as `::queue` unless a separate transfer queue has
+    /// been created with `ProQueBuilder::separate_io_queue` or set with
+    /// `::set_io_queue`.
+    pub fn io_queue(&self) -> &Queue {
+        self.io_queue.as_ref().unwrap_or(&self.queue)
+    }
+
+    /// Returns true if this `ProQue` has a transfer queue separate from its
+    /// compute queue.
+    pub fn has_separate_io_queue(&self) -> bool {
+        self.io_queue.is_some()
+    }
+
+    /// Sets the queue used for transfers.
+    ///
+    /// `io_queue` must belong to the same context and device as this
+    /// `ProQue`. Buffers already created keep their default queue.
+    pub fn set_io_queue(&mut self, io_queue: Queue) {
+        self.io_queue = Some(io_queue);
+    }
+
+    /// Blocks until all commands on both the compute and transfer queues
+    /// have completed.
+    pub fn finish_all(&self) -> OclResult<()> {
+        self.queue.finish()?;
+        if let Some(ref io_queue) = self.io_queue {
+            io_queue.finish()?;
+        }
+        Ok(())
+    }
+
     /// Returns the contained context.
     pub fn context(&self) -> &Context {
         &self.context
@@ -186,32 +542,107 @@ impl ProQue {
         &self.program
     }
 
-    /// Returns the current `dims` or panics.
+    /// Returns true if this `ProQue` collects kernel execution times (see
+    /// `ProQueBuilder::profiling`).
+    pub fn is_profiling(&self) -> bool {
+        self.profiler.is_some()
+    }
+
+    /// Enqueues `kernel`, recording its execution time under the kernel's
+    /// name if profiling is enabled.
+    ///
+    /// # Safety
     ///
-    /// [UNSTABLE]: Evaluate which 'dims' method to keep. Leaning towards this
-    /// version at the moment.
-    pub fn dims(&self) -> &SpatialDims {
-        self.dims_result().expect(DIMS_ERR_MSG)
+    /// The same as for `Kernel::enq`.
+    pub unsafe fn enq_kernel(&self, kernel: &Kernel) -> OclResult<()> {
+        match self.profiler {
+            Some(_) => {
+                let mut event = Event::empty();
+                kernel.cmd().enew(&mut event).enq()?;
+                self.record_event(kernel.name()?, &event)
+            },
+            None => kernel.enq(),
+        }
     }
 
-    /// Returns the current `dims` or an error.
+    /// Records the execution time of the command associated with `event`
+    /// under `name`. Does nothing if profiling is not enabled.
     ///
-    /// [UNSTABLE]: Evaluate which 'dims' method to keep. Leaning towards the
-    /// above, panicking version at the moment.
-    pub fn dims_result(&self) -> OclResult<&SpatialDims> {
+    /// `event` must come from a queue with profiling enabled, such as
+    /// `::queue` or `::io_queue`.
+    pub fn record_event<S: Into<String>>(&self, name: S, event: &Event) -> OclResult<()> {
+        match self.profiler {
+            Some(ref profiler) => profiler.lock().unwrap_or_else(|e| e.into_inner())
+                .record(name.into(), event.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns execution time statistics for each kernel recorded so far,
+    /// blocking until all recorded commands have completed.
+    ///
+    /// # Errors
+    ///
+    /// Profiling was not enabled with `ProQueBuilder::profiling`.
+    ///
+    pub fn profiling_summary(&self) -> OclResult<ProfilingSummary> {
+        match self.profiler {
+            Some(ref profiler) => profiler.lock().unwrap_or_else(|e| e.into_inner()).summary(),
+            None => Err("ProQue::profiling_summary: Profiling is not enabled. Use \
+                'ProQueBuilder::profiling' to enable it.".into()),
+        }
+    }
+
+    /// Discards all collected execution time statistics.
+    pub fn reset_profiling(&self) {
+        if let Some(ref profiler) = self.profiler {
+            profiler.lock().unwrap_or_else(|e| e.into_inner()).reset();
+        }
+    }
+
+    /// Starts collecting kernel execution times.
+    fn enable_profiling(&mut self) {
+        self.profiler = Some(Arc::new(Mutex::new(KernelProfiler::default())));
+    }
+
+    /// Returns the current `dims` or an error if none have been set.
+    ///
+    /// Default dimensions are optional; only methods which need them
+    /// (`::create_buffer`, `::create_image`, etc.) return an error when they
+    /// are unset.
+    pub fn dims(&self) -> OclResult<&SpatialDims> {
         match self.dims {
             Some(ref dims) => Ok(dims),
             None => Err(DIMS_ERR_MSG.into()),
         }
     }
+
+    /// Returns the current `dims` or an error.
+    ///
+    /// Equivalent to `::dims`.
+    pub fn dims_result(&self) -> OclResult<&SpatialDims> {
+        self.dims()
+    }
+
+    /// Returns the dimensions set under `name` or an error.
+    pub fn dims_named(&self, name: &str) -> OclResult<&SpatialDims> {
+        self.named_dims.get(name).ok_or_else(|| format!("ProQue::dims_named: No dimensions \
+            named '{}' have been set. Use 'ProQueBuilder::dims_named' during creation or \
+            'ProQue::set_dims_named' after creation to specify.", name).into())
+    }
+
+    /// Returns the names of all named dimension sets.
+    pub fn dims_names(&self) -> Vec<&str> {
+        self.named_dims.keys().map(|k| k.as_str()).collect()
+    }
 }
 
 impl MemLen for ProQue {
     fn to_len(&self) -> usize {
-        self.dims().to_len()
+        self.dims().expect(DIMS_ERR_MSG).to_len()
     }
     fn to_len_padded(&self, incr: usize) -> usize {
-        self.dims().to_len_padded(incr)
+        self.dims().expect(DIMS_ERR_MSG).to_len_padded(incr)
     }
     fn to_lens(&self) -> [usize; 3] {
         self.dims_result().expect("ocl::ProQue::to_lens()")
@@ -242,6 +673,91 @@ impl Deref for ProQue {
 }
 
 
+/// The same program built on several devices, with a `ProQue` for each
+/// device (see `ProQue::replicate_across`).
+///
+/// All `ProQue`s share a single context and program but each has its own
+/// queue(s) and kernel cache. Scaffolding for data-parallel work split
+/// across several devices:
+///
+/// ```rust,ignore
+/// let multi = pro_que.replicate_across(&Device::list_all(platform)?)?;
+/// multi.for_each_device(|pq| {
+///     let buffer = pq.create_buffer::<f32>()?;
+///     let kernel = pq.kernel_builder("add").arg(&buffer).arg(1.0f32).build()?;
+///     unsafe { kernel.enq() }
+/// })?;
+/// multi.finish_all()?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct MultiProQue {
+    context: Context,
+    program: Program,
+    pro_ques: Vec<ProQue>,
+}
+
+impl MultiProQue {
+    /// Returns the context shared by every device.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Returns the program, built for every device.
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Returns the devices, in the order they were specified.
+    pub fn devices(&self) -> Vec<Device> {
+        self.pro_ques.iter().map(|pq| pq.device()).collect()
+    }
+
+    /// Returns the `ProQue` for the device at `idx`.
+    pub fn get(&self, idx: usize) -> Option<&ProQue> {
+        self.pro_ques.get(idx)
+    }
+
+    /// Returns an iterator over the `ProQue` of each device.
+    pub fn iter(&self) -> std::slice::Iter<'_, ProQue> {
+        self.pro_ques.iter()
+    }
+
+    /// Returns the number of devices.
+    pub fn len(&self) -> usize {
+        self.pro_ques.len()
+    }
+
+    /// Returns true if there are no devices.
+    pub fn is_empty(&self) -> bool {
+        self.pro_ques.is_empty()
+    }
+
+    /// Calls `f` with the `ProQue` of each device in turn, stopping at the
+    /// first error.
+    pub fn for_each_device<F>(&self, mut f: F) -> OclResult<()>
+            where F: FnMut(&ProQue) -> OclResult<()> {
+        for pro_que in &self.pro_ques {
+            f(pro_que)?;
+        }
+        Ok(())
+    }
+
+    /// Blocks until all commands on every device's queues have completed.
+    pub fn finish_all(&self) -> OclResult<()> {
+        self.for_each_device(|pq| pq.finish_all())
+    }
+}
+
+
+/// A program input of a `ProQueBuilder` which is not compiled from source.
+enum Prebuilt<'b> {
+    Program(Program),
+    Binary(Device, &'b [u8]),
+    #[cfg(feature = "opencl_version_2_1")]
+    Il(&'b [u8]),
+}
+
+
 /// A builder for `ProQue`.
 #[must_use = "builders do nothing unless '::build' is called"]
 pub struct ProQueBuilder<'b> {
@@ -249,8 +765,13 @@ pub struct ProQueBuilder<'b> {
     context: Option<Context>,
     device_spec: Option<DeviceSpecifier>,
     program_builder: Option<ProgramBuilder<'b>>,
+    prebuilt: Option<Prebuilt<'b>>,
     dims: Option<SpatialDims>,
+    named_dims: HashMap<String, SpatialDims>,
     queue_properties: Option<CommandQueueProperties>,
+    separate_io_queue: bool,
+    reloadable: bool,
+    profiling: bool,
 }
 
 impl<'b> ProQueBuilder<'b> {
@@ -265,14 +786,26 @@ impl<'b> ProQueBuilder<'b> {
     /// pass them as arguments to the `::context` and `::prog_bldr` methods
     /// respectively.
     ///
+    /// If no platform, context, or device is set, the default platform and
+    /// device are used. These may be chosen at run time with the
+    /// `OCL_DEFAULT_PLATFORM_NAME`, `OCL_DEFAULT_PLATFORM_IDX`,
+    /// `OCL_DEFAULT_DEVICE_NAME`, `OCL_DEFAULT_DEVICE_IDX`, and
+    /// `OCL_DEFAULT_DEVICE_TYPE` environment variables (see
+    /// `Platform::default` and `Device::first`).
+    ///
     pub fn new() -> ProQueBuilder<'b> {
         ProQueBuilder {
             platform: None,
             context: None,
             device_spec: None,
             program_builder: None,
+            prebuilt: None,
             dims: None,
+            named_dims: HashMap::new(),
             queue_properties: None,
+            separate_io_queue: false,
+            reloadable: false,
+            profiling: false,
         }
     }
 
@@ -311,6 +844,26 @@ impl<'b> ProQueBuilder<'b> {
         self
     }
 
+    /// Selects the most capable device meeting `criteria` and returns the
+    /// `ProQueBuilder`.
+    ///
+    /// If neither a platform nor a context has been set, devices on every
+    /// platform are considered. If a context has been set, only its devices
+    /// are considered. When no device matches, building returns an error
+    /// listing the devices which were considered.
+    ///
+    /// ```rust,ignore
+    /// let pro_que = ProQue::builder()
+    ///     .src(src)
+    ///     .device_criteria(DeviceCriteria::gpu().min_global_mem_gb(8)
+    ///         .extension("cl_khr_fp64"))
+    ///     .build()?;
+    /// ```
+    ///
+    pub fn device_criteria(&mut self, criteria: DeviceCriteria) -> &mut ProQueBuilder<'b> {
+        self.device(criteria)
+    }
+
     /// Adds some source code to be compiled and returns the `ProQueBuilder`.
     ///
     /// Creates a `ProgramBuilder` if one has not already been added. Attempts
@@ -320,11 +873,12 @@ impl<'b> ProQueBuilder<'b> {
     /// source files. Pass an *unbuilt* `ProgramBuilder` to the
     /// `::program_builder` method (described below).
     pub fn src<S: Into<String>>(&mut self, src: S) -> &mut ProQueBuilder<'b> {
-        if self.program_builder.is_some() {
+        if self.program_builder.is_some() || self.prebuilt.is_some() {
             panic!("ocl::ProQueBuilder::src: Cannot set src if a 'ProgramBuilder' is already \
                 defined. Please use the '::program_builder' method for more complex build \
                 configurations.");
         } else {
+            let src: String = src.into();
             let mut pb = Program::builder();
             pb.src(src);
             self.program_builder = Some(pb);
@@ -343,8 +897,9 @@ impl<'b> ProQueBuilder<'b> {
     /// the device specified by `::device_idx` or the default device if none has
     /// been specified.
     pub fn prog_bldr(&mut self, program_builder: ProgramBuilder<'b>) -> &mut ProQueBuilder<'b> {
-        assert!(self.program_builder.is_none(), "ProQueBuilder::prog_bldr(): Cannot set the \
-            'ProgramBuilder' using this method after one has already been set or after '::src' has \
+        assert!(self.program_builder.is_none() && self.prebuilt.is_none(),
+            "ProQueBuilder::prog_bldr(): Cannot set the 'ProgramBuilder' using this method after \
+            one has already been set or after '::src', '::program', '::binary', or '::il' has \
             been called.");
 
         assert!(program_builder.get_device_spec().is_none(), "ProQueBuilder::prog_bldr(): The \
@@ -355,6 +910,57 @@ impl<'b> ProQueBuilder<'b> {
         self
     }
 
+    /// Uses an already built program and returns the `ProQueBuilder`.
+    ///
+    /// The program's context is used unless one is set with `::context` (in
+    /// which case they must match), and its first device is used unless one
+    /// is set with `::device`.
+    ///
+    /// ## Panics
+    ///
+    /// No other program input (`::src`, `::prog_bldr`, `::binary`, or
+    /// `::il`) may have been specified.
+    pub fn program(&mut self, program: Program) -> &mut ProQueBuilder<'b> {
+        self.set_prebuilt(Prebuilt::Program(program), "program");
+        self
+    }
+
+    /// Builds the program from a precompiled binary for `device` and returns
+    /// the `ProQueBuilder`.
+    ///
+    /// `device` is used unless a device is set with `::device` (in which case
+    /// they must match). The binary is typically one previously returned by
+    /// `ProgramInfo::Binaries` for the same device and driver.
+    ///
+    /// ## Panics
+    ///
+    /// No other program input (`::src`, `::prog_bldr`, `::program`, or
+    /// `::il`) may have been specified.
+    pub fn binary(&mut self, device: Device, binary: &'b [u8]) -> &mut ProQueBuilder<'b> {
+        self.set_prebuilt(Prebuilt::Binary(device, binary), "binary");
+        self
+    }
+
+    /// Builds the program from intermediate language (e.g. SPIR-V) and
+    /// returns the `ProQueBuilder`.
+    ///
+    /// ## Panics
+    ///
+    /// No other program input (`::src`, `::prog_bldr`, `::program`, or
+    /// `::binary`) may have been specified.
+    #[cfg(feature = "opencl_version_2_1")]
+    pub fn il(&mut self, il: &'b [u8]) -> &mut ProQueBuilder<'b> {
+        self.set_prebuilt(Prebuilt::Il(il), "il");
+        self
+    }
+
+    fn set_prebuilt(&mut self, prebuilt: Prebuilt<'b>, method: &str) {
+        assert!(self.program_builder.is_none() && self.prebuilt.is_none(),
+            "ocl::ProQueBuilder::{}: A program, binary, IL, source, or 'ProgramBuilder' has \
+            already been specified.", method);
+        self.prebuilt = Some(prebuilt);
+    }
+
     /// Sets the built-in dimensions.
     ///
     /// This is optional.
@@ -369,6 +975,16 @@ impl<'b> ProQueBuilder<'b> {
         self
     }
 
+    /// Sets a named set of dimensions, e.g. `"tiles"`.
+    ///
+    /// This is optional and may be called multiple times with different
+    /// names. See `ProQue::set_dims_named`.
+    pub fn dims_named<S, D>(&mut self, name: S, dims: D) -> &mut ProQueBuilder<'b>
+            where S: Into<String>, D: Into<SpatialDims> {
+        self.named_dims.insert(name.into(), dims.into());
+        self
+    }
+
     /// Sets the command queue properties.
     ///
     /// Optional.
@@ -378,21 +994,78 @@ impl<'b> ProQueBuilder<'b> {
         self
     }
 
+    /// Creates a second queue, used for transfers, in addition to the queue
+    /// used for kernels (see `ProQue::io_queue`).
+    ///
+    /// Both queues are created with the properties set by
+    /// `::queue_properties`. Optional; defaults to `false`.
+    ///
+    pub fn separate_io_queue(&mut self, separate: bool) -> &mut ProQueBuilder<'b> {
+        self.separate_io_queue = separate;
+        self
+    }
+
+    /// Makes the program reloadable, so that kernels created from it are
+    /// refreshed by `ProQue::rebuild` (see `ProgramBuilder::reloadable`).
+    ///
+    /// Optional. Programs built from a reloadable `ProgramBuilder` passed to
+    /// `::prog_bldr` are also reloadable.
+    ///
+    pub fn reloadable(&mut self) -> &mut ProQueBuilder<'b> {
+        self.reloadable = true;
+        self
+    }
+
+    /// Creates the queues with profiling enabled and collects kernel
+    /// execution time statistics (see `ProQue::profiling_summary`).
+    ///
+    /// Optional; defaults to `false`.
+    ///
+    pub fn profiling(&mut self, profiling: bool) -> &mut ProQueBuilder<'b> {
+        self.profiling = profiling;
+        self
+    }
+
 
     /// Returns a new `ProQue`.
     ///
     /// ## Errors
     ///
-    /// A `ProgramBuilder` or some source code must have been specified with
-    /// `::prog_bldr` or `::src` before building.
+    /// A `ProgramBuilder`, some source code, a built program, a binary, or IL
+    /// must have been specified with `::prog_bldr`, `::src`, `::program`,
+    /// `::binary`, or `::il` before building.
     ///
     pub fn build(&self) -> OclResult<ProQue> {
-        let program_builder = match self.program_builder {
-            Some(ref program_builder) => program_builder,
-            None => return Err("ProQueBuilder::build(): No program builder or kernel source defined. \
+        if self.program_builder.is_none() && self.prebuilt.is_none() {
+            return Err("ProQueBuilder::build(): No program builder or kernel source defined. \
                 OpenCL programs must have some source code to be compiled. Use '::src' to directly \
                 add source code or '::program_builder' for more complex builds. Please see the \
-                'ProQueBuilder' and 'ProgramBuilder' documentation for more information.".into()),
+                'ProQueBuilder' and 'ProgramBuilder' documentation for more information.".into());
+        }
+
+        // A built program brings its own context:
+        let context_spec = match (&self.prebuilt, &self.context) {
+            (&Some(Prebuilt::Program(ref program)), Some(context)) => {
+                if program.context()?.as_core() != context.as_core() {
+                    return Err("ProQueBuilder::build(): The program passed to '::program' does \
+                        not belong to the context passed to '::context'.".into());
+                }
+                Some(context.clone())
+            },
+            (&Some(Prebuilt::Program(ref program)), &None) => Some(program.context()?),
+            (_, context) => context.clone(),
+        };
+
+        // Criteria with neither a platform nor a context search every platform:
+        let selected = match (&self.device_spec, self.platform, &context_spec) {
+            (&Some(DeviceSpecifier::Criteria(ref criteria)), None, &None) => {
+                let mut devices = Vec::new();
+                for platform in Platform::list()? {
+                    devices.extend(Device::list_all(platform)?);
+                }
+                Some(criteria.select_from(&devices)?)
+            },
+            _ => None,
         };
 
         // If no platform is set or no context platform is set, use the first available:
@@ -402,7 +1075,7 @@ impl<'b> ProQueBuilder<'b> {
                     platform and context cannot both be set.");
                 *plt
             },
-            None => match self.context {
+            None => match context_spec {
                 Some(ref context) => {
                     let plat = context.platform()?;
 
@@ -411,14 +1084,22 @@ impl<'b> ProQueBuilder<'b> {
 
                     plat.unwrap_or_default()
                 },
-                None => Platform::default(),
+                None => match selected {
+                    Some(device) => device.platform()?,
+                    None => Platform::try_default()?,
+                },
             },
         };
 
 
         // Resolve the device and ensure only one was specified.
-        let device = match self.device_spec {
-            Some(ref ds) => {
+        let device = match (selected, &self.device_spec) {
+            (Some(device), _) => device,
+            (None, &Some(DeviceSpecifier::Criteria(ref criteria))) => match context_spec {
+                Some(ref context) => criteria.select_from(&context.devices())?,
+                None => criteria.select_from(&Device::list_all(platform)?)?,
+            },
+            (None, Some(ds)) => {
                 let device_list = ds.to_device_list(Some(platform))?;
 
                 if device_list.len() == 1 {
@@ -430,17 +1111,37 @@ impl<'b> ProQueBuilder<'b> {
                         device_list.len()).into());
                 }
             },
-            None => Device::first(platform)?,
+            (None, &None) => match self.prebuilt {
+                Some(Prebuilt::Binary(device, _)) => device,
+                Some(Prebuilt::Program(ref program)) => match program.devices()?.first() {
+                    Some(&device) => device,
+                    None => return Err("ProQueBuilder::build(): The program passed to \
+                        '::program' has no associated devices.".into()),
+                },
+                _ => Device::first(platform)?,
+            },
         };
 
+        match self.prebuilt {
+            Some(Prebuilt::Binary(binary_device, _)) if binary_device != device => {
+                return Err("ProQueBuilder::build(): The device passed to '::binary' differs from \
+                    the device passed to '::device'.".into());
+            },
+            Some(Prebuilt::Program(ref program)) if !program.devices()?.contains(&device) => {
+                return Err(format!("ProQueBuilder::build(): The program passed to '::program' \
+                    was not built for the device '{}'.", device.name()?).into());
+            },
+            _ => (),
+        }
+
         if DEBUG_PRINT { println!("ProQue::build(): device: {:?}", device); }
 
         // If no context was set, creates one using the above platform and the
         // pre-set device index (default [0]).
-        let context = match self.context {
-            Some(ref ctx) => {
+        let context = match context_spec {
+            Some(ctx) => {
                 assert!(ctx.devices().contains(&device));
-                ctx.clone()
+                ctx
             }
             None => {
                 Context::builder()
@@ -452,22 +1153,48 @@ impl<'b> ProQueBuilder<'b> {
 
         if DEBUG_PRINT { println!("ProQue::build(): context.devices(): {:?}", context.devices()); }
 
-        let queue = Queue::new(&context, device, self.queue_properties)?;
+        let queue_properties = match (self.queue_properties, self.profiling) {
+            (Some(props), true) => Some(props | CommandQueueProperties::PROFILING_ENABLE),
+            (None, true) => Some(CommandQueueProperties::PROFILING_ENABLE),
+            (props, false) => props,
+        };
+        let queue = Queue::new(&context, device, queue_properties)?;
+        let io_queue = if self.separate_io_queue {
+            Some(Queue::new(&context, device, queue_properties)?)
+        } else {
+            None
+        };
 
-        // println!("PROQUEBUILDER: About to load SRC_STRINGS.");
-        let src_strings = program_builder.get_src_strings().map_err(|e| e.to_string())?;
-        // println!("PROQUEBUILDER: About to load CMPLR_OPTS.");
-        let cmplr_opts = program_builder.get_compiler_options().map_err(|e| e.to_string())?;
-        // println!("PROQUEBUILDER: All done.");
+        let mut program = match (&self.program_builder, &self.prebuilt) {
+            (Some(program_builder), _) => {
+                let src_strings = program_builder.get_src_strings().map_err(|e| e.to_string())?;
+                let cmplr_opts = program_builder.get_compiler_options()
+                    .map_err(|e| e.to_string())?;
 
-        let program = Program::with_source(
-            &context,
-            &src_strings,
-            Some(&[device]),
-            &cmplr_opts,
-        )?;
+                program_builder.attach_handle(Program::with_source(
+                    &context,
+                    &src_strings,
+                    Some(&[device]),
+                    &cmplr_opts,
+                )?)
+            },
+            (&None, &Some(Prebuilt::Program(ref program))) => program.clone(),
+            (&None, &Some(Prebuilt::Binary(_, binary))) => {
+                Program::with_binary(&context, &[device], &[binary], &CString::default())?
+            },
+            #[cfg(feature = "opencl_version_2_1")]
+            (&None, &Some(Prebuilt::Il(il))) => {
+                Program::with_il(il, Some(&[device]), &CString::default(), &context)?
+            },
+            (&None, &None) => unreachable!(),
+        };
+        if self.reloadable { program.set_reloadable(); }
 
-        Ok(ProQue::new(context, queue, program, self.dims))
+        let mut pro_que = ProQue::new(context, queue, program, self.dims);
+        pro_que.named_dims = self.named_dims.clone();
+        if self.profiling { pro_que.enable_profiling(); }
+        if let Some(io_queue) = io_queue { pro_que.set_io_queue(io_queue); }
+        Ok(pro_que)
     }
 }
 
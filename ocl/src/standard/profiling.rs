@@ -0,0 +1,469 @@
+//! Timeline export of profiled commands.
+//!
+//! A `Trace` collects events, each with a label, and exports their device
+//! timestamps in the Chrome trace event format, viewable in
+//! `about://tracing` or Perfetto (`ui.perfetto.dev`). Each queue is shown as
+//! a separate track, making overlap between transfers and kernels on
+//! different queues easy to spot.
+//!
+//! Events must come from queues with profiling enabled (see
+//! `Queue::with_profiling`) and must have completed before the trace is
+//! exported.
+//!
+//! A `DeviceTimerSync` (see `Device::timer_sync`, OpenCL 2.1+) relates device
+//! timestamps to host `Instant`s so that device timelines can be merged
+//! with host-side tracing.
+//!
+//! A `ProfilingSummary` holds per-kernel execution time statistics
+//! collected by a `ProQue` built with `ProQueBuilder::profiling`.
+
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+use crate::core::{CommandType, EventInfo, EventInfoResult};
+use crate::error::Result as OclResult;
+use crate::standard::{Event, Kernel, Queue};
+
+
+/// A single recorded command.
+#[derive(Clone, Debug)]
+struct Record {
+    label: String,
+    command_type: Option<CommandType>,
+    queue: usize,
+    event: Event,
+}
+
+
+/// A collection of labeled events which can be exported as a Chrome trace.
+///
+/// ```rust,ignore
+/// let mut trace = Trace::new();
+/// let mut event = Event::empty();
+/// kernel.cmd().enew(&mut event).enq()?;
+/// trace.record_kernel(&kernel, &event)?;
+/// queue.finish()?;
+/// std::fs::write("trace.json", trace.to_json()?)?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Trace {
+    records: Vec<Record>,
+    // Track order and names, keyed by queue pointer.
+    queues: Vec<(usize, Option<String>)>,
+}
+
+impl Trace {
+    /// Returns a new, empty trace.
+    pub fn new() -> Trace {
+        Trace::default()
+    }
+
+    /// Records `event` under `label`.
+    ///
+    /// The event's command type and queue are looked up immediately; its
+    /// timestamps are not read until the trace is exported.
+    pub fn record<S: Into<String>>(&mut self, label: S, event: &Event) -> OclResult<()> {
+        let command_type = match event.info(EventInfo::CommandType)? {
+            EventInfoResult::CommandType(command_type) => Some(command_type),
+            _ => None,
+        };
+        let queue = self.queue_track(event.queue_core()?.as_ptr() as usize);
+        let record = Record { label: label.into(), command_type, queue, event: event.clone() };
+        self.records.push(record);
+        Ok(())
+    }
+
+    /// Records `event`, labeled with the name of `kernel`.
+    pub fn record_kernel(&mut self, kernel: &Kernel, event: &Event) -> OclResult<()> {
+        let name = kernel.name()?;
+        self.record(name, event)
+    }
+
+    /// Records `event` under its own label (see `Event::set_label`), or
+    /// under the name of its command type if it has none.
+    pub fn record_event(&mut self, event: &Event) -> OclResult<()> {
+        let label = match event.label() {
+            Some(label) => label,
+            None => match event.info(EventInfo::CommandType)? {
+                EventInfoResult::CommandType(command_type) => format!("{:?}", command_type),
+                _ => "Unknown".to_owned(),
+            },
+        };
+        self.record(label, event)
+    }
+
+    /// Sets the name shown for the track of commands enqueued on `queue`.
+    ///
+    /// Unnamed queues are shown as "Queue 0", "Queue 1", etc. in the order
+    /// their first event was recorded.
+    pub fn set_queue_name<S: Into<String>>(&mut self, queue: &Queue, name: S) {
+        let idx = self.queue_track(queue.as_core().as_ptr() as usize);
+        self.queues[idx].1 = Some(name.into());
+    }
+
+    /// Returns the number of recorded events.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns true if no events have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Removes all recorded events, keeping queue names.
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    /// Returns the recorded events in the Chrome trace event (JSON) format.
+    ///
+    /// Times are in microseconds, relative to the earliest time at which a
+    /// recorded command was enqueued.
+    ///
+    /// Returns an error for which `Error::is_profiling_unavailable` is true
+    /// if any recorded event has no profiling information (see
+    /// `Event::profiling`).
+    pub fn to_json(&self) -> OclResult<String> {
+        let mut profiles = Vec::with_capacity(self.records.len());
+        for record in &self.records {
+            profiles.push(record.event.profiling()?);
+        }
+        let origin = profiles.iter().map(|p| p.queued()).min().unwrap_or(0);
+        let micros = |ns: u64| ns.saturating_sub(origin) as f64 / 1000.0;
+
+        let mut entries = Vec::with_capacity(self.queues.len() + self.records.len());
+
+        for (tid, (_, name)) in self.queues.iter().enumerate() {
+            let name = match *name {
+                Some(ref name) => name.clone(),
+                None => format!("Queue {}", tid),
+            };
+            entries.push(format!("{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":0,\"tid\":{},\
+                \"args\":{{\"name\":{}}}}}", tid, json_string(&name)));
+        }
+
+        for (record, profile) in self.records.iter().zip(profiles.iter()) {
+            let category = match record.command_type {
+                Some(command_type) => format!("{:?}", command_type),
+                None => "Unknown".to_owned(),
+            };
+            entries.push(format!("{{\"name\":{},\"cat\":{},\"ph\":\"X\",\"pid\":0,\"tid\":{},\
+                \"ts\":{:.3},\"dur\":{:.3},\"args\":{{\"queued\":{:.3},\"submit\":{:.3}}}}}",
+                json_string(&record.label), json_string(&category), record.queue,
+                micros(profile.start()), profile.duration().as_nanos() as f64 / 1000.0,
+                micros(profile.queued()), micros(profile.submit())));
+        }
+
+        Ok(format!("{{\"traceEvents\":[{}],\"displayTimeUnit\":\"ns\"}}", entries.join(",")))
+    }
+
+    /// Returns the track index for the queue with pointer `key`, adding a
+    /// track if necessary.
+    fn queue_track(&mut self, key: usize) -> usize {
+        match self.queues.iter().position(|&(k, _)| k == key) {
+            Some(idx) => idx,
+            None => {
+                self.queues.push((key, None));
+                self.queues.len() - 1
+            },
+        }
+    }
+}
+
+
+/// A device timestamp paired with the host time at which it was taken.
+///
+/// Device timestamps (such as those of `EventProfiling`) are in nanoseconds
+/// on the device's own clock. A sync point allows them to be converted to
+/// host `Instant`s, e.g. to line up GPU work with host-side spans.
+///
+/// Device and host clocks may drift apart; take a new sync point
+/// periodically for long-running traces.
+#[derive(Clone, Copy, Debug)]
+pub struct DeviceTimerSync {
+    device_timestamp: u64,
+    host_timestamp: u64,
+    instant: Instant,
+}
+
+impl DeviceTimerSync {
+    /// Returns a new sync point from a device timestamp and the matching
+    /// host timestamp (in the clock used by `clGetHostTimer`) and `Instant`.
+    pub fn new(device_timestamp: u64, host_timestamp: u64, instant: Instant) -> DeviceTimerSync {
+        DeviceTimerSync { device_timestamp, host_timestamp, instant }
+    }
+
+    /// Returns the device timestamp, in nanoseconds.
+    pub fn device_timestamp(&self) -> u64 {
+        self.device_timestamp
+    }
+
+    /// Returns the host timestamp, in nanoseconds, as reported by the
+    /// OpenCL implementation.
+    pub fn host_timestamp(&self) -> u64 {
+        self.host_timestamp
+    }
+
+    /// Returns the host `Instant` corresponding to the device timestamp.
+    pub fn instant(&self) -> Instant {
+        self.instant
+    }
+
+    /// Converts the device timestamp `device_ns` to a host `Instant`.
+    ///
+    /// Returns `None` if the result can not be represented as an `Instant`,
+    /// for example a timestamp from before the host's clock started.
+    pub fn to_instant(&self, device_ns: u64) -> Option<Instant> {
+        if device_ns >= self.device_timestamp {
+            self.instant.checked_add(Duration::from_nanos(device_ns - self.device_timestamp))
+        } else {
+            self.instant.checked_sub(Duration::from_nanos(self.device_timestamp - device_ns))
+        }
+    }
+
+    /// Converts the device timestamp `device_ns` to a host timestamp, in the
+    /// clock used by `clGetHostTimer`.
+    pub fn to_host_timestamp(&self, device_ns: u64) -> u64 {
+        (self.host_timestamp as i64).wrapping_add(
+            device_ns.wrapping_sub(self.device_timestamp) as i64) as u64
+    }
+}
+
+
+/// Execution time statistics for the commands recorded under one name (see
+/// `ProfilingSummary`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KernelStats {
+    name: String,
+    count: u64,
+    total: Duration,
+    min: Duration,
+    max: Duration,
+}
+
+impl KernelStats {
+    /// Returns new, empty statistics for `name`.
+    fn new(name: String) -> KernelStats {
+        KernelStats { name, count: 0, total: Duration::default(), min: Duration::default(),
+            max: Duration::default() }
+    }
+
+    /// Adds a single execution time.
+    fn add(&mut self, duration: Duration) {
+        if self.count == 0 || duration < self.min { self.min = duration; }
+        if duration > self.max { self.max = duration; }
+        self.total += duration;
+        self.count += 1;
+    }
+
+    /// Returns the kernel name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of recorded executions.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the summed execution time.
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    /// Returns the shortest execution time.
+    pub fn min(&self) -> Duration {
+        self.min
+    }
+
+    /// Returns the longest execution time.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Returns the mean execution time.
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            Duration::default()
+        } else {
+            Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64)
+        }
+    }
+}
+
+
+/// Per-kernel execution time statistics collected by a `ProQue` (see
+/// `ProQueBuilder::profiling` and `ProQue::profiling_summary`).
+///
+/// Kernels are sorted by total execution time, longest first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProfilingSummary {
+    kernels: Vec<KernelStats>,
+}
+
+impl ProfilingSummary {
+    /// Returns the statistics for each kernel, longest total time first.
+    pub fn kernels(&self) -> &[KernelStats] {
+        &self.kernels
+    }
+
+    /// Returns the statistics for the kernel named `name`, if any have been
+    /// recorded.
+    pub fn get(&self, name: &str) -> Option<&KernelStats> {
+        self.kernels.iter().find(|k| k.name == name)
+    }
+
+    /// Returns the summed execution time of all kernels.
+    pub fn total(&self) -> Duration {
+        self.kernels.iter().map(|k| k.total).sum()
+    }
+
+    /// Returns true if no kernels have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.kernels.is_empty()
+    }
+}
+
+impl std::fmt::Display for ProfilingSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let width = self.kernels.iter().map(|k| k.name.len()).max().unwrap_or(0).max(6);
+        writeln!(f, "{:<w$} {:>8} {:>12} {:>12} {:>12} {:>12}", "kernel", "count", "total",
+            "mean", "min", "max", w = width)?;
+        for k in &self.kernels {
+            writeln!(f, "{:<w$} {:>8} {:>12} {:>12} {:>12} {:>12}", k.name, k.count,
+                format!("{:?}", k.total), format!("{:?}", k.mean()), format!("{:?}", k.min),
+                format!("{:?}", k.max), w = width)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// Collects kernel execution times for a `ProQue`.
+///
+/// Events are held until complete, then folded into per-name statistics.
+#[derive(Debug, Default)]
+pub(crate) struct KernelProfiler {
+    pending: Vec<(String, Event)>,
+    stats: Vec<KernelStats>,
+}
+
+impl KernelProfiler {
+    /// Records `event` under `name`.
+    pub(crate) fn record(&mut self, name: String, event: Event) -> OclResult<()> {
+        self.pending.push((name, event));
+        if self.pending.len() >= 64 { self.fold(false)?; }
+        Ok(())
+    }
+
+    /// Returns the statistics collected so far, first waiting for any
+    /// pending events.
+    pub(crate) fn summary(&mut self) -> OclResult<ProfilingSummary> {
+        self.fold(true)?;
+        let mut kernels = self.stats.clone();
+        kernels.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+        Ok(ProfilingSummary { kernels })
+    }
+
+    /// Discards all pending events and statistics.
+    pub(crate) fn reset(&mut self) {
+        self.pending.clear();
+        self.stats.clear();
+    }
+
+    /// Folds pending events into the statistics. Incomplete events are kept
+    /// unless `wait` is set.
+    fn fold(&mut self, wait: bool) -> OclResult<()> {
+        let mut pending = Vec::with_capacity(self.pending.len());
+        for (name, event) in self.pending.drain(..) {
+            if wait {
+                event.wait_for()?;
+            } else if !event.is_complete()? {
+                pending.push((name, event));
+                continue;
+            }
+            let duration = event.profiling()?.duration();
+            match self.stats.iter_mut().find(|k| k.name == name) {
+                Some(stats) => stats.add(duration),
+                None => {
+                    let mut stats = KernelStats::new(name);
+                    stats.add(duration);
+                    self.stats.push(stats);
+                },
+            }
+        }
+        self.pending = pending;
+        Ok(())
+    }
+}
+
+
+/// Returns `s` as a quoted and escaped JSON string.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); },
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use super::{json_string, DeviceTimerSync, KernelStats, ProfilingSummary};
+
+    #[test]
+    fn json_string_escapes() {
+        assert_eq!(json_string("add"), "\"add\"");
+        assert_eq!(json_string("a \"b\"\\c\n"), "\"a \\\"b\\\"\\\\c\\n\"");
+        assert_eq!(json_string("\u{1}"), "\"\\u0001\"");
+    }
+
+    #[test]
+    fn device_timer_sync() {
+        let now = Instant::now();
+        let sync = DeviceTimerSync::new(5_000, 1_000_000, now);
+        assert_eq!(sync.to_instant(5_000), Some(now));
+        assert_eq!(sync.to_instant(7_000), Some(now + Duration::from_nanos(2_000)));
+        assert_eq!(sync.to_instant(3_000), now.checked_sub(Duration::from_nanos(2_000)));
+
+        // Far enough before the sync point to precede any `Instant`:
+        let sync = DeviceTimerSync::new(u64::max_value(), 0, now);
+        let earliest = now.checked_sub(Duration::from_nanos(u64::max_value()));
+        assert_eq!(sync.to_instant(0), earliest);
+        assert_eq!(sync.to_host_timestamp(7_000), 1_002_000);
+        assert_eq!(sync.to_host_timestamp(4_000), 999_000);
+    }
+
+    #[test]
+    fn kernel_stats() {
+        let mut stats = KernelStats::new("add".to_owned());
+        assert_eq!(stats.mean(), Duration::default());
+        for &us in &[30, 10, 20] {
+            stats.add(Duration::from_micros(us));
+        }
+        assert_eq!(stats.count(), 3);
+        assert_eq!(stats.total(), Duration::from_micros(60));
+        assert_eq!(stats.min(), Duration::from_micros(10));
+        assert_eq!(stats.max(), Duration::from_micros(30));
+        assert_eq!(stats.mean(), Duration::from_micros(20));
+
+        let summary = ProfilingSummary { kernels: vec![stats] };
+        assert_eq!(summary.total(), Duration::from_micros(60));
+        assert!(summary.get("add").is_some() && summary.get("mul").is_none());
+        let table = summary.to_string();
+        assert!(table.starts_with("kernel"));
+        assert!(table.lines().nth(1).unwrap().starts_with("add"));
+    }
+}
@@ -6,15 +6,27 @@ use std::io::Read;
 use std::fs::File;
 use std::path::PathBuf;
 use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::convert::Into;
+use std::thread;
+use std::sync::{Arc, Mutex, Condvar};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context as TaskContext, Poll, Waker};
+use futures::channel::oneshot;
 
 
 use crate::core::{self, Result as OclCoreResult, Program as ProgramCore, Context as ContextCore,
-    ProgramInfo, ProgramInfoResult, ProgramBuildInfo, ProgramBuildInfoResult};
-#[cfg(feature = "opencl_version_2_1")]
-use core::ClVersions;
-use crate::error::{Result as OclResult, Error as OclError};
-use crate::standard::{Context, Device, DeviceSpecifier};
+    ProgramInfo, ProgramInfoResult, ProgramBuildInfo, ProgramBuildInfoResult, ProgramBuildError,
+    ProgramBuildStatus, Error as OclCoreError, ErrorKind as OclCoreErrorKind};
+use crate::core::ffi::c_void;
+use crate::core::ClVersions;
+use crate::error::{Result as OclResult, Error as OclError, ErrorKind as OclErrorKind};
+use crate::standard::{Context, Device, DeviceSpecifier, Quirks};
+use crate::standard::locality;
+use crate::standard::quirks::BUILD_LOCK;
+use crate::standard::reload::ProgramHandle;
 
 
 /// A program from which kernels can be created from.
@@ -27,7 +39,10 @@ use crate::standard::{Context, Device, DeviceSpecifier};
 /// as you please.
 ///
 #[derive(Clone, Debug)]
-pub struct Program(ProgramCore);
+pub struct Program {
+    obj_core: ProgramCore,
+    handle: Option<ProgramHandle>,
+}
 
 impl Program {
     /// Returns a new `ProgramBuilder`.
@@ -44,7 +59,7 @@ impl Program {
             devices: Option<&[Device]>, cmplr_opts: &CString) -> OclResult<Program> {
         let program = core::create_program_with_source(context, src_strings)?;
         core::build_program(&program, devices, cmplr_opts, None, None)?;
-        Ok(Program(program))
+        Ok(Program { obj_core: program, handle: None })
     }
 
     /// Returns a new program built from pre-created build components and device
@@ -56,7 +71,20 @@ impl Program {
             binaries: &[&[u8]], cmplr_opts: &CString) -> OclResult<Program> {
         let program = core::create_program_with_binary(context, devices, binaries)?;
         core::build_program(&program, Some(devices), cmplr_opts, None, None)?;
-        Ok(Program(program))
+        Ok(Program { obj_core: program, handle: None })
+    }
+
+    /// Returns a new program containing the built-in kernels named in
+    /// `kernel_names` (see `Device::built_in_kernels`).
+    ///
+    /// Built-in programs need not be built; kernels may be created from the
+    /// program directly.
+    pub fn with_built_in_kernels(context: &ContextCore, devices: &[Device],
+            kernel_names: &[&str]) -> OclResult<Program> {
+        let device_versions = context.device_versions()?;
+        let program = core::create_program_with_built_in_kernels(context, devices, kernel_names,
+            Some(&device_versions))?;
+        Ok(Program { obj_core: program, handle: None })
     }
 
     /// Returns a new program built from pre-created build components and device
@@ -68,19 +96,19 @@ impl Program {
         let program = core::create_program_with_il(context, il, Some(&device_versions))?;
         core::build_program(&program, devices, cmplr_opts, None, None)?;
 
-        Ok(Program(program))
+        Ok(Program { obj_core: program, handle: None })
     }
 
     /// Returns a reference to the core pointer wrapper, usable by functions in
     /// the `core` module.
     #[inline]
     pub fn as_core(&self) -> &ProgramCore {
-        &self.0
+        &self.obj_core
     }
 
     /// Returns info about this program.
     pub fn info(&self, info_kind: ProgramInfo) -> OclCoreResult<ProgramInfoResult> {
-        core::get_program_info(&self.0, info_kind)
+        core::get_program_info(&self.obj_core, info_kind)
     }
 
     /// Returns info about this program's build.
@@ -88,7 +116,156 @@ impl Program {
     /// * TODO: Check that device is valid.
     pub fn build_info(&self, device: Device, info_kind: ProgramBuildInfo)
             -> OclCoreResult<ProgramBuildInfoResult> {
-        core::get_program_build_info(&self.0, &device, info_kind)
+        core::get_program_build_info(&self.obj_core, &device, info_kind)
+    }
+
+    /// Returns the reference count of this program.
+    pub fn reference_count(&self) -> OclResult<u32> {
+        match self.info(ProgramInfo::ReferenceCount)? {
+            ProgramInfoResult::ReferenceCount(count) => Ok(count),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the context this program was created within.
+    pub fn context(&self) -> OclResult<Context> {
+        match self.info(ProgramInfo::Context)? {
+            ProgramInfoResult::Context(context) => Ok(Context::from(context)),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the number of devices associated with this program.
+    pub fn num_devices(&self) -> OclResult<u32> {
+        match self.info(ProgramInfo::NumDevices)? {
+            ProgramInfoResult::NumDevices(num) => Ok(num),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the devices associated with this program.
+    pub fn devices(&self) -> OclResult<Vec<Device>> {
+        match self.info(ProgramInfo::Devices)? {
+            ProgramInfoResult::Devices(devices) => Ok(Device::list_from_core(devices)),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the source this program was created with (empty if created
+    /// from binaries or IL).
+    pub fn source(&self) -> OclResult<String> {
+        match self.info(ProgramInfo::Source)? {
+            ProgramInfoResult::Source(src) => Ok(src),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the length, in bytes, of the source this program was created
+    /// with.
+    pub fn source_len(&self) -> OclResult<usize> {
+        self.source().map(|src| src.len())
+    }
+
+    /// Returns the size, in bytes, of the binary for each associated device.
+    pub fn binary_sizes(&self) -> OclResult<Vec<usize>> {
+        match self.info(ProgramInfo::BinarySizes)? {
+            ProgramInfoResult::BinarySizes(sizes) => Ok(sizes),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the number of kernels declared in this program.
+    pub fn num_kernels(&self) -> OclResult<usize> {
+        match self.info(ProgramInfo::NumKernels)? {
+            ProgramInfoResult::NumKernels(num) => Ok(num),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the names of the kernels declared in this program.
+    pub fn kernel_names(&self) -> OclResult<Vec<String>> {
+        match self.info(ProgramInfo::KernelNames)? {
+            ProgramInfoResult::KernelNames(names) => Ok(names.split(';')
+                .filter(|n| !n.is_empty())
+                .map(|n| n.to_owned())
+                .collect()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the total amount of storage, in bytes, used by program
+    /// variables in the global address space for `device`.
+    ///
+    /// Requires OpenCL 2.0 or later.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn global_variable_total_size(&self, device: Device) -> OclResult<usize> {
+        match self.build_info(device, ProgramBuildInfo::BuildGlobalVariableTotalSize)? {
+            ProgramBuildInfoResult::BuildGlobalVariableTotalSize(size) => Ok(size),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Gives this program a kernel registry, if it has none, so that kernels
+    /// created from it are refreshed by `::rebuild`.
+    pub(crate) fn set_reloadable(&mut self) {
+        if self.handle.is_none() {
+            self.handle = Some(ProgramHandle::new());
+        }
+    }
+
+    /// Returns the registry of kernels created from this program if it was
+    /// built with `ProgramBuilder::reloadable`.
+    pub fn handle(&self) -> Option<&ProgramHandle> {
+        self.handle.as_ref()
+    }
+
+    /// Rebuilds this program from `src` for the same context and devices,
+    /// using the compiler options of the original build.
+    ///
+    /// `src` replaces the complete program source, including any defines
+    /// injected by the original `ProgramBuilder`.
+    ///
+    /// If this program is reloadable, every live kernel created from it is
+    /// re-created from the new program with its most recently set argument
+    /// values. Those kernels use their re-created objects when next enqueued
+    /// (see `Kernel::refresh`).
+    ///
+    /// Upon error, neither this program nor its kernels are changed.
+    pub fn rebuild<S: Into<String>>(&mut self, src: S) -> OclResult<()> {
+        let context = self.context()?;
+        let devices = self.devices()?;
+        let cmplr_opts = self.build_options(&devices)?;
+
+        let mut serialize = false;
+        for device in &devices {
+            serialize |= Quirks::for_device(device)?.serialize_builds;
+        }
+        let _build_guard = if serialize {
+            Some(BUILD_LOCK.lock().unwrap_or_else(|e| e.into_inner()))
+        } else {
+            None
+        };
+
+        let src_strings = [CString::new(src.into())?];
+        let program = Program::with_source(&context, &src_strings, Some(&devices), &cmplr_opts)?;
+
+        if let Some(ref handle) = self.handle {
+            handle.reload(&program.obj_core)?;
+        }
+        self.obj_core = program.obj_core;
+        Ok(())
+    }
+
+    /// Returns the compiler options this program was built with for the
+    /// first of `devices`.
+    pub(crate) fn build_options(&self, devices: &[Device]) -> OclResult<CString> {
+        match devices.first() {
+            Some(&device) => match self.build_info(device, ProgramBuildInfo::BuildOptions)? {
+                ProgramBuildInfoResult::BuildOptions(opts) => Ok(CString::new(opts)?),
+                _ => unreachable!(),
+            },
+            None => Err("The program has no associated devices.".into()),
+        }
     }
 
     fn fmt_info(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -108,7 +285,7 @@ impl Program {
 
 impl From<ProgramCore> for Program {
     fn from(core: ProgramCore) -> Program {
-        Program(core)
+        Program { obj_core: core, handle: None }
     }
 }
 
@@ -122,13 +299,13 @@ impl Deref for Program {
     type Target = ProgramCore;
 
     fn deref(&self) -> &ProgramCore {
-        &self.0
+        &self.obj_core
     }
 }
 
 impl DerefMut for Program {
     fn deref_mut(&mut self) -> &mut ProgramCore {
-        &mut self.0
+        &mut self.obj_core
     }
 }
 
@@ -151,6 +328,8 @@ pub enum BuildOpt {
     IncludeDefine { ident: String, val: String },
     IncludeRaw(String),
     IncludeRawEof(String),
+    IncludeFragment { name: String, src: String },
+    Substitute { pattern: String, replacement: String },
 }
 
 impl BuildOpt {
@@ -169,6 +348,164 @@ impl BuildOpt {
             val,
         }
     }
+
+    /// Returns a `BuildOpt::Substitute`.
+    pub fn substitute<P: Into<String>, R: Into<String>>(pattern: P, replacement: R) -> BuildOpt {
+        BuildOpt::Substitute {
+            pattern: pattern.into(),
+            replacement: replacement.into(),
+        }
+    }
+}
+
+
+/// A piece of program source, optionally tagged with a name.
+///
+/// Named fragments are identified by name in build error output. Any type
+/// which can be passed to `ProgramBuilder::src` can be converted into an
+/// unnamed fragment. A `(name, source)` tuple converts into a named one.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SourceFragment {
+    name: Option<String>,
+    src: String,
+}
+
+impl SourceFragment {
+    /// Returns a new fragment named `name`.
+    pub fn new<N: Into<String>, S: Into<String>>(name: N, src: S) -> SourceFragment {
+        SourceFragment { name: Some(name.into()), src: src.into() }
+    }
+
+    /// Returns the fragment's name, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the fragment's source text.
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+}
+
+impl From<String> for SourceFragment {
+    fn from(src: String) -> SourceFragment {
+        SourceFragment { name: None, src }
+    }
+}
+
+impl<'a> From<&'a str> for SourceFragment {
+    fn from(src: &'a str) -> SourceFragment {
+        SourceFragment { name: None, src: src.to_owned() }
+    }
+}
+
+impl<'a> From<&'a String> for SourceFragment {
+    fn from(src: &'a String) -> SourceFragment {
+        SourceFragment { name: None, src: src.clone() }
+    }
+}
+
+impl<N: Into<String>, S: Into<String>> From<(N, S)> for SourceFragment {
+    fn from((name, src): (N, S)) -> SourceFragment {
+        SourceFragment::new(name, src)
+    }
+}
+
+
+/// The line range occupied by each fragment of a program's combined
+/// source, used to trace build errors back to their origin.
+#[derive(Clone, Debug)]
+struct SourceMap {
+    // (fragment name, first line, line count)
+    fragments: Vec<(String, usize, usize)>,
+}
+
+impl SourceMap {
+    fn new(src_strings: &[(String, CString)]) -> SourceMap {
+        let mut fragments = Vec::with_capacity(src_strings.len());
+        let mut line = 1;
+
+        for (name, src) in src_strings {
+            let bytes = src.as_bytes();
+            let newlines = bytes.iter().filter(|&&b| b == b'\n').count();
+            // Without a trailing newline, the last line is shared with the
+            // start of the next fragment:
+            let lines = newlines + (bytes.last() != Some(&b'\n')) as usize;
+
+            if bytes.iter().any(|b| !b.is_ascii_whitespace()) {
+                fragments.push((name.clone(), line, lines));
+            }
+            line += newlines;
+        }
+
+        SourceMap { fragments }
+    }
+
+    /// Returns the name of the fragment containing `line` of the combined
+    /// source and the corresponding line within that fragment.
+    fn locate(&self, line: usize) -> Option<(&str, usize)> {
+        self.fragments.iter().rev()
+            .find(|&&(_, first, count)| line >= first && line < first + count)
+            .map(|&(ref name, first, _)| (name.as_str(), line - first + 1))
+    }
+
+    /// Annotates each line of a build log which refers to a source line
+    /// (e.g. `<source>:12:5: error: ...`) with the originating fragment.
+    fn annotate(&self, log: &str) -> String {
+        let mut annotated = String::with_capacity(log.len() * 2);
+
+        for log_line in log.lines() {
+            annotated.push_str(log_line);
+
+            let parts: Vec<&str> = log_line.split(':').collect();
+            let src_line = parts.iter().skip(1).take(parts.len().saturating_sub(2))
+                .find(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|p| p.parse::<usize>().ok());
+
+            if let Some((name, line)) = src_line.and_then(|l| self.locate(l)) {
+                annotated.push_str(&format!("  [fragment '{}', line {}]", name, line));
+            }
+            annotated.push('\n');
+        }
+
+        annotated.push_str("\nSource fragments:\n");
+        for &(ref name, first, count) in &self.fragments {
+            annotated.push_str(&format!("    lines {}-{}: '{}'\n", first, first + count - 1,
+                name));
+        }
+        annotated
+    }
+
+    /// Replaces the build log contained within a build error with an
+    /// annotated copy. Other errors are returned unchanged.
+    fn annotate_err(&self, err: OclError) -> OclError {
+        if let OclErrorKind::OclCore(ref core_err) = *err.kind() {
+            if let OclCoreErrorKind::ProgramBuild(ProgramBuildError::BuildLog(ref log)) =
+                    *core_err.kind() {
+                return OclCoreError::from(ProgramBuildError::BuildLog(self.annotate(log)))
+                    .into();
+            }
+        }
+        err
+    }
+
+    /// Annotates `err` using `source_map`, if any.
+    fn annotate_opt(source_map: Option<&SourceMap>, err: OclError) -> OclError {
+        match source_map {
+            Some(source_map) => source_map.annotate_err(err),
+            None => err,
+        }
+    }
+}
+
+
+/// A program created, but not necessarily built, by `ProgramBuilder`.
+enum CreatedProgram {
+    /// A program yet to be built, along with the source map of a program
+    /// created from source.
+    Unbuilt(ProgramCore, Option<SourceMap>),
+    /// A program of built-in kernels, which need not be built.
+    BuiltIn(Program),
 }
 
 
@@ -180,20 +517,41 @@ enum CreateWith<'b> {
     Source(Vec<PathBuf>),
     Binaries(&'b[&'b [u8]]),
     Il(&'b [u8]),
+    BuiltInKernels(Vec<String>),
 }
 
 
-/// A builder for `Program`.
+/// A scalar type which a SPIR-V specialization constant may be set to with
+/// `ProgramBuilder::spec_constant`.
+///
+/// Implemented for `bool` and the primitive integer and floating point types.
+///
+/// ### Safety
 ///
-// * [SOMEDAY TODO]: Keep track of line number range for each string and print
-// out during build failure.
-//
+/// Every byte of a value must be initialized (the type may have no padding),
+/// as the value is passed to the implementation as raw bytes.
+#[cfg(feature = "opencl_version_2_2")]
+pub unsafe trait SpecConstantPrm: Copy {}
+
+#[cfg(feature = "opencl_version_2_2")]
+macro_rules! impl_spec_constant_prm {
+    ( $( $ty:ty ),* ) => { $( unsafe impl SpecConstantPrm for $ty {} )* };
+}
+
+#[cfg(feature = "opencl_version_2_2")]
+impl_spec_constant_prm!(bool, i8, u8, i16, u16, i32, u32, i64, u64, f32, f64);
+
+
+/// A builder for `Program`.
 #[must_use = "builders do nothing unless '::build' is called"]
 #[derive(Clone, Debug)]
 pub struct ProgramBuilder<'b> {
     options: Vec<BuildOpt>,
     with: CreateWith<'b>,
     device_spec: Option<DeviceSpecifier>,
+    reloadable: bool,
+    #[cfg(feature = "opencl_version_2_2")]
+    spec_constants: Vec<(u32, Vec<u8>)>,
 }
 
 impl<'b> ProgramBuilder<'b> {
@@ -203,6 +561,9 @@ impl<'b> ProgramBuilder<'b> {
             options: Vec::with_capacity(64),
             with: CreateWith::None,
             device_spec: None,
+            reloadable: false,
+            #[cfg(feature = "opencl_version_2_2")]
+            spec_constants: Vec::new(),
         }
     }
 
@@ -230,6 +591,37 @@ impl<'b> ProgramBuilder<'b> {
         self
     }
 
+    /// Injects a `#define {name} {val}` directive at the top of the program
+    /// source.
+    ///
+    /// Unlike `::cmplr_def`, `val` may be of any type which can be formatted
+    /// as a string (including floats and type names).
+    ///
+    /// ## Example
+    ///
+    /// `...define("WIDTH", width)...`
+    ///
+    pub fn define<'a, S, V>(&'a mut self, name: S, val: V) -> &'a mut ProgramBuilder<'b>
+            where S: Into<String>, V: ToString {
+        self.options.push(BuildOpt::include_def(name, val.to_string()));
+        self
+    }
+
+    /// Replaces every occurrence of `pattern` with `replacement` throughout
+    /// the program source (including source files) before compilation.
+    ///
+    /// Substitutions are applied in the order they are specified.
+    ///
+    /// ## Example
+    ///
+    /// `...substitute("{{TYPE}}", "float4")...`
+    ///
+    pub fn substitute<'a, P, R>(&'a mut self, pattern: P, replacement: R) -> &'a mut ProgramBuilder<'b>
+            where P: Into<String>, R: Into<String> {
+        self.options.push(BuildOpt::substitute(pattern, replacement));
+        self
+    }
+
     /// Pushes pre-created build option to the list of options.
     ///
     /// If either `::il` or `::binaries` are used and raw source is added, it
@@ -264,25 +656,42 @@ impl<'b> ProgramBuilder<'b> {
         self
     }
 
-    /// Adds raw text to the program source.
+    /// Adds raw text or a named fragment to the program source.
     //
     // TODO: Deprecate
     // #[deprecated(since = "0.19.2", note = "Use `::source` instead.")]
-    pub fn src<'a, S: Into<String>>(&'a mut self, src: S) -> &'a mut ProgramBuilder<'b> {
+    pub fn src<'a, S: Into<SourceFragment>>(&'a mut self, src: S) -> &'a mut ProgramBuilder<'b> {
         self.source(src)
     }
 
-    /// Adds raw text to the program source.
+    /// Adds raw text or a named fragment to the program source.
+    ///
+    /// A named fragment whose name and text are both identical to one added
+    /// previously is ignored. Unnamed strings are always included, as are
+    /// fragments which share text but not a name. Lines referenced in the
+    /// build log of a failed build are annotated with the name of the
+    /// fragment they came from (unnamed fragments are numbered in the order
+    /// they were added).
+    ///
+    /// ## Example
+    ///
+    /// `...source(("reduce", reduce_src)).source(("scan", scan_src))...`
+    ///
     //
     // [TODO]: Possibly accept Into<CString>
-    pub fn source<'a, S: Into<String>>(&'a mut self, src: S) -> &'a mut ProgramBuilder<'b> {
+    pub fn source<'a, S: Into<SourceFragment>>(&'a mut self, src: S) -> &'a mut ProgramBuilder<'b> {
+        let option = match src.into() {
+            SourceFragment { name: Some(name), src } => BuildOpt::IncludeFragment { name, src },
+            SourceFragment { name: None, src } => BuildOpt::IncludeRawEof(src),
+        };
+
         match self.with {
             CreateWith::None => {
                 self.with = CreateWith::Source(Vec::with_capacity(8));
-                self.options.push(BuildOpt::IncludeRawEof(src.into()));
+                self.options.push(option);
             }
             CreateWith::Source(_) => {
-                self.options.push(BuildOpt::IncludeRawEof(src.into()));
+                self.options.push(option);
             }
             _ => panic!("Source may not be used with binaries or il."),
         }
@@ -324,9 +733,54 @@ impl<'b> ProgramBuilder<'b> {
         self
     }
 
+    /// Specifies the built-in kernels, provided by the device, which the
+    /// program will contain (see `Device::built_in_kernels`).
+    ///
+    /// Built-in kernels may not be combined with source, binaries, or IL.
+    pub fn built_in_kernels<'a>(&'a mut self, kernel_names: &[&str])
+            -> &'a mut ProgramBuilder<'b> {
+        match self.with {
+            CreateWith::None => self.with = CreateWith::BuiltInKernels(
+                kernel_names.iter().map(|&name| name.to_owned()).collect()),
+            CreateWith::BuiltInKernels(_) => panic!("Built-in kernels have already been \
+                specified."),
+            _ => panic!("Built-in kernels may not be used with source, binaries, or il."),
+        }
+        self
+    }
+
+    /// Sets the value of the specialization constant with the id `spec_id`
+    /// in the SPIR-V IL specified with `::il`.
+    ///
+    /// `value` must be of the same size as the constant declared in the IL
+    /// (a `bool` constant is one byte). Setting a constant more than once
+    /// overrides the previous value.
+    ///
+    /// ## Example
+    ///
+    /// `...il(&spirv).spec_constant(0, 64u32).spec_constant(1, true)...`
+    ///
+    #[cfg(feature = "opencl_version_2_2")]
+    pub fn spec_constant<'a, T: SpecConstantPrm>(&'a mut self, spec_id: u32, value: T)
+            -> &'a mut ProgramBuilder<'b> {
+        // `SpecConstantPrm` types have no padding:
+        let bytes = unsafe {
+            std::slice::from_raw_parts(&value as *const T as *const u8, std::mem::size_of::<T>())
+        }.to_vec();
+        self.spec_constants.retain(|&(id, _)| id != spec_id);
+        self.spec_constants.push((spec_id, bytes));
+        self
+    }
+
     /// Specifies a list of devices to build this program on. The devices must
     /// be associated with the context passed to `::build` later on.
     ///
+    /// When not specified, the program is built for every device in the
+    /// context. Building for only the devices which will run the program
+    /// avoids failures on contexts where some devices lack a feature (such
+    /// as an extension) which the program source requires. Duplicate devices
+    /// are ignored.
+    ///
     /// Devices may be specified in any number of ways including simply
     /// passing a device or slice of devices. See the [`impl
     /// From`][device_specifier_from] section of
@@ -347,6 +801,17 @@ impl<'b> ProgramBuilder<'b> {
         self
     }
 
+    /// Makes the program reloadable.
+    ///
+    /// Kernels created from a reloadable program are re-created, with their
+    /// arguments intact, whenever the program is rebuilt using
+    /// `Program::rebuild`. Each argument value set on such a kernel is
+    /// copied and retained for this purpose.
+    pub fn reloadable<'a>(&'a mut self) -> &'a mut ProgramBuilder<'b> {
+        self.reloadable = true;
+        self
+    }
+
     /// Returns the devices specified to be associated the program.
     pub fn get_device_spec(&self) -> &Option<DeviceSpecifier> {
         &self.device_spec
@@ -406,23 +871,50 @@ impl<'b> ProgramBuilder<'b> {
     }
 
     /// Parses `self.options` for options intended for inclusion at the end of
-    /// the final program source and returns them as a list of strings.
-    fn get_includes_eof(&self) -> OclResult<Vec<CString>> {
+    /// the final program source and returns them as a list of named strings,
+    /// skipping repeated named fragments.
+    fn get_includes_eof(&self) -> OclResult<Vec<(String, CString)>> {
         let mut strings = Vec::with_capacity(64);
-        strings.push(CString::new("\n".as_bytes())?);
+        let mut seen: HashSet<(&str, &str)> = HashSet::with_capacity(64);
+        let mut unnamed_count = 0;
+        strings.push((String::new(), CString::new("\n".as_bytes())?));
 
         for option in &self.options {
-            if let BuildOpt::IncludeRawEof(ref text) = *option {
-                strings.push(CString::new(text.clone().into_bytes())?);
-            }
+            let (name, text) = match *option {
+                BuildOpt::IncludeRawEof(ref text) => {
+                    unnamed_count += 1;
+                    (format!("<source #{}>", unnamed_count), text)
+                },
+                BuildOpt::IncludeFragment { ref name, ref src } => {
+                    if !seen.insert((name.as_str(), src.as_str())) {
+                        continue;
+                    }
+                    (name.clone(), src)
+                },
+                _ => continue,
+            };
+
+            strings.push((name, CString::new(text.clone().into_bytes())?));
         }
 
         strings.shrink_to_fit();
         Ok(strings)
     }
 
+    /// Applies all `BuildOpt::Substitute` options to `src`.
+    fn substitute_all(&self, src: String) -> String {
+        self.options.iter().fold(src, |src, option| match *option {
+            BuildOpt::Substitute { ref pattern, ref replacement } => {
+                src.replace(pattern.as_str(), replacement)
+            },
+            _ => src,
+        })
+    }
+
     /// Returns the final program source code as a list of strings.
     ///
+    /// Any substitutions (see `::substitute`) are applied to each string.
+    ///
     /// ### Order of Inclusion
     ///
     /// 1. Macro definitions and code strings specified by a
@@ -432,10 +924,18 @@ impl<'b> ProgramBuilder<'b> {
     ///   `BuildOpt::IncludeRawEof` via `::bo`
     ///
     pub fn get_src_strings(&self) -> OclResult<Vec<CString>> {
-        let mut src_strings: Vec<CString> = Vec::with_capacity(64);
+        Ok(self.get_named_src_strings()?.into_iter().map(|(_, src)| src).collect())
+    }
+
+    /// Returns the final program source code as a list of strings, each
+    /// paired with the name of the fragment, file, or build option it came
+    /// from.
+    fn get_named_src_strings(&self) -> OclResult<Vec<(String, CString)>> {
+        let mut src_strings: Vec<(String, CString)> = Vec::with_capacity(64);
         let mut src_file_history: HashSet<PathBuf> = HashSet::with_capacity(64);
 
-        src_strings.extend_from_slice(&self.get_includes()?);
+        src_strings.extend(self.get_includes()?.into_iter()
+            .map(|src| ("<build options>".to_owned(), src)));
 
         let src_paths = match self.with {
             CreateWith::Source(ref paths) => paths,
@@ -452,95 +952,514 @@ impl<'b> ProgramBuilder<'b> {
 
             src_file_handle.read_to_end(&mut src_bytes)?;
             src_bytes.shrink_to_fit();
-            src_strings.push(CString::new(src_bytes)?);
+            src_strings.push((src_path.display().to_string(), CString::new(src_bytes)?));
+        }
+
+        src_strings.extend(self.get_includes_eof()?);
+
+        let has_substitutions = self.options.iter()
+            .any(|o| matches!(*o, BuildOpt::Substitute { .. }));
+
+        if has_substitutions {
+            src_strings = src_strings.into_iter()
+                .map(|(name, cs)| {
+                    let src = cs.into_string().map_err(|err| OclError::from(err.to_string()))?;
+                    Ok((name, CString::new(self.substitute_all(src))?))
+                })
+                .collect::<OclResult<Vec<_>>>()?;
         }
 
-        src_strings.extend_from_slice(&self.get_includes_eof()?);
         src_strings.shrink_to_fit();
         Ok(src_strings)
     }
 
-    /// Returns a newly built Program.
-    //
-    // * TODO: If the context is associated with more than one device,
-    // check that at least one of those devices has been specified. An empty
-    // device list will cause an `OpenCL` error in that case.
-    // * TODO: Check for duplicate devices in the final device list.
-    // * TODO: Consider moving context to its own method.
-    #[cfg(not(feature = "opencl_version_2_1"))]
-    pub fn build(&self, context: &Context) -> OclResult<Program> {
-        let device_list = match self.device_spec {
-            Some(ref ds) => ds.to_device_list(context.platform()?)?,
-            None => context.devices(),
-        };
+    /// Returns a key identifying the program this builder would produce.
+    ///
+    /// The key is a hash of the final, preprocessed source (with all
+    /// defines and substitutions applied) and the compiler options, so any
+    /// change to either yields a different key. Keys are only stable within
+    /// a single build of an application.
+    pub fn cache_key(&self) -> OclResult<u64> {
+        let mut hasher = DefaultHasher::new();
 
         match self.with {
-            CreateWith::Il(_) => {
-                return Err("ocl::ProgramBuilder::build: Unreachable section (IL).".into());
-            },
+            CreateWith::Source(_) => self.get_src_strings()?.hash(&mut hasher),
+            CreateWith::Binaries(bins) => bins.hash(&mut hasher),
+            CreateWith::Il(il) => il.hash(&mut hasher),
+            CreateWith::BuiltInKernels(ref names) => names.hash(&mut hasher),
+            CreateWith::None => (),
+        }
+
+        self.get_compiler_options()?.hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+
+    /// Creates the program without building it.
+    ///
+    /// Specialization constants, if any, are set on programs created from
+    /// IL. Used by both `::build` and `::build_future`.
+    fn create(&self, context: &Context, device_list: &[Device]) -> OclResult<CreatedProgram> {
+        let program = match self.with {
             CreateWith::Source(_) => {
-                Program::with_source(
-                    context,
-                    &self.get_src_strings()?,
-                    Some(&device_list[..]),
-                    &self.get_compiler_options()?,
-                ).map_err(OclError::from)
+                let named_src_strings = self.get_named_src_strings()?;
+                let src_strings: Vec<CString> = named_src_strings.iter()
+                    .map(|(_, src)| src.clone())
+                    .collect();
+                let program = core::create_program_with_source(context, &src_strings)?;
+                return Ok(CreatedProgram::Unbuilt(program,
+                    Some(SourceMap::new(&named_src_strings))));
             },
             CreateWith::Binaries(bins) => {
-                Program::with_binary(
-                    context,
-                    &device_list[..],
-                    bins,
-                    &self.get_compiler_options()?,
-                )
+                core::create_program_with_binary(context, device_list, bins)?
+            },
+            #[cfg(feature = "opencl_version_2_1")]
+            CreateWith::Il(il) => {
+                let device_versions = context.device_versions()?;
+                let program = core::create_program_with_il(context, il,
+                    Some(&device_versions))?;
+
+                #[cfg(feature = "opencl_version_2_2")]
+                for &(spec_id, ref value) in &self.spec_constants {
+                    core::set_program_specialization_constant(&program, spec_id, &value[..],
+                        Some(&device_versions))?;
+                }
+                program
+            },
+            #[cfg(not(feature = "opencl_version_2_1"))]
+            CreateWith::Il(_) => {
+                return Err("ocl::ProgramBuilder::create: Unreachable section (IL).".into());
+            },
+            CreateWith::BuiltInKernels(ref names) => {
+                let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+                let program = Program::with_built_in_kernels(context, device_list, &names)?;
+                return Ok(CreatedProgram::BuiltIn(program));
             },
             CreateWith::None => return Err("Unable to build program: no source, binary, \
                 or IL has been specified".into()),
+        };
+        Ok(CreatedProgram::Unbuilt(program, None))
+    }
+
+    /// Creates the program and starts building it, returning a future which
+    /// resolves to it once the driver reports that the build has completed.
+    ///
+    /// Unlike `::build_async`, no thread is used: the build runs in the
+    /// background within the driver and completion is signaled by its
+    /// build callback. Use `FutureBuild::progress` to check the build status
+    /// for each device in the meantime.
+    ///
+    /// Devices which require builds to be serialized (see
+    /// `Quirks::serialize_builds`) are built before this function returns.
+    pub fn build_future(&self, context: &Context) -> OclResult<FutureBuild> {
+        let device_list = self.resolve_devices(context)?;
+
+        let mut serialize = false;
+        for device in &device_list {
+            serialize |= Quirks::for_device(device)?.serialize_builds;
+        }
+        if serialize {
+            let program = self.build(context)?;
+            return Ok(FutureBuild::built(program, device_list));
+        }
+
+        let (program, source_map) = match self.create(context, &device_list)? {
+            CreatedProgram::Unbuilt(program, source_map) => (program, source_map),
+            CreatedProgram::BuiltIn(program) => {
+                return Ok(FutureBuild::built(self.attach_handle(program), device_list));
+            },
+        };
+
+        let notify = Arc::new(BuildNotify::default());
+        let user_data = Arc::into_raw(notify.clone()) as *mut c_void;
+
+        // If the build fails to start, the callback may or may not have been
+        // called. The reference passed to it is leaked rather than risk
+        // freeing it twice.
+        unsafe {
+            core::build_program_notify(&program, Some(&device_list[..]),
+                &self.get_compiler_options()?, _complete_build, user_data)
+        }.map_err(|err| SourceMap::annotate_opt(source_map.as_ref(), err.into()))?;
+
+        Ok(FutureBuild {
+            program: Some(self.attach_handle(Program::from(program))),
+            devices: device_list,
+            notify: Some(notify),
+            source_map,
+        })
+    }
+
+    /// Gives `program` a kernel registry if this builder is reloadable.
+    pub(crate) fn attach_handle(&self, mut program: Program) -> Program {
+        if self.reloadable {
+            program.set_reloadable();
+        }
+        program
+    }
+
+    /// Returns the list of devices to build for: those specified with
+    /// `::devices` (less duplicates), or all devices in `context`.
+    fn resolve_devices(&self, context: &Context) -> OclResult<Vec<Device>> {
+        let context_devices = context.devices();
+
+        let specified = match self.device_spec {
+            Some(ref ds) => ds.to_device_list(context.platform()?)?,
+            None => return Ok(context_devices),
+        };
+
+        let mut device_list: Vec<Device> = Vec::with_capacity(specified.len());
+        for device in specified {
+            if !context_devices.contains(&device) {
+                return Err(format!("ProgramBuilder::build: The device '{}' is not associated \
+                    with the context.", device.name()?).into());
+            }
+            if !device_list.contains(&device) {
+                device_list.push(device);
+            }
         }
+
+        if device_list.is_empty() {
+            return Err("ProgramBuilder::build: The specified device list is empty.".into());
+        }
+        Ok(device_list)
     }
 
     /// Returns a newly built Program.
     //
-    // * TODO: If the context is associated with more than one device,
-    // check that at least one of those devices has been specified. An empty
-    // device list will cause an `OpenCL` error in that case.
-    //
-    // * TODO: Check for duplicate devices in the final device list.
-    #[cfg(feature = "opencl_version_2_1")]
+    // * TODO: Consider moving context to its own method.
     pub fn build(&self, context: &Context) -> OclResult<Program> {
-        let device_list = match self.device_spec {
-            Some(ref ds) => ds.to_device_list(context.platform()?)?,
-            None => context.devices().to_owned(),
+        let device_list = self.resolve_devices(context)?;
+
+        let mut serialize = false;
+        for device in &device_list {
+            serialize |= Quirks::for_device(device)?.serialize_builds;
+        }
+        let _build_guard = if serialize {
+            Some(BUILD_LOCK.lock().unwrap_or_else(|e| e.into_inner()))
+        } else {
+            None
         };
 
-        match self.with {
-            CreateWith::Il(il) => {
-                Program::with_il(
-                    il,
-                    Some(&device_list[..]),
-                    &self.get_compiler_options()?,
-                    context
-                )
-            },
-            CreateWith::Source(_) => {
-                Program::with_source(
-                    context,
-                    &self.get_src_strings()?,
-                    Some(&device_list[..]),
-                    &self.get_compiler_options()?,
-                )
-            },
-            CreateWith::Binaries(bins) => {
-                Program::with_binary(
-                    context,
-                    &device_list[..],
-                    bins,
-                    &self.get_compiler_options()?,
-                )
+        let program = match self.create(context, &device_list)? {
+            CreatedProgram::Unbuilt(program, source_map) => {
+                core::build_program(&program, Some(&device_list[..]),
+                    &self.get_compiler_options()?, None, None)
+                    .map_err(|err| SourceMap::annotate_opt(source_map.as_ref(), err.into()))?;
+                Program::from(program)
             },
-            CreateWith::None => Err("Unable to build program: no source, binary, \
-                or IL has been specified".into()),
+            CreatedProgram::BuiltIn(program) => program,
+        };
+
+        Ok(self.attach_handle(program))
+    }
+}
+
+impl ProgramBuilder<'static> {
+    /// Builds the program on a dedicated thread, returning a future which
+    /// resolves to it once built.
+    ///
+    /// `clBuildProgram` blocks the calling thread for the duration of the
+    /// build (which can be lengthy), so building this way keeps executor
+    /// threads free in the meantime. See `::build`.
+    pub fn build_async(&self, context: &Context) -> FutureProgram {
+        let (tx, rx) = oneshot::channel();
+        let builder = self.clone();
+        let context = context.clone();
+
+        // If the thread can not be spawned, `tx` is dropped and the future
+        // resolves to an error.
+        thread::Builder::new().name("ocl-program-build".to_owned()).spawn(move || {
+            let device = builder.resolve_devices(&context).ok().and_then(|d| d.first().cloned());
+            if let Some(node) = device.and_then(|d| locality::internal_thread_node(&d)) {
+                locality::pin_current_thread(node).ok();
+            }
+            let _ = tx.send(builder.build(&context));
+        }).ok();
+
+        FutureProgram { rx }
+    }
+}
+
+
+/// The state shared between a `FutureBuild` and the driver's build callback.
+#[derive(Debug, Default)]
+struct BuildNotify {
+    // (is complete, waker of the task awaiting completion)
+    state: Mutex<(bool, Option<Waker>)>,
+    cvar: Condvar,
+}
+
+impl BuildNotify {
+    /// Returns true if the build has completed, otherwise arranges for
+    /// `waker` to be woken once it does.
+    #[cfg(not(feature = "async_block"))]
+    fn poll_complete(&self, waker: &Waker) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if !state.0 {
+            state.1 = Some(waker.clone());
+        }
+        state.0
+    }
+
+    /// Blocks until the build has completed.
+    #[cfg(feature = "async_block")]
+    fn wait(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        while !state.0 {
+            state = self.cvar.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+
+extern "C" fn _complete_build(_program: *mut c_void, user_data: *mut c_void) {
+    core::guard_callback("_complete_build", || {
+        let notify = unsafe { Arc::from_raw(user_data as *const BuildNotify) };
+        let waker = {
+            let mut state = notify.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.0 = true;
+            state.1.take()
+        };
+        notify.cvar.notify_all();
+        // Waking only schedules the task; it does not call into OpenCL.
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    })
+}
+
+
+/// A future which resolves to a `Program` once the driver reports that its
+/// build has completed.
+///
+/// Resolves to an error containing the build log if the build failed for
+/// any device.
+///
+/// Returned by `ProgramBuilder::build_future`.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct FutureBuild {
+    program: Option<Program>,
+    devices: Vec<Device>,
+    notify: Option<Arc<BuildNotify>>,
+    source_map: Option<SourceMap>,
+}
+
+impl FutureBuild {
+    /// Returns a `FutureBuild` for a program which has already been built.
+    fn built(program: Program, devices: Vec<Device>) -> FutureBuild {
+        FutureBuild { program: Some(program), devices, notify: None, source_map: None }
+    }
+
+    /// Returns the devices the program is being built for.
+    #[inline]
+    pub fn devices(&self) -> &[Device] {
+        &self.devices
+    }
+
+    /// Returns the current build status for each device, without blocking.
+    pub fn progress(&self) -> OclResult<Vec<(Device, ProgramBuildStatus)>> {
+        let program = match self.program {
+            Some(ref program) => program,
+            None => return Err("FutureBuild::progress: Already resolved.".into()),
+        };
+        Ok(self.devices.iter().cloned().zip(self.progress_of(program)?).collect())
+    }
+
+    /// Blocks the current thread until the build has completed.
+    pub fn wait(self) -> OclResult<Program> {
+        futures::executor::block_on(self)
+    }
+
+    /// Returns the program, or the build log if the build failed for any
+    /// device.
+    fn resolve(&mut self) -> OclResult<Program> {
+        let program = self.program.take()
+            .ok_or_else(|| OclError::from("FutureBuild::poll: Already resolved."))?;
+
+        if self.notify.is_some() {
+            let build_failed = self.progress_of(&program)?.iter()
+                .any(|&status| status != ProgramBuildStatus::Success);
+            if build_failed {
+                let err = match core::program_build_err(program.as_core(), &self.devices) {
+                    Err(err) => OclError::from(OclCoreError::from(err)),
+                    Ok(()) => "FutureBuild::poll: The build failed without a build log.".into(),
+                };
+                return Err(match self.source_map {
+                    Some(ref source_map) => source_map.annotate_err(err),
+                    None => err,
+                });
+            }
+        }
+        Ok(program)
+    }
+
+    /// Returns the build status of `program` for each device.
+    fn progress_of(&self, program: &Program) -> OclResult<Vec<ProgramBuildStatus>> {
+        self.devices.iter().map(|&device| {
+            match program.build_info(device, ProgramBuildInfo::BuildStatus)? {
+                ProgramBuildInfoResult::BuildStatus(status) => Ok(status),
+                _ => unreachable!(),
+            }
+        }).collect()
+    }
+}
+
+#[cfg(not(feature = "async_block"))]
+impl Future for FutureBuild {
+    type Output = OclResult<Program>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let is_complete = match this.notify {
+            Some(ref notify) => notify.poll_complete(cx.waker()),
+            None => true,
+        };
+        if is_complete { Poll::Ready(this.resolve()) } else { Poll::Pending }
+    }
+}
+
+/// Blocking implementation.
+#[cfg(feature = "async_block")]
+impl Future for FutureBuild {
+    type Output = OclResult<Program>;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Some(ref notify) = this.notify {
+            notify.wait();
         }
+        Poll::Ready(this.resolve())
+    }
+}
+
+impl Unpin for FutureBuild {}
+
+
+/// A future which resolves to a `Program` once it has been built.
+///
+/// Returned by `ProgramBuilder::build_async`.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct FutureProgram {
+    rx: oneshot::Receiver<OclResult<Program>>,
+}
+
+impl FutureProgram {
+    /// Blocks the current thread until the program has been built.
+    pub fn wait(self) -> OclResult<Program> {
+        futures::executor::block_on(self)
     }
 }
 
+impl Future for FutureProgram {
+    type Output = OclResult<Program>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.rx).poll(cx).map(|res| res.unwrap_or_else(|_| {
+            Err("FutureProgram::poll: The build thread exited without a result.".into())
+        }))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use super::{ProgramBuilder, SourceMap};
+
+    #[test]
+    fn preprocess() -> crate::Result<()> {
+        let src = r#"
+            __kernel void scale({{TYPE}}* buffer) {
+                buffer[get_global_id(0)] *= SCALE;
+            }
+        "#;
+
+        let mut builder = ProgramBuilder::new();
+        builder.src(src).define("SCALE", 2.5f32).substitute("{{TYPE}}", "__global float4");
+
+        let combined = builder.get_src_strings()?.into_iter()
+            .map(|cs| cs.into_string().unwrap())
+            .collect::<String>();
+
+        assert!(combined.contains("#define SCALE  2.5"));
+        assert!(combined.contains("scale(__global float4* buffer)"));
+        assert!(!combined.contains("{{TYPE}}"));
+
+        let key = builder.cache_key()?;
+        assert_eq!(key, builder.cache_key()?);
+
+        builder.substitute("float4", "float8");
+        assert_ne!(key, builder.cache_key()?);
+        Ok(())
+    }
+
+    #[test]
+    fn fragments() -> crate::Result<()> {
+        let mut builder = ProgramBuilder::new();
+        builder.src(("helpers", "float twice(float x) { return x * 2.0f; }\n"))
+            .src("__kernel void a() {}\n")
+            .src(("helpers", "float twice(float x) { return x * 2.0f; }\n"))
+            .src("__kernel void a() {}\n")
+            .src(("other", "float twice(float x) { return x * 2.0f; }\n"));
+
+        let combined = builder.get_src_strings()?.into_iter()
+            .map(|cs| cs.into_string().unwrap())
+            .collect::<String>();
+        // Only the repeated named fragment is dropped:
+        assert_eq!(combined.matches("float twice").count(), 2);
+        assert_eq!(combined.matches("__kernel void a()").count(), 2);
+
+        let map = SourceMap::new(&[
+            ("one".to_owned(), CString::new("a\nb\n")?),
+            ("two".to_owned(), CString::new("c\nd\ne\n")?),
+        ]);
+        assert_eq!(map.locate(2), Some(("one", 2)));
+        assert_eq!(map.locate(4), Some(("two", 2)));
+        assert_eq!(map.locate(6), None);
+
+        let log = map.annotate("<source>:5:3: error: use of undeclared identifier 'x'");
+        assert!(log.contains("[fragment 'two', line 3]"));
+        Ok(())
+    }
+
+    #[test]
+    fn fragments_without_trailing_newline() -> crate::Result<()> {
+        // Combined: "a\nb" + "c\nd\n" + "e\nf" == "a\nbc\nd\ne\nf".
+        let map = SourceMap::new(&[
+            ("one".to_owned(), CString::new("a\nb")?),
+            ("two".to_owned(), CString::new("c\nd\n")?),
+            ("three".to_owned(), CString::new("e\nf")?),
+        ]);
+        assert_eq!(map.locate(1), Some(("one", 1)));
+        // Shared by the end of 'one' and the start of 'two':
+        assert_eq!(map.locate(2), Some(("two", 1)));
+        assert_eq!(map.locate(3), Some(("two", 2)));
+        assert_eq!(map.locate(4), Some(("three", 1)));
+        assert_eq!(map.locate(5), Some(("three", 2)));
+        assert_eq!(map.locate(6), None);
+
+        let log = map.annotate("<source>:5:1: error: expected ';'");
+        assert!(log.contains("[fragment 'three', line 2]"));
+        assert!(log.contains("lines 1-2: 'one'"));
+        assert!(log.contains("lines 4-5: 'three'"));
+        Ok(())
+    }
+
+    #[cfg(feature = "opencl_version_2_2")]
+    #[test]
+    fn spec_constants() {
+        let mut builder = ProgramBuilder::new();
+        builder.spec_constant(0, 64u32).spec_constant(1, true).spec_constant(2, 0.5f64);
+        assert_eq!(builder.spec_constants, vec![
+            (0, 64u32.to_ne_bytes().to_vec()),
+            (1, vec![1u8]),
+            (2, 0.5f64.to_ne_bytes().to_vec()),
+        ]);
+
+        // Setting a constant again replaces its value:
+        builder.spec_constant(0, 7u16);
+        assert_eq!(builder.spec_constants.len(), 3);
+        assert_eq!(builder.spec_constants.last(), Some(&(0, 7u16.to_ne_bytes().to_vec())));
+    }
+}
@@ -1,11 +1,29 @@
 //! An `OpenCL` command queue.
 
 use std;
+use std::cell::RefCell;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context as TaskContext, Poll};
 use crate::core::{self, Result as OclCoreResult, CommandQueue as CommandQueueCore, CommandQueueInfo,
-    CommandQueueInfoResult, OpenclVersion, CommandQueueProperties, ClWaitListPtr, ClContextPtr};
+    CommandQueueInfoResult, OpenclVersion, CommandQueueProperties, ClWaitListPtr, ClContextPtr,
+    DeviceInfo, DeviceInfoResult, QueuePriority, QueueThrottle, QueueProperties,
+    VersionLowError, VersionKind, Error as OclCoreError, Mem as MemCore};
+use crate::core::ffi::{c_void, cl_queue_properties};
 use crate::error::{Error as OclError, Result as OclResult};
-use crate::standard::{Context, Device, Event};
+use crate::standard::{Context, Device, Event, Quirks, TransferPolicy, TransferMetrics,
+    ClWaitListPtrEnum};
+use crate::standard::quirks;
+use crate::standard::scheduler::Priority;
+use crate::standard::flush::{FlushPolicy, FlushState};
+use crate::standard::tracking::TrackingState;
+
+
+thread_local! {
+    static THREAD_DEFAULT: RefCell<Option<Queue>> = const { RefCell::new(None) };
+}
 
 /// A command queue which manages all actions taken on kernels, buffers, and
 /// images.
@@ -20,6 +38,11 @@ use crate::standard::{Context, Device, Event};
 pub struct Queue {
     obj_core: CommandQueueCore,
     device_version: OpenclVersion,
+    quirks: Quirks,
+    transfer_policy: TransferPolicy,
+    transfer_metrics: TransferMetrics,
+    flush: Arc<FlushState>,
+    tracking: Arc<TrackingState>,
 }
 
 impl Queue {
@@ -27,14 +50,120 @@ impl Queue {
     pub fn new(context: &Context, device: Device, properties: Option<CommandQueueProperties>)
             -> OclResult<Queue> {
         let obj_core = core::create_command_queue(context, &device, properties)?;
+        Queue::from_parts(obj_core, device)
+    }
+
+    fn from_parts(obj_core: CommandQueueCore, device: Device) -> OclResult<Queue> {
         let device_version = device.version()?;
+        let quirks = Quirks::for_device(&device)?;
 
         Ok(Queue {
             obj_core,
             device_version,
+            quirks,
+            transfer_policy: TransferPolicy::new(),
+            transfer_metrics: TransferMetrics::new(),
+            flush: Arc::new(FlushState::default()),
+            tracking: Arc::new(TrackingState::default()),
         })
     }
 
+    /// Returns a new `QueueBuilder`.
+    pub fn builder() -> QueueBuilder {
+        QueueBuilder::new()
+    }
+
+    /// Returns a new Queue on the device specified by `device` with the
+    /// given `properties` (e.g. out-of-order execution or profiling).
+    ///
+    /// Returns an error naming the unsupported properties if `device` does
+    /// not support all of `properties`.
+    ///
+    /// On-device queues (`CommandQueueProperties::ON_DEVICE`) require the
+    /// `opencl_version_2_0` feature. See `QueueBuilder::on_device`.
+    pub fn new_with_properties(context: &Context, device: Device, properties: CommandQueueProperties)
+            -> OclResult<Queue> {
+        Queue::with_queue_properties(context, device, QueueProperties::from(properties))
+    }
+
+    /// Returns a new Queue on the device specified by `device`, created from
+    /// a full properties list which may include an on-device queue size,
+    /// priority and throttle hints, and vendor-specific properties.
+    ///
+    /// Properties accepted by the OpenCL 1.x `clCreateCommandQueue` (the
+    /// `CL_QUEUE_PROPERTIES` bitfield alone) are passed to it on any device.
+    /// Any other properties require an OpenCL 2.0+ device and the
+    /// `opencl_version_2_0` feature. Priority and throttle hints are dropped
+    /// if the device does not support them.
+    pub fn with_queue_properties(context: &Context, device: Device, mut properties: QueueProperties)
+            -> OclResult<Queue> {
+        // Hints are ignored by devices which do not support them:
+        if properties.get_priority().is_some() &&
+                !device.has_extension("cl_khr_priority_hints")? {
+            properties.set_priority(None);
+        }
+        if properties.get_throttle().is_some() &&
+                !device.has_extension("cl_khr_throttle_hints")? {
+            properties.set_throttle(None);
+        }
+
+        let flags = properties.get_flags().unwrap_or_else(CommandQueueProperties::empty);
+
+        if flags.intersects(CommandQueueProperties::ON_DEVICE |
+                CommandQueueProperties::ON_DEVICE_DEFAULT) {
+            return Queue::new_khr(context, device, &properties);
+        }
+
+        if properties.get_size().is_some() {
+            return Err("Queue::new_with_properties: A queue size may only be specified for \
+                on-device queues.".into());
+        }
+
+        let supported = match device.info(DeviceInfo::QueueProperties)? {
+            DeviceInfoResult::QueueProperties(p) => p,
+            _ => unreachable!(),
+        };
+
+        if !supported.contains(flags) {
+            return Err(format!("Queue::new_with_properties: The device '{}' does not support \
+                the command queue properties: {:?}", device.name()?, flags - supported).into());
+        }
+
+        if properties.is_legacy_compatible() {
+            Queue::new(context, device, Some(flags))
+        } else {
+            Queue::new_khr(context, device, &properties)
+        }
+    }
+
+    /// Creates a queue with `clCreateCommandQueueWithProperties`.
+    #[cfg(feature = "opencl_version_2_0")]
+    fn new_khr(context: &Context, device: Device, properties: &QueueProperties) -> OclResult<Queue> {
+        device.require_version(OpenclVersion::new(2, 0), "Queue::new_with_properties (on-device \
+            queues, queue hints, and vendor queue properties)")?;
+        let device_version = device.version()?;
+        let obj_core = core::create_command_queue_with_properties_list(context, &device,
+            properties, Some(&device_version))?;
+        Queue::from_parts(obj_core, device)
+    }
+
+    #[cfg(not(feature = "opencl_version_2_0"))]
+    fn new_khr(_context: &Context, _device: Device, _properties: &QueueProperties)
+            -> OclResult<Queue> {
+        Err("Queue::new_with_properties: On-device queues, queue priority or throttle \
+            hints, and vendor queue properties require the 'opencl_version_2_0' feature.".into())
+    }
+
+    /// Returns a new in-order Queue on the device specified by `device` with
+    /// profiling enabled.
+    ///
+    /// Profiling must be enabled for event timing (e.g.
+    /// `ProfilingInfo::Start`) to be available for commands enqueued on the
+    /// queue.
+    pub fn with_profiling(context: &Context, device: Device) -> OclResult<Queue> {
+        Queue::new_with_properties(context, device, CommandQueueProperties::new().profiling())
+    }
+
     /// Issues all previously queued OpenCL commands to the device.
     pub fn flush(&self) -> OclResult<()> {
         core::flush(&self.obj_core).map_err(OclError::from)
@@ -45,6 +174,18 @@ impl Queue {
         core::finish(&self.obj_core).map_err(OclError::from)
     }
 
+    /// Returns a future which resolves once all commands previously enqueued
+    /// on this queue have completed.
+    ///
+    /// A non-blocking alternative to `::finish` for use within async code.
+    /// A marker command is enqueued and the queue flushed; the returned
+    /// future completes when the marker's event does.
+    pub fn finish_async(&self) -> OclResult<FutureFinish> {
+        let marker = self.enqueue_marker(None::<&Event>)?;
+        self.flush()?;
+        Ok(FutureFinish { marker })
+    }
+
     /// Enqueues a marker command which waits for either a list of events to
     /// complete, or all previously enqueued commands to complete.
     pub fn enqueue_marker<Ewl>(&self, ewait: Option<Ewl>) -> OclResult<Event>
@@ -56,6 +197,72 @@ impl Queue {
             .map_err(OclError::from)
     }
 
+    /// Enqueues a command which runs the host function `user_func` on the
+    /// queue's device, returning its event.
+    ///
+    /// The device must support `DeviceExecCapabilities::NATIVE_KERNEL`. See
+    /// `core::enqueue_native_kernel` for how `args` and `mem_objects` are
+    /// passed to `user_func`.
+    ///
+    /// ## Safety
+    ///
+    /// `user_func` must interpret `args` correctly and may run on any thread.
+    pub unsafe fn enqueue_native_kernel<Ewl>(&self, user_func: extern "C" fn(*mut c_void),
+            args: &mut [u8], mem_objects: &[(&MemCore, usize)], ewait: Option<Ewl>)
+            -> OclResult<Event>
+            where Ewl: ClWaitListPtr
+    {
+        let mut event = Event::empty();
+        core::enqueue_native_kernel(&self.obj_core, user_func, args, mem_objects, ewait,
+            Some(&mut event)).map(|_| event).map_err(OclError::from)
+    }
+
+    /// Enqueues a barrier command which waits for either a list of events
+    /// to complete, or all previously enqueued commands to complete.
+    ///
+    /// Unlike a marker, a barrier also blocks all commands enqueued
+    /// afterwards until it completes, which makes it the usual way of
+    /// ordering commands within an out-of-order queue.
+    pub fn enqueue_barrier<Ewl>(&self, ewait: Option<Ewl>) -> OclResult<Event>
+            where Ewl: ClWaitListPtr
+    {
+        let mut barrier_event = Event::empty();
+        core::enqueue_barrier_with_wait_list(&self.obj_core, ewait, Some(&mut barrier_event),
+                Some(&self.device_version)).map(|_| barrier_event)
+            .map_err(OclError::from)
+    }
+
+    /// Enqueues a marker command which completes once every event in `ewait`
+    /// has, returning its event.
+    ///
+    /// `ewait` may be an event, a list of events (`&EventList`, `&[Event]`,
+    /// etc.) or `None::<&Event>`, which waits for all commands previously
+    /// enqueued on this queue instead. Events from other queues (within the
+    /// same context) are accepted, so the returned event can be used to
+    /// order work across queues.
+    pub fn marker<'e, Ewl>(&self, ewait: Ewl) -> OclResult<Event>
+            where Ewl: Into<ClWaitListPtrEnum<'e>> {
+        let ewait = self.fan_in_wait_list(Some(ewait.into()))?;
+        self.enqueue_marker(ewait)
+    }
+
+    /// Enqueues a barrier command which completes once every event in
+    /// `ewait` has, returning its event.
+    ///
+    /// Commands enqueued on this queue afterwards do not start until the
+    /// barrier completes. `ewait` is as for `::marker`.
+    pub fn barrier<'e, Ewl>(&self, ewait: Ewl) -> OclResult<Event>
+            where Ewl: Into<ClWaitListPtrEnum<'e>> {
+        let ewait = self.fan_in_wait_list(Some(ewait.into()))?;
+        self.enqueue_barrier(ewait)
+    }
+
+    /// Applies the `max_wait_list_len` quirk to `ewait`.
+    pub(crate) fn fan_in_wait_list<'a>(&self, ewait: Option<ClWaitListPtrEnum<'a>>)
+            -> OclResult<Option<ClWaitListPtrEnum<'a>>> {
+        quirks::fan_in_wait_list(&self.obj_core, ewait, self.quirks.max_wait_list_len)
+    }
+
     /// Returns a reference to the core pointer wrapper, usable by functions in
     /// the `core` module.
     #[inline]
@@ -78,6 +285,154 @@ impl Queue {
         self.device_version
     }
 
+    /// Returns an error for which `Error::version_low` returns the details
+    /// if the queue's device does not support at least OpenCL `required`.
+    ///
+    /// See `Device::require_version`.
+    pub fn require_version(&self, required: OpenclVersion, feature: &str) -> OclResult<()> {
+        if self.device_version < required {
+            return Err(OclCoreError::from(VersionLowError::new(VersionKind::Device,
+                self.device_version, required, feature)).into());
+        }
+        Ok(())
+    }
+
+    /// Returns the properties this queue was created with.
+    pub fn properties(&self) -> OclResult<CommandQueueProperties> {
+        match self.info(CommandQueueInfo::Properties)? {
+            CommandQueueInfoResult::Properties(p) => Ok(p),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns true if commands in this queue may execute out of order.
+    pub fn is_out_of_order(&self) -> OclResult<bool> {
+        self.properties().map(|p| p.contains(CommandQueueProperties::OUT_OF_ORDER_EXEC_MODE_ENABLE))
+    }
+
+    /// Returns true if profiling is enabled for this queue.
+    pub fn profiling_enabled(&self) -> OclResult<bool> {
+        self.properties().map(|p| p.contains(CommandQueueProperties::PROFILING_ENABLE))
+    }
+
+    /// Returns true if this is an on-device queue, usable only by kernels
+    /// enqueuing work from the device.
+    pub fn is_on_device(&self) -> OclResult<bool> {
+        self.properties().map(|p| p.contains(CommandQueueProperties::ON_DEVICE))
+    }
+
+    /// Returns the driver workarounds which were active for this queue's
+    /// device when the queue was created.
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
+    /// Sets the policy used to split up large buffer reads and writes
+    /// enqueued on this queue.
+    ///
+    /// Buffers may override this with `Buffer::set_transfer_policy`.
+    pub fn set_transfer_policy(&mut self, policy: TransferPolicy) -> &mut Queue {
+        self.transfer_policy = policy;
+        self
+    }
+
+    /// Returns the policy used to split up large buffer reads and writes.
+    pub fn transfer_policy(&self) -> &TransferPolicy {
+        &self.transfer_policy
+    }
+
+    /// Returns the per-chunk timings of split transfers enqueued on this
+    /// queue (or any of its clones).
+    pub fn transfer_metrics(&self) -> &TransferMetrics {
+        &self.transfer_metrics
+    }
+
+    /// Sets when this queue (and all of its clones) is flushed
+    /// automatically.
+    ///
+    /// Use `FlushPolicy::Async` or `FlushPolicy::Every` on drivers which do
+    /// not start work until a queue is flushed.
+    pub fn set_flush_policy(&self, policy: FlushPolicy) {
+        self.flush.set_policy(policy)
+    }
+
+    /// Returns the automatic flush policy.
+    pub fn flush_policy(&self) -> FlushPolicy {
+        self.flush.policy()
+    }
+
+    /// Flushes this queue, after a command enqueued on it has returned
+    /// `result`, if the flush policy calls for it.
+    pub(crate) fn enqueued<R>(&self, result: OclResult<R>, is_async: bool) -> OclResult<R> {
+        self.flush.enqueued(&self.obj_core, result, is_async)
+    }
+
+    /// Enables or disables implicit dependency tracking for this queue (and
+    /// all of its clones).
+    ///
+    /// While enabled, each command enqueued on this queue waits for the
+    /// command enqueued on it before, in addition to any events passed with
+    /// `::ewait`. This gives in-order execution on an out-of-order queue
+    /// without managing event lists by hand. Commands enqueued directly with
+    /// the `core` functions are not tracked.
+    ///
+    /// Disabling tracking forgets the last command's event.
+    pub fn set_track_dependencies(&self, enabled: bool) {
+        self.tracking.set_enabled(enabled)
+    }
+
+    /// Returns the dependency tracking state.
+    pub(crate) fn tracking(&self) -> &TrackingState {
+        &self.tracking
+    }
+
+    /// Returns true if implicit dependency tracking is enabled.
+    pub fn tracks_dependencies(&self) -> bool {
+        self.tracking.is_enabled()
+    }
+
+    /// Returns the event of the last command enqueued while dependency
+    /// tracking was enabled, if any.
+    pub fn last_event(&self) -> Option<Event> {
+        self.tracking.last_event()
+    }
+
+    /// Makes this queue the default for the current thread, returning the
+    /// previous default, if any.
+    ///
+    /// Code anywhere on this thread can then obtain it with
+    /// `Queue::thread_default` rather than having a queue passed down to it.
+    pub fn set_thread_default(&self) -> Option<Queue> {
+        THREAD_DEFAULT.with(|q| q.replace(Some(self.clone())))
+    }
+
+    /// Removes the default queue for the current thread, returning it.
+    pub fn clear_thread_default() -> Option<Queue> {
+        THREAD_DEFAULT.with(|q| q.replace(None))
+    }
+
+    /// Returns the default queue for the current thread, if one has been
+    /// set with `::set_thread_default` or `::with_thread_default`.
+    pub fn thread_default() -> Option<Queue> {
+        THREAD_DEFAULT.with(|q| q.borrow().clone())
+    }
+
+    /// Calls `f` with this queue as the default for the current thread,
+    /// restoring the previous default afterwards (even if `f` panics).
+    pub fn with_thread_default<R, F: FnOnce() -> R>(&self, f: F) -> R {
+        struct Restore(Option<Queue>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let prev = self.0.take();
+                THREAD_DEFAULT.with(|q| *q.borrow_mut() = prev);
+            }
+        }
+
+        let _restore = Restore(self.set_thread_default());
+        f()
+    }
+
     /// Returns info about this queue.
     pub fn info(&self, info_kind: CommandQueueInfo) -> OclCoreResult<CommandQueueInfoResult> {
         core::get_command_queue_info(&self.obj_core, info_kind)
@@ -131,3 +486,233 @@ unsafe impl<'a> ClContextPtr for &'a Queue {
             Unable to obtain a context pointer.")
     }
 }
+
+
+/// A future which resolves once all previously enqueued commands on a queue
+/// have completed.
+///
+/// Returned by `Queue::finish_async`.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct FutureFinish {
+    marker: Event,
+}
+
+impl FutureFinish {
+    /// Returns the event of the marker command being waited on.
+    pub fn marker(&self) -> &Event {
+        &self.marker
+    }
+}
+
+impl Future for FutureFinish {
+    type Output = OclResult<()>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.marker).poll(cx)
+    }
+}
+
+
+/// A builder for `Queue`.
+#[must_use = "builders do nothing unless '::build' is called"]
+#[derive(Clone, Debug, Default)]
+pub struct QueueBuilder {
+    context: Option<Context>,
+    device: Option<Device>,
+    properties: CommandQueueProperties,
+    queue_size: Option<u32>,
+    priority: Option<QueuePriority>,
+    throttle: Option<QueueThrottle>,
+    flush_policy: FlushPolicy,
+    track_dependencies: bool,
+    vendor_properties: Vec<(cl_queue_properties, cl_queue_properties)>,
+    quirks: Option<Quirks>,
+}
+
+impl QueueBuilder {
+    /// Returns a new `QueueBuilder`.
+    ///
+    /// A context must be specified with `::context` before building.
+    ///
+    /// ## Defaults
+    ///
+    /// * The first device associated with the context
+    /// * In-order execution with profiling disabled
+    ///
+    pub fn new() -> QueueBuilder {
+        QueueBuilder::default()
+    }
+
+    /// Specifies the context to create the queue within.
+    pub fn context(&mut self, context: &Context) -> &mut QueueBuilder {
+        self.context = Some(context.clone());
+        self
+    }
+
+    /// Specifies the device the queue will submit commands to.
+    ///
+    /// Must be associated with the context.
+    pub fn device(&mut self, device: Device) -> &mut QueueBuilder {
+        self.device = Some(device);
+        self
+    }
+
+    /// Specifies all queue properties directly.
+    ///
+    /// Overwrites all previously specified properties.
+    pub fn properties(&mut self, properties: CommandQueueProperties) -> &mut QueueBuilder {
+        self.properties = properties;
+        self
+    }
+
+    /// Allows commands to execute out of order.
+    ///
+    /// Commands enqueued on an out-of-order queue are only ordered by their
+    /// wait lists (and by barriers), allowing independent kernels to run
+    /// concurrently.
+    pub fn out_of_order(&mut self) -> &mut QueueBuilder {
+        self.properties = self.properties.out_of_order();
+        self
+    }
+
+    /// Enables profiling of commands enqueued on the queue.
+    ///
+    /// Required for event timing information.
+    pub fn profiling(&mut self) -> &mut QueueBuilder {
+        self.properties = self.properties.profiling();
+        self
+    }
+
+    /// Makes this an on-device queue, onto which kernels may enqueue other
+    /// kernels (OpenCL 2.0 device-side enqueue).
+    ///
+    /// On-device queues are always out-of-order. Commands can not be enqueued
+    /// on them from the host; pass the queue to a kernel as a `queue_t`
+    /// argument instead. Requires the `opencl_version_2_0` feature.
+    pub fn on_device(&mut self) -> &mut QueueBuilder {
+        self.properties = self.properties.out_of_order() | CommandQueueProperties::ON_DEVICE;
+        self
+    }
+
+    /// Makes this the default on-device queue for the device, as returned by
+    /// `get_default_queue()` within kernels.
+    ///
+    /// Implies `::on_device`.
+    pub fn on_device_default(&mut self) -> &mut QueueBuilder {
+        self.on_device();
+        self.properties |= CommandQueueProperties::ON_DEVICE_DEFAULT;
+        self
+    }
+
+    /// Specifies the size of an on-device queue, in bytes.
+    ///
+    /// Defaults to the device's preferred size
+    /// (`CL_DEVICE_QUEUE_ON_DEVICE_PREFERRED_SIZE`).
+    pub fn queue_size(&mut self, size: u32) -> &mut QueueBuilder {
+        self.queue_size = Some(size);
+        self
+    }
+
+    /// Specifies the scheduling priority of the queue relative to other
+    /// queues on the device (`cl_khr_priority_hints`).
+    ///
+    /// Marking a latency-sensitive queue `QueuePriority::High` lets its
+    /// commands run ahead of batch work submitted on lower priority queues.
+    /// Ignored by devices which do not support the extension. Requires the
+    /// `opencl_version_2_0` feature.
+    ///
+    /// If unspecified (and the `opencl_version_2_0` feature is enabled), a
+    /// queue built within a `Priority::scope` other than `Priority::NORMAL`
+    /// takes its priority from the current task's tag.
+    pub fn priority(&mut self, priority: QueuePriority) -> &mut QueueBuilder {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Specifies how aggressively the device may be clocked while executing
+    /// commands from the queue (`cl_khr_throttle_hints`).
+    ///
+    /// Ignored by devices which do not support the extension. Requires the
+    /// `opencl_version_2_0` feature.
+    pub fn throttle(&mut self, throttle: QueueThrottle) -> &mut QueueBuilder {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    /// Specifies a vendor-specific property, passed to
+    /// `clCreateCommandQueueWithProperties` along with the standard ones.
+    ///
+    /// Requires an OpenCL 2.0+ device and the `opencl_version_2_0` feature.
+    pub fn vendor_property(&mut self, name: cl_queue_properties, value: cl_queue_properties)
+            -> &mut QueueBuilder {
+        self.vendor_properties.push((name, value));
+        self
+    }
+
+    /// Specifies when the queue is flushed automatically (see
+    /// `Queue::set_flush_policy`).
+    pub fn flush_policy(&mut self, policy: FlushPolicy) -> &mut QueueBuilder {
+        self.flush_policy = policy;
+        self
+    }
+
+    /// Enables implicit dependency tracking (see
+    /// `Queue::set_track_dependencies`).
+    pub fn track_dependencies(&mut self, enabled: bool) -> &mut QueueBuilder {
+        self.track_dependencies = enabled;
+        self
+    }
+
+    /// Specifies the driver workarounds to apply to commands enqueued on the
+    /// queue, in place of those active for the device (see
+    /// `Quirks::for_device`).
+    pub fn quirks(&mut self, quirks: Quirks) -> &mut QueueBuilder {
+        self.quirks = Some(quirks);
+        self
+    }
+
+    /// Returns the properties list the queue will be created with.
+    pub(crate) fn queue_properties(&self) -> QueueProperties {
+        // Without the feature, a hint would make creation fail on devices
+        // supporting the extension, so only explicit priorities are used.
+        let task_priority = match Priority::current() {
+            Priority::NORMAL => None,
+            _ if cfg!(not(feature = "opencl_version_2_0")) => None,
+            task_priority => Some(task_priority.into()),
+        };
+        let priority = self.priority.or(task_priority);
+
+        let mut properties = QueueProperties::new().flags(self.properties);
+        properties.set_size(self.queue_size);
+        properties.set_priority(priority);
+        properties.set_throttle(self.throttle);
+        for &(name, value) in self.vendor_properties.iter() {
+            properties.set_vendor(name, value);
+        }
+        properties
+    }
+
+    /// Returns a new `Queue`.
+    pub fn build(&self) -> OclResult<Queue> {
+        let context = match self.context {
+            Some(ref c) => c,
+            None => return Err("QueueBuilder::build: No context specified.".into()),
+        };
+
+        let device = match self.device {
+            Some(d) => d,
+            None => match context.devices().first() {
+                Some(&d) => d,
+                None => return Err("QueueBuilder::build: The context has no devices.".into()),
+            },
+        };
+
+        let mut queue = Queue::with_queue_properties(context, device, self.queue_properties())?;
+        if let Some(quirks) = self.quirks { queue.quirks = quirks; }
+        queue.set_flush_policy(self.flush_policy);
+        if self.track_dependencies { queue.set_track_dependencies(true); }
+        Ok(queue)
+    }
+}
@@ -0,0 +1,240 @@
+//! A pool of command queues on a single device.
+//!
+//! Submitting independent work (e.g. transfers and kernels) through several
+//! queues allows a device to overlap it.
+//!
+//! A pool may also contain queues created with different device-side
+//! priority hints (see `QueuePool::with_priorities`), in which case each
+//! submission is dispatched among the queues matching the priority of the
+//! submitting task (see `Priority::scope`).
+
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::core::{CommandQueueProperties, QueuePriority};
+use crate::error::Result as OclResult;
+use crate::standard::{Context, Device, Queue, Event, Priority};
+
+
+/// How a `QueuePool` chooses the queue for each submission.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DispatchPolicy {
+    /// Cycle through the queues in order.
+    #[default]
+    RoundRobin,
+    /// Use the queue with the fewest incomplete submissions.
+    ///
+    /// Completion is tracked by enqueuing a marker after each submission.
+    LeastLoaded,
+}
+
+
+#[derive(Debug)]
+struct Inner {
+    queues: Vec<Queue>,
+    // The priority hint each queue was created with (`Medium` if none).
+    priorities: Vec<QueuePriority>,
+    policy: DispatchPolicy,
+    next: AtomicUsize,
+    // Markers enqueued after each submission, per queue (`LeastLoaded` only).
+    outstanding: Mutex<Vec<Vec<Event>>>,
+}
+
+
+/// A fixed set of queues on one device which are handed out in turn.
+///
+/// Cloning a `QueuePool` returns a handle to the same set of queues.
+#[derive(Clone, Debug)]
+pub struct QueuePool {
+    inner: Arc<Inner>,
+}
+
+impl QueuePool {
+    /// Returns a new pool of `count` in-order queues on `device` using
+    /// round-robin dispatch.
+    pub fn new(context: &Context, device: Device, count: usize) -> OclResult<QueuePool> {
+        QueuePool::with_policy(context, device, count, None, DispatchPolicy::RoundRobin)
+    }
+
+    /// Returns a new pool of `count` queues on `device`, each created with
+    /// `properties`, using the specified dispatch policy.
+    pub fn with_policy(context: &Context, device: Device, count: usize,
+            properties: Option<CommandQueueProperties>, policy: DispatchPolicy)
+            -> OclResult<QueuePool> {
+        if count == 0 {
+            return Err("QueuePool::with_policy: The pool must contain at least one queue.".into());
+        }
+
+        let queues = (0..count)
+            .map(|_| match properties {
+                Some(props) => Queue::new_with_properties(context, device, props),
+                None => Queue::new(context, device, None),
+            })
+            .collect::<OclResult<Vec<_>>>()?;
+
+        Ok(QueuePool::from_parts(queues, vec![QueuePriority::Medium; count], policy))
+    }
+
+    /// Returns a new pool containing one queue on `device` for each of
+    /// `priorities`, each created with that priority hint, using the
+    /// specified dispatch policy.
+    ///
+    /// Submissions are dispatched among the queues whose priority matches
+    /// that of the submitting task (`Priority::current`, converted to a
+    /// `QueuePriority`), or among all queues if none match. Devices without
+    /// `cl_khr_priority_hints` ignore the hints but the queues are still
+    /// chosen by priority. Requires the `opencl_version_2_0` feature for the
+    /// hints to be passed to the device.
+    pub fn with_priorities(context: &Context, device: Device, priorities: &[QueuePriority],
+            policy: DispatchPolicy) -> OclResult<QueuePool> {
+        if priorities.is_empty() {
+            return Err("QueuePool::with_priorities: The pool must contain at least one queue."
+                .into());
+        }
+
+        let queues = priorities.iter()
+            .map(|&_priority| {
+                let mut builder = Queue::builder();
+                builder.context(context).device(device);
+                #[cfg(feature = "opencl_version_2_0")]
+                builder.priority(_priority);
+                builder.build()
+            })
+            .collect::<OclResult<Vec<_>>>()?;
+
+        Ok(QueuePool::from_parts(queues, priorities.to_vec(), policy))
+    }
+
+    fn from_parts(queues: Vec<Queue>, priorities: Vec<QueuePriority>, policy: DispatchPolicy)
+            -> QueuePool {
+        QueuePool {
+            inner: Arc::new(Inner {
+                outstanding: Mutex::new(vec![Vec::new(); queues.len()]),
+                queues,
+                priorities,
+                policy,
+                next: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Returns the indices of the queues eligible for a submission from the
+    /// current task.
+    fn candidates(&self) -> Vec<usize> {
+        let wanted = QueuePriority::from(Priority::current());
+        let matching: Vec<usize> = (0..self.inner.queues.len())
+            .filter(|&idx| self.inner.priorities[idx] == wanted)
+            .collect();
+        if matching.is_empty() { (0..self.inner.queues.len()).collect() } else { matching }
+    }
+
+    /// Returns the index of the queue to be used for the next submission.
+    fn next_idx(&self) -> OclResult<usize> {
+        let candidates = self.candidates();
+        let len = candidates.len();
+
+        match self.inner.policy {
+            DispatchPolicy::RoundRobin => {
+                Ok(candidates[self.inner.next.fetch_add(1, Ordering::Relaxed) % len])
+            },
+            DispatchPolicy::LeastLoaded => {
+                let loads = self.outstanding()?;
+                // Break ties round-robin so that idle queues are all used.
+                let start = self.inner.next.fetch_add(1, Ordering::Relaxed);
+                Ok((0..len).map(|i| candidates[(start + i) % len])
+                    .min_by_key(|&idx| loads[idx])
+                    .unwrap())
+            },
+        }
+    }
+
+    /// Returns the next queue according to the dispatch policy and the
+    /// current task's priority.
+    ///
+    /// Commands enqueued directly on the returned queue are not counted by
+    /// the `LeastLoaded` policy. Use `::enqueue` instead.
+    pub fn next(&self) -> OclResult<&Queue> {
+        self.next_idx().map(|idx| &self.inner.queues[idx])
+    }
+
+    /// Calls `f` with the next queue according to the dispatch policy and the
+    /// current task's priority and returns its result.
+    pub fn enqueue<R, F>(&self, f: F) -> OclResult<R>
+            where F: FnOnce(&Queue) -> OclResult<R> {
+        let idx = self.next_idx()?;
+        let queue = &self.inner.queues[idx];
+        let result = f(queue)?;
+
+        if self.inner.policy == DispatchPolicy::LeastLoaded {
+            let marker = queue.enqueue_marker(None::<&Event>)?;
+            self.inner.outstanding.lock().unwrap()[idx].push(marker);
+        }
+        Ok(result)
+    }
+
+    /// Returns the number of incomplete submissions on each queue.
+    ///
+    /// Always zero unless the `LeastLoaded` policy is in use.
+    pub fn outstanding(&self) -> OclResult<Vec<usize>> {
+        let mut outstanding = self.inner.outstanding.lock().unwrap();
+        let mut counts = Vec::with_capacity(outstanding.len());
+        for markers in outstanding.iter_mut() {
+            let mut idx = 0;
+            while idx < markers.len() {
+                if markers[idx].is_complete()? {
+                    drop(markers.swap_remove(idx));
+                } else {
+                    idx += 1;
+                }
+            }
+            counts.push(markers.len());
+        }
+        Ok(counts)
+    }
+
+    /// Flushes every queue in the pool.
+    pub fn flush(&self) -> OclResult<()> {
+        self.inner.queues.iter().try_for_each(|q| q.flush())
+    }
+
+    /// Blocks until all commands on every queue in the pool have completed.
+    pub fn finish(&self) -> OclResult<()> {
+        self.inner.queues.iter().try_for_each(|q| q.finish())?;
+        self.inner.outstanding.lock().unwrap().iter_mut().for_each(|m| m.clear());
+        Ok(())
+    }
+
+    /// Returns the queues in the pool.
+    pub fn queues(&self) -> &[Queue] {
+        &self.inner.queues
+    }
+
+    /// Returns the number of queues in the pool.
+    pub fn len(&self) -> usize {
+        self.inner.queues.len()
+    }
+
+    /// Returns true if the pool contains no queues.
+    ///
+    /// Pools always contain at least one queue, so this is always false.
+    pub fn is_empty(&self) -> bool {
+        self.inner.queues.is_empty()
+    }
+
+    /// Returns the priority hint of each queue in the pool.
+    pub fn priorities(&self) -> &[QueuePriority] {
+        &self.inner.priorities
+    }
+
+    /// Returns the dispatch policy.
+    pub fn policy(&self) -> DispatchPolicy {
+        self.inner.policy
+    }
+}
+
+impl std::ops::Index<usize> for QueuePool {
+    type Output = Queue;
+
+    fn index(&self, idx: usize) -> &Queue {
+        &self.inner.queues[idx]
+    }
+}
@@ -0,0 +1,248 @@
+//! A registry of driver bug workarounds.
+//!
+//! Some OpenCL implementations misbehave in ways which can be avoided by
+//! using the API a little differently. The standard types consult the
+//! `Quirks` active for a device and apply whichever of the following
+//! workarounds are enabled:
+//!
+//! * `blocking_writes`: Buffer writes are always enqueued as blocking.
+//! * `max_wait_list_len`: The maximum number of events a command may wait on.
+//!   Longer wait lists are first reduced with intermediate marker commands.
+//! * `serialize_builds`: Program builds are serialized process-wide.
+//!
+//! No workaround is enabled by default: the built-in table, matched by
+//! platform name, device name, and driver version, is currently empty.
+//! Every entry added to it must cite the upstream report of the bug it works
+//! around; until then, workarounds must be enabled for a device with
+//! `Quirks::set_override` (or per queue with `QueueBuilder::quirks`).
+//!
+//! Quirks can be inspected with `Quirks::for_device`, replaced for a device
+//! with `Quirks::set_override`, or disabled altogether with
+//! `Quirks::set_enabled(false)` or by setting the `OCL_DISABLE_QUIRKS`
+//! environment variable.
+
+use std::collections::HashMap;
+use std::env;
+use std::slice;
+use std::sync::Mutex;
+use crate::core::{self, DeviceInfo, DeviceInfoResult, PlatformInfo, ClWaitListPtr,
+    CommandQueue as CommandQueueCore};
+use crate::ffi::cl_event;
+use crate::error::{Error as OclError, Result as OclResult};
+use crate::standard::{Device, Event, EventList, ClWaitListPtrEnum};
+
+
+/// Workarounds active for a particular device.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Quirks {
+    /// Enqueue all buffer writes as blocking, regardless of what was
+    /// requested.
+    pub blocking_writes: bool,
+    /// The maximum number of events a single command may wait on.
+    pub max_wait_list_len: Option<usize>,
+    /// Never build more than one program at a time (process-wide).
+    pub serialize_builds: bool,
+}
+
+
+/// An entry in a quirk table.
+///
+/// Each `Some` field must be contained within (or for `driver_version`,
+/// be a prefix of) the corresponding device property for the entry to
+/// apply.
+pub(crate) struct QuirkEntry {
+    pub(crate) platform: Option<&'static str>,
+    pub(crate) device: Option<&'static str>,
+    pub(crate) driver_version: Option<&'static str>,
+    /// The upstream bug report describing the misbehavior.
+    pub(crate) source: &'static str,
+    pub(crate) apply: fn(&mut Quirks),
+}
+
+impl QuirkEntry {
+    pub(crate) fn matches(&self, id: &DriverId) -> bool {
+        self.platform.map(|p| id.platform.contains(p)).unwrap_or(true) &&
+            self.device.map(|d| id.device.contains(d)).unwrap_or(true) &&
+            self.driver_version.map(|v| id.driver_version.starts_with(v)).unwrap_or(true)
+    }
+}
+
+
+/// Built-in quirks, applied in order.
+///
+/// Entries require a `source` link to the driver's bug tracker. None have
+/// been confirmed against an upstream report yet, so the table is empty and
+/// all workarounds must currently be enabled with `Quirks::set_override`.
+static QUIRK_TABLE: &[QuirkEntry] = &[];
+
+
+/// The strings used to match a device against the quirk table.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DriverId {
+    pub platform: String,
+    pub device: String,
+    pub driver_version: String,
+}
+
+impl DriverId {
+    /// Queries the identifying strings for `device`.
+    pub fn of(device: &Device) -> OclResult<DriverId> {
+        let platform = match device.info(DeviceInfo::Platform)? {
+            DeviceInfoResult::Platform(p) => p,
+            _ => unreachable!(),
+        };
+
+        Ok(DriverId {
+            platform: core::get_platform_info(platform, PlatformInfo::Name)
+                .map(|r| r.to_string()).map_err(OclError::from)?,
+            device: device.name()?,
+            driver_version: device.info(DeviceInfo::DriverVersion)?.to_string(),
+        })
+    }
+}
+
+
+struct Registry {
+    disabled: Option<bool>,
+    overrides: HashMap<Device, Quirks>,
+    cache: HashMap<Device, Quirks>,
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+fn with_registry<R, F: FnOnce(&mut Registry) -> R>(f: F) -> R {
+    let mut reg = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    let reg = reg.get_or_insert_with(|| Registry {
+        disabled: None,
+        overrides: HashMap::new(),
+        cache: HashMap::new(),
+    });
+    f(reg)
+}
+
+
+impl Quirks {
+    /// Returns the quirks from the built-in table which apply to the driver
+    /// identified by `id`, ignoring overrides.
+    pub fn builtin(id: &DriverId) -> Quirks {
+        Quirks::lookup(QUIRK_TABLE, id)
+    }
+
+    /// Returns the sources cited by the built-in table entries which apply to
+    /// the driver identified by `id`.
+    pub fn builtin_sources(id: &DriverId) -> Vec<&'static str> {
+        QUIRK_TABLE.iter().filter(|e| e.matches(id)).map(|e| e.source).collect()
+    }
+
+    /// Returns the quirks from `table` which apply to the driver identified
+    /// by `id`, applying matching entries in order.
+    pub(crate) fn lookup(table: &[QuirkEntry], id: &DriverId) -> Quirks {
+        let mut quirks = Quirks::default();
+        for entry in table.iter().filter(|e| e.matches(id)) {
+            (entry.apply)(&mut quirks);
+        }
+        quirks
+    }
+
+    /// Returns the quirks currently active for `device`.
+    ///
+    /// Results are cached per device.
+    pub fn for_device(device: &Device) -> OclResult<Quirks> {
+        let cached = with_registry(|reg| {
+            let disabled = *reg.disabled.get_or_insert_with(|| env::var("OCL_DISABLE_QUIRKS").is_ok());
+            if disabled { return Some(Quirks::default()); }
+            reg.overrides.get(device).or_else(|| reg.cache.get(device)).cloned()
+        });
+
+        if let Some(quirks) = cached { return Ok(quirks); }
+
+        let quirks = Quirks::builtin(&DriverId::of(device)?);
+        with_registry(|reg| reg.cache.insert(*device, quirks));
+        Ok(quirks)
+    }
+
+    /// Replaces the quirks active for `device`.
+    ///
+    /// Only affects queues (and other objects) created afterwards.
+    pub fn set_override(device: &Device, quirks: Quirks) {
+        with_registry(|reg| reg.overrides.insert(*device, quirks));
+    }
+
+    /// Removes an override previously set with `::set_override`.
+    pub fn clear_override(device: &Device) {
+        with_registry(|reg| reg.overrides.remove(device));
+    }
+
+    /// Enables or disables all quirks (including overrides) process-wide.
+    ///
+    /// Overrides the `OCL_DISABLE_QUIRKS` environment variable.
+    pub fn set_enabled(enabled: bool) {
+        with_registry(|reg| reg.disabled = Some(!enabled));
+    }
+
+    /// Returns the workarounds needed by a command which may run on a device
+    /// with either `self` or `other` active: every flag set in either, and
+    /// the smaller of each limit.
+    pub fn union(self, other: Quirks) -> Quirks {
+        let min = |a: Option<usize>, b: Option<usize>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        Quirks {
+            blocking_writes: self.blocking_writes || other.blocking_writes,
+            max_wait_list_len: min(self.max_wait_list_len, other.max_wait_list_len),
+            serialize_builds: self.serialize_builds || other.serialize_builds,
+        }
+    }
+
+    /// Returns true if any workaround is active.
+    pub fn any(&self) -> bool {
+        *self != Quirks::default()
+    }
+}
+
+
+/// Returns `ewait` unchanged unless it contains more than `max_len` events,
+/// in which case the events are gathered (fanned in) by marker commands
+/// enqueued on `queue`, each waiting on no more than `max_len` events, and a
+/// list of those markers is returned in its place.
+pub(crate) fn fan_in_wait_list<'a>(queue: &CommandQueueCore, ewait: Option<ClWaitListPtrEnum<'a>>,
+        max_len: Option<usize>) -> OclResult<Option<ClWaitListPtrEnum<'a>>> {
+    let max_len = match max_len {
+        Some(max_len) => max_len.max(2),
+        None => return Ok(ewait),
+    };
+
+    let ewait = match ewait {
+        Some(ewait) => ewait,
+        None => return Ok(None),
+    };
+
+    let count = ewait.count() as usize;
+    if count <= max_len { return Ok(Some(ewait)); }
+
+    let enqueue_markers = |events: &[cl_event]| -> OclResult<EventList> {
+        let mut markers = EventList::with_capacity(events.len() / max_len + 1);
+        for chunk in events.chunks(max_len) {
+            let mut marker = Event::empty();
+            core::enqueue_marker_with_wait_list(queue, Some(chunk), Some(&mut marker), None)?;
+            markers.push(marker);
+        }
+        Ok(markers)
+    };
+
+    let events = unsafe { slice::from_raw_parts(ewait.as_ptr_ptr(), count) };
+    let mut markers = enqueue_markers(events)?;
+    while markers.len() > max_len {
+        let ptrs = unsafe { slice::from_raw_parts(markers.as_ptr_ptr(), markers.len()) };
+        markers = enqueue_markers(ptrs)?;
+    }
+
+    Ok(Some(ClWaitListPtrEnum::BoxTraitObj(Box::new(markers))))
+}
+
+
+/// Held while building a program on a device with the `serialize_builds`
+/// quirk.
+pub(crate) static BUILD_LOCK: Mutex<()> = Mutex::new(());
@@ -0,0 +1,159 @@
+//! Re-creation of kernels after their program has been rebuilt.
+//!
+//! Kernels built from a reloadable program (see
+//! `ProgramBuilder::reloadable`) register themselves with the program's
+//! `ProgramHandle` and keep a copy of each argument value they are given.
+//! When the program is rebuilt with `Program::rebuild`, each live kernel is
+//! re-created from the new program and its arguments are replayed onto it.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::slice;
+use crate::core::{self, Kernel as KernelCore, Program as ProgramCore, ArgVal};
+use crate::core::ffi::c_void;
+use crate::error::{Error as OclError, Result as OclResult};
+
+
+/// A copy of a kernel argument value.
+#[derive(Debug)]
+struct CachedArg {
+    size: usize,
+    bytes: Option<Vec<u8>>,
+    is_mem_null: bool,
+}
+
+impl CachedArg {
+    fn new(arg_val: &ArgVal) -> CachedArg {
+        let (size, value) = arg_val.as_raw();
+        let bytes = if value.is_null() {
+            None
+        } else {
+            Some(unsafe { slice::from_raw_parts(value as *const u8, size) }.to_vec())
+        };
+
+        CachedArg { size, bytes, is_mem_null: arg_val.is_mem_null() }
+    }
+
+    fn as_arg_val(&self) -> ArgVal<'_> {
+        let value = match self.bytes {
+            Some(ref bytes) => bytes.as_ptr() as *const c_void,
+            None => std::ptr::null(),
+        };
+        // Only null `Mem` arguments need to be distinguished from others.
+        unsafe { ArgVal::from_raw(self.size, value, self.is_mem_null) }
+    }
+}
+
+
+#[derive(Debug, Default)]
+struct ReloadState {
+    replacement: Option<KernelCore>,
+    args: BTreeMap<u32, CachedArg>,
+}
+
+
+/// The reload state of a single kernel.
+#[derive(Debug)]
+pub(crate) struct KernelReload {
+    name: String,
+    state: Mutex<ReloadState>,
+}
+
+impl KernelReload {
+    fn lock(&self) -> MutexGuard<'_, ReloadState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Sets an argument on `current` (and on the replacement kernel, if
+    /// any), keeping a copy for later replay.
+    pub(crate) fn set_arg(&self, current: &KernelCore, arg_idx: u32, arg_val: ArgVal)
+            -> OclResult<()> {
+        let mut state = self.lock();
+        core::set_kernel_arg(current, arg_idx, arg_val.clone())?;
+        if let Some(ref replacement) = state.replacement {
+            core::set_kernel_arg(replacement, arg_idx, arg_val.clone())?;
+        }
+        state.args.insert(arg_idx, CachedArg::new(&arg_val));
+        Ok(())
+    }
+
+    /// Returns the kernel re-created by the most recent rebuild, if it has
+    /// not yet been taken.
+    pub(crate) fn replacement(&self) -> Option<KernelCore> {
+        self.lock().replacement.clone()
+    }
+
+    /// Takes the kernel re-created by the most recent rebuild.
+    pub(crate) fn take_replacement(&self) -> Option<KernelCore> {
+        self.lock().replacement.take()
+    }
+}
+
+
+/// A registry of the kernels created from a reloadable program.
+///
+/// Shared among clones of a `Program`.
+#[derive(Clone, Debug, Default)]
+pub struct ProgramHandle {
+    kernels: Arc<Mutex<Vec<Weak<KernelReload>>>>,
+}
+
+impl ProgramHandle {
+    /// Returns a new, empty registry.
+    pub fn new() -> ProgramHandle {
+        ProgramHandle::default()
+    }
+
+    /// Returns the number of live kernels registered.
+    pub fn kernel_count(&self) -> usize {
+        let mut kernels = self.kernels.lock().unwrap_or_else(|e| e.into_inner());
+        kernels.retain(|k| k.upgrade().is_some());
+        kernels.len()
+    }
+
+    /// Registers the kernel named `name` with the arguments it was built with.
+    pub(crate) fn register(&self, name: &str, args: &[(u32, ArgVal)]) -> Arc<KernelReload> {
+        let reload = Arc::new(KernelReload {
+            name: name.to_owned(),
+            state: Mutex::new(ReloadState {
+                replacement: None,
+                args: args.iter().map(|&(idx, ref val)| (idx, CachedArg::new(val))).collect(),
+            }),
+        });
+
+        let mut kernels = self.kernels.lock().unwrap_or_else(|e| e.into_inner());
+        kernels.retain(|k| k.upgrade().is_some());
+        kernels.push(Arc::downgrade(&reload));
+        reload
+    }
+
+    /// Re-creates every live registered kernel from `program` and replays
+    /// its arguments.
+    ///
+    /// Either every kernel receives its replacement or, upon error, none do.
+    pub(crate) fn reload(&self, program: &ProgramCore) -> OclResult<()> {
+        let kernels = self.kernels.lock().unwrap_or_else(|e| e.into_inner());
+        let live: Vec<Arc<KernelReload>> = kernels.iter().filter_map(|k| k.upgrade()).collect();
+        let mut states: Vec<MutexGuard<ReloadState>> = live.iter().map(|k| k.lock()).collect();
+        let mut replacements = Vec::with_capacity(live.len());
+
+        for (reload, state) in live.iter().zip(states.iter()) {
+            let kernel = core::create_kernel(program, &reload.name).map_err(|err| {
+                OclError::from(format!("Program::rebuild: Unable to re-create kernel '{}': {}",
+                    reload.name, err))
+            })?;
+
+            let arg_vals: Vec<(u32, ArgVal)> = state.args.iter()
+                .map(|(&idx, arg)| (idx, arg.as_arg_val()))
+                .filter(|(_, val)| !val.is_mem_null())
+                .collect();
+            core::set_kernel_args(&kernel, &arg_vals)?;
+            replacements.push(kernel);
+        }
+
+        for (state, kernel) in states.iter_mut().zip(replacements) {
+            state.replacement = Some(kernel);
+        }
+        Ok(())
+    }
+}
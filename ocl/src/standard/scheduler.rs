@@ -0,0 +1,227 @@
+//! Priority tags and priority-ordered command dispatch.
+//!
+//! A `Priority` tag links host-side task priorities with the order in which
+//! OpenCL commands are submitted to a device. Executors which assign
+//! priorities to their tasks can make the priority of the task currently
+//! being polled visible with `Priority::scope`. Command futures created
+//! within that scope (`FutureMemMap`, `FutureReadGuard`, etc.) inherit the
+//! tag as do commands submitted to a `FairScheduler` without an explicit
+//! priority, and queues built within it are given the corresponding
+//! device-side priority hint (see `QueueBuilder::priority`).
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use crate::core::QueuePriority;
+use crate::error::Result as OclResult;
+use crate::standard::Queue;
+
+
+thread_local! {
+    static CURRENT_PRIORITY: Cell<Priority> = const { Cell::new(Priority::NORMAL) };
+}
+
+
+/// A scheduling priority tag.
+///
+/// Higher values are dispatched first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Priority(pub i32);
+
+impl Priority {
+    pub const LOW: Priority = Priority(-100);
+    pub const NORMAL: Priority = Priority(0);
+    pub const HIGH: Priority = Priority(100);
+
+    /// Returns the priority of the task currently running on this thread.
+    ///
+    /// Returns `Priority::NORMAL` when called outside of a `::scope`.
+    pub fn current() -> Priority {
+        CURRENT_PRIORITY.with(|p| p.get())
+    }
+
+    /// Runs `f` with `self` set as the current priority for this thread,
+    /// restoring the previous priority afterwards (even if `f` panics).
+    ///
+    /// Executors should wrap each call to a task's `poll` with this.
+    pub fn scope<R, F: FnOnce() -> R>(self, f: F) -> R {
+        struct Restore(Priority);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT_PRIORITY.with(|p| p.set(self.0));
+            }
+        }
+
+        let _restore = Restore(CURRENT_PRIORITY.with(|p| p.replace(self)));
+        f()
+    }
+}
+
+impl Default for Priority {
+    fn default() -> Priority {
+        Priority::NORMAL
+    }
+}
+
+impl From<i32> for Priority {
+    fn from(p: i32) -> Priority {
+        Priority(p)
+    }
+}
+
+impl From<Priority> for QueuePriority {
+    /// Maps a priority tag onto the three levels of `cl_khr_priority_hints`:
+    /// `HIGH` and above to `High`, `LOW` and below to `Low`, and anything in
+    /// between to `Medium`.
+    fn from(p: Priority) -> QueuePriority {
+        if p >= Priority::HIGH {
+            QueuePriority::High
+        } else if p <= Priority::LOW {
+            QueuePriority::Low
+        } else {
+            QueuePriority::Medium
+        }
+    }
+}
+
+
+/// A command enqueue deferred until its queue is chosen.
+type DeferredCmd = Box<dyn FnOnce(&Queue) -> OclResult<()> + Send>;
+
+/// A command waiting to be dispatched.
+struct PendingCmd {
+    priority: Priority,
+    seq: u64,
+    cmd: DeferredCmd,
+}
+
+#[derive(Default)]
+struct Inner {
+    pending: VecDeque<PendingCmd>,
+    next_seq: u64,
+    aging_interval: u64,
+}
+
+impl Inner {
+    /// Returns the index of the pending command with the highest effective
+    /// priority, the oldest winning ties.
+    fn next_idx(&self) -> Option<usize> {
+        let mut best: Option<(usize, i64)> = None;
+        for (idx, pc) in self.pending.iter().enumerate() {
+            let age_bonus = (self.next_seq - pc.seq).checked_div(self.aging_interval)
+                .unwrap_or(0);
+            let eff = i64::from(pc.priority.0) + age_bonus as i64;
+            if best.map(|(_, b)| eff > b).unwrap_or(true) {
+                best = Some((idx, eff));
+            }
+        }
+        best.map(|(idx, _)| idx)
+    }
+}
+
+
+/// A shareable, priority-ordered queue of pending commands.
+///
+/// Commands are dispatched in order of priority, first-in-first-out among
+/// commands of equal priority. To prevent starvation, a command's effective
+/// priority is raised by one for every `aging_interval` commands submitted
+/// after it (disabled when set to zero, the default).
+///
+/// Cloning a `FairScheduler` returns a handle to the same queue.
+#[derive(Clone)]
+pub struct FairScheduler {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl FairScheduler {
+    /// Returns a new, empty scheduler.
+    pub fn new() -> FairScheduler {
+        FairScheduler { inner: Arc::new(Mutex::new(Inner::default())) }
+    }
+
+    /// Sets the number of later submissions after which a pending command's
+    /// effective priority is raised by one.
+    pub fn set_aging_interval(&self, interval: u64) {
+        self.inner.lock().unwrap().aging_interval = interval;
+    }
+
+    /// Submits a command tagged with the current task's priority (see
+    /// `Priority::current`).
+    pub fn submit<F>(&self, cmd: F)
+            where F: FnOnce(&Queue) -> OclResult<()> + Send + 'static {
+        self.submit_with_priority(Priority::current(), cmd)
+    }
+
+    /// Submits a command with an explicit priority.
+    pub fn submit_with_priority<F>(&self, priority: Priority, cmd: F)
+            where F: FnOnce(&Queue) -> OclResult<()> + Send + 'static {
+        let mut inner = self.inner.lock().unwrap();
+        let seq = inner.next_seq;
+        inner.next_seq += 1;
+        inner.pending.push_back(PendingCmd { priority, seq, cmd: Box::new(cmd) });
+    }
+
+    /// Removes and returns the pending command with the highest effective
+    /// priority.
+    fn pop(&self) -> Option<PendingCmd> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_idx().and_then(|idx| inner.pending.remove(idx))
+    }
+
+    /// Dispatches the single highest priority pending command to `queue`.
+    ///
+    /// Returns `Ok(false)` if no commands were pending.
+    pub fn dispatch_one(&self, queue: &Queue) -> OclResult<bool> {
+        match self.pop() {
+            Some(pc) => {
+                pc.priority.scope(|| (pc.cmd)(queue))?;
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    /// Dispatches all pending commands to `queue` in priority order,
+    /// returning the number dispatched.
+    ///
+    /// Each command runs within a `Priority::scope` of its own tag so that
+    /// any futures it creates inherit it.
+    pub fn dispatch(&self, queue: &Queue) -> OclResult<usize> {
+        let mut count = 0;
+        while self.dispatch_one(queue)? {
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Returns the priority of the command which would be dispatched next.
+    pub fn peek_priority(&self) -> Option<Priority> {
+        let inner = self.inner.lock().unwrap();
+        inner.next_idx().map(|idx| inner.pending[idx].priority)
+    }
+
+    /// Returns the number of pending commands.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().pending.len()
+    }
+
+    /// Returns true if no commands are pending.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for FairScheduler {
+    fn default() -> FairScheduler {
+        FairScheduler::new()
+    }
+}
+
+impl std::fmt::Debug for FairScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("FairScheduler")
+            .field("pending", &self.len())
+            .finish()
+    }
+}
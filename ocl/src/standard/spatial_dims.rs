@@ -6,6 +6,8 @@ use num_traits::{Num, ToPrimitive};
 use crate::error::{Result as OclResult};
 use crate::standard::{MemLen, WorkDims};
 use crate::core::util;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer, Deserialize, Deserializer, de::Error as _};
 
 
 #[derive(Debug, Fail)]
@@ -121,6 +123,28 @@ impl SpatialDims {
         Ok(util::padded_len(self.to_len(), incr))
     }
 
+    /// Returns these dimensions with each rounded up to the nearest multiple
+    /// of the corresponding dimension of `multiple`.
+    ///
+    /// Used to pad a global work size to a multiple of the local work size.
+    /// Dimensions are returned unchanged if `multiple` is unspecified or has
+    /// a different number of dimensions.
+    pub fn padded_to(&self, multiple: &SpatialDims) -> SpatialDims {
+        let pad = |len: usize, incr: usize| {
+            if incr == 0 { len } else { util::padded_len(len, incr) }
+        };
+        match (*self, *multiple) {
+            (SpatialDims::One(x), SpatialDims::One(mx)) => SpatialDims::One(pad(x, mx)),
+            (SpatialDims::Two(x, y), SpatialDims::Two(mx, my)) => {
+                SpatialDims::Two(pad(x, mx), pad(y, my))
+            },
+            (SpatialDims::Three(x, y, z), SpatialDims::Three(mx, my, mz)) => {
+                SpatialDims::Three(pad(x, mx), pad(y, my), pad(z, mz))
+            },
+            (dims, _) => dims,
+        }
+    }
+
     /// Returns `true` if this `SpatialDims` is an `Unspecified` variant.
     pub fn is_unspecified(&self) -> bool {
         if let SpatialDims::Unspecified = *self { true } else { false }
@@ -325,6 +349,46 @@ impl<'a, T: Num + ToPrimitive + Debug + Copy> From<&'a [T; 3]> for SpatialDims {
     }
 }
 
+/// Serialized as a list of zero to three lengths (`[]`, `[1024]`,
+/// `[64, 64]`, etc.).
+#[cfg(feature = "serde")]
+impl Serialize for SpatialDims {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            SpatialDims::Unspecified => serializer.collect_seq(&[] as &[usize]),
+            SpatialDims::One(d0) => serializer.collect_seq(&[d0]),
+            SpatialDims::Two(d0, d1) => serializer.collect_seq(&[d0, d1]),
+            SpatialDims::Three(d0, d1, d2) => serializer.collect_seq(&[d0, d1, d2]),
+        }
+    }
+}
+
+/// Deserialized from either a list of zero to three lengths or a single
+/// length.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for SpatialDims {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SpatialDims, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Len(usize),
+            Lens(Vec<usize>),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Len(d0) => Ok(SpatialDims::One(d0)),
+            Repr::Lens(lens) => match lens[..] {
+                [] => Ok(SpatialDims::Unspecified),
+                [d0] => Ok(SpatialDims::One(d0)),
+                [d0, d1] => Ok(SpatialDims::Two(d0, d1)),
+                [d0, d1, d2] => Ok(SpatialDims::Three(d0, d1, d2)),
+                _ => Err(D::Error::custom(format!("expected at most three dimensions, \
+                    found {}", lens.len()))),
+            },
+        }
+    }
+}
+
 
 #[inline]
 pub fn to_usize<T: Num + ToPrimitive + Debug + Copy>(val: T) -> usize {
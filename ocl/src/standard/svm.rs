@@ -0,0 +1,878 @@
+//! Shared virtual memory (OpenCL 2.0+).
+//!
+//! An `SvmBuffer` is allocated with `clSVMAlloc` and, unlike a `Buffer`, has
+//! the same address on the host and on the device. Pointers into it may be
+//! stored within the buffer itself (or within other SVM buffers) and
+//! followed by kernels, allowing pointer-based data structures such as
+//! linked lists and trees to be shared with the device.
+//!
+//! Coarse-grained buffers must be mapped (see `SvmBuffer::map`) before the
+//! host accesses them and unmapped before kernels use them. Fine-grained
+//! buffers (`FineSvmBuffer`) may be accessed by the host directly, as a
+//! slice, on devices which support them. Fine-grained buffers created with
+//! `FineSvmBuffer::with_atomics` may be viewed as a slice of host atomics
+//! (see `FineSvmBuffer::atomics`) and updated by the host and by kernels at
+//! the same time. Kernels which follow pointers into SVM buffers other than
+//! their arguments must be told about those buffers: record the pointers
+//! stored in a buffer with `SvmBuffer::link` and kernels will pass them on
+//! automatically (or use `Kernel::set_exec_info` with
+//! `KernelExecInfo::SvmPtrs` directly).
+//!
+//! Copy, fill, read, write, map and unmap commands may be enqueued
+//! asynchronously, with events, using `SvmBuffer::cmd`.
+//!
+//! Requires the `opencl_version_2_0` feature and a device supporting
+//! `DeviceSvmCapabilities::COARSE_GRAIN_BUFFER` or `FINE_GRAIN_BUFFER` (see
+//! `Device::svm_capabilities`).
+
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::slice;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Arc, Weak, Mutex};
+use std::sync::atomic::{AtomicI32, AtomicU32, AtomicI64, AtomicU64};
+use crate::core::{self, OclPrm, MemFlags, MapFlags, DeviceSvmCapabilities, ClNullEventPtr};
+use crate::core::ffi::c_void;
+use crate::ffi::{cl_command_queue, cl_uint};
+use crate::error::{Error as OclError, Result as OclResult};
+use crate::standard::{Context, Queue, Event, ClWaitListPtrEnum, ClNullEventPtrEnum};
+use crate::standard::tracking::Tracker;
+
+
+type FreeFn = Box<dyn FnOnce() + Send>;
+
+/// Data passed to `_free_svm` when an allocation is dropped.
+struct FreeData {
+    context: Context,
+    on_free: Option<FreeFn>,
+}
+
+/// Frees the allocations passed by `clEnqueueSVMFree` then calls the
+/// `FreeFn` set with `SvmBuffer::on_free`, if any.
+extern "C" fn _free_svm(_queue: cl_command_queue, num_svm_pointers: cl_uint,
+        svm_pointers: *const *const c_void, user_data: *mut c_void) {
+    core::guard_callback("_free_svm", || {
+        if user_data.is_null() { return; }
+        let data = unsafe { Box::from_raw(user_data as *mut FreeData) };
+        let ptrs = unsafe { slice::from_raw_parts(svm_pointers, num_svm_pointers as usize) };
+        for &ptr in ptrs {
+            unsafe { core::svm_free(&data.context, ptr as *mut c_void); }
+        }
+        if let Some(f) = data.on_free { f(); }
+    })
+}
+
+
+/// A shared virtual memory allocation, freed when the last reference is
+/// dropped.
+pub(crate) struct SvmAlloc {
+    ptr: *mut c_void,
+    context: Context,
+    // The allocation is freed once this queue has completed the commands
+    // enqueued before it was dropped.
+    queue: Queue,
+    // Allocations which this one holds pointers into (see `SvmBuffer::link`).
+    links: Mutex<Vec<Weak<SvmAlloc>>>,
+    on_free: Mutex<Option<FreeFn>>,
+}
+
+impl SvmAlloc {
+    /// Returns the address of the allocation.
+    pub(crate) fn as_ptr(&self) -> *mut c_void {
+        self.ptr
+    }
+}
+
+/// Returns every live allocation reachable through the links of `roots`,
+/// excluding the roots themselves.
+pub(crate) fn linked_allocs<'a, I>(roots: I) -> Vec<Arc<SvmAlloc>>
+        where I: IntoIterator<Item = &'a Arc<SvmAlloc>> {
+    let mut stack: Vec<Arc<SvmAlloc>> = roots.into_iter().cloned().collect();
+    let mut visited: HashSet<usize> = stack.iter().map(|a| a.ptr as usize).collect();
+    let mut linked = Vec::new();
+
+    while let Some(alloc) = stack.pop() {
+        let links = alloc.links.lock().expect("SvmAlloc links poisoned");
+        for link in links.iter().filter_map(Weak::upgrade) {
+            if visited.insert(link.ptr as usize) {
+                linked.push(link.clone());
+                stack.push(link);
+            }
+        }
+    }
+    linked
+}
+
+// The allocation is only accessed through `SvmBuffer`, which requires a
+// mutable reference to map it.
+unsafe impl Send for SvmAlloc {}
+unsafe impl Sync for SvmAlloc {}
+
+impl fmt::Debug for SvmAlloc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SvmAlloc")
+            .field("ptr", &self.ptr)
+            .field("context", &self.context)
+            .field("queue", &self.queue)
+            .finish()
+    }
+}
+
+impl Drop for SvmAlloc {
+    fn drop(&mut self) {
+        let on_free = self.on_free.get_mut().unwrap_or_else(|e| e.into_inner()).take();
+        let data = Box::into_raw(Box::new(FreeData { context: self.context.clone(), on_free }));
+        let enqueued = unsafe {
+            core::enqueue_svm_free(self.queue.as_core(), &[self.ptr], Some(_free_svm),
+                data as *mut c_void, None::<ClWaitListPtrEnum>, None::<ClNullEventPtrEnum>,
+                Some(&self.queue.device_version()))
+        };
+        match enqueued {
+            Ok(()) => { let _ = self.queue.flush(); },
+            Err(_) => {
+                // `clSVMFree` does not wait for commands using the allocation:
+                let data = unsafe { Box::from_raw(data) };
+                let _ = self.queue.finish();
+                unsafe { core::svm_free(&self.context, self.ptr); }
+                if let Some(f) = data.on_free { f(); }
+            },
+        }
+    }
+}
+
+
+/// A coarse-grained shared virtual memory buffer of `len` elements of `T`.
+///
+/// The buffer is zeroed on creation. Set it as a kernel argument with
+/// `KernelBuilder::arg_svm` or `Kernel::set_arg_svm`; kernels keep the
+/// allocation alive for as long as it is set as an argument.
+///
+/// Once the last reference to the allocation is dropped, it is freed without
+/// blocking after the queue it was created with has completed all commands
+/// enqueued before that point (see `::on_free`). Commands using the buffer
+/// on other queues must be complete before it is dropped.
+///
+/// ```rust,ignore
+/// let mut nodes = SvmBuffer::<u64>::new(&queue, 1024)?;
+/// {
+///     let mut map = nodes.map()?;
+///     map[0] = 42;
+/// } // Unmapped here.
+/// let kernel = Kernel::builder().program(&program).name("walk").queue(queue)
+///     .global_work_size(1).arg_svm(&nodes).build()?;
+/// ```
+#[derive(Debug)]
+pub struct SvmBuffer<T: OclPrm> {
+    alloc: Arc<SvmAlloc>,
+    len: usize,
+    queue: Queue,
+    _t: PhantomData<T>,
+}
+
+impl<T: OclPrm> SvmBuffer<T> {
+    /// Returns a new, zeroed, readable and writable buffer of `len` elements
+    /// with `queue` as its default queue.
+    pub fn new(queue: &Queue, len: usize) -> OclResult<SvmBuffer<T>> {
+        SvmBuffer::with_flags(queue, MemFlags::new().read_write(), len)
+    }
+
+    /// Returns a new, zeroed buffer of `len` elements created with `flags`.
+    ///
+    /// `flags` may contain only one of `MemFlags::READ_WRITE`, `WRITE_ONLY`,
+    /// or `READ_ONLY`, describing device access.
+    pub fn with_flags(queue: &Queue, flags: MemFlags, len: usize) -> OclResult<SvmBuffer<T>> {
+        let mut buffer = SvmBuffer::alloc(queue, flags, len,
+            DeviceSvmCapabilities::COARSE_GRAIN_BUFFER, mem::align_of::<T>())?;
+        for val in buffer.map_with_flags(MapFlags::new().write_invalidate_region())?.iter_mut() {
+            *val = T::default();
+        }
+        Ok(buffer)
+    }
+
+    /// Allocates an uninitialized buffer aligned to `align` bytes (at least
+    /// that of `T`), first checking that the device has the capabilities
+    /// `required`.
+    fn alloc(queue: &Queue, flags: MemFlags, len: usize, required: DeviceSvmCapabilities,
+            align: usize) -> OclResult<SvmBuffer<T>> {
+        debug_assert!(align >= mem::align_of::<T>() && align.is_power_of_two());
+        if len == 0 {
+            return Err("SvmBuffer::new: The length must be non-zero.".into());
+        }
+        let caps = queue.device().svm_capabilities()?;
+        if !caps.contains(required) {
+            return Err(format!("SvmBuffer::new: The device '{}' does not support the SVM \
+                capabilities: {:?} (supported: {:?}).", queue.device().name()?,
+                required - caps, caps).into());
+        }
+
+        let context = queue.context();
+        let size = len.checked_mul(mem::size_of::<T>())
+            .ok_or("SvmBuffer::new: The buffer size overflows 'usize'.")?;
+        let ptr = core::svm_alloc(&context, flags, size, align as u32, None)?;
+        let alloc = Arc::new(SvmAlloc { ptr, context, queue: queue.clone(),
+            links: Mutex::new(Vec::new()), on_free: Mutex::new(None) });
+        Ok(SvmBuffer { alloc, len, queue: queue.clone(), _t: PhantomData })
+    }
+
+    /// Returns a new, readable and writable buffer containing a copy of
+    /// `data`.
+    pub fn from_slice(queue: &Queue, data: &[T]) -> OclResult<SvmBuffer<T>> {
+        let mut buffer = SvmBuffer::new(queue, data.len())?;
+        buffer.write_from(data)?;
+        Ok(buffer)
+    }
+
+    /// Maps the whole buffer for reading and writing by the host, blocking
+    /// until the map is complete.
+    ///
+    /// The buffer is unmapped when the returned `SvmMap` is dropped or
+    /// unmapped.
+    pub fn map(&mut self) -> OclResult<SvmMap<'_, T>> {
+        self.map_with_flags(MapFlags::new().read().write())
+    }
+
+    /// Maps the whole buffer for host access with `flags`, blocking until
+    /// the map is complete.
+    pub fn map_with_flags(&mut self, flags: MapFlags) -> OclResult<SvmMap<'_, T>> {
+        unsafe {
+            core::enqueue_svm_map(self.queue.as_core(), true, flags, self.alloc.as_ptr(),
+                self.len * mem::size_of::<T>(), None::<ClWaitListPtrEnum>, None::<&mut Event>,
+                Some(&self.queue.device_version()))?;
+        }
+        Ok(SvmMap { buffer: self, unmapped: false })
+    }
+
+    /// Copies `data` to the start of the buffer.
+    pub fn write_from(&mut self, data: &[T]) -> OclResult<()> {
+        if data.len() > self.len {
+            return Err(format!("SvmBuffer::write_from: The source length ({}) exceeds the \
+                buffer length ({}).", data.len(), self.len).into());
+        }
+        let flags = if data.len() == self.len {
+            MapFlags::new().write_invalidate_region()
+        } else {
+            MapFlags::new().write()
+        };
+        let mut map = self.map_with_flags(flags)?;
+        map[..data.len()].copy_from_slice(data);
+        map.unmap().map(|_| ())
+    }
+
+    /// Copies the start of the buffer into `data`.
+    pub fn read_into(&mut self, data: &mut [T]) -> OclResult<()> {
+        if data.len() > self.len {
+            return Err(format!("SvmBuffer::read_into: The destination length ({}) exceeds \
+                the buffer length ({}).", data.len(), self.len).into());
+        }
+        let map = self.map_with_flags(MapFlags::new().read())?;
+        data.copy_from_slice(&map[..data.len()]);
+        map.unmap().map(|_| ())
+    }
+
+    /// Returns the length of the buffer, in elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the buffer is empty. Always false.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the device (and host) address of the first element.
+    ///
+    /// The address may be stored in SVM buffers and followed by kernels. The
+    /// host may only dereference it while the buffer is mapped.
+    pub fn as_ptr(&self) -> *const T {
+        self.alloc.as_ptr() as *const T
+    }
+
+    /// Returns the device (and host) address of the first element.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.alloc.as_ptr() as *mut T
+    }
+
+    /// Returns the default queue, used for mapping and unmapping.
+    pub fn default_queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    /// Sets the default queue, used for mapping and unmapping.
+    ///
+    /// `queue` must belong to the context the buffer was created in.
+    pub fn set_default_queue(&mut self, queue: Queue) -> &mut SvmBuffer<T> {
+        self.queue = queue;
+        self
+    }
+
+    /// Returns the context the buffer was allocated in.
+    pub fn context(&self) -> &Context {
+        &self.alloc.context
+    }
+
+    /// Calls `f` once the allocation has been freed.
+    ///
+    /// The allocation is freed once every reference to it (including those
+    /// held by kernels) has been dropped and the queue it was created with
+    /// has completed all commands enqueued before then. `f` is called on a
+    /// thread owned by the OpenCL runtime, so it should return quickly and
+    /// must not call blocking OpenCL functions. Closures set by multiple
+    /// calls are all called, in order.
+    ///
+    /// ```rust,ignore
+    /// let (tx, rx) = mpsc::channel();
+    /// buffer.on_free(move || { let _ = tx.send(()); });
+    /// drop(buffer);
+    /// rx.recv()?; // The buffer's memory has been released.
+    /// ```
+    pub fn on_free<F>(&self, f: F) where F: FnOnce() + Send + 'static {
+        let mut on_free = self.alloc.on_free.lock().expect("SvmAlloc on_free poisoned");
+        *on_free = Some(match on_free.take() {
+            Some(prev) => Box::new(move || { prev(); f(); }),
+            None => Box::new(f),
+        });
+    }
+
+    /// Returns a command builder used to enqueue copy, fill, read, write, map
+    /// and unmap commands on the buffer.
+    ///
+    /// ```rust,ignore
+    /// let mut event = Event::empty();
+    /// buffer.cmd().fill(0.0).enew(&mut event).enq()?;
+    /// buffer.cmd().copy(&other, 0).offset(16).len(16).ewait(&event).enq()?;
+    /// ```
+    pub fn cmd(&self) -> SvmCmd<'_, T> {
+        SvmCmd::new(self)
+    }
+
+    /// Records that this buffer holds pointers into `target`.
+    ///
+    /// Kernels with this buffer as an argument make every buffer reachable
+    /// through its links available to the device (using
+    /// `KernelExecInfo::SvmPtrs`) and keep those buffers alive while the
+    /// argument is set. Links must be recorded before the buffer is set as an
+    /// argument. A link does not keep `target` alive by itself.
+    ///
+    /// ```rust,ignore
+    /// // Each of `nodes` points to a `values` element:
+    /// nodes.link(&values);
+    /// let kernel = pro_que.kernel_builder("walk").arg_svm(&nodes).build()?;
+    /// ```
+    pub fn link<U, B>(&self, target: &B) -> &SvmBuffer<T>
+            where U: OclPrm, B: AsRef<SvmBuffer<U>> {
+        let target = Arc::downgrade(&target.as_ref().alloc);
+        let mut links = self.alloc.links.lock().expect("SvmAlloc links poisoned");
+        if !links.iter().any(|link| link.ptr_eq(&target)) {
+            links.push(target);
+        }
+        self
+    }
+
+    /// Returns a reference to the allocation, keeping it alive.
+    pub(crate) fn svm_alloc(&self) -> Arc<SvmAlloc> {
+        self.alloc.clone()
+    }
+}
+
+impl<T: OclPrm> AsRef<SvmBuffer<T>> for SvmBuffer<T> {
+    fn as_ref(&self) -> &SvmBuffer<T> {
+        self
+    }
+}
+
+
+/// A fine-grained shared virtual memory buffer of `len` elements of `T`,
+/// accessible by the host directly as a slice without mapping.
+///
+/// Requires a device supporting `DeviceSvmCapabilities::FINE_GRAIN_BUFFER`.
+/// Set it as a kernel argument as for an `SvmBuffer`.
+///
+/// ### Host and device access
+///
+/// Writes by the host before a kernel is enqueued are visible to that
+/// kernel, and writes by a kernel are visible to the host once the kernel's
+/// event is complete (or its queue is finished). While a kernel using the
+/// buffer may be running, the host must not dereference the buffer as a
+/// slice, as the kernel may be writing to it. The host may however access
+/// elements which no running kernel accesses through raw pointers (see
+/// `::as_mut_ptr`), as fine-grained buffers are coherent between the host
+/// and the device. Accessing an element a running kernel also accesses is a
+/// data race, unless the buffer was created with `::with_atomics` and both
+/// the host and the kernel only use atomic operations on it (see
+/// `::atomics`), the kernel's with `memory_scope_all_svm_devices`.
+///
+/// ```rust,ignore
+/// let mut buffer = FineSvmBuffer::<f32>::new(&queue, 1024)?;
+/// buffer[0] = 1.0;
+/// let mut event = Event::empty();
+/// kernel.cmd().enew(&mut event).enq()?;
+/// event.wait_for()?;
+/// println!("{}", buffer[0]);
+/// ```
+#[derive(Debug)]
+pub struct FineSvmBuffer<T: OclPrm> {
+    buffer: SvmBuffer<T>,
+    atomics: bool,
+}
+
+impl<T: OclPrm> FineSvmBuffer<T> {
+    /// Returns a new, zeroed, readable and writable fine-grained buffer of
+    /// `len` elements with `queue` as its default queue.
+    pub fn new(queue: &Queue, len: usize) -> OclResult<FineSvmBuffer<T>> {
+        FineSvmBuffer::create(queue, len, false, mem::align_of::<T>())
+    }
+
+    /// Returns a new, zeroed fine-grained buffer of `len` elements which may
+    /// be updated concurrently by the host and by kernels using atomics.
+    ///
+    /// Requires a device supporting `DeviceSvmCapabilities::ATOMICS`.
+    pub fn with_atomics(queue: &Queue, len: usize) -> OclResult<FineSvmBuffer<T>>
+            where T: SvmAtomicPrm {
+        let align = mem::align_of::<T>().max(mem::align_of::<T::Atomic>());
+        FineSvmBuffer::create(queue, len, true, align)
+    }
+
+    /// Returns a new, readable and writable fine-grained buffer containing a
+    /// copy of `data`.
+    pub fn from_slice(queue: &Queue, data: &[T]) -> OclResult<FineSvmBuffer<T>> {
+        let mut buffer = FineSvmBuffer::new(queue, data.len())?;
+        buffer.copy_from_slice(data);
+        Ok(buffer)
+    }
+
+    fn create(queue: &Queue, len: usize, atomics: bool, align: usize)
+            -> OclResult<FineSvmBuffer<T>> {
+        let (flags, required) = if atomics {
+            (MemFlags::new().read_write().svm_fine_grain_buffer().svm_atomics(),
+                DeviceSvmCapabilities::FINE_GRAIN_BUFFER | DeviceSvmCapabilities::ATOMICS)
+        } else {
+            (MemFlags::new().read_write().svm_fine_grain_buffer(),
+                DeviceSvmCapabilities::FINE_GRAIN_BUFFER)
+        };
+        let buffer = SvmBuffer::alloc(queue, flags, len, required, align)?;
+        let mut buffer = FineSvmBuffer { buffer, atomics };
+        for val in buffer.iter_mut() {
+            *val = T::default();
+        }
+        Ok(buffer)
+    }
+
+    /// Returns true if the buffer was created with `::with_atomics`.
+    pub fn has_atomics(&self) -> bool {
+        self.atomics
+    }
+
+    /// Returns the buffer's elements as host atomics.
+    ///
+    /// The returned slice may be shared between host threads and used while
+    /// kernels update the buffer with `memory_scope_all_svm_devices` atomics.
+    /// Returns an error unless the buffer was created with `::with_atomics`.
+    ///
+    /// ```rust,ignore
+    /// let mut counter = FineSvmBuffer::<i32>::with_atomics(&queue, 1)?;
+    /// kernel.set_arg_svm(0, &counter)?;
+    /// unsafe { kernel.enq()?; }
+    /// // Observe the kernel's progress while it runs:
+    /// let count = counter.atomics()?[0].load(Ordering::SeqCst);
+    /// ```
+    pub fn atomics(&mut self) -> OclResult<&[T::Atomic]> where T: SvmAtomicPrm {
+        if !self.atomics {
+            return Err("FineSvmBuffer::atomics: The buffer was not created with \
+                'FineSvmBuffer::with_atomics'.".into());
+        }
+        Ok(unsafe {
+            slice::from_raw_parts(self.buffer.as_ptr() as *const T::Atomic, self.buffer.len)
+        })
+    }
+
+    /// Returns the device (and host) address of the first element.
+    pub fn as_ptr(&self) -> *const T {
+        self.buffer.as_ptr()
+    }
+
+    /// Returns the device (and host) address of the first element.
+    ///
+    /// Elements which no running kernel accesses may be read and written
+    /// through the pointer while kernels use the buffer.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.buffer.as_mut_ptr()
+    }
+
+    /// Returns the default queue.
+    pub fn default_queue(&self) -> &Queue {
+        self.buffer.default_queue()
+    }
+
+    /// Returns the context the buffer was allocated in.
+    pub fn context(&self) -> &Context {
+        self.buffer.context()
+    }
+
+    /// Returns a command builder used to enqueue copy, fill, read and write
+    /// commands on the buffer (see `SvmBuffer::cmd`).
+    pub fn cmd(&mut self) -> SvmCmd<'_, T> {
+        SvmCmd::new(&self.buffer)
+    }
+
+    /// Calls `f` once the allocation has been freed (see
+    /// `SvmBuffer::on_free`).
+    pub fn on_free<F>(&self, f: F) where F: FnOnce() + Send + 'static {
+        self.buffer.on_free(f)
+    }
+
+    /// Records that this buffer holds pointers into `target` (see
+    /// `SvmBuffer::link`).
+    pub fn link<U, B>(&self, target: &B) -> &FineSvmBuffer<T>
+            where U: OclPrm, B: AsRef<SvmBuffer<U>> {
+        self.buffer.link(target);
+        self
+    }
+}
+
+impl<T: OclPrm> Deref for FineSvmBuffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.buffer.as_ptr(), self.buffer.len) }
+    }
+}
+
+impl<T: OclPrm> DerefMut for FineSvmBuffer<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.buffer.as_mut_ptr(), self.buffer.len) }
+    }
+}
+
+impl<T: OclPrm> AsRef<SvmBuffer<T>> for FineSvmBuffer<T> {
+    fn as_ref(&self) -> &SvmBuffer<T> {
+        &self.buffer
+    }
+}
+
+
+/// The operation an `SvmCmd` enqueues.
+enum SvmCmdKind<'c, T: OclPrm> {
+    Unspecified,
+    Copy { dst: &'c SvmBuffer<T>, dst_offset: usize },
+    Fill { pattern: T },
+    Read { dst: &'c mut [T] },
+    Write { src: &'c [T] },
+    Map { flags: MapFlags },
+    Unmap,
+}
+
+impl<'c, T: OclPrm> SvmCmdKind<'c, T> {
+    fn is_unspec(&self) -> bool {
+        if let SvmCmdKind::Unspecified = *self { true } else { false }
+    }
+}
+
+/// A shared virtual memory buffer command builder used to enqueue copies,
+/// fills, reads, writes, maps and unmaps (see `SvmBuffer::cmd`).
+///
+/// Commands operate on the whole buffer unless a region is selected with
+/// `::offset` and `::len` (both in elements).
+///
+/// ### Panics
+///
+/// Only one operation (`::copy`, `::fill`, etc.) may be specified per
+/// command.
+#[must_use = "commands do nothing unless enqueued"]
+pub struct SvmCmd<'c, T: OclPrm> {
+    buffer: &'c SvmBuffer<T>,
+    queue: &'c Queue,
+    kind: SvmCmdKind<'c, T>,
+    block: bool,
+    offset: usize,
+    len: Option<usize>,
+    ewait: Option<ClWaitListPtrEnum<'c>>,
+    enew: Option<ClNullEventPtrEnum<'c>>,
+}
+
+impl<'c, T: OclPrm> SvmCmd<'c, T> {
+    fn new(buffer: &'c SvmBuffer<T>) -> SvmCmd<'c, T> {
+        SvmCmd {
+            buffer,
+            queue: &buffer.queue,
+            kind: SvmCmdKind::Unspecified,
+            block: true,
+            offset: 0,
+            len: None,
+            ewait: None,
+            enew: None,
+        }
+    }
+
+    fn set_kind(mut self, kind: SvmCmdKind<'c, T>) -> SvmCmd<'c, T> {
+        assert!(self.kind.is_unspec(), "ocl::SvmCmd: Operation kind already set for this \
+            command.");
+        self.kind = kind;
+        self
+    }
+
+    /// Specifies that this command will copy the selected region into `dst`,
+    /// starting at element `dst_offset`.
+    pub fn copy<B>(self, dst: &'c B, dst_offset: usize) -> SvmCmd<'c, T>
+            where B: AsRef<SvmBuffer<T>> {
+        self.set_kind(SvmCmdKind::Copy { dst: dst.as_ref(), dst_offset })
+    }
+
+    /// Specifies that this command will fill the selected region with
+    /// `pattern`.
+    ///
+    /// As OpenCL has no blocking fill, a blocking fill waits for the
+    /// command's event.
+    pub fn fill(self, pattern: T) -> SvmCmd<'c, T> {
+        self.set_kind(SvmCmdKind::Fill { pattern })
+    }
+
+    /// Specifies that this command will read the buffer, starting at the
+    /// selected offset, into `dst`.
+    pub fn read(self, dst: &'c mut [T]) -> SvmCmd<'c, T> {
+        let len = dst.len();
+        self.set_kind(SvmCmdKind::Read { dst }).len(len)
+    }
+
+    /// Specifies that this command will write `src` to the buffer, starting at
+    /// the selected offset.
+    pub fn write(self, src: &'c [T]) -> SvmCmd<'c, T> {
+        let len = src.len();
+        self.set_kind(SvmCmdKind::Write { src }).len(len)
+    }
+
+    /// Specifies that this command will map the selected region of a
+    /// coarse-grained buffer for host access.
+    ///
+    /// The region must be unmapped, with `::unmap` and the same offset,
+    /// before it is used by the device. Prefer `SvmBuffer::map` unless the
+    /// map must be enqueued asynchronously.
+    pub fn map(self, flags: MapFlags) -> SvmCmd<'c, T> {
+        self.set_kind(SvmCmdKind::Map { flags })
+    }
+
+    /// Specifies that this command will unmap a region previously mapped with
+    /// `::map`.
+    ///
+    /// `::len` is ignored. As OpenCL has no blocking unmap, a blocking unmap
+    /// waits for the command's event.
+    pub fn unmap(self) -> SvmCmd<'c, T> {
+        self.set_kind(SvmCmdKind::Unmap)
+    }
+
+    /// Specifies a queue to use for this call only.
+    pub fn queue(mut self, queue: &'c Queue) -> SvmCmd<'c, T> {
+        self.queue = queue;
+        self
+    }
+
+    /// Specifies the first element of the region operated on. Default is 0.
+    pub fn offset(mut self, offset: usize) -> SvmCmd<'c, T> {
+        self.offset = offset;
+        self
+    }
+
+    /// Specifies the number of elements operated on. Defaults to the rest of
+    /// the buffer (or the length of the data for reads and writes).
+    pub fn len(mut self, len: usize) -> SvmCmd<'c, T> {
+        self.len = Some(len);
+        self
+    }
+
+    /// Specifies whether or not to block the current thread until completion.
+    ///
+    /// Default is `block = true`.
+    ///
+    /// ## Safety
+    ///
+    /// When performing non-blocking commands, the caller must ensure that
+    /// the data (or the affected region of the buffer) is not accessed by the
+    /// host until the command completes.
+    pub unsafe fn block(mut self, block: bool) -> SvmCmd<'c, T> {
+        self.block = block;
+        self
+    }
+
+    /// Specifies an event or list of events to wait on before the command
+    /// will run.
+    pub fn ewait<'e, Ewl>(mut self, ewait: Ewl) -> SvmCmd<'c, T>
+            where 'e: 'c, Ewl: Into<ClWaitListPtrEnum<'e>> {
+        self.ewait = Some(ewait.into());
+        self
+    }
+
+    /// Specifies the destination to store a new, optionally created event
+    /// associated with this command.
+    pub fn enew<'e, En>(mut self, enew: En) -> SvmCmd<'c, T>
+            where 'e: 'c, En: Into<ClNullEventPtrEnum<'e>> {
+        self.enew = Some(enew.into());
+        self
+    }
+
+    /// Enqueues this command.
+    pub fn enq(mut self) -> OclResult<()> {
+        let buffer_len = self.buffer.len;
+        let len = self.len.unwrap_or_else(|| buffer_len.saturating_sub(self.offset));
+        if self.offset.checked_add(len).map_or(true, |end| end > buffer_len) {
+            return Err(format!("SvmCmd::enq: The region (offset: {}, len: {}) exceeds the \
+                buffer length ({}).", self.offset, len, buffer_len).into());
+        }
+        match self.kind {
+            SvmCmdKind::Copy { dst, dst_offset } => {
+                if dst_offset.checked_add(len).map_or(true, |end| end > dst.len) {
+                    return Err("SvmCmd::enq: The copy exceeds the destination buffer \
+                        length.".into());
+                }
+            },
+            SvmCmdKind::Unspecified => return Err("SvmCmd::enq: No operation specified. \
+                Use '.copy(...)', '.fill(...)', etc. before calling '.enq()'.".into()),
+            _ => (),
+        }
+
+        let queue = self.queue;
+        let ptr = unsafe { (self.buffer.alloc.as_ptr() as *mut T).add(self.offset) };
+        let size = len * mem::size_of::<T>();
+        let block = self.block;
+        let device_version = queue.device_version();
+
+        // Fills and unmaps have no blocking form, so blocking ones wait on an
+        // event of their own, copied to the caller's destination afterwards:
+        let mut event = Event::empty();
+        let waits = block && match self.kind {
+            SvmCmdKind::Fill { .. } | SvmCmdKind::Unmap => true,
+            _ => false,
+        };
+        let (enew, mut caller_enew) = if waits {
+            if let Some(ref enew) = self.enew { enew.check_capacity()?; }
+            (Some(ClNullEventPtrEnum::Event(&mut event)), self.enew.take())
+        } else {
+            (self.enew.take(), None)
+        };
+
+        let mut tracker = Tracker::new(queue, enew)?;
+        let ewait = queue.fan_in_wait_list(tracker.wait_list(self.ewait.take()))?;
+        let kind = mem::replace(&mut self.kind, SvmCmdKind::Unspecified);
+
+        let result = unsafe {
+            match kind {
+                SvmCmdKind::Copy { dst, dst_offset } => {
+                    let dst_ptr = (dst.alloc.as_ptr() as *mut T).add(dst_offset);
+                    core::enqueue_svm_memcpy(queue, block, dst_ptr as *mut c_void,
+                        ptr as *const c_void, size, ewait, tracker.enew(), Some(&device_version))
+                },
+                SvmCmdKind::Fill { pattern } => {
+                    core::enqueue_svm_mem_fill(queue, ptr as *mut c_void, pattern, size, ewait,
+                        tracker.enew(), Some(&device_version))
+                },
+                SvmCmdKind::Read { dst } => {
+                    core::enqueue_svm_memcpy(queue, block, dst.as_mut_ptr() as *mut c_void,
+                        ptr as *const c_void, size, ewait, tracker.enew(), Some(&device_version))
+                },
+                SvmCmdKind::Write { src } => {
+                    core::enqueue_svm_memcpy(queue, block, ptr as *mut c_void,
+                        src.as_ptr() as *const c_void, size, ewait, tracker.enew(),
+                        Some(&device_version))
+                },
+                SvmCmdKind::Map { flags } => {
+                    core::enqueue_svm_map(queue, block, flags, ptr as *mut c_void, size, ewait,
+                        tracker.enew(), Some(&device_version))
+                },
+                SvmCmdKind::Unmap => {
+                    core::enqueue_svm_unmap(queue, ptr as *mut c_void, ewait, tracker.enew(),
+                        Some(&device_version))
+                },
+                SvmCmdKind::Unspecified => unreachable!(),
+            }
+        };
+        let result = tracker.finish(result.map_err(OclError::from));
+        queue.enqueued(result, false)?;
+
+        if waits {
+            if let Some(ref mut enew) = caller_enew {
+                unsafe { enew.clone_from(&event) }
+            }
+            event.wait_for()?;
+        }
+        Ok(())
+    }
+}
+
+
+/// A primitive type with a host atomic counterpart of identical size, usable
+/// with `FineSvmBuffer::atomics`.
+///
+/// Implemented for the 32 and 64-bit integers, which correspond to the
+/// OpenCL C `atomic_int`, `atomic_uint`, `atomic_long` and `atomic_ulong`
+/// types (the 64-bit types also require the `cl_khr_int64_base_atomics` and
+/// `cl_khr_int64_extended_atomics` extensions on the device).
+///
+/// ### Safety
+///
+/// `Atomic` must have the same size and in-memory representation as `Self`.
+/// Its alignment may be greater (as for `AtomicI64` on some 32-bit targets);
+/// `FineSvmBuffer::with_atomics` aligns buffers for it.
+pub unsafe trait SvmAtomicPrm: OclPrm {
+    /// The host atomic type.
+    type Atomic: Send + Sync;
+}
+
+unsafe impl SvmAtomicPrm for i32 { type Atomic = AtomicI32; }
+unsafe impl SvmAtomicPrm for u32 { type Atomic = AtomicU32; }
+unsafe impl SvmAtomicPrm for i64 { type Atomic = AtomicI64; }
+unsafe impl SvmAtomicPrm for u64 { type Atomic = AtomicU64; }
+
+
+/// A host mapping of an entire `SvmBuffer` (see `SvmBuffer::map`).
+///
+/// Dereferences to a slice of the buffer's elements. Unmapped when dropped;
+/// use `::unmap` to handle errors or to obtain the unmap event.
+#[derive(Debug)]
+pub struct SvmMap<'a, T: OclPrm> {
+    buffer: &'a mut SvmBuffer<T>,
+    unmapped: bool,
+}
+
+impl<'a, T: OclPrm> SvmMap<'a, T> {
+    /// Enqueues an unmap command on the buffer's default queue, returning its
+    /// event.
+    ///
+    /// Commands enqueued on other queues which use the buffer must wait for
+    /// the returned event.
+    pub fn unmap(mut self) -> OclResult<Event> {
+        self.unmapped = true;
+        self.enqueue_unmap()
+    }
+
+    fn enqueue_unmap(&self) -> OclResult<Event> {
+        let mut event = Event::empty();
+        unsafe {
+            core::enqueue_svm_unmap(self.buffer.queue.as_core(), self.buffer.alloc.as_ptr(),
+                None::<ClWaitListPtrEnum>, Some(&mut event),
+                Some(&self.buffer.queue.device_version()))?;
+        }
+        Ok(event)
+    }
+}
+
+impl<'a, T: OclPrm> Deref for SvmMap<'a, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.buffer.as_ptr(), self.buffer.len) }
+    }
+}
+
+impl<'a, T: OclPrm> DerefMut for SvmMap<'a, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.buffer.as_mut_ptr(), self.buffer.len) }
+    }
+}
+
+impl<'a, T: OclPrm> Drop for SvmMap<'a, T> {
+    fn drop(&mut self) {
+        if !self.unmapped {
+            self.enqueue_unmap().ok();
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! Rust collections stored in fine-grained shared virtual memory (OpenCL
+//! 2.0+).
+//!
+//! `SvmBox` and `SvmVec` are stable, `OclPrm`-only counterparts of `Box`
+//! and `Vec` whose contents live in fine-grained SVM and may therefore be
+//! passed to kernels (with `KernelBuilder::arg_svm`) and traversed by them.
+//!
+//! With the `svm_allocator_api` feature (which requires a nightly compiler),
+//! `SvmAllocator` also implements the `Allocator` trait, allowing any
+//! standard collection to be allocated in SVM (`Vec::new_in`, etc.). Such
+//! allocations are passed to kernels with `KernelBuilder::arg_svm_ptr`.
+//!
+//! All types require a device supporting
+//! `DeviceSvmCapabilities::FINE_GRAIN_BUFFER`.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::ptr::{self, NonNull};
+#[cfg(feature = "svm_allocator_api")]
+use std::alloc::{Allocator, AllocError};
+use crate::core::{self, OclPrm, MemFlags, DeviceSvmCapabilities};
+use crate::core::ffi::c_void;
+use crate::error::Result as OclResult;
+use crate::standard::{Context, Queue, SvmBuffer, FineSvmBuffer, ClWaitListPtrEnum,
+    ClNullEventPtrEnum};
+
+
+/// A single value of `T` stored in fine-grained shared virtual memory.
+///
+/// Dereferences to the value. See `FineSvmBuffer` for the rules governing
+/// host and device access.
+///
+/// ```rust,ignore
+/// let mut params = SvmBox::new(&queue, Params { scale: 2.0, count: 16 })?;
+/// let kernel = pro_que.kernel_builder("run").arg_svm(&params).build()?;
+/// params.count = 32;
+/// ```
+#[derive(Debug)]
+pub struct SvmBox<T: OclPrm> {
+    buffer: FineSvmBuffer<T>,
+}
+
+impl<T: OclPrm> SvmBox<T> {
+    /// Returns a new box containing `val` with `queue` as its default queue.
+    pub fn new(queue: &Queue, val: T) -> OclResult<SvmBox<T>> {
+        let mut buffer = FineSvmBuffer::new(queue, 1)?;
+        buffer[0] = val;
+        Ok(SvmBox { buffer })
+    }
+
+    /// Returns the device (and host) address of the value.
+    pub fn as_ptr(&self) -> *const T {
+        self.buffer.as_ptr()
+    }
+
+    /// Returns the device (and host) address of the value.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.buffer.as_mut_ptr()
+    }
+
+    /// Consumes the box, returning its value.
+    pub fn into_inner(self) -> T {
+        self.buffer[0]
+    }
+}
+
+impl<T: OclPrm> Deref for SvmBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.buffer[0]
+    }
+}
+
+impl<T: OclPrm> DerefMut for SvmBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.buffer[0]
+    }
+}
+
+impl<T: OclPrm> AsRef<SvmBuffer<T>> for SvmBox<T> {
+    fn as_ref(&self) -> &SvmBuffer<T> {
+        self.buffer.as_ref()
+    }
+}
+
+
+/// A growable vector of `T` stored in fine-grained shared virtual memory.
+///
+/// Dereferences to a slice of its elements. See `FineSvmBuffer` for the
+/// rules governing host and device access.
+///
+/// Growing the vector beyond its capacity moves its contents to a new
+/// allocation, invalidating pointers into it. Kernels which already have
+/// the vector as an argument keep the old allocation alive and continue to
+/// use it until the argument is set again.
+///
+/// ```rust,ignore
+/// let mut vec = SvmVec::new(&queue)?;
+/// for i in 0..1000 {
+///     vec.push(i as f32)?;
+/// }
+/// let kernel = pro_que.kernel_builder("sum").arg_svm(&vec).arg(vec.len() as u32).build()?;
+/// ```
+pub struct SvmVec<T: OclPrm> {
+    buffer: FineSvmBuffer<T>,
+    len: usize,
+}
+
+impl<T: OclPrm> SvmVec<T> {
+    /// Returns a new, empty vector with a small initial capacity and `queue`
+    /// as its default queue.
+    pub fn new(queue: &Queue) -> OclResult<SvmVec<T>> {
+        SvmVec::with_capacity(queue, 16)
+    }
+
+    /// Returns a new, empty vector with room for at least `capacity`
+    /// elements.
+    pub fn with_capacity(queue: &Queue, capacity: usize) -> OclResult<SvmVec<T>> {
+        Ok(SvmVec { buffer: FineSvmBuffer::new(queue, capacity.max(1))?, len: 0 })
+    }
+
+    /// Returns a new vector containing a copy of `data`.
+    pub fn from_slice(queue: &Queue, data: &[T]) -> OclResult<SvmVec<T>> {
+        let mut vec = SvmVec::with_capacity(queue, data.len())?;
+        vec.extend_from_slice(data)?;
+        Ok(vec)
+    }
+
+    /// Returns the number of elements the vector can hold without moving to
+    /// a new allocation.
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Ensures room for at least `additional` more elements, moving to a new
+    /// allocation if necessary.
+    pub fn reserve(&mut self, additional: usize) -> OclResult<()> {
+        let required = self.len.checked_add(additional)
+            .ok_or("SvmVec::reserve: The capacity overflows 'usize'.")?;
+        if required > self.capacity() {
+            let doubled = self.capacity().checked_mul(2)
+                .ok_or("SvmVec::reserve: The capacity overflows 'usize'.")?;
+            let capacity = required.max(doubled);
+            let mut buffer = FineSvmBuffer::new(self.buffer.default_queue(), capacity)?;
+            buffer[..self.len].copy_from_slice(&self.buffer[..self.len]);
+            self.buffer = buffer;
+        }
+        Ok(())
+    }
+
+    /// Appends `val`, growing the vector if necessary.
+    pub fn push(&mut self, val: T) -> OclResult<()> {
+        self.reserve(1)?;
+        self.buffer[self.len] = val;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Appends a copy of `data`, growing the vector if necessary.
+    pub fn extend_from_slice(&mut self, data: &[T]) -> OclResult<()> {
+        self.reserve(data.len())?;
+        self.buffer[self.len..self.len + data.len()].copy_from_slice(data);
+        self.len += data.len();
+        Ok(())
+    }
+
+    /// Removes and returns the last element, if any.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            None
+        } else {
+            self.len -= 1;
+            Some(self.buffer[self.len])
+        }
+    }
+
+    /// Shortens the vector to `len` elements. Has no effect if `len` is
+    /// greater than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    /// Removes all elements, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Returns the device (and host) address of the first element.
+    pub fn as_ptr(&self) -> *const T {
+        self.buffer.as_ptr()
+    }
+
+    /// Returns the device (and host) address of the first element.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.buffer.as_mut_ptr()
+    }
+
+    /// Returns the default queue.
+    pub fn default_queue(&self) -> &Queue {
+        self.buffer.default_queue()
+    }
+}
+
+impl<T: OclPrm> Deref for SvmVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.buffer[..self.len]
+    }
+}
+
+impl<T: OclPrm> DerefMut for SvmVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.buffer[..self.len]
+    }
+}
+
+impl<T: OclPrm> AsRef<SvmBuffer<T>> for SvmVec<T> {
+    fn as_ref(&self) -> &SvmBuffer<T> {
+        self.buffer.as_ref()
+    }
+}
+
+impl<T: OclPrm> fmt::Debug for SvmVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+
+/// An allocator of fine-grained shared virtual memory.
+///
+/// With the `svm_allocator_api` feature, implements the (nightly-only)
+/// `Allocator` trait:
+///
+/// ```rust,ignore
+/// let alloc = SvmAllocator::new(&queue)?;
+/// let mut nodes: Vec<Node, _> = Vec::new_in(alloc.clone());
+/// nodes.push(Node { value: 1, next: ptr::null() });
+/// let kernel = unsafe {
+///     pro_que.kernel_builder("walk").arg_svm_ptr(nodes.as_ptr()).build()?
+/// };
+/// ```
+///
+/// Deallocated memory is freed, without blocking, once the allocator's
+/// queue has completed all commands enqueued before the deallocation.
+/// Commands using allocations on other queues must be complete before those
+/// allocations are deallocated.
+#[derive(Debug, Clone)]
+pub struct SvmAllocator {
+    context: Context,
+    queue: Queue,
+    flags: MemFlags,
+}
+
+impl SvmAllocator {
+    /// Returns a new allocator for the context and device of `queue`.
+    pub fn new(queue: &Queue) -> OclResult<SvmAllocator> {
+        SvmAllocator::create(queue, false)
+    }
+
+    /// Returns a new allocator whose allocations may be updated concurrently
+    /// by the host and by kernels using atomics.
+    ///
+    /// Requires a device supporting `DeviceSvmCapabilities::ATOMICS`.
+    pub fn with_atomics(queue: &Queue) -> OclResult<SvmAllocator> {
+        SvmAllocator::create(queue, true)
+    }
+
+    fn create(queue: &Queue, atomics: bool) -> OclResult<SvmAllocator> {
+        let (flags, required) = if atomics {
+            (MemFlags::new().read_write().svm_fine_grain_buffer().svm_atomics(),
+                DeviceSvmCapabilities::FINE_GRAIN_BUFFER | DeviceSvmCapabilities::ATOMICS)
+        } else {
+            (MemFlags::new().read_write().svm_fine_grain_buffer(),
+                DeviceSvmCapabilities::FINE_GRAIN_BUFFER)
+        };
+        let caps = queue.device().svm_capabilities()?;
+        if !caps.contains(required) {
+            return Err(format!("SvmAllocator::new: The device '{}' does not support the SVM \
+                capabilities: {:?} (supported: {:?}).", queue.device().name()?,
+                required - caps, caps).into());
+        }
+        Ok(SvmAllocator { context: queue.context(), queue: queue.clone(), flags })
+    }
+
+    /// Allocates uninitialized memory for `layout`.
+    ///
+    /// Zero-sized layouts return a dangling, suitably aligned pointer.
+    pub fn alloc(&self, layout: Layout) -> OclResult<NonNull<u8>> {
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+        let ptr = core::svm_alloc(&self.context, self.flags, layout.size(),
+            layout.align() as u32, None)?;
+        NonNull::new(ptr as *mut u8).ok_or_else(|| "SvmAllocator::alloc: Null pointer.".into())
+    }
+
+    /// Frees memory previously returned by `::alloc` with the same `layout`.
+    ///
+    /// ### Safety
+    ///
+    /// `ptr` must have been returned by this allocator (or a clone of it) and
+    /// must not be used afterwards.
+    pub unsafe fn dealloc(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() == 0 { return; }
+        let ptr = ptr.as_ptr() as *mut c_void;
+        let enqueued = core::enqueue_svm_free(self.queue.as_core(), &[ptr], None, ptr::null_mut(),
+            None::<ClWaitListPtrEnum>, None::<ClNullEventPtrEnum>,
+            Some(&self.queue.device_version()));
+        if enqueued.is_ok() {
+            let _ = self.queue.flush();
+        } else {
+            // `clSVMFree` does not wait for commands using the allocation:
+            let _ = self.queue.finish();
+            core::svm_free(&self.context, ptr);
+        }
+    }
+
+    /// Returns the queue deallocations are enqueued on.
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    /// Returns the context allocations are made in.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+}
+
+#[cfg(feature = "svm_allocator_api")]
+unsafe impl Allocator for SvmAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let ptr = self.alloc(layout).map_err(|_| AllocError)?;
+        Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.dealloc(ptr, layout)
+    }
+}
+
+/// Returns a dangling pointer aligned for `layout`.
+fn dangling(layout: Layout) -> NonNull<u8> {
+    NonNull::new(layout.align() as *mut u8).expect("alignment is non-zero")
+}
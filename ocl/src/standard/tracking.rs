@@ -0,0 +1,161 @@
+//! Implicit dependency tracking for command queues.
+//!
+//! When enabled on a `Queue`, each command enqueued through this crate's
+//! command builders waits for the previous command enqueued on that queue,
+//! giving in-order semantics on an out-of-order queue without passing events
+//! around by hand. Other queues are unaffected, so commands can still be
+//! ordered selectively by enabling tracking on only some queues.
+//!
+//! The state is shared by a queue and its clones. A command holds it locked
+//! from reading the queue's last event until recording its own, so that
+//! commands enqueued concurrently from several threads still form a single
+//! chain.
+
+use std::sync::{Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::core::ClNullEventPtr;
+use crate::error::Result as OclResult;
+use crate::standard::{Event, EventList, Queue, ClWaitListPtrEnum, ClNullEventPtrEnum};
+
+
+/// The tracking state shared by a queue and its clones.
+#[derive(Debug, Default)]
+pub(crate) struct TrackingState {
+    enabled: AtomicBool,
+    last: Mutex<Option<Event>>,
+}
+
+impl TrackingState {
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn last_event(&self) -> Option<Event> {
+        self.lock().clone()
+    }
+
+    /// Enables or disables tracking.
+    ///
+    /// Disabling tracking forgets the last event.
+    pub(crate) fn set_enabled(&self, enabled: bool) {
+        let mut last = self.lock();
+        self.enabled.store(enabled, Ordering::Release);
+        if !enabled {
+            *last = None;
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Event>> {
+        self.last.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+
+/// Links a single command into its queue's chain of tracked commands and
+/// labels its event.
+///
+/// A no-op unless tracking is enabled on the queue or a label is set.
+/// Otherwise, `wait_list` adds the queue's last event to the command's wait
+/// list, `enew` substitutes an event owned by the tracker (copied into the
+/// caller's destination by `finish`), and `finish` labels that event and
+/// records it as the queue's last.
+pub(crate) struct Tracker<'q, 'e> {
+    last: Option<MutexGuard<'q, Option<Event>>>,
+    event: Event,
+    enew: Option<ClNullEventPtrEnum<'e>>,
+    label: Option<&'e str>,
+}
+
+impl<'q, 'e> Tracker<'q, 'e> {
+    /// Returns a tracker for a command to be enqueued on `queue` which
+    /// would otherwise create its event in `enew`.
+    ///
+    /// If tracking is enabled, the queue's tracking state stays locked until
+    /// the tracker is dropped. Returns an error if `enew` has no room for the
+    /// event.
+    pub(crate) fn new(queue: &'q Queue, enew: Option<ClNullEventPtrEnum<'e>>)
+            -> OclResult<Tracker<'q, 'e>> {
+        if let Some(ref enew) = enew {
+            enew.check_capacity()?;
+        }
+        let state = queue.tracking();
+        let last = if state.is_enabled() {
+            // Checked again under the lock, which `set_enabled` also holds:
+            Some(state.lock()).filter(|_| state.is_enabled())
+        } else {
+            None
+        };
+        Ok(Tracker { last, event: Event::empty(), enew, label: None })
+    }
+
+    /// Sets the label for the command's event (see `Event::set_label`).
+    ///
+    /// Only events which the caller asked for are labeled.
+    pub(crate) fn labeled(mut self, label: Option<&'e str>) -> Tracker<'q, 'e> {
+        self.label = label;
+        self
+    }
+
+    fn intercepts(&self) -> bool {
+        self.last.is_some() || (self.label.is_some() && self.enew.is_some())
+    }
+
+    /// Returns `ewait` with the queue's last event added.
+    pub(crate) fn wait_list<'a>(&self, ewait: Option<ClWaitListPtrEnum<'a>>)
+            -> Option<ClWaitListPtrEnum<'a>> {
+        let last = match self.last.as_ref().and_then(|last| (**last).clone()) {
+            Some(last) => last,
+            None => return ewait,
+        };
+        match ewait {
+            Some(ewait) => {
+                let mut list = EventList::from(ewait);
+                list.push(last);
+                Some(ClWaitListPtrEnum::BoxTraitObj(Box::new(list)))
+            },
+            None => Some(ClWaitListPtrEnum::EventOwned(last)),
+        }
+    }
+
+    /// Returns the destination for the command's new event.
+    pub(crate) fn enew(&mut self) -> Option<ClNullEventPtrEnum<'_>> {
+        if self.intercepts() {
+            Some(ClNullEventPtrEnum::Event(&mut self.event))
+        } else {
+            self.enew.take()
+        }
+    }
+
+    /// Copies `event`, created by a command which does not use `::enew`,
+    /// into the caller's destination, labeling it, and records it as the
+    /// queue's last event.
+    pub(crate) fn record(mut self, event: &Event) {
+        if let Some(ref mut enew) = self.enew {
+            unsafe { enew.clone_from(event) }
+        }
+        self.record_event(event)
+    }
+
+    fn record_event(&mut self, event: &Event) {
+        // Only events which the caller asked for are labeled:
+        if let (Some(label), Some(_)) = (self.label, self.enew.as_ref()) {
+            event.set_label(label);
+        }
+        if let Some(ref mut last) = self.last {
+            **last = Some(event.clone());
+        }
+    }
+
+    /// Passes through the result of enqueuing the command, labeling and
+    /// recording its event if it succeeded.
+    pub(crate) fn finish<R>(mut self, result: OclResult<R>) -> OclResult<R> {
+        if result.is_err() || self.event.is_empty() { return result; }
+
+        if let Some(ref mut enew) = self.enew {
+            unsafe { enew.clone_from(&self.event) }
+        }
+        let event = self.event.clone();
+        self.record_event(&event);
+        result
+    }
+}
@@ -0,0 +1,324 @@
+//! Splitting of large buffer transfers into chunks.
+//!
+//! A `TransferPolicy` set on a queue (or overridden per buffer) causes large
+//! linear reads and writes to be enqueued as a series of smaller commands,
+//! with a bounded number in flight at once. The realized throughput of each
+//! chunk is recorded in the queue's `TransferMetrics`, allowing the policy
+//! to be tuned for a particular host/device interconnect.
+
+use std::mem;
+use std::ops::Range;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::core::{CommandQueueInfo, CommandQueueInfoResult, CommandQueueProperties, ClNullEventPtr};
+use crate::error::Result as OclResult;
+use crate::standard::{Queue, Event, EventArray, EventList, ClWaitListPtrEnum, ClNullEventPtrEnum};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+
+/// The direction of a transfer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TransferKind {
+    Read,
+    Write,
+}
+
+
+/// Controls how large linear buffer reads and writes are split up.
+///
+/// When deserialized, a `concurrency` of zero is rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize),
+    serde(try_from = "TransferPolicyRepr"))]
+pub struct TransferPolicy {
+    chunk_size: Option<usize>,
+    concurrency: usize,
+}
+
+impl TransferPolicy {
+    /// Returns a new policy which does not split transfers.
+    pub fn new() -> TransferPolicy {
+        TransferPolicy { chunk_size: None, concurrency: 2 }
+    }
+
+    /// Sets the maximum size of each chunk, in bytes.
+    ///
+    /// Transfers no larger than `bytes` are enqueued as a single command.
+    /// A size of zero disables splitting.
+    pub fn chunk_size(mut self, bytes: usize) -> TransferPolicy {
+        self.chunk_size = if bytes == 0 { None } else { Some(bytes) };
+        self
+    }
+
+    /// Sets the maximum number of chunks which may be in flight at once
+    /// (default: 2).
+    ///
+    /// ## Panics
+    ///
+    /// `chunks` must be greater than zero.
+    pub fn concurrency(mut self, chunks: usize) -> TransferPolicy {
+        assert!(chunks > 0, "TransferPolicy::concurrency: Concurrency must be greater than zero.");
+        self.concurrency = chunks;
+        self
+    }
+
+    /// Returns the maximum chunk size, in bytes, if splitting is enabled.
+    pub fn get_chunk_size(&self) -> Option<usize> {
+        self.chunk_size
+    }
+
+    /// Returns the maximum number of chunks in flight at once.
+    pub fn get_concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Returns the element ranges a transfer of `len` elements of
+    /// `elem_size` bytes each would be split into, or `None` if it would
+    /// not be split.
+    pub fn chunks(&self, len: usize, elem_size: usize) -> Option<Vec<Range<usize>>> {
+        let chunk_len = match self.chunk_size {
+            Some(bytes) => (bytes / elem_size.max(1)).max(1),
+            None => return None,
+        };
+
+        if len <= chunk_len { return None; }
+
+        Some((0..len).step_by(chunk_len)
+            .map(|start| start..(start + chunk_len).min(len))
+            .collect())
+    }
+}
+
+impl Default for TransferPolicy {
+    fn default() -> TransferPolicy {
+        TransferPolicy::new()
+    }
+}
+
+
+/// The unvalidated, deserialized form of a `TransferPolicy`.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(default)]
+struct TransferPolicyRepr {
+    chunk_size: Option<usize>,
+    concurrency: usize,
+}
+
+#[cfg(feature = "serde")]
+impl Default for TransferPolicyRepr {
+    fn default() -> TransferPolicyRepr {
+        let TransferPolicy { chunk_size, concurrency } = TransferPolicy::new();
+        TransferPolicyRepr { chunk_size, concurrency }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<TransferPolicyRepr> for TransferPolicy {
+    type Error = String;
+
+    fn try_from(repr: TransferPolicyRepr) -> Result<TransferPolicy, String> {
+        if repr.concurrency == 0 {
+            return Err("TransferPolicy: Concurrency must be greater than zero.".to_owned());
+        }
+        Ok(TransferPolicy { chunk_size: repr.chunk_size, concurrency: repr.concurrency })
+    }
+}
+
+
+/// Timing for a single chunk of a split transfer.
+#[derive(Clone, Copy, Debug)]
+pub struct ChunkMetrics {
+    pub kind: TransferKind,
+    /// The offset of the chunk within the buffer, in bytes.
+    pub offset: usize,
+    /// The size of the chunk, in bytes.
+    pub len: usize,
+    /// The time spent transferring the chunk. Measured on the device when
+    /// the queue has profiling enabled and on the host otherwise.
+    pub duration: Duration,
+}
+
+impl ChunkMetrics {
+    /// Returns the realized throughput in bytes per second.
+    pub fn throughput(&self) -> f64 {
+        let secs = self.duration.as_secs() as f64 + f64::from(self.duration.subsec_nanos()) * 1e-9;
+        if secs == 0.0 { 0.0 } else { self.len as f64 / secs }
+    }
+}
+
+
+/// A shared record of chunk timings.
+///
+/// Each queue has its own metrics which are shared with its clones.
+#[derive(Clone, Debug, Default)]
+pub struct TransferMetrics {
+    chunks: Arc<Mutex<Vec<ChunkMetrics>>>,
+}
+
+impl TransferMetrics {
+    /// Returns a new, empty record.
+    pub fn new() -> TransferMetrics {
+        TransferMetrics::default()
+    }
+
+    fn record(&self, chunk: ChunkMetrics) {
+        self.chunks.lock().unwrap().push(chunk);
+    }
+
+    /// Returns a copy of all recorded chunk timings, oldest first.
+    pub fn chunks(&self) -> Vec<ChunkMetrics> {
+        self.chunks.lock().unwrap().clone()
+    }
+
+    /// Returns the mean throughput of recorded chunks of the specified
+    /// kind, in bytes per second.
+    pub fn mean_throughput(&self, kind: TransferKind) -> Option<f64> {
+        let chunks = self.chunks.lock().unwrap();
+        let (bytes, duration) = chunks.iter().filter(|c| c.kind == kind)
+            .fold((0usize, Duration::new(0, 0)), |(b, d), c| (b + c.len, d + c.duration));
+        let secs = duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) * 1e-9;
+        if secs == 0.0 { None } else { Some(bytes as f64 / secs) }
+    }
+
+    /// Discards all recorded timings.
+    pub fn clear(&self) {
+        self.chunks.lock().unwrap().clear();
+    }
+}
+
+
+/// The events of the chunks of a transfer enqueued so far.
+///
+/// Chunks read from or write to host memory which may only be borrowed for
+/// the duration of `ChunkedTransfer::enqueue`. If enqueuing fails part way
+/// through, dropping this waits for the chunks already enqueued so that none
+/// is still in flight once the error is returned.
+struct InFlight(EventList);
+
+impl InFlight {
+    /// Returns the events without waiting on them.
+    fn release(mut self) -> EventList {
+        mem::replace(&mut self.0, EventList::new())
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let _ = self.0.wait_for();
+    }
+}
+
+
+/// A transfer to be enqueued as a series of chunks.
+pub(crate) struct ChunkedTransfer<'q> {
+    pub queue: &'q Queue,
+    pub kind: TransferKind,
+    pub chunks: Vec<Range<usize>>,
+    pub concurrency: usize,
+    pub elem_size: usize,
+    /// The offset of the transfer within the buffer, in elements.
+    pub offset: usize,
+    pub block: bool,
+}
+
+impl<'q> ChunkedTransfer<'q> {
+    /// Enqueues each chunk by calling `enq_chunk` with its element range, a
+    /// list of events to wait on, and an event to store the new command's
+    /// event in.
+    ///
+    /// Chunks are not waited on from the host. Instead, each waits on the
+    /// chunk enqueued `concurrency` chunks before it, so that no more than
+    /// `concurrency` are in flight at once, and on `ewait`. The timing of
+    /// each chunk is recorded in the queue's metrics as it completes. If
+    /// `enew` is specified, it receives a marker event which completes after
+    /// the last chunk. If `block` is set, returns once every chunk has
+    /// completed and been recorded.
+    ///
+    /// If any step fails, every chunk already enqueued (including that of a
+    /// failed `enq_chunk` call, if it created an event) is waited on before
+    /// the error is returned, whether or not `block` is set.
+    pub fn enqueue<F>(self, ewait: Option<ClWaitListPtrEnum>, enew: Option<ClNullEventPtrEnum>,
+            mut enq_chunk: F) -> OclResult<()>
+            where F: FnMut(Range<usize>, Option<&EventArray<2>>, &mut Event) -> OclResult<()> {
+        let ChunkedTransfer { queue, kind, chunks, concurrency, elem_size, offset, block } = self;
+        let profiling = match queue.info(CommandQueueInfo::Properties) {
+            Ok(CommandQueueInfoResult::Properties(props)) =>
+                props.contains(CommandQueueProperties::PROFILING_ENABLE),
+            _ => false,
+        };
+        let metrics = queue.transfer_metrics();
+
+        let start_marker = match ewait {
+            Some(ewait) => Some(queue.enqueue_marker(Some(ewait))?),
+            None => None,
+        };
+
+        let chunk_count = chunks.len();
+        let mut in_flight = InFlight(EventList::with_capacity(chunk_count));
+        let last_complete = Arc::new(Mutex::new(Instant::now()));
+        let (recorded_tx, recorded_rx) = mpsc::channel();
+
+        for (idx, range) in chunks.into_iter().enumerate() {
+            let mut wait_list = EventArray::<2>::new();
+            if let Some(ref marker) = start_marker { let _ = wait_list.push(marker.clone()); }
+            if idx >= concurrency {
+                let _ = wait_list.push(in_flight.0[idx - concurrency].clone());
+            }
+
+            let mut event = Event::empty();
+            let result = enq_chunk(range.clone(),
+                if wait_list.is_empty() { None } else { Some(&wait_list) }, &mut event);
+            if !event.is_empty() { in_flight.0.push(event.clone()); }
+            result?;
+            let enqueued = Instant::now();
+
+            let (chunk_event, metrics) = (event.clone(), metrics.clone());
+            let (last_complete, recorded_tx) = (last_complete.clone(), recorded_tx.clone());
+            event.on_complete(move |status| {
+                if status.is_ok() {
+                    let now = Instant::now();
+                    let mut last_complete = last_complete.lock().unwrap_or_else(|e| e.into_inner());
+
+                    // Fall back to host timing for any chunk without profiling info:
+                    let device_duration = if profiling {
+                        chunk_event.profiling_duration().ok().and_then(|d| d)
+                    } else {
+                        None
+                    };
+                    let duration = device_duration
+                        .unwrap_or_else(|| now - enqueued.max(*last_complete));
+                    *last_complete = now;
+
+                    metrics.record(ChunkMetrics {
+                        kind,
+                        offset: (offset + range.start) * elem_size,
+                        len: range.len() * elem_size,
+                        duration,
+                    });
+                }
+                let _ = recorded_tx.send(());
+            })?;
+        }
+
+        if let Some(mut enew) = enew {
+            let marker = queue.enqueue_marker(Some(&in_flight.0))?;
+            unsafe { enew.clone_from(&marker) }
+        }
+
+        let all_events = in_flight.release();
+        if block {
+            all_events.wait_for()?;
+            // Completion callbacks may run after the wait returns:
+            drop(recorded_tx);
+            for _ in 0..chunk_count {
+                if recorded_rx.recv().is_err() { break; }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+//! Support for test suites which run on OpenCL devices.
+//!
+//! Provides the pieces most GPU test suites end up writing for themselves:
+//!
+//! * `skip_if_no_device!`: Returns early from a test (rather than failing)
+//!   on machines without any OpenCL device.
+//! * `TestEnv`: A context and queue created for a single test. When dropped
+//!   the queue is finished and the test fails if any buffers or images
+//!   created within the context are still alive.
+//! * `SeededRng`: A small, deterministic generator for test data. The seed
+//!   can be overridden with the `OCL_TEST_SEED` environment variable to
+//!   reproduce a failure.
+//!
+//! ```rust,ignore
+//! #[test]
+//! fn add() {
+//!     let (platform, device) = skip_if_no_device!();
+//!     let env = TestEnv::with_device(platform, device).unwrap();
+//!     let data: Vec<f32> = SeededRng::from_env().vec(1024);
+//!
+//!     let buffer = Buffer::builder().queue(env.queue().clone()).len(data.len())
+//!         .copy_host_slice(&data).build().unwrap();
+//!     // ...
+//! }
+//! ```
+
+use std;
+use std::env;
+use std::thread;
+use crate::core::OclPrm;
+use crate::error::Result as OclResult;
+use crate::standard::{Platform, Device, Context, Queue, Program, SourceFragment};
+
+
+/// The seed used by `SeededRng::from_env` when `OCL_TEST_SEED` is not set.
+pub const DEFAULT_SEED: u64 = 0x005E_ED0C_10C1;
+
+
+/// Returns the first device found on any platform, or `None` if no OpenCL
+/// device is available.
+pub fn first_device() -> Option<(Platform, Device)> {
+    Platform::list().ok()?.into_iter()
+        .filter_map(|p| Device::first(p).ok().map(|d| (p, d)))
+        .next()
+}
+
+
+/// Evaluates to the `(Platform, Device)` returned by
+/// `testing::first_device`, or returns from the enclosing function (after
+/// printing a note) if no device is available.
+#[macro_export]
+macro_rules! skip_if_no_device {
+    () => {
+        match $crate::testing::first_device() {
+            Some(platform_device) => platform_device,
+            None => {
+                eprintln!("Skipping test: No OpenCL device available.");
+                return;
+            },
+        }
+    };
+}
+
+
+/// A context and queue for the exclusive use of a single test.
+///
+/// When dropped, the queue is finished and, unless `::allow_leaks` has been
+/// called, a panic is raised if any buffers or images created within the
+/// context remain alive. Declare the `TestEnv` before any memory objects so
+/// that they are dropped first.
+#[derive(Debug)]
+pub struct TestEnv {
+    context: Context,
+    queue: Queue,
+    check_leaks: bool,
+}
+
+impl TestEnv {
+    /// Returns a new environment using the first available device.
+    pub fn new() -> OclResult<TestEnv> {
+        match first_device() {
+            Some((platform, device)) => TestEnv::with_device(platform, device),
+            None => Err("TestEnv::new: No OpenCL device available.".into()),
+        }
+    }
+
+    /// Returns a new environment using `device`.
+    pub fn with_device(platform: Platform, device: Device) -> OclResult<TestEnv> {
+        let context = Context::builder().platform(platform).devices(device).build()?;
+        let queue = Queue::new(&context, device, None)?;
+        Ok(TestEnv { context, queue, check_leaks: true })
+    }
+
+    /// Builds a program from `src` for this environment's device.
+    pub fn program<S: Into<SourceFragment>>(&self, src: S) -> OclResult<Program> {
+        Program::builder().src(src).devices(self.queue.device()).build(&self.context)
+    }
+
+    /// Disables the leak check performed when this environment is dropped.
+    pub fn allow_leaks(&mut self) -> &mut TestEnv {
+        self.check_leaks = false;
+        self
+    }
+
+    /// Returns the context.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Returns the queue.
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    /// Returns the device.
+    pub fn device(&self) -> Device {
+        self.queue.device()
+    }
+}
+
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        // Don't turn an existing failure into an abort:
+        if thread::panicking() { return; }
+
+        self.queue.finish().expect("TestEnv::drop: Unable to finish queue");
+
+        if self.check_leaks {
+            let leaked = self.context.dump_allocations();
+            if !leaked.is_empty() {
+                let list: Vec<String> = leaked.iter().map(|a| format!("    {}", a)).collect();
+                panic!("TestEnv: {} memory object(s) still alive at the end of the test:\n{}",
+                    leaked.len(), list.join("\n"));
+            }
+        }
+    }
+}
+
+
+/// Types which `SeededRng` can generate.
+///
+/// Integers span their full range and floats lie within `[0, 1)`.
+pub trait Generate: OclPrm {
+    /// Returns a new value drawn from `rng`.
+    fn generate(rng: &mut SeededRng) -> Self;
+}
+
+macro_rules! impl_generate_int {
+    ( $( $ty:ty ),+ ) => { $(
+        impl Generate for $ty {
+            #[inline]
+            fn generate(rng: &mut SeededRng) -> $ty {
+                rng.next_u64() as $ty
+            }
+        }
+    )+ }
+}
+
+impl_generate_int!(u8, i8, u16, i16, u32, i32, u64, i64, usize, isize);
+
+impl Generate for f32 {
+    #[inline]
+    fn generate(rng: &mut SeededRng) -> f32 {
+        (rng.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+impl Generate for f64 {
+    #[inline]
+    fn generate(rng: &mut SeededRng) -> f64 {
+        (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+
+/// A deterministic pseudo-random generator (xorshift64*) for test data.
+///
+/// Not suitable for anything other than generating test inputs.
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    seed: u64,
+    state: u64,
+}
+
+impl SeededRng {
+    /// Returns a new generator seeded with `seed`.
+    pub fn new(seed: u64) -> SeededRng {
+        // The state must never be zero.
+        SeededRng { seed, state: (seed ^ 0x9E37_79B9_7F4A_7C15) | 1 }
+    }
+
+    /// Returns a new generator seeded from the `OCL_TEST_SEED` environment
+    /// variable or with `DEFAULT_SEED` if it is unset or invalid.
+    pub fn from_env() -> SeededRng {
+        let seed = env::var("OCL_TEST_SEED").ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_SEED);
+        SeededRng::new(seed)
+    }
+
+    /// Returns the seed this generator was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the next 64 bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a value in `[low, high)`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `low >= high`.
+    pub fn range(&mut self, low: u64, high: u64) -> u64 {
+        assert!(low < high, "SeededRng::range: Empty range.");
+        low + self.next_u64() % (high - low)
+    }
+
+    /// Returns a new value of type `T`.
+    pub fn gen<T: Generate>(&mut self) -> T {
+        T::generate(self)
+    }
+
+    /// Returns a vector of `len` values of type `T`.
+    pub fn vec<T: Generate>(&mut self, len: usize) -> Vec<T> {
+        (0..len).map(|_| T::generate(self)).collect()
+    }
+}
+
+impl Default for SeededRng {
+    fn default() -> SeededRng {
+        SeededRng::new(DEFAULT_SEED)
+    }
+}
+
+impl std::fmt::Display for SeededRng {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "SeededRng (seed: {}; reproduce with OCL_TEST_SEED={})", self.seed, self.seed)
+    }
+}
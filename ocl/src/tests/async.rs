@@ -7,14 +7,27 @@
 // #![allow(unused_imports, unused_variables, unused_mut)]
 
 use std::thread;
-use futures::{Future};
-use crate::core::Status;
+use std::future::Future;
+use std::pin::Pin;
+use futures::{future, FutureExt, TryFutureExt, StreamExt};
+use futures::executor::block_on;
+use crate::core::{Status, ProgramBuildStatus};
 use crate::{Platform, Device, Context, Queue, Program, Kernel, Event, Buffer, RwVec};
 use crate::traits::IntoRawEventArray;
-use crate::error::{Error as OclError, Result as OclResult};
+use crate::error::Result as OclResult;
 use crate::flags::{MemFlags, CommandQueueProperties};
 use crate::prm::Int4;
 use crate::ffi::{cl_event, c_void};
+use std::time::Duration;
+use crate::standard::{ProQue, UserEvent, EventList, EventArray, FutureFinish, FutureKernel,
+    FutureProgram};
+use crate::r#async::{join_events, select_events, FutureReadGuard, FutureWriteGuard, FutureUpgrade,
+    FutureMemMap, FutureSharedMemMap, FutureWriteVec, FutureReadVec, FutureFlush, FutureFlood,
+    EventListFuture, EventSelect, KernelStream, ReadGuard, WriteGuard, UpgradableReadGuard, MemMap,
+    SharedMemMap, CommandChain, DropPolicy, scope};
+use std::task::{Context as TaskContext, Poll};
+use futures::task::noop_waker_ref;
+use super::{nop_pro_que, filled_buffer};
 
 // Size of buffers and kernel work size:
 //
@@ -118,7 +131,7 @@ pub fn write_init(
         verify_init_event: Option<&Event>,
         write_init_event: &mut Option<Event>,
         write_val: i32, task_iter: i32)
-        -> Box<dyn Future<Item=i32, Error=OclError> + Send>
+        -> Pin<Box<dyn Future<Output=OclResult<i32>> + Send>>
 {
     extern "C" fn _write_complete(_: cl_event, _: i32, task_iter : *mut c_void) {
         if PRINT { println!("* Write init complete  \t(iter: {})", task_iter as usize); }
@@ -144,14 +157,12 @@ pub fn write_init(
 
     // println!("######     'release_event' (generate): {:?}", release_event);
 
-    let future_write_vec = future_guard.and_then(move |mut data| {
+    let future_write_vec = future_guard.map_ok(move |mut data| {
         if PRINT { println!("* Write init starting  \t(iter: {}) ...", task_iter); }
 
         for val in data.iter_mut() {
             *val = Int4::splat(write_val);
         }
-
-        Ok(())
     });
 
     let mut future_write_buffer = src_buf.cmd().write(rw_vec)
@@ -170,9 +181,9 @@ pub fn write_init(
     unsafe { write_init_event.as_ref().unwrap().set_callback(_write_complete,
         task_iter as *mut c_void).unwrap(); }
 
-    let future_drop_guard = future_write_buffer.and_then(move |_| Ok(()));
+    let future_drop_guard = future_write_buffer.map_ok(move |_| ());
 
-    Box::new(future_write_vec.join(future_drop_guard).map(move |(_, _)| task_iter))
+    Box::pin(future::try_join(future_write_vec, future_drop_guard).map_ok(move |(_, _)| task_iter))
 }
 
 
@@ -191,7 +202,7 @@ pub fn verify_init(
         write_init_event: Option<&Event>,
         verify_init_event: &mut Option<Event>,
         correct_val: i32, task_iter: i32)
-        -> Box<dyn Future<Item=i32, Error=OclError> + Send>
+        -> Pin<Box<dyn Future<Output=OclResult<i32>> + Send>>
 {
     extern "C" fn _verify_starting(_: cl_event, _: i32, task_iter : *mut c_void) {
         if PRINT { println!("* Verify init starting \t(iter: {}) ...", task_iter as usize); }
@@ -217,7 +228,7 @@ pub fn verify_init(
         .unwrap().clone());
 
     // The future which will actually verify the initial value:
-    Box::new(future_read_data.and_then(move |data| {
+    Box::pin(future_read_data.map(move |res| res.and_then(|data| {
         let mut val_count = 0;
 
         for (idx, val) in data.iter().enumerate() {
@@ -232,7 +243,7 @@ pub fn verify_init(
         if PRINT { println!("* Verify init complete \t(iter: {})", task_iter); }
 
         Ok(val_count)
-    }))
+    })))
 }
 
 
@@ -310,7 +321,7 @@ pub fn verify_add(
         wait_event: Option<&Event>,
         verify_add_event: &mut Option<Event>,
         correct_val: i32, task_iter: i32)
-        -> Box<dyn Future<Item=i32, Error=OclError> + Send>
+        -> Pin<Box<dyn Future<Output=OclResult<i32>> + Send>>
 {
     extern "C" fn _verify_starting(_: cl_event, _: i32, task_iter : *mut c_void) {
         if PRINT { println!("* Verify add starting  \t(iter: {}) ...", task_iter as usize); }
@@ -330,7 +341,7 @@ pub fn verify_add(
     *verify_add_event = Some(future_read_data.create_release_event(verify_add_unmap_queue)
         .unwrap().clone());
 
-    Box::new(future_read_data.and_then(move |mut data| {
+    Box::pin(future_read_data.map(move |res| res.and_then(|mut data| {
         let mut val_count = 0;
 
         for (idx, val) in data.iter().enumerate() {
@@ -350,7 +361,7 @@ pub fn verify_add(
         if PRINT { println!("* Verify add complete  \t(iter: {})", task_iter); }
 
         Ok(val_count)
-    }))
+    })))
 }
 
 /// Creates an out-of-order queue or a shorter error message if unsupported.
@@ -513,7 +524,7 @@ pub fn rw_vec() {
 
                 if PRINT { println!("All commands for iteration {} enqueued", task_iter); }
 
-                let task = write_init.join3(verify_init, verify_add);
+                let task = future::try_join3(write_init, verify_init, verify_add);
                 // let task = write_init.join(verify_add);
 
                 threads.push(thread::Builder::new()
@@ -521,7 +532,7 @@ pub fn rw_vec() {
                         .spawn(move ||
                 {
                     if PRINT { println!("Waiting on task iter [{}]...", task_iter); }
-                    match task.wait() {
+                    match block_on(task) {
                         Ok(res) => {
                             if PRINT { println!("Task iter [{}] complete with result: {:?}", task_iter, res); }
                             true
@@ -556,4 +567,590 @@ pub fn rw_vec() {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+
+#[test]
+fn join_and_select_events() {
+    let pro_que = nop_pro_que(64);
+    let gates: Vec<UserEvent> = (0..3).map(|_| UserEvent::new(pro_que.context()).unwrap())
+        .collect();
+    let events: Vec<_> = gates.iter().map(|gate| gate.clone().into_event()).collect();
+
+    let remote = gates[1].clone();
+    let completer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        remote.set_complete().unwrap();
+    });
+    let (idx, event) = block_on(select_events(events.clone())).unwrap();
+    completer.join().unwrap();
+    assert_eq!(idx, 1);
+    assert!(event.is_complete().unwrap());
+
+    let remote: Vec<UserEvent> = vec![gates[0].clone(), gates[2].clone()];
+    let completer = thread::spawn(move || {
+        for gate in remote {
+            thread::sleep(Duration::from_millis(10));
+            gate.set_complete().unwrap();
+        }
+    });
+    let list = block_on(join_events(events)).unwrap();
+    completer.join().unwrap();
+    assert_eq!(list.len(), 3);
+    assert!(list.is_complete().unwrap());
+
+    let failed = UserEvent::new(pro_que.context()).unwrap();
+    let pending = UserEvent::new(pro_que.context()).unwrap();
+    failed.set_error(Status::CL_OUT_OF_RESOURCES).unwrap();
+    let err = block_on(select_events(vec![pending.clone().into_event(),
+        failed.clone().into_event()])).unwrap_err();
+    assert_eq!(err.failed_event().unwrap().index(), 1);
+    let err = block_on(join_events(vec![failed.into_event()])).unwrap_err();
+    assert_eq!(err.failed_event().unwrap().index(), 0);
+    assert!(block_on(select_events(Vec::new())).is_err());
+    pending.set_complete().unwrap();
+}
+
+
+// Compile-time checks that future types can be spawned onto any
+// multi-threaded executor.
+fn assert_spawnable<T: Send + 'static>() {}
+
+#[test]
+fn futures_are_spawnable() {
+    assert_spawnable::<Event>();
+    assert_spawnable::<EventList>();
+    assert_spawnable::<EventArray<4>>();
+    assert_spawnable::<FutureFinish>();
+    assert_spawnable::<FutureKernel<WriteGuard<Vec<f32>>>>();
+    assert_spawnable::<FutureReadGuard<Vec<Int4>>>();
+    assert_spawnable::<FutureWriteGuard<Vec<Int4>>>();
+    assert_spawnable::<FutureUpgrade<Vec<Int4>>>();
+    assert_spawnable::<ReadGuard<Vec<Int4>>>();
+    assert_spawnable::<WriteGuard<Vec<Int4>>>();
+    assert_spawnable::<FutureMemMap<Int4>>();
+    assert_spawnable::<MemMap<Int4>>();
+    assert_spawnable::<FutureSharedMemMap<Int4>>();
+    assert_spawnable::<SharedMemMap<Int4>>();
+    assert_spawnable::<FutureWriteVec<Int4>>();
+    assert_spawnable::<FutureReadVec<Int4>>();
+    assert_spawnable::<FutureProgram>();
+    assert_spawnable::<FutureFlush<Int4>>();
+    assert_spawnable::<FutureFlood<Int4>>();
+    assert_spawnable::<EventListFuture>();
+    assert_spawnable::<EventSelect>();
+    assert_spawnable::<KernelStream<Int4>>();
+    assert_spawnable::<crate::Error>();
+}
+
+
+static KERNEL_FUTURE_SRC: &str = r#"
+    __kernel void add(__global int* buf, int addend) {
+        buf[get_global_id(0)] += addend;
+    }
+"#;
+
+#[test]
+fn kernel_enq_async() {
+    let pro_que = ProQue::builder().src(KERNEL_FUTURE_SRC).dims(512).build().unwrap();
+    let buffer = filled_buffer::<i32>(pro_que.queue(), 512, 1);
+    let kernel = pro_que.kernel_builder("add").arg(&buffer).arg(&5i32).build().unwrap();
+
+    let mut event = Event::empty();
+    let future_kernel = unsafe { kernel.cmd().enew(&mut event).enq_async().unwrap() };
+    block_on(async {
+        future_kernel.await.unwrap();
+    });
+    assert!(event.is_complete().unwrap());
+
+    // Hold a guard for the duration of a kernel, then reuse it:
+    let rw_vec = RwVec::from(vec![0i32; 512]);
+    let guard = rw_vec.clone().write().wait().unwrap();
+    let future_kernel = unsafe { kernel.cmd().enq_async_holding(guard).unwrap() };
+    let kernel_event = future_kernel.event().clone();
+    let mut guard = future_kernel.wait().unwrap();
+    assert!(kernel_event.is_complete().unwrap());
+    buffer.read(guard.as_mut_slice()).enq().unwrap();
+    assert!(guard.iter().all(|&v| v == 11));
+    drop(guard);
+
+    // Dropping an unresolved future holding a guard waits for the kernel:
+    let guard = rw_vec.clone().read().wait().unwrap();
+    drop(unsafe { kernel.cmd().enq_async_holding(guard).unwrap() });
+    assert!(rw_vec.clone().write().wait().is_ok());
+}
+
+
+static COMMAND_CHAIN_SRC: &str = r#"
+    __kernel void add(__global float* src, __global float* dst, float scalar) {
+        uint idx = get_global_id(0);
+        dst[idx] = src[idx] + scalar;
+    }
+"#;
+
+#[test]
+fn chain_write_kernel_read() {
+    let pro_que = ProQue::builder().src(COMMAND_CHAIN_SRC).dims(1024).build().unwrap();
+    let src_buffer = pro_que.create_buffer::<f32>().unwrap();
+    let dst_buffer = pro_que.create_buffer::<f32>().unwrap();
+    let kernel = pro_que.kernel_builder("add")
+        .arg(&src_buffer)
+        .arg(&dst_buffer)
+        .arg(10.0f32)
+        .build().unwrap();
+
+    let src_vec: Vec<f32> = (0..1024).map(|i| i as f32).collect();
+    let mut chain_event = Event::empty();
+    let chain = CommandChain::new()
+        .write(&src_buffer, src_vec.clone())
+        .kernel(&kernel)
+        .read(&dst_buffer)
+        .read(&src_buffer)
+        .enew(&mut chain_event);
+    assert_eq!(chain.len(), 4);
+
+    let future_chain = unsafe { chain.enq().unwrap() };
+    assert!(!chain_event.is_empty());
+    let mut reads = block_on(future_chain).unwrap();
+    assert!(chain_event.is_complete().unwrap());
+
+    assert_eq!(reads.len(), 2);
+    assert!(reads.take::<i32>(0).is_err());
+    let dst_vec = reads.take::<f32>(0).unwrap();
+    assert!(dst_vec.iter().zip(src_vec.iter()).all(|(&d, &s)| d == s + 10.0));
+    assert!(reads.take::<f32>(0).is_err());
+    assert_eq!(reads.take::<f32>(1).unwrap(), src_vec);
+    assert!(reads.take::<f32>(2).is_err());
+
+    // A chain on a separate queue, dropped before completion:
+    let queue = Queue::new(pro_que.context(), pro_que.device(), None).unwrap();
+    let future_chain = unsafe {
+        CommandChain::new()
+            .queue(&queue)
+            .ewait(&chain_event)
+            .write(&src_buffer, vec![1.0; 1024])
+            .kernel(&kernel)
+            .enq().unwrap()
+    };
+    drop(future_chain);
+    let mut dst_vec = vec![0.0f32; 1024];
+    dst_buffer.read(&mut dst_vec).enq().unwrap();
+    assert!(dst_vec.iter().all(|&d| d == 11.0));
+
+    // Guarded links take their place in the lock order of an `RwVec`:
+    let src_rw_vec = RwVec::from(vec![2.0f32; 1024]);
+    let dst_rw_vec = RwVec::from(vec![0.0f32; 1024]);
+    let future_chain = unsafe {
+        CommandChain::new()
+            .write_guarded(&src_buffer, src_rw_vec.clone().read())
+            .kernel(&kernel)
+            .read_guarded(&dst_buffer, dst_rw_vec.clone().write())
+            .enq().unwrap()
+    };
+    let later = dst_rw_vec.clone().read();
+    assert!(block_on(future_chain).unwrap().is_empty());
+    assert!(later.wait().unwrap().iter().all(|&d| d == 12.0));
+
+    // Dropping a chain resolves its guards:
+    let future_chain = unsafe {
+        CommandChain::new()
+            .write_guarded(&src_buffer, src_rw_vec.clone().read())
+            .enq().unwrap()
+    };
+    drop(future_chain);
+    assert!(src_rw_vec.clone().write().wait().is_ok());
+
+    // An empty chain resolves immediately:
+    let reads = unsafe { CommandChain::new().enq().unwrap() }.wait().unwrap();
+    assert!(reads.is_empty());
+}
+
+
+#[test]
+fn drop_pending_futures() {
+    let pro_que = nop_pro_que(1024);
+    let buffer = filled_buffer::<i32>(pro_que.queue(), 1024, 5);
+
+    // Blocking (the default) unmaps once the map command completes:
+    let mut unmap_event = Event::empty();
+    let future_map = unsafe { buffer.map().enq_async().unwrap() }.enew_unmap(&mut unmap_event);
+    assert_eq!(future_map.drop_policy(), DropPolicy::Block);
+    drop(future_map);
+    unmap_event.wait_for().unwrap();
+
+    // Detaching does not wait on a map command which can not yet run:
+    let gate = UserEvent::new(pro_que.context()).unwrap();
+    let mut unmap_event = Event::empty();
+    let future_map = unsafe { buffer.map().ewait(&gate).enq_async().unwrap() }
+        .enew_unmap(&mut unmap_event)
+        .with_drop_policy(DropPolicy::Detach);
+    drop(future_map);
+    assert!(!unmap_event.is_complete().unwrap());
+    gate.set_complete().unwrap();
+    unmap_event.wait_for().unwrap();
+
+    // Cancelling returns the unmap command's event:
+    let gate = UserEvent::new(pro_que.context()).unwrap();
+    let future_map = unsafe { buffer.map().ewait(&gate).enq_async().unwrap() };
+    let cancel_event = future_map.cancel().unwrap();
+    gate.set_complete().unwrap();
+    cancel_event.wait_for().unwrap();
+
+    // A detached write frees its `Vec` once complete:
+    let gate = UserEvent::new(pro_que.context()).unwrap();
+    let mut write_event = Event::empty();
+    let future_vec = buffer.write(vec![8i32; 1024]).ewait(&gate).enew(&mut write_event)
+        .enq_async_vec().unwrap()
+        .with_drop_policy(DropPolicy::Detach);
+    drop(future_vec);
+    gate.set_complete().unwrap();
+    write_event.wait_for().unwrap();
+    let mut read = vec![0i32; 1024];
+    buffer.read(&mut read).enq().unwrap();
+    assert!(read.iter().all(|&v| v == 8));
+
+    // Cancelling a write returns its `Vec`:
+    let vec = buffer.write(vec![2i32; 1024]).enq_async_vec().unwrap().cancel();
+    assert_eq!(vec.len(), 1024);
+
+    // A detached chain holds its data until complete:
+    let gate = UserEvent::new(pro_que.context()).unwrap();
+    let mut chain_event = Event::empty();
+    let future_chain = unsafe {
+        CommandChain::new()
+            .ewait(Event::from(gate.clone()))
+            .write(&buffer, vec![4i32; 1024])
+            .read(&buffer)
+            .enew(&mut chain_event)
+            .enq().unwrap()
+    }.with_drop_policy(DropPolicy::Detach);
+    drop(future_chain);
+    gate.set_complete().unwrap();
+    chain_event.wait_for().unwrap();
+    buffer.read(&mut read).enq().unwrap();
+    assert!(read.iter().all(|&v| v == 4));
+}
+
+#[cfg(not(feature = "async_block"))]
+#[test]
+fn blocking_deferred_function() {
+    let pro_que = nop_pro_que(1024);
+    let gate = UserEvent::new(pro_que.context()).unwrap();
+    let blocker = UserEvent::new(pro_que.context()).unwrap();
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    {
+        let blocker = blocker.clone();
+        crate::standard::completion::on_complete(&Event::from(gate.clone()), move || {
+            blocker.wait_for().unwrap();
+            tx.send(()).unwrap();
+        });
+    }
+    gate.set_complete().unwrap();
+
+    // Tasks are still woken while a deferred function blocks:
+    let other = UserEvent::new(pro_que.context()).unwrap();
+    let setter = {
+        let other = other.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            other.set_complete().unwrap();
+        })
+    };
+    block_on(Event::from(other)).unwrap();
+    setter.join().unwrap();
+    assert!(rx.try_recv().is_err());
+
+    blocker.set_complete().unwrap();
+    rx.recv_timeout(Duration::from_secs(5)).unwrap();
+}
+
+
+static ASYNC_WAITS_SRC: &str = r#"
+    __kernel void add(__global float* buffer, float scalar) {
+        buffer[get_global_id(0)] += scalar;
+    }
+"#;
+
+#[test]
+fn async_waits() {
+    let pro_que = ProQue::builder().src(ASYNC_WAITS_SRC).dims(1024).build().unwrap();
+
+    // Program build:
+    let program = block_on(Program::builder().src(ASYNC_WAITS_SRC)
+        .build_async(pro_que.context())).unwrap();
+    let kernel = crate::Kernel::builder()
+        .program(&program)
+        .name("add")
+        .queue(pro_que.queue().clone())
+        .global_work_size(1024)
+        .arg(None::<&Buffer<f32>>)
+        .arg(1.0f32)
+        .build().unwrap();
+    assert!(Program::builder().src("__kernel void broken( {").build_async(pro_que.context())
+        .wait().is_err());
+
+    // Event and event list:
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+    kernel.set_arg(0, &buffer).unwrap();
+    let mut kernel_event = Event::empty();
+    unsafe { kernel.cmd().enew(&mut kernel_event).enq().unwrap(); }
+    block_on(kernel_event.wait_async()).unwrap();
+    assert!(kernel_event.is_complete().unwrap());
+
+    let mut events = EventList::new();
+    unsafe { kernel.cmd().enew(&mut events).enq().unwrap(); }
+    unsafe { kernel.cmd().enew(&mut events).enq().unwrap(); }
+    let events = block_on(events.wait_all_async()).unwrap();
+    assert_eq!(events.len(), 2);
+
+    // Read into an owned `Vec`:
+    let mut read_event = Event::empty();
+    let vec = block_on(buffer.read(vec![0.0f32; 1024]).enew(&mut read_event)
+        .enq_async_vec().unwrap()).unwrap();
+    assert!(read_event.is_complete().unwrap());
+    assert!(vec.iter().all(|&v| v == 3.0));
+    assert!(buffer.read(vec).enq().is_err());
+
+    let vec = buffer.read(vec![0.0f32; 512]).offset(512).enq_async_vec().unwrap()
+        .wait().unwrap();
+    assert_eq!(vec.len(), 512);
+    assert!(vec.iter().all(|&v| v == 3.0));
+}
+
+
+#[test]
+fn scoped_read() {
+    let pro_que = nop_pro_que(1024);
+    let buffer = pro_que.buffer_builder::<u32>().fill_val(7).build().unwrap();
+    let mut vec = vec![0u32; 1024];
+    let mut tail = vec![0u32; 256];
+
+    scope(|scope| {
+        let mut read_event = Event::empty();
+        let slice = block_on(buffer.read(&mut vec[..]).enew(&mut read_event)
+            .enq_async_scoped(scope).unwrap()).unwrap();
+        assert!(read_event.is_complete().unwrap());
+        assert!(slice.iter().all(|&v| v == 7));
+
+        // Dropping (or leaking) the future is fine, the scope waits:
+        ::std::mem::forget(buffer.read(&mut tail[..]).offset(768).enq_async_scoped(scope)
+            .unwrap());
+    });
+    assert_eq!(vec, vec![7u32; 1024]);
+    assert_eq!(tail, vec![7u32; 256]);
+
+    // A destination other than a slice is rejected:
+    scope(|scope| {
+        assert!(buffer.read(vec![0u32; 1024]).enq_async_scoped(scope).is_err());
+    });
+}
+
+
+static KERNEL_STREAM_SRC: &str = r#"
+    __kernel void step(__global int* state, __global int* out) {
+        uint idx = get_global_id(0);
+        state[idx] += 1;
+        out[idx] = state[idx];
+    }
+"#;
+
+#[test]
+fn stream_kernel_iterations() {
+    let pro_que = ProQue::builder().src(KERNEL_STREAM_SRC).dims(256).build().unwrap();
+    let state = pro_que.create_buffer::<i32>().unwrap();
+    let buffers = [pro_que.create_buffer::<i32>().unwrap(),
+        pro_que.create_buffer::<i32>().unwrap()];
+    let kernel = pro_que.kernel_builder("step")
+        .arg(&state)
+        .arg_named("out", None::<&Buffer<i32>>)
+        .build().unwrap();
+    let out_idx = kernel.named_arg_idx("out").unwrap();
+    let read_queue = Queue::new(pro_que.context(), pro_que.device(), None).unwrap();
+
+    let mut stream = KernelStream::new(kernel, out_idx, buffers).iterations(5)
+        .read_queue(read_queue);
+
+    let mut step = 0;
+    block_on(async {
+        while let Some(frame) = stream.next().await {
+            let frame = frame.unwrap();
+            step += 1;
+            assert_eq!(frame.len(), 256);
+            assert!(frame.iter().all(|&v| v == step));
+        }
+    });
+    assert_eq!(step, 5);
+    assert_eq!(stream.enqueued(), 5);
+    assert!(block_on(stream.next()).is_none());
+
+    // Dropping an unbounded stream mid-flight waits on its pending reads:
+    let buffers = [pro_que.create_buffer::<i32>().unwrap(),
+        pro_que.create_buffer::<i32>().unwrap()];
+    let kernel = pro_que.kernel_builder("step").arg(&state).arg(&buffers[0]).build().unwrap();
+    let mut stream = KernelStream::new(kernel, 1, buffers);
+    assert!(block_on(stream.next()).unwrap().is_ok());
+    drop(stream);
+}
+
+
+#[test]
+fn upgrade_and_downgrade() {
+    let mut cx = TaskContext::from_waker(noop_waker_ref());
+    let rw_vec = RwVec::from(vec![1i32; 64]);
+
+    // A lone upgradable reader upgrades immediately:
+    let reader = rw_vec.clone().upgradable_read().wait().unwrap();
+    let mut writer = UpgradableReadGuard::upgrade(reader).wait().unwrap();
+    writer[0] = 2;
+
+    // Downgrading admits queued readers:
+    let mut future_reader = rw_vec.clone().read();
+    assert!(future_reader.poll_unpin(&mut cx).is_pending());
+    let reader_a = WriteGuard::downgrade(writer);
+    let reader_b = match future_reader.poll_unpin(&mut cx) {
+        Poll::Ready(guard) => guard.unwrap(),
+        Poll::Pending => panic!("Read request not fulfilled after downgrade."),
+    };
+    assert_eq!(reader_a[0], 2);
+    drop(reader_a);
+
+    // Upgradable reads are held alongside plain reads but not each other:
+    let upgradable = rw_vec.clone().upgradable_read().wait().unwrap();
+    let mut future_upgradable = rw_vec.clone().upgradable_read();
+    assert!(future_upgradable.poll_unpin(&mut cx).is_pending());
+
+    // An upgrade waits for the other reader to be released:
+    let mut future_writer = UpgradableReadGuard::upgrade(upgradable);
+    assert!(future_writer.poll_unpin(&mut cx).is_pending());
+    drop(reader_b);
+    match future_writer.poll_unpin(&mut cx) {
+        Poll::Ready(guard) => assert_eq!(guard.unwrap()[0], 2),
+        Poll::Pending => panic!("Upgrade not granted after other reader released."),
+    }
+    let upgradable = match future_upgradable.poll_unpin(&mut cx) {
+        Poll::Ready(guard) => guard.unwrap(),
+        Poll::Pending => panic!("Upgradable read request not fulfilled after release."),
+    };
+
+    // A plain read can not be upgraded while another read is upgradable:
+    assert!(rw_vec.clone().read().upgrade_after_command().wait().is_err());
+
+    // A withdrawn upgrade leaves the lock usable:
+    let reader_b = rw_vec.clone().read().wait().unwrap();
+    drop(UpgradableReadGuard::upgrade(upgradable));
+    drop(reader_b);
+    assert_eq!(rw_vec.clone().read().upgrade_after_command().wait().unwrap()[0], 2);
+    assert_eq!(rw_vec.clone().write().wait().unwrap()[0], 2);
+}
+
+#[test]
+fn read_analyze_write_cycle() {
+    let pro_que = nop_pro_que(256);
+    let buffer = filled_buffer::<i32>(pro_que.queue(), 256, 3);
+    let rw_vec = RwVec::from(vec![0i32; 256]);
+
+    let mut guard = buffer.read(rw_vec.clone().write()).enq_async().unwrap().wait().unwrap();
+
+    // Queued after the cycle began; requesting the lock again below would
+    // deadlock behind it:
+    let later = rw_vec.clone().write();
+
+    for val in guard.iter_mut() { *val *= 2; }
+    let reader = ReadGuard::into_future_guard(WriteGuard::downgrade(guard));
+    let guard = buffer.write(reader).enq_async().unwrap().wait().unwrap();
+    drop(guard);
+
+    let mut vec = vec![0i32; 256];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 6));
+    assert!(later.wait().unwrap().iter().all(|&v| v == 6));
+}
+
+
+#[test]
+fn shared_read_mapping() {
+    let pro_que = nop_pro_que(1024);
+    let buffer = filled_buffer::<i32>(pro_que.queue(), 1024, 7);
+
+    let mut unmap_event = Event::empty();
+    let future_map = unsafe { buffer.map().enq_async_shared().unwrap() }
+        .enew_unmap(&mut unmap_event);
+    let guard = future_map.wait().unwrap();
+
+    let guards: Vec<_> = (0..4).map(|_| guard.clone()).collect();
+    assert_eq!(guard.guard_count(), 5);
+
+    let readers: Vec<_> = guards.into_iter().map(|guard| {
+        thread::spawn(move || guard.iter().map(|&v| v as i64).sum::<i64>())
+    }).collect();
+
+    for reader in readers {
+        assert_eq!(reader.join().unwrap(), 7 * 1024);
+    }
+
+    // Unmapped only once the last guard is dropped:
+    assert_eq!(guard.guard_count(), 1);
+    assert!(!unmap_event.is_complete().unwrap());
+    drop(guard);
+    unmap_event.wait_for().unwrap();
+}
+
+
+#[test]
+fn write_from_owned_vec() {
+    let pro_que = nop_pro_que(1024);
+    let buffer = Buffer::<i32>::builder().queue(pro_que.queue().clone()).len(1024)
+        .build().unwrap();
+
+    let mut write_event = Event::empty();
+    let future_vec = buffer.write(vec![3i32; 1024]).enew(&mut write_event)
+        .enq_async_vec().unwrap();
+    let mut vec = block_on(future_vec).unwrap();
+    assert!(write_event.is_complete().unwrap());
+    assert_eq!(vec.len(), 1024);
+
+    let mut read = vec![0i32; 1024];
+    buffer.read(&mut read).enq().unwrap();
+    assert!(read.iter().all(|&v| v == 3));
+
+    // Reuse the returned `Vec` for a partial write:
+    for val in vec.iter_mut() { *val = 9; }
+    let vec = buffer.write(vec).offset(512).len(512).enq_async_vec().unwrap().wait().unwrap();
+    assert_eq!(vec.len(), 1024);
+    buffer.read(&mut read).enq().unwrap();
+    assert!(read[..512].iter().all(|&v| v == 3));
+    assert!(read[512..].iter().all(|&v| v == 9));
+
+    // Dropping the future before completion blocks rather than freeing the `Vec`:
+    drop(buffer.write(vec).enq_async_vec().unwrap());
+    assert!(buffer.write(vec![0i32; 4]).enq_async().is_err());
+    assert!(buffer.write(vec![0i32; 4]).enq().is_err());
+}
+
+
+static BUILD_FUTURE_SRC: &str = r#"
+    __kernel void add(__global float* buffer, float scalar) {
+        buffer[get_global_id(0)] += scalar;
+    }
+"#;
+
+#[test]
+fn build_with_callback() {
+    let pro_que = ProQue::builder().src(BUILD_FUTURE_SRC).dims(1024).build().unwrap();
+
+    let future_build = Program::builder().src(BUILD_FUTURE_SRC).build_future(pro_que.context())
+        .unwrap();
+    assert_eq!(future_build.devices().len(), pro_que.context().devices().len());
+    for (_, status) in future_build.progress().unwrap() {
+        assert!(status == ProgramBuildStatus::InProgress || status == ProgramBuildStatus::Success);
+    }
+    let program = block_on(future_build).unwrap();
+    assert!(program.kernel_names().unwrap().contains(&"add".to_owned()));
+
+    let err = Program::builder()
+        .src(("broken.cl", "__kernel void broken( {"))
+        .build_future(pro_que.context()).unwrap()
+        .wait().unwrap_err();
+    assert!(format!("{}", err).contains("broken.cl"));
+}
@@ -105,7 +105,7 @@ fn buffer_copy_standard() {
     unsafe { kernel.enq().unwrap(); }
 
     // Copy src to dst:
-    let copy_range = (IDX, pro_que.dims()[0] - 100);
+    let copy_range = (IDX, pro_que.dims().unwrap()[0] - 100);
     src_buffer.cmd().copy(&dst_buffer, Some(copy_range.0), Some(copy_range.1 - copy_range.0))
         .enq().unwrap();
 
@@ -113,7 +113,7 @@ fn buffer_copy_standard() {
     src_buffer.read(&mut src_vec).enq().unwrap();
     dst_buffer.read(&mut dst_vec).enq().unwrap();
 
-    for i in 0..pro_que.dims()[0] {
+    for i in 0..pro_que.dims().unwrap()[0] {
         assert!((src_vec[i] - ADDEND).abs() < 0.001);
 
         if i >= copy_range.0 && i < copy_range.1 {
@@ -38,7 +38,7 @@ fn buffer_ops_rect() {
         .build().unwrap();
 
     // SRC_BUFFER:
-    let mut vec = vec![0.0f32; proque.dims().to_len()];
+    let mut vec = vec![0.0f32; proque.dims().unwrap().to_len()];
     // let buf = unsafe { Buffer::new_unchecked(
     //     flags::MEM_READ_WRITE | flags::MEM_COPY_HOST_PTR,
     //     proque.dims().to_len().unwrap(), Some(&vec), proque.queue()) };
@@ -48,7 +48,7 @@ fn buffer_ops_rect() {
     let buf = Buffer::builder()
         .queue(proque.queue().clone())
         .flags(core::MEM_READ_WRITE | core::MEM_COPY_HOST_PTR)
-        .len(proque.dims().clone())
+        .len(*proque.dims().unwrap())
         .copy_host_slice(&vec)
         .build().unwrap();
 
@@ -64,11 +64,11 @@ fn buffer_ops_rect() {
     //========================================================================
     //========================================================================
     // Make sure that pro_que's dims are correct:
-    let dims = proque.dims().to_lens().unwrap();
+    let dims = proque.dims().unwrap().to_lens().unwrap();
     assert_eq!(DIMS, dims);
 
     // Verify buffer and vector lengths:
-    let len = proque.dims().to_len();
+    let len = proque.dims().unwrap().to_len();
     assert_eq!(buf.len(), len);
     assert_eq!(vec.len(), len);
 
@@ -79,7 +79,7 @@ fn buffer_ops_rect() {
     // READ AND VERIFY #1 (LINEAR):
     buf.read(&mut vec).enq().unwrap();
 
-    for idx in 0..proque.dims().to_len() {
+    for idx in 0..proque.dims().unwrap().to_len() {
         // DEBUG:
         // print!("[{:02}]", vec[i]);
         // if i % 20 == 19 { print!("\n"); }
@@ -242,7 +242,7 @@ fn buffer_ops_rect() {
     //========================================================================
     //========================================================================
     // Source Buffer:
-    let mut vec_src = vec![0.0f32; proque.dims().to_len()];
+    let mut vec_src = vec![0.0f32; proque.dims().unwrap().to_len()];
     // let buf_src = unsafe { Buffer::new_unchecked(
     //     flags::MEM_READ_ONLY | flags::MEM_HOST_WRITE_ONLY | flags::MEM_COPY_HOST_PTR,
     //     proque.dims().to_len().unwrap(), Some(&vec_src), proque.queue()) };
@@ -251,12 +251,12 @@ fn buffer_ops_rect() {
     let buf_src = Buffer::builder()
         .queue(proque.queue().clone())
         .flags(core::MEM_READ_WRITE | core::MEM_COPY_HOST_PTR)
-        .len(proque.dims().clone())
+        .len(*proque.dims().unwrap())
         .copy_host_slice(&vec_src)
         .build().unwrap();
 
     // Destination Buffer:
-    let mut vec_dst = vec![0.0f32; proque.dims().to_len()];
+    let mut vec_dst = vec![0.0f32; proque.dims().unwrap().to_len()];
     // let buf_dst = unsafe { Buffer::new_unchecked(
     //     flags::MEM_WRITE_ONLY | flags::MEM_HOST_READ_ONLY | flags::MEM_COPY_HOST_PTR,
     //     proque.dims().to_len().unwrap(), Some(&vec_dst), proque.queue()) };
@@ -265,7 +265,7 @@ fn buffer_ops_rect() {
     let buf_dst = Buffer::builder()
         .queue(proque.queue().clone())
         .flags(core::MEM_READ_WRITE | core::MEM_COPY_HOST_PTR)
-        .len(proque.dims().clone())
+        .len(*proque.dims().unwrap())
         .copy_host_slice(&vec_dst)
         .build().unwrap();
 
@@ -24,7 +24,6 @@
 
 extern crate chrono;
 extern crate futures;
-extern crate futures_cpupool;
 // extern crate fil_ocl as ocl;
 // #[macro_use] extern crate colorify;
 
@@ -32,12 +31,16 @@ use std::fmt::Debug;
 use std::thread::{self, JoinHandle};
 use std::sync::mpsc::{self, Receiver};
 use self::chrono::{Duration, DateTime, Local};
-use self::futures::{Future};
-use self::futures_cpupool::{Builder as CpuPoolBuilder, CpuFuture};
+use std::future::Future;
+use std::pin::Pin;
+use self::futures::{future, FutureExt, TryFutureExt};
+use self::futures::executor::{block_on, ThreadPool};
+use self::futures::future::RemoteHandle;
+use self::futures::task::SpawnExt;
 use crate::{Platform, Device, Context, Queue, Program, Kernel, Event, Buffer, RwVec};
 use crate::traits::{IntoRawEventArray};
 use crate::r#async::{BufferSink, BufferStream};
-use crate::error::{Error as OclError};
+use crate::error::{Result as OclResult};
 use crate::flags::{MemFlags, CommandQueueProperties};
 use crate::prm::Int4;
 use crate::ffi::{cl_event, c_void};
@@ -104,8 +107,8 @@ pub fn timestamp() -> String {
 }
 
 /// Returns a thread hooked up to the provided receiver which simply waits for
-/// completion of each `CpuFuture` sent until none remain.
-pub fn completion_thread<T, E>(rx: Receiver<Option<CpuFuture<T, E>>>)
+/// completion of each `RemoteHandle` sent until none remain.
+pub fn completion_thread<T, E>(rx: Receiver<Option<RemoteHandle<Result<T, E>>>>)
         -> JoinHandle<()>
         where T: Send + 'static, E: Send + Debug + 'static
 {
@@ -115,7 +118,7 @@ pub fn completion_thread<T, E>(rx: Receiver<Option<CpuFuture<T, E>>>)
         loop {
             match rx.recv().unwrap() {
                 Some(task) => {
-                    task.wait().unwrap();
+                    block_on(task).unwrap();
                     if PRINT { println!("Task {} complete (t: {}s)", task_i, timestamp()); }
                     task_i += 1;
                     continue;
@@ -194,7 +197,7 @@ pub fn write_init(src_buf_sink: &BufferSink<Int4>,
         write_val: i32, task_iter: i32)
         // -> AndThen<FutureMemMap<Int4>, OclResult<i32>,
         //     impl FnOnce(MemMap<Int4>) -> OclResult<i32>>
-        -> Box<dyn Future<Item=i32, Error=OclError> + Send>
+        -> Pin<Box<dyn Future<Output=OclResult<i32>> + Send>>
 {
     extern "C" fn _write_write_complete(_: cl_event, _: i32, task_iter : *mut c_void) {
         if PRINT {
@@ -236,7 +239,7 @@ pub fn write_init(src_buf_sink: &BufferSink<Int4>,
     }
 
     let future_write = future_write_data
-        .and_then(move |mut data| {
+        .map_ok(move |mut data| {
             if PRINT {
                 println!("* Write init starting \t\t(iter: {}, t: {}s) ...",
                     task_iter, timestamp());
@@ -246,10 +249,10 @@ pub fn write_init(src_buf_sink: &BufferSink<Int4>,
                 *val = Int4::new(write_val, write_val, write_val, write_val);
             }
 
-            Ok(task_iter)
+            task_iter
         });
 
-    Box::new(future_write.join(future_flush).map(|(task_iter, _)| task_iter))
+    Box::pin(future::try_join(future_write, future_flush).map_ok(|(task_iter, _)| task_iter))
 }
 
 
@@ -267,7 +270,7 @@ pub fn verify_init(src_buf: &Buffer<Int4>, dst_vec: &RwVec<Int4>, common_queue:
         correct_val: i32, task_iter: i32)
         // -> AndThen<PendingRwGuard<Int4>, OclResult<i32>,
         //     impl FnOnce(RwGuard<Int4>) -> OclResult<i32>>
-        -> Box<dyn Future<Item=i32, Error=OclError> + Send>
+        -> Pin<Box<dyn Future<Output=OclResult<i32>> + Send>>
 {
     extern "C" fn _verify_starting(_: cl_event, _: i32, task_iter : *mut c_void) {
         if PRINT {
@@ -302,7 +305,7 @@ pub fn verify_init(src_buf: &Buffer<Int4>, dst_vec: &RwVec<Int4>, common_queue:
         .unwrap().clone());
 
     // The future which will actually verify the initial value:
-    Box::new(future_read_data.and_then(move |data| {
+    Box::pin(future_read_data.map(move |res| res.and_then(|data| {
         let mut val_count = 0;
 
         for (idx, val) in data.iter().enumerate() {
@@ -320,7 +323,7 @@ pub fn verify_init(src_buf: &Buffer<Int4>, dst_vec: &RwVec<Int4>, common_queue:
         }
 
         Ok(val_count)
-    }))
+    })))
 }
 
 
@@ -400,7 +403,7 @@ pub fn verify_add(dst_buf_stream: &BufferStream<Int4>,
         correct_val: i32, task_iter: i32)
         // -> AndThen<FutureMemMap<Int4>, OclResult<i32>,
         //     impl FnOnce(MemMap<Int4>) -> OclResult<i32>>
-        -> Box<dyn Future<Item=i32, Error=OclError> + Send>
+        -> Pin<Box<dyn Future<Output=OclResult<i32>> + Send>>
 {
     extern "C" fn _verify_starting(_: cl_event, _: i32, task_iter : *mut c_void) {
         if PRINT {
@@ -425,7 +428,7 @@ pub fn verify_add(dst_buf_stream: &BufferStream<Int4>,
     let future_read_data = dst_buf_stream.clone().read()
         .enew_release(queue, verify_add_event.as_mut().unwrap());
 
-    let future_read = future_read_data.and_then(move |data| {
+    let future_read = future_read_data.map(move |res| res.and_then(|data| {
         let mut val_count = 0;
         let cval = Int4::splat(correct_val);
 
@@ -443,9 +446,9 @@ pub fn verify_add(dst_buf_stream: &BufferStream<Int4>,
         }
 
         Ok(val_count)
-    });
+    }));
 
-    Box::new(future_flood.join(future_read).map(|(_, task_iter)| task_iter))
+    Box::pin(future::try_join(future_flood, future_read).map_ok(|(_, task_iter)| task_iter))
 }
 
 
@@ -533,10 +536,10 @@ pub fn buffer_sink_stream_cycles() {
 
     // Thread pool for offloaded tasks.
     // let thread_pool = CpuPool::new_num_cpus();
-    let thread_pool = CpuPoolBuilder::new().name_prefix("pool_th_").create();
+    let thread_pool = ThreadPool::builder().name_prefix("pool_th_").create().unwrap();
 
     // A channel with room to keep a pre-specified number of tasks in-flight.
-    let (tx, rx) = mpsc::sync_channel::<Option<CpuFuture<_, _>>>(MAX_CONCURRENT_TASK_COUNT - 2);
+    let (tx, rx) = mpsc::sync_channel::<Option<RemoteHandle<_>>>(MAX_CONCURRENT_TASK_COUNT - 2);
 
     // Create a thread to handle the stream of work. If this were graphics,
     // this thread could represent the processing being done after a 'finish'
@@ -603,8 +606,8 @@ pub fn buffer_sink_stream_cycles() {
                 task_iter, timestamp());
         }
 
-        let join = write_init.join3(verify_init, verify_add);
-        let join_spawned = thread_pool.spawn(join);
+        let join = future::try_join3(write_init, verify_init, verify_add);
+        let join_spawned = thread_pool.spawn_with_handle(join).unwrap();
 
         // This places our already spawned and running task into the queue for
         // later collection by our completion thread. This call will block if
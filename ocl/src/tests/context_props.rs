@@ -69,4 +69,113 @@ fn test_context_props() {
         //     event.wait().unwrap();
         // }
     }
-}
\ No newline at end of file
+}
+#[test]
+fn context_error_callback() {
+    use std::sync::{Arc, Mutex};
+    use crate::standard::{Buffer, Queue};
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let errors_cb = errors.clone();
+    let context = Context::builder()
+        .error_callback(move |msg, _info| errors_cb.lock().unwrap().push(msg.to_owned()))
+        .build().unwrap();
+
+    // The context is usable as normal:
+    let queue = Queue::new(&context, context.devices()[0], None).unwrap();
+    let buffer = Buffer::<u32>::builder().queue(queue).len(64).fill_val(3).build().unwrap();
+    let mut vec = vec![0u32; 64];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 3));
+
+    // Any errors reported must have come through the closure intact:
+    assert!(errors.lock().unwrap().iter().all(|msg| !msg.is_empty()));
+
+    // The closure is released with the last `Context`:
+    drop(buffer);
+    drop(context);
+    assert_eq!(Arc::strong_count(&errors), 1);
+}
+
+#[test]
+fn context_multi_device() {
+    let platform = Platform::default();
+    let devices = Device::list_all(platform).unwrap();
+    if devices.is_empty() { return; }
+
+    // The platform is taken from the listed devices:
+    let context = Context::builder().devices(&devices).build().unwrap();
+    assert_eq!(context.devices(), devices);
+    assert_eq!(context.num_devices().unwrap() as usize, devices.len());
+    assert!(context.reference_count().unwrap() >= 1);
+    assert_eq!(context.properties().unwrap().get_platform(), Some(*platform.as_core()));
+    let queues = context.queues_for_all_devices().unwrap();
+    assert_eq!(queues.len(), devices.len());
+    for (queue, device) in queues.iter().zip(devices.iter()) {
+        assert_eq!(queue.device(), *device);
+        queue.finish().unwrap();
+    }
+
+    let twice = [devices[0], devices[0]];
+    let err = Context::builder().platform(platform).devices(&twice[..]).build().unwrap_err();
+    assert!(err.to_string().contains("more than once"));
+}
+
+#[test]
+fn context_from_type() {
+    use crate::core::{DeviceType, Status};
+
+    let context = Context::from_type(DeviceType::ALL).unwrap();
+    let platform = context.platform().unwrap().unwrap();
+    assert_eq!(context.devices(), Device::list_all(platform).unwrap());
+
+    // A type which no platform has fails with the last platform's error:
+    let has_custom = Platform::list().unwrap().into_iter()
+        .any(|p| !Device::list(p, Some(DeviceType::CUSTOM)).unwrap().is_empty());
+    if !has_custom {
+        let err = Context::from_type(DeviceType::CUSTOM).unwrap_err();
+        assert_eq!(err.api_status(), Some(Status::CL_DEVICE_NOT_FOUND));
+    }
+}
+
+#[test]
+fn context_props_d3d11_device() {
+    use crate::core::ContextProperties;
+    use crate::ffi::{c_void, CL_CONTEXT_D3D11_DEVICE_KHR};
+
+    let d3d11_device = 0x1000 as *mut c_void;
+    let props = ContextProperties::new().d3d11_device(d3d11_device);
+    assert_eq!(props.to_raw(), vec![CL_CONTEXT_D3D11_DEVICE_KHR as isize, 0x1000, 0]);
+}
+
+#[test]
+fn context_props_typed() {
+    use crate::core::{ContextProperties, ContextProperty};
+    use crate::ffi::{c_void, CL_CONTEXT_INTEROP_USER_SYNC, CL_GL_CONTEXT_KHR, CL_GLX_DISPLAY_KHR};
+
+    let props = ContextProperties::new()
+        .vendor(0x4300, 7)
+        .interop_user_sync(true)
+        .gl_context(0x10 as *mut c_void)
+        .vendor(0x4300, 8);
+    assert!(props.validate().is_ok());
+    assert!(props.get_interop_user_sync());
+    assert_eq!(props.get_vendor(), &[(0x4300, 8)]);
+
+    let raw = props.to_raw();
+    assert_eq!(raw, vec![CL_CONTEXT_INTEROP_USER_SYNC as isize, 1, CL_GL_CONTEXT_KHR, 0x10,
+        0x4300, 8, 0]);
+
+    let parsed = unsafe { ContextProperties::from_raw(&raw).unwrap() };
+    assert_eq!(parsed.get_vendor(), &[(0x4300, 8)]);
+    assert!(parsed.get(ContextProperty::GlContextKhr).is_some());
+
+    // A display requires a GL context:
+    let props = ContextProperties::new().glx_display(0x20 as *mut c_void);
+    assert!(props.validate().is_err());
+    assert!(props.clone().gl_context(0x10 as *mut c_void).validate().is_ok());
+
+    // Standard properties can not be passed as vendor properties:
+    let props = ContextProperties::new().vendor(CL_GLX_DISPLAY_KHR, 1);
+    assert!(props.validate().unwrap_err().to_string().contains("GlxDisplayKhr"));
+}
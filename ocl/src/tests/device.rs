@@ -0,0 +1,266 @@
+use crate::core::{DeviceType, DeviceAffinityDomain, DevicePartition, NameVersion, NumericVersion,
+    OpenclVersion};
+use crate::standard::{Platform, Device, DeviceCriteria, DeviceSpecifier, Context, ProQue, Queue,
+    SubDevice, DeviceExtensions, Event};
+use super::{nop_pro_que, filled_buffer};
+
+#[test]
+fn device_select() {
+    let all = Device::select(&DeviceCriteria::new()).unwrap();
+    if all.is_empty() { return; }
+    for device in &all {
+        assert!(device.is_available().unwrap());
+    }
+
+    // Devices are sorted best first:
+    let best = all[0];
+    for device in &all[1..] {
+        if device.device_type().unwrap() == best.device_type().unwrap() {
+            assert!(device.max_compute_units().unwrap() as u64
+                * device.max_clock_frequency().unwrap() as u64
+                <= best.max_compute_units().unwrap() as u64
+                * best.max_clock_frequency().unwrap() as u64);
+        }
+    }
+
+    let criteria = DeviceCriteria::new()
+        .device_type(best.device_type().unwrap())
+        .min_global_mem_size(best.global_mem_size().unwrap())
+        .min_version(best.version().unwrap());
+    assert!(Device::select(&criteria).unwrap().contains(&best));
+    assert!(Device::select(&DeviceCriteria::new().extension("cl_nonexistent_ext")).unwrap()
+        .is_empty());
+    assert!(Device::select(&DeviceCriteria::new().min_global_mem_gb(u64::max_value())).unwrap()
+        .is_empty());
+
+    // As a device specifier:
+    let platform = best.platform().unwrap();
+    let context = Context::builder().platform(platform)
+        .devices(DeviceCriteria::new().device_type(DeviceType::ALL))
+        .build().unwrap();
+    assert_eq!(context.devices()[0], DeviceCriteria::new()
+        .filter(&Device::list_all(platform).unwrap()).unwrap()[0]);
+
+    let err = Context::builder().platform(Platform::default())
+        .devices(DeviceCriteria::new().min_compute_units(u32::max_value()))
+        .build().unwrap_err();
+    assert!(err.to_string().contains("selection criteria"));
+}
+
+#[test]
+fn device_first_default() {
+    let platform = Platform::default();
+    let devices = Device::list(platform, None).unwrap();
+    if devices.is_empty() { return; }
+
+    // Respects `OCL_DEFAULT_DEVICE_*` if set, otherwise the first device:
+    let first = Device::first(platform).unwrap();
+    assert!(devices.contains(&first));
+    assert_eq!(*first.as_core(), crate::core::default_device(&platform).unwrap());
+    assert_eq!(DeviceSpecifier::First.to_device_list(Some(platform)).unwrap(), vec![first]);
+    if std::env::var_os("OCL_DEFAULT_DEVICE_NAME").is_none()
+            && std::env::var_os("OCL_DEFAULT_DEVICE_IDX").is_none() {
+        assert_eq!(first, devices[0]);
+    }
+}
+
+#[test]
+fn device_list_ranked() {
+    let platform = Platform::default();
+    let ranked = Device::list_ranked(platform).unwrap();
+    for pair in ranked.windows(2) {
+        assert!(pair[0].1 >= pair[1].1);
+    }
+    for &(device, score) in &ranked {
+        assert_eq!(device.score().unwrap(), score);
+        assert_eq!(score.global_mem_size, device.global_mem_size().unwrap());
+    }
+    let filtered = DeviceCriteria::new().filter(&Device::list_all(platform).unwrap()).unwrap();
+    assert_eq!(ranked.iter().map(|&(d, _)| d).collect::<Vec<_>>(), filtered);
+}
+
+#[test]
+fn pro_que_device_criteria() {
+    let best = match Device::select(&DeviceCriteria::new()).unwrap().first() {
+        Some(&device) => device,
+        None => return,
+    };
+
+    let pro_que = ProQue::builder()
+        .src("__kernel void nop() {}")
+        .device_criteria(DeviceCriteria::new()
+            .device_type(best.device_type().unwrap())
+            .min_global_mem_size(best.global_mem_size().unwrap()))
+        .build().unwrap();
+    assert!(DeviceCriteria::new().device_type(best.device_type().unwrap())
+        .matches(&pro_que.device()).unwrap());
+
+    let criteria = DeviceCriteria::gpu().min_global_mem_gb(1 << 20)
+        .extension("cl_nonexistent_ext");
+    let err = ProQue::builder()
+        .src("__kernel void nop() {}")
+        .device_criteria(criteria.clone())
+        .build().unwrap_err().to_string();
+    assert!(err.contains(&criteria.to_string()), "{}", err);
+    assert!(err.contains("cl_nonexistent_ext"));
+    assert!(err.contains(&best.name().unwrap()), "{}", err);
+}
+
+
+/// Returns a device which can be partitioned equally, if there is one.
+fn partitionable_device() -> Option<Device> {
+    Device::list(Platform::default(), Some(DeviceType::CPU)).ok()?.into_iter()
+        .find(|d| d.version().map(|v| v >= [1, 2].into()).unwrap_or(false)
+            && d.max_compute_units().map(|n| n >= 2).unwrap_or(false))
+}
+
+#[test]
+fn sub_devices() {
+    let device = match partitionable_device() {
+        Some(device) => device,
+        None => return,
+    };
+    let units = device.max_compute_units().unwrap();
+
+    let sub_devices: Vec<SubDevice> = match device.partition_equally(1) {
+        Ok(sub_devices) => sub_devices,
+        // Equal partitioning is not supported by every CPU device:
+        Err(_) => return,
+    };
+    assert_eq!(sub_devices.len(), units as usize);
+    for sub_device in &sub_devices {
+        assert_eq!(sub_device.max_compute_units().unwrap(), 1);
+    }
+
+    let by_counts = device.partition_by_counts(&[1, units - 1]).unwrap();
+    assert_eq!(by_counts.len(), 2);
+    assert_eq!(by_counts[1].max_compute_units().unwrap(), units - 1);
+
+    // Contexts keep their sub-devices alive:
+    let context = Context::builder().platform(device.platform().unwrap())
+        .devices(&sub_devices[..1]).build().unwrap();
+    let cloned = sub_devices[0].clone();
+    drop(sub_devices);
+    let queue = Queue::new(&context, cloned.device(), None).unwrap();
+    queue.finish().unwrap();
+
+    // Not every device supports every affinity domain:
+    let _ = device.partition_by_affinity(DeviceAffinityDomain::NEXT_PARTITIONABLE);
+}
+
+#[test]
+fn numa_queues() {
+    let device = match partitionable_device() {
+        Some(device) => device,
+        None => return,
+    };
+    if !device.supports_numa_partition().unwrap() { return; }
+
+    let queues = device.numa_queues(None).unwrap();
+    assert!(!queues.is_empty());
+    for (idx, &(ref node, ref queue)) in queues.iter().enumerate() {
+        assert_eq!(node.partition_idx(), idx);
+        assert!(node.compute_units().unwrap() >= 1);
+        assert_eq!(queue.device(), node.device());
+        match node.device().partition_type().unwrap() {
+            Some(DevicePartition::ByAffinityDomain(domain)) => {
+                assert!(domain.contains(DeviceAffinityDomain::NUMA))
+            },
+            other => panic!("Unexpected partition type: {:?}", other),
+        }
+        queue.finish().unwrap();
+    }
+    assert_eq!(device.partition_type().unwrap(), None);
+}
+
+
+#[test]
+fn device_extensions_parse() {
+    let exts = DeviceExtensions::from_names("cl_khr_fp64  cl_khr_int64_base_atomics\n");
+    assert_eq!(exts.len(), 2);
+    assert!(exts.contains("cl_khr_fp64"));
+    assert!(!exts.contains("cl_khr_fp"));
+    assert_eq!(exts.version("cl_khr_fp64"), None);
+    assert_eq!(exts.to_string(), "cl_khr_fp64 cl_khr_int64_base_atomics");
+
+    let exts = DeviceExtensions::from_name_versions(vec![NameVersion {
+        name: "cl_khr_fp64".to_owned(),
+        version: NumericVersion::new(1, 0, 0),
+    }]);
+    assert_eq!(exts.version("cl_khr_fp64"), Some(NumericVersion::new(1, 0, 0)));
+    assert_eq!(exts.iter().collect::<Vec<_>>(), vec!["cl_khr_fp64"]);
+}
+
+#[test]
+fn device_has_extension() {
+    for device in Device::select(&DeviceCriteria::new()).unwrap() {
+        let exts = device.extensions().unwrap();
+        for name in exts.iter() {
+            assert!(device.has_extension(name).unwrap());
+        }
+        assert!(!device.has_extension("cl_nonexistent_ext").unwrap());
+    }
+}
+
+
+#[test]
+fn platform_select() {
+    let platforms = Platform::list().unwrap();
+    if platforms.is_empty() {
+        assert!(Platform::preferred().is_err());
+        return;
+    }
+    assert_eq!(Platform::list_filtered(None, None).unwrap().len(), platforms.len());
+
+    let preferred = Platform::preferred().unwrap();
+    assert!(platforms.iter().any(|p| p.as_core() == preferred.as_core()));
+
+    let vendor = preferred.vendor().unwrap().to_uppercase();
+    let version = preferred.as_core().version().unwrap();
+    assert!(Platform::list_filtered(Some(&vendor), Some(version)).unwrap().iter()
+        .any(|p| p.as_core() == preferred.as_core()));
+    assert!(Platform::list_filtered(Some("no such vendor"), None).unwrap().is_empty());
+    assert!(Platform::list_filtered(None, Some(OpenclVersion::new(99, 0))).unwrap().is_empty());
+}
+
+
+#[test]
+fn capability_report() {
+    for device in Device::select(&DeviceCriteria::new()).unwrap() {
+        let report = device.capability_report().unwrap();
+        assert_eq!(report.name, device.name().unwrap());
+        assert_eq!(report.opencl_version, device.version().unwrap().to_string());
+        assert_eq!(report.global_mem_size, device.global_mem_size().unwrap());
+        assert_eq!(report.max_work_group_size, device.max_wg_size().unwrap());
+        assert_eq!(report.extensions.len(), device.extensions().unwrap().len());
+
+        #[cfg(feature = "serde")]
+        {
+            use crate::standard::CapabilityReport;
+            let text = serde_json::to_string(&report).unwrap();
+            assert_eq!(serde_json::from_str::<CapabilityReport>(&text).unwrap(), report);
+        }
+    }
+}
+
+
+#[test]
+fn pinned_internal_threads() {
+    let pro_que = nop_pro_que(64);
+    let device = pro_que.device();
+    // Pinning needs a known node (and is a no-op without one):
+    let node = device.numa_node().unwrap();
+    assert!(node.is_some() || !device.pin_current_thread().unwrap());
+
+    // Waits are still woken by a (possibly pinned) waker thread:
+    Device::set_pin_internal_threads(true);
+    let buffer = filled_buffer::<u32>(pro_que.queue(), 64, 1);
+    let mut event = Event::empty();
+    buffer.cmd().fill(2, None).enew(&mut event).enq().unwrap();
+    futures::executor::block_on(event).unwrap();
+    Device::set_pin_internal_threads(false);
+
+    let mut vec = vec![0u32; 64];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 2));
+}
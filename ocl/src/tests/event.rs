@@ -0,0 +1,453 @@
+use std::sync::mpsc;
+use std::time::Duration;
+use futures::executor::block_on;
+use crate::core::{CommandExecutionStatus, Status, CommandType};
+use crate::standard::{ProQue, Buffer, Event, EventList, UserEvent, Queue, CommandGraph, EventArray,
+    Trace};
+use std::thread;
+use crate::flags::CommandQueueProperties;
+use std::task::Poll;
+use futures::{future, FutureExt};
+use super::{nop_pro_que};
+
+#[test]
+fn event_on_complete() {
+    let pro_que = nop_pro_que(1024);
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    let mut event = Event::empty();
+    buffer.cmd().fill(5, None).enew(&mut event).enq().unwrap();
+    let tx_fill = tx.clone();
+    event.on_complete(move |status| tx_fill.send(status.unwrap()).unwrap()).unwrap();
+    pro_que.queue().flush().unwrap();
+
+    let status = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(status, CommandExecutionStatus::Complete);
+
+    // Registered on an already completed event, the callback still runs:
+    event.on_complete(move |status| tx.send(status.unwrap()).unwrap()).unwrap();
+    assert_eq!(rx.recv_timeout(Duration::from_secs(10)).unwrap(), CommandExecutionStatus::Complete);
+
+    // Empty events have no callbacks:
+    assert!(Event::empty().on_complete(|_| ()).is_err());
+}
+
+#[test]
+fn event_channels() {
+    let pro_que = nop_pro_que(1024);
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+
+    let mut event = Event::empty();
+    buffer.cmd().fill(5, None).enew(&mut event).enq().unwrap();
+    let rx = event.into_oneshot().unwrap();
+    pro_que.queue().flush().unwrap();
+    block_on(rx).unwrap().unwrap();
+
+    let gate = UserEvent::new(pro_que.context()).unwrap();
+    let mut events = EventList::new();
+    events.push(gate.clone().into_event());
+    for _ in 0..4 {
+        buffer.cmd().fill(6, None).ewait(&gate).enew(&mut events).enq().unwrap();
+    }
+    pro_que.queue().flush().unwrap();
+    let rx = events.into_receiver().unwrap();
+    assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+    gate.set_complete().unwrap();
+    rx.recv_timeout(Duration::from_secs(10)).unwrap().unwrap();
+
+    assert!(EventList::new().into_receiver().unwrap().recv().unwrap().is_ok());
+
+    let failed = UserEvent::new(pro_que.context()).unwrap();
+    let pending = UserEvent::new(pro_que.context()).unwrap();
+    let rx = EventList::from(vec![pending.clone().into_event(), failed.clone().into_event()])
+        .into_receiver().unwrap();
+    failed.set_error(Status::CL_OUT_OF_RESOURCES).unwrap();
+    let err = rx.recv_timeout(Duration::from_secs(10)).unwrap().unwrap_err();
+    assert_eq!(err.failed_event().unwrap().index(), 1);
+    pending.set_complete().unwrap();
+}
+
+
+#[test]
+fn user_event_gates_commands() {
+    let pro_que = nop_pro_que(1024);
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+
+    let gate = UserEvent::new(pro_que.context()).unwrap();
+    let mut fill_event = Event::empty();
+    buffer.cmd().fill(7, None).ewait(&gate).enew(&mut fill_event).enq().unwrap();
+    pro_que.queue().flush().unwrap();
+
+    thread::sleep(Duration::from_millis(50));
+    assert!(!fill_event.is_complete().unwrap());
+
+    // Host-side work completes on another thread:
+    let remote = gate.clone();
+    thread::spawn(move || remote.set_complete().unwrap()).join().unwrap();
+    fill_event.wait_for().unwrap();
+
+    let mut vec = vec![0u32; buffer.len()];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 7));
+
+    // A status may only be set once and must be an error:
+    assert!(gate.set_complete().is_err());
+    let failed = UserEvent::new(pro_que.context()).unwrap();
+    assert!(failed.set_error(Status::CL_SUCCESS).is_err());
+    failed.set_error(Status::CL_OUT_OF_RESOURCES).unwrap();
+    assert!(failed.set_complete().is_err());
+}
+
+#[test]
+fn event_list_failed_event() {
+    let pro_que = nop_pro_que(64);
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+
+    let mut events = EventList::new();
+    buffer.cmd().fill(1, None).enew(&mut events).enq().unwrap();
+    let failed = UserEvent::new(pro_que.context()).unwrap();
+    failed.set_error(Status::CL_OUT_OF_RESOURCES).unwrap();
+    events.push(failed.clone().into_event());
+
+    let err = events.wait_for().unwrap_err();
+    let failed_event = err.failed_event().expect("no failed event");
+    assert_eq!(failed_event.index(), 1);
+    assert_eq!(failed_event.status(), Some(Status::CL_OUT_OF_RESOURCES));
+    assert_eq!(failed_event.command_type(), Some(CommandType::User));
+}
+
+#[test]
+fn event_status_polling() {
+    let pro_que = nop_pro_que(64);
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+
+    let mut events = EventList::new();
+    buffer.cmd().fill(1, None).enew(&mut events).enq().unwrap();
+    events.wait_for().unwrap();
+    let gate = UserEvent::new(pro_que.context()).unwrap();
+    events.push(gate.clone().into_event());
+
+    assert_eq!(gate.status().unwrap(), CommandExecutionStatus::Submitted);
+    assert_eq!(events.first_incomplete().unwrap(), Some(1));
+    assert!(!events.is_complete().unwrap());
+
+    gate.set_complete().unwrap();
+    assert_eq!(gate.status().unwrap(), CommandExecutionStatus::Complete);
+    assert_eq!(events.first_incomplete().unwrap(), None);
+    assert!(events.is_complete().unwrap());
+
+    let failed = UserEvent::new(pro_que.context()).unwrap();
+    failed.set_error(Status::CL_OUT_OF_RESOURCES).unwrap();
+    assert!(failed.status().is_err());
+    events.push(failed.into_event());
+    assert_eq!(events.first_incomplete().unwrap_err().failed_event().unwrap().index(), 2);
+}
+
+#[test]
+fn event_list_auto_prune() {
+    let pro_que = nop_pro_que(64);
+
+    let mut events = EventList::new();
+    events.set_auto_prune(Some(4));
+    assert_eq!(events.auto_prune_threshold(), Some(4));
+
+    let gate = UserEvent::new(pro_que.context()).unwrap();
+    events.push(gate.clone().into_event());
+
+    for _ in 0..32 {
+        let done = UserEvent::new(pro_que.context()).unwrap();
+        done.set_complete().unwrap();
+        events.push(done.into_event());
+        assert!(events.len() <= 4);
+    }
+    assert_eq!(events.first_incomplete().unwrap(), Some(0));
+
+    // Pending events are never pruned.
+    let gates: Vec<_> = (0..16).map(|_| UserEvent::new(pro_que.context()).unwrap()).collect();
+    for gate in &gates { events.push(gate.clone().into_event()); }
+    assert!(events.len() >= 17);
+
+    gate.set_complete().unwrap();
+    for gate in &gates { gate.set_complete().unwrap(); }
+    events.clear_completed().unwrap();
+    assert!(events.is_empty());
+
+    events.set_auto_prune(None);
+    for _ in 0..16 {
+        let done = UserEvent::new(pro_que.context()).unwrap();
+        done.set_complete().unwrap();
+        events.push(done.into_event());
+    }
+    assert_eq!(events.len(), 16);
+}
+
+
+#[test]
+fn command_graph_enq() {
+    let pro_que = ProQue::builder()
+        .src("__kernel void add(__global uint* buf, uint val) { buf[get_global_id(0)] += val; }")
+        .dims(64)
+        .build().unwrap();
+
+    // Dependencies only matter on an out-of-order queue but are harmless
+    // otherwise.
+    let queue = Queue::new(&pro_que.context(), pro_que.device(),
+        Some(CommandQueueProperties::OUT_OF_ORDER_EXEC_MODE_ENABLE))
+        .unwrap_or_else(|_| pro_que.queue().clone());
+    let buffer = Buffer::<u32>::builder().queue(queue.clone()).len(64).build().unwrap();
+    let add_one = pro_que.kernel_builder("add").queue(queue.clone()).arg(&buffer).arg(1u32)
+        .build().unwrap();
+    let add_ten = pro_que.kernel_builder("add").queue(queue.clone()).arg(&buffer).arg(10u32)
+        .build().unwrap();
+
+    let data = vec![5u32; 64];
+    let mut result = vec![0u32; 64];
+    {
+        let mut graph = CommandGraph::new();
+        // Added out of order; dependencies determine the enqueue order.
+        graph.add("download", buffer.cmd().read(&mut result)).after("add_ten");
+        graph.add("add_ten", |ewait: &EventList, enew: &mut Event| unsafe {
+            add_ten.cmd().ewait(ewait).enew(enew).enq()
+        }).after("add_one");
+        graph.add("add_one", |ewait: &EventList, enew: &mut Event| unsafe {
+            add_one.cmd().ewait(ewait).enew(enew).enq()
+        }).after("upload");
+        graph.add("upload", buffer.cmd().write(&data));
+        assert_eq!(graph.len(), 4);
+
+        graph.enq().unwrap();
+        assert!(graph.enq().is_err());
+        graph.wait_for().unwrap();
+        assert!(graph.event("upload").unwrap().is_complete().unwrap());
+    }
+    assert_eq!(result, vec![16u32; 64]);
+}
+
+
+#[test]
+fn option_and_array_event_args() {
+    let pro_que = ProQue::builder()
+        .src("__kernel void add(__global uint* buf, uint val) { buf[get_global_id(0)] += val; }")
+        .dims(64)
+        .build().unwrap();
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+    let kernel = pro_que.kernel_builder("add").arg(&buffer).arg(1u32).build().unwrap();
+
+    let mut first: Option<Event> = None;
+    buffer.cmd().fill(1, None).enew(&mut first).enq().unwrap();
+    let first = first.unwrap();
+
+    // A destination which already holds an event has it replaced.
+    let mut second = Some(first.clone());
+    buffer.cmd().fill(2, None).ewait(&Some(first.clone())).enew(&mut second).enq().unwrap();
+    let second = second.unwrap();
+    assert!(second != first);
+
+    let mut kernel_event = Event::empty();
+    unsafe { kernel.cmd().ewait(&[first, second]).enew(&mut kernel_event).enq().unwrap(); }
+
+    let mut added: Option<Event> = None;
+    unsafe { kernel.cmd().ewait(&kernel_event).enew(&mut added).enq().unwrap(); }
+
+    let none: Option<Event> = None;
+    let mut result = vec![0u32; 64];
+    buffer.cmd().read(&mut result).ewait(&none).ewait(&added).enq().unwrap();
+    assert_eq!(result, vec![4u32; 64]);
+}
+
+
+#[test]
+fn inline_event_array() {
+    let pro_que = nop_pro_que(64);
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+
+    let mut events = EventArray::<16>::new();
+    assert_eq!(events.capacity(), 16);
+
+    for i in 0..16 {
+        buffer.cmd().fill(i, None).enew(&mut events).enq().unwrap();
+    }
+    assert!(events.is_full());
+    assert!(events.push(Event::empty()).is_err());
+    // A full array is rejected before anything is enqueued:
+    assert!(buffer.cmd().fill(16, None).enew(&mut events).enq().is_err());
+    assert_eq!(events.len(), 16);
+
+    let mut marker = EventArray::<1>::new();
+    buffer.cmd().fill(99, None).ewait(&events).enew(&mut marker).enq().unwrap();
+    marker.wait_for().unwrap();
+    assert!(events.is_complete().unwrap());
+
+    events.clear_completed().unwrap();
+    assert!(events.is_empty());
+
+    // Default capacity, and conversion into a list:
+    let default: EventArray = EventArray::from(marker[0].clone());
+    assert_eq!(default.capacity(), 8);
+    let list = EventList::from(marker);
+    assert_eq!(list.len(), 1);
+}
+
+
+#[test]
+fn callback_driven_completion() {
+    let pro_que = nop_pro_que(64);
+
+    for _ in 0..4 {
+        let gate = UserEvent::new(pro_que.context()).unwrap();
+        let mut event = gate.clone().into_event();
+        let mut polls = 0;
+
+        let remote = gate.clone();
+        let completer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            remote.set_complete().unwrap();
+        });
+
+        // Repeated polls from the same task register it only once, and it
+        // must still be woken by the event's callback.
+        block_on(future::poll_fn(|cx| {
+            polls += 1;
+            if let Poll::Ready(res) = event.poll_unpin(cx) { return Poll::Ready(res); }
+            event.poll_unpin(cx)
+        })).unwrap();
+
+        completer.join().unwrap();
+        assert!(polls >= 1);
+    }
+}
+
+
+// Polls without blocking, so not applicable to the blocking implementation.
+#[cfg(not(feature = "async_block"))]
+#[test]
+fn completion_wakes_once() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::{Context, Wake, Waker};
+    use std::time::Instant;
+
+    /// Counts the times it is woken.
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let pro_que = nop_pro_que(64);
+    let gate = UserEvent::new(pro_que.context()).unwrap();
+    let mut event = gate.clone().into_event();
+
+    let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+    let waker = Waker::from(counter.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    // Polled twice before completion, the task is still only woken once:
+    assert!(event.poll_unpin(&mut cx).is_pending());
+    assert!(event.poll_unpin(&mut cx).is_pending());
+    assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+    gate.set_complete().unwrap();
+    let start = Instant::now();
+    while counter.0.load(Ordering::SeqCst) == 0 {
+        assert!(start.elapsed() < Duration::from_secs(5), "The task was never woken.");
+        thread::sleep(Duration::from_millis(1));
+    }
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+    assert!(event.poll_unpin(&mut cx).is_ready());
+}
+
+
+#[test]
+fn event_label() {
+    let pro_que = nop_pro_que(64);
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+
+    let mut fill = Event::empty();
+    buffer.cmd().fill(1, None).enew(&mut fill).label("fill a").enq().unwrap();
+    assert_eq!(fill.label().as_ref().map(|l| l.as_str()), Some("fill a"));
+    assert!(format!("{:?}", fill).contains("fill a"));
+
+    // Clones share the label:
+    fill.clone().set_label("fill b");
+    assert_eq!(fill.label().unwrap(), "fill b");
+    assert!(Event::empty().label().is_none());
+
+    let failed = UserEvent::new(pro_que.context()).unwrap();
+    failed.set_label("gate");
+    failed.set_error(Status::CL_OUT_OF_RESOURCES).unwrap();
+    let mut events = EventList::new();
+    events.push(fill.clone());
+    events.push(failed.clone().into_event());
+
+    let err = events.wait_for().unwrap_err();
+    let failed_event = err.failed_event().expect("no failed event");
+    assert_eq!(failed_event.label(), Some("gate"));
+    assert!(err.to_string().contains("'gate'"));
+}
+
+#[test]
+fn event_label_trace() {
+    let pro_que = nop_pro_que(64);
+    let queue = Queue::with_profiling(&pro_que.context(), pro_que.device()).unwrap();
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+
+    let mut trace = Trace::new();
+    let mut labeled = Event::empty();
+    buffer.cmd().queue(&queue).fill(1, None).enew(&mut labeled).label("init").enq().unwrap();
+    trace.record_event(&labeled).unwrap();
+    let mut unlabeled = Event::empty();
+    buffer.cmd().queue(&queue).fill(2, None).enew(&mut unlabeled).enq().unwrap();
+    trace.record_event(&unlabeled).unwrap();
+    queue.finish().unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&trace.to_json().unwrap()).unwrap();
+    let entries = json["traceEvents"].as_array().unwrap();
+    assert_eq!(entries[1]["name"], "init");
+    assert_eq!(entries[2]["name"], "FillBuffer");
+}
+
+
+#[test]
+fn event_wait_timeout() {
+    let pro_que = nop_pro_que(1024);
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+
+    let gate = UserEvent::new(pro_que.context()).unwrap();
+    let mut fill = Event::empty();
+    buffer.cmd().fill(7, None).ewait(&gate).enew(&mut fill).enq().unwrap();
+    pro_que.queue().flush().unwrap();
+
+    let err = fill.wait_timeout(Duration::from_millis(50)).unwrap_err();
+    assert!(err.is_timed_out());
+
+    let mut events = EventList::new();
+    events.push(gate.clone().into_event());
+    events.push(fill.clone());
+    assert!(events.wait_timeout(Duration::from_millis(50)).unwrap_err().is_timed_out());
+
+    let remote = gate.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        remote.set_complete().unwrap();
+    });
+    events.wait_timeout(Duration::from_secs(10)).unwrap();
+    fill.wait_timeout(Duration::from_secs(10)).unwrap();
+    // No deadline:
+    fill.wait_timeout(Duration::MAX).unwrap();
+    assert!(EventList::new().wait_timeout(Duration::from_millis(0)).is_ok());
+
+    // Failures are reported without waiting out the timeout:
+    let failed = UserEvent::new(pro_que.context()).unwrap();
+    let mut events = EventList::new();
+    events.push(fill.clone());
+    events.push(failed.clone().into_event());
+    failed.set_error(Status::CL_OUT_OF_RESOURCES).unwrap();
+    let err = events.wait_timeout(Duration::from_secs(10)).unwrap_err();
+    assert!(!err.is_timed_out());
+    assert_eq!(err.failed_event().expect("no failed event").index(), 1);
+}
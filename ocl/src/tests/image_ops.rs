@@ -6,8 +6,8 @@
 
 use crate::core::{self, DeviceInfo, DeviceInfoResult};
 use crate::flags;
-use crate::standard::{ProQue, Image, Sampler, Platform, Device};
-use crate::enums::{AddressingMode, FilterMode, ImageChannelOrder, ImageChannelDataType, MemObjectType};
+use crate::standard::{ProQue, Sampler, Platform, Device};
+use crate::enums::{AddressingMode, FilterMode, ImageChannelOrder, ImageChannelDataType};
 use crate::prm::{Int4};
 use crate::tests;
 
@@ -69,26 +69,20 @@ fn image_ops() {
 
     let sampler = Sampler::new(proque.context(), false, AddressingMode::None, FilterMode::Nearest).unwrap();
 
-    let mut vec = vec![0i32; proque.dims().to_len() * 4];
+    let mut vec = vec![0i32; proque.dims().unwrap().to_len() * 4];
 
     // Source and destination images and a vec to shuffle data:
-    let img_src = Image::<i32>::builder()
+    let img_src = proque.image_builder::<i32>()
         .channel_order(ImageChannelOrder::Rgba)
         .channel_data_type(ImageChannelDataType::SignedInt32)
-        .image_type(MemObjectType::Image3d)
-        .dims(proque.dims())
         .flags(flags::MEM_READ_WRITE | flags::MEM_COPY_HOST_PTR)
         .copy_host_slice(&vec)
-        .queue(proque.queue().clone())
         .build().unwrap();
-    let img_dst = Image::<i32>::builder()
+    let img_dst = proque.image_builder::<i32>()
         .channel_order(ImageChannelOrder::Rgba)
         .channel_data_type(ImageChannelDataType::SignedInt32)
-        .image_type(MemObjectType::Image3d)
-        .dims(proque.dims())
         .flags(flags::MEM_WRITE_ONLY | flags::MEM_COPY_HOST_PTR)
         .copy_host_slice(&vec)
-        .queue(proque.queue().clone())
         .build().unwrap();
 
     let kernel_add = proque.kernel_builder("add")
@@ -110,12 +104,12 @@ fn image_ops() {
     //========================================================================
     //========================================================================
     // Make sure that pro_que's dims are correct:
-    let dims = proque.dims().to_lens().unwrap();
+    let dims = proque.dims().unwrap().to_lens().unwrap();
     assert_eq!(DIMS, dims);
     assert_eq!(DIMS, kernel_add.default_global_work_size().to_lens().unwrap());
 
     // Verify image and vector lengths:
-    let len = proque.dims().to_len();
+    let len = proque.dims().unwrap().to_len();
     assert_eq!(img_src.dims().to_len(), len);
     assert_eq!(img_dst.dims().to_len(), len);
 
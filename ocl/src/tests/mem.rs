@@ -0,0 +1,266 @@
+use crate::standard::{ProQue, Context, MemPool, Event, Buffer, TransferPolicy, TransferKind,
+    AllocationKind, DeviceCache, HostMirror, UserEvent};
+use crate::standard::transfer::ChunkedTransfer;
+use crate::core::{self, MemFlags, DeviceInfo, DeviceInfoResult};
+use crate::error::Error as OclError;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+use super::{nop_pro_que, filled_buffer};
+
+#[test]
+fn rent_and_return() {
+    let src = r#"
+        __kernel void add(__global float* buffer, float addend) {
+            buffer[get_global_id(0)] += addend;
+        }
+    "#;
+
+    let pro_que = ProQue::builder()
+        .src(src)
+        .dims([1 << 10])
+        .build().unwrap();
+
+    let pool = MemPool::<f32>::new(pro_que.queue().clone());
+    let flags = MemFlags::new().read_write();
+
+    let first_ptr = {
+        let mut scratch = pool.rent_buffer(pro_que.dims().unwrap().to_len(), flags).unwrap();
+
+        let kernel = pro_que.kernel_builder("add")
+            .arg(&*scratch)
+            .arg(10.0f32)
+            .build().unwrap();
+
+        let mut event = Event::empty();
+        unsafe { kernel.cmd().enew(&mut event).enq().unwrap(); }
+        scratch.register_event(event.clone());
+        event.wait_for().unwrap();
+        scratch.as_core().as_ptr()
+    };
+
+    assert_eq!(pool.available() + pool.in_flight(), 1);
+
+    // The buffer is reused once its event has completed:
+    let scratch = pool.rent_buffer(pro_que.dims().unwrap().to_len(), flags).unwrap();
+    assert_eq!(scratch.as_core().as_ptr(), first_ptr);
+    assert_eq!(pool.available() + pool.in_flight(), 0);
+
+    // Differing lengths are never interchanged:
+    let other = pool.rent_buffer(pro_que.dims().unwrap().to_len() * 2, flags).unwrap();
+    assert_ne!(other.as_core().as_ptr(), first_ptr);
+}
+
+
+#[test]
+fn chunked_write_read() {
+    let src = r#"
+        __kernel void add(__global float* buffer, float addend) {
+            buffer[get_global_id(0)] += addend;
+        }
+    "#;
+
+    let pro_que = ProQue::builder()
+        .src(src)
+        .dims([1 << 12])
+        .build().unwrap();
+
+    let mut queue = pro_que.queue().clone();
+    queue.set_transfer_policy(TransferPolicy::new().chunk_size(1 << 10).concurrency(3));
+
+    let buffer: Buffer<f32> = pro_que.create_buffer().unwrap();
+    let src_vec: Vec<f32> = (0..buffer.len()).map(|i| i as f32).collect();
+
+    let mut write_event = Event::empty();
+    buffer.write(&src_vec).queue(&queue).enew(&mut write_event).enq().unwrap();
+    assert!(write_event.is_complete().unwrap());
+
+    let mut dst_vec = vec![0.0f32; buffer.len()];
+    buffer.read(&mut dst_vec).queue(&queue).enq().unwrap();
+    assert_eq!(src_vec, dst_vec);
+
+    // Each chunk of 256 elements was recorded for both the write and read:
+    let chunks = queue.transfer_metrics().chunks();
+    assert_eq!(chunks.len(), 32);
+    assert_eq!(chunks.iter().filter(|c| c.kind == TransferKind::Write).count(), 16);
+    assert!(chunks.iter().all(|c| c.len == 1 << 10));
+
+    // A buffer override disables splitting:
+    let mut buffer = buffer;
+    buffer.set_transfer_policy(Some(TransferPolicy::new()));
+    queue.transfer_metrics().clear();
+    buffer.read(&mut dst_vec).queue(&queue).enq().unwrap();
+    assert!(queue.transfer_metrics().chunks().is_empty());
+}
+
+
+#[test]
+fn chunked_transfer_failure_waits() {
+    let pro_que = nop_pro_que(1 << 12);
+    let queue = pro_que.queue().clone();
+    let buffer = filled_buffer(&queue, 1 << 12, 0.0f32);
+    let src_vec: Vec<f32> = (0..buffer.len()).map(|i| i as f32).collect();
+
+    // Holds back the first chunk until well after the second has failed:
+    let gate = UserEvent::new(pro_que.context()).unwrap();
+    let remote = gate.clone();
+    let completer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        remote.set_complete().unwrap();
+    });
+
+    let transfer = ChunkedTransfer { queue: &queue, kind: TransferKind::Write,
+        chunks: TransferPolicy::new().chunk_size(1 << 10).chunks(buffer.len(), 4).unwrap(),
+        concurrency: 2, elem_size: 4, offset: 0, block: false };
+    let mut first_event = None;
+    let result = transfer.enqueue(Some((&gate).into()), None, |range, ewait, enew| {
+        if range.start > 0 { return Err("Injected failure.".into()); }
+        unsafe {
+            core::enqueue_write_buffer(&queue, buffer.as_core(), false, range.start,
+                &src_vec[range], ewait, Some(&mut *enew)).map_err(OclError::from)?;
+        }
+        first_event = Some(enew.clone());
+        Ok(())
+    });
+
+    // The first chunk completed before the error was returned:
+    assert!(result.is_err());
+    assert!(first_event.unwrap().is_complete().unwrap());
+    completer.join().unwrap();
+}
+
+
+#[cfg(feature = "serde")]
+#[test]
+fn transfer_policy_serde() {
+    let policy: TransferPolicy = serde_json::from_str(r#"{ "chunk_size": 4096 }"#).unwrap();
+    assert_eq!(policy, TransferPolicy::new().chunk_size(4096));
+    let text = serde_json::to_string(&policy.concurrency(4)).unwrap();
+    assert_eq!(serde_json::from_str::<TransferPolicy>(&text).unwrap(), policy.concurrency(4));
+
+    let err = serde_json::from_str::<TransferPolicy>(r#"{ "concurrency": 0 }"#).unwrap_err();
+    assert!(err.to_string().contains("Concurrency must be greater than zero"), "{}", err);
+}
+
+
+#[test]
+fn dump_allocations() {
+    let pro_que = nop_pro_que(1024);
+    let context = pro_que.context();
+
+    let small: Buffer<u8> = pro_que.buffer_builder().len(256).tag("small").build().unwrap();
+    let large: Buffer<f32> = pro_que.create_buffer().unwrap();
+    large.set_tag("large");
+    let _sub = large.create_sub_buffer(None, 0, 16).unwrap();
+
+    let allocations = context.dump_allocations();
+    assert_eq!(allocations.len(), 2);
+    assert_eq!(allocations[0].tag(), Some("large"));
+    assert_eq!(allocations[0].size(), 1024 * 4);
+    assert_eq!(allocations[0].kind(), AllocationKind::Buffer);
+    assert!(allocations[0].location().unwrap().file().ends_with("allocations.rs"));
+    assert_eq!(allocations[1].tag(), Some("small"));
+
+    drop(small);
+    assert_eq!(context.dump_allocations().len(), 1);
+}
+
+#[test]
+fn memory_usage_and_limit() {
+    let pro_que = nop_pro_que(1024);
+    let context = pro_que.context();
+    let device = pro_que.device();
+
+    let on_queue: Buffer<f32> = pro_que.create_buffer().unwrap();
+    let unattributed = Buffer::<u8>::builder().context(context).len(256).build().unwrap();
+
+    let usage = context.memory_usage();
+    assert_eq!(usage.total(), 1024 * 4 + 256);
+    assert_eq!(usage.device(&device), 1024 * 4);
+    assert_eq!(usage.unattributed(), 256);
+
+    let global_mem_size = match device.info(DeviceInfo::GlobalMemSize).unwrap() {
+        DeviceInfoResult::GlobalMemSize(size) => size,
+        _ => unreachable!(),
+    };
+    // Leave room for exactly 1024 more bytes.
+    context.set_memory_headroom(Some(global_mem_size - (1024 * 4 + 256) - 1024));
+    assert!(context.memory_headroom().is_some());
+
+    let _fits: Buffer<u8> = pro_que.buffer_builder().len(1024).build().unwrap();
+    let err = pro_que.buffer_builder::<u8>().len(1).build().unwrap_err();
+    let limit_err = err.memory_limit().unwrap();
+    assert_eq!(limit_err.requested(), 1);
+    assert_eq!(limit_err.used(), 1024 * 4 + 256 + 1024);
+
+    drop(on_queue);
+    let _now_fits: Buffer<u8> = pro_que.buffer_builder().len(1024).build().unwrap();
+
+    context.set_memory_headroom(None);
+    assert!(context.memory_headroom().is_none());
+    drop(unattributed);
+}
+
+
+#[test]
+fn device_cache() {
+    let pro_que = nop_pro_que(256);
+    let cache = pro_que.context().device_cache();
+    assert!(cache.is_empty());
+
+    let table = cache.get_or_insert_with("table", || {
+        let buffer: Buffer<u32> = pro_que.create_buffer()?;
+        let mut ready = Event::empty();
+        buffer.cmd().fill(5, None).enew(&mut ready).enq()?;
+        Ok((buffer, Some(ready)))
+    }).unwrap();
+
+    // Shared by every handle for the context:
+    let shared = pro_que.context().device_cache().get::<u32>("table").unwrap().unwrap();
+    assert_eq!(shared.as_core().as_ptr(), table.as_core().as_ptr());
+    let rewrapped = Context::from(pro_que.context().as_core().clone());
+    assert!(rewrapped.device_cache().contains("table"));
+    assert!(cache.get::<f32>("table").is_err());
+    assert_eq!(cache.size(), 256 * 4);
+
+    let mut vec = vec![0u32; 256];
+    shared.read(&mut vec).ewait(shared.ready()).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 5));
+
+    // Least recently used entries are evicted first:
+    let limited = DeviceCache::new(Some(2 * 256 * 4));
+    limited.insert("a", pro_que.create_buffer::<u32>().unwrap(), None).unwrap();
+    limited.insert("b", pro_que.create_buffer::<u32>().unwrap(), None).unwrap();
+    limited.get::<u32>("a").unwrap();
+    limited.insert("c", pro_que.create_buffer::<u32>().unwrap(), None).unwrap();
+    assert!(limited.contains("a") && !limited.contains("b") && limited.contains("c"));
+    assert!(limited.insert("d", pro_que.buffer_builder::<u32>().len(1024).build().unwrap(), None)
+        .is_err());
+
+    limited.set_capacity(Some(256 * 4));
+    assert_eq!(limited.len(), 1);
+    assert!(cache.remove("table"));
+}
+
+
+#[test]
+fn host_mirror_sync() {
+    let pro_que = nop_pro_que(64);
+    let host = Arc::new(RwLock::new(vec![0u32; 64]));
+    let mirror = HostMirror::new(host.clone(), pro_que.queue().clone()).unwrap();
+
+    mirror.write(8..16, |slice| slice.iter_mut().for_each(|v| *v = 1));
+    host.write().unwrap()[60] = 2;
+    mirror.mark_dirty(60..61).unwrap();
+    assert!(mirror.mark_dirty(60..65).is_err());
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = 20..10;
+    assert!(mirror.mark_dirty(reversed).is_err());
+    assert_eq!(mirror.dirty().ranges(), &[8..16, 60..61]);
+
+    assert_eq!(mirror.sync().unwrap(), 9);
+    assert!(mirror.dirty().is_empty());
+    let mut vec = vec![0u32; 64];
+    mirror.buffer().read(&mut vec).enq().unwrap();
+    assert_eq!(vec, *host.read().unwrap());
+}
@@ -20,17 +20,38 @@ pub mod kernel_arg;
 pub mod vector_types;
 pub mod context_props;
 pub mod r#async;
+pub mod queue;
+pub mod event;
+pub mod device;
+pub mod program;
+pub mod pro_que;
+pub mod mem;
 pub mod buffer_sink_stream_cycles;
+pub mod testing_harness;
+#[cfg(feature = "opencl_version_2_0")]
+pub mod svm;
 
 use self::rand::Rng;
-use crate::core::OclScl;
+use crate::core::{OclScl, OclPrm};
 use crate::error::{Result as OclResult};
+use crate::{ProQue, Queue, Buffer, SpatialDims};
 
 const PRINT_ITERS_MAX: i32 = 3;
 const PRINT_SLICES_MAX: usize = 16;
 const PRINT: bool = false;
 
 
+/// Returns a `ProQue` built from an empty kernel, for tests which only need a
+/// context, a device and a queue.
+fn nop_pro_que<D: Into<SpatialDims>>(dims: D) -> ProQue {
+    ProQue::builder().src("__kernel void nop() {}").dims(dims).build().unwrap()
+}
+
+/// Returns a buffer of `len` elements on `queue`, each set to `val`.
+fn filled_buffer<T: OclPrm>(queue: &Queue, len: usize, val: T) -> Buffer<T> {
+    Buffer::<T>::builder().queue(queue.clone()).len(len).fill_val(val).build().unwrap()
+}
+
 fn gen_region_origin(dims: &[usize; 3]) -> ([usize; 3], [usize; 3]) {
     let mut rng = rand::weak_rng();
 
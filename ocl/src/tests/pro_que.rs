@@ -0,0 +1,283 @@
+use crate::standard::{ProQue, Event, Device, Program, Buffer};
+use std::cell::Cell;
+use crate::core::{ProgramInfo, ProgramInfoResult, DeviceInfo, DeviceInfoResult, ImageChannelOrder,
+    ImageChannelDataType, ImageInfo, ImageInfoResult};
+use crate::flags;
+use super::{nop_pro_que};
+
+#[test]
+fn pro_que_io_queue() {
+    let src = r#"
+        __kernel void add(__global float* buffer, float scalar) {
+            buffer[get_global_id(0)] += scalar;
+        }
+    "#;
+
+    let pro_que = ProQue::builder().src(src).dims(1024).build().unwrap();
+    assert!(!pro_que.has_separate_io_queue());
+    assert_eq!(pro_que.io_queue().as_ptr(), pro_que.queue().as_ptr());
+
+    let pro_que = ProQue::builder().src(src).dims(1024).separate_io_queue(true).build().unwrap();
+    assert!(pro_que.has_separate_io_queue());
+    assert!(pro_que.io_queue().as_ptr() != pro_que.queue().as_ptr());
+
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+    assert_eq!(buffer.default_queue().unwrap().as_ptr(), pro_que.io_queue().as_ptr());
+
+    let kernel = pro_que.kernel_builder("add").arg(&buffer).arg(1.0f32).build().unwrap();
+    assert_eq!(kernel.default_queue().unwrap().as_ptr(), pro_que.queue().as_ptr());
+
+    // Order the read on the transfer queue after the kernel:
+    let mut kernel_event = Event::empty();
+    unsafe { kernel.cmd().enew(&mut kernel_event).enq().unwrap(); }
+    let mut vec = vec![0.0f32; buffer.len()];
+    buffer.read(&mut vec).ewait(&kernel_event).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 1.0));
+
+    pro_que.finish_all().unwrap();
+}
+
+
+#[test]
+fn pro_que_kernel_cache() {
+    let src = r#"
+        __kernel void add(__global float* buffer, float scalar) {
+            buffer[get_global_id(0)] += scalar;
+        }
+    "#;
+
+    let pro_que = ProQue::builder().src(src).dims(1024).build().unwrap();
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+    assert!(pro_que.kernel("add").is_none());
+
+    let created = Cell::new(0);
+    for _ in 0..3 {
+        let kernel = pro_que.create_kernel("add", |kb| {
+            created.set(created.get() + 1);
+            kb.arg(&buffer).arg_named("scalar", 0.0f32);
+        }).unwrap();
+        let kernel = kernel.lock();
+        kernel.set_arg("scalar", 2.0f32).unwrap();
+        unsafe { kernel.enq().unwrap(); }
+    }
+    assert_eq!(created.get(), 1);
+
+    let mut vec = vec![0.0f32; buffer.len()];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 6.0));
+
+    // Clones share the cache:
+    let clone = pro_que.clone();
+    assert!(clone.kernel("add").is_some());
+    clone.clear_kernels();
+    assert!(pro_que.kernel("add").is_none());
+}
+
+
+#[test]
+fn multi_pro_que() {
+    let src = r#"
+        __kernel void add(__global float* buffer, float scalar) {
+            buffer[get_global_id(0)] += scalar;
+        }
+    "#;
+
+    let pro_que = ProQue::builder().src(src).dims(256).build().unwrap();
+    let devices = Device::list_all(pro_que.device().platform().unwrap()).unwrap();
+    let multi = pro_que.replicate_across(&devices).unwrap();
+    assert_eq!(multi.len(), devices.len());
+    assert_eq!(multi.devices(), devices);
+    assert_eq!(multi.program().devices().unwrap().len(), devices.len());
+
+    let mut results = Vec::new();
+    multi.for_each_device(|pq| {
+        assert_eq!(pq.context().as_ptr(), multi.context().as_ptr());
+        let buffer = pq.create_buffer::<f32>()?;
+        let kernel = pq.kernel_builder("add").arg(&buffer).arg(2.0f32).build()?;
+        unsafe { kernel.enq()?; }
+        let mut vec = vec![0.0f32; buffer.len()];
+        buffer.read(&mut vec).enq()?;
+        results.push(vec);
+        Ok(())
+    }).unwrap();
+    multi.finish_all().unwrap();
+
+    assert_eq!(results.len(), devices.len());
+    assert!(results.iter().all(|vec| vec.iter().all(|&v| v == 2.0)));
+    assert!(pro_que.replicate_across(&[]).is_err());
+}
+
+
+static PRO_QUE_PREBUILT_SRC: &str = r#"
+    __kernel void add(__global float* buffer, float scalar) {
+        buffer[get_global_id(0)] += scalar;
+    }
+"#;
+
+fn run_add(pro_que: &ProQue) -> Vec<f32> {
+    let buffer: Buffer<f32> = pro_que.create_buffer().unwrap();
+    let kernel = pro_que.kernel_builder("add").arg(&buffer).arg(1.0f32).build().unwrap();
+    unsafe { kernel.enq().unwrap(); }
+    let mut vec = vec![0.0f32; buffer.len()];
+    buffer.read(&mut vec).enq().unwrap();
+    vec
+}
+
+#[test]
+fn pro_que_prebuilt_program() {
+    let source = ProQue::builder().src(PRO_QUE_PREBUILT_SRC).dims(64).build().unwrap();
+    let program = Program::builder().src(PRO_QUE_PREBUILT_SRC).devices(source.device())
+        .build(source.context()).unwrap();
+
+    let pro_que = ProQue::builder().program(program.clone()).dims(64).build().unwrap();
+    assert_eq!(pro_que.context().as_core(), source.context().as_core());
+    assert_eq!(pro_que.device(), source.device());
+    assert!(run_add(&pro_que).iter().all(|&v| v == 1.0));
+
+    let other = ProQue::builder().src(PRO_QUE_PREBUILT_SRC).build().unwrap();
+    assert!(ProQue::builder().program(program).context(other.context().clone()).build().is_err());
+}
+
+#[test]
+fn pro_que_binary() {
+    let source = ProQue::builder().src(PRO_QUE_PREBUILT_SRC).dims(64).build().unwrap();
+    let binary = match source.program().info(ProgramInfo::Binaries).unwrap() {
+        ProgramInfoResult::Binaries(mut binaries) => binaries.remove(0),
+        _ => unreachable!(),
+    };
+    // Some implementations do not provide binaries:
+    if binary.is_empty() { return; }
+
+    let pro_que = ProQue::builder().binary(source.device(), &binary).dims(64).build().unwrap();
+    assert_eq!(pro_que.device(), source.device());
+    assert!(run_add(&pro_que).iter().all(|&v| v == 1.0));
+}
+
+
+#[test]
+fn pro_que_buffers() {
+    let pro_que = nop_pro_que(64);
+
+    let buffer = pro_que.create_buffer_with_flags::<i32>(flags::MEM_READ_ONLY).unwrap();
+    assert_eq!(buffer.len(), 64);
+    assert!(buffer.flags().unwrap().contains(flags::MEM_READ_ONLY));
+
+    let buffer = pro_que.create_buffer_filled(7i32).unwrap();
+    let mut vec = vec![0i32; buffer.len()];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 7));
+
+    let data: Vec<i32> = (0..10).collect();
+    let buffer = pro_que.create_buffer_from_slice(&data).unwrap();
+    assert_eq!(buffer.len(), 10);
+    let mut vec = vec![0i32; buffer.len()];
+    buffer.read(&mut vec).enq().unwrap();
+    assert_eq!(vec, data);
+}
+
+#[test]
+fn pro_que_image() {
+    let pro_que = nop_pro_que((16, 8));
+    match pro_que.device().info(DeviceInfo::ImageSupport).unwrap() {
+        DeviceInfoResult::ImageSupport(true) => (),
+        _ => return,
+    }
+
+    let image = pro_que.create_image::<f32>(ImageChannelOrder::Rgba,
+        ImageChannelDataType::Float).unwrap();
+    match image.info(ImageInfo::Width).unwrap() {
+        ImageInfoResult::Width(width) => assert_eq!(width, 16),
+        _ => unreachable!(),
+    }
+    match image.info(ImageInfo::Height).unwrap() {
+        ImageInfoResult::Height(height) => assert_eq!(height, 8),
+        _ => unreachable!(),
+    }
+    assert_eq!(image.default_queue().unwrap().as_ptr(), pro_que.io_queue().as_ptr());
+    match image.info(ImageInfo::ElementSize).unwrap() {
+        ImageInfoResult::ElementSize(size) => assert_eq!(size, 16),
+        _ => unreachable!(),
+    }
+}
+
+
+static PRO_QUE_NAMED_DIMS_SRC: &str = r#"
+    __kernel void add(__global float* buffer, float scalar) {
+        buffer[get_global_id(0)] += scalar;
+    }
+"#;
+
+#[test]
+fn pro_que_named_dims() {
+    let mut pro_que = ProQue::builder().src(PRO_QUE_NAMED_DIMS_SRC).dims_named("tiles", 32).build()
+        .unwrap();
+    assert!(pro_que.dims().is_err());
+    assert!(pro_que.create_buffer::<f32>().is_err());
+    // Builders fall back to an explicit length:
+    assert_eq!(pro_que.buffer_builder::<f32>().len(16).build().unwrap().len(), 16);
+    assert!(pro_que.dims_named("rows").is_err());
+
+    pro_que.set_dims_named("rows", 8);
+    let mut names = pro_que.dims_names();
+    names.sort();
+    assert_eq!(names, ["rows", "tiles"]);
+
+    let buffer = pro_que.create_buffer_named_dims::<f32>("tiles").unwrap();
+    assert_eq!(buffer.len(), 32);
+
+    let kernel = pro_que.kernel_builder_named_dims("add", "rows").unwrap()
+        .arg(&buffer)
+        .arg(1.0f32)
+        .build().unwrap();
+    unsafe { kernel.enq().unwrap(); }
+
+    let mut vec = vec![0.0f32; buffer.len()];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec[..8].iter().all(|&v| v == 1.0));
+    assert!(vec[8..].iter().all(|&v| v == 0.0));
+}
+
+
+static PRO_QUE_PROFILING_SRC: &str = r#"
+    __kernel void add(__global float* buffer, float scalar) {
+        buffer[get_global_id(0)] += scalar;
+    }
+
+    __kernel void mul(__global float* buffer, float scalar) {
+        buffer[get_global_id(0)] *= scalar;
+    }
+"#;
+
+#[test]
+fn pro_que_profiling() {
+    let pro_que = ProQue::builder().src(PRO_QUE_PROFILING_SRC).dims(1 << 12).profiling(true).build()
+        .unwrap();
+    assert!(pro_que.is_profiling());
+    assert!(pro_que.queue().profiling_enabled().unwrap());
+
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+    let add = pro_que.kernel_builder("add").arg(&buffer).arg(1.0f32).build().unwrap();
+    let mul = pro_que.kernel_builder("mul").arg(&buffer).arg(2.0f32).build().unwrap();
+    for _ in 0..3 {
+        unsafe {
+            pro_que.enq_kernel(&add).unwrap();
+            pro_que.enq_kernel(&mul).unwrap();
+        }
+    }
+    unsafe { pro_que.enq_kernel(&add).unwrap(); }
+
+    let summary = pro_que.profiling_summary().unwrap();
+    assert_eq!(summary.kernels().len(), 2);
+    let add_stats = summary.get("add").unwrap();
+    assert_eq!(add_stats.count(), 4);
+    assert!(add_stats.min() <= add_stats.mean() && add_stats.mean() <= add_stats.max());
+    assert_eq!(summary.get("mul").unwrap().count(), 3);
+    assert_eq!(summary.total(), add_stats.total() + summary.get("mul").unwrap().total());
+
+    pro_que.reset_profiling();
+    assert!(pro_que.profiling_summary().unwrap().is_empty());
+
+    let pro_que = ProQue::builder().src(PRO_QUE_PROFILING_SRC).dims(64).build().unwrap();
+    assert!(!pro_que.is_profiling());
+    assert!(pro_que.profiling_summary().is_err());
+}
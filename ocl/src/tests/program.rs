@@ -0,0 +1,306 @@
+use crate::standard::{Context, Program, Device, Platform, ProQue, Buffer, Kernel, DeviceCriteria,
+    Queue, Event};
+use crate::core::{ProgramInfo, ProgramInfoResult, DeviceInfo, DeviceInfoResult,
+    DeviceExecCapabilities};
+use std::{mem, ptr};
+use crate::ffi::c_void;
+use super::{filled_buffer};
+
+static PROGRAM_DEVICES_SRC: &str = r#"
+    __kernel void add(__global float* buffer, float addend) {
+        buffer[get_global_id(0)] += addend;
+    }
+"#;
+
+#[test]
+fn build_for_device_subset() {
+    let context = Context::builder().build().unwrap();
+    let device = context.devices()[0];
+
+    // Duplicates are ignored:
+    let program = Program::builder()
+        .src(PROGRAM_DEVICES_SRC)
+        .devices(&[device, device][..])
+        .build(&context).unwrap();
+
+    match program.info(ProgramInfo::Devices).unwrap() {
+        ProgramInfoResult::Devices(devices) => assert_eq!(devices, vec![device.into()]),
+        _ => unreachable!(),
+    }
+
+    // Devices outside of the context are rejected:
+    let outside = Platform::list().unwrap().into_iter()
+        .flat_map(|p| Device::list_all(p).unwrap())
+        .find(|d| !context.devices().contains(d));
+
+    if let Some(outside) = outside {
+        assert!(Program::builder().src(PROGRAM_DEVICES_SRC).devices(outside).build(&context)
+            .is_err());
+    }
+}
+
+#[test]
+fn typed_info() {
+    let context = Context::builder().build().unwrap();
+    let program = Program::builder().src(PROGRAM_DEVICES_SRC).build(&context).unwrap();
+
+    assert_eq!(program.num_devices().unwrap() as usize, context.devices().len());
+    assert_eq!(program.devices().unwrap(), context.devices());
+    assert_eq!(program.num_kernels().unwrap(), 1);
+    assert_eq!(program.kernel_names().unwrap(), vec!["add".to_owned()]);
+    assert!(program.source_len().unwrap() > 0);
+
+    let kernel = crate::Kernel::builder()
+        .program(&program)
+        .name("add")
+        .arg(None::<&crate::Buffer<f32>>)
+        .arg(0.0f32)
+        .build().unwrap();
+
+    assert_eq!(kernel.name().unwrap(), "add");
+    assert_eq!(kernel.num_args().unwrap(), 2);
+    assert_eq!(kernel.program().unwrap().as_ptr(), program.as_ptr());
+    assert_eq!(kernel.context().unwrap().as_ptr(), context.as_ptr());
+    assert!(kernel.reference_count().unwrap() >= 1);
+}
+
+
+static PROGRAM_RELOAD_SRC: &str = r#"
+    __kernel void add(__global float* buffer, float addend) {
+        buffer[get_global_id(0)] += addend;
+    }
+"#;
+
+static PROGRAM_RELOAD_SRC_DOUBLED: &str = r#"
+    __kernel void add(__global float* buffer, float addend) {
+        buffer[get_global_id(0)] += addend * 2.0f;
+    }
+"#;
+
+#[test]
+fn rebuild_refreshes_kernels() {
+    let pro_que = ProQue::builder().src(PROGRAM_RELOAD_SRC).dims(64).build().unwrap();
+    let mut program = Program::builder()
+        .src(PROGRAM_RELOAD_SRC)
+        .reloadable()
+        .build(pro_que.context()).unwrap();
+
+    let buffer: Buffer<f32> = pro_que.create_buffer().unwrap();
+    let mut kernel = Kernel::builder()
+        .program(&program)
+        .name("add")
+        .queue(pro_que.queue().clone())
+        .global_work_size(64)
+        .arg(&buffer)
+        .arg(0.0f32)
+        .build().unwrap();
+
+    kernel.set_arg(1, 1.0f32).unwrap();
+    assert_eq!(program.handle().unwrap().kernel_count(), 1);
+
+    unsafe { kernel.enq().unwrap(); }
+    program.rebuild(PROGRAM_RELOAD_SRC_DOUBLED).unwrap();
+    assert!(kernel.is_stale());
+
+    // The re-created kernel is used, with its arguments replayed:
+    unsafe { kernel.enq().unwrap(); }
+    let mut vec = vec![0.0f32; 64];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 3.0));
+
+    assert!(kernel.refresh().unwrap());
+    assert!(!kernel.is_stale());
+
+    // A failed rebuild leaves the program and its kernels untouched:
+    assert!(program.rebuild("__kernel void other() {}").is_err());
+    assert!(!kernel.is_stale());
+
+    drop(kernel);
+    assert_eq!(program.handle().unwrap().kernel_count(), 0);
+}
+
+#[test]
+fn pro_que_rebuild() {
+    let mut pro_que = ProQue::builder().src(PROGRAM_RELOAD_SRC).dims(64).reloadable().build()
+        .unwrap();
+    let buffer: Buffer<f32> = pro_que.create_buffer().unwrap();
+    let queue = pro_que.queue().clone();
+    let kernel = pro_que.create_kernel("add", |kb| { kb.arg(&buffer).arg(1.0f32); }).unwrap();
+    unsafe { kernel.lock().enq().unwrap(); }
+
+    pro_que.rebuild(PROGRAM_RELOAD_SRC_DOUBLED).unwrap();
+    assert_eq!(pro_que.queue().as_ptr(), queue.as_ptr());
+
+    // The cached kernel is refreshed, keeping its arguments:
+    let cached = pro_que.kernel("add").unwrap();
+    assert!(cached.lock().is_stale());
+    unsafe { cached.lock().enq().unwrap(); }
+    let mut vec = vec![0.0f32; 64];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 3.0));
+
+    // Without a kernel registry, the cache is cleared instead:
+    let mut pro_que = ProQue::builder().src(PROGRAM_RELOAD_SRC).dims(64).build().unwrap();
+    pro_que.create_kernel("add", |kb| { kb.arg(&buffer).arg(1.0f32); }).unwrap();
+    pro_que.rebuild(PROGRAM_RELOAD_SRC_DOUBLED).unwrap();
+    assert!(pro_que.kernel("add").is_none());
+    assert!(pro_que.rebuild("__kernel void broken( {}").is_err());
+}
+
+
+#[test]
+fn built_in_kernels() {
+    for device in Device::select(&DeviceCriteria::new()).unwrap() {
+        let names = device.built_in_kernels().unwrap();
+        if names.is_empty() || device.version().unwrap() < [1, 2].into() { continue; }
+
+        let context = Context::builder().platform(device.platform().unwrap()).devices(device)
+            .build().unwrap();
+        let program = Program::builder().devices(device).built_in_kernels(&[&names[0]])
+            .build(&context).unwrap();
+        match program.info(ProgramInfo::KernelNames).unwrap() {
+            ProgramInfoResult::KernelNames(kernel_names) => {
+                assert!(kernel_names.split(';').any(|name| name == names[0]));
+            },
+            _ => unreachable!(),
+        }
+    }
+}
+
+extern "C" fn fill_sevens(args: *mut c_void) {
+    unsafe {
+        let data = ptr::read_unaligned(args as *const *mut u32);
+        for i in 0..4 { *data.add(i) = 7; }
+    }
+}
+
+#[test]
+fn native_kernel() {
+    for device in Device::select(&DeviceCriteria::new()).unwrap() {
+        match device.info(DeviceInfo::ExecutionCapabilities).unwrap() {
+            DeviceInfoResult::ExecutionCapabilities(caps) => {
+                if !caps.contains(DeviceExecCapabilities::NATIVE_KERNEL) { continue; }
+            },
+            _ => unreachable!(),
+        }
+
+        let context = Context::builder().platform(device.platform().unwrap()).devices(device)
+            .build().unwrap();
+        let queue = Queue::new(&context, device, None).unwrap();
+        let buffer = filled_buffer::<u32>(&queue, 4, 0u32);
+
+        let mut args = vec![0u8; mem::size_of::<*mut u32>()];
+        let event: Event = unsafe {
+            queue.enqueue_native_kernel(fill_sevens, &mut args, &[(buffer.as_core(), 0)],
+                None::<&Event>).unwrap()
+        };
+        event.wait_for().unwrap();
+
+        let mut result = vec![0u32; 4];
+        buffer.read(&mut result).enq().unwrap();
+        assert_eq!(result, vec![7; 4]);
+    }
+}
+
+
+#[cfg(feature = "opencl_version_2_0")]
+#[test]
+fn global_variable_total_size() {
+    use crate::core::OpenclVersion;
+
+    let src = r#"
+        __global float scale = 2.0f;
+        __global int counts[16];
+
+        __kernel void scale_by(__global float* buffer) {
+            buffer[get_global_id(0)] *= scale;
+            counts[get_global_id(0) % 16] += 1;
+        }
+    "#;
+
+    let context = Context::builder().build().unwrap();
+    let device = context.devices()[0];
+    if device.version().unwrap() < OpenclVersion::new(2, 0) { return; }
+
+    let program = Program::builder().devices(device).src(src).cmplr_opt("-cl-std=CL2.0")
+        .build(&context).unwrap();
+    let size = program.global_variable_total_size(device).unwrap();
+    assert!(size >= 4 + 16 * 4, "size: {}", size);
+}
+
+
+/// A SPIR-V module containing `kernel void fill(global uint* out)`, which
+/// stores the value of the `uint` specialization constant with id 0 (default:
+/// 7) to `out[0]`.
+#[cfg(feature = "opencl_version_2_2")]
+static FILL_SPEC_CONSTANT_SPIRV: &[u32] = &[
+    // Header: magic, version 1.0, generator, id bound, schema.
+    0x0723_0203, 0x0001_0000, 0, 9, 0,
+    // OpCapability Addresses, OpCapability Kernel
+    (2 << 16) | 17, 4,
+    (2 << 16) | 17, 6,
+    // OpMemoryModel Physical64 OpenCL
+    (3 << 16) | 14, 2, 2,
+    // OpEntryPoint Kernel %6 "fill"
+    (5 << 16) | 15, 6, 6, 0x6c6c_6966, 0,
+    // OpDecorate %5 SpecId 0
+    (4 << 16) | 71, 5, 1, 0,
+    // %1 = OpTypeVoid
+    (2 << 16) | 19, 1,
+    // %2 = OpTypeInt 32 0
+    (4 << 16) | 21, 2, 32, 0,
+    // %3 = OpTypePointer CrossWorkgroup %2
+    (4 << 16) | 32, 3, 5, 2,
+    // %4 = OpTypeFunction %1 %3
+    (4 << 16) | 33, 4, 1, 3,
+    // %5 = OpSpecConstant %2 7
+    (4 << 16) | 50, 2, 5, 7,
+    // %6 = OpFunction %1 None %4
+    (5 << 16) | 54, 1, 6, 0, 4,
+    // %7 = OpFunctionParameter %3
+    (3 << 16) | 55, 3, 7,
+    // %8 = OpLabel
+    (2 << 16) | 248, 8,
+    // OpStore %7 %5 Aligned 4
+    (5 << 16) | 62, 7, 5, 2, 4,
+    // OpReturn, OpFunctionEnd
+    (1 << 16) | 253,
+    (1 << 16) | 56,
+];
+
+#[cfg(feature = "opencl_version_2_2")]
+#[test]
+fn spec_constants() {
+    use crate::core::OpenclVersion;
+
+    let context = Context::builder().build().unwrap();
+    let device = context.devices()[0];
+    if device.version().unwrap() < OpenclVersion::new(2, 2) { return; }
+    match device.info(DeviceInfo::IlVersion).unwrap() {
+        DeviceInfoResult::IlVersion(ref il) if il.contains("SPIR-V") => (),
+        _ => return,
+    }
+
+    let il: Vec<u8> = FILL_SPEC_CONSTANT_SPIRV.iter().flat_map(|w| w.to_ne_bytes().to_vec())
+        .collect();
+    let queue = Queue::new(&context, device, None).unwrap();
+
+    let run = |value: Option<u32>| -> u32 {
+        let mut builder = Program::builder();
+        builder.devices(device).il(&il);
+        if let Some(value) = value { builder.spec_constant(0, value); }
+        let program = builder.build(&context).unwrap();
+
+        let buffer = filled_buffer::<u32>(&queue, 1, 0);
+        let kernel = Kernel::builder().program(&program).name("fill").queue(queue.clone())
+            .global_work_size(1).arg(&buffer).build().unwrap();
+        unsafe { kernel.enq().unwrap(); }
+
+        let mut out = vec![0u32; 1];
+        buffer.read(&mut out).enq().unwrap();
+        out[0]
+    };
+
+    assert_eq!(run(None), 7);
+    assert_eq!(run(Some(42)), 42);
+}
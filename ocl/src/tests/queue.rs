@@ -0,0 +1,594 @@
+use crate::standard::{ProQue, Queue, Buffer, Event, EventList, Quirks, Trace, QueuePool,
+    DispatchPolicy, FlushPolicy, Priority, DriverId};
+use crate::core::{DeviceInfo, DeviceInfoResult, CommandQueueProperties, ProfilingInfo,
+    ProfilingInfoResult, OpenclVersion, QueuePriority, QueueThrottle, QueueProperties};
+use futures::executor::block_on;
+use crate::ffi;
+use crate::r#async::RwVec;
+use std::thread;
+use super::{nop_pro_que, filled_buffer};
+
+#[test]
+fn long_wait_list_fan_in() {
+    let pro_que = ProQue::builder()
+        .src("__kernel void add(__global float* buf) { buf[get_global_id(0)] += 1.0f; }")
+        .dims(64)
+        .build().unwrap();
+
+    // Injected rather than set with `Quirks::set_override`, which would
+    // affect tests running concurrently on the same device:
+    let quirks = Quirks { max_wait_list_len: Some(3), ..Quirks::default() };
+    let queue = Queue::builder().context(&pro_que.context()).device(pro_que.device())
+        .quirks(quirks).build().unwrap();
+    assert_eq!(queue.quirks().max_wait_list_len, Some(3));
+
+    let buffer: Buffer<f32> = Buffer::builder().queue(queue.clone()).len(64).fill_val(0.0).build()
+        .unwrap();
+
+    let mut events = EventList::new();
+    for _ in 0..20 {
+        let mut event = Event::empty();
+        buffer.cmd().fill(0.0f32, None).enew(&mut event).enq().unwrap();
+        events.push(event);
+    }
+
+    let kernel = pro_que.kernel_builder("add").queue(queue.clone()).quirks(quirks).arg(&buffer)
+        .build().unwrap();
+    assert_eq!(kernel.quirks().max_wait_list_len, Some(3));
+    unsafe { kernel.cmd().ewait(&events).enq().unwrap(); }
+
+    let mut vec = vec![0.0f32; 64];
+    buffer.read(&mut vec).ewait(&events).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 1.0));
+}
+
+
+#[test]
+fn quirk_table_lookup() {
+    use crate::standard::quirks::QuirkEntry;
+
+    static TABLE: &[QuirkEntry] = &[
+        QuirkEntry { platform: Some("Acme"), device: None, driver_version: None,
+            source: "test", apply: |q| q.serialize_builds = true },
+        QuirkEntry { platform: Some("Acme"), device: Some("GPU"), driver_version: Some("1."),
+            source: "test", apply: |q| q.max_wait_list_len = Some(16) },
+        QuirkEntry { platform: None, device: Some("Acme GPU"), driver_version: None,
+            source: "test", apply: |q| q.max_wait_list_len = Some(8) },
+    ];
+
+    let id = |platform: &str, device: &str, driver_version: &str| DriverId {
+        platform: platform.into(), device: device.into(), driver_version: driver_version.into(),
+    };
+
+    // No entry matches:
+    assert_eq!(Quirks::lookup(TABLE, &id("Other", "Acme CPU", "1.2")), Quirks::default());
+    // Platform only:
+    assert_eq!(Quirks::lookup(TABLE, &id("Acme Platform", "Acme CPU", "1.2")),
+        Quirks { serialize_builds: true, ..Quirks::default() });
+    // The driver version is matched as a prefix:
+    assert_eq!(Quirks::lookup(TABLE, &id("Acme", "GPU", "2.1")),
+        Quirks { serialize_builds: true, ..Quirks::default() });
+    // Later entries override earlier ones:
+    assert_eq!(Quirks::lookup(TABLE, &id("Acme", "Acme GPU", "1.0")),
+        Quirks { serialize_builds: true, max_wait_list_len: Some(8), ..Quirks::default() });
+
+    let a = Quirks { blocking_writes: true, max_wait_list_len: Some(16), ..Quirks::default() };
+    let b = Quirks { serialize_builds: true, max_wait_list_len: Some(8), ..Quirks::default() };
+    assert_eq!(a.union(b), Quirks { blocking_writes: true, max_wait_list_len: Some(8),
+        serialize_builds: true });
+    assert_eq!(a.union(Quirks::default()), a);
+
+    let pro_que = nop_pro_que(64);
+    let driver_id = DriverId::of(&pro_que.device()).unwrap();
+    let builtin = Quirks::builtin(&driver_id);
+    assert_eq!(builtin.any(), !Quirks::builtin_sources(&driver_id).is_empty());
+}
+
+
+#[test]
+fn out_of_order_queue() {
+    let pro_que = ProQue::builder()
+        .src("__kernel void add(__global float* buf, float val) { buf[get_global_id(0)] += val; }")
+        .dims(256)
+        .build().unwrap();
+
+    let supported = match pro_que.device().info(DeviceInfo::QueueProperties).unwrap() {
+        DeviceInfoResult::QueueProperties(p) => p,
+        _ => unreachable!(),
+    };
+    if !supported.contains(CommandQueueProperties::OUT_OF_ORDER_EXEC_MODE_ENABLE) {
+        assert!(Queue::builder().context(&pro_que.context()).device(pro_que.device())
+            .out_of_order().build().is_err());
+        return;
+    }
+
+    let queue = Queue::builder().context(&pro_que.context()).device(pro_que.device())
+        .out_of_order().build().unwrap();
+    assert!(queue.is_out_of_order().unwrap());
+
+    let buf_a: Buffer<f32> = pro_que.buffer_builder().queue(queue.clone()).fill_val(0.0).build()
+        .unwrap();
+    let buf_b: Buffer<f32> = pro_que.buffer_builder().queue(queue.clone()).fill_val(0.0).build()
+        .unwrap();
+
+    // Independent kernels, free to run concurrently:
+    let kern_a = pro_que.kernel_builder("add").queue(queue.clone()).arg(&buf_a).arg(1.0f32)
+        .build().unwrap();
+    let kern_b = pro_que.kernel_builder("add").queue(queue.clone()).arg(&buf_b).arg(2.0f32)
+        .build().unwrap();
+
+    let mut events = EventList::new();
+    unsafe {
+        kern_a.cmd().enew(&mut events).enq().unwrap();
+        kern_b.cmd().enew(&mut events).enq().unwrap();
+    }
+
+    let marker = queue.enqueue_marker(Some(&events)).unwrap();
+    let barrier = queue.enqueue_barrier(None::<&EventList>).unwrap();
+
+    let mut vec = vec![0.0f32; 256];
+    buf_a.read(&mut vec).ewait(&barrier).enq().unwrap();
+    assert!(marker.is_complete().unwrap());
+    assert!(vec.iter().all(|&v| v == 1.0));
+    buf_b.read(&mut vec).ewait(&barrier).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 2.0));
+}
+
+#[cfg(feature = "opencl_version_2_0")]
+#[test]
+fn on_device_queue() {
+    use crate::core::OpenclVersion;
+
+    let pro_que = nop_pro_que(1);
+    if pro_que.device().version().unwrap() < OpenclVersion::new(2, 0) { return; }
+
+    let queue = Queue::builder().context(&pro_que.context()).device(pro_que.device())
+        .on_device_default().queue_size(16 * 1024).build().unwrap();
+    assert!(queue.is_on_device().unwrap());
+    assert!(queue.is_out_of_order().unwrap());
+}
+
+
+#[test]
+fn queue_with_profiling() {
+    let pro_que = nop_pro_que(64);
+    assert!(!pro_que.queue().profiling_enabled().unwrap());
+
+    let queue = Queue::with_profiling(&pro_que.context(), pro_que.device()).unwrap();
+    assert!(queue.profiling_enabled().unwrap());
+    assert!(!queue.is_out_of_order().unwrap());
+
+    let buffer: Buffer<u32> = pro_que.buffer_builder().queue(queue.clone()).build().unwrap();
+    let mut event = Event::empty();
+    buffer.cmd().fill(7, None).enew(&mut event).enq().unwrap();
+    event.wait_for().unwrap();
+
+    let start = match event.profiling_info(ProfilingInfo::Start).unwrap() {
+        ProfilingInfoResult::Start(t) => t,
+        _ => unreachable!(),
+    };
+    let end = match event.profiling_info(ProfilingInfo::End).unwrap() {
+        ProfilingInfoResult::End(t) => t,
+        _ => unreachable!(),
+    };
+    assert!(end >= start);
+}
+
+#[test]
+fn profiling_unavailable() {
+    let pro_que = nop_pro_que(64);
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+    let mut event = Event::empty();
+    buffer.cmd().fill(7, None).enew(&mut event).enq().unwrap();
+    event.wait_for().unwrap();
+
+    // Non-profiling queue:
+    assert!(event.profiling_info(ProfilingInfo::Start).unwrap_err().is_profiling_unavailable());
+    assert_eq!(event.profiling_time(ProfilingInfo::Start).unwrap(), None);
+    assert_eq!(event.profiling_duration().unwrap(), None);
+
+    // User event:
+    let user_event = Event::user(pro_que.context()).unwrap();
+    user_event.set_complete().unwrap();
+    assert_eq!(user_event.profiling_time(ProfilingInfo::End).unwrap(), None);
+
+    let queue = Queue::with_profiling(&pro_que.context(), pro_que.device()).unwrap();
+    buffer.cmd().queue(&queue).fill(8, None).enew(&mut event).enq().unwrap();
+    event.wait_for().unwrap();
+    assert!(event.profiling_duration().unwrap().is_some());
+}
+
+#[test]
+fn event_profiling() {
+    let pro_que = nop_pro_que(64);
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+    let mut event = Event::empty();
+    buffer.cmd().fill(7, None).enew(&mut event).enq().unwrap();
+    event.wait_for().unwrap();
+    assert!(event.profiling().unwrap_err().is_profiling_unavailable());
+
+    let queue = Queue::with_profiling(&pro_que.context(), pro_que.device()).unwrap();
+    buffer.cmd().queue(&queue).fill(8, None).enew(&mut event).enq().unwrap();
+    event.wait_for().unwrap();
+
+    let profiling = event.profiling().unwrap();
+    assert!(profiling.queued() <= profiling.submit());
+    assert!(profiling.submit() <= profiling.start());
+    assert!(profiling.start() <= profiling.end());
+    assert_eq!(Some(profiling.duration()), event.profiling_duration().unwrap());
+    assert!(profiling.queue_latency() <= profiling.total());
+}
+
+#[test]
+fn trace_export() {
+    let src = "__kernel void add(__global uint* a) { a[get_global_id(0)] += 1; }";
+    let pro_que = ProQue::builder().src(src).dims(64).build().unwrap();
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+    let kernel_queue = Queue::with_profiling(&pro_que.context(), pro_que.device()).unwrap();
+    let transfer_queue = Queue::with_profiling(&pro_que.context(), pro_que.device()).unwrap();
+    let kernel = pro_que.kernel_builder("add").queue(kernel_queue.clone()).arg(&buffer)
+        .build().unwrap();
+
+    let mut trace = Trace::new();
+    trace.set_queue_name(&transfer_queue, "transfers \"0\"");
+    let mut fill = Event::empty();
+    buffer.cmd().queue(&transfer_queue).fill(1, None).enew(&mut fill).enq().unwrap();
+    trace.record("fill", &fill).unwrap();
+    let mut add = Event::empty();
+    unsafe { kernel.cmd().ewait(&fill).enew(&mut add).enq().unwrap(); }
+    trace.record_kernel(&kernel, &add).unwrap();
+    assert_eq!(trace.len(), 2);
+    add.wait_for().unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&trace.to_json().unwrap()).unwrap();
+    let entries = json["traceEvents"].as_array().unwrap();
+    assert_eq!(entries.len(), 4);
+    assert_eq!(entries[0]["args"]["name"], "transfers \"0\"");
+    assert_eq!(entries[1]["args"]["name"], "Queue 1");
+    assert_eq!(entries[2]["name"], "fill");
+    assert_eq!(entries[2]["tid"], 0);
+    assert_eq!(entries[3]["name"], "add");
+    assert_eq!(entries[3]["cat"], "NdrangeKernel");
+    assert_eq!(entries[3]["tid"], 1);
+    assert!(entries[3]["ts"].as_f64().unwrap() >= entries[2]["ts"].as_f64().unwrap());
+
+    trace.clear();
+    assert!(trace.is_empty());
+}
+
+#[cfg(feature = "opencl_version_2_1")]
+#[test]
+fn device_timer_sync() {
+    let pro_que = nop_pro_que(64);
+    let device = pro_que.device();
+    if device.version().unwrap() < [2, 1].into() {
+        assert!(device.timer_sync().unwrap_err().version_low().is_some());
+        return;
+    }
+
+    let queue = Queue::with_profiling(&pro_que.context(), device).unwrap();
+    let sync = device.timer_sync().unwrap();
+    let mut event = Event::empty();
+    let kernel = pro_que.kernel_builder("nop").queue(queue.clone()).build().unwrap();
+    unsafe { kernel.cmd().enew(&mut event).enq().unwrap(); }
+    queue.finish().unwrap();
+
+    let start = event.profiling().unwrap().start();
+    assert!(start >= sync.device_timestamp());
+    assert!(sync.to_instant(start).unwrap() >= sync.instant());
+    assert!(device.host_timer().unwrap() >= sync.host_timestamp());
+}
+
+
+#[test]
+fn finish_async() {
+    let pro_que = nop_pro_que(1 << 16);
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+    buffer.cmd().fill(3, None).enq().unwrap();
+
+    let finish = pro_que.queue().finish_async().unwrap();
+    block_on(finish).unwrap();
+
+    let mut vec = vec![0u32; buffer.len()];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 3));
+}
+
+
+#[test]
+fn queue_pool_round_robin() {
+    let pro_que = nop_pro_que(256);
+    let pool = QueuePool::new(&pro_que.context(), pro_que.device(), 3).unwrap();
+    assert_eq!(pool.len(), 3);
+
+    let used: Vec<_> = (0..6).map(|_| pool.next().unwrap().as_ptr()).collect();
+    assert_eq!(used[0], pool[0].as_ptr());
+    assert_eq!(used[1], pool[1].as_ptr());
+    assert_eq!(used[2], pool[2].as_ptr());
+    assert_eq!(&used[0..3], &used[3..6]);
+
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+    for i in 0..6u32 {
+        pool.enqueue(|q| buffer.cmd().queue(q).fill(i, None).enq()).unwrap();
+        pool.finish().unwrap();
+    }
+
+    let mut vec = vec![0u32; buffer.len()];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 5));
+}
+
+#[test]
+fn queue_pool_least_loaded() {
+    let pro_que = nop_pro_que(256);
+    let pool = QueuePool::with_policy(&pro_que.context(), pro_que.device(), 2, None,
+        DispatchPolicy::LeastLoaded).unwrap();
+
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+    for _ in 0..8 {
+        pool.enqueue(|q| buffer.cmd().queue(q).fill(1, None).enq()).unwrap();
+    }
+    assert!(pool.outstanding().unwrap().iter().sum::<usize>() <= 8);
+
+    pool.finish().unwrap();
+    assert_eq!(pool.outstanding().unwrap(), vec![0, 0]);
+}
+
+#[test]
+fn queue_pool_priorities() {
+    let pro_que = nop_pro_que(256);
+    let pool = QueuePool::with_priorities(&pro_que.context(), pro_que.device(),
+        &[QueuePriority::High, QueuePriority::Medium, QueuePriority::Medium],
+        DispatchPolicy::RoundRobin).unwrap();
+    assert_eq!(pool.len(), 3);
+    assert!(!pool.is_empty());
+    assert_eq!(pool.priorities()[0], QueuePriority::High);
+
+    // High priority tasks only use the high priority queue:
+    let high: Vec<_> = Priority::HIGH.scope(|| {
+        (0..4).map(|_| pool.next().unwrap().as_ptr()).collect()
+    });
+    assert!(high.iter().all(|&q| q == pool[0].as_ptr()));
+
+    // Normal priority tasks use the others:
+    let normal: Vec<_> = (0..4).map(|_| pool.next().unwrap().as_ptr()).collect();
+    assert!(normal.iter().all(|&q| q != pool[0].as_ptr()));
+    assert!(normal.contains(&pool[1].as_ptr()) && normal.contains(&pool[2].as_ptr()));
+
+    // Low priority tasks, having no matching queue, use any:
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+    Priority::LOW.scope(|| {
+        for _ in 0..3 {
+            pool.enqueue(|q| buffer.cmd().queue(q).fill(1, None).enq()).unwrap();
+        }
+    });
+    pool.finish().unwrap();
+}
+
+
+#[test]
+fn queue_priority_throttle() {
+    let pro_que = nop_pro_que(64);
+    let device = pro_que.device();
+    let hinted = device.has_extension("cl_khr_priority_hints").unwrap() ||
+        device.has_extension("cl_khr_throttle_hints").unwrap();
+
+    let result = Queue::builder().context(&pro_que.context()).device(device)
+        .priority(QueuePriority::High).throttle(QueueThrottle::Low).build();
+
+    // Without the extensions the hints are dropped and a plain queue is
+    // created.
+    if hinted && (cfg!(not(feature = "opencl_version_2_0")) ||
+            device.version().unwrap() < OpenclVersion::new(2, 0)) {
+        assert!(result.is_err());
+        return;
+    }
+    let queue = result.unwrap();
+
+    let buffer: Buffer<u32> = pro_que.buffer_builder().queue(queue).build().unwrap();
+    buffer.cmd().fill(3, None).enq().unwrap();
+    let mut vec = vec![0u32; buffer.len()];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 3));
+}
+
+#[test]
+fn queue_priority_from_task() {
+    assert_eq!(QueuePriority::from(Priority::HIGH), QueuePriority::High);
+    assert_eq!(QueuePriority::from(Priority(500)), QueuePriority::High);
+    assert_eq!(QueuePriority::from(Priority(10)), QueuePriority::Medium);
+    assert_eq!(QueuePriority::from(Priority::LOW), QueuePriority::Low);
+
+    let pro_que = nop_pro_que(64);
+    let mut builder = Queue::builder();
+    builder.context(&pro_que.context()).device(pro_que.device());
+
+    // Untagged, no priority is requested at all:
+    assert_eq!(builder.queue_properties().get_priority(), None);
+    // Within a scope, the task's priority is used:
+    let priority = Priority::HIGH.scope(|| builder.queue_properties().get_priority());
+    if cfg!(feature = "opencl_version_2_0") {
+        assert_eq!(priority, Some(QueuePriority::High));
+    } else {
+        assert_eq!(priority, None);
+    }
+    // An explicit priority wins:
+    builder.priority(QueuePriority::Low);
+    let priority = Priority::HIGH.scope(|| builder.queue_properties().get_priority());
+    assert_eq!(priority, Some(QueuePriority::Low));
+}
+
+#[test]
+fn queue_properties_list() {
+    let props = QueueProperties::new()
+        .flags(CommandQueueProperties::PROFILING_ENABLE)
+        .vendor(0x4000, 1)
+        .vendor(0x4000, 2);
+    assert!(!props.is_legacy_compatible());
+    assert_eq!(props.to_raw(), vec![ffi::CL_QUEUE_PROPERTIES as ffi::cl_queue_properties,
+        CommandQueueProperties::PROFILING_ENABLE.bits(), 0x4000, 2, 0]);
+
+    let pro_que = nop_pro_que(64);
+    let device = pro_que.device();
+
+    // The bitfield alone uses the 1.x path on any device:
+    let queue = Queue::with_queue_properties(&pro_que.context(), device,
+        QueueProperties::from(CommandQueueProperties::PROFILING_ENABLE)).unwrap();
+    assert!(queue.properties().unwrap().contains(CommandQueueProperties::PROFILING_ENABLE));
+
+    if cfg!(not(feature = "opencl_version_2_0")) ||
+            device.version().unwrap() < OpenclVersion::new(2, 0) {
+        assert!(Queue::builder().context(&pro_que.context()).device(device)
+            .vendor_property(0x4000, 1).build().is_err());
+    }
+}
+
+
+#[test]
+fn cross_queue_marker_barrier() {
+    let pro_que = nop_pro_que(1024);
+    let queue_a = pro_que.queue().clone();
+    let queue_b = Queue::new(&pro_que.context(), pro_que.device(), None).unwrap();
+
+    let buffer: Buffer<i32> = pro_que.create_buffer().unwrap();
+    let mut written = Event::empty();
+    buffer.cmd().queue(&queue_a).fill(9, None).enew(&mut written).enq().unwrap();
+    queue_a.flush().unwrap();
+
+    // Queue B waits on queue A's fill:
+    let marker = queue_b.marker(&written).unwrap();
+    let mut vec = vec![0i32; buffer.len()];
+    buffer.cmd().queue(&queue_b).read(&mut vec).ewait(&marker).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 9));
+
+    let mut events = EventList::new();
+    buffer.cmd().queue(&queue_a).fill(4, None).enew(&mut events).enq().unwrap();
+    let barrier = queue_b.barrier(&events).unwrap();
+    buffer.cmd().queue(&queue_b).read(&mut vec).enq().unwrap();
+    assert!(barrier.is_complete().unwrap());
+    assert!(vec.iter().all(|&v| v == 4));
+
+    // No wait list: all previously enqueued commands.
+    queue_b.marker(None::<&Event>).unwrap().wait_for().unwrap();
+}
+
+
+#[test]
+fn flush_policies() {
+    let pro_que = nop_pro_que(1024);
+    let queue = Queue::builder()
+        .context(pro_que.context())
+        .device(pro_que.device())
+        .flush_policy(FlushPolicy::Async)
+        .build().unwrap();
+    assert_eq!(queue.flush_policy(), FlushPolicy::Async);
+    assert_eq!(queue.clone().flush_policy(), FlushPolicy::Async);
+
+    let buffer = filled_buffer::<i32>(&queue, 1024, 5);
+
+    // Never flushed explicitly:
+    let rw_vec = RwVec::from(vec![0i32; 1024]);
+    let guard = buffer.read(&rw_vec).enq_async().unwrap().wait().unwrap();
+    assert!(guard.iter().all(|&v| v == 5));
+    drop(guard);
+
+    queue.set_flush_policy(FlushPolicy::Every(2));
+    for val in 0..5 {
+        buffer.cmd().fill(val, None).enq().unwrap();
+    }
+    queue.finish().unwrap();
+    let mut vec = vec![0i32; 1024];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 4));
+
+    queue.set_flush_policy(FlushPolicy::Manual);
+    assert_eq!(queue.flush_policy(), FlushPolicy::Manual);
+}
+
+
+#[test]
+fn implicit_dependencies() {
+    let pro_que = ProQue::builder()
+        .src("__kernel void add(__global int* buf, int val) { buf[get_global_id(0)] += val; }")
+        .dims(1024)
+        .build().unwrap();
+
+    let queue = match Queue::builder()
+            .context(pro_que.context())
+            .device(pro_que.device())
+            .properties(CommandQueueProperties::OUT_OF_ORDER_EXEC_MODE_ENABLE)
+            .track_dependencies(true)
+            .build() {
+        Ok(q) => q,
+        // Out-of-order queues are optional.
+        Err(_) => return,
+    };
+    assert!(queue.tracks_dependencies());
+    assert!(queue.last_event().is_none());
+
+    let buffer = filled_buffer::<i32>(&queue, 1024, 0);
+    let kernel = pro_que.kernel_builder("add")
+        .queue(queue.clone())
+        .arg(&buffer)
+        .arg(0i32)
+        .build().unwrap();
+
+    let mut fill_event = Event::empty();
+    buffer.cmd().fill(1, None).enew(&mut fill_event).enq().unwrap();
+    assert!(!fill_event.is_empty());
+    assert_eq!(queue.last_event().unwrap(), fill_event);
+
+    // Each command waits for the one before, so the additions and the
+    // final read see a consistent order without any events being passed.
+    for val in 1..5 {
+        kernel.set_arg(1, val).unwrap();
+        unsafe { kernel.enq().unwrap(); }
+    }
+    buffer.cmd().fill(100, None).enq().unwrap();
+    unsafe { kernel.enq().unwrap(); }
+
+    let mut vec = vec![0i32; buffer.len()];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 104));
+
+    queue.set_track_dependencies(false);
+    assert!(queue.last_event().is_none());
+}
+
+
+// Helper code which enqueues on whatever queue its caller has set.
+fn fill_with_default(buffer: &Buffer<u32>, val: u32) {
+    let queue = Queue::thread_default().expect("no default queue");
+    buffer.cmd().queue(&queue).fill(val, None).enq().unwrap();
+}
+
+#[test]
+fn thread_default_queue() {
+    let pro_que = nop_pro_que(64);
+    let queue_a = pro_que.queue().clone();
+    let queue_b = Queue::new(&pro_que.context(), pro_que.device(), None).unwrap();
+    let buffer: Buffer<u32> = pro_que.create_buffer().unwrap();
+
+    assert!(Queue::thread_default().is_none());
+    assert!(queue_a.set_thread_default().is_none());
+    fill_with_default(&buffer, 1);
+    // The queues are unordered with respect to each other:
+    queue_a.finish().unwrap();
+
+    queue_b.with_thread_default(|| {
+        assert_eq!(Queue::thread_default().unwrap().as_ptr(), queue_b.as_ptr());
+        fill_with_default(&buffer, 2);
+        queue_b.finish().unwrap();
+    });
+    assert_eq!(Queue::thread_default().unwrap().as_ptr(), queue_a.as_ptr());
+
+    // Defaults are per thread:
+    thread::spawn(|| assert!(Queue::thread_default().is_none())).join().unwrap();
+
+    let mut vec = vec![0u32; buffer.len()];
+    buffer.read(&mut vec).queue(&queue_a).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 2));
+
+    assert_eq!(Queue::clear_thread_default().unwrap().as_ptr(), queue_a.as_ptr());
+    assert!(Queue::thread_default().is_none());
+}
@@ -0,0 +1,390 @@
+use std::mem;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+use crate::core::{DeviceSvmCapabilities, MapFlags};
+use crate::standard::{ProQue, Program, SvmBuffer, FineSvmBuffer, Kernel, Event, SvmBox, SvmVec,
+    SvmAllocator};
+
+static SRC: &'static str = r#"
+    __kernel void add(__global float* buffer, float scalar) {
+        buffer[get_global_id(0)] += scalar;
+    }
+
+    // Sums a linked list of `(value, next)` pairs stored in SVM.
+    __kernel void sum_list(__global ulong* nodes, __global ulong* result) {
+        ulong sum = 0;
+        __global ulong* node = nodes;
+        while (node) {
+            sum += node[0];
+            node = (__global ulong*)node[1];
+        }
+        result[0] = sum;
+    }
+
+    // Sums the values pointed to by `ptrs`, which point into another buffer.
+    __kernel void sum_ptrs(__global ulong* ptrs, uint count, __global ulong* result) {
+        ulong sum = 0;
+        for (uint i = 0; i < count; i++) {
+            sum += *(__global ulong*)ptrs[i];
+        }
+        result[0] = sum;
+    }
+"#;
+
+static ATOMIC_SRC: &'static str = r#"
+    __kernel void count(__global atomic_int* counter) {
+        atomic_fetch_add_explicit(counter, 1, memory_order_relaxed,
+            memory_scope_all_svm_devices);
+    }
+"#;
+
+#[test]
+fn svm_buffer() {
+    let pro_que = ProQue::builder().src(SRC).dims(64).build().unwrap();
+    if !pro_que.device().svm_capabilities().unwrap()
+            .contains(DeviceSvmCapabilities::COARSE_GRAIN_BUFFER) {
+        assert!(SvmBuffer::<f32>::new(pro_que.queue(), 64).is_err());
+        return;
+    }
+
+    let mut buffer = SvmBuffer::<f32>::new(pro_que.queue(), 64).unwrap();
+    assert_eq!(buffer.len(), 64);
+    assert!(buffer.map().unwrap().iter().all(|&v| v == 0.0));
+
+    let kernel = pro_que.kernel_builder("add").arg_svm(&buffer).arg(2.0f32).build().unwrap();
+    unsafe { kernel.enq().unwrap(); }
+    kernel.set_arg(1, 1.0f32).unwrap();
+    unsafe { kernel.enq().unwrap(); }
+
+    let mut vec = vec![0.0f32; 64];
+    buffer.read_into(&mut vec).unwrap();
+    assert!(vec.iter().all(|&v| v == 3.0));
+
+    // The kernel keeps the allocation alive:
+    let other = SvmBuffer::from_slice(pro_que.queue(), &[10.0f32; 64]).unwrap();
+    kernel.set_arg_svm(0, &other).unwrap();
+    drop(other);
+    unsafe { kernel.enq().unwrap(); }
+    pro_que.finish().unwrap();
+}
+
+#[test]
+fn svm_commands() {
+    let pro_que = ProQue::builder().src(SRC).dims(64).build().unwrap();
+    if !pro_que.device().svm_capabilities().unwrap()
+            .contains(DeviceSvmCapabilities::COARSE_GRAIN_BUFFER) {
+        return;
+    }
+
+    let mut src = SvmBuffer::<f32>::new(pro_que.queue(), 64).unwrap();
+    let dst = SvmBuffer::<f32>::new(pro_que.queue(), 64).unwrap();
+
+    let mut fill_event = Event::empty();
+    src.cmd().fill(4.0).enew(&mut fill_event).enq().unwrap();
+    src.cmd().fill(-1.0).offset(60).ewait(&fill_event).enq().unwrap();
+
+    let mut copy_event = Event::empty();
+    src.cmd().copy(&dst, 32).len(32).enew(&mut copy_event).enq().unwrap();
+    dst.cmd().write(&[2.0f32; 16]).ewait(&copy_event).enq().unwrap();
+
+    let mut vec = vec![0.0f32; 64];
+    dst.cmd().read(&mut vec).enq().unwrap();
+    assert!(vec[..16].iter().all(|&v| v == 2.0));
+    assert!(vec[16..32].iter().all(|&v| v == 0.0));
+    assert!(vec[32..64].iter().all(|&v| v == 4.0));
+
+    src.read_into(&mut vec).unwrap();
+    assert!(vec[..60].iter().all(|&v| v == 4.0) && vec[60..].iter().all(|&v| v == -1.0));
+
+    // Asynchronous map and unmap of a region:
+    let mut map_event = Event::empty();
+    unsafe {
+        src.cmd().map(MapFlags::new().write_invalidate_region()).offset(8).len(8)
+            .block(false).enew(&mut map_event).enq().unwrap();
+        map_event.wait_for().unwrap();
+        std::slice::from_raw_parts_mut(src.as_mut_ptr().add(8), 8)
+            .iter_mut().for_each(|v| *v = 7.0);
+    }
+    src.cmd().unmap().offset(8).enq().unwrap();
+    src.read_into(&mut vec).unwrap();
+    assert!(vec[8..16].iter().all(|&v| v == 7.0) && vec[16] == 4.0);
+
+    assert!(src.cmd().fill(0.0).offset(60).len(8).enq().is_err());
+    assert!(src.cmd().copy(&dst, 48).len(32).enq().is_err());
+    assert!(src.cmd().enq().is_err());
+}
+
+#[test]
+fn svm_deferred_free() {
+    let pro_que = ProQue::builder().src(SRC).dims(1 << 20).build().unwrap();
+    if !pro_que.device().svm_capabilities().unwrap()
+            .contains(DeviceSvmCapabilities::COARSE_GRAIN_BUFFER) {
+        return;
+    }
+
+    let buffer = SvmBuffer::<f32>::new(pro_que.queue(), 1 << 20).unwrap();
+    let (tx, rx) = mpsc::channel();
+    let tx_first = tx.clone();
+    buffer.on_free(move || { tx_first.send(1).unwrap(); });
+    buffer.on_free(move || { tx.send(2).unwrap(); });
+
+    // The kernel keeps the buffer alive after it is dropped:
+    let kernel = pro_que.kernel_builder("add").arg_svm(&buffer).arg(1.0f32).build().unwrap();
+    drop(buffer);
+    unsafe { kernel.enq().unwrap(); }
+    assert!(rx.try_recv().is_err());
+
+    // Freed, without blocking, after the kernel has run:
+    drop(kernel);
+    assert_eq!(rx.recv_timeout(Duration::from_secs(10)).unwrap(), 1);
+    assert_eq!(rx.recv_timeout(Duration::from_secs(10)).unwrap(), 2);
+    pro_que.finish().unwrap();
+}
+
+#[test]
+fn svm_linked_list() {
+    let pro_que = ProQue::builder().src(SRC).dims(1).build().unwrap();
+    if !pro_que.device().svm_capabilities().unwrap()
+            .contains(DeviceSvmCapabilities::COARSE_GRAIN_BUFFER) {
+        return;
+    }
+
+    // Nodes link to one another using their shared (host and device) address:
+    let mut nodes = SvmBuffer::<u64>::new(pro_que.queue(), 8).unwrap();
+    let base = nodes.as_ptr() as u64;
+    {
+        let mut map = nodes.map().unwrap();
+        for i in 0..4 {
+            map[i * 2] = (i + 1) as u64;
+            map[i * 2 + 1] = if i < 3 { base + ((i + 1) * 16) as u64 } else { 0 };
+        }
+    }
+    let mut result = SvmBuffer::<u64>::new(pro_que.queue(), 1).unwrap();
+
+    let kernel = Kernel::builder()
+        .program(pro_que.program())
+        .name("sum_list")
+        .queue(pro_que.queue().clone())
+        .global_work_size(1)
+        .arg_svm(&nodes)
+        .arg_svm(&result)
+        .build().unwrap();
+    unsafe { kernel.enq().unwrap(); }
+
+    let mut sum = [0u64];
+    result.read_into(&mut sum).unwrap();
+    assert_eq!(sum[0], 1 + 2 + 3 + 4);
+}
+
+#[test]
+fn svm_linked_buffers() {
+    let pro_que = ProQue::builder().src(SRC).dims(1).build().unwrap();
+    if !pro_que.device().svm_capabilities().unwrap()
+            .contains(DeviceSvmCapabilities::COARSE_GRAIN_BUFFER) {
+        return;
+    }
+
+    let values = SvmBuffer::from_slice(pro_que.queue(), &[1u64, 2, 3, 4]).unwrap();
+    let base = values.as_ptr() as u64;
+    let ptrs: Vec<u64> = (0..4).map(|i| base + i * 8).collect();
+    let ptrs = SvmBuffer::from_slice(pro_que.queue(), &ptrs).unwrap();
+    ptrs.link(&values);
+    let mut result = SvmBuffer::<u64>::new(pro_que.queue(), 1).unwrap();
+
+    // `values` is passed to the kernel through exec info and kept alive:
+    let kernel = pro_que.kernel_builder("sum_ptrs")
+        .arg_svm_named("ptrs", &ptrs)
+        .arg(4u32)
+        .arg_svm(&result)
+        .build().unwrap();
+    drop(values);
+    unsafe { kernel.enq().unwrap(); }
+
+    let mut sum = [0u64];
+    result.read_into(&mut sum).unwrap();
+    assert_eq!(sum[0], 1 + 2 + 3 + 4);
+
+    let other_values = SvmBuffer::from_slice(pro_que.queue(), &[10u64, 20]).unwrap();
+    let base = other_values.as_ptr() as u64;
+    let other_ptrs = SvmBuffer::from_slice(pro_que.queue(), &[base, base + 8]).unwrap();
+    other_ptrs.link(&other_values);
+    kernel.set_arg_svm("ptrs", &other_ptrs).unwrap();
+    kernel.set_arg(1, 2u32).unwrap();
+    unsafe { kernel.enq().unwrap(); }
+
+    result.read_into(&mut sum).unwrap();
+    assert_eq!(sum[0], 10 + 20);
+}
+
+#[test]
+fn fine_svm_buffer() {
+    let pro_que = ProQue::builder().src(SRC).dims(64).build().unwrap();
+    let caps = pro_que.device().svm_capabilities().unwrap();
+    if !caps.contains(DeviceSvmCapabilities::FINE_GRAIN_BUFFER) {
+        assert!(FineSvmBuffer::<f32>::new(pro_que.queue(), 64).is_err());
+        return;
+    }
+
+    let mut buffer = FineSvmBuffer::<f32>::new(pro_que.queue(), 64).unwrap();
+    assert!(buffer.iter().all(|&v| v == 0.0));
+    assert!(!buffer.has_atomics());
+    assert!(FineSvmBuffer::<i32>::new(pro_que.queue(), 1).unwrap().atomics().is_err());
+
+    // Host writes made before enqueuing are visible to the kernel:
+    for (i, val) in buffer.iter_mut().enumerate() {
+        *val = i as f32;
+    }
+    let kernel = pro_que.kernel_builder("add").arg_svm(&buffer).arg(1.0f32).build().unwrap();
+    let mut event = Event::empty();
+    unsafe { kernel.cmd().enew(&mut event).enq().unwrap(); }
+
+    // Kernel writes are visible to the host once its event is complete:
+    event.wait_for().unwrap();
+    assert!(buffer.iter().enumerate().all(|(i, &v)| v == i as f32 + 1.0));
+
+    let other = FineSvmBuffer::from_slice(pro_que.queue(), &[5.0f32; 64]).unwrap();
+    kernel.set_arg_svm(0, &other).unwrap();
+    unsafe { kernel.enq().unwrap(); }
+    pro_que.finish().unwrap();
+    assert!(other.iter().all(|&v| v == 6.0));
+
+    // A blocking fill is complete, and visible to the host, once enqueued:
+    let mut event = Event::empty();
+    buffer.cmd().fill(2.0).enew(&mut event).enq().unwrap();
+    assert!(event.is_complete().unwrap());
+    assert!(buffer.iter().all(|&v| v == 2.0));
+}
+
+#[test]
+fn fine_svm_buffer_concurrent() {
+    let pro_que = ProQue::builder().src(SRC).dims(1 << 20).build().unwrap();
+    if !pro_que.device().svm_capabilities().unwrap()
+            .contains(DeviceSvmCapabilities::FINE_GRAIN_BUFFER) {
+        return;
+    }
+
+    // The kernel updates only the first half of the buffer:
+    let len = 1 << 20;
+    let mut buffer = FineSvmBuffer::<f32>::new(pro_que.queue(), len * 2).unwrap();
+    let kernel = pro_que.kernel_builder("add").global_work_size(len).arg_svm(&buffer)
+        .arg(1.0f32).build().unwrap();
+    let mut event = Event::empty();
+    unsafe { kernel.cmd().enew(&mut event).enq().unwrap(); }
+    pro_que.queue().flush().unwrap();
+
+    // Meanwhile, the host may update the other half through a raw pointer:
+    let second_half = unsafe {
+        std::slice::from_raw_parts_mut(buffer.as_mut_ptr().add(len), len)
+    };
+    for (i, val) in second_half.iter_mut().enumerate() {
+        *val = i as f32;
+    }
+
+    event.wait_for().unwrap();
+    assert!(buffer[..len].iter().all(|&v| v == 1.0));
+    assert!(buffer[len..].iter().enumerate().all(|(i, &v)| v == i as f32));
+}
+
+#[test]
+fn fine_svm_buffer_atomics() {
+    let pro_que = ProQue::builder().src(SRC).dims(1024).build().unwrap();
+    let caps = pro_que.device().svm_capabilities().unwrap();
+    if !caps.contains(DeviceSvmCapabilities::FINE_GRAIN_BUFFER
+            | DeviceSvmCapabilities::ATOMICS) {
+        assert!(FineSvmBuffer::<i32>::with_atomics(pro_que.queue(), 1).is_err());
+        return;
+    }
+
+    let mut prog_bldr = Program::builder();
+    prog_bldr.src(ATOMIC_SRC).cmplr_opt("-cl-std=CL2.0");
+    let pro_que = ProQue::builder().prog_bldr(prog_bldr).context(pro_que.context().clone())
+        .dims(1024).build().unwrap();
+
+    let mut counter = FineSvmBuffer::<i32>::with_atomics(pro_que.queue(), 1).unwrap();
+    assert!(counter.has_atomics());
+    counter.atomics().unwrap()[0].fetch_add(100, Ordering::SeqCst);
+
+    let kernel = pro_que.kernel_builder("count").arg_svm(&counter).build().unwrap();
+    unsafe { kernel.enq().unwrap(); }
+
+    // The host may update the counter while the kernel runs:
+    let atomics = counter.atomics().unwrap();
+    for _ in 0..4 {
+        atomics[0].fetch_add(1, Ordering::SeqCst);
+    }
+
+    pro_que.finish().unwrap();
+    assert_eq!(counter.atomics().unwrap()[0].load(Ordering::SeqCst), 100 + 1024 + 4);
+    assert_eq!(counter[0], 100 + 1024 + 4);
+
+    // Aligned for the atomic type even where it is more aligned than `i64`:
+    let wide = FineSvmBuffer::<i64>::with_atomics(pro_que.queue(), 1).unwrap();
+    assert_eq!(wide.as_ptr() as usize % mem::align_of::<AtomicI64>(), 0);
+}
+
+#[test]
+fn svm_collections() {
+    let pro_que = ProQue::builder().src(SRC).dims(100).build().unwrap();
+    if !pro_que.device().svm_capabilities().unwrap()
+            .contains(DeviceSvmCapabilities::FINE_GRAIN_BUFFER) {
+        assert!(SvmVec::<f32>::new(pro_que.queue()).is_err());
+        assert!(SvmAllocator::new(pro_que.queue()).is_err());
+        return;
+    }
+
+    let mut vec = SvmVec::with_capacity(pro_que.queue(), 4).unwrap();
+    for i in 0..100 {
+        vec.push(i as f32).unwrap();
+    }
+    assert_eq!(vec.len(), 100);
+    assert!(vec.capacity() >= 100);
+
+    let kernel = pro_que.kernel_builder("add").arg_svm(&vec).arg(0.5f32).build().unwrap();
+    unsafe { kernel.enq().unwrap(); }
+    pro_que.finish().unwrap();
+    assert!(vec.iter().enumerate().all(|(i, &v)| v == i as f32 + 0.5));
+    assert_eq!(vec.pop(), Some(99.5));
+    assert!(vec.reserve(usize::max_value()).is_err());
+
+    let mut scalar = SvmBox::new(pro_que.queue(), 1.0f32).unwrap();
+    *scalar += 1.0;
+    let kernel = pro_que.kernel_builder("add").global_work_size(1).arg_svm(&scalar)
+        .arg(3.0f32).build().unwrap();
+    unsafe { kernel.enq().unwrap(); }
+    pro_que.finish().unwrap();
+    assert_eq!(scalar.into_inner(), 5.0);
+
+    // Raw allocations, passed by pointer:
+    let alloc = SvmAllocator::new(pro_que.queue()).unwrap();
+    let layout = std::alloc::Layout::array::<f32>(100).unwrap();
+    unsafe {
+        let ptr = alloc.alloc(layout).unwrap().as_ptr() as *mut f32;
+        std::slice::from_raw_parts_mut(ptr, 100).iter_mut().for_each(|v| *v = 1.0);
+        let kernel = pro_que.kernel_builder("add").arg_svm_ptr(ptr).arg(1.0f32).build().unwrap();
+        kernel.enq().unwrap();
+        pro_que.finish().unwrap();
+        assert!(std::slice::from_raw_parts(ptr, 100).iter().all(|&v| v == 2.0));
+        drop(kernel);
+        alloc.dealloc(std::ptr::NonNull::new_unchecked(ptr as *mut u8), layout);
+    }
+}
+
+#[cfg(feature = "svm_allocator_api")]
+#[test]
+fn svm_allocator_vec() {
+    let pro_que = ProQue::builder().src(SRC).dims(64).build().unwrap();
+    if !pro_que.device().svm_capabilities().unwrap()
+            .contains(DeviceSvmCapabilities::FINE_GRAIN_BUFFER) {
+        return;
+    }
+
+    let mut vec: Vec<f32, _> = Vec::new_in(SvmAllocator::new(pro_que.queue()).unwrap());
+    vec.extend((0..64).map(|i| i as f32));
+    let kernel = unsafe {
+        pro_que.kernel_builder("add").arg_svm_ptr(vec.as_ptr()).arg(1.0f32).build().unwrap()
+    };
+    unsafe { kernel.enq().unwrap(); }
+    pro_que.finish().unwrap();
+    assert!(vec.iter().enumerate().all(|(i, &v)| v == i as f32 + 1.0));
+}
@@ -0,0 +1,49 @@
+use std::panic::{self, AssertUnwindSafe};
+use crate::standard::{Buffer, Kernel};
+use crate::testing::{TestEnv, SeededRng};
+use crate::skip_if_no_device;
+
+#[test]
+fn harness() {
+    let (platform, device) = skip_if_no_device!();
+    let env = TestEnv::with_device(platform, device).unwrap();
+    let data: Vec<f32> = SeededRng::from_env().vec(1024);
+    assert!(data.iter().all(|&v| v >= 0.0 && v < 1.0));
+
+    let program = env.program("__kernel void dbl(__global float* buf) { \
+        buf[get_global_id(0)] *= 2.0f; }").unwrap();
+    let buffer = Buffer::builder().queue(env.queue().clone()).len(data.len())
+        .copy_host_slice(&data).build().unwrap();
+    let kernel = Kernel::builder().program(&program).name("dbl").queue(env.queue().clone())
+        .global_work_size(data.len()).arg(&buffer).build().unwrap();
+    unsafe { kernel.enq().unwrap(); }
+
+    let mut result = vec![0.0f32; data.len()];
+    buffer.read(&mut result).enq().unwrap();
+    assert!(data.iter().zip(result.iter()).all(|(&d, &r)| r == d * 2.0));
+}
+
+#[test]
+fn harness_leak_check() {
+    let (platform, device) = skip_if_no_device!();
+    let env = TestEnv::with_device(platform, device).unwrap();
+    let buffer = Buffer::<u8>::builder().queue(env.queue().clone()).len(64).build().unwrap();
+
+    // Only checked when a device is present, rather than with
+    // `#[should_panic]`, which would fail on machines without one:
+    let panic = panic::catch_unwind(AssertUnwindSafe(|| drop(env))).unwrap_err();
+    let msg = panic.downcast_ref::<String>().map(|s| s.as_str())
+        .or_else(|| panic.downcast_ref::<&str>().cloned())
+        .unwrap_or("");
+    assert!(msg.contains("still alive"), "unexpected panic: {:?}", msg);
+    drop(buffer);
+}
+
+#[test]
+fn seeded_rng_is_deterministic() {
+    let a: Vec<u32> = SeededRng::new(7).vec(16);
+    let b: Vec<u32> = SeededRng::new(7).vec(16);
+    let c: Vec<u32> = SeededRng::new(8).vec(16);
+    assert_eq!(a, b);
+    assert!(a != c);
+}